@@ -0,0 +1,63 @@
+//! Benchmarks of the main DSP code paths, measured with criterion
+//!
+//! Run all benchmarks, or only the ones with a name matching a filter:
+//!   cargo bench --bench dsp [filter]
+//! Save a baseline before a change, then compare to it after the change:
+//!   cargo bench --bench dsp -- --save-baseline before
+//!   cargo bench --bench dsp -- --baseline before
+//! Baselines are kept in target/criterion and depend on the machine they were measured on.
+
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+
+use muslib::algs::fft::Fft1024;
+use muslib::algs::hpcp::HPCP;
+use muslib::algs::stft::FFT;
+use muslib::algs::synth::Synthesizer;
+use muslib::algs::Algorithm;
+
+fn signal(n: usize) -> Vec<f64> {
+    (0..n).map(|x| (x as f64 * 0.05).sin()).collect()
+}
+
+fn fft(c: &mut Criterion) {
+    let mut group = c.benchmark_group("fft");
+    for size in [256, 1024, 4096] {
+        let mut fft = FFT::new();
+        let frame = signal(size);
+        let mut output = Vec::new();
+        group.bench_with_input(BenchmarkId::from_parameter(size), &frame, |b, frame| {
+            b.iter(|| fft.compute_into(black_box(frame), &mut output).unwrap())
+        });
+    }
+
+    let fft = Fft1024::new();
+    let frame: [f64; 1024] = signal(1024).try_into().unwrap();
+    let mut output = [(0.0, 0.0); 1024];
+    group.bench_function("fixed/1024", |b| {
+        b.iter(|| fft.fft(black_box(&frame), &mut output))
+    });
+    group.finish();
+}
+
+fn hpcp(c: &mut Criterion) {
+    // one frame of spectral peaks, as for each frame of a 44.1 kHz signal
+    let mut hpcp = HPCP::new();
+    let peaks: (Vec<f64>, Vec<f64>) = (1..40).map(|x| (x as f64 * 110.0, 1.0 / x as f64)).unzip();
+    let mut output = Vec::new();
+    c.bench_function("hpcp/frame", |b| {
+        b.iter(|| hpcp.compute_into(black_box(&peaks), &mut output).unwrap())
+    });
+}
+
+fn synth(c: &mut Criterion) {
+    let mut synth = Synthesizer::new();
+    let notes = (vec![440.0, 880.0, 660.0, 0.0], vec![0.25; 4]);
+    c.bench_function("synth/1s", |b| {
+        b.iter(|| synth.compute(black_box(notes.clone())).unwrap())
+    });
+}
+
+criterion_group!(benches, fft, hpcp, synth);
+criterion_main!(benches);
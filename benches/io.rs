@@ -0,0 +1,44 @@
+//! Benchmarks of loading and writing audio files, measured with criterion
+//!
+//! Run them like the DSP benchmarks, see benches/dsp.rs:
+//!   cargo bench --bench io [filter]
+
+use std::env::temp_dir;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+
+use muslib::mixer::{Loader, Writer};
+
+fn signal(n: usize) -> Vec<f64> {
+    (0..n).map(|x| (x as f64 * 0.05).sin() * 0.5).collect()
+}
+
+fn loader(c: &mut Criterion) {
+    let path = temp_dir().join("muslib-bench-loader.wav");
+    Writer::new()
+        .file(path.clone())
+        .write(&signal(44100))
+        .unwrap();
+    c.bench_function("loader/1s", |b| {
+        b.iter(|| {
+            Loader::<f64>::new()
+                .file(path.clone())
+                .load()
+                .map(|x| x.data().len())
+                .unwrap()
+        })
+    });
+    std::fs::remove_file(path).unwrap();
+}
+
+fn writer(c: &mut Criterion) {
+    let path = temp_dir().join("muslib-bench-writer.wav");
+    let samples = signal(44100);
+    c.bench_function("writer/1s", |b| {
+        b.iter(|| Writer::new().file(path.clone()).write(&samples).unwrap())
+    });
+    std::fs::remove_file(path).unwrap();
+}
+
+criterion_group!(benches, loader, writer);
+criterion_main!(benches);
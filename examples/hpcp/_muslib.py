@@ -3,9 +3,9 @@ import math
 import muslib
 
 
-def u16_to_f64(data: list[int]) -> list[float]:
+def i16_to_f64(data: list[int]) -> list[float]:
     d = 1 << 15
-    return [(x - d) / d for x in data]
+    return [x / d for x in data]
 
 
 # the absolute value of each element in a vector of complex numbers
@@ -102,7 +102,7 @@ hpcp = muslib.HPCP()
 size = 2048
 data = "sample.wav"
 data, sample_rate = loader.compute(data)
-data = u16_to_f64(data[:size])
+data = i16_to_f64(data[:size])
 data = fft.compute(data)
 data = magnitude(data)
 frequencies, magnitudes = peaks(data, sample_rate=sample_rate, threshold=0.5)
@@ -2,11 +2,11 @@ use std::env::args;
 
 use muslib::mixer::Loader;
 
-fn main() {
-    let f = args().last().unwrap();
+fn main() -> muslib::Result<()> {
+    let f = args().next_back().unwrap();
 
     let loader = &mut Loader::<f64>::new();
-    let _ = loader.file(f.into()).mono().load();
+    loader.file(f.into()).mono().load()?;
 
     let audio = loader.data();
     let outstr = audio
@@ -15,4 +15,5 @@ fn main() {
         .collect::<Vec<_>>()
         .join(" ");
     println!("{}", outstr);
+    Ok(())
 }
@@ -0,0 +1,189 @@
+"""Generate type stubs for the muslib Python module.
+
+Signatures come from the compiled module, that is from #[pyo3(signature)] data,
+and types come from the data descriptors and the docs of compute methods.
+Build and install the module first, e.g. with `maturin develop`, then run:
+
+    python examples/scripts/stubgen.py > muslib.pyi
+
+Pass --check to compare with an existing muslib.pyi instead.
+"""
+
+import inspect
+import re
+from sys import argv, exit
+
+import muslib
+
+KNOWN = {
+    "__call__": "None",
+    "__enter__": "Self",
+    "__exit__": "None",
+    "__getstate__": "dict[str, Any]",
+    "__iter__": "Self",
+    "__len__": "int",
+    "__repr__": "str",
+    "__setstate__": "None",
+    "close": "None",
+    "configure": "None",
+    "from_dict": "Self",
+    "open": "MonoWriterStream",
+    "reset": "None",
+    "to_dict": "dict[str, Any]",
+    "write": "None",
+}
+
+
+def attribute_type(doc):
+    """type of a data descriptor from a doc like "Param: float -- description" """
+    m = re.match(r"(Input|Output|Param): (.*?) --", doc or "")
+    return m.group(2) if m else "Any"
+
+
+def section(doc, name):
+    """lines of a section like Inputs or Outputs in a doc of a compute method"""
+    m = re.search(name + r":\n((?:  - .*\n?)+)", doc or "")
+    return [line.strip()[2:] for line in m.group(1).strip().split("\n")] if m else []
+
+
+def inputs(doc):
+    """argument types from the Inputs section in a doc of a compute method"""
+    types = {}
+    for line in section(doc, "Inputs"):
+        name, t = line.split(" -- ")[0].split(": ", 1)
+        types[name] = t
+    return types
+
+
+def outputs(doc):
+    """return type from the Outputs section in a doc of a compute method"""
+    types = []
+    for line in section(doc, "Outputs"):
+        if " -- " in line:
+            types.append(line.split(" -- ")[0])
+        else:
+            types.append(line.split(": ", 1)[1])
+    if not types:
+        return "None"
+    return types[0] if len(types) == 1 else "tuple[%s]" % ", ".join(types)
+
+
+def literal_type(default):
+    """type of a default value, if it is a simple literal"""
+    if default in ("True", "False"):
+        return "bool"
+    if default.startswith('"'):
+        return "str"
+    if re.fullmatch(r"-?\d+", default):
+        return "int"
+    if re.fullmatch(r"-?\d+\.\d*(e-?\d+)?", default):
+        return "float"
+    return "Any"
+
+
+def arguments(signature, types, optional):
+    args = []
+    for arg in signature.strip("()").split(", "):
+        if not arg or arg.startswith("$") or arg == "/":
+            continue
+        name, _, default = arg.partition("=")
+        if name.startswith("*"):
+            args.append("%s: Any" % name)
+            continue
+        t = types.get(name, "Any")
+        if t == "Any":
+            t = literal_type(default)
+        if default == "None" and optional and not t.startswith("Optional"):
+            t = "Optional[%s]" % t
+        args.append("%s: %s%s" % (name, t, " = ..." if default else ""))
+    return args
+
+
+def method(cls, name, types):
+    if name == "compute_async":
+        # same arguments as .compute(), outputs are awaited
+        lines = method(cls, "compute", types)
+        lines[-1] = re.sub(r"def compute\((.*)\) -> (.*): \.\.\.", r"def compute_async(\1) -> Awaitable[\2]: ...", lines[-1])
+        return lines
+    raw = inspect.getattr_static(cls, name)
+    kind = type(raw).__name__
+    signature = getattr(getattr(cls, name), "__text_signature__", None)
+    if kind == "wrapper_descriptor" or signature is None:
+        signature = "($self)"
+    doc = getattr(raw, "__doc__", None)
+    if name in KNOWN:
+        ret = KNOWN[name]
+    elif name.startswith("compute"):
+        ret = outputs(doc)
+    else:
+        ret = "Any"
+    if name == "__next__":
+        ret = "list[float]"
+    args = ["cls" if kind == "classmethod_descriptor" else "self"]
+    if kind == "staticmethod":
+        args = []
+    types = dict(types, **inputs(doc))
+    args += arguments(signature, types, name.startswith("compute"))
+    lines = []
+    if kind == "classmethod_descriptor":
+        lines.append("    @classmethod")
+    elif kind == "staticmethod":
+        lines.append("    @staticmethod")
+    lines.append("    def %s(%s) -> %s: ..." % (name, ", ".join(args), ret))
+    return lines
+
+
+def stub(cls):
+    lines = ["class %s:" % cls.__name__]
+    if cls.__doc__:
+        doc = cls.__doc__.strip().replace("\n", "\n    ")
+        lines.append('    """%s"""' % doc)
+    types = {}
+    attributes = []
+    methods = []
+    for name, raw in sorted(vars(cls).items()):
+        if type(raw).__name__ == "getset_descriptor":
+            types[name] = attribute_type(raw.__doc__)
+            attributes.append(name)
+        elif callable(raw) or type(raw).__name__.endswith("method_descriptor"):
+            if name.startswith("__") and name not in KNOWN and name != "__next__":
+                continue
+            methods.append(name)
+    init = cls.__text_signature__
+    for arg in (init or "").strip("()").split(", "):
+        name, _, default = arg.partition("=")
+        if types.get(name) == "Any":
+            types[name] = literal_type(default)
+    for name in attributes:
+        lines.append("    %s: %s" % (name, types[name]))
+    if init is not None:
+        args = ["self"] + arguments(init, types, False)
+        lines.append("    def __init__(%s) -> None: ..." % ", ".join(args))
+    for name in methods:
+        lines += method(cls, name, types)
+    return "\n".join(lines)
+
+
+def generate():
+    out = [
+        '"""%s"""' % muslib.__doc__.strip(),
+        "",
+        "from typing import Any, Awaitable, Optional, Self",
+        "",
+        "class MuslibError(Exception): ...",
+    ]
+    for name, cls in sorted(vars(muslib).items()):
+        if isinstance(cls, type) and name != "MuslibError":
+            out += ["", stub(cls)]
+    return "\n".join(out) + "\n"
+
+
+if __name__ == "__main__":
+    stubs = generate()
+    if "--check" in argv:
+        with open("muslib.pyi", "r") as f:
+            if f.read() != stubs:
+                print("muslib.pyi is out of date")
+                exit(1)
+    else:
+        print(stubs, end="")
@@ -0,0 +1,28 @@
+use std::env::args;
+
+use muslib::algs::stft::FFT;
+use muslib::algs::Algorithm;
+use muslib::mixer::Loader;
+use muslib::streaming::{map, Compute, Frames, Node};
+
+fn main() -> muslib::Result<()> {
+    let f = args().next_back().unwrap();
+
+    // decode the file lazily and print the loudest frequency bin of each frame
+    let packets = Loader::<f64>::new().file(f.into()).mono().packets()?;
+    let mut network =
+        Frames::new(2048, 1024)
+            .then(Compute(FFT::new()))
+            .then(map(|spectrum: Vec<(f32, f32)>| {
+                let bins = spectrum.iter().take(spectrum.len() / 2 + 1);
+                let energy = bins.map(|(re, im)| re * re + im * im).enumerate();
+                energy
+                    .fold((0, 0.0), |a, b| if b.1 > a.1 { b } else { a })
+                    .0
+            }));
+
+    network.run(packets, |bin| {
+        println!("{}", bin);
+        Ok(())
+    })
+}
@@ -1,14 +1,16 @@
 use muslib::algs::*;
+use muslib::mixer::Writer;
 
-fn main() {
-    let mut s = synth::Synthesizer::new();
-    s.envelope = vec![0.1, 0.02, 0.2, 0.6, 0.1];
-    s.freq = vec![440.0, 880.0, 660.0, 0.0]; // A_4, A_5, E_5, silence
-    s.durations = vec![0.8, 0.7, 1.0, 0.5];
-    s.compute();
+fn main() -> muslib::Result<()> {
+    let mut s = synth::Synthesizer::builder()
+        .envelope(vec![0.1, 0.02, 0.2, 0.6, 0.1])
+        .build();
+    let freq = vec![440.0, 880.0, 660.0, 0.0]; // A_4, A_5, E_5, silence
+    let durations = vec![0.8, 0.7, 1.0, 0.5];
+    let pcm_data = s.compute((freq, durations))?;
 
-    let mut w = io::MonoWriter::new();
-    w.file = "test.wav".into();
-    w.pcm_data = s.pcm_data.unwrap();
-    w.compute()
+    Writer::new()
+        .sample_rate(44100)
+        .file("test.wav".into())
+        .write(&pcm_data)
 }
@@ -0,0 +1,117 @@
+#ifndef MUSLIB_H
+#define MUSLIB_H
+
+/* Generated with cbindgen from src/ffi.rs, do not edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// Opaque handle of an Algorithm, created with muslib_create() and released with muslib_free()
+typedef struct MuslibAlgorithm MuslibAlgorithm;
+
+// Flat array of samples passed to and returned from muslib_compute()
+//
+// Arrays returned by muslib are owned by the caller and released with muslib_free_array().
+typedef struct MuslibArray {
+  // pointer to the first value, may be null when the array is empty
+  double *data;
+  // number of values
+  size_t len;
+} MuslibArray;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Create an Algorithm by its name, e.g. "HPCP", configured with parameters given as a JSON object.
+//
+// Every Algorithm of this crate is available under the name of its type.
+// Parameters missing from the object, or all of them when `params` is null, keep their defaults.
+// Returns null on error, see muslib_last_error().
+//
+// # Safety
+//
+// `name` and `params` must be null or point to NUL-terminated strings.
+MuslibAlgorithm *muslib_create(const char *name, const char *params);
+
+// Set new parameters of an Algorithm given as a JSON object, clearing its state.
+//
+// Parameters missing from the object are set to their defaults.
+// Returns 0 on success and -1 on error, see muslib_last_error().
+//
+// # Safety
+//
+// `algorithm` must be null or a handle returned by muslib_create(),
+// and `params` must be null or point to a NUL-terminated string.
+int32_t muslib_configure(MuslibAlgorithm *algorithm, const char *params);
+
+// Compute an Algorithm for flat arrays of samples and write its outputs.
+//
+// Inputs are passed as separate arrays, e.g. the left and right channel to MidSide,
+// and trailing optional ones like a sidechain can be left out.
+// TruePeakDetector takes one array for each channel.
+// FFT outputs and IFFT inputs interleave real and imaginary parts, regions found by
+// Declicker and Declipper and events found by the problem detectors interleave their start and end,
+// and overs found by TruePeakDetector are (channel, start, end) triples.
+// A position missing from the output of StartStopSilence is NaN.
+// MonoLoader, MonoFloatLoader and MonoWriter take paths and are computed with muslib_compute_json().
+// Writes the outputs to `outputs` and returns how many were written, or -1 on error,
+// see muslib_last_error(). Each written array has to be released with muslib_free_array().
+//
+// # Safety
+//
+// `algorithm` must be null or a handle returned by muslib_create(),
+// `inputs` must point to `count` arrays, each pointing to `len` values,
+// and `outputs` must point to room for `capacity` arrays.
+ptrdiff_t muslib_compute(MuslibAlgorithm *algorithm,
+                         const MuslibArray *inputs,
+                         size_t count,
+                         MuslibArray *outputs,
+                         size_t capacity);
+
+// Compute an Algorithm for its input given as JSON and return its output as JSON.
+//
+// The input has the same shape as in Python, e.g. `[[440.0], [1.0]]` for a Synthesizer.
+// Returns null on error, see muslib_last_error().
+// The returned string has to be released with muslib_free_string().
+//
+// # Safety
+//
+// `algorithm` must be null or a handle returned by muslib_create(),
+// and `input` must be null or point to a NUL-terminated string.
+char *muslib_compute_json(MuslibAlgorithm *algorithm, const char *input);
+
+// Message of the last error on this thread, or null if there was none.
+//
+// The message stays valid until the next failing call on the same thread.
+const char *muslib_last_error(void);
+
+// Release an Algorithm created with muslib_create().
+//
+// # Safety
+//
+// `algorithm` must be null or a handle returned by muslib_create() that was not released yet.
+void muslib_free(MuslibAlgorithm *algorithm);
+
+// Release an array written by muslib_compute().
+//
+// # Safety
+//
+// `array` must be written by muslib_compute() and not released yet.
+void muslib_free_array(MuslibArray array);
+
+// Release a string returned by muslib_compute_json().
+//
+// # Safety
+//
+// `s` must be null or a string returned by muslib_compute_json() that was not released yet.
+void muslib_free_string(char *s);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* MUSLIB_H */
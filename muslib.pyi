@@ -0,0 +1,793 @@
+"""Rust library for music synthesis and processing, inspired by Essentia.
+
+Inputs accept lists as well as numpy arrays or any other buffer, contiguous ones are read in place.
+Outputs are returned as numpy arrays owning their memory when numpy is installed, and as lists otherwise.
+The GIL is released while computing, so algorithms can run concurrently in threads.
+Each .compute() has a .compute_async() variant that can be awaited in asyncio code.
+
+Classes are grouped in submodules, e.g. muslib.io or muslib.synth,
+and all of them are also available directly in muslib."""
+
+from typing import Any, Awaitable, Optional, Self
+
+class MuslibError(Exception): ...
+
+class AudioMetadata:
+    """Tags and stream properties of an audio file, read without decoding it
+    
+    Fields missing from the file are None."""
+    album: Optional[str]
+    artist: Optional[str]
+    bitrate: Optional[int]
+    channels: Optional[int]
+    codec: Optional[str]
+    duration: Optional[float]
+    file: str
+    sample_rate: Optional[int]
+    tags: list[tuple[str, str]]
+    title: Optional[str]
+    track: Optional[int]
+    def __init__(self, file: str) -> None: ...
+    def __repr__(self) -> str: ...
+
+class ClickDetector:
+    """Detect impulsive clicks and discontinuities of the waveform, e.g. from bad edits
+    
+    Samples that linear prediction from the preceding ones misses by far are flagged,
+    as in the Declicker, but they're only reported with their severity and not repaired."""
+    events: Optional[list[tuple[float, float]]]
+    order: int
+    sample_rate: float
+    severities: Optional[list[float]]
+    signal: list[float]
+    threshold: float
+    def __init__(self, sample_rate: float = ..., order: int = ..., threshold: float = ...) -> None: ...
+    def __call__(self) -> None: ...
+    def __getstate__(self) -> dict[str, Any]: ...
+    def __repr__(self) -> str: ...
+    def __setstate__(self, state: Any) -> None: ...
+    def compute(self, signal: Optional[list[float]] = ...) -> tuple[list[tuple[float, float]], list[float]]: ...
+    def compute_async(self, signal: Optional[list[float]] = ...) -> Awaitable[tuple[list[tuple[float, float]], list[float]]]: ...
+    def configure(self, **params: Any) -> None: ...
+    @classmethod
+    def from_dict(cls, params: Any) -> Self: ...
+    def reset(self) -> None: ...
+    def to_dict(self) -> dict[str, Any]: ...
+
+class Compressor:
+    """Downward compressor with an optional sidechain input"""
+    attack: float
+    gain: Optional[list[float]]
+    makeup: float
+    processed: Optional[list[float]]
+    ratio: float
+    release: float
+    sample_rate: float
+    sidechain: Optional[list[float]]
+    sidechain_filter: str
+    sidechain_frequency: float
+    signal: list[float]
+    threshold: float
+    def __init__(self, sample_rate: float = ..., threshold: float = ..., ratio: float = ..., attack: float = ..., release: float = ..., makeup: float = ..., sidechain_filter: str = ..., sidechain_frequency: float = ...) -> None: ...
+    def __call__(self) -> None: ...
+    def __getstate__(self) -> dict[str, Any]: ...
+    def __repr__(self) -> str: ...
+    def __setstate__(self, state: Any) -> None: ...
+    def compute(self, signal: Optional[list[float]] = ..., sidechain: Optional[list[float]] = ...) -> list[float]: ...
+    def compute_async(self, signal: Optional[list[float]] = ..., sidechain: Optional[list[float]] = ...) -> Awaitable[list[float]]: ...
+    def configure(self, **params: Any) -> None: ...
+    @classmethod
+    def from_dict(cls, params: Any) -> Self: ...
+    def reset(self) -> None: ...
+    def to_dict(self) -> dict[str, Any]: ...
+
+class Declicker:
+    """Detect and repair impulsive clicks with linear prediction"""
+    margin: int
+    method: str
+    order: int
+    regions: Optional[list[tuple[int, int]]]
+    restored: Optional[list[float]]
+    signal: list[float]
+    threshold: float
+    def __init__(self, order: int = ..., threshold: float = ..., margin: int = ..., method: str = ...) -> None: ...
+    def __call__(self) -> None: ...
+    def __getstate__(self) -> dict[str, Any]: ...
+    def __repr__(self) -> str: ...
+    def __setstate__(self, state: Any) -> None: ...
+    def compute(self, signal: Optional[list[float]] = ...) -> tuple[list[float], list[tuple[int, int]]]: ...
+    def compute_async(self, signal: Optional[list[float]] = ...) -> Awaitable[tuple[list[float], list[tuple[int, int]]]]: ...
+    def configure(self, **params: Any) -> None: ...
+    @classmethod
+    def from_dict(cls, params: Any) -> Self: ...
+    def reset(self) -> None: ...
+    def to_dict(self) -> dict[str, Any]: ...
+
+class Declipper:
+    """Detect and repair clipped regions by interpolating over them"""
+    clip_level: float
+    method: str
+    min_run: int
+    order: int
+    regions: Optional[list[tuple[int, int]]]
+    restored: Optional[list[float]]
+    signal: list[float]
+    def __init__(self, clip_level: float = ..., min_run: int = ..., order: int = ..., method: str = ...) -> None: ...
+    def __call__(self) -> None: ...
+    def __getstate__(self) -> dict[str, Any]: ...
+    def __repr__(self) -> str: ...
+    def __setstate__(self, state: Any) -> None: ...
+    def compute(self, signal: Optional[list[float]] = ...) -> tuple[list[float], list[tuple[int, int]]]: ...
+    def compute_async(self, signal: Optional[list[float]] = ...) -> Awaitable[tuple[list[float], list[tuple[int, int]]]]: ...
+    def configure(self, **params: Any) -> None: ...
+    @classmethod
+    def from_dict(cls, params: Any) -> Self: ...
+    def reset(self) -> None: ...
+    def to_dict(self) -> dict[str, Any]: ...
+
+class Denoiser:
+    """Noise reduction with spectral subtraction or Wiener gain over the STFT"""
+    denoised: Optional[list[float]]
+    floor: float
+    frame_size: int
+    hop_size: int
+    method: str
+    noise_end: float
+    noise_profile: Optional[list[float]]
+    noise_start: float
+    quietest_fraction: float
+    reduction: float
+    sample_rate: float
+    signal: list[float]
+    def __init__(self, sample_rate: float = ..., frame_size: int = ..., hop_size: int = ..., noise_start: float = ..., noise_end: float = ..., quietest_fraction: float = ..., method: str = ..., reduction: float = ..., floor: float = ...) -> None: ...
+    def __call__(self) -> None: ...
+    def __getstate__(self) -> dict[str, Any]: ...
+    def __repr__(self) -> str: ...
+    def __setstate__(self, state: Any) -> None: ...
+    def compute(self, signal: Optional[list[float]] = ...) -> list[float]: ...
+    def compute_async(self, signal: Optional[list[float]] = ...) -> Awaitable[list[float]]: ...
+    def configure(self, **params: Any) -> None: ...
+    @classmethod
+    def from_dict(cls, params: Any) -> Self: ...
+    def reset(self) -> None: ...
+    def to_dict(self) -> dict[str, Any]: ...
+
+class Envelope:
+    """envelope used for wavetable generation, linear unless its segments are curved
+    
+    It's either applied to tones of a known duration with .multiplier(),
+    or played like a key with .note_on() and .note_off() when the length isn't known in advance."""
+    a: float
+    attack_curve: str
+    d: float
+    decay_curve: str
+    h: float
+    r: float
+    release_curve: str
+    s: float
+    def __init__(self, a: float = ..., h: float = ..., d: float = ..., s: float = ..., r: float = ..., attack_curve: str = ..., decay_curve: str = ..., release_curve: str = ...) -> None: ...
+    def __getstate__(self) -> dict[str, Any]: ...
+    def __repr__(self) -> str: ...
+    def __setstate__(self, state: Any) -> None: ...
+    def active(self) -> Any: ...
+    @staticmethod
+    def adsr(a: float, d: float, s: float, r: float) -> Any: ...
+    def configure(self, **params: Any) -> None: ...
+    @classmethod
+    def from_dict(cls, params: Any) -> Self: ...
+    def multiplier(self, g: Any, x: Any, duration: int = ...) -> Any: ...
+    def next_level(self, sample_rate: Any) -> Any: ...
+    def note_off(self) -> Any: ...
+    def note_on(self) -> Any: ...
+    def reset(self) -> None: ...
+    def to_dict(self) -> dict[str, Any]: ...
+
+class FFT:
+    """Fast Fourier transform of a single audio frame or a spectrogram of a whole signal
+    
+    Framing, windowing and zero-padding parameters only apply to the spectrogram,
+    single frames are transformed as they are."""
+    fft_data: list[tuple[float, float]]
+    frame: list[float]
+    frame_size: int
+    hop_size: int
+    signal: list[float]
+    spectrogram: Optional[list[list[float]]]
+    window: str
+    zero_padding: int
+    def __init__(self, frame_size: int = ..., hop_size: int = ..., window: str = ..., zero_padding: int = ...) -> None: ...
+    def __getstate__(self) -> dict[str, Any]: ...
+    def __repr__(self) -> str: ...
+    def __setstate__(self, state: Any) -> None: ...
+    def compute(self, frame: Optional[list[float]] = ...) -> list[tuple[float, float]]: ...
+    def compute_async(self, frame: Optional[list[float]] = ...) -> Awaitable[list[tuple[float, float]]]: ...
+    def compute_batch(self, frames: list[list[float]]) -> list[list[tuple[float, float]]]: ...
+    def compute_matrix(self, signal: Optional[list[float]] = ..., sample_rate: float = ...) -> Matrix: ...
+    def compute_spectrogram(self, signal: Optional[list[float]] = ...) -> list[list[float]]: ...
+    def configure(self, **params: Any) -> None: ...
+    @classmethod
+    def from_dict(cls, params: Any) -> Self: ...
+    def reset(self) -> None: ...
+    def to_dict(self) -> dict[str, Any]: ...
+
+class FrameGenerator:
+    """Generate consecutive frames from a file, a signal or a stream of blocks
+    
+    The source can be a path to a file that will be decoded lazily,
+    a whole signal given as a list of floats,
+    or any iterable that yields blocks of samples, e.g. from a live input.
+    The last frame is padded with zeros."""
+    frame_size: int
+    hop_size: int
+    sample_rate: Optional[int]
+    def __init__(self, source: Any, frame_size: int = ..., hop_size: int = ...) -> None: ...
+    def __iter__(self) -> Self: ...
+    def __next__(self) -> list[float]: ...
+    def __repr__(self) -> str: ...
+
+class GapDetector:
+    """Detect gaps, i.e. dropouts of silence in the middle of a signal
+    
+    Silence at the start and at the end of the signal is not a gap."""
+    events: Optional[list[tuple[float, float]]]
+    min_duration: float
+    sample_rate: float
+    signal: list[float]
+    threshold: float
+    def __init__(self, sample_rate: float = ..., threshold: float = ..., min_duration: float = ...) -> None: ...
+    def __call__(self) -> None: ...
+    def __getstate__(self) -> dict[str, Any]: ...
+    def __repr__(self) -> str: ...
+    def __setstate__(self, state: Any) -> None: ...
+    def compute(self, signal: Optional[list[float]] = ...) -> list[tuple[float, float]]: ...
+    def compute_async(self, signal: Optional[list[float]] = ...) -> Awaitable[list[tuple[float, float]]]: ...
+    def configure(self, **params: Any) -> None: ...
+    @classmethod
+    def from_dict(cls, params: Any) -> Self: ...
+    def reset(self) -> None: ...
+    def to_dict(self) -> dict[str, Any]: ...
+
+class Gate:
+    """Noise gate with an optional sidechain input"""
+    attack: float
+    gain: Optional[list[float]]
+    hold: float
+    processed: Optional[list[float]]
+    range: float
+    release: float
+    sample_rate: float
+    sidechain: Optional[list[float]]
+    sidechain_filter: str
+    sidechain_frequency: float
+    signal: list[float]
+    threshold: float
+    def __init__(self, sample_rate: float = ..., threshold: float = ..., range: float = ..., attack: float = ..., hold: float = ..., release: float = ..., sidechain_filter: str = ..., sidechain_frequency: float = ...) -> None: ...
+    def __call__(self) -> None: ...
+    def __getstate__(self) -> dict[str, Any]: ...
+    def __repr__(self) -> str: ...
+    def __setstate__(self, state: Any) -> None: ...
+    def compute(self, signal: Optional[list[float]] = ..., sidechain: Optional[list[float]] = ...) -> list[float]: ...
+    def compute_async(self, signal: Optional[list[float]] = ..., sidechain: Optional[list[float]] = ...) -> Awaitable[list[float]]: ...
+    def configure(self, **params: Any) -> None: ...
+    @classmethod
+    def from_dict(cls, params: Any) -> Self: ...
+    def reset(self) -> None: ...
+    def to_dict(self) -> dict[str, Any]: ...
+
+class Generator:
+    """tone generator with a given frequency and sample rate
+    
+    Samples are either computed at any index with .amplitude(), or one after another
+    with .next_sample(), accumulating the phase so the frequency can change without clicks."""
+    band_limited: bool
+    freq: float
+    sample_rate: float
+    seed: int
+    waveform: str
+    def __init__(self, freq: float = ..., sample_rate: float = ..., waveform: str = ..., band_limited: bool = ..., seed: int = ...) -> None: ...
+    def __getstate__(self) -> dict[str, Any]: ...
+    def __repr__(self) -> str: ...
+    def __setstate__(self, state: Any) -> None: ...
+    def amplitude(self, x: Any) -> Any: ...
+    def configure(self, **params: Any) -> None: ...
+    @classmethod
+    def from_dict(cls, params: Any) -> Self: ...
+    def next_sample(self) -> Any: ...
+    def reset(self) -> None: ...
+    def time(self, t: Any) -> Any: ...
+    def to_dict(self) -> dict[str, Any]: ...
+
+class HPCP:
+    """Harmonic Pitch Class Profile computed from spectral peaks"""
+    band_split_frequency: float
+    frequencies: list[float]
+    harmonic_peaks: Any
+    harmonics: int
+    hpcp_data: Optional[list[float]]
+    magnitudes: list[float]
+    max_frequency: float
+    min_frequency: float
+    nonlinear_post: bool
+    normalized: bool
+    reference_frequency: float
+    sample_rate: float
+    size: int
+    weighting: str
+    weighting_window_size: float
+    def __init__(self, size: int = ..., sample_rate: float = ..., reference_frequency: float = ..., band_split_frequency: float = ..., max_frequency: float = ..., min_frequency: float = ..., harmonics: int = ..., weighting: str = ..., weighting_window_size: float = ..., normalized: bool = ..., nonlinear_post: bool = ...) -> None: ...
+    def __call__(self) -> None: ...
+    def __getstate__(self) -> dict[str, Any]: ...
+    def __repr__(self) -> str: ...
+    def __setstate__(self, state: Any) -> None: ...
+    def compute(self, frequencies: Optional[list[float]] = ..., magnitudes: Optional[list[float]] = ...) -> list[float]: ...
+    def compute_async(self, frequencies: Optional[list[float]] = ..., magnitudes: Optional[list[float]] = ...) -> Awaitable[list[float]]: ...
+    def compute_batch(self, frequencies: list[list[float]], magnitudes: list[list[float]]) -> list[list[float]]: ...
+    def compute_matrix(self, frequencies: list[list[float]], magnitudes: list[list[float]], hop_size: int = ...) -> Matrix: ...
+    def configure(self, **params: Any) -> None: ...
+    @classmethod
+    def from_dict(cls, params: Any) -> Self: ...
+    def reset(self) -> None: ...
+    def to_dict(self) -> dict[str, Any]: ...
+
+class HumDetector:
+    """Detect mains hum at 50 or 60 Hz and its harmonics
+    
+    The energy at the mains frequency and its harmonics is compared with the energy
+    at nearby frequencies in overlapping frames, hum is found in frames where it stands out
+    and it's reported when it lasts long enough."""
+    events: Optional[list[tuple[float, float]]]
+    frame_duration: float
+    frequencies: Optional[list[float]]
+    harmonics: int
+    min_duration: float
+    sample_rate: float
+    signal: list[float]
+    threshold: float
+    def __init__(self, sample_rate: float = ..., frame_duration: float = ..., harmonics: int = ..., threshold: float = ..., min_duration: float = ...) -> None: ...
+    def __call__(self) -> None: ...
+    def __getstate__(self) -> dict[str, Any]: ...
+    def __repr__(self) -> str: ...
+    def __setstate__(self, state: Any) -> None: ...
+    def compute(self, signal: Optional[list[float]] = ...) -> tuple[list[tuple[float, float]], list[float]]: ...
+    def compute_async(self, signal: Optional[list[float]] = ...) -> Awaitable[tuple[list[tuple[float, float]], list[float]]]: ...
+    def configure(self, **params: Any) -> None: ...
+    @classmethod
+    def from_dict(cls, params: Any) -> Self: ...
+    def reset(self) -> None: ...
+    def to_dict(self) -> dict[str, Any]: ...
+
+class IFFT:
+    """Inverse fast Fourier transform of a single spectrum frame"""
+    fft_data: list[tuple[float, float]]
+    frame: list[float]
+    def __init__(self) -> None: ...
+    def __getstate__(self) -> dict[str, Any]: ...
+    def __repr__(self) -> str: ...
+    def __setstate__(self, state: Any) -> None: ...
+    def compute(self, fft_data: Optional[list[tuple[float, float]]] = ...) -> list[float]: ...
+    def compute_async(self, fft_data: Optional[list[tuple[float, float]]] = ...) -> Awaitable[list[float]]: ...
+    def configure(self, **params: Any) -> None: ...
+    @classmethod
+    def from_dict(cls, params: Any) -> Self: ...
+    def reset(self) -> None: ...
+    def to_dict(self) -> dict[str, Any]: ...
+
+class LFO:
+    """low-frequency oscillator modulating the frequency or the amplitude of a tone
+    
+    As vibrato its depth is in cents above and below the frequency,
+    as tremolo it's the fraction of the amplitude taken away at the lowest point."""
+    depth: float
+    rate: float
+    waveform: str
+    def __init__(self, rate: float = ..., depth: float = ..., waveform: str = ...) -> None: ...
+    def __getstate__(self) -> dict[str, Any]: ...
+    def __repr__(self) -> str: ...
+    def __setstate__(self, state: Any) -> None: ...
+    def at(self, x: Any, sample_rate: Any) -> Any: ...
+    def configure(self, **params: Any) -> None: ...
+    @classmethod
+    def from_dict(cls, params: Any) -> Self: ...
+    def reset(self) -> None: ...
+    def to_dict(self) -> dict[str, Any]: ...
+
+class Matrix:
+    """Analysis results of consecutive frames with the values along both axes
+    
+    Rows are bins and columns are frames, so it can be shown directly with
+    plt.imshow(m, origin="lower", aspect="auto", extent=m.extent).
+    It converts to a 2-D numpy array with numpy.asarray()."""
+    bins: list[float]
+    data: list[list[float]]
+    extent: tuple[float, float, float, float]
+    shape: tuple[int, int]
+    times: list[float]
+    def __len__(self) -> int: ...
+    def __repr__(self) -> str: ...
+
+class MedianFilter:
+    """Median smoothing of a signal or a feature sequence"""
+    array: list[float]
+    edge: str
+    smoothed: Optional[list[float]]
+    window_size: int
+    def __init__(self, window_size: int = ..., edge: str = ...) -> None: ...
+    def __call__(self) -> None: ...
+    def __getstate__(self) -> dict[str, Any]: ...
+    def __repr__(self) -> str: ...
+    def __setstate__(self, state: Any) -> None: ...
+    def compute(self, array: Optional[list[float]] = ...) -> list[float]: ...
+    def compute_async(self, array: Optional[list[float]] = ...) -> Awaitable[list[float]]: ...
+    def configure(self, **params: Any) -> None: ...
+    @classmethod
+    def from_dict(cls, params: Any) -> Self: ...
+    def reset(self) -> None: ...
+    def to_dict(self) -> dict[str, Any]: ...
+
+class MidSide:
+    """Split a stereo signal into mid and side signals"""
+    left: list[float]
+    mid: Optional[list[float]]
+    right: list[float]
+    side: Optional[list[float]]
+    def __init__(self) -> None: ...
+    def __call__(self) -> None: ...
+    def __getstate__(self) -> dict[str, Any]: ...
+    def __repr__(self) -> str: ...
+    def __setstate__(self, state: Any) -> None: ...
+    def compute(self, left: Optional[list[float]] = ..., right: Optional[list[float]] = ...) -> tuple[list[float], list[float]]: ...
+    def compute_async(self, left: Optional[list[float]] = ..., right: Optional[list[float]] = ...) -> Awaitable[tuple[list[float], list[float]]]: ...
+    def configure(self, **params: Any) -> None: ...
+    @classmethod
+    def from_dict(cls, params: Any) -> Self: ...
+    def reset(self) -> None: ...
+    def to_dict(self) -> dict[str, Any]: ...
+
+class MonoFloatLoader:
+    """Load a track from a file and mix it down to mono floating point samples"""
+    audio: Optional[list[float]]
+    dtype: str
+    file: str
+    sample_rate: int
+    def __init__(self, dtype: str = ...) -> None: ...
+    def __call__(self) -> None: ...
+    def __getstate__(self) -> dict[str, Any]: ...
+    def __repr__(self) -> str: ...
+    def __setstate__(self, state: Any) -> None: ...
+    def compute(self, file: Optional[str] = ..., progress: Optional[Callable[[float], Any]] = ...) -> tuple[list[float], int]: ...
+    def compute_async(self, file: Optional[str] = ..., progress: Optional[Callable[[float], Any]] = ...) -> Awaitable[tuple[list[float], int]]: ...
+    def configure(self, **params: Any) -> None: ...
+    @classmethod
+    def from_dict(cls, params: Any) -> Self: ...
+    def reset(self) -> None: ...
+    def to_dict(self) -> dict[str, Any]: ...
+
+class MonoLoader:
+    """Load a track from a file and mix it down to mono 16-bit pcm"""
+    file: str
+    pcm_data: Optional[list[int]]
+    sample_rate: int
+    def __init__(self) -> None: ...
+    def __call__(self) -> None: ...
+    def __getstate__(self) -> dict[str, Any]: ...
+    def __repr__(self) -> str: ...
+    def __setstate__(self, state: Any) -> None: ...
+    def compute(self, file: Optional[str] = ..., progress: Optional[Callable[[float], Any]] = ...) -> tuple[list[int], int]: ...
+    def compute_async(self, file: Optional[str] = ..., progress: Optional[Callable[[float], Any]] = ...) -> Awaitable[tuple[list[int], int]]: ...
+    def configure(self, **params: Any) -> None: ...
+    @classmethod
+    def from_dict(cls, params: Any) -> Self: ...
+    def reset(self) -> None: ...
+    def to_dict(self) -> dict[str, Any]: ...
+
+class MonoWriter:
+    """Write mono 16-bit pcm data to a WAV file"""
+    file: str
+    pcm_data: list[int]
+    sample_rate: int
+    def __init__(self, sample_rate: int = ...) -> None: ...
+    def __call__(self) -> None: ...
+    def __getstate__(self) -> dict[str, Any]: ...
+    def __repr__(self) -> str: ...
+    def __setstate__(self, state: Any) -> None: ...
+    def compute(self, file: Optional[str] = ..., pcm_data: Optional[list[int]] = ...) -> None: ...
+    def compute_async(self, file: Optional[str] = ..., pcm_data: Optional[list[int]] = ...) -> Awaitable[None]: ...
+    def configure(self, **params: Any) -> None: ...
+    @classmethod
+    def from_dict(cls, params: Any) -> Self: ...
+    @staticmethod
+    def open(file: str, sample_rate: int = ...) -> MonoWriterStream: ...
+    def reset(self) -> None: ...
+    def to_dict(self) -> dict[str, Any]: ...
+
+class MonoWriterStream:
+    """Stream of mono 16-bit pcm data written to a WAV file block by block
+    
+    Created with MonoWriter.open(), the file is finalized with .close()
+    or at the end of a with block."""
+    closed: bool
+    file: str
+    sample_rate: int
+    samples: int
+    def __enter__(self) -> Self: ...
+    def __exit__(self, _exc_type: Any, _exc_value: Any, _traceback: Any) -> None: ...
+    def __repr__(self) -> str: ...
+    def close(self) -> None: ...
+    def flush(self) -> Any: ...
+    def write(self, pcm_data: Any) -> None: ...
+
+class MovingAverage:
+    """Moving-average smoothing of a signal or a feature sequence"""
+    array: list[float]
+    edge: str
+    smoothed: Optional[list[float]]
+    window_size: int
+    def __init__(self, window_size: int = ..., edge: str = ...) -> None: ...
+    def __call__(self) -> None: ...
+    def __getstate__(self) -> dict[str, Any]: ...
+    def __repr__(self) -> str: ...
+    def __setstate__(self, state: Any) -> None: ...
+    def compute(self, array: Optional[list[float]] = ...) -> list[float]: ...
+    def compute_async(self, array: Optional[list[float]] = ...) -> Awaitable[list[float]]: ...
+    def configure(self, **params: Any) -> None: ...
+    @classmethod
+    def from_dict(cls, params: Any) -> Self: ...
+    def reset(self) -> None: ...
+    def to_dict(self) -> dict[str, Any]: ...
+
+class Noise:
+    """source of white, pink or brown noise rendered to buffers, e.g. to test filters or spectra
+    
+    The noise is the same as the noise waveforms of a Generator with the same seed,
+    so any part of it can be rendered again the same, and different seeds are independent."""
+    color: str
+    seed: int
+    def __init__(self, color: str = ..., seed: int = ...) -> None: ...
+    def __getstate__(self) -> dict[str, Any]: ...
+    def __repr__(self) -> str: ...
+    def __setstate__(self, state: Any) -> None: ...
+    def at(self, x: Any) -> Any: ...
+    def configure(self, **params: Any) -> None: ...
+    @classmethod
+    def from_dict(cls, params: Any) -> Self: ...
+    def render(self, n: Any) -> Any: ...
+    def reset(self) -> None: ...
+    def to_dict(self) -> dict[str, Any]: ...
+
+class Resample:
+    """Convert a signal between sample rates
+    
+    Linear interpolation is the fastest, but aliases and dulls high frequencies.
+    Polyphase filtering tabulates a windowed sinc for each phase of a rational ratio of
+    integer rates like 44100/48000, and falls back to the windowed sinc for other rates.
+    The windowed sinc is evaluated for every output sample, with the longest filter."""
+    input_sample_rate: float
+    output_sample_rate: float
+    quality: str
+    resampled: Optional[list[float]]
+    signal: list[float]
+    def __init__(self, input_sample_rate: float = ..., output_sample_rate: float = ..., quality: str = ...) -> None: ...
+    def __call__(self) -> None: ...
+    def __getstate__(self) -> dict[str, Any]: ...
+    def __repr__(self) -> str: ...
+    def __setstate__(self, state: Any) -> None: ...
+    def compute(self, signal: Optional[list[float]] = ...) -> list[float]: ...
+    def compute_async(self, signal: Optional[list[float]] = ...) -> Awaitable[list[float]]: ...
+    def configure(self, **params: Any) -> None: ...
+    @classmethod
+    def from_dict(cls, params: Any) -> Self: ...
+    def reset(self) -> None: ...
+    def to_dict(self) -> dict[str, Any]: ...
+
+class SaturationDetector:
+    """Detect saturation, i.e. flat runs of samples near full scale
+    
+    Unlike the Declipper, which looks for runs at exactly the peak level,
+    it also finds saturation that was lowered in level after it happened."""
+    differential: float
+    events: Optional[list[tuple[float, float]]]
+    min_duration: float
+    sample_rate: float
+    signal: list[float]
+    threshold: float
+    def __init__(self, sample_rate: float = ..., threshold: float = ..., differential: float = ..., min_duration: float = ...) -> None: ...
+    def __call__(self) -> None: ...
+    def __getstate__(self) -> dict[str, Any]: ...
+    def __repr__(self) -> str: ...
+    def __setstate__(self, state: Any) -> None: ...
+    def compute(self, signal: Optional[list[float]] = ...) -> list[tuple[float, float]]: ...
+    def compute_async(self, signal: Optional[list[float]] = ...) -> Awaitable[list[tuple[float, float]]]: ...
+    def configure(self, **params: Any) -> None: ...
+    @classmethod
+    def from_dict(cls, params: Any) -> Self: ...
+    def reset(self) -> None: ...
+    def to_dict(self) -> dict[str, Any]: ...
+
+class StartStopSilence:
+    """Find the first and the last non-silent frames of a recording, frame by frame
+    
+    Frames of a stream are given one at a time and the outputs are updated with each of them,
+    so after the last frame they bound the audible part of the recording.
+    Call .reset() before starting with another recording."""
+    frame: list[float]
+    start_frame: Optional[int]
+    stop_frame: Optional[int]
+    threshold: float
+    def __init__(self, threshold: float = ...) -> None: ...
+    def __call__(self) -> None: ...
+    def __getstate__(self) -> dict[str, Any]: ...
+    def __repr__(self) -> str: ...
+    def __setstate__(self, state: Any) -> None: ...
+    def compute(self, frame: Optional[list[float]] = ...) -> tuple[Optional[int], Optional[int]]: ...
+    def compute_async(self, frame: Optional[list[float]] = ...) -> Awaitable[tuple[Optional[int], Optional[int]]]: ...
+    def configure(self, **params: Any) -> None: ...
+    @classmethod
+    def from_dict(cls, params: Any) -> Self: ...
+    def reset(self) -> None: ...
+    def to_dict(self) -> dict[str, Any]: ...
+
+class StereoWidener:
+    """Adjust the width of a stereo image with mid-side processing"""
+    left: list[float]
+    right: list[float]
+    stereo_data: Optional[tuple[list[float], list[float]]]
+    width: float
+    def __init__(self, width: float = ...) -> None: ...
+    def __call__(self) -> None: ...
+    def __getstate__(self) -> dict[str, Any]: ...
+    def __repr__(self) -> str: ...
+    def __setstate__(self, state: Any) -> None: ...
+    def compute(self, left: Optional[list[float]] = ..., right: Optional[list[float]] = ...) -> tuple[list[float], list[float]]: ...
+    def compute_async(self, left: Optional[list[float]] = ..., right: Optional[list[float]] = ...) -> Awaitable[tuple[list[float], list[float]]]: ...
+    def configure(self, **params: Any) -> None: ...
+    @classmethod
+    def from_dict(cls, params: Any) -> Self: ...
+    def reset(self) -> None: ...
+    def to_dict(self) -> dict[str, Any]: ...
+
+class Sweep:
+    """sine sweeping from one frequency to another over a duration, e.g. to measure filters
+    
+    A linear sweep changes the frequency by the same number of Hz every second,
+    a logarithmic one by the same number of octaves, spending as much time on every octave.
+    The phase is integrated exactly, so the sweep is continuous and starts at phase 0."""
+    duration: float
+    f0: float
+    f1: float
+    log: bool
+    sample_rate: float
+    def __init__(self, f0: float = ..., f1: float = ..., duration: float = ..., sample_rate: float = ..., log: bool = ...) -> None: ...
+    def __getstate__(self) -> dict[str, Any]: ...
+    def __repr__(self) -> str: ...
+    def __setstate__(self, state: Any) -> None: ...
+    def amplitude(self, x: Any) -> Any: ...
+    def configure(self, **params: Any) -> None: ...
+    def freq(self, t: Any) -> Any: ...
+    @classmethod
+    def from_dict(cls, params: Any) -> Self: ...
+    def render(self) -> Any: ...
+    def reset(self) -> None: ...
+    def to_dict(self) -> dict[str, Any]: ...
+
+class Synthesizer:
+    """Synthesizer for sequences of simple tones"""
+    amplitudes: list[float]
+    durations: list[float]
+    envelope: list[float]
+    fade: float
+    freq: list[float]
+    glide: float
+    pan: float
+    pans: list[float]
+    pcm_data: Optional[list[int]]
+    sample_rate: int
+    tremolo: list[float]
+    unison: list[float]
+    vibrato: list[float]
+    waveform: str
+    def __init__(self, sample_rate: int = ..., envelope: list[float] = ..., waveform: str = ..., glide: float = ..., vibrato: list[float] = ..., tremolo: list[float] = ..., unison: list[float] = ..., fade: float = ..., pan: float = ...) -> None: ...
+    def __call__(self) -> None: ...
+    def __getstate__(self) -> dict[str, Any]: ...
+    def __repr__(self) -> str: ...
+    def __setstate__(self, state: Any) -> None: ...
+    def compute(self, freq: Optional[list[float]] = ..., durations: Optional[list[float]] = ..., amplitudes: Optional[list[float]] = ...) -> list[int]: ...
+    def compute_async(self, freq: Optional[list[float]] = ..., durations: Optional[list[float]] = ..., amplitudes: Optional[list[float]] = ...) -> Awaitable[list[int]]: ...
+    def compute_notes(self, notes: list[tuple[float, float, float, float]]) -> list[int]: ...
+    def compute_u16(self, freq: Optional[list[float]] = ..., durations: Optional[list[float]] = ..., amplitudes: Optional[list[float]] = ...) -> list[int]: ...
+    def configure(self, **params: Any) -> None: ...
+    @classmethod
+    def from_dict(cls, params: Any) -> Self: ...
+    def reset(self) -> None: ...
+    def stereo(self, freq: list[float] = ..., durations: list[float] = ..., amplitudes: list[float] = ..., pans: list[float] = ...) -> Any: ...
+    def stereo_notes(self, notes: list[tuple[float, float, float, float, float]]) -> Any: ...
+    def to_dict(self) -> dict[str, Any]: ...
+
+class TruePeakDetector:
+    """Measure true peaks of a multi-channel signal after ITU-R BS.1770
+    
+    Each channel is oversampled four times with the interpolation filter of the recommendation,
+    so peaks between samples, which a DAC would reproduce, are not missed.
+    Overs are the regions where the true peak exceeds the threshold."""
+    channels: list[list[float]]
+    overs: Optional[list[list[tuple[float, float]]]]
+    peaks: Optional[list[float]]
+    sample_rate: float
+    threshold: float
+    def __init__(self, sample_rate: float = ..., threshold: float = ...) -> None: ...
+    def __call__(self) -> None: ...
+    def __getstate__(self) -> dict[str, Any]: ...
+    def __repr__(self) -> str: ...
+    def __setstate__(self, state: Any) -> None: ...
+    def compute(self, channels: Optional[list[list[float]]] = ...) -> tuple[list[float], list[list[tuple[float, float]]]]: ...
+    def compute_async(self, channels: Optional[list[list[float]]] = ...) -> Awaitable[tuple[list[float], list[list[tuple[float, float]]]]]: ...
+    def configure(self, **params: Any) -> None: ...
+    @classmethod
+    def from_dict(cls, params: Any) -> Self: ...
+    def reset(self) -> None: ...
+    def to_dict(self) -> dict[str, Any]: ...
+
+class Unison:
+    """unison of detuned copies of a tone, spread across the stereo field
+    
+    Voices are detuned evenly from half of the detune below the tone to half of it above,
+    and panned evenly from the left to the right by the spread. They start in phase
+    and are mixed at equal gains, scaled down so they fit the range."""
+    detune: float
+    spread: float
+    voices: int
+    def __init__(self, voices: int = ..., detune: float = ..., spread: float = ...) -> None: ...
+    def __getstate__(self) -> dict[str, Any]: ...
+    def __repr__(self) -> str: ...
+    def __setstate__(self, state: Any) -> None: ...
+    def configure(self, **params: Any) -> None: ...
+    @classmethod
+    def from_dict(cls, params: Any) -> Self: ...
+    def reset(self) -> None: ...
+    def to_dict(self) -> dict[str, Any]: ...
+    def voice(self, k: Any) -> Any: ...
+
+class WSOLA:
+    """Time-stretching with waveform similarity overlap-add (WSOLA)"""
+    factor: float
+    frame_size: int
+    signal: list[float]
+    stretched: Optional[list[float]]
+    tolerance: int
+    def __init__(self, factor: float = ..., frame_size: int = ..., tolerance: int = ...) -> None: ...
+    def __call__(self) -> None: ...
+    def __getstate__(self) -> dict[str, Any]: ...
+    def __repr__(self) -> str: ...
+    def __setstate__(self, state: Any) -> None: ...
+    def compute(self, signal: Optional[list[float]] = ...) -> list[float]: ...
+    def compute_async(self, signal: Optional[list[float]] = ...) -> Awaitable[list[float]]: ...
+    def configure(self, **params: Any) -> None: ...
+    @classmethod
+    def from_dict(cls, params: Any) -> Self: ...
+    def reset(self) -> None: ...
+    def to_dict(self) -> dict[str, Any]: ...
+
+class Wavetable:
+    """wavetable generator"""
+    envelope: Any
+    fade: Any
+    generator: Any
+    pan: Any
+    samples: Any
+    tremolo: Any
+    unison: Any
+    vibrato: Any
+    def __init__(self, generator: Any = ..., envelope: Any = ..., samples: Any = ..., vibrato: Any = ..., tremolo: Any = ..., unison: Any = ..., fade: Any = ..., pan: Any = ...) -> None: ...
+    def __getstate__(self) -> dict[str, Any]: ...
+    def __repr__(self) -> str: ...
+    def __setstate__(self, state: Any) -> None: ...
+    def configure(self, **params: Any) -> None: ...
+    def f32(self) -> Any: ...
+    def f64(self) -> Any: ...
+    @classmethod
+    def from_dict(cls, params: Any) -> Self: ...
+    def i16(self) -> Any: ...
+    def reset(self) -> None: ...
+    def stereo(self) -> Any: ...
+    def time(self, t: Any) -> Any: ...
+    def to_dict(self) -> dict[str, Any]: ...
+    def u16(self) -> Any: ...
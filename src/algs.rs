@@ -1,16 +1,391 @@
+/// struct exported as a Python class when built with the "python" feature
+///
+/// It takes the struct with its #[pyclass] attribute and #[pyo3] attributes of the fields,
+/// which are all left out without the feature, so the struct is plain Rust.
+/// pyo3 doesn't read field attributes behind cfg_attr, so they can't be made conditional directly.
+#[cfg(feature = "python")]
+macro_rules! python_class {
+    (
+        $(#[doc = $doc:literal])*
+        #[pyclass $(($($args:tt)*))?]
+        $(#[derive($($derive:path),*)])?
+        $vis:vis struct $name:ident {
+            $(
+                $(#[doc = $field_doc:literal])*
+                $(#[pyo3($($field_args:tt)*)])?
+                $field_vis:vis $field:ident: $ty:ty
+            ),* $(,)?
+        }
+    ) => {
+        $(#[doc = $doc])*
+        #[::pyo3::pyclass $(($($args)*))?]
+        $(#[derive($($derive),*)])?
+        $vis struct $name {
+            $(
+                $(#[doc = $field_doc])*
+                $(#[pyo3($($field_args)*)])?
+                $field_vis $field: $ty
+            ),*
+        }
+    };
+}
+
+/// struct exported as a Python class when built with the "python" feature
+#[cfg(not(feature = "python"))]
+macro_rules! python_class {
+    (
+        $(#[doc = $doc:literal])*
+        #[pyclass $(($($args:tt)*))?]
+        $(#[derive($($derive:path),*)])?
+        $vis:vis struct $name:ident {
+            $(
+                $(#[doc = $field_doc:literal])*
+                $(#[pyo3($($field_args:tt)*)])?
+                $field_vis:vis $field:ident: $ty:ty
+            ),* $(,)?
+        }
+    ) => {
+        $(#[doc = $doc])*
+        $(#[derive($($derive),*)])?
+        $vis struct $name {
+            $(
+                $(#[doc = $field_doc])*
+                $field_vis $field: $ty
+            ),*
+        }
+    };
+}
+
+/// noise reduction with spectral subtraction
+#[cfg(feature = "std")]
+pub mod denoise;
+/// dynamics processors with sidechain input
+#[cfg(feature = "std")]
+pub mod dynamics;
+/// FFT plans of sizes known at compile time
+pub mod fft;
+/// smoothing, biquad and allpass filters
+pub mod filters;
+/// frame cutting for frame-by-frame analysis of streams
+#[cfg(feature = "std")]
+pub mod frames;
 /// harmonic pitch class profile
+#[cfg(feature = "std")]
 pub mod hpcp;
+/// polyphonic instrument played with MIDI messages
+#[cfg(feature = "std")]
+pub mod instrument;
 /// input and output with wav files
+#[cfg(feature = "std")]
 pub mod io;
+/// detection of audio problems for quality control
+#[cfg(feature = "std")]
+pub mod problems;
+/// sample rate conversion
+#[cfg(feature = "std")]
+pub mod resample;
+/// restoration of clicks and clipped regions
+#[cfg(feature = "std")]
+pub mod restore;
+/// mid-side processing of stereo signals
+#[cfg(feature = "std")]
+pub mod stereo;
 /// short-time Fourier transform: FFT and IFFT
+#[cfg(feature = "std")]
 pub mod stft;
+/// time-stretching with WSOLA
+#[cfg(feature = "std")]
+pub mod stretch;
 /// synthesizer for simple waveforms
 pub mod synth;
+/// events scheduled at sample positions for an instrument
+#[cfg(feature = "std")]
+pub mod timeline;
+
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::vec::Vec;
+use core::fmt::Debug;
+
+use crate::{Error, Result};
+
+/// largest number of samples an Algorithm produces from its parameters,
+/// above 27 hours at 44100 Hz, so lengths computed from durations can't overflow
+pub(crate) const MAX_LENGTH: usize = u32::MAX as usize;
+
+/// check that a parameter is a finite number from range <min; max>
+///
+/// Fails with Error::InvalidParam naming the parameter and the offending value otherwise,
+/// an infinite bound leaves the range open on that side.
+pub(crate) fn check_range(name: &str, value: f64, min: f64, max: f64) -> Result<()> {
+    if value.is_finite() && value >= min && value <= max {
+        return Ok(());
+    }
+    Err(Error::InvalidParam(
+        match (min.is_finite(), max.is_finite()) {
+            (true, true) => format!(
+                "{} must be a number from range <{}; {}>, got {}",
+                name, min, max, value
+            ),
+            (true, false) => format!(
+                "{} must be a finite number of at least {}, got {}",
+                name, min, value
+            ),
+            (false, true) => format!(
+                "{} must be a finite number of at most {}, got {}",
+                name, max, value
+            ),
+            (false, false) => format!("{} must be a finite number, got {}", name, value),
+        },
+    ))
+}
+
+/// check that a parameter is a finite number above 0, like a sample rate or a frequency
+pub(crate) fn check_positive(name: &str, value: f64) -> Result<()> {
+    if value.is_finite() && value > 0.0 {
+        return Ok(());
+    }
+    Err(Error::InvalidParam(format!(
+        "{} must be a finite number above 0, got {}",
+        name, value
+    )))
+}
+
+/// check that a parameter has one of the allowed values, like a method given by name
+///
+/// Fails with Error::InvalidParam naming the parameter, the choices and the offending value otherwise.
+pub(crate) fn check_choice<T: PartialEq + Debug>(
+    name: &str,
+    value: T,
+    choices: &[T],
+) -> Result<()> {
+    if choices.contains(&value) {
+        return Ok(());
+    }
+    Err(Error::InvalidParam(format!(
+        "{} must be one of {:?}, got {:?}",
+        name, choices, value
+    )))
+}
+
+/// check that a number of samples computed from parameters is at most MAX_LENGTH
+pub(crate) fn check_length(name: &str, samples: f64) -> Result<()> {
+    if samples <= MAX_LENGTH as f64 {
+        return Ok(());
+    }
+    Err(Error::InvalidParam(format!(
+        "{} must be at most {} samples long, got {}",
+        name, MAX_LENGTH, samples
+    )))
+}
 
 /// abstraction for all exported Algorithms
-pub trait Algorithm {
+///
+/// Parameters are set once when the Algorithm is created,
+/// then every call to .compute() takes its Inputs and returns its Outputs.
+pub trait Algorithm: Sized {
+    /// data consumed by a single computation
+    type Input;
+    /// data produced by a single computation
+    type Output;
+    /// parameters that configure the Algorithm
+    type Params: Default;
+
     /// create a new instance of the Algorithm with default parameters
-    fn new() -> Self;
+    fn new() -> Self {
+        Self::with_params(Self::Params::default())
+    }
+    /// create a new instance of the Algorithm with given parameters
+    fn with_params(params: Self::Params) -> Self;
     /// compute the Algorithm for given Inputs to produce some Outputs
-    fn compute(&mut self);
+    fn compute(&mut self, input: Self::Input) -> Result<Self::Output>;
+    /// compute the Algorithm for borrowed Inputs, writing Outputs to a buffer reused between calls
+    ///
+    /// By default the Inputs are cloned and the Outputs replace the buffer,
+    /// algorithms working frame by frame reuse the memory of their buffers instead,
+    /// so thousands of frames can be processed without an allocation for each of them.
+    fn compute_into(&mut self, input: &Self::Input, output: &mut Self::Output) -> Result<()>
+    where
+        Self::Input: Clone,
+    {
+        *output = self.compute(input.clone())?;
+        Ok(())
+    }
+}
+
+/// abstraction for filters and effects that process audio in place
+///
+/// Processors carry their state across blocks, so processing a signal block by block
+/// gives the same output as processing it at once, delayed by their latency.
+/// Algorithms that look at the whole signal, like the Denoiser, Declicker and Declipper, are not Processors.
+///
+/// The Processors of this crate -- Biquad, FirstOrderAllpass, SecondOrderAllpass, MovingAverage,
+/// MedianFilter, Compressor, Gate and a Chain of them -- are real-time safe:
+/// .process() neither locks nor allocates once buffers reused between calls have grown to the block size,
+/// which happens on the first block.
+/// They can run inside an audio callback after a warm-up call with the largest block.
+/// Setting up a Chain, changing parameters and Python calls are not real-time safe.
+pub trait Processor {
+    /// process a block of samples in place
+    fn process(&mut self, block: &mut [f64]);
+    /// delay in samples introduced by this Processor
+    fn latency(&self) -> usize {
+        0
+    }
+    /// clear any state carried over between blocks
+    fn reset(&mut self) {}
+}
+
+/// Chain of Processors applied one after another in a single pass
+#[derive(Default)]
+pub struct Chain {
+    processors: Vec<Box<dyn Processor>>,
+}
+
+impl Chain {
+    /// create a new empty Chain
+    pub fn new() -> Self {
+        Chain {
+            processors: Vec::new(),
+        }
+    }
+
+    /// append a Processor at the end of this Chain
+    pub fn push(&mut self, processor: impl Processor + 'static) -> &mut Self {
+        self.processors.push(Box::new(processor));
+        self
+    }
+
+    /// number of Processors in this Chain
+    pub fn len(&self) -> usize {
+        self.processors.len()
+    }
+
+    /// check whether this Chain has no Processors
+    pub fn is_empty(&self) -> bool {
+        self.processors.is_empty()
+    }
+}
+
+impl Processor for Chain {
+    fn process(&mut self, block: &mut [f64]) {
+        for p in self.processors.iter_mut() {
+            p.process(block);
+        }
+    }
+
+    fn latency(&self) -> usize {
+        self.processors.iter().map(|p| p.latency()).sum()
+    }
+
+    fn reset(&mut self) {
+        for p in self.processors.iter_mut() {
+            p.reset();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::cell::Cell;
+
+    use super::dynamics::{Compressor, Gate};
+    use super::filters::{
+        Biquad, FirstOrderAllpass, MedianFilter, MovingAverage, SecondOrderAllpass,
+    };
+    use super::{Algorithm, Chain, Processor};
+
+    /// system allocator counting allocations of each thread
+    struct Counter;
+
+    thread_local! {
+        static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+    }
+
+    unsafe impl GlobalAlloc for Counter {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            ALLOCATIONS.with(|x| x.set(x.get() + 1));
+            System.alloc(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout)
+        }
+    }
+
+    #[global_allocator]
+    static COUNTER: Counter = Counter;
+
+    struct Gain(f64);
+
+    impl Processor for Gain {
+        fn process(&mut self, block: &mut [f64]) {
+            for x in block.iter_mut() {
+                *x *= self.0;
+            }
+        }
+    }
+
+    struct Delay(Vec<f64>);
+
+    impl Processor for Delay {
+        fn process(&mut self, block: &mut [f64]) {
+            for x in block.iter_mut() {
+                self.0.push(*x);
+                *x = self.0.remove(0);
+            }
+        }
+
+        fn latency(&self) -> usize {
+            self.0.len()
+        }
+
+        fn reset(&mut self) {
+            self.0.iter_mut().for_each(|x| *x = 0.0);
+        }
+    }
+
+    #[test]
+    fn chain() {
+        let mut chain = Chain::new();
+        chain
+            .push(Gain(2.0))
+            .push(Delay(vec![0.0; 2]))
+            .push(Gain(0.5));
+        assert_eq!(chain.len(), 3);
+        assert_eq!(chain.latency(), 2);
+
+        let mut block = [1.0, 2.0, 3.0];
+        chain.process(&mut block);
+        assert_eq!(block, [0.0, 0.0, 1.0]);
+        chain.process(&mut block);
+        assert_eq!(block, [2.0, 3.0, 0.0]);
+
+        chain.reset();
+        let mut block = [4.0, 5.0];
+        chain.process(&mut block);
+        assert_eq!(block, [0.0, 0.0]);
+    }
+
+    #[test]
+    fn realtime() {
+        let mut chain = Chain::new();
+        chain
+            .push(Biquad::lowpass(1000.0, 0.707, 44100.0))
+            .push(FirstOrderAllpass::from_frequency(1000.0, 44100.0))
+            .push(SecondOrderAllpass::from_frequency(1000.0, 2.0, 44100.0))
+            .push(Compressor::new())
+            .push(Gate::new())
+            .push(MovingAverage::new())
+            .push(MedianFilter::new());
+
+        let mut block: Vec<f64> = (0..512).map(|x| (x as f64 * 0.1).sin()).collect();
+        chain.process(&mut block);
+
+        let before = ALLOCATIONS.with(|x| x.get());
+        chain.process(&mut block);
+        chain.process(&mut block[..100]);
+        assert_eq!(ALLOCATIONS.with(|x| x.get()), before);
+    }
 }
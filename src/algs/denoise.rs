@@ -0,0 +1,593 @@
+#[cfg(feature = "python")]
+use pyo3::Python;
+use symphonia::core::dsp::complex::Complex;
+use symphonia::core::dsp::fft::Fft;
+
+use super::stft;
+use super::{check_choice, check_positive, check_range, Algorithm};
+use crate::json::{self, FromJson, Json, ToJson};
+#[cfg(feature = "python")]
+use crate::pymod::array::{Array, ArrayView};
+#[cfg(feature = "python")]
+use crate::pymod::state::pymethods_with_params;
+use crate::{Error, Result};
+#[cfg(feature = "python")]
+use pyo3::PyResult;
+
+python_class! {
+    /// Noise reduction with spectral subtraction or Wiener gain over the STFT
+    #[pyclass(module = "muslib", get_all, set_all)]
+    pub struct Denoiser {
+        /// Input: list[float] -- audio signal
+        pub signal: Vec<f64>,
+
+        /// Output: Optional[list[float]] -- denoised audio signal
+        pub denoised: Option<Vec<f64>>,
+        /// Output: Optional[list[float]] -- learned noise power spectrum, frame_size // 2 + 1 bins
+        pub noise_profile: Option<Vec<f64>>,
+
+        /// Param: float -- sampling rate of the audio signal in Hz (default: 44100)
+        pub sample_rate: f64,
+        /// Param: int -- size of the analysis frame, a power of two from 2 to 65536 (default: 2048)
+        pub frame_size: usize,
+        /// Param: int -- number of samples between consecutive frames, at most frame_size (default: 512)
+        pub hop_size: usize,
+        /// Param: float -- start of the noise-only region in seconds (default: 0)
+        pub noise_start: f64,
+        /// Param: float -- end of the noise-only region in seconds, if not after noise_start the quietest frames are used instead (default: 0)
+        pub noise_end: f64,
+        /// Param: float -- fraction of the quietest frames used for the noise profile, from 0 to 1 (default: 0.1)
+        pub quietest_fraction: f64,
+        /// Param: str -- gain function, one of {subtraction, wiener} (default: wiener)
+        pub method: String,
+        /// Param: float -- over-subtraction factor applied to the noise profile (default: 1.0)
+        pub reduction: f64,
+        /// Param: float -- minimum gain applied to any bin from 0 to 1, limits musical noise (default: 0.05)
+        pub floor: f64,
+    }
+}
+
+#[cfg(feature = "python")]
+pymethods_with_params! {
+    impl Denoiser {
+        #[new]
+        #[pyo3(signature = (
+            sample_rate=44100.0,
+            frame_size=2048,
+            hop_size=512,
+            noise_start=0.0,
+            noise_end=0.0,
+            quietest_fraction=0.1,
+            method="wiener",
+            reduction=1.0,
+            floor=0.05,
+        ))]
+        #[allow(clippy::too_many_arguments)]
+        fn pynew(
+            sample_rate: f64,
+            frame_size: usize,
+            hop_size: usize,
+            noise_start: f64,
+            noise_end: f64,
+            quietest_fraction: f64,
+            method: &str,
+            reduction: f64,
+            floor: f64,
+        ) -> PyResult<Self> {
+            let denoiser = Self::with_params(DenoiserParams {
+                sample_rate,
+                frame_size,
+                hop_size,
+                noise_start,
+                noise_end,
+                quietest_fraction,
+                method: method.into(),
+                reduction,
+                floor,
+            });
+            denoiser.check()?;
+            Ok(denoiser)
+        }
+
+        /// Compute the Algorithm
+        ///
+        /// Inputs:
+        ///   - signal: list[float]
+        ///
+        /// Outputs:
+        ///   - denoised: list[float]
+        ///
+        /// See data descriptors for more details.
+        #[pyo3(name = "compute", signature = (signal=None))]
+        fn pycompute(&mut self, py: Python<'_>, signal: Option<ArrayView<f64>>) -> PyResult<Array<f64>> {
+            if let Some(arg) = signal {
+                arg.assign_to(&mut self.signal)
+            }
+
+            py.allow_threads(|| self.run())?;
+
+            Ok(self.denoised.as_ref().unwrap().clone().into())
+        }
+
+        fn __call__(&mut self, py: Python<'_>) -> PyResult<()> {
+            Ok(py.allow_threads(|| self.run())?)
+        }
+    }
+
+    params = [
+        "sample_rate",
+        "frame_size",
+        "hop_size",
+        "noise_start",
+        "noise_end",
+        "quietest_fraction",
+        "method",
+        "reduction",
+        "floor",
+    ];
+    async = compute_async;
+
+    fn reset(&mut self) {
+        self.denoised = None;
+        self.noise_profile = None;
+    }
+}
+
+/// Parameters of the Denoiser, see its data descriptors for details
+#[derive(Debug, Clone, PartialEq)]
+pub struct DenoiserParams {
+    /// sampling rate of the audio signal in Hz
+    pub sample_rate: f64,
+    /// size of the analysis frame, a power of two from 2 to 65536
+    pub frame_size: usize,
+    /// number of samples between consecutive frames, at most frame_size
+    pub hop_size: usize,
+    /// start of the noise-only region in seconds
+    pub noise_start: f64,
+    /// end of the noise-only region in seconds
+    pub noise_end: f64,
+    /// fraction of the quietest frames used for the noise profile
+    pub quietest_fraction: f64,
+    /// gain function, one of {subtraction, wiener}
+    pub method: String,
+    /// over-subtraction factor applied to the noise profile
+    pub reduction: f64,
+    /// minimum gain applied to any bin
+    pub floor: f64,
+}
+
+impl Default for DenoiserParams {
+    fn default() -> Self {
+        DenoiserParams {
+            sample_rate: 44100.0,
+            frame_size: 2048,
+            hop_size: 512,
+            noise_start: 0.0,
+            noise_end: 0.0,
+            quietest_fraction: 0.1,
+            method: "wiener".into(),
+            reduction: 1.0,
+            floor: 0.05,
+        }
+    }
+}
+
+impl ToJson for DenoiserParams {
+    fn to_json(&self) -> Json {
+        Json::object([
+            ("sample_rate", self.sample_rate.to_json()),
+            ("frame_size", self.frame_size.to_json()),
+            ("hop_size", self.hop_size.to_json()),
+            ("noise_start", self.noise_start.to_json()),
+            ("noise_end", self.noise_end.to_json()),
+            ("quietest_fraction", self.quietest_fraction.to_json()),
+            ("method", self.method.to_json()),
+            ("reduction", self.reduction.to_json()),
+            ("floor", self.floor.to_json()),
+        ])
+    }
+}
+
+impl FromJson for DenoiserParams {
+    fn from_json(json: &Json) -> Result<Self> {
+        let mut params = Self::default();
+        for (key, value) in json.fields()? {
+            match key.as_str() {
+                "sample_rate" => params.sample_rate = FromJson::from_json(value)?,
+                "frame_size" => params.frame_size = FromJson::from_json(value)?,
+                "hop_size" => params.hop_size = FromJson::from_json(value)?,
+                "noise_start" => params.noise_start = FromJson::from_json(value)?,
+                "noise_end" => params.noise_end = FromJson::from_json(value)?,
+                "quietest_fraction" => params.quietest_fraction = FromJson::from_json(value)?,
+                "method" => params.method = json::checked(value, |m: &String| check_method(m))?,
+                "reduction" => params.reduction = FromJson::from_json(value)?,
+                "floor" => params.floor = FromJson::from_json(value)?,
+                _ => return Err(json::unknown_key(key)),
+            }
+        }
+        Ok(params)
+    }
+}
+
+/// Builder of a Denoiser starting from default parameters, see Denoiser::builder()
+#[derive(Debug, Clone, Default)]
+pub struct DenoiserBuilder {
+    params: DenoiserParams,
+}
+
+impl DenoiserBuilder {
+    /// sampling rate of the audio signal in Hz
+    pub fn sample_rate(mut self, sample_rate: f64) -> Self {
+        self.params.sample_rate = sample_rate;
+        self
+    }
+
+    /// size of the analysis frame, a power of two from 2 to 65536
+    pub fn frame_size(mut self, frame_size: usize) -> Self {
+        self.params.frame_size = frame_size;
+        self
+    }
+
+    /// number of samples between consecutive frames
+    pub fn hop_size(mut self, hop_size: usize) -> Self {
+        self.params.hop_size = hop_size;
+        self
+    }
+
+    /// start of the noise-only region in seconds
+    pub fn noise_start(mut self, noise_start: f64) -> Self {
+        self.params.noise_start = noise_start;
+        self
+    }
+
+    /// end of the noise-only region in seconds
+    pub fn noise_end(mut self, noise_end: f64) -> Self {
+        self.params.noise_end = noise_end;
+        self
+    }
+
+    /// fraction of the quietest frames used for the noise profile
+    pub fn quietest_fraction(mut self, quietest_fraction: f64) -> Self {
+        self.params.quietest_fraction = quietest_fraction;
+        self
+    }
+
+    /// gain function, one of {subtraction, wiener}
+    pub fn method(mut self, method: &str) -> Self {
+        self.params.method = method.into();
+        self
+    }
+
+    /// over-subtraction factor applied to the noise profile
+    pub fn reduction(mut self, reduction: f64) -> Self {
+        self.params.reduction = reduction;
+        self
+    }
+
+    /// minimum gain applied to any bin
+    pub fn floor(mut self, floor: f64) -> Self {
+        self.params.floor = floor;
+        self
+    }
+
+    /// create the Denoiser with the parameters set so far
+    pub fn build(self) -> Denoiser {
+        Denoiser::with_params(self.params)
+    }
+}
+
+impl Algorithm for Denoiser {
+    /// audio signal
+    type Input = Vec<f64>;
+    /// denoised audio signal
+    type Output = Vec<f64>;
+    type Params = DenoiserParams;
+
+    fn with_params(params: DenoiserParams) -> Self {
+        Denoiser {
+            signal: Vec::new(),
+
+            denoised: None,
+            noise_profile: None,
+
+            sample_rate: params.sample_rate,
+            frame_size: params.frame_size,
+            hop_size: params.hop_size,
+            noise_start: params.noise_start,
+            noise_end: params.noise_end,
+            quietest_fraction: params.quietest_fraction,
+            method: params.method,
+            reduction: params.reduction,
+            floor: params.floor,
+        }
+    }
+
+    fn compute(&mut self, signal: Vec<f64>) -> Result<Vec<f64>> {
+        self.signal = signal;
+        self.run()?;
+        Ok(self.denoised.take().unwrap())
+    }
+}
+
+impl Denoiser {
+    /// start building a Denoiser from default parameters
+    pub fn builder() -> DenoiserBuilder {
+        DenoiserBuilder::default()
+    }
+
+    /// check the parameters, with Error::InvalidParam for the first one out of its range
+    fn check(&self) -> Result<()> {
+        check_positive("sample_rate", self.sample_rate)?;
+        check_range(
+            "frame_size",
+            self.frame_size as f64,
+            2.0,
+            Fft::MAX_SIZE as f64,
+        )?;
+        if !self.frame_size.is_power_of_two() {
+            return Err(Error::InvalidParam(format!(
+                "frame_size must be a power of two, got {}",
+                self.frame_size
+            )));
+        }
+        check_range(
+            "hop_size",
+            self.hop_size as f64,
+            1.0,
+            self.frame_size as f64,
+        )?;
+        check_range("noise_start", self.noise_start, 0.0, f64::INFINITY)?;
+        check_range("noise_end", self.noise_end, 0.0, f64::INFINITY)?;
+        check_range("quietest_fraction", self.quietest_fraction, 0.0, 1.0)?;
+        check_method(&self.method)?;
+        check_range("reduction", self.reduction, 0.0, f64::INFINITY)?;
+        check_range("floor", self.floor, 0.0, 1.0)
+    }
+
+    /// reduce the noise in the signal and set the outputs
+    ///
+    /// Fails with Error::InvalidParam for parameters out of their ranges.
+    fn run(&mut self) -> Result<()> {
+        self.check()?;
+        let n = self.frame_size;
+        let hop = self.hop_size;
+        let fft = Fft::new(n);
+        let window: Vec<f64> = (0..n)
+            .map(|i| 0.5 - 0.5 * (2.0 * std::f64::consts::PI * i as f64 / n as f64).cos())
+            .collect();
+
+        // frames start before the signal, so every sample is covered by the same number of frames
+        let len = self.signal.len() as isize;
+        let positions: Vec<isize> = (0..)
+            .map(|k| k * hop as isize - (n - hop) as isize)
+            .take_while(|p| *p < len)
+            .collect();
+
+        let noise = self.learn_noise(&fft, &window, &positions);
+
+        let mut output = vec![0.0; self.signal.len()];
+        let mut norm = vec![0.0; self.signal.len()];
+        for p in positions.iter() {
+            let mut buf = self.spectrum(&fft, &window, *p);
+            for (i, x) in buf.iter_mut().enumerate() {
+                let bin = std::cmp::min(i, n - i);
+                let power = (x.re * x.re + x.im * x.im) as f64;
+                *x = x.scale(self.gain(power, noise[bin]) as f32);
+            }
+            stft::ifft_inplace(&fft, &mut buf);
+
+            for (i, x) in buf.iter().enumerate() {
+                let j = p + i as isize;
+                if j >= 0 && j < len {
+                    output[j as usize] += window[i] * x.re as f64;
+                    norm[j as usize] += window[i] * window[i];
+                }
+            }
+        }
+
+        for (x, w) in output.iter_mut().zip(norm.iter()) {
+            if *w > 1e-9 {
+                *x /= *w;
+            }
+        }
+
+        // Output
+        self.denoised = Some(output);
+        self.noise_profile = Some(noise);
+        Ok(())
+    }
+
+    fn spectrum(&self, fft: &Fft, window: &[f64], position: isize) -> Vec<Complex> {
+        let mut buf = vec![Complex { re: 0.0, im: 0.0 }; window.len()];
+        for (i, x) in buf.iter_mut().enumerate() {
+            let j = position + i as isize;
+            if j >= 0 && (j as usize) < self.signal.len() {
+                x.re = (window[i] * self.signal[j as usize]) as f32;
+            }
+        }
+        fft.fft_inplace(&mut buf);
+        buf
+    }
+
+    /// average power spectrum over the noise region or the quietest frames
+    fn learn_noise(&self, fft: &Fft, window: &[f64], positions: &[isize]) -> Vec<f64> {
+        let n = window.len();
+        let power = |p: isize| -> Vec<f64> {
+            self.spectrum(fft, window, p)
+                .iter()
+                .take(n / 2 + 1)
+                .map(|x| (x.re * x.re + x.im * x.im) as f64)
+                .collect()
+        };
+
+        let start = (self.noise_start * self.sample_rate).round() as isize;
+        let end = (self.noise_end * self.sample_rate).round() as isize;
+        let mut selected: Vec<Vec<f64>> = positions
+            .iter()
+            .filter(|p| end > start && **p >= start && **p + n as isize <= end)
+            .map(|p| power(*p))
+            .collect();
+
+        if selected.is_empty() {
+            // partial frames at the edges are padded with zeros and would look too quiet
+            let inner: Vec<isize> = positions
+                .iter()
+                .filter(|p| **p >= 0 && **p + n as isize <= self.signal.len() as isize)
+                .copied()
+                .collect();
+            let candidates = if inner.is_empty() { positions } else { &inner };
+
+            let mut frames: Vec<(f64, Vec<f64>)> = candidates
+                .iter()
+                .map(|p| power(*p))
+                .map(|x| (x.iter().sum(), x))
+                .collect();
+            frames.sort_by(|a, b| a.0.total_cmp(&b.0));
+            let count = (self.quietest_fraction * frames.len() as f64).ceil() as usize;
+            selected = frames
+                .into_iter()
+                .take(std::cmp::max(count, 1))
+                .map(|x| x.1)
+                .collect();
+        }
+
+        let mut noise = vec![0.0; n / 2 + 1];
+        for frame in selected.iter() {
+            for (x, y) in noise.iter_mut().zip(frame.iter()) {
+                *x += y / selected.len() as f64;
+            }
+        }
+        noise
+    }
+
+    /// gain for a bin with the given power and noise power
+    fn gain(&self, power: f64, noise: f64) -> f64 {
+        if power <= 0.0 {
+            return self.floor;
+        }
+
+        let noise = self.reduction * noise;
+        let g = match self.method.as_str() {
+            "subtraction" => (1.0 - noise / power).max(0.0).sqrt(),
+            _ => {
+                let snr = (power - noise).max(0.0) / noise.max(f64::MIN_POSITIVE);
+                snr / (1.0 + snr)
+            }
+        };
+        g.max(self.floor)
+    }
+}
+
+/// check that the gain function is known
+fn check_method(method: &str) -> Result<()> {
+    check_choice("method", method, &["subtraction", "wiener"])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Algorithm, Denoiser, DenoiserParams};
+    use crate::Error;
+
+    fn noise(n: usize) -> Vec<f64> {
+        // simple linear congruential generator for reproducible noise
+        let mut state: u32 = 1;
+        (0..n)
+            .map(|_| {
+                state = state.wrapping_mul(1664525).wrapping_add(1013904223);
+                0.1 * ((state >> 8) as f64 / (1 << 24) as f64 - 0.5)
+            })
+            .collect()
+    }
+
+    fn energy(x: &[f64]) -> f64 {
+        x.iter().map(|x| x * x).sum()
+    }
+
+    #[test]
+    fn denoise() {
+        let n = 44100;
+        let tone: Vec<f64> = (0..n)
+            .map(|i| {
+                if i < n / 2 {
+                    0.0
+                } else {
+                    0.5 * (2.0 * std::f64::consts::PI * 440.0 * i as f64 / 44100.0).sin()
+                }
+            })
+            .collect();
+        let signal: Vec<f64> = tone.iter().zip(noise(n)).map(|(x, y)| x + y).collect();
+
+        for (start, end) in [(0.0, 0.4), (0.0, 0.0)] {
+            let mut denoiser = Denoiser::with_params(DenoiserParams {
+                noise_start: start,
+                noise_end: end,
+                ..Default::default()
+            });
+            let output = denoiser.compute(signal.clone()).unwrap();
+            assert_eq!(output.len(), signal.len());
+
+            // at least 6 dB less noise in the noise-only part
+            let before = energy(&signal[..n / 2]);
+            let after = energy(&output[..n / 2]);
+            assert!(after < before / 4.0, "test {} {}", start, end);
+
+            // residual error in the tonal part is lower than the original noise
+            let error: Vec<f64> = output[n / 2..]
+                .iter()
+                .zip(tone[n / 2..].iter())
+                .map(|(x, y)| x - y)
+                .collect();
+            assert!(energy(&error) < before / 2.0, "test {} {}", start, end);
+        }
+    }
+
+    #[test]
+    fn small_frames() {
+        let signal = noise(1000);
+        for frame_size in 0..=32 {
+            let mut denoiser = Denoiser::with_params(DenoiserParams {
+                frame_size,
+                hop_size: frame_size / 2,
+                ..Default::default()
+            });
+            // frames are never adjusted, other sizes are rejected
+            if !frame_size.is_power_of_two() || frame_size < 2 {
+                let result = denoiser.compute(signal.clone());
+                assert!(matches!(result, Err(Error::InvalidParam(_))));
+                continue;
+            }
+            let output = denoiser.compute(signal.clone()).unwrap();
+            assert_eq!(output.len(), signal.len());
+            assert!(
+                output.iter().all(|x| x.is_finite()),
+                "frame size {}",
+                frame_size
+            );
+            assert!(
+                energy(&output) <= energy(&signal),
+                "frame size {}",
+                frame_size
+            );
+        }
+
+        let invalid = |params| {
+            let mut denoiser = Denoiser::with_params(params);
+            matches!(
+                denoiser.compute(signal.clone()),
+                Err(Error::InvalidParam(_))
+            )
+        };
+        assert!(invalid(DenoiserParams {
+            hop_size: 4096,
+            ..Default::default()
+        }));
+        assert!(invalid(DenoiserParams {
+            method: "spectral".into(),
+            ..Default::default()
+        }));
+        assert!(invalid(DenoiserParams {
+            floor: 2.0,
+            ..Default::default()
+        }));
+    }
+}
@@ -0,0 +1,1229 @@
+#[cfg(feature = "python")]
+use pyo3::{PyResult, Python};
+
+use super::filters::Biquad;
+use super::{check_choice, check_positive, check_range, Algorithm, Processor};
+use crate::json::{self, FromJson, Json, ToJson};
+#[cfg(feature = "python")]
+use crate::pymod::array::{Array, ArrayView};
+#[cfg(feature = "python")]
+use crate::pymod::state::pymethods_with_params;
+use crate::Result;
+
+python_class! {
+    /// Downward compressor with an optional sidechain input
+    ///
+    /// Parameters out of their ranges fail .compute() with Error::InvalidParam,
+    /// while .process() takes them as they are, so they should be checked before streaming.
+    #[pyclass(module = "muslib")]
+    pub struct Compressor {
+        /// Input: list[float] -- audio signal
+        #[pyo3(get, set)]
+        pub signal: Vec<f64>,
+        /// Input: Optional[list[float]] -- key signal driving the gain reduction instead of the input signal
+        #[pyo3(get, set)]
+        pub sidechain: Option<Vec<f64>>,
+
+        /// Output: Optional[list[float]] -- compressed audio signal
+        #[pyo3(get)]
+        pub processed: Option<Vec<f64>>,
+        /// Output: Optional[list[float]] -- linear gain applied to each sample
+        #[pyo3(get)]
+        pub gain: Option<Vec<f64>>,
+
+        /// Param: float -- sampling rate of the audio signal in Hz (default: 44100)
+        #[pyo3(get)]
+        pub sample_rate: f64,
+        /// Param: float -- level above which the gain is reduced in dB (default: -20)
+        #[pyo3(get)]
+        pub threshold: f64,
+        /// Param: float -- input to output level ratio above the threshold, at least 1 (default: 4)
+        #[pyo3(get)]
+        pub ratio: f64,
+        /// Param: float -- attack time in seconds (default: 0.01)
+        #[pyo3(get)]
+        pub attack: f64,
+        /// Param: float -- release time in seconds (default: 0.1)
+        #[pyo3(get)]
+        pub release: f64,
+        /// Param: float -- gain applied after compression in dB (default: 0)
+        #[pyo3(get)]
+        pub makeup: f64,
+        /// Param: str -- filter applied to the key signal, one of {none, highpass, lowpass} (default: none)
+        #[pyo3(get)]
+        pub sidechain_filter: String,
+        /// Param: float -- cutoff frequency of the sidechain filter in Hz (default: 100)
+        #[pyo3(get)]
+        pub sidechain_frequency: f64,
+
+        detector: Detector,
+    }
+}
+
+#[cfg(feature = "python")]
+pymethods_with_params! {
+    impl Compressor {
+        #[new]
+        #[pyo3(signature = (
+            sample_rate=44100.0,
+            threshold=-20.0,
+            ratio=4.0,
+            attack=0.01,
+            release=0.1,
+            makeup=0.0,
+            sidechain_filter="none",
+            sidechain_frequency=100.0,
+        ))]
+        #[allow(clippy::too_many_arguments)]
+        fn pynew(
+            sample_rate: f64,
+            threshold: f64,
+            ratio: f64,
+            attack: f64,
+            release: f64,
+            makeup: f64,
+            sidechain_filter: &str,
+            sidechain_frequency: f64,
+        ) -> PyResult<Self> {
+            let compressor = Self::with_params(CompressorParams {
+                sample_rate,
+                threshold,
+                ratio,
+                attack,
+                release,
+                makeup,
+                sidechain_filter: sidechain_filter.into(),
+                sidechain_frequency,
+            });
+            compressor.check()?;
+            Ok(compressor)
+        }
+
+        #[setter(sample_rate)]
+        fn set_sample_rate(&mut self, sample_rate: f64) -> PyResult<()> {
+            check_param("sample_rate", sample_rate)?;
+            self.sample_rate = sample_rate;
+            Ok(())
+        }
+
+        #[setter(threshold)]
+        fn set_threshold(&mut self, threshold: f64) -> PyResult<()> {
+            check_param("threshold", threshold)?;
+            self.threshold = threshold;
+            Ok(())
+        }
+
+        #[setter(ratio)]
+        fn set_ratio(&mut self, ratio: f64) -> PyResult<()> {
+            check_param("ratio", ratio)?;
+            self.ratio = ratio;
+            Ok(())
+        }
+
+        #[setter(attack)]
+        fn set_attack(&mut self, attack: f64) -> PyResult<()> {
+            check_param("attack", attack)?;
+            self.attack = attack;
+            Ok(())
+        }
+
+        #[setter(release)]
+        fn set_release(&mut self, release: f64) -> PyResult<()> {
+            check_param("release", release)?;
+            self.release = release;
+            Ok(())
+        }
+
+        #[setter(makeup)]
+        fn set_makeup(&mut self, makeup: f64) -> PyResult<()> {
+            check_param("makeup", makeup)?;
+            self.makeup = makeup;
+            Ok(())
+        }
+
+        #[setter(sidechain_filter)]
+        fn set_sidechain_filter(&mut self, sidechain_filter: &str) -> PyResult<()> {
+            check_sidechain_filter(sidechain_filter)?;
+            self.sidechain_filter = sidechain_filter.into();
+            Ok(())
+        }
+
+        #[setter(sidechain_frequency)]
+        fn set_sidechain_frequency(&mut self, sidechain_frequency: f64) -> PyResult<()> {
+            check_param("sidechain_frequency", sidechain_frequency)?;
+            self.sidechain_frequency = sidechain_frequency;
+            Ok(())
+        }
+
+        /// Compute the Algorithm
+        ///
+        /// Inputs:
+        ///   - signal: list[float]
+        ///   - sidechain: Optional[list[float]]
+        ///
+        /// Outputs:
+        ///   - processed: list[float]
+        ///
+        /// See data descriptors for more details.
+        #[pyo3(name = "compute", signature = (signal=None, sidechain=None))]
+        fn pycompute(
+            &mut self,
+            py: Python<'_>,
+            signal: Option<ArrayView<f64>>,
+            sidechain: Option<ArrayView<f64>>,
+        ) -> PyResult<Array<f64>> {
+            if let Some(arg) = signal {
+                arg.assign_to(&mut self.signal)
+            }
+            if let Some(arg) = sidechain {
+                arg.assign_to(self.sidechain.get_or_insert_with(Vec::new))
+            }
+
+            py.allow_threads(|| self.run())?;
+
+            Ok(self.processed.as_ref().unwrap().clone().into())
+        }
+
+        fn __call__(&mut self, py: Python<'_>) -> PyResult<()> {
+            Ok(py.allow_threads(|| self.run())?)
+        }
+    }
+
+    params = [
+        "sample_rate",
+        "threshold",
+        "ratio",
+        "attack",
+        "release",
+        "makeup",
+        "sidechain_filter",
+        "sidechain_frequency",
+    ];
+    async = compute_async;
+
+    fn reset(&mut self) {
+        self.processed = None;
+        self.gain = None;
+        Processor::reset(self);
+    }
+}
+
+/// Parameters of the Compressor, see its data descriptors for details
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompressorParams {
+    /// sampling rate of the audio signal in Hz
+    pub sample_rate: f64,
+    /// level above which the gain is reduced in dB
+    pub threshold: f64,
+    /// input to output level ratio above the threshold, at least 1
+    pub ratio: f64,
+    /// attack time in seconds
+    pub attack: f64,
+    /// release time in seconds
+    pub release: f64,
+    /// gain applied after compression in dB
+    pub makeup: f64,
+    /// filter applied to the key signal, one of {none, highpass, lowpass}
+    pub sidechain_filter: String,
+    /// cutoff frequency of the sidechain filter in Hz
+    pub sidechain_frequency: f64,
+}
+
+impl Default for CompressorParams {
+    fn default() -> Self {
+        CompressorParams {
+            sample_rate: 44100.0,
+            threshold: -20.0,
+            ratio: 4.0,
+            attack: 0.01,
+            release: 0.1,
+            makeup: 0.0,
+            sidechain_filter: "none".into(),
+            sidechain_frequency: 100.0,
+        }
+    }
+}
+
+impl ToJson for CompressorParams {
+    fn to_json(&self) -> Json {
+        Json::object([
+            ("sample_rate", self.sample_rate.to_json()),
+            ("threshold", self.threshold.to_json()),
+            ("ratio", self.ratio.to_json()),
+            ("attack", self.attack.to_json()),
+            ("release", self.release.to_json()),
+            ("makeup", self.makeup.to_json()),
+            ("sidechain_filter", self.sidechain_filter.to_json()),
+            ("sidechain_frequency", self.sidechain_frequency.to_json()),
+        ])
+    }
+}
+
+impl FromJson for CompressorParams {
+    fn from_json(json: &Json) -> Result<Self> {
+        let mut params = Self::default();
+        for (key, value) in json.fields()? {
+            match key.as_str() {
+                "sample_rate" => params.sample_rate = FromJson::from_json(value)?,
+                "threshold" => params.threshold = FromJson::from_json(value)?,
+                "ratio" => params.ratio = FromJson::from_json(value)?,
+                "attack" => params.attack = FromJson::from_json(value)?,
+                "release" => params.release = FromJson::from_json(value)?,
+                "makeup" => params.makeup = FromJson::from_json(value)?,
+                "sidechain_filter" => {
+                    params.sidechain_filter =
+                        json::checked(value, |f: &String| check_sidechain_filter(f))?
+                }
+                "sidechain_frequency" => params.sidechain_frequency = FromJson::from_json(value)?,
+                _ => return Err(json::unknown_key(key)),
+            }
+        }
+        Ok(params)
+    }
+}
+
+/// Builder of a Compressor starting from default parameters, see Compressor::builder()
+#[derive(Debug, Clone, Default)]
+pub struct CompressorBuilder {
+    params: CompressorParams,
+}
+
+impl CompressorBuilder {
+    /// sampling rate of the audio signal in Hz
+    pub fn sample_rate(mut self, sample_rate: f64) -> Self {
+        self.params.sample_rate = sample_rate;
+        self
+    }
+
+    /// level above which the gain is reduced in dB
+    pub fn threshold(mut self, threshold: f64) -> Self {
+        self.params.threshold = threshold;
+        self
+    }
+
+    /// input to output level ratio above the threshold, at least 1
+    pub fn ratio(mut self, ratio: f64) -> Self {
+        self.params.ratio = ratio;
+        self
+    }
+
+    /// attack time in seconds
+    pub fn attack(mut self, attack: f64) -> Self {
+        self.params.attack = attack;
+        self
+    }
+
+    /// release time in seconds
+    pub fn release(mut self, release: f64) -> Self {
+        self.params.release = release;
+        self
+    }
+
+    /// gain applied after compression in dB
+    pub fn makeup(mut self, makeup: f64) -> Self {
+        self.params.makeup = makeup;
+        self
+    }
+
+    /// filter applied to the key signal, one of {none, highpass, lowpass}
+    pub fn sidechain_filter(mut self, sidechain_filter: &str) -> Self {
+        self.params.sidechain_filter = sidechain_filter.into();
+        self
+    }
+
+    /// cutoff frequency of the sidechain filter in Hz
+    pub fn sidechain_frequency(mut self, sidechain_frequency: f64) -> Self {
+        self.params.sidechain_frequency = sidechain_frequency;
+        self
+    }
+
+    /// create the Compressor with the parameters set so far
+    pub fn build(self) -> Compressor {
+        Compressor::with_params(self.params)
+    }
+}
+
+impl Algorithm for Compressor {
+    /// audio signal and an optional key signal
+    type Input = (Vec<f64>, Option<Vec<f64>>);
+    /// processed audio signal
+    type Output = Vec<f64>;
+    type Params = CompressorParams;
+
+    fn with_params(params: CompressorParams) -> Self {
+        Compressor {
+            signal: Vec::new(),
+            sidechain: None,
+
+            processed: None,
+            gain: None,
+
+            sample_rate: params.sample_rate,
+            threshold: params.threshold,
+            ratio: params.ratio,
+            attack: params.attack,
+            release: params.release,
+            makeup: params.makeup,
+            sidechain_filter: params.sidechain_filter,
+            sidechain_frequency: params.sidechain_frequency,
+
+            detector: Detector::new(),
+        }
+    }
+
+    fn compute(&mut self, (signal, sidechain): Self::Input) -> Result<Vec<f64>> {
+        self.signal = signal;
+        self.sidechain = sidechain;
+        self.run()?;
+        Ok(self.processed.take().unwrap())
+    }
+}
+
+impl Processor for Compressor {
+    fn process(&mut self, block: &mut [f64]) {
+        // a stored sidechain is consumed in step with the signal
+        let sidechain = self.sidechain.take();
+        let key = sidechain.as_deref().map(|s| self.detector.rest(s));
+        self.apply(block, key, None);
+        self.sidechain = sidechain;
+    }
+
+    fn reset(&mut self) {
+        self.detector = Detector::new();
+    }
+}
+
+impl Compressor {
+    /// start building a Compressor from default parameters
+    pub fn builder() -> CompressorBuilder {
+        CompressorBuilder::default()
+    }
+
+    /// process a block in place, keyed by the matching block of a streamed sidechain
+    ///
+    /// A shorter sidechain block leaves the rest of the block without a key.
+    pub fn process_sidechain(&mut self, block: &mut [f64], sidechain: &[f64]) {
+        self.apply(block, Some(sidechain), None);
+    }
+
+    /// check the parameters, with Error::InvalidParam for the first one out of its range
+    fn check(&self) -> Result<()> {
+        check_param("sample_rate", self.sample_rate)?;
+        check_param("threshold", self.threshold)?;
+        check_param("ratio", self.ratio)?;
+        check_param("attack", self.attack)?;
+        check_param("release", self.release)?;
+        check_param("makeup", self.makeup)?;
+        check_sidechain_filter(&self.sidechain_filter)?;
+        check_param("sidechain_frequency", self.sidechain_frequency)
+    }
+
+    /// process the whole signal from a fresh state and set the outputs
+    ///
+    /// Fails with Error::InvalidParam for parameters out of their ranges.
+    fn run(&mut self) -> Result<()> {
+        self.check()?;
+        self.reset();
+        let mut output = self.signal.clone();
+        let mut gain = Vec::with_capacity(output.len());
+        let sidechain = self.sidechain.take();
+        self.apply(&mut output, sidechain.as_deref(), Some(&mut gain));
+        self.sidechain = sidechain;
+
+        // Output
+        self.processed = Some(output);
+        self.gain = Some(gain);
+        Ok(())
+    }
+
+    /// process a block in place with the key aligned to it, and append the applied gain if requested
+    ///
+    /// Nothing is allocated here unless the sidechain filter changed,
+    /// so it is safe to call from an audio callback.
+    fn apply(&mut self, block: &mut [f64], key: Option<&[f64]>, mut gain: Option<&mut Vec<f64>>) {
+        self.detector.prepare(
+            &self.sidechain_filter,
+            self.sidechain_frequency,
+            self.sample_rate,
+        );
+        let attack = coefficient(self.attack, self.sample_rate);
+        let release = coefficient(self.release, self.sample_rate);
+
+        for (i, x) in block.iter_mut().enumerate() {
+            let k = self.detector.key(*x, key, i);
+            let level = self.detector.follow(k, attack, release);
+            let level = 20.0 * level.max(1e-12).log10();
+
+            let reduction = if level > self.threshold {
+                (self.threshold - level) * (1.0 - 1.0 / self.ratio)
+            } else {
+                0.0
+            };
+            let g = 10.0_f64.powf((reduction + self.makeup) / 20.0);
+
+            *x *= g;
+            if let Some(gain) = &mut gain {
+                gain.push(g);
+            }
+        }
+        self.detector.position += block.len();
+    }
+}
+
+python_class! {
+    /// Noise gate with an optional sidechain input
+    ///
+    /// Parameters out of their ranges fail .compute() with Error::InvalidParam,
+    /// while .process() takes them as they are, so they should be checked before streaming.
+    #[pyclass(module = "muslib")]
+    pub struct Gate {
+        /// Input: list[float] -- audio signal
+        #[pyo3(get, set)]
+        pub signal: Vec<f64>,
+        /// Input: Optional[list[float]] -- key signal opening the gate instead of the input signal
+        #[pyo3(get, set)]
+        pub sidechain: Option<Vec<f64>>,
+
+        /// Output: Optional[list[float]] -- gated audio signal
+        #[pyo3(get)]
+        pub processed: Option<Vec<f64>>,
+        /// Output: Optional[list[float]] -- linear gain applied to each sample
+        #[pyo3(get)]
+        pub gain: Option<Vec<f64>>,
+
+        /// Param: float -- sampling rate of the audio signal in Hz (default: 44100)
+        #[pyo3(get)]
+        pub sample_rate: f64,
+        /// Param: float -- level below which the gate closes in dB (default: -40)
+        #[pyo3(get)]
+        pub threshold: f64,
+        /// Param: float -- attenuation of the closed gate in dB (default: -80)
+        #[pyo3(get)]
+        pub range: f64,
+        /// Param: float -- time to open the gate in seconds (default: 0.001)
+        #[pyo3(get)]
+        pub attack: f64,
+        /// Param: float -- time the gate stays open after the key falls below the threshold in seconds (default: 0.01)
+        #[pyo3(get)]
+        pub hold: f64,
+        /// Param: float -- time to close the gate in seconds (default: 0.05)
+        #[pyo3(get)]
+        pub release: f64,
+        /// Param: str -- filter applied to the key signal, one of {none, highpass, lowpass} (default: none)
+        #[pyo3(get)]
+        pub sidechain_filter: String,
+        /// Param: float -- cutoff frequency of the sidechain filter in Hz (default: 100)
+        #[pyo3(get)]
+        pub sidechain_frequency: f64,
+
+        detector: Detector,
+        gain_state: f64,
+        hold_counter: usize,
+    }
+}
+
+#[cfg(feature = "python")]
+pymethods_with_params! {
+    impl Gate {
+        #[new]
+        #[pyo3(signature = (
+            sample_rate=44100.0,
+            threshold=-40.0,
+            range=-80.0,
+            attack=0.001,
+            hold=0.01,
+            release=0.05,
+            sidechain_filter="none",
+            sidechain_frequency=100.0,
+        ))]
+        #[allow(clippy::too_many_arguments)]
+        fn pynew(
+            sample_rate: f64,
+            threshold: f64,
+            range: f64,
+            attack: f64,
+            hold: f64,
+            release: f64,
+            sidechain_filter: &str,
+            sidechain_frequency: f64,
+        ) -> PyResult<Self> {
+            let gate = Self::with_params(GateParams {
+                sample_rate,
+                threshold,
+                range,
+                attack,
+                hold,
+                release,
+                sidechain_filter: sidechain_filter.into(),
+                sidechain_frequency,
+            });
+            gate.check()?;
+            Ok(gate)
+        }
+
+        #[setter(sample_rate)]
+        fn set_sample_rate(&mut self, sample_rate: f64) -> PyResult<()> {
+            check_param("sample_rate", sample_rate)?;
+            self.sample_rate = sample_rate;
+            Ok(())
+        }
+
+        #[setter(threshold)]
+        fn set_threshold(&mut self, threshold: f64) -> PyResult<()> {
+            check_param("threshold", threshold)?;
+            self.threshold = threshold;
+            Ok(())
+        }
+
+        #[setter(range)]
+        fn set_range(&mut self, range: f64) -> PyResult<()> {
+            check_param("range", range)?;
+            self.range = range;
+            Ok(())
+        }
+
+        #[setter(attack)]
+        fn set_attack(&mut self, attack: f64) -> PyResult<()> {
+            check_param("attack", attack)?;
+            self.attack = attack;
+            Ok(())
+        }
+
+        #[setter(hold)]
+        fn set_hold(&mut self, hold: f64) -> PyResult<()> {
+            check_param("hold", hold)?;
+            self.hold = hold;
+            Ok(())
+        }
+
+        #[setter(release)]
+        fn set_release(&mut self, release: f64) -> PyResult<()> {
+            check_param("release", release)?;
+            self.release = release;
+            Ok(())
+        }
+
+        #[setter(sidechain_filter)]
+        fn set_sidechain_filter(&mut self, sidechain_filter: &str) -> PyResult<()> {
+            check_sidechain_filter(sidechain_filter)?;
+            self.sidechain_filter = sidechain_filter.into();
+            Ok(())
+        }
+
+        #[setter(sidechain_frequency)]
+        fn set_sidechain_frequency(&mut self, sidechain_frequency: f64) -> PyResult<()> {
+            check_param("sidechain_frequency", sidechain_frequency)?;
+            self.sidechain_frequency = sidechain_frequency;
+            Ok(())
+        }
+
+        /// Compute the Algorithm
+        ///
+        /// Inputs:
+        ///   - signal: list[float]
+        ///   - sidechain: Optional[list[float]]
+        ///
+        /// Outputs:
+        ///   - processed: list[float]
+        ///
+        /// See data descriptors for more details.
+        #[pyo3(name = "compute", signature = (signal=None, sidechain=None))]
+        fn pycompute(
+            &mut self,
+            py: Python<'_>,
+            signal: Option<ArrayView<f64>>,
+            sidechain: Option<ArrayView<f64>>,
+        ) -> PyResult<Array<f64>> {
+            if let Some(arg) = signal {
+                arg.assign_to(&mut self.signal)
+            }
+            if let Some(arg) = sidechain {
+                arg.assign_to(self.sidechain.get_or_insert_with(Vec::new))
+            }
+
+            py.allow_threads(|| self.run())?;
+
+            Ok(self.processed.as_ref().unwrap().clone().into())
+        }
+
+        fn __call__(&mut self, py: Python<'_>) -> PyResult<()> {
+            Ok(py.allow_threads(|| self.run())?)
+        }
+    }
+
+    params = [
+        "sample_rate",
+        "threshold",
+        "range",
+        "attack",
+        "hold",
+        "release",
+        "sidechain_filter",
+        "sidechain_frequency",
+    ];
+    async = compute_async;
+
+    fn reset(&mut self) {
+        self.processed = None;
+        self.gain = None;
+        Processor::reset(self);
+    }
+}
+
+/// Parameters of the Gate, see its data descriptors for details
+#[derive(Debug, Clone, PartialEq)]
+pub struct GateParams {
+    /// sampling rate of the audio signal in Hz
+    pub sample_rate: f64,
+    /// level below which the gate closes in dB
+    pub threshold: f64,
+    /// attenuation of the closed gate in dB
+    pub range: f64,
+    /// time to open the gate in seconds
+    pub attack: f64,
+    /// time the gate stays open after the key falls below the threshold in seconds
+    pub hold: f64,
+    /// time to close the gate in seconds
+    pub release: f64,
+    /// filter applied to the key signal, one of {none, highpass, lowpass}
+    pub sidechain_filter: String,
+    /// cutoff frequency of the sidechain filter in Hz
+    pub sidechain_frequency: f64,
+}
+
+impl Default for GateParams {
+    fn default() -> Self {
+        GateParams {
+            sample_rate: 44100.0,
+            threshold: -40.0,
+            range: -80.0,
+            attack: 0.001,
+            hold: 0.01,
+            release: 0.05,
+            sidechain_filter: "none".into(),
+            sidechain_frequency: 100.0,
+        }
+    }
+}
+
+impl ToJson for GateParams {
+    fn to_json(&self) -> Json {
+        Json::object([
+            ("sample_rate", self.sample_rate.to_json()),
+            ("threshold", self.threshold.to_json()),
+            ("range", self.range.to_json()),
+            ("attack", self.attack.to_json()),
+            ("hold", self.hold.to_json()),
+            ("release", self.release.to_json()),
+            ("sidechain_filter", self.sidechain_filter.to_json()),
+            ("sidechain_frequency", self.sidechain_frequency.to_json()),
+        ])
+    }
+}
+
+impl FromJson for GateParams {
+    fn from_json(json: &Json) -> Result<Self> {
+        let mut params = Self::default();
+        for (key, value) in json.fields()? {
+            match key.as_str() {
+                "sample_rate" => params.sample_rate = FromJson::from_json(value)?,
+                "threshold" => params.threshold = FromJson::from_json(value)?,
+                "range" => params.range = FromJson::from_json(value)?,
+                "attack" => params.attack = FromJson::from_json(value)?,
+                "hold" => params.hold = FromJson::from_json(value)?,
+                "release" => params.release = FromJson::from_json(value)?,
+                "sidechain_filter" => {
+                    params.sidechain_filter =
+                        json::checked(value, |f: &String| check_sidechain_filter(f))?
+                }
+                "sidechain_frequency" => params.sidechain_frequency = FromJson::from_json(value)?,
+                _ => return Err(json::unknown_key(key)),
+            }
+        }
+        Ok(params)
+    }
+}
+
+/// Builder of a Gate starting from default parameters, see Gate::builder()
+#[derive(Debug, Clone, Default)]
+pub struct GateBuilder {
+    params: GateParams,
+}
+
+impl GateBuilder {
+    /// sampling rate of the audio signal in Hz
+    pub fn sample_rate(mut self, sample_rate: f64) -> Self {
+        self.params.sample_rate = sample_rate;
+        self
+    }
+
+    /// level below which the gate closes in dB
+    pub fn threshold(mut self, threshold: f64) -> Self {
+        self.params.threshold = threshold;
+        self
+    }
+
+    /// attenuation of the closed gate in dB
+    pub fn range(mut self, range: f64) -> Self {
+        self.params.range = range;
+        self
+    }
+
+    /// time to open the gate in seconds
+    pub fn attack(mut self, attack: f64) -> Self {
+        self.params.attack = attack;
+        self
+    }
+
+    /// time the gate stays open after the key falls below the threshold in seconds
+    pub fn hold(mut self, hold: f64) -> Self {
+        self.params.hold = hold;
+        self
+    }
+
+    /// time to close the gate in seconds
+    pub fn release(mut self, release: f64) -> Self {
+        self.params.release = release;
+        self
+    }
+
+    /// filter applied to the key signal, one of {none, highpass, lowpass}
+    pub fn sidechain_filter(mut self, sidechain_filter: &str) -> Self {
+        self.params.sidechain_filter = sidechain_filter.into();
+        self
+    }
+
+    /// cutoff frequency of the sidechain filter in Hz
+    pub fn sidechain_frequency(mut self, sidechain_frequency: f64) -> Self {
+        self.params.sidechain_frequency = sidechain_frequency;
+        self
+    }
+
+    /// create the Gate with the parameters set so far
+    pub fn build(self) -> Gate {
+        Gate::with_params(self.params)
+    }
+}
+
+impl Algorithm for Gate {
+    /// audio signal and an optional key signal
+    type Input = (Vec<f64>, Option<Vec<f64>>);
+    /// processed audio signal
+    type Output = Vec<f64>;
+    type Params = GateParams;
+
+    fn with_params(params: GateParams) -> Self {
+        let mut gate = Gate {
+            signal: Vec::new(),
+            sidechain: None,
+
+            processed: None,
+            gain: None,
+
+            sample_rate: params.sample_rate,
+            threshold: params.threshold,
+            range: params.range,
+            attack: params.attack,
+            hold: params.hold,
+            release: params.release,
+            sidechain_filter: params.sidechain_filter,
+            sidechain_frequency: params.sidechain_frequency,
+
+            detector: Detector::new(),
+            gain_state: 0.0,
+            hold_counter: 0,
+        };
+        // start closed, the same as after a reset
+        gate.reset();
+        gate
+    }
+
+    fn compute(&mut self, (signal, sidechain): Self::Input) -> Result<Vec<f64>> {
+        self.signal = signal;
+        self.sidechain = sidechain;
+        self.run()?;
+        Ok(self.processed.take().unwrap())
+    }
+}
+
+impl Processor for Gate {
+    fn process(&mut self, block: &mut [f64]) {
+        // a stored sidechain is consumed in step with the signal
+        let sidechain = self.sidechain.take();
+        let key = sidechain.as_deref().map(|s| self.detector.rest(s));
+        self.apply(block, key, None);
+        self.sidechain = sidechain;
+    }
+
+    fn reset(&mut self) {
+        self.detector = Detector::new();
+        self.gain_state = 10.0_f64.powf(self.range / 20.0);
+        self.hold_counter = 0;
+    }
+}
+
+impl Gate {
+    /// start building a Gate from default parameters
+    pub fn builder() -> GateBuilder {
+        GateBuilder::default()
+    }
+
+    /// process a block in place, keyed by the matching block of a streamed sidechain
+    ///
+    /// A shorter sidechain block leaves the rest of the block without a key.
+    pub fn process_sidechain(&mut self, block: &mut [f64], sidechain: &[f64]) {
+        self.apply(block, Some(sidechain), None);
+    }
+
+    /// check the parameters, with Error::InvalidParam for the first one out of its range
+    fn check(&self) -> Result<()> {
+        check_param("sample_rate", self.sample_rate)?;
+        check_param("threshold", self.threshold)?;
+        check_param("range", self.range)?;
+        check_param("attack", self.attack)?;
+        check_param("hold", self.hold)?;
+        check_param("release", self.release)?;
+        check_sidechain_filter(&self.sidechain_filter)?;
+        check_param("sidechain_frequency", self.sidechain_frequency)
+    }
+
+    /// process the whole signal from a fresh state and set the outputs
+    ///
+    /// Fails with Error::InvalidParam for parameters out of their ranges.
+    fn run(&mut self) -> Result<()> {
+        self.check()?;
+        self.reset();
+        let mut output = self.signal.clone();
+        let mut gain = Vec::with_capacity(output.len());
+        let sidechain = self.sidechain.take();
+        self.apply(&mut output, sidechain.as_deref(), Some(&mut gain));
+        self.sidechain = sidechain;
+
+        // Output
+        self.processed = Some(output);
+        self.gain = Some(gain);
+        Ok(())
+    }
+
+    /// process a block in place with the key aligned to it, and append the applied gain if requested
+    ///
+    /// Nothing is allocated here unless the sidechain filter changed,
+    /// so it is safe to call from an audio callback.
+    fn apply(&mut self, block: &mut [f64], key: Option<&[f64]>, mut gain: Option<&mut Vec<f64>>) {
+        self.detector.prepare(
+            &self.sidechain_filter,
+            self.sidechain_frequency,
+            self.sample_rate,
+        );
+        // the level detector itself reacts instantly, the gain is smoothed instead
+        let release = coefficient(self.release, self.sample_rate);
+        let attack = coefficient(self.attack, self.sample_rate);
+        let hold = (self.hold * self.sample_rate).round() as usize;
+        let closed = 10.0_f64.powf(self.range / 20.0);
+
+        for (i, x) in block.iter_mut().enumerate() {
+            let k = self.detector.key(*x, key, i);
+            let level = self.detector.follow(k, 0.0, release);
+            let level = 20.0 * level.max(1e-12).log10();
+
+            let target = if level >= self.threshold {
+                self.hold_counter = hold;
+                1.0
+            } else if self.hold_counter > 0 {
+                self.hold_counter -= 1;
+                1.0
+            } else {
+                closed
+            };
+
+            let c = if target > self.gain_state {
+                attack
+            } else {
+                release
+            };
+            self.gain_state = c * self.gain_state + (1.0 - c) * target;
+
+            *x *= self.gain_state;
+            if let Some(gain) = &mut gain {
+                gain.push(self.gain_state);
+            }
+        }
+        self.detector.position += block.len();
+    }
+}
+
+/// envelope follower shared by the dynamics processors
+struct Detector {
+    envelope: f64,
+    filter: Option<Biquad>,
+    /// type, cutoff and sample rate the sidechain filter was created for
+    design: Option<(String, f64, f64)>,
+    /// number of samples processed since the last reset
+    position: usize,
+}
+
+impl Detector {
+    fn new() -> Self {
+        Detector {
+            envelope: 0.0,
+            filter: None,
+            design: None,
+            position: 0,
+        }
+    }
+
+    /// create the sidechain filter before the first block, and again whenever its parameters change
+    fn prepare(&mut self, filter: &str, freq: f64, sample_rate: f64) {
+        let current = self
+            .design
+            .as_ref()
+            .is_some_and(|(f, q, r)| f == filter && *q == freq && *r == sample_rate);
+        if !current {
+            // names other than none are rejected by check_sidechain_filter() before processing
+            self.filter = match filter {
+                "highpass" => Some(Biquad::highpass(freq, 0.707, sample_rate)),
+                "lowpass" => Some(Biquad::lowpass(freq, 0.707, sample_rate)),
+                _ => None,
+            };
+            self.design = Some((filter.into(), freq, sample_rate));
+        }
+    }
+
+    /// part of a whole sidechain not consumed yet, starting at the next sample of the signal
+    fn rest<'a>(&self, sidechain: &'a [f64]) -> &'a [f64] {
+        sidechain.get(self.position..).unwrap_or(&[])
+    }
+
+    /// key for the i-th sample x of a block, taken from the sidechain if available and filtered
+    fn key(&mut self, x: f64, sidechain: Option<&[f64]>, i: usize) -> f64 {
+        let k = match sidechain {
+            // a shorter sidechain leaves the rest of the signal without a key
+            Some(s) => *s.get(i).unwrap_or(&0.0),
+            None => x,
+        };
+        match self.filter.as_mut() {
+            Some(f) => f.tick(k),
+            None => k,
+        }
+    }
+
+    /// peak envelope with separate attack and release smoothing
+    fn follow(&mut self, x: f64, attack: f64, release: f64) -> f64 {
+        let x = x.abs();
+        let c = if x > self.envelope { attack } else { release };
+        self.envelope = c * self.envelope + (1.0 - c) * x;
+        self.envelope
+    }
+}
+
+/// check a parameter of the dynamics processors, with Error::InvalidParam if it's out of its range
+///
+/// Rates and frequencies must be above 0, times at least 0 and the ratio at least 1,
+/// levels in dB can be any finite number.
+fn check_param(name: &str, value: f64) -> Result<()> {
+    match name {
+        "sample_rate" | "sidechain_frequency" => check_positive(name, value),
+        "attack" | "hold" | "release" => check_range(name, value, 0.0, f64::INFINITY),
+        "ratio" => check_range(name, value, 1.0, f64::INFINITY),
+        _ => check_range(name, value, f64::NEG_INFINITY, f64::INFINITY),
+    }
+}
+
+/// check that the sidechain filter is one of none, highpass and lowpass
+fn check_sidechain_filter(filter: &str) -> Result<()> {
+    check_choice("sidechain_filter", filter, &["none", "highpass", "lowpass"])
+}
+
+/// one-pole smoothing coefficient for a time constant in seconds
+fn coefficient(time: f64, sample_rate: f64) -> f64 {
+    if time <= 0.0 {
+        return 0.0;
+    }
+    (-1.0 / (time * sample_rate)).exp()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Algorithm, Compressor, CompressorParams, Gate, GateParams};
+    use crate::algs::Processor;
+    use crate::Error;
+
+    fn tone(amplitude: f64, n: usize) -> Vec<f64> {
+        (0..n)
+            .map(|i| amplitude * (2.0 * std::f64::consts::PI * 440.0 * i as f64 / 44100.0).sin())
+            .collect()
+    }
+
+    fn peak(x: &[f64]) -> f64 {
+        x.iter().fold(0.0, |m: f64, x| m.max(x.abs()))
+    }
+
+    #[test]
+    fn compressor() {
+        // a full-scale tone is 20 dB over the threshold, reduced by 15 dB with ratio 4
+        let mut compressor = Compressor::new();
+        let output = compressor.compute((tone(1.0, 44100), None)).unwrap();
+        let expected = 10.0_f64.powf(-15.0 / 20.0);
+        assert!((peak(&output[22050..]) - expected).abs() < 0.02);
+
+        // quiet signals pass unchanged
+        let signal = tone(0.05, 4410);
+        let mut compressor = Compressor::new();
+        let output = compressor.compute((signal.clone(), None)).unwrap();
+        assert_eq!(output, signal);
+    }
+
+    #[test]
+    fn ducking() {
+        // a loud key in the second half ducks a quiet pad
+        let pad = tone(0.05, 44100);
+        let mut key = vec![0.0; 22050];
+        key.append(&mut tone(1.0, 22050));
+
+        let mut compressor = Compressor::with_params(CompressorParams {
+            threshold: -30.0,
+            ..Default::default()
+        });
+        let output = compressor.compute((pad.clone(), Some(key))).unwrap();
+
+        assert_eq!(peak(&output[..22050]), peak(&pad[..22050]));
+        assert!(peak(&output[33075..]) < peak(&pad) / 4.0);
+    }
+
+    #[test]
+    fn gate() {
+        let mut signal = tone(0.001, 22050);
+        signal.append(&mut tone(0.5, 22050));
+
+        let mut gate = Gate::new();
+        let output = gate.compute((signal.clone(), None)).unwrap();
+
+        assert!(peak(&output[..22050]) < 1e-6);
+        assert!((peak(&output[33075..]) - 0.5).abs() < 1e-3);
+
+        // with a silent sidechain the gate never opens
+        let mut gate = Gate::new();
+        let silence = vec![0.0; signal.len()];
+        let output = gate.compute((signal.clone(), Some(silence))).unwrap();
+        assert!(peak(&output) < 1e-4);
+
+        // a new gate processes blocks the same as a reset one, starting closed
+        let mut reset = Gate::new();
+        reset.process(&mut signal.clone());
+        reset.reset();
+        let (mut x, mut y) = (signal[22000..].to_vec(), signal[22000..].to_vec());
+        Gate::new().process(&mut x);
+        reset.process(&mut y);
+        assert_eq!(x, y);
+        assert!(peak(&x[..50]) < 1e-6);
+    }
+
+    #[test]
+    fn sidechain_blocks() {
+        let pad = tone(0.05, 8192);
+        let mut key = vec![0.0; 3000];
+        key.append(&mut tone(1.0, 4000));
+        let params = CompressorParams {
+            threshold: -30.0,
+            sidechain_filter: "highpass".into(),
+            ..Default::default()
+        };
+        let expected = Compressor::with_params(params.clone())
+            .compute((pad.clone(), Some(key.clone())))
+            .unwrap();
+        assert!(peak(&expected[6000..7000]) < peak(&pad) / 4.0);
+
+        // a streamed sidechain is consumed block by block, past its end there is no key
+        let mut streamed = Compressor::with_params(params.clone());
+        let mut stored = Compressor::with_params(params.clone());
+        stored.sidechain = Some(key.clone());
+        let (mut x, mut y) = (pad.clone(), pad.clone());
+        let mut start = 0;
+        for n in [100, 1000, 3000, 3096, 996] {
+            let end = (start + n).min(key.len());
+            streamed.process_sidechain(&mut x[start..start + n], &key[start.min(end)..end]);
+            stored.process(&mut y[start..start + n]);
+            start += n;
+        }
+        assert_eq!(x, expected);
+        assert_eq!(y, expected);
+
+        // changing the sidechain filter after the first block takes effect on the next one
+        let mut gate = Gate::new();
+        gate.sidechain_filter = "lowpass".into();
+        gate.sidechain_frequency = 20000.0;
+        let mut signal = tone(0.5, 44100);
+        gate.process(&mut signal[..4410]);
+        assert!((peak(&signal[2205..4410]) - 0.5).abs() < 1e-3);
+        gate.sidechain_frequency = 20.0;
+        gate.process(&mut signal[4410..]);
+        assert!(peak(&signal[33075..]) < 1e-3);
+    }
+
+    #[test]
+    fn invalid_params() {
+        let invalid = |result| matches!(result, Err(Error::InvalidParam(_)));
+
+        for ratio in [0.5, 0.0, -4.0, f64::NAN, f64::INFINITY] {
+            let mut compressor = Compressor::builder().ratio(ratio).build();
+            assert!(
+                invalid(compressor.compute((tone(1.0, 64), None))),
+                "test {}",
+                ratio
+            );
+        }
+        for sample_rate in [0.0, -44100.0, f64::NAN] {
+            let mut compressor = Compressor::builder().sample_rate(sample_rate).build();
+            assert!(
+                invalid(compressor.compute((tone(1.0, 64), None))),
+                "test {}",
+                sample_rate
+            );
+            let mut gate = Gate::with_params(GateParams {
+                sample_rate,
+                ..Default::default()
+            });
+            assert!(
+                invalid(gate.compute((tone(1.0, 64), None))),
+                "test {}",
+                sample_rate
+            );
+        }
+        for time in [-0.01, f64::NAN, f64::INFINITY] {
+            let mut compressor = Compressor::builder().attack(time).build();
+            assert!(
+                invalid(compressor.compute((tone(1.0, 64), None))),
+                "test {}",
+                time
+            );
+            let mut gate = Gate::with_params(GateParams {
+                hold: time,
+                ..Default::default()
+            });
+            assert!(
+                invalid(gate.compute((tone(1.0, 64), None))),
+                "test {}",
+                time
+            );
+        }
+        let mut gate = Gate::with_params(GateParams {
+            range: f64::NEG_INFINITY,
+            ..Default::default()
+        });
+        assert!(invalid(gate.compute((tone(1.0, 64), None))));
+
+        // a misspelled sidechain filter isn't taken for none
+        let mut compressor = Compressor::builder().sidechain_filter("highpas").build();
+        assert!(invalid(compressor.compute((tone(1.0, 64), None))));
+        let mut gate = Gate::with_params(GateParams {
+            sidechain_filter: "low".into(),
+            ..Default::default()
+        });
+        assert!(invalid(gate.compute((tone(1.0, 64), None))));
+
+        // a ratio of 1 leaves the signal as it is
+        let signal = tone(1.0, 4410);
+        let mut compressor = Compressor::builder().ratio(1.0).build();
+        assert_eq!(compressor.compute((signal.clone(), None)).unwrap(), signal);
+    }
+}
@@ -0,0 +1,222 @@
+#[cfg(not(feature = "std"))]
+use core::ops::{Add, Mul, Sub};
+
+#[cfg(feature = "std")]
+pub use symphonia::core::dsp::complex::Complex;
+
+#[cfg(not(feature = "std"))]
+use crate::math::Float;
+
+/// FFT plan of a size known at compile time, for fixed-configuration real-time pipelines
+///
+/// Twiddle factors are computed once when the plan is created,
+/// and each transform works in a buffer on the stack, so nothing is allocated after .new().
+/// The size must be a power of two of at least 2, other sizes fail to compile.
+/// Unlike the FFT Algorithm it takes and returns arrays of exactly N samples,
+/// there is no windowing, zero-padding or truncation.
+///
+/// ```
+/// use muslib::algs::fft::Fft1024;
+///
+/// let fft = Fft1024::new();
+/// let frame = [1.0; 1024];
+/// let mut spectrum = [(0.0, 0.0); 1024];
+/// fft.fft(&frame, &mut spectrum);
+/// assert_eq!(spectrum[0], (1024.0, 0.0));
+/// ```
+pub struct Fft<const N: usize> {
+    /// exp(-2 pi i k / N) for each k up to N
+    twiddles: [Complex; N],
+}
+
+/// complex number of the bins transformed in place, the one of symphonia with std
+#[cfg(not(feature = "std"))]
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Complex {
+    /// real part
+    pub re: f32,
+    /// imaginary part
+    pub im: f32,
+}
+
+#[cfg(not(feature = "std"))]
+impl Complex {
+    /// create a complex number from its real and imaginary parts
+    pub fn new(re: f32, im: f32) -> Self {
+        Complex { re, im }
+    }
+
+    /// complex conjugate
+    pub fn conj(&self) -> Self {
+        Complex::new(self.re, -self.im)
+    }
+
+    /// multiply both parts by a scalar
+    pub fn scale(&self, scale: f32) -> Self {
+        Complex::new(self.re * scale, self.im * scale)
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl Add for Complex {
+    type Output = Complex;
+
+    fn add(self, rhs: Complex) -> Complex {
+        Complex::new(self.re + rhs.re, self.im + rhs.im)
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl Sub for Complex {
+    type Output = Complex;
+
+    fn sub(self, rhs: Complex) -> Complex {
+        Complex::new(self.re - rhs.re, self.im - rhs.im)
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl Mul for Complex {
+    type Output = Complex;
+
+    fn mul(self, rhs: Complex) -> Complex {
+        Complex::new(
+            self.re * rhs.re - self.im * rhs.im,
+            self.re * rhs.im + self.im * rhs.re,
+        )
+    }
+}
+
+/// FFT plan for frames of 512 samples
+pub type Fft512 = Fft<512>;
+/// FFT plan for frames of 1024 samples
+pub type Fft1024 = Fft<1024>;
+/// FFT plan for frames of 2048 samples
+pub type Fft2048 = Fft<2048>;
+/// FFT plan for frames of 4096 samples
+pub type Fft4096 = Fft<4096>;
+/// FFT plan for frames of 8192 samples
+pub type Fft8192 = Fft<8192>;
+
+impl<const N: usize> Default for Fft<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> Fft<N> {
+    const VALID_SIZE: () = assert!(
+        N >= 2 && N.is_power_of_two(),
+        "FFT size must be a power of two"
+    );
+
+    /// precompute the plan
+    pub fn new() -> Self {
+        let () = Self::VALID_SIZE;
+        let mut twiddles = [Complex::new(0.0, 0.0); N];
+        for (k, w) in twiddles.iter_mut().enumerate() {
+            let phase = -2.0 * core::f64::consts::PI * k as f64 / N as f64;
+            *w = Complex::new(phase.cos() as f32, phase.sin() as f32);
+        }
+        Fft { twiddles }
+    }
+
+    /// transform a frame of real samples into complex bins
+    pub fn fft(&self, frame: &[f64; N], output: &mut [(f32, f32); N]) {
+        let mut buf = [Complex::new(0.0, 0.0); N];
+        for (b, x) in buf.iter_mut().zip(frame.iter()) {
+            b.re = *x as f32;
+        }
+        self.fft_inplace(&mut buf);
+        for (o, b) in output.iter_mut().zip(buf.iter()) {
+            *o = (b.re, b.im);
+        }
+    }
+
+    /// transform complex bins back into real samples
+    pub fn ifft(&self, fft_data: &[(f32, f32); N], output: &mut [f32; N]) {
+        let mut buf = [Complex::new(0.0, 0.0); N];
+        for (b, x) in buf.iter_mut().zip(fft_data.iter()) {
+            *b = Complex::new(x.0, x.1);
+        }
+        self.ifft_inplace(&mut buf);
+        for (o, b) in output.iter_mut().zip(buf.iter()) {
+            *o = b.re;
+        }
+    }
+
+    /// forward transform in place
+    pub fn fft_inplace(&self, buf: &mut [Complex; N]) {
+        self.transform(buf, false);
+    }
+
+    /// inverse transform in place, scaled by 1/N like the IFFT Algorithm
+    pub fn ifft_inplace(&self, buf: &mut [Complex; N]) {
+        self.transform(buf, true);
+        let scale = 1.0 / N as f32;
+        for x in buf.iter_mut() {
+            *x = x.scale(scale);
+        }
+    }
+
+    /// iterative radix-2 decimation in time
+    fn transform(&self, buf: &mut [Complex; N], inverse: bool) {
+        let bits = N.trailing_zeros();
+        for i in 0..N {
+            let j = i.reverse_bits() >> (usize::BITS - bits);
+            if j > i {
+                buf.swap(i, j);
+            }
+        }
+
+        let mut len = 2;
+        while len <= N {
+            let half = len / 2;
+            let step = N / len;
+            for start in (0..N).step_by(len) {
+                for k in 0..half {
+                    let w = self.twiddles[k * step];
+                    let w = if inverse { w.conj() } else { w };
+                    let a = buf[start + k];
+                    let b = buf[start + k + half] * w;
+                    buf[start + k] = a + b;
+                    buf[start + k + half] = a - b;
+                }
+            }
+            len *= 2;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Fft, Fft512};
+    use crate::algs::stft::{FFT, IFFT};
+    use crate::algs::Algorithm;
+
+    #[test]
+    fn same_as_algorithm() {
+        let frame: Vec<f64> = (0..512).map(|x| (x as f64 * 0.3).sin() + 0.1).collect();
+        let expected = FFT::new().compute(frame.clone()).unwrap();
+
+        let fft = Fft512::new();
+        let mut spectrum = [(0.0, 0.0); 512];
+        fft.fft(&frame.clone().try_into().unwrap(), &mut spectrum);
+        for (x, y) in spectrum.iter().zip(expected.iter()) {
+            assert!((x.0 - y.0).abs() < 1e-3 && (x.1 - y.1).abs() < 1e-3);
+        }
+
+        let fft_data = spectrum.iter().map(|x| (x.0 as f64, x.1 as f64)).collect();
+        let expected = IFFT::new().compute(fft_data).unwrap();
+        let mut samples = [0.0; 512];
+        fft.ifft(&spectrum, &mut samples);
+        for ((x, y), z) in samples.iter().zip(expected.iter()).zip(frame.iter()) {
+            assert!((x - y).abs() < 1e-4 && (*x as f64 - z).abs() < 1e-4);
+        }
+
+        // the smallest plan
+        let mut spectrum = [(0.0, 0.0); 2];
+        Fft::<2>::new().fft(&[1.0, -1.0], &mut spectrum);
+        assert_eq!(spectrum, [(0.0, 0.0), (2.0, 0.0)]);
+    }
+}
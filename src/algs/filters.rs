@@ -0,0 +1,1007 @@
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+#[cfg(feature = "python")]
+use pyo3::{PyResult, Python};
+
+use super::{check_choice, Algorithm, Processor};
+use crate::json::{self, FromJson, Json, ToJson};
+#[cfg(not(feature = "std"))]
+use crate::math::Float;
+#[cfg(feature = "python")]
+use crate::pymod::array::{Array, ArrayView};
+#[cfg(feature = "python")]
+use crate::pymod::state::pymethods_with_params;
+use crate::Result;
+
+python_class! {
+    /// Moving-average smoothing of a signal or a feature sequence
+    #[pyclass(module = "muslib")]
+    pub struct MovingAverage {
+        /// Input: list[float] -- signal or feature sequence
+        #[pyo3(get, set)]
+        pub array: Vec<f64>,
+
+        /// Output: Optional[list[float]] -- smoothed sequence of the same length
+        #[pyo3(get, set)]
+        pub smoothed: Option<Vec<f64>>,
+
+        /// Param: int -- size of the centered window, rounded up to an odd number (default: 5)
+        #[pyo3(get, set)]
+        pub window_size: usize,
+        /// Param: str -- edge handling, one of {shrink, reflect, nearest, zero} (default: shrink)
+        #[pyo3(get)]
+        pub edge: String,
+
+        // input of earlier blocks while processing a stream
+        lookahead: Lookahead,
+    }
+}
+
+#[cfg(feature = "python")]
+pymethods_with_params! {
+    impl MovingAverage {
+        #[new]
+        #[pyo3(signature = (
+            window_size=5,
+            edge="shrink",
+        ))]
+        fn pynew(window_size: usize, edge: &str) -> PyResult<Self> {
+            check_edge(edge)?;
+            Ok(Self::with_params(MovingAverageParams {
+                window_size,
+                edge: edge.into(),
+            }))
+        }
+
+        #[setter(edge)]
+        fn set_edge(&mut self, edge: &str) -> PyResult<()> {
+            check_edge(edge)?;
+            self.edge = edge.into();
+            Ok(())
+        }
+
+        /// Compute the Algorithm
+        ///
+        /// Inputs:
+        ///   - array: list[float]
+        ///
+        /// Outputs:
+        ///   - smoothed: list[float]
+        ///
+        /// See data descriptors for more details.
+        #[pyo3(name = "compute", signature = (array=None))]
+        fn pycompute(&mut self, py: Python<'_>, array: Option<ArrayView<f64>>) -> Array<f64> {
+            if let Some(arg) = array {
+                arg.assign_to(&mut self.array)
+            }
+
+            py.allow_threads(|| self.run());
+
+            self.smoothed.as_ref().unwrap().clone().into()
+        }
+
+        fn __call__(&mut self, py: Python<'_>) {
+            py.allow_threads(|| self.run())
+        }
+    }
+
+    params = ["window_size", "edge"];
+    async = compute_async;
+
+    fn reset(&mut self) {
+        self.smoothed = None;
+        Processor::reset(self);
+    }
+}
+
+/// Parameters of the MovingAverage, see its data descriptors for details
+#[derive(Debug, Clone, PartialEq)]
+pub struct MovingAverageParams {
+    /// size of the centered window, rounded up to an odd number
+    pub window_size: usize,
+    /// edge handling, one of {shrink, reflect, nearest, zero}
+    pub edge: String,
+}
+
+impl Default for MovingAverageParams {
+    fn default() -> Self {
+        MovingAverageParams {
+            window_size: 5,
+            edge: "shrink".into(),
+        }
+    }
+}
+
+impl ToJson for MovingAverageParams {
+    fn to_json(&self) -> Json {
+        Json::object([
+            ("window_size", self.window_size.to_json()),
+            ("edge", self.edge.to_json()),
+        ])
+    }
+}
+
+impl FromJson for MovingAverageParams {
+    fn from_json(json: &Json) -> Result<Self> {
+        let mut params = Self::default();
+        for (key, value) in json.fields()? {
+            match key.as_str() {
+                "window_size" => params.window_size = FromJson::from_json(value)?,
+                "edge" => params.edge = json::checked(value, |e: &String| check_edge(e))?,
+                _ => return Err(json::unknown_key(key)),
+            }
+        }
+        Ok(params)
+    }
+}
+
+/// Builder of a MovingAverage starting from default parameters, see MovingAverage::builder()
+#[derive(Debug, Clone, Default)]
+pub struct MovingAverageBuilder {
+    params: MovingAverageParams,
+}
+
+impl MovingAverageBuilder {
+    /// size of the centered window, rounded up to an odd number
+    pub fn window_size(mut self, window_size: usize) -> Self {
+        self.params.window_size = window_size;
+        self
+    }
+
+    /// edge handling, one of {shrink, reflect, nearest, zero}
+    pub fn edge(mut self, edge: &str) -> Self {
+        self.params.edge = edge.into();
+        self
+    }
+
+    /// create the MovingAverage with the parameters set so far
+    pub fn build(self) -> MovingAverage {
+        MovingAverage::with_params(self.params)
+    }
+}
+
+impl Algorithm for MovingAverage {
+    /// signal or feature sequence
+    type Input = Vec<f64>;
+    /// smoothed sequence of the same length
+    type Output = Vec<f64>;
+    type Params = MovingAverageParams;
+
+    fn with_params(params: MovingAverageParams) -> Self {
+        MovingAverage {
+            array: Vec::new(),
+
+            smoothed: None,
+
+            window_size: params.window_size,
+            edge: params.edge,
+
+            lookahead: Lookahead::default(),
+        }
+    }
+
+    fn compute(&mut self, array: Vec<f64>) -> Result<Vec<f64>> {
+        self.array = array;
+        self.run();
+        Ok(self.smoothed.take().unwrap())
+    }
+
+    fn compute_into(&mut self, array: &Vec<f64>, output: &mut Vec<f64>) -> Result<()> {
+        output.resize(array.len(), 0.0);
+        let edge = Edge::from_str(&self.edge);
+        moving_average_into(array, self.window_size, &edge, output);
+        Ok(())
+    }
+}
+
+impl MovingAverage {
+    /// start building a MovingAverage from default parameters
+    pub fn builder() -> MovingAverageBuilder {
+        MovingAverageBuilder::default()
+    }
+
+    /// smooth the array with a moving average and set the output
+    fn run(&mut self) {
+        let edge = Edge::from_str(&self.edge);
+        self.smoothed = Some(moving_average(&self.array, self.window_size, &edge));
+    }
+}
+
+impl Processor for MovingAverage {
+    fn process(&mut self, block: &mut [f64]) {
+        let edge = Edge::from_str(&self.edge);
+        let lookahead = &mut self.lookahead;
+        lookahead.push(block, self.window_size / 2);
+        for (j, y) in block.iter_mut().enumerate() {
+            *y = match lookahead.window(j, &edge) {
+                Some(window) => {
+                    let (sum, count) = window.fold((0.0, 0), |(s, n), x| (s + x, n + 1));
+                    sum / count as f64
+                }
+                None => 0.0,
+            };
+        }
+        lookahead.advance(block.len());
+    }
+
+    fn latency(&self) -> usize {
+        self.window_size / 2
+    }
+
+    fn reset(&mut self) {
+        self.lookahead.clear();
+    }
+}
+
+python_class! {
+    /// Median smoothing of a signal or a feature sequence
+    #[pyclass(module = "muslib")]
+    pub struct MedianFilter {
+        /// Input: list[float] -- signal or feature sequence
+        #[pyo3(get, set)]
+        pub array: Vec<f64>,
+
+        /// Output: Optional[list[float]] -- smoothed sequence of the same length
+        #[pyo3(get, set)]
+        pub smoothed: Option<Vec<f64>>,
+
+        /// Param: int -- size of the centered window, rounded up to an odd number (default: 5)
+        #[pyo3(get, set)]
+        pub window_size: usize,
+        /// Param: str -- edge handling, one of {shrink, reflect, nearest, zero} (default: shrink)
+        #[pyo3(get)]
+        pub edge: String,
+
+        // scratch buffer for sorting windows while processing blocks
+        window: Vec<f64>,
+        // input of earlier blocks while processing a stream
+        lookahead: Lookahead,
+    }
+}
+
+#[cfg(feature = "python")]
+pymethods_with_params! {
+    impl MedianFilter {
+        #[new]
+        #[pyo3(signature = (
+            window_size=5,
+            edge="shrink",
+        ))]
+        fn pynew(window_size: usize, edge: &str) -> PyResult<Self> {
+            check_edge(edge)?;
+            Ok(Self::with_params(MedianFilterParams {
+                window_size,
+                edge: edge.into(),
+            }))
+        }
+
+        #[setter(edge)]
+        fn set_edge(&mut self, edge: &str) -> PyResult<()> {
+            check_edge(edge)?;
+            self.edge = edge.into();
+            Ok(())
+        }
+
+        /// Compute the Algorithm
+        ///
+        /// Inputs:
+        ///   - array: list[float]
+        ///
+        /// Outputs:
+        ///   - smoothed: list[float]
+        ///
+        /// See data descriptors for more details.
+        #[pyo3(name = "compute", signature = (array=None))]
+        fn pycompute(&mut self, py: Python<'_>, array: Option<ArrayView<f64>>) -> Array<f64> {
+            if let Some(arg) = array {
+                arg.assign_to(&mut self.array)
+            }
+
+            py.allow_threads(|| self.run());
+
+            self.smoothed.as_ref().unwrap().clone().into()
+        }
+
+        fn __call__(&mut self, py: Python<'_>) {
+            py.allow_threads(|| self.run())
+        }
+    }
+
+    params = ["window_size", "edge"];
+    async = compute_async;
+
+    fn reset(&mut self) {
+        self.smoothed = None;
+        Processor::reset(self);
+    }
+}
+
+/// Parameters of the MedianFilter, see its data descriptors for details
+#[derive(Debug, Clone, PartialEq)]
+pub struct MedianFilterParams {
+    /// size of the centered window, rounded up to an odd number
+    pub window_size: usize,
+    /// edge handling, one of {shrink, reflect, nearest, zero}
+    pub edge: String,
+}
+
+impl Default for MedianFilterParams {
+    fn default() -> Self {
+        MedianFilterParams {
+            window_size: 5,
+            edge: "shrink".into(),
+        }
+    }
+}
+
+impl ToJson for MedianFilterParams {
+    fn to_json(&self) -> Json {
+        Json::object([
+            ("window_size", self.window_size.to_json()),
+            ("edge", self.edge.to_json()),
+        ])
+    }
+}
+
+impl FromJson for MedianFilterParams {
+    fn from_json(json: &Json) -> Result<Self> {
+        let mut params = Self::default();
+        for (key, value) in json.fields()? {
+            match key.as_str() {
+                "window_size" => params.window_size = FromJson::from_json(value)?,
+                "edge" => params.edge = json::checked(value, |e: &String| check_edge(e))?,
+                _ => return Err(json::unknown_key(key)),
+            }
+        }
+        Ok(params)
+    }
+}
+
+/// Builder of a MedianFilter starting from default parameters, see MedianFilter::builder()
+#[derive(Debug, Clone, Default)]
+pub struct MedianFilterBuilder {
+    params: MedianFilterParams,
+}
+
+impl MedianFilterBuilder {
+    /// size of the centered window, rounded up to an odd number
+    pub fn window_size(mut self, window_size: usize) -> Self {
+        self.params.window_size = window_size;
+        self
+    }
+
+    /// edge handling, one of {shrink, reflect, nearest, zero}
+    pub fn edge(mut self, edge: &str) -> Self {
+        self.params.edge = edge.into();
+        self
+    }
+
+    /// create the MedianFilter with the parameters set so far
+    pub fn build(self) -> MedianFilter {
+        MedianFilter::with_params(self.params)
+    }
+}
+
+impl Algorithm for MedianFilter {
+    /// signal or feature sequence
+    type Input = Vec<f64>;
+    /// smoothed sequence of the same length
+    type Output = Vec<f64>;
+    type Params = MedianFilterParams;
+
+    fn with_params(params: MedianFilterParams) -> Self {
+        MedianFilter {
+            array: Vec::new(),
+
+            smoothed: None,
+
+            window_size: params.window_size,
+            edge: params.edge,
+
+            window: Vec::new(),
+            lookahead: Lookahead::default(),
+        }
+    }
+
+    fn compute(&mut self, array: Vec<f64>) -> Result<Vec<f64>> {
+        self.array = array;
+        self.run();
+        Ok(self.smoothed.take().unwrap())
+    }
+
+    fn compute_into(&mut self, array: &Vec<f64>, output: &mut Vec<f64>) -> Result<()> {
+        output.resize(array.len(), 0.0);
+        let edge = Edge::from_str(&self.edge);
+        median_into(array, self.window_size, &edge, &mut self.window, output);
+        Ok(())
+    }
+}
+
+impl MedianFilter {
+    /// start building a MedianFilter from default parameters
+    pub fn builder() -> MedianFilterBuilder {
+        MedianFilterBuilder::default()
+    }
+
+    /// smooth the array with a running median and set the output
+    fn run(&mut self) {
+        let edge = Edge::from_str(&self.edge);
+        self.smoothed = Some(median(&self.array, self.window_size, &edge));
+    }
+}
+
+impl Processor for MedianFilter {
+    fn process(&mut self, block: &mut [f64]) {
+        let edge = Edge::from_str(&self.edge);
+        let lookahead = &mut self.lookahead;
+        lookahead.push(block, self.window_size / 2);
+        // the window buffer is reused, so nothing is allocated once it holds a whole window
+        self.window.clear();
+        self.window.reserve(self.window_size | 1);
+        for (j, y) in block.iter_mut().enumerate() {
+            *y = match lookahead.window(j, &edge) {
+                Some(window) => {
+                    self.window.clear();
+                    self.window.extend(window);
+                    middle(&mut self.window)
+                }
+                None => 0.0,
+            };
+        }
+        lookahead.advance(block.len());
+    }
+
+    fn latency(&self) -> usize {
+        self.window_size / 2
+    }
+
+    fn reset(&mut self) {
+        self.lookahead.clear();
+    }
+}
+
+/// input kept between blocks by Processors smoothing with a centered window
+///
+/// Their output is delayed by half a window, so the window around every output sample
+/// has been seen whole and blocks join without seams.
+/// The start of the stream is handled with the edge handling like the start of a whole sequence.
+#[derive(Default)]
+struct Lookahead {
+    /// the last samples of earlier blocks, up to a whole window, followed by the current block
+    buffer: Vec<f64>,
+    /// index of the first sample of the buffer in the stream
+    first: usize,
+    /// first samples of the stream, up to the center of the first window
+    start: Vec<f64>,
+    /// number of samples of the stream before the current block
+    seen: usize,
+    /// half of the window size, the buffers are cleared when it changes
+    half: usize,
+}
+
+impl Lookahead {
+    /// append a block after the samples of earlier blocks
+    fn push(&mut self, block: &[f64], half: usize) {
+        if half != self.half {
+            self.clear();
+            self.half = half;
+        }
+        // room for a whole window before the block, so later blocks of the same size don't allocate
+        let room = 2 * half + block.len();
+        self.buffer.reserve(room.saturating_sub(self.buffer.len()));
+        self.buffer.extend_from_slice(block);
+        let missing = (half + 1).saturating_sub(self.start.len());
+        self.start.extend(block.iter().take(missing));
+    }
+
+    /// values in the window around the output sample j of the current block,
+    /// None while the output is still delayed at the start of the stream
+    fn window<'a>(&'a self, j: usize, edge: &'a Edge) -> Option<impl Iterator<Item = f64> + 'a> {
+        let half = self.half as isize;
+        let center = (self.seen + j) as isize - half;
+        if center < 0 {
+            return None;
+        }
+        Some((center - half..=center + half).filter_map(move |i| self.get(i, edge)))
+    }
+
+    /// sample at an index in the stream, or None if left out by the edge handling
+    fn get(&self, i: isize, edge: &Edge) -> Option<f64> {
+        if i >= 0 {
+            return Some(self.buffer[i as usize - self.first]);
+        }
+        match edge {
+            Edge::Shrink => None,
+            Edge::Zero => Some(0.0),
+            Edge::Nearest => Some(self.start[0]),
+            Edge::Reflect => Some(self.start[(-i) as usize]),
+        }
+    }
+
+    /// move past the current block, keeping only the samples a later window can reach
+    fn advance(&mut self, n: usize) {
+        self.seen += n;
+        let drop = self.buffer.len().saturating_sub(2 * self.half);
+        self.buffer.drain(..drop);
+        self.first += drop;
+    }
+
+    /// forget the stream
+    fn clear(&mut self) {
+        self.buffer.clear();
+        self.start.clear();
+        self.first = 0;
+        self.seen = 0;
+    }
+}
+
+/// check that the edge handling is known
+fn check_edge(edge: &str) -> Result<()> {
+    check_choice("edge", edge, &["shrink", "reflect", "nearest", "zero"])
+}
+
+/// edge handling for windows reaching outside of the sequence
+pub enum Edge {
+    /// use only the part of the window inside the sequence
+    Shrink,
+    /// mirror the sequence around the edge sample
+    Reflect,
+    /// repeat the edge sample
+    Nearest,
+    /// pad the sequence with zeros
+    Zero,
+}
+
+impl Edge {
+    fn from_str(edge: &str) -> Self {
+        match edge {
+            "shrink" => Edge::Shrink,
+            "reflect" => Edge::Reflect,
+            "nearest" => Edge::Nearest,
+            "zero" => Edge::Zero,
+            _ => Edge::Shrink,
+        }
+    }
+
+    /// value at any index, None if it should be left out of the window
+    fn get(&self, x: &[f64], i: isize) -> Option<f64> {
+        let n = x.len() as isize;
+        if n == 0 {
+            return None;
+        }
+        if i >= 0 && i < n {
+            return Some(x[i as usize]);
+        }
+
+        match self {
+            Edge::Shrink => None,
+            Edge::Zero => Some(0.0),
+            Edge::Nearest => Some(x[i.clamp(0, n - 1) as usize]),
+            Edge::Reflect => {
+                if n == 1 {
+                    return Some(x[0]);
+                }
+                // reflection without repeating the edge sample is periodic
+                let period = 2 * (n - 1);
+                let j = i.rem_euclid(period);
+                let j = if j < n { j } else { period - j };
+                Some(x[j as usize])
+            }
+        }
+    }
+}
+
+/// centered moving average with the given window size and edge handling
+pub fn moving_average(x: &[f64], window_size: usize, edge: &Edge) -> Vec<f64> {
+    let mut output = vec![0.0; x.len()];
+    moving_average_into(x, window_size, edge, &mut output);
+    output
+}
+
+/// centered moving average written to an output of the same length, without allocating
+pub fn moving_average_into(x: &[f64], window_size: usize, edge: &Edge, output: &mut [f64]) {
+    let half = (window_size / 2) as isize;
+
+    let mut sum = 0.0;
+    let mut count = 0;
+    for i in -half - 1..half {
+        if let Some(v) = edge.get(x, i) {
+            sum += v;
+            count += 1;
+        }
+    }
+
+    for (i, y) in output.iter_mut().enumerate().take(x.len()) {
+        let i = i as isize;
+        if let Some(v) = edge.get(x, i + half) {
+            sum += v;
+            count += 1;
+        }
+        if let Some(v) = edge.get(x, i - half - 1) {
+            sum -= v;
+            count -= 1;
+        }
+        *y = sum / count as f64;
+    }
+}
+
+/// centered running median with the given window size and edge handling
+pub fn median(x: &[f64], window_size: usize, edge: &Edge) -> Vec<f64> {
+    let mut output = vec![0.0; x.len()];
+    median_into(x, window_size, edge, &mut Vec::new(), &mut output);
+    output
+}
+
+/// centered running median written to an output of the same length
+///
+/// Windows are sorted in a scratch buffer, nothing is allocated once it holds a whole window.
+pub fn median_into(
+    x: &[f64],
+    window_size: usize,
+    edge: &Edge,
+    window: &mut Vec<f64>,
+    output: &mut [f64],
+) {
+    let half = (window_size / 2) as isize;
+
+    window.clear();
+    window.reserve(2 * half as usize + 1);
+    for (i, y) in output.iter_mut().enumerate().take(x.len()) {
+        let i = i as isize;
+        window.clear();
+        window.extend((i - half..=i + half).filter_map(|j| edge.get(x, j)));
+        *y = middle(window);
+    }
+}
+
+/// median of the values of a window, sorting them in place
+fn middle(window: &mut [f64]) -> f64 {
+    window.sort_unstable_by(|a, b| a.total_cmp(b));
+
+    let m = window.len() / 2;
+    if window.len() % 2 == 1 {
+        window[m]
+    } else {
+        (window[m - 1] + window[m]) / 2.0
+    }
+}
+
+/// biquad filter section `H(z) = (b0 + b1 z^-1 + b2 z^-2) / (1 + a1 z^-1 + a2 z^-2)`
+pub struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+    x: [f64; 2],
+    y: [f64; 2],
+}
+
+impl Biquad {
+    /// create a new biquad section from normalized coefficients
+    pub fn new(b: [f64; 3], a: [f64; 2]) -> Self {
+        Biquad {
+            b,
+            a,
+            x: [0.0; 2],
+            y: [0.0; 2],
+        }
+    }
+
+    /// create a new second-order lowpass section with coefficients from the audio EQ cookbook
+    pub fn lowpass(freq: f64, q: f64, sample_rate: f64) -> Self {
+        let (cos, alpha) = Self::prewarp(freq, q, sample_rate);
+        let b = (1.0 - cos) / 2.0;
+        Self::normalized([b, 2.0 * b, b], [1.0 + alpha, -2.0 * cos, 1.0 - alpha])
+    }
+
+    /// create a new second-order highpass section with coefficients from the audio EQ cookbook
+    pub fn highpass(freq: f64, q: f64, sample_rate: f64) -> Self {
+        let (cos, alpha) = Self::prewarp(freq, q, sample_rate);
+        let b = (1.0 + cos) / 2.0;
+        Self::normalized([b, -2.0 * b, b], [1.0 + alpha, -2.0 * cos, 1.0 - alpha])
+    }
+
+    fn prewarp(freq: f64, q: f64, sample_rate: f64) -> (f64, f64) {
+        let w = 2.0 * core::f64::consts::PI * freq / sample_rate;
+        (w.cos(), w.sin() / (2.0 * q))
+    }
+
+    fn normalized(b: [f64; 3], a: [f64; 3]) -> Self {
+        Self::new(
+            [b[0] / a[0], b[1] / a[0], b[2] / a[0]],
+            [a[1] / a[0], a[2] / a[0]],
+        )
+    }
+
+    /// filter a single sample
+    pub fn tick(&mut self, x: f64) -> f64 {
+        let y = self.b[0] * x + self.b[1] * self.x[0] + self.b[2] * self.x[1]
+            - self.a[0] * self.y[0]
+            - self.a[1] * self.y[1];
+        self.x = [x, self.x[0]];
+        self.y = [y, self.y[0]];
+        y
+    }
+}
+
+impl Processor for Biquad {
+    fn process(&mut self, block: &mut [f64]) {
+        for x in block.iter_mut() {
+            *x = self.tick(*x);
+        }
+    }
+
+    fn reset(&mut self) {
+        self.x = [0.0; 2];
+        self.y = [0.0; 2];
+    }
+}
+
+/// first-order allpass section `H(z) = (a + z^-1) / (1 + a z^-1)`
+pub struct FirstOrderAllpass {
+    /// filter coefficient, stable for |a| < 1
+    pub a: f64,
+    x1: f64,
+    y1: f64,
+}
+
+impl FirstOrderAllpass {
+    /// create a new first-order allpass section from its coefficient
+    pub fn new(a: f64) -> Self {
+        FirstOrderAllpass {
+            a,
+            x1: 0.0,
+            y1: 0.0,
+        }
+    }
+
+    /// create a new first-order allpass section with a phase shift of -pi/2 at the given frequency
+    pub fn from_frequency(freq: f64, sample_rate: f64) -> Self {
+        let t = (core::f64::consts::PI * freq / sample_rate).tan();
+        Self::new((t - 1.0) / (t + 1.0))
+    }
+
+    /// filter a single sample
+    pub fn tick(&mut self, x: f64) -> f64 {
+        let y = self.a * x + self.x1 - self.a * self.y1;
+        self.x1 = x;
+        self.y1 = y;
+        y
+    }
+
+    /// unwrapped phase response in radians at the given frequency
+    pub fn phase(&self, freq: f64, sample_rate: f64) -> f64 {
+        let w = 2.0 * core::f64::consts::PI * freq / sample_rate;
+        pole_phase(-self.a, 0.0, w)
+    }
+}
+
+impl Processor for FirstOrderAllpass {
+    fn process(&mut self, block: &mut [f64]) {
+        for x in block.iter_mut() {
+            *x = self.tick(*x);
+        }
+    }
+
+    fn reset(&mut self) {
+        self.x1 = 0.0;
+        self.y1 = 0.0;
+    }
+}
+
+/// second-order allpass section `H(z) = (a2 + a1 z^-1 + z^-2) / (1 + a1 z^-1 + a2 z^-2)`
+pub struct SecondOrderAllpass {
+    /// first filter coefficient
+    pub a1: f64,
+    /// second filter coefficient, stable for |a2| < 1
+    pub a2: f64,
+    x: [f64; 2],
+    y: [f64; 2],
+}
+
+impl SecondOrderAllpass {
+    /// create a new second-order allpass section from its coefficients
+    pub fn new(a1: f64, a2: f64) -> Self {
+        SecondOrderAllpass {
+            a1,
+            a2,
+            x: [0.0; 2],
+            y: [0.0; 2],
+        }
+    }
+
+    /// create a new second-order allpass section with a phase shift of -pi at the given frequency
+    ///
+    /// The phase transition gets steeper with a higher quality factor.
+    pub fn from_frequency(freq: f64, q: f64, sample_rate: f64) -> Self {
+        // coefficients from the audio EQ cookbook
+        let w = 2.0 * core::f64::consts::PI * freq / sample_rate;
+        let alpha = w.sin() / (2.0 * q);
+        Self::new(
+            -2.0 * w.cos() / (1.0 + alpha),
+            (1.0 - alpha) / (1.0 + alpha),
+        )
+    }
+
+    /// filter a single sample
+    pub fn tick(&mut self, x: f64) -> f64 {
+        let y = self.a2 * x + self.a1 * self.x[0] + self.x[1]
+            - self.a1 * self.y[0]
+            - self.a2 * self.y[1];
+        self.x = [x, self.x[0]];
+        self.y = [y, self.y[0]];
+        y
+    }
+
+    /// unwrapped phase response in radians at the given frequency
+    pub fn phase(&self, freq: f64, sample_rate: f64) -> f64 {
+        let w = 2.0 * core::f64::consts::PI * freq / sample_rate;
+
+        // poles are the roots of z^2 + a1 z + a2
+        let d = self.a1 * self.a1 - 4.0 * self.a2;
+        if d < 0.0 {
+            let r = self.a2.sqrt();
+            let theta = (-self.a1 / (2.0 * r)).clamp(-1.0, 1.0).acos();
+            pole_phase(r, theta, w) + pole_phase(r, -theta, w)
+        } else {
+            let p1 = (-self.a1 + d.sqrt()) / 2.0;
+            let p2 = (-self.a1 - d.sqrt()) / 2.0;
+            pole_phase(p1, 0.0, w) + pole_phase(p2, 0.0, w)
+        }
+    }
+}
+
+impl Processor for SecondOrderAllpass {
+    fn process(&mut self, block: &mut [f64]) {
+        for x in block.iter_mut() {
+            *x = self.tick(*x);
+        }
+    }
+
+    fn reset(&mut self) {
+        self.x = [0.0; 2];
+        self.y = [0.0; 2];
+    }
+}
+
+/// phase of a first-order allpass factor with the pole at r * e^(j theta)
+fn pole_phase(r: f64, theta: f64, w: f64) -> f64 {
+    -w - 2.0 * (r * (w - theta).sin()).atan2(1.0 - r * (w - theta).cos())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        median, moving_average, Biquad, Edge, FirstOrderAllpass, MedianFilter, MovingAverage,
+        Processor, SecondOrderAllpass,
+    };
+    use crate::algs::Algorithm;
+
+    #[test]
+    fn streaming() {
+        let x: Vec<f64> = (0..300)
+            .map(|i| (i as f64 * 0.37).sin() + (i % 7) as f64)
+            .collect();
+        for edge in ["shrink", "reflect", "nearest", "zero"] {
+            let mut average = MovingAverage::builder().window_size(9).edge(edge).build();
+            let mut median = MedianFilter::builder().window_size(6).edge(edge).build();
+            let expected = [
+                average.compute(x.clone()).unwrap(),
+                median.compute(x.clone()).unwrap(),
+            ];
+            let processors: [&mut dyn Processor; 2] = [&mut average, &mut median];
+
+            // blocks of any size give the whole output delayed by the latency
+            for (p, expected) in processors.into_iter().zip(expected) {
+                let half = p.latency();
+                let mut y = x.clone();
+                for block in [1, 2, 37, 64, 196].iter().scan(0, |i, n| {
+                    *i += n;
+                    Some(*i - n..*i)
+                }) {
+                    p.process(&mut y[block]);
+                }
+                assert!(y[..half].iter().all(|y| *y == 0.0));
+                for (y, e) in y[half..].iter().zip(&expected) {
+                    assert!((y - e).abs() < 1e-9, "{} {} {}", edge, y, e);
+                }
+                p.reset();
+            }
+        }
+    }
+
+    #[test]
+    fn moving_average_edges() {
+        let input = [1.0, 2.0, 3.0, 4.0, 8.0];
+        let edges = [Edge::Shrink, Edge::Reflect, Edge::Nearest, Edge::Zero];
+        let result = [
+            [1.5, 2.0, 3.0, 5.0, 6.0],
+            [5.0 / 3.0, 2.0, 3.0, 5.0, 16.0 / 3.0],
+            [4.0 / 3.0, 2.0, 3.0, 5.0, 20.0 / 3.0],
+            [1.0, 2.0, 3.0, 5.0, 4.0],
+        ];
+
+        for i in 0..edges.len() {
+            assert_eq!(
+                moving_average(&input, 3, &edges[i]),
+                result[i],
+                "test {}",
+                i
+            );
+        }
+    }
+
+    #[test]
+    fn median_edges() {
+        let input = [5.0, 1.0, 9.0, 2.0, 2.0, 100.0, 3.0];
+        let edges = [Edge::Shrink, Edge::Reflect, Edge::Nearest, Edge::Zero];
+        let result = [
+            [3.0, 5.0, 2.0, 2.0, 2.0, 3.0, 51.5],
+            [1.0, 5.0, 2.0, 2.0, 2.0, 3.0, 100.0],
+            [5.0, 5.0, 2.0, 2.0, 2.0, 3.0, 3.0],
+            [1.0, 5.0, 2.0, 2.0, 2.0, 3.0, 3.0],
+        ];
+
+        for i in 0..edges.len() {
+            assert_eq!(median(&input, 3, &edges[i]), result[i], "test {}", i);
+        }
+    }
+
+    #[test]
+    fn allpass() {
+        let fs = 44100.0;
+        let pi = std::f64::consts::PI;
+
+        let mut first = FirstOrderAllpass::from_frequency(1000.0, fs);
+        let mut second = SecondOrderAllpass::from_frequency(1000.0, 2.0, fs);
+
+        let phase = [
+            first.phase(0.0, fs),
+            first.phase(1000.0, fs),
+            first.phase(fs / 2.0, fs),
+            second.phase(0.0, fs),
+            second.phase(1000.0, fs),
+            second.phase(fs / 2.0, fs),
+        ];
+        let result = [0.0, -pi / 2.0, -pi, 0.0, -pi, -2.0 * pi];
+        for i in 0..phase.len() {
+            assert!((phase[i] - result[i]).abs() < 1e-9, "test {}", i);
+        }
+
+        // unit gain at all frequencies keeps the energy of an impulse
+        let sections: [&mut dyn Processor; 2] = [&mut first, &mut second];
+        for (i, section) in sections.into_iter().enumerate() {
+            let mut impulse = vec![0.0; 44100];
+            impulse[0] = 1.0;
+            section.process(&mut impulse);
+            let energy: f64 = impulse.iter().map(|x| x * x).sum();
+            assert!((energy - 1.0).abs() < 1e-9, "test {}", i);
+        }
+    }
+
+    #[test]
+    fn biquad() {
+        let fs = 8000.0;
+        let sine = |freq: f64| -> Vec<f64> {
+            (0..8000)
+                .map(|i| (2.0 * std::f64::consts::PI * freq * i as f64 / fs).sin())
+                .collect()
+        };
+        let peak = |x: &[f64]| x[4000..].iter().fold(0.0, |m: f64, x| m.max(x.abs()));
+
+        let filters = [
+            Biquad::lowpass(500.0, 0.707, fs),
+            Biquad::highpass(500.0, 0.707, fs),
+        ];
+        // gain well below and well above the cutoff
+        let result = [(1.0, 0.0), (0.0, 1.0)];
+        for (i, mut filter) in filters.into_iter().enumerate() {
+            let mut low = sine(50.0);
+            let mut high = sine(3000.0);
+            filter.process(&mut low);
+            filter.reset();
+            filter.process(&mut high);
+            assert!((peak(&low) - result[i].0).abs() < 0.05, "test {}", i);
+            assert!((peak(&high) - result[i].1).abs() < 0.05, "test {}", i);
+        }
+    }
+}
@@ -0,0 +1,251 @@
+#[cfg(feature = "python")]
+use pyo3::exceptions::PyValueError;
+#[cfg(feature = "python")]
+use pyo3::types::{PyIterator, PyString};
+#[cfg(feature = "python")]
+use pyo3::{pyclass, pymethods, Py, PyAny, PyRefMut, PyResult, Python};
+
+#[cfg(feature = "python")]
+use crate::mixer::{Loader, Packets};
+#[cfg(feature = "python")]
+use crate::pymod::array::{Array, ArrayView};
+use crate::sample::Sample;
+
+/// Generate consecutive frames from a file, a signal or a stream of blocks
+///
+/// The source can be a path to a file that will be decoded lazily,
+/// a whole signal given as a list of floats,
+/// or any iterable that yields blocks of samples, e.g. from a live input.
+/// The last frame is padded with zeros.
+#[cfg(feature = "python")]
+#[pyclass(module = "muslib")]
+pub struct FrameGenerator {
+    /// Param: int -- number of samples in each frame (default: 1024)
+    #[pyo3(get)]
+    pub frame_size: usize,
+    /// Param: int -- number of samples between the starts of consecutive frames (default: 512)
+    #[pyo3(get)]
+    pub hop_size: usize,
+    /// Output: Optional[int] -- sample rate of the decoded file
+    #[pyo3(get)]
+    pub sample_rate: Option<usize>,
+
+    source: Source,
+    cutter: FrameCutter,
+}
+
+/// where the FrameGenerator takes its samples from
+#[cfg(feature = "python")]
+enum Source {
+    Packets(Box<Packets<f64>>),
+    Blocks(Py<PyIterator>),
+    Finished,
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl FrameGenerator {
+    #[new]
+    #[pyo3(signature = (
+        source,
+        frame_size=1024,
+        hop_size=512,
+    ))]
+    fn pynew(source: &PyAny, frame_size: usize, hop_size: usize) -> PyResult<Self> {
+        if frame_size == 0 || hop_size == 0 {
+            return Err(PyValueError::new_err(
+                "frame_size and hop_size must be positive",
+            ));
+        }
+
+        let mut cutter = FrameCutter::new(frame_size, hop_size);
+        let source = if let Ok(file) = source.downcast::<PyString>() {
+            let mut loader = Loader::<f64>::new();
+            let packets = loader.file(file.to_str()?.into()).packets();
+            Source::Packets(Box::new(packets?))
+        } else if let Ok(signal) = source.extract::<ArrayView<f64>>() {
+            cutter.push(&signal);
+            Source::Finished
+        } else {
+            Source::Blocks(source.iter()?.into())
+        };
+
+        Ok(FrameGenerator {
+            frame_size,
+            hop_size,
+            sample_rate: None,
+            source,
+            cutter,
+        })
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "FrameGenerator(frame_size={}, hop_size={})",
+            self.frame_size, self.hop_size
+        )
+    }
+
+    fn __iter__(slf: PyRefMut<'_, Self>) -> PyRefMut<'_, Self> {
+        slf
+    }
+
+    fn __next__(&mut self, py: Python<'_>) -> PyResult<Option<Array<f64>>> {
+        loop {
+            if let Some(frame) = self.cutter.pop() {
+                return Ok(Some(frame.into()));
+            }
+
+            match &mut self.source {
+                Source::Packets(packets) => match py.allow_threads(|| packets.next()) {
+                    Some(block) => {
+                        self.cutter.push(&block?);
+                        self.sample_rate = packets.sample_rate().map(|x| x as usize);
+                    }
+                    None => self.source = Source::Finished,
+                },
+                Source::Blocks(blocks) => match blocks.as_ref(py).next() {
+                    Some(block) => self.cutter.push(&block?.extract::<ArrayView<f64>>()?),
+                    None => self.source = Source::Finished,
+                },
+                Source::Finished => return Ok(self.cutter.flush().map(|x| x.into())),
+            }
+        }
+    }
+}
+
+/// cutter of a stream of samples of any type into overlapping frames
+pub struct FrameCutter<S = f64> {
+    frame_size: usize,
+    hop_size: usize,
+    buffer: Vec<S>,
+    // samples still to be dropped when the hop is longer than a frame
+    skip: usize,
+}
+
+impl<S: Sample> FrameCutter<S> {
+    /// create a new FrameCutter for a given frame and hop size
+    pub fn new(frame_size: usize, hop_size: usize) -> Self {
+        FrameCutter {
+            frame_size,
+            hop_size,
+            buffer: Vec::with_capacity(frame_size),
+            skip: 0,
+        }
+    }
+
+    /// append a block of samples at the end of the stream
+    pub fn push(&mut self, samples: &[S]) {
+        let skip = std::cmp::min(self.skip, samples.len());
+        self.skip -= skip;
+        self.buffer.extend_from_slice(&samples[skip..]);
+    }
+
+    /// take the next frame if enough samples were pushed
+    pub fn pop(&mut self) -> Option<Vec<S>> {
+        let mut frame = Vec::with_capacity(self.frame_size);
+        self.pop_into(&mut frame).then_some(frame)
+    }
+
+    /// copy the next frame to a reused buffer if enough samples were pushed
+    ///
+    /// Returns false and leaves the buffer untouched if there is no frame yet.
+    /// Nothing is allocated once the buffer and the stream hold a whole frame,
+    /// so frames can be cut inside an audio callback.
+    pub fn pop_into(&mut self, frame: &mut Vec<S>) -> bool {
+        if self.buffer.len() < self.frame_size {
+            return false;
+        }
+        frame.clear();
+        frame.extend_from_slice(&self.buffer[..self.frame_size]);
+        self.advance();
+        true
+    }
+
+    /// take the next frame padded with zeros after the stream has ended
+    ///
+    /// Returns None once the remaining samples were all included in a frame.
+    pub fn flush(&mut self) -> Option<Vec<S>> {
+        if let Some(frame) = self.pop() {
+            return Some(frame);
+        }
+        if self.buffer.is_empty() {
+            return None;
+        }
+        let mut frame = self.buffer.clone();
+        frame.resize(self.frame_size, S::from_f64(0.0));
+        self.advance();
+        Some(frame)
+    }
+
+    fn advance(&mut self) {
+        let hop = std::cmp::min(self.hop_size, self.buffer.len());
+        self.buffer.drain(..hop);
+        self.skip = self.hop_size - hop;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FrameCutter;
+
+    #[test]
+    fn frame_cutter() {
+        let input: Vec<f64> = (1..=10).map(|x| x as f64).collect();
+        let result = [
+            // frame size, hop size, frames
+            (
+                4,
+                2,
+                vec![
+                    vec![1.0, 2.0, 3.0, 4.0],
+                    vec![3.0, 4.0, 5.0, 6.0],
+                    vec![5.0, 6.0, 7.0, 8.0],
+                    vec![7.0, 8.0, 9.0, 10.0],
+                    vec![9.0, 10.0, 0.0, 0.0],
+                ],
+            ),
+            (3, 5, vec![vec![1.0, 2.0, 3.0], vec![6.0, 7.0, 8.0]]),
+        ];
+
+        for (i, (frame_size, hop_size, frames)) in result.iter().enumerate() {
+            let mut cutter = FrameCutter::new(*frame_size, *hop_size);
+            let mut output = Vec::new();
+            // push blocks of uneven size like a stream would
+            for block in input.chunks(3) {
+                cutter.push(block);
+                while let Some(frame) = cutter.pop() {
+                    output.push(frame);
+                }
+            }
+            while let Some(frame) = cutter.flush() {
+                output.push(frame);
+            }
+            assert_eq!(&output, frames, "test {}", i);
+        }
+    }
+
+    #[test]
+    fn frame_cutter_reuse() {
+        let mut cutter = FrameCutter::new(3, 2);
+        let mut frame = vec![0.0; 10];
+        assert!(!cutter.pop_into(&mut frame));
+        assert_eq!(frame.len(), 10);
+        cutter.push(&[1.0, 2.0, 3.0, 4.0, 5.0]);
+        assert!(cutter.pop_into(&mut frame));
+        assert_eq!(frame, [1.0, 2.0, 3.0]);
+        assert!(cutter.pop_into(&mut frame));
+        assert_eq!(frame, [3.0, 4.0, 5.0]);
+        assert!(!cutter.pop_into(&mut frame));
+    }
+
+    #[test]
+    fn frame_cutter_pcm() {
+        // the last frame of raw pcm values is padded with silence rather than zeros
+        let mut cutter = FrameCutter::<u16>::new(4, 4);
+        cutter.push(&[1, 2, 3, 4, 5]);
+        assert_eq!(cutter.flush(), Some(vec![1, 2, 3, 4]));
+        assert_eq!(cutter.flush(), Some(vec![5, 0x8000, 0x8000, 0x8000]));
+        assert_eq!(cutter.flush(), None);
+    }
+}
@@ -1,140 +1,461 @@
-use pyo3::{pyclass, pymethods};
+#[cfg(feature = "python")]
+use pyo3::exceptions::PyValueError;
+#[cfg(feature = "python")]
+use pyo3::{PyResult, Python};
+
+use super::{check_choice, Algorithm};
+use crate::json::{self, FromJson, Json, ToJson};
+#[cfg(feature = "python")]
+use crate::pymod::array::{Array, Array2, ArrayView};
+#[cfg(feature = "python")]
+use crate::pymod::matrix::Matrix;
+#[cfg(feature = "python")]
+use crate::pymod::state::pymethods_with_params;
+use crate::simd;
+use crate::Result;
+
+python_class! {
+    /// Harmonic Pitch Class Profile computed from spectral peaks
+    #[pyclass(module = "muslib", get_all)]
+    pub struct HPCP {
+        /// Input: list[float] -- frequencies of the spectral peaks
+        #[pyo3(set)]
+        pub frequencies: Vec<f64>,
+        /// Input: list[float] -- magnitudes of spectral peaks
+        #[pyo3(set)]
+        pub magnitudes: Vec<f64>,
+
+        /// Output: Optional[list[float]] -- resulting harmonic pitch class profile
+        #[pyo3(set)]
+        pub hpcp_data: Option<Vec<f64>>,
+
+        /// Param: int -- the size of the output HPCP, one of {12, 24, 36} (default: 12)
+        pub size: usize,
+        /// Param: float -- sampling rate of the audio signal in Hz (default: 44100)
+        #[pyo3(set)]
+        pub sample_rate: f64,
+        /// Param: float -- the reference frequency for semitone index calculation (default: 440)
+        #[pyo3(set)]
+        pub reference_frequency: f64,
+        /// Param: float -- split frequency for low and high bands (default: 500)
+        #[pyo3(set)]
+        pub band_split_frequency: f64,
+        /// Param: float -- maximum frequency that contributes to the HPCP in Hz (default: 5000)
+        #[pyo3(set)]
+        pub max_frequency: f64,
+        /// Param: float -- minimum frequency that contributes to the HPCP in Hz (default: 40)
+        #[pyo3(set)]
+        pub min_frequency: f64,
+        /// Param: int -- number of additional harmonics for frequency contribution (default: 0)
+        #[pyo3(set)]
+        pub harmonics: usize,
+        /// Param: str -- whether to use a squared cosine weighting funcion for determining frequency contribution (default: true)
+        #[pyo3(set)]
+        pub weighting: bool,
+        /// Param: float -- size in semitones of the window used for weighting
+        #[pyo3(set)]
+        pub weighting_window_size: f64,
+        /// Param: bool -- whether to normalize output vectors (default: true)
+        #[pyo3(set)]
+        pub normalized: bool,
+        /// Param: bool -- whether to apply nonlinear post-processin on output vectors (default: false)
+        #[pyo3(set)]
+        pub nonlinear_post: bool,
+
+        harmonic_peaks: Vec<(f64, f64)>,
+    }
+}
+
+#[cfg(feature = "python")]
+pymethods_with_params! {
+    impl HPCP {
+        #[new]
+        #[pyo3(signature = (
+            size=12,
+            sample_rate=44100.0,
+            reference_frequency=440.0,
+            band_split_frequency=500.0,
+            max_frequency=5000.0,
+            min_frequency=40.0,
+            harmonics=0,
+            weighting=true,
+            weighting_window_size=1.0,
+            normalized=true,
+            nonlinear_post=false,
+        ))]
+        #[allow(clippy::too_many_arguments)]
+        fn pynew(
+            size: usize,
+            sample_rate: f64,
+            reference_frequency: f64,
+            band_split_frequency: f64,
+            max_frequency: f64,
+            min_frequency: f64,
+            harmonics: usize,
+            weighting: bool,
+            weighting_window_size: f64,
+            normalized: bool,
+            nonlinear_post: bool,
+        ) -> PyResult<Self> {
+            check_size(size)?;
+            Ok(Self::with_params(HPCPParams {
+                size,
+                sample_rate,
+                reference_frequency,
+                band_split_frequency,
+                max_frequency,
+                min_frequency,
+                harmonics,
+                weighting,
+                weighting_window_size,
+                normalized,
+                nonlinear_post,
+            }))
+        }
+
+        #[setter(size)]
+        fn set_size(&mut self, size: usize) -> PyResult<()> {
+            check_size(size)?;
+            self.size = size;
+            Ok(())
+        }
+
+        /// Compute the Algorithm
+        ///
+        /// Inputs:
+        ///   - frequencies: list[float]
+        ///   - magnitudes: list[float]
+        ///
+        /// Outputs:
+        ///   - hpcp_data: list[float]
+        ///
+        /// See data descriptors for more details.
+        #[pyo3(name = "compute", signature = (frequencies=None, magnitudes=None))]
+        fn pycompute(
+            &mut self,
+            py: Python<'_>,
+            frequencies: Option<ArrayView<f64>>,
+            magnitudes: Option<ArrayView<f64>>,
+        ) -> Array<f64> {
+            if let Some(arg) = frequencies {
+                arg.assign_to(&mut self.frequencies)
+            }
+            if let Some(arg) = magnitudes {
+                arg.assign_to(&mut self.magnitudes)
+            }
+
+            py.allow_threads(|| self.run());
+
+            self.hpcp_data.as_ref().unwrap().clone().into()
+        }
 
-use super::Algorithm;
+        /// Compute the Algorithm for many frames in a single call
+        ///
+        /// Inputs:
+        ///   - frequencies: list[list[float]]
+        ///   - magnitudes: list[list[float]]
+        ///
+        /// Outputs:
+        ///   - list[list[float]] -- harmonic pitch class profile of each frame
+        ///
+        /// See data descriptors for more details.
+        fn compute_batch(
+            &mut self,
+            py: Python<'_>,
+            frequencies: Array2<f64>,
+            magnitudes: Array2<f64>,
+        ) -> PyResult<Array2<f64>> {
+            Ok(self.batch(py, frequencies.0, magnitudes.0)?.into())
+        }
+
+        /// Compute a chromagram of consecutive frames as a Matrix for plotting
+        ///
+        /// Inputs:
+        ///   - frequencies: list[list[float]]
+        ///   - magnitudes: list[list[float]]
+        ///   - hop_size: int -- number of samples between the starts of consecutive frames
+        ///
+        /// Outputs:
+        ///   - Matrix -- profiles with a row for each pitch class in semitones above reference_frequency and a column for each frame
+        ///
+        /// See data descriptors for more details.
+        #[pyo3(signature = (frequencies, magnitudes, hop_size = 512))]
+        fn compute_matrix(
+            &mut self,
+            py: Python<'_>,
+            frequencies: Array2<f64>,
+            magnitudes: Array2<f64>,
+            hop_size: usize,
+        ) -> PyResult<Matrix> {
+            let frames = self.batch(py, frequencies.0, magnitudes.0)?;
+            let bins = (0..self.size)
+                .map(|i| i as f64 * 12.0 / self.size as f64)
+                .collect();
+            let hop = hop_size as f64 / self.sample_rate;
+            Ok(Matrix::new(frames, hop, bins))
+        }
 
-#[pyclass(get_all, set_all)]
-pub struct HPCP {
-    /// Input: list[float] -- frequencies of the spectral peaks
-    pub frequencies: Vec<f64>,
-    /// Input: list[float] -- magnitudes of spectral peaks
-    pub magnitudes: Vec<f64>,
+        fn __call__(&mut self, py: Python<'_>) {
+            py.allow_threads(|| self.run())
+        }
+    }
 
-    /// Output: Optional[list[float]] -- resulting harmonic pitch class profile
-    pub hpcp_data: Option<Vec<f64>>,
+    params = [
+        "size",
+        "sample_rate",
+        "reference_frequency",
+        "band_split_frequency",
+        "max_frequency",
+        "min_frequency",
+        "harmonics",
+        "weighting",
+        "weighting_window_size",
+        "normalized",
+        "nonlinear_post",
+    ];
+    async = compute_async;
+
+    fn reset(&mut self) {
+        self.hpcp_data = None;
+    }
+}
 
-    /// Param: int -- the size of the output HPCP, one of {12, 24, 36} (default: 12)
+/// Parameters of the HPCP, see its data descriptors for details
+#[derive(Debug, Clone, PartialEq)]
+pub struct HPCPParams {
+    /// size of the output HPCP, one of {12, 24, 36}
     pub size: usize,
-    /// Param: float -- sampling rate of the audio signal in Hz (default: 44100)
+    /// sampling rate of the audio signal in Hz
     pub sample_rate: f64,
-    /// Param: float -- the reference frequency for semitone index calculation (default: 440)
+    /// reference frequency for semitone index calculation
     pub reference_frequency: f64,
-    /// Param: float -- split frequency for low and high bands (default: 500)
+    /// split frequency for low and high bands
     pub band_split_frequency: f64,
-    /// Param: float -- maximum frequency that contributes to the HPCP in Hz (default: 5000)
+    /// maximum frequency that contributes to the HPCP in Hz
     pub max_frequency: f64,
-    /// Param: float -- minimum frequency that contributes to the HPCP in Hz (default: 40)
+    /// minimum frequency that contributes to the HPCP in Hz
     pub min_frequency: f64,
-    /// Param: int -- number of additional harmonics for frequency contribution (default: 0)
+    /// number of additional harmonics for frequency contribution
     pub harmonics: usize,
-    /// Param: str -- whether to use a squared cosine weighting funcion for determining frequency contribution (default: true)
+    /// whether to use a squared cosine weighting function for frequency contribution
     pub weighting: bool,
-    /// Param: float -- size in semitones of the window used for weighting
+    /// size in semitones of the window used for weighting
     pub weighting_window_size: f64,
-    /// Param: bool -- whether to normalize output vectors (default: true)
+    /// whether to normalize output vectors
     pub normalized: bool,
-    /// Param: bool -- whether to apply nonlinear post-processin on output vectors (default: false)
+    /// whether to apply nonlinear post-processing on output vectors
     pub nonlinear_post: bool,
+}
 
-    harmonic_peaks: Vec<(f64, f64)>,
+impl Default for HPCPParams {
+    fn default() -> Self {
+        HPCPParams {
+            size: 12,
+            sample_rate: 44100.0,
+            reference_frequency: 440.0,
+            band_split_frequency: 500.0,
+            max_frequency: 5000.0,
+            min_frequency: 40.0,
+            harmonics: 0,
+            weighting: true,
+            weighting_window_size: 1.0,
+            normalized: true,
+            nonlinear_post: false,
+        }
+    }
 }
 
-#[pymethods]
-impl HPCP {
-    #[new]
-    #[pyo3(signature = (
-        size=12,
-        sample_rate=44100.0,
-        reference_frequency=440.0,
-        band_split_frequency=500.0,
-        max_frequency=5000.0,
-        min_frequency=40.0,
-        harmonics=0,
-        weighting=true,
-        weighting_window_size=1.0,
-        normalized=true,
-        nonlinear_post=false,
-    ))]
-    fn pynew(
-        size: usize,
-        sample_rate: f64,
-        reference_frequency: f64,
-        band_split_frequency: f64,
-        max_frequency: f64,
-        min_frequency: f64,
-        harmonics: usize,
-        weighting: bool,
-        weighting_window_size: f64,
-        normalized: bool,
-        nonlinear_post: bool,
-    ) -> Self {
+impl ToJson for HPCPParams {
+    fn to_json(&self) -> Json {
+        Json::object([
+            ("size", self.size.to_json()),
+            ("sample_rate", self.sample_rate.to_json()),
+            ("reference_frequency", self.reference_frequency.to_json()),
+            ("band_split_frequency", self.band_split_frequency.to_json()),
+            ("max_frequency", self.max_frequency.to_json()),
+            ("min_frequency", self.min_frequency.to_json()),
+            ("harmonics", self.harmonics.to_json()),
+            ("weighting", self.weighting.to_json()),
+            (
+                "weighting_window_size",
+                self.weighting_window_size.to_json(),
+            ),
+            ("normalized", self.normalized.to_json()),
+            ("nonlinear_post", self.nonlinear_post.to_json()),
+        ])
+    }
+}
+
+impl FromJson for HPCPParams {
+    fn from_json(json: &Json) -> Result<Self> {
+        let mut params = Self::default();
+        for (key, value) in json.fields()? {
+            match key.as_str() {
+                "size" => params.size = json::checked(value, |size| check_size(*size))?,
+                "sample_rate" => params.sample_rate = FromJson::from_json(value)?,
+                "reference_frequency" => params.reference_frequency = FromJson::from_json(value)?,
+                "band_split_frequency" => params.band_split_frequency = FromJson::from_json(value)?,
+                "max_frequency" => params.max_frequency = FromJson::from_json(value)?,
+                "min_frequency" => params.min_frequency = FromJson::from_json(value)?,
+                "harmonics" => params.harmonics = FromJson::from_json(value)?,
+                "weighting" => params.weighting = FromJson::from_json(value)?,
+                "weighting_window_size" => {
+                    params.weighting_window_size = FromJson::from_json(value)?
+                }
+                "normalized" => params.normalized = FromJson::from_json(value)?,
+                "nonlinear_post" => params.nonlinear_post = FromJson::from_json(value)?,
+                _ => return Err(json::unknown_key(key)),
+            }
+        }
+        Ok(params)
+    }
+}
+
+/// Builder of an HPCP starting from default parameters, see HPCP::builder()
+#[derive(Debug, Clone, Default)]
+pub struct HPCPBuilder {
+    params: HPCPParams,
+}
+
+impl HPCPBuilder {
+    /// size of the output HPCP, one of {12, 24, 36}
+    pub fn size(mut self, size: usize) -> Self {
+        self.params.size = size;
+        self
+    }
+
+    /// sampling rate of the audio signal in Hz
+    pub fn sample_rate(mut self, sample_rate: f64) -> Self {
+        self.params.sample_rate = sample_rate;
+        self
+    }
+
+    /// reference frequency for semitone index calculation
+    pub fn reference_frequency(mut self, reference_frequency: f64) -> Self {
+        self.params.reference_frequency = reference_frequency;
+        self
+    }
+
+    /// split frequency for low and high bands
+    pub fn band_split_frequency(mut self, band_split_frequency: f64) -> Self {
+        self.params.band_split_frequency = band_split_frequency;
+        self
+    }
+
+    /// maximum frequency that contributes to the HPCP in Hz
+    pub fn max_frequency(mut self, max_frequency: f64) -> Self {
+        self.params.max_frequency = max_frequency;
+        self
+    }
+
+    /// minimum frequency that contributes to the HPCP in Hz
+    pub fn min_frequency(mut self, min_frequency: f64) -> Self {
+        self.params.min_frequency = min_frequency;
+        self
+    }
+
+    /// number of additional harmonics for frequency contribution
+    pub fn harmonics(mut self, harmonics: usize) -> Self {
+        self.params.harmonics = harmonics;
+        self
+    }
+
+    /// whether to use a squared cosine weighting function for frequency contribution
+    pub fn weighting(mut self, weighting: bool) -> Self {
+        self.params.weighting = weighting;
+        self
+    }
+
+    /// size in semitones of the window used for weighting
+    pub fn weighting_window_size(mut self, weighting_window_size: f64) -> Self {
+        self.params.weighting_window_size = weighting_window_size;
+        self
+    }
+
+    /// whether to normalize output vectors
+    pub fn normalized(mut self, normalized: bool) -> Self {
+        self.params.normalized = normalized;
+        self
+    }
+
+    /// whether to apply nonlinear post-processing on output vectors
+    pub fn nonlinear_post(mut self, nonlinear_post: bool) -> Self {
+        self.params.nonlinear_post = nonlinear_post;
+        self
+    }
+
+    /// create the HPCP with the parameters set so far
+    pub fn build(self) -> HPCP {
+        HPCP::with_params(self.params)
+    }
+}
+
+impl Algorithm for HPCP {
+    /// frequencies and magnitudes of the spectral peaks
+    type Input = (Vec<f64>, Vec<f64>);
+    /// harmonic pitch class profile
+    type Output = Vec<f64>;
+    type Params = HPCPParams;
+
+    fn with_params(params: HPCPParams) -> Self {
         HPCP {
             frequencies: Vec::new(),
             magnitudes: Vec::new(),
 
             hpcp_data: None,
 
-            size,
-            sample_rate,
-            reference_frequency,
-            band_split_frequency,
-            max_frequency,
-            min_frequency,
-            harmonics,
-            weighting,
-            weighting_window_size,
-            normalized,
-            nonlinear_post,
+            size: params.size,
+            sample_rate: params.sample_rate,
+            reference_frequency: params.reference_frequency,
+            band_split_frequency: params.band_split_frequency,
+            max_frequency: params.max_frequency,
+            min_frequency: params.min_frequency,
+            harmonics: params.harmonics,
+            weighting: params.weighting,
+            weighting_window_size: params.weighting_window_size,
+            normalized: params.normalized,
+            nonlinear_post: params.nonlinear_post,
 
             harmonic_peaks: Vec::new(),
         }
     }
 
-    /// Compute the Algorithm
-    ///
-    /// Inputs:
-    ///   - frequencies: list[float]
-    ///   - magnitudes: list[float]
-    ///
-    /// Outputs:
-    ///   - hpcp_data: list[float]
-    ///
-    /// See data descriptors for more details.
-    #[pyo3(name = "compute", signature = (frequencies=None, magnitudes=None))]
-    fn pycompute(
-        &mut self,
-        frequencies: Option<Vec<f64>>,
-        magnitudes: Option<Vec<f64>>,
-    ) -> Vec<f64> {
-        if let Some(arg) = frequencies {
-            self.frequencies = arg
-        }
-        if let Some(arg) = magnitudes {
-            self.magnitudes = arg
-        }
-
-        self.compute();
-
-        self.hpcp_data.as_ref().unwrap().clone()
+    fn compute(&mut self, (frequencies, magnitudes): Self::Input) -> Result<Vec<f64>> {
+        self.frequencies = frequencies;
+        self.magnitudes = magnitudes;
+        self.run();
+        Ok(self.hpcp_data.take().unwrap())
     }
 
-    fn __call__(&mut self) {
-        self.compute()
+    fn compute_into(&mut self, input: &Self::Input, output: &mut Vec<f64>) -> Result<()> {
+        // inputs are copied to buffers of this HPCP kept between calls
+        self.frequencies.clone_from(&input.0);
+        self.magnitudes.clone_from(&input.1);
+        self.run();
+        output.clone_from(self.hpcp_data.as_ref().unwrap());
+        Ok(())
     }
 }
 
-impl Algorithm for HPCP {
-    fn new() -> Self {
-        Self::pynew(
-            12, 44100.0, 440.0, 500.0, 5000.0, 40.0, 0, true, 1.0, true, false,
-        )
+/// check that the size of the output HPCP is supported
+fn check_size(size: usize) -> Result<()> {
+    check_choice("size", size, &[12, 24, 36])
+}
+
+impl HPCP {
+    /// start building an HPCP from default parameters
+    pub fn builder() -> HPCPBuilder {
+        HPCPBuilder::default()
     }
 
-    fn compute(&mut self) {
+    /// compute the profile of the spectral peaks and set the output
+    fn run(&mut self) {
         self.adjust_input();
         self.init_harmonic_peaks();
 
         let mut output_low = vec![0.0; self.size];
         let mut output_high = vec![0.0; self.size];
+        let mut weights = Vec::new();
 
         for i in 0..self.frequencies.len() {
             let freq = self.frequencies[i];
@@ -142,9 +463,9 @@ impl Algorithm for HPCP {
 
             if freq >= self.min_frequency && freq <= self.max_frequency {
                 if freq < self.band_split_frequency {
-                    self.add_contribution(freq, mag, &mut output_low);
+                    self.add_contribution(freq, mag, &mut output_low, &mut weights);
                 } else {
-                    self.add_contribution(freq, mag, &mut output_high);
+                    self.add_contribution(freq, mag, &mut output_high, &mut weights);
                 }
             }
         }
@@ -155,19 +476,17 @@ impl Algorithm for HPCP {
             Self::normalize(&mut output_high);
         }
 
-        for i in 0..self.size {
-            output_high[i] += output_low[i];
-        }
+        simd::add(&mut output_high, &output_low);
 
         // normalize the sum again
         if self.normalized {
             Self::normalize(&mut output_high);
 
             if self.nonlinear_post {
-                for i in 0..self.size {
-                    output_high[i] = (output_high[i] * std::f64::consts::PI / 2.0).sin().powi(2);
-                    if output_high[i] < 0.6 {
-                        output_high[i] *= (output_high[i] / 0.6).powi(2);
+                for x in output_high.iter_mut() {
+                    *x = (*x * std::f64::consts::PI / 2.0).sin().powi(2);
+                    if *x < 0.6 {
+                        *x *= (*x / 0.6).powi(2);
                     }
                 }
             }
@@ -176,9 +495,35 @@ impl Algorithm for HPCP {
         // Output
         self.hpcp_data = Some(output_high);
     }
-}
 
-impl HPCP {
+    /// compute profiles of many frames without holding the GIL
+    #[cfg(feature = "python")]
+    fn batch(
+        &mut self,
+        py: Python<'_>,
+        frequencies: Vec<Vec<f64>>,
+        magnitudes: Vec<Vec<f64>>,
+    ) -> PyResult<Vec<Vec<f64>>> {
+        if frequencies.len() != magnitudes.len() {
+            return Err(PyValueError::new_err(format!(
+                "got {} frames of frequencies and {} frames of magnitudes",
+                frequencies.len(),
+                magnitudes.len()
+            )));
+        }
+
+        Ok(py.allow_threads(|| {
+            let mut output = Vec::with_capacity(frequencies.len());
+            for (f, m) in frequencies.into_iter().zip(magnitudes) {
+                self.frequencies = f;
+                self.magnitudes = m;
+                self.run();
+                output.push(self.hpcp_data.as_ref().unwrap().clone());
+            }
+            output
+        }))
+    }
+
     fn maxf(a: f64, b: f64) -> f64 {
         if a > b {
             a
@@ -204,9 +549,7 @@ impl HPCP {
             return;
         }
 
-        for i in 0..target.len() {
-            target[i] /= m;
-        }
+        simd::div(target, m);
     }
 
     fn adjust_input(&mut self) {
@@ -264,37 +607,44 @@ impl HPCP {
         }
     }
 
-    fn add_contribution(&self, freq: f64, mag: f64, target: &mut [f64]) {
-        for harmonic in &self.harmonic_peaks {
-            let f = freq * (2.0_f64).powf(-harmonic.0 / 12.0);
-            let w = harmonic.1;
-
-            let size = self.size as f64;
-            let bin_f = size * (freq / self.reference_frequency).log2();
-
-            if self.weighting {
-                // add contributions with weight
-
-                let resolution = size / 12.0;
-                let left = (bin_f - resolution * self.weighting_window_size / 2.0).ceil() as i64;
-                let right =
-                    1 + (bin_f + resolution * self.weighting_window_size / 2.0).floor() as i64;
+    /// add the contribution of a peak and its harmonics to the profile
+    ///
+    /// The weighting window is the same for every harmonic, so its weights are computed once
+    /// for each peak and vectorized, in a buffer reused between peaks.
+    fn add_contribution(&self, freq: f64, mag: f64, target: &mut [f64], weights: &mut Vec<f64>) {
+        let size = self.size as f64;
+        let bin_f = size * (freq / self.reference_frequency).log2();
+
+        if self.weighting {
+            // add contributions with weight
+
+            let resolution = size / 12.0;
+            let width = resolution * self.weighting_window_size;
+            let left = (bin_f - width / 2.0).ceil() as i64;
+            let right = 1 + (bin_f + width / 2.0).floor() as i64;
+
+            // skip invalid
+            if right < left {
+                return;
+            }
 
-                // skip invalid
-                if right < left {
-                    continue;
-                }
+            // weights of all bins in the window, by their distance from the peak
+            weights.resize((right - left) as usize, 0.0);
+            simd::cos2(weights, (left as f64 - bin_f) / width, 1.0 / width);
 
-                // apply weight to all bins in the window
-                for i in left..right {
-                    let distance =
-                        (bin_f - i as f64).abs() / (resolution * self.weighting_window_size);
-                    let weight = (std::f64::consts::PI * distance).cos().powi(2);
+            for harmonic in &self.harmonic_peaks {
+                let w = harmonic.1;
+                for (i, weight) in (left..right).zip(weights.iter()) {
                     let bin = ((i + self.size as i64) as usize) % self.size;
                     target[bin] += weight * mag.powi(2) * w.powi(2);
                 }
-            } else {
-                // add contribution without weight
+            }
+        } else {
+            // add contribution without weight
+
+            for harmonic in &self.harmonic_peaks {
+                let f = freq * (2.0_f64).powf(-harmonic.0 / 12.0);
+                let w = harmonic.1;
 
                 // skip invalid
                 if f <= 0.0 {
@@ -325,18 +675,12 @@ mod tests {
             [0.25, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0],
         ];
 
-        let mut hpcp = HPCP::new();
-        hpcp.band_split_frequency = 0.0;
+        let mut hpcp = HPCP::builder().band_split_frequency(0.0).build();
 
         for i in 0..input.len() {
-            hpcp.frequencies.clone_from(&input[i].0);
-            hpcp.magnitudes.clone_from(&input[i].1);
-            hpcp.compute();
             let r: Vec<f64> = hpcp
-                .hpcp_data
-                .as_ref()
+                .compute(input[i].clone())
                 .unwrap()
-                .clone()
                 // round for the poor with precision to the 2nd decimal place
                 .iter()
                 .map(|x| (*x * 100.0).round() / 100.0)
@@ -0,0 +1,271 @@
+use super::synth::{Envelope, Gate, Stage, Waveform};
+use crate::midi::Message;
+use crate::notes::Tuning;
+
+/// Polyphonic instrument played in real time with MIDI messages
+///
+/// Every note on takes a free voice, or the one started the longest time ago when all are busy,
+/// and every note off starts the release of its envelope.
+/// The sustain pedal (controller 64) holds released notes until it's lifted,
+/// the volume (controller 7) scales all voices, all notes off (controller 123) releases them
+/// and all sound off (controller 120) silences them at once.
+///
+/// Nothing is locked or allocated after it's created, so .render() can be called from an audio
+/// callback, with messages received from a MIDI input port handled in between blocks,
+/// see [crate::midi::input] with the "midi-input" feature.
+pub struct Instrument {
+    sample_rate: f64,
+    pub(super) waveform: Waveform,
+    pub(super) envelope: Envelope,
+    channel: Option<u8>,
+    tuning: Tuning,
+    voices: Vec<Voice>,
+    volume: f64,
+    pedal: bool,
+    notes: u64,
+}
+
+/// a single note sounding in the Instrument
+#[derive(Clone, Copy)]
+struct Voice {
+    pitch: u8,
+    /// phase increment per sample
+    increment: f64,
+    gain: f64,
+    phase: f64,
+    gate: Gate,
+    /// key released while the sustain pedal is down
+    held: bool,
+    /// number of the note, to find the oldest voice
+    order: u64,
+}
+
+impl Voice {
+    const IDLE: Voice = Voice {
+        pitch: 0,
+        increment: 0.0,
+        gain: 0.0,
+        phase: 0.0,
+        gate: Gate::IDLE,
+        held: false,
+        order: 0,
+    };
+
+    /// start the release from the current level
+    fn release(&mut self) {
+        self.held = false;
+        self.gate.release();
+    }
+}
+
+impl Instrument {
+    /// create an Instrument with 16 sine voices and a short envelope, listening on all channels
+    pub fn new(sample_rate: f64) -> Self {
+        Instrument {
+            sample_rate,
+            waveform: Waveform::Sin,
+            envelope: Envelope::adsr(0.005, 0.0, 1.0, 0.05),
+            channel: None,
+            tuning: Tuning::default(),
+            voices: vec![Voice::IDLE; 16],
+            volume: 1.0,
+            pedal: false,
+            notes: 0,
+        }
+    }
+
+    /// set the number of notes that can sound at once
+    pub fn voices(mut self, voices: usize) -> Self {
+        self.voices = vec![Voice::IDLE; std::cmp::max(voices, 1)];
+        self
+    }
+
+    /// set the waveform of all voices
+    pub fn waveform(mut self, waveform: Waveform) -> Self {
+        self.waveform = waveform;
+        self
+    }
+
+    /// set the envelope of all notes, the sustain level is kept until a note off
+    pub fn envelope(mut self, envelope: Envelope) -> Self {
+        self.envelope = envelope;
+        self
+    }
+
+    /// listen only to messages of a channel from 0 to 15, or to all of them with None
+    pub fn channel(mut self, channel: Option<u8>) -> Self {
+        self.channel = channel;
+        self
+    }
+
+    /// set the tuning of notes, A4 is at 440 Hz by default
+    pub fn tuning(mut self, tuning: Tuning) -> Self {
+        self.tuning = tuning;
+        self
+    }
+
+    /// number of voices currently sounding, including released notes that are fading out
+    pub fn active(&self) -> usize {
+        self.voices
+            .iter()
+            .filter(|v| v.gate.stage != Stage::Idle)
+            .count()
+    }
+
+    /// play a MIDI message
+    pub fn handle(&mut self, message: Message) {
+        let channel = match message {
+            Message::NoteOn { channel, .. }
+            | Message::NoteOff { channel, .. }
+            | Message::ControlChange { channel, .. } => channel,
+        };
+        if self.channel.is_some_and(|c| c != channel) {
+            return;
+        }
+
+        match message {
+            Message::NoteOn {
+                pitch, velocity, ..
+            } => {
+                // retrigger the same pitch, or take a free voice, or steal the oldest one
+                let voices = &mut self.voices;
+                let i = voices
+                    .iter()
+                    .position(|v| v.gate.stage != Stage::Idle && v.pitch == pitch)
+                    .or_else(|| voices.iter().position(|v| v.gate.stage == Stage::Idle))
+                    .unwrap_or_else(|| (0..voices.len()).min_by_key(|i| voices[*i].order).unwrap());
+                let frequency = self.tuning.frequency(pitch as f64);
+                let voice = &mut voices[i];
+                if voice.gate.stage == Stage::Idle {
+                    voice.phase = 0.0;
+                }
+                voice.pitch = pitch;
+                voice.increment = frequency / self.sample_rate;
+                voice.gain = velocity as f64 / 127.0;
+                voice.gate.enter(Stage::Attack);
+                voice.held = false;
+                voice.order = self.notes;
+                self.notes += 1;
+            }
+            Message::NoteOff { pitch, .. } => {
+                for voice in self.voices.iter_mut() {
+                    let sounding = !matches!(voice.gate.stage, Stage::Idle | Stage::Release);
+                    if sounding && voice.pitch == pitch && !voice.held {
+                        if self.pedal {
+                            voice.held = true;
+                        } else {
+                            voice.release();
+                        }
+                    }
+                }
+            }
+            Message::ControlChange {
+                controller, value, ..
+            } => match controller {
+                7 => self.volume = value as f64 / 127.0,
+                64 => {
+                    self.pedal = value >= 64;
+                    if !self.pedal {
+                        for voice in self.voices.iter_mut().filter(|v| v.held) {
+                            voice.release();
+                        }
+                    }
+                }
+                120 => self.voices.fill(Voice::IDLE),
+                123 => {
+                    for voice in self.voices.iter_mut() {
+                        if !matches!(voice.gate.stage, Stage::Idle | Stage::Release) {
+                            voice.release();
+                        }
+                    }
+                }
+                _ => {}
+            },
+        }
+    }
+
+    /// render the next block of samples of all sounding voices
+    pub fn render(&mut self, block: &mut [f64]) {
+        block.fill(0.0);
+        for voice in self.voices.iter_mut() {
+            if voice.gate.stage == Stage::Idle {
+                continue;
+            }
+            let gain = voice.gain * self.volume;
+            for y in block.iter_mut() {
+                let level = voice.gate.advance(&self.envelope, self.sample_rate);
+                *y += gain * level * self.waveform.at_phase(voice.phase);
+                voice.phase += voice.increment;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Instrument;
+    use crate::algs::synth::{Curve, Envelope};
+    use crate::midi::Message;
+
+    #[test]
+    fn instrument() {
+        let on = |pitch| Message::NoteOn {
+            channel: 0,
+            pitch,
+            velocity: 127,
+        };
+        let off = |pitch| Message::NoteOff { channel: 0, pitch };
+        let pedal = |value| Message::ControlChange {
+            channel: 0,
+            controller: 64,
+            value,
+        };
+
+        let mut instrument = Instrument::new(8000.0).voices(2);
+        let mut block = vec![0.0; 800];
+        instrument.handle(on(69));
+        instrument.render(&mut block);
+        // 44 periods of A4 in 0.1 seconds, rising through zero between each two of them
+        let crossings = block
+            .windows(2)
+            .filter(|x| x[0] < 0.0 && x[1] >= 0.0)
+            .count();
+        assert_eq!(crossings, 43);
+        assert!(block.iter().all(|x| x.abs() <= 1.0));
+
+        // the oldest note is stolen for the third one
+        instrument.handle(on(72));
+        instrument.handle(on(76));
+        assert_eq!(instrument.active(), 2);
+
+        // released notes are held by the pedal and fade out once it's lifted
+        instrument.handle(pedal(127));
+        instrument.handle(off(72));
+        instrument.handle(off(76));
+        instrument.render(&mut block);
+        assert_eq!(instrument.active(), 2);
+        instrument.handle(pedal(0));
+        instrument.render(&mut block);
+        assert_eq!(instrument.active(), 0);
+        assert_eq!(block[799], 0.0);
+
+        // curved segments end at the same levels
+        let envelope = Envelope::adsr(0.01, 0.01, 0.5, 0.01).curves(
+            Curve::Exponential,
+            Curve::Logarithmic,
+            Curve::Exponential,
+        );
+        let mut instrument = Instrument::new(8000.0).envelope(envelope);
+        instrument.handle(on(69));
+        instrument.render(&mut block);
+        assert!(block[700..].iter().all(|x| x.abs() <= 0.5 + 1e-12));
+        instrument.handle(off(69));
+        instrument.render(&mut block);
+        assert_eq!((instrument.active(), block[799]), (0, 0.0));
+
+        // messages of other channels are ignored
+        let mut instrument = Instrument::new(8000.0).channel(Some(1));
+        instrument.handle(on(60));
+        assert_eq!(instrument.active(), 0);
+    }
+}
@@ -1,23 +1,119 @@
-use pyo3::{pyclass, pymethods};
+#[cfg(feature = "python")]
+use pyo3::exceptions::PyValueError;
+#[cfg(feature = "python")]
+use pyo3::{pyclass, pymethods, IntoPy, PyAny, PyCell, PyObject, PyRef, PyResult, Python};
+#[cfg(feature = "python")]
+use symphonia::core::conv::ConvertibleSample;
 
-use super::Algorithm;
+use super::{check_choice, Algorithm};
+use crate::json::{self, FromJson, Json, ToJson};
 use crate::mixer::{Loader, Writer};
+#[cfg(feature = "python")]
+use crate::mixer::{Packets, Stream};
+#[cfg(feature = "python")]
+use crate::pymod::array::{Array, Pcm16};
+#[cfg(feature = "python")]
+use crate::pymod::state::{self, pymethods_with_params};
+#[cfg(feature = "python")]
+use crate::sample::Sample;
+use crate::Result;
 
-#[pyclass(get_all)]
-pub struct MonoLoader {
-    /// Input: str -- path to a file that will be loaded
-    #[pyo3(set)]
-    pub file: String,
-    /// Output: Optional[list[int]] -- raw 16-bit pcm values of loaded data
-    pub pcm_data: Option<Vec<u16>>,
-    /// Output: int -- sample rate
-    pub sample_rate: usize,
+python_class! {
+    /// Load a track from a file and mix it down to mono 16-bit pcm
+    #[pyclass(module = "muslib", get_all)]
+    pub struct MonoLoader {
+        /// Input: str -- path to a file that will be loaded
+        #[pyo3(set)]
+        pub file: String,
+        /// Output: Optional[list[int]] -- signed 16-bit pcm values of loaded data, silent at 0
+        pub pcm_data: Option<Vec<i16>>,
+        /// Output: int -- sample rate
+        pub sample_rate: usize,
+    }
+}
+
+#[cfg(feature = "python")]
+pymethods_with_params! {
+    impl MonoLoader {
+        #[new]
+        fn pynew() -> Self {
+            Self::with_params(())
+        }
+
+        /// Compute the Algorithm
+        ///
+        /// Inputs:
+        ///   - file: str
+        ///   - progress: Optional[Callable[[float], Any]]
+        ///
+        /// Outputs:
+        ///   - pcm_data: list[int]
+        ///   - sample_rate: int
+        ///
+        /// The progress callable is called with the fraction of the file loaded so far.
+        /// Loading is cancelled when it raises an exception.
+        ///
+        /// See data descriptors for more details.
+        #[pyo3(name = "compute", signature = (file=None, progress=None))]
+        fn pycompute(
+            &mut self,
+            py: Python<'_>,
+            file: Option<String>,
+            progress: Option<&PyAny>,
+        ) -> PyResult<(Array<i16>, usize)> {
+            if let Some(arg) = file {
+                self.file = arg
+            }
+
+            match progress {
+                Some(progress) => {
+                    let mut packets = Loader::<i16>::new()
+                        .file(self.file.clone().into())
+                        .packets()?;
+                    self.pcm_data = Some(decode(py, &mut packets, progress)?);
+                    self.sample_rate = packets.sample_rate().unwrap_or(0) as usize;
+                }
+                None => py.allow_threads(|| self.load())?,
+            }
+
+            Ok((
+                self.pcm_data.as_ref().unwrap().clone().into(),
+                self.sample_rate,
+            ))
+        }
+
+        fn __call__(&mut self, py: Python<'_>) -> PyResult<()> {
+            Ok(py.allow_threads(|| self.load())?)
+        }
+    }
+
+    params = [];
+    async = compute_async;
+
+    fn reset(&mut self) {
+        self.pcm_data = None;
+        self.sample_rate = 0;
+    }
 }
 
-#[pymethods]
 impl MonoLoader {
-    #[new]
-    fn pynew() -> Self {
+    fn load(&mut self) -> Result<()> {
+        let mut loader = Loader::<i16>::new();
+        loader.file(self.file.clone().into()).load()?;
+        self.pcm_data = Some(loader.data());
+        self.sample_rate = loader.sample_rate().unwrap_or(0) as usize;
+        Ok(())
+    }
+}
+
+impl Algorithm for MonoLoader {
+    /// path to a file that will be loaded
+    type Input = String;
+    /// signed 16-bit pcm values of loaded data, silent at 0, and the sample rate
+    type Output = (Vec<i16>, usize);
+    type Params = ();
+
+    fn with_params(_: ()) -> Self {
         MonoLoader {
             file: "".into(),
             pcm_data: None,
@@ -25,109 +121,522 @@ impl MonoLoader {
         }
     }
 
-    /// Compute the Algorithm
-    ///
-    /// Inputs:
-    ///   - file: str
-    ///
-    /// Outputs:
-    ///   - pcm_data: list[int]
-    ///   - sample_rate: int
-    ///
-    /// See data descriptors for more details.
-    #[pyo3(name = "compute", signature = (file=None))]
-    fn pycompute(&mut self, file: Option<String>) -> (Vec<u16>, usize) {
-        if let Some(arg) = file {
-            self.file = arg
+    fn compute(&mut self, file: String) -> Result<Self::Output> {
+        self.file = file;
+        self.load()?;
+        Ok((self.pcm_data.take().unwrap(), self.sample_rate))
+    }
+}
+
+python_class! {
+    /// Load a track from a file and mix it down to mono floating point samples
+    #[pyclass(module = "muslib", get_all)]
+    pub struct MonoFloatLoader {
+        /// Input: str -- path to a file that will be loaded
+        #[pyo3(set)]
+        pub file: String,
+        /// Output: Optional[list[float]] -- samples of loaded data in range [-1, 1]
+        pub audio: Option<Vec<f64>>,
+        /// Output: int -- sample rate
+        pub sample_rate: usize,
+
+        /// Param: str -- precision of returned samples, one of {float32, float64} (default: float32)
+        pub dtype: String,
+    }
+}
+
+#[cfg(feature = "python")]
+pymethods_with_params! {
+    impl MonoFloatLoader {
+        #[new]
+        #[pyo3(signature = (
+            dtype="float32",
+        ))]
+        fn pynew(dtype: &str) -> PyResult<Self> {
+            check_dtype(dtype)?;
+            Ok(Self::with_params(MonoFloatLoaderParams {
+                dtype: dtype.into(),
+            }))
+        }
+
+        #[setter(dtype)]
+        fn set_dtype(&mut self, dtype: &str) -> PyResult<()> {
+            check_dtype(dtype)?;
+            self.dtype = dtype.into();
+            Ok(())
+        }
+
+        /// Compute the Algorithm
+        ///
+        /// Inputs:
+        ///   - file: str
+        ///   - progress: Optional[Callable[[float], Any]]
+        ///
+        /// Outputs:
+        ///   - audio: list[float]
+        ///   - sample_rate: int
+        ///
+        /// The progress callable is called with the fraction of the file loaded so far.
+        /// Loading is cancelled when it raises an exception.
+        ///
+        /// See data descriptors for more details.
+        #[pyo3(name = "compute", signature = (file=None, progress=None))]
+        fn pycompute(
+            &mut self,
+            py: Python<'_>,
+            file: Option<String>,
+            progress: Option<&PyAny>,
+        ) -> PyResult<(PyObject, usize)> {
+            if let Some(arg) = file {
+                self.file = arg
+            }
+
+            check_dtype(&self.dtype)?;
+
+            match progress {
+                Some(progress) => {
+                    let mut packets = Loader::<f64>::new()
+                        .file(self.file.clone().into())
+                        .packets()?;
+                    self.audio = Some(decode(py, &mut packets, progress)?);
+                    self.sample_rate = packets.sample_rate().unwrap_or(0) as usize;
+                }
+                None => py.allow_threads(|| self.load())?,
+            }
+
+            let audio = self.audio.as_ref().unwrap().clone();
+            let audio = if self.dtype == "float32" {
+                Array(audio.iter().map(|x| *x as f32).collect()).into_py(py)
+            } else {
+                Array(audio).into_py(py)
+            };
+            Ok((audio, self.sample_rate))
+        }
+
+        fn __call__(&mut self, py: Python<'_>) -> PyResult<()> {
+            Ok(py.allow_threads(|| self.load())?)
         }
+    }
+
+    params = ["dtype"];
+    async = compute_async;
 
-        self.compute();
+    fn reset(&mut self) {
+        self.audio = None;
+        self.sample_rate = 0;
+    }
+}
 
-        (self.pcm_data.as_ref().unwrap().clone(), self.sample_rate)
+impl MonoFloatLoader {
+    /// start building a MonoFloatLoader from default parameters
+    pub fn builder() -> MonoFloatLoaderBuilder {
+        MonoFloatLoaderBuilder::default()
     }
 
-    fn __call__(&mut self) {
-        self.compute()
+    fn load(&mut self) -> Result<()> {
+        let mut loader = Loader::<f64>::new();
+        loader.file(self.file.clone().into()).load()?;
+        self.audio = Some(loader.data());
+        self.sample_rate = loader.sample_rate().unwrap_or(0) as usize;
+        Ok(())
     }
 }
 
-impl Algorithm for MonoLoader {
-    fn new() -> Self {
-        Self::pynew()
+/// check that the precision of samples is known
+fn check_dtype(dtype: &str) -> Result<()> {
+    check_choice("dtype", dtype, &["float32", "float64"])
+}
+
+/// Parameters of the MonoFloatLoader, see its data descriptors for details
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonoFloatLoaderParams {
+    /// precision of samples returned to Python, one of {float32, float64}
+    pub dtype: String,
+}
+
+impl Default for MonoFloatLoaderParams {
+    fn default() -> Self {
+        MonoFloatLoaderParams {
+            dtype: "float32".into(),
+        }
+    }
+}
+
+impl ToJson for MonoFloatLoaderParams {
+    fn to_json(&self) -> Json {
+        Json::object([("dtype", self.dtype.to_json())])
     }
+}
 
-    fn compute(&mut self) {
-        let mut loader = Loader::<u16>::new();
-        loader
+impl FromJson for MonoFloatLoaderParams {
+    fn from_json(json: &Json) -> Result<Self> {
+        let mut params = Self::default();
+        for (key, value) in json.fields()? {
+            match key.as_str() {
+                "dtype" => params.dtype = json::checked(value, |d: &String| check_dtype(d))?,
+                _ => return Err(json::unknown_key(key)),
+            }
+        }
+        Ok(params)
+    }
+}
+
+/// Builder of a MonoFloatLoader starting from default parameters, see MonoFloatLoader::builder()
+#[derive(Debug, Clone, Default)]
+pub struct MonoFloatLoaderBuilder {
+    params: MonoFloatLoaderParams,
+}
+
+impl MonoFloatLoaderBuilder {
+    /// precision of samples returned to Python, one of {float32, float64}
+    pub fn dtype(mut self, dtype: &str) -> Self {
+        self.params.dtype = dtype.into();
+        self
+    }
+
+    /// create the MonoFloatLoader with the parameters set so far
+    pub fn build(self) -> MonoFloatLoader {
+        MonoFloatLoader::with_params(self.params)
+    }
+}
+
+impl Algorithm for MonoFloatLoader {
+    /// path to a file that will be loaded
+    type Input = String;
+    /// samples of loaded data in range [-1, 1] and the sample rate
+    type Output = (Vec<f64>, usize);
+    type Params = MonoFloatLoaderParams;
+
+    fn with_params(params: MonoFloatLoaderParams) -> Self {
+        MonoFloatLoader {
+            file: "".into(),
+            audio: None,
+            sample_rate: 0,
+            dtype: params.dtype,
+        }
+    }
+
+    fn compute(&mut self, file: String) -> Result<Self::Output> {
+        self.file = file;
+        self.load()?;
+        Ok((self.audio.take().unwrap(), self.sample_rate))
+    }
+}
+
+/// decode all packets, calling a Python callable with the progress after each step of 1%
+///
+/// An exception raised by the callable, or a signal like KeyboardInterrupt, cancels decoding.
+#[cfg(feature = "python")]
+fn decode<T: ConvertibleSample + Sample>(
+    py: Python<'_>,
+    packets: &mut Packets<T>,
+    progress: &PyAny,
+) -> PyResult<Vec<T>> {
+    let mut data = Vec::new();
+    let mut reported = 0.0;
+    while let Some(block) = py.allow_threads(|| packets.next()) {
+        data.append(&mut block?);
+        py.check_signals()?;
+        let fraction = packets.progress().unwrap_or(0.0);
+        if fraction - reported >= 0.01 {
+            progress.call1((fraction,))?;
+            reported = fraction;
+        }
+    }
+    if reported < 1.0 {
+        progress.call1((1.0,))?;
+    }
+    Ok(data)
+}
+
+python_class! {
+    /// Write mono 16-bit pcm data to a WAV file
+    #[pyclass(module = "muslib", get_all)]
+    pub struct MonoWriter {
+        /// Input: str -- path to a file that will be written
+        #[pyo3(set)]
+        pub file: String,
+        /// Input: list[int] -- signed 16-bit pcm values of data to be written, silent at 0,
+        /// like the output of MonoLoader and the Synthesizer, compute() also takes
+        /// unsigned 16-bit buffers as offset binary pcm and bytes of signed 16-bit little-endian samples
+        pub pcm_data: Vec<i16>,
+        /// Param: int -- sample rate
+        #[pyo3(set)]
+        pub sample_rate: usize,
+    }
+}
+
+#[cfg(feature = "python")]
+pymethods_with_params! {
+    impl MonoWriter {
+        #[new]
+        #[pyo3(signature = (
+            sample_rate=44100,
+        ))]
+        fn pynew(sample_rate: usize) -> Self {
+            Self::with_params(MonoWriterParams { sample_rate })
+        }
+
+        /// Compute the Algorithm
+        ///
+        /// Inputs:
+        ///   - file: str
+        ///   - pcm_data: list[int]
+        ///
+        /// See data descriptors for more details.
+        #[pyo3(name = "compute", signature = (file=None, pcm_data=None))]
+        fn pycompute(
+            &mut self,
+            py: Python<'_>,
+            file: Option<String>,
+            pcm_data: Option<Pcm16>,
+        ) -> PyResult<()> {
+            if let Some(arg) = file {
+                self.file = arg
+            }
+            if let Some(arg) = pcm_data {
+                self.pcm_data = arg.0
+            }
+
+            Ok(py.allow_threads(|| self.write())?)
+        }
+
+        fn __call__(&mut self, py: Python<'_>) -> PyResult<()> {
+            Ok(py.allow_threads(|| self.write())?)
+        }
+
+        /// Open a file to write pcm data in consecutive blocks
+        ///
+        /// Use the returned MonoWriterStream as a context manager,
+        /// so the file is finalized at the end of the with block:
+        ///
+        /// ```python
+        /// with MonoWriter.open("out.wav", 44100) as w:
+        ///     w.write(chunk)
+        /// ```
+        #[staticmethod]
+        #[pyo3(signature = (file, sample_rate=44100))]
+        fn open(py: Python<'_>, file: String, sample_rate: usize) -> PyResult<MonoWriterStream> {
+            let stream = py.allow_threads(|| {
+                Writer::new()
+                    .sample_rate(sample_rate as u32)
+                    .file(file.clone().into())
+                    .open()
+            })?;
+
+            Ok(MonoWriterStream {
+                file,
+                sample_rate,
+                samples: 0,
+                stream: Some(stream),
+            })
+        }
+    }
+
+    params = ["sample_rate"];
+    async = compute_async;
+}
+
+impl MonoWriter {
+    /// start building a MonoWriter from default parameters
+    pub fn builder() -> MonoWriterBuilder {
+        MonoWriterBuilder::default()
+    }
+
+    fn write(&self) -> Result<()> {
+        Writer::new()
+            .sample_rate(self.sample_rate as u32)
             .file(self.file.clone().into())
-            .load()
-            .expect("Load failed");
-        self.pcm_data = Some(loader.data());
-        self.sample_rate = loader.sample_rate().unwrap() as usize;
+            .write(&self.pcm_data)
     }
 }
 
-#[pyclass(get_all)]
-pub struct MonoWriter {
-    /// Input: str -- path to a file that will be written
-    #[pyo3(set)]
-    pub file: String,
-    /// Input: list[int] -- raw 16-bit pcm values of data to be written
-    pub pcm_data: Vec<u16>,
-    /// Param: int -- sample rate
-    #[pyo3(set)]
+/// Parameters of the MonoWriter, see its data descriptors for details
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonoWriterParams {
+    /// sample rate
     pub sample_rate: usize,
 }
 
-#[pymethods]
-impl MonoWriter {
-    #[new]
-    #[pyo3(signature = (
-        sample_rate=44100,
-    ))]
-    fn pynew(sample_rate: usize) -> Self {
+impl Default for MonoWriterParams {
+    fn default() -> Self {
+        MonoWriterParams { sample_rate: 44100 }
+    }
+}
+
+impl ToJson for MonoWriterParams {
+    fn to_json(&self) -> Json {
+        Json::object([("sample_rate", self.sample_rate.to_json())])
+    }
+}
+
+impl FromJson for MonoWriterParams {
+    fn from_json(json: &Json) -> Result<Self> {
+        let mut params = Self::default();
+        for (key, value) in json.fields()? {
+            match key.as_str() {
+                "sample_rate" => params.sample_rate = FromJson::from_json(value)?,
+                _ => return Err(json::unknown_key(key)),
+            }
+        }
+        Ok(params)
+    }
+}
+
+/// Builder of a MonoWriter starting from default parameters, see MonoWriter::builder()
+#[derive(Debug, Clone, Default)]
+pub struct MonoWriterBuilder {
+    params: MonoWriterParams,
+}
+
+impl MonoWriterBuilder {
+    /// sample rate
+    pub fn sample_rate(mut self, sample_rate: usize) -> Self {
+        self.params.sample_rate = sample_rate;
+        self
+    }
+
+    /// create the MonoWriter with the parameters set so far
+    pub fn build(self) -> MonoWriter {
+        MonoWriter::with_params(self.params)
+    }
+}
+
+impl Algorithm for MonoWriter {
+    /// path to a file that will be written and signed 16-bit pcm values of data to be written
+    type Input = (String, Vec<i16>);
+    type Output = ();
+    type Params = MonoWriterParams;
+
+    fn with_params(params: MonoWriterParams) -> Self {
         MonoWriter {
             file: "".into(),
             pcm_data: Vec::new(),
-            sample_rate,
+            sample_rate: params.sample_rate,
         }
     }
 
-    /// Compute the Algorithm
-    ///
-    /// Inputs:
-    ///   - file: str
-    ///   - pcm_data: list[int]
+    fn compute(&mut self, (file, pcm_data): Self::Input) -> Result<()> {
+        self.file = file;
+        self.pcm_data = pcm_data;
+        self.write()
+    }
+}
+
+/// Stream of mono 16-bit pcm data written to a WAV file block by block
+///
+/// Created with MonoWriter.open(), the file is finalized with .close()
+/// or at the end of a with block.
+#[cfg(feature = "python")]
+#[pyclass(module = "muslib")]
+pub struct MonoWriterStream {
+    /// Param: str -- path to the file being written
+    #[pyo3(get)]
+    pub file: String,
+    /// Param: int -- sample rate
+    #[pyo3(get)]
+    pub sample_rate: usize,
+    /// Output: int -- number of samples written so far
+    #[pyo3(get)]
+    pub samples: usize,
+
+    stream: Option<Stream>,
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl MonoWriterStream {
+    /// Append a block of pcm data at the end of the file
     ///
-    /// See data descriptors for more details.
-    #[pyo3(name = "compute", signature = (file=None, pcm_data=None))]
-    fn pycompute(&mut self, file: Option<String>, pcm_data: Option<Vec<u16>>) {
-        if let Some(arg) = file {
-            self.file = arg
-        }
-        if let Some(arg) = pcm_data {
-            self.pcm_data = arg
+    /// Takes the same kinds of pcm data as MonoWriter.compute().
+    fn write(&mut self, py: Python<'_>, pcm_data: Pcm16) -> PyResult<()> {
+        let stream = self
+            .stream
+            .as_mut()
+            .ok_or_else(|| PyValueError::new_err("write to a closed file"))?;
+        py.allow_threads(|| stream.write(&pcm_data.0))?;
+        self.samples = stream.len() as usize;
+        Ok(())
+    }
+
+    /// Update the header and flush the file, so it can be read before it's closed
+    fn flush(&mut self, py: Python<'_>) -> PyResult<()> {
+        let stream = self
+            .stream
+            .as_mut()
+            .ok_or_else(|| PyValueError::new_err("flush a closed file"))?;
+        Ok(py.allow_threads(|| stream.flush())?)
+    }
+
+    /// Finalize the file, further calls have no effect
+    fn close(&mut self, py: Python<'_>) -> PyResult<()> {
+        match self.stream.take() {
+            Some(stream) => Ok(py.allow_threads(|| stream.finalize())?),
+            None => Ok(()),
         }
+    }
 
-        self.compute();
+    /// Output: bool -- True once the file was finalized
+    #[getter]
+    fn closed(&self) -> bool {
+        self.stream.is_none()
     }
 
-    fn __call__(&mut self) {
-        self.compute()
+    fn __enter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
     }
-}
 
-impl Algorithm for MonoWriter {
-    fn new() -> Self {
-        Self::pynew(44100)
+    fn __exit__(
+        &mut self,
+        py: Python<'_>,
+        _exc_type: &PyAny,
+        _exc_value: &PyAny,
+        _traceback: &PyAny,
+    ) -> PyResult<()> {
+        self.close(py)
     }
 
-    fn compute(&mut self) {
-        let mut writer = Writer::new();
-        writer
-            .sample_rate(self.sample_rate as u32)
-            .file(self.file.clone().into())
-            .write(&self.pcm_data)
-            .unwrap();
+    fn __repr__(slf: &PyCell<Self>) -> PyResult<String> {
+        state::repr(slf, &["file", "sample_rate"])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MonoLoader, MonoWriter};
+    use crate::algs::synth::Synthesizer;
+    use crate::algs::Algorithm;
+    use crate::mixer::Loader;
+
+    #[test]
+    fn signed_pcm() {
+        let path = std::env::temp_dir().join("muslib-io-signed.wav");
+        let file = path.to_string_lossy().into_owned();
+        let mut writer = MonoWriter::builder().sample_rate(8000).build();
+        let mut loader = MonoLoader::new();
+
+        // silent and all-positive output of the synthesizer has no negative values to tell its format
+        let mut synth = Synthesizer::builder()
+            .sample_rate(8000)
+            .waveform("sqr")
+            .fade(0.0)
+            .build();
+        let silence = synth.compute((vec![0.0], vec![0.01])).unwrap();
+        let positive = synth.compute((vec![1.0], vec![0.1])).unwrap();
+        let sine = synth.compute((vec![440.0], vec![0.1])).unwrap();
+        assert!(silence.iter().all(|x| *x == 0));
+        assert!(positive.iter().all(|x| *x >= 0) && positive.iter().any(|x| *x > 0));
+
+        // the output of the synthesizer is written as it is and loaded back the same
+        for pcm in [silence, positive, sine] {
+            writer.compute((file.clone(), pcm.clone())).unwrap();
+            let (data, sample_rate) = loader.compute(file.clone()).unwrap();
+            assert_eq!(data, pcm);
+            assert_eq!(sample_rate, 8000);
+            let mut raw = Loader::<i16>::new();
+            raw.file(path.clone()).load().unwrap();
+            assert_eq!(raw.data(), pcm);
+        }
+        std::fs::remove_file(path).unwrap();
     }
 }
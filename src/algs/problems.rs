@@ -0,0 +1,1633 @@
+#[cfg(feature = "python")]
+use pyo3::Python;
+
+use super::restore::{self, Regions};
+use super::Algorithm;
+use crate::json::{self, FromJson, Json, ToJson};
+#[cfg(feature = "python")]
+use crate::pymod::array::{Array, Array2, ArrayView};
+#[cfg(feature = "python")]
+use crate::pymod::state::pymethods_with_params;
+use crate::Result;
+
+/// events in a signal given as pairs of start and end times in seconds
+pub type Events = Vec<(f64, f64)>;
+
+/// mains frequencies checked for hum in Hz
+const MAINS: [f64; 2] = [50.0, 60.0];
+
+/// oversampling factor of true peak measurements
+const OVERSAMPLING: usize = 4;
+
+/// delay of the oversampled signal in samples of the output rate
+const OVERSAMPLING_DELAY: f64 = 23.5;
+
+/// polyphase interpolation filter for true peak measurements from ITU-R BS.1770, one row per phase
+#[rustfmt::skip]
+const TRUE_PEAK_FILTER: [[f64; 12]; OVERSAMPLING] = [
+    [
+        0.0017089843750, 0.0109863281250, -0.0196533203125, 0.0332031250000,
+        -0.0594482421875, 0.1373291015625, 0.9721679687500, -0.1022949218750,
+        0.0476074218750, -0.0266113281250, 0.0148925781250, -0.0083007812500,
+    ],
+    [
+        -0.0291748046875, 0.0292968750000, -0.0517578125000, 0.0891113281250,
+        -0.1665039062500, 0.4650878906250, 0.7797851562500, -0.2003173828125,
+        0.1015625000000, -0.0582275390625, 0.0330810546875, -0.0189208984375,
+    ],
+    [
+        -0.0189208984375, 0.0330810546875, -0.0582275390625, 0.1015625000000,
+        -0.2003173828125, 0.7797851562500, 0.4650878906250, -0.1665039062500,
+        0.0891113281250, -0.0517578125000, 0.0292968750000, -0.0291748046875,
+    ],
+    [
+        -0.0083007812500, 0.0148925781250, -0.0266113281250, 0.0476074218750,
+        -0.1022949218750, 0.9721679687500, 0.1373291015625, -0.0594482421875,
+        0.0332031250000, -0.0196533203125, 0.0109863281250, 0.0017089843750,
+    ],
+];
+
+python_class! {
+    /// Detect gaps, i.e. dropouts of silence in the middle of a signal
+    ///
+    /// Silence at the start and at the end of the signal is not a gap.
+    #[pyclass(module = "muslib", get_all, set_all)]
+    pub struct GapDetector {
+        /// Input: list[float] -- audio signal
+        pub signal: Vec<f64>,
+
+        /// Output: Optional[list[tuple[float, float]]] -- gaps as [start, end) times in seconds
+        pub events: Option<Events>,
+
+        /// Param: float -- sampling rate of the audio signal in Hz (default: 44100)
+        pub sample_rate: f64,
+        /// Param: float -- level in dBFS at or below which samples are silent (default: -60)
+        pub threshold: f64,
+        /// Param: float -- minimum duration of a gap in seconds (default: 0.01)
+        pub min_duration: f64,
+    }
+}
+
+#[cfg(feature = "python")]
+pymethods_with_params! {
+    impl GapDetector {
+        #[new]
+        #[pyo3(signature = (
+            sample_rate=44100.0,
+            threshold=-60.0,
+            min_duration=0.01,
+        ))]
+        fn pynew(sample_rate: f64, threshold: f64, min_duration: f64) -> Self {
+            Self::with_params(GapDetectorParams {
+                sample_rate,
+                threshold,
+                min_duration,
+            })
+        }
+
+        /// Compute the Algorithm
+        ///
+        /// Inputs:
+        ///   - signal: list[float]
+        ///
+        /// Outputs:
+        ///   - events: list[tuple[float, float]]
+        ///
+        /// See data descriptors for more details.
+        #[pyo3(name = "compute", signature = (signal=None))]
+        fn pycompute(&mut self, py: Python<'_>, signal: Option<ArrayView<f64>>) -> Events {
+            if let Some(arg) = signal {
+                arg.assign_to(&mut self.signal)
+            }
+
+            py.allow_threads(|| self.run());
+
+            self.events.as_ref().unwrap().clone()
+        }
+
+        fn __call__(&mut self, py: Python<'_>) {
+            py.allow_threads(|| self.run())
+        }
+    }
+
+    params = ["sample_rate", "threshold", "min_duration"];
+    async = compute_async;
+
+    fn reset(&mut self) {
+        self.events = None;
+    }
+}
+
+/// Parameters of the GapDetector, see its data descriptors for details
+#[derive(Debug, Clone, PartialEq)]
+pub struct GapDetectorParams {
+    /// sampling rate of the audio signal in Hz
+    pub sample_rate: f64,
+    /// level in dBFS at or below which samples are silent
+    pub threshold: f64,
+    /// minimum duration of a gap in seconds
+    pub min_duration: f64,
+}
+
+impl Default for GapDetectorParams {
+    fn default() -> Self {
+        GapDetectorParams {
+            sample_rate: 44100.0,
+            threshold: -60.0,
+            min_duration: 0.01,
+        }
+    }
+}
+
+impl ToJson for GapDetectorParams {
+    fn to_json(&self) -> Json {
+        Json::object([
+            ("sample_rate", self.sample_rate.to_json()),
+            ("threshold", self.threshold.to_json()),
+            ("min_duration", self.min_duration.to_json()),
+        ])
+    }
+}
+
+impl FromJson for GapDetectorParams {
+    fn from_json(json: &Json) -> Result<Self> {
+        let mut params = Self::default();
+        for (key, value) in json.fields()? {
+            match key.as_str() {
+                "sample_rate" => params.sample_rate = FromJson::from_json(value)?,
+                "threshold" => params.threshold = FromJson::from_json(value)?,
+                "min_duration" => params.min_duration = FromJson::from_json(value)?,
+                _ => return Err(json::unknown_key(key)),
+            }
+        }
+        Ok(params)
+    }
+}
+
+/// Builder of a GapDetector starting from default parameters, see GapDetector::builder()
+#[derive(Debug, Clone, Default)]
+pub struct GapDetectorBuilder {
+    params: GapDetectorParams,
+}
+
+impl GapDetectorBuilder {
+    /// sampling rate of the audio signal in Hz
+    pub fn sample_rate(mut self, sample_rate: f64) -> Self {
+        self.params.sample_rate = sample_rate;
+        self
+    }
+
+    /// level in dBFS at or below which samples are silent
+    pub fn threshold(mut self, threshold: f64) -> Self {
+        self.params.threshold = threshold;
+        self
+    }
+
+    /// minimum duration of a gap in seconds
+    pub fn min_duration(mut self, min_duration: f64) -> Self {
+        self.params.min_duration = min_duration;
+        self
+    }
+
+    /// create the GapDetector with the parameters set so far
+    pub fn build(self) -> GapDetector {
+        GapDetector::with_params(self.params)
+    }
+}
+
+impl Algorithm for GapDetector {
+    /// audio signal
+    type Input = Vec<f64>;
+    /// detected gaps
+    type Output = Events;
+    type Params = GapDetectorParams;
+
+    fn with_params(params: GapDetectorParams) -> Self {
+        GapDetector {
+            signal: Vec::new(),
+
+            events: None,
+
+            sample_rate: params.sample_rate,
+            threshold: params.threshold,
+            min_duration: params.min_duration,
+        }
+    }
+
+    fn compute(&mut self, signal: Vec<f64>) -> Result<Self::Output> {
+        self.signal = signal;
+        self.run();
+        Ok(self.events.take().unwrap())
+    }
+}
+
+impl GapDetector {
+    /// start building a GapDetector from default parameters
+    pub fn builder() -> GapDetectorBuilder {
+        GapDetectorBuilder::default()
+    }
+
+    /// detect gaps in the signal and set the outputs
+    fn run(&mut self) {
+        let min_run = samples(self.min_duration, self.sample_rate);
+        let regions = detect_gaps(&self.signal, db_to_amplitude(self.threshold), min_run);
+
+        // Output
+        self.events = Some(events(&regions, self.sample_rate));
+    }
+}
+
+python_class! {
+    /// Detect saturation, i.e. flat runs of samples near full scale
+    ///
+    /// Unlike the Declipper, which looks for runs at exactly the peak level,
+    /// it also finds saturation that was lowered in level after it happened.
+    #[pyclass(module = "muslib", get_all, set_all)]
+    pub struct SaturationDetector {
+        /// Input: list[float] -- audio signal
+        pub signal: Vec<f64>,
+
+        /// Output: Optional[list[tuple[float, float]]] -- saturated regions as [start, end) times in seconds
+        pub events: Option<Events>,
+
+        /// Param: float -- sampling rate of the audio signal in Hz (default: 44100)
+        pub sample_rate: f64,
+        /// Param: float -- level in dBFS at or above which samples may be saturated (default: -1)
+        pub threshold: f64,
+        /// Param: float -- maximum difference of consecutive saturated samples (default: 0.0001)
+        pub differential: f64,
+        /// Param: float -- minimum duration of a saturated region in seconds (default: 0.0002)
+        pub min_duration: f64,
+    }
+}
+
+#[cfg(feature = "python")]
+pymethods_with_params! {
+    impl SaturationDetector {
+        #[new]
+        #[pyo3(signature = (
+            sample_rate=44100.0,
+            threshold=-1.0,
+            differential=0.0001,
+            min_duration=0.0002,
+        ))]
+        fn pynew(sample_rate: f64, threshold: f64, differential: f64, min_duration: f64) -> Self {
+            Self::with_params(SaturationDetectorParams {
+                sample_rate,
+                threshold,
+                differential,
+                min_duration,
+            })
+        }
+
+        /// Compute the Algorithm
+        ///
+        /// Inputs:
+        ///   - signal: list[float]
+        ///
+        /// Outputs:
+        ///   - events: list[tuple[float, float]]
+        ///
+        /// See data descriptors for more details.
+        #[pyo3(name = "compute", signature = (signal=None))]
+        fn pycompute(&mut self, py: Python<'_>, signal: Option<ArrayView<f64>>) -> Events {
+            if let Some(arg) = signal {
+                arg.assign_to(&mut self.signal)
+            }
+
+            py.allow_threads(|| self.run());
+
+            self.events.as_ref().unwrap().clone()
+        }
+
+        fn __call__(&mut self, py: Python<'_>) {
+            py.allow_threads(|| self.run())
+        }
+    }
+
+    params = ["sample_rate", "threshold", "differential", "min_duration"];
+    async = compute_async;
+
+    fn reset(&mut self) {
+        self.events = None;
+    }
+}
+
+/// Parameters of the SaturationDetector, see its data descriptors for details
+#[derive(Debug, Clone, PartialEq)]
+pub struct SaturationDetectorParams {
+    /// sampling rate of the audio signal in Hz
+    pub sample_rate: f64,
+    /// level in dBFS at or above which samples may be saturated
+    pub threshold: f64,
+    /// maximum difference of consecutive saturated samples
+    pub differential: f64,
+    /// minimum duration of a saturated region in seconds
+    pub min_duration: f64,
+}
+
+impl Default for SaturationDetectorParams {
+    fn default() -> Self {
+        SaturationDetectorParams {
+            sample_rate: 44100.0,
+            threshold: -1.0,
+            differential: 0.0001,
+            min_duration: 0.0002,
+        }
+    }
+}
+
+impl ToJson for SaturationDetectorParams {
+    fn to_json(&self) -> Json {
+        Json::object([
+            ("sample_rate", self.sample_rate.to_json()),
+            ("threshold", self.threshold.to_json()),
+            ("differential", self.differential.to_json()),
+            ("min_duration", self.min_duration.to_json()),
+        ])
+    }
+}
+
+impl FromJson for SaturationDetectorParams {
+    fn from_json(json: &Json) -> Result<Self> {
+        let mut params = Self::default();
+        for (key, value) in json.fields()? {
+            match key.as_str() {
+                "sample_rate" => params.sample_rate = FromJson::from_json(value)?,
+                "threshold" => params.threshold = FromJson::from_json(value)?,
+                "differential" => params.differential = FromJson::from_json(value)?,
+                "min_duration" => params.min_duration = FromJson::from_json(value)?,
+                _ => return Err(json::unknown_key(key)),
+            }
+        }
+        Ok(params)
+    }
+}
+
+/// Builder of a SaturationDetector starting from default parameters, see SaturationDetector::builder()
+#[derive(Debug, Clone, Default)]
+pub struct SaturationDetectorBuilder {
+    params: SaturationDetectorParams,
+}
+
+impl SaturationDetectorBuilder {
+    /// sampling rate of the audio signal in Hz
+    pub fn sample_rate(mut self, sample_rate: f64) -> Self {
+        self.params.sample_rate = sample_rate;
+        self
+    }
+
+    /// level in dBFS at or above which samples may be saturated
+    pub fn threshold(mut self, threshold: f64) -> Self {
+        self.params.threshold = threshold;
+        self
+    }
+
+    /// maximum difference of consecutive saturated samples
+    pub fn differential(mut self, differential: f64) -> Self {
+        self.params.differential = differential;
+        self
+    }
+
+    /// minimum duration of a saturated region in seconds
+    pub fn min_duration(mut self, min_duration: f64) -> Self {
+        self.params.min_duration = min_duration;
+        self
+    }
+
+    /// create the SaturationDetector with the parameters set so far
+    pub fn build(self) -> SaturationDetector {
+        SaturationDetector::with_params(self.params)
+    }
+}
+
+impl Algorithm for SaturationDetector {
+    /// audio signal
+    type Input = Vec<f64>;
+    /// detected saturated regions
+    type Output = Events;
+    type Params = SaturationDetectorParams;
+
+    fn with_params(params: SaturationDetectorParams) -> Self {
+        SaturationDetector {
+            signal: Vec::new(),
+
+            events: None,
+
+            sample_rate: params.sample_rate,
+            threshold: params.threshold,
+            differential: params.differential,
+            min_duration: params.min_duration,
+        }
+    }
+
+    fn compute(&mut self, signal: Vec<f64>) -> Result<Self::Output> {
+        self.signal = signal;
+        self.run();
+        Ok(self.events.take().unwrap())
+    }
+}
+
+impl SaturationDetector {
+    /// start building a SaturationDetector from default parameters
+    pub fn builder() -> SaturationDetectorBuilder {
+        SaturationDetectorBuilder::default()
+    }
+
+    /// detect saturated regions in the signal and set the outputs
+    fn run(&mut self) {
+        let min_run = samples(self.min_duration, self.sample_rate);
+        let regions = detect_saturation(
+            &self.signal,
+            db_to_amplitude(self.threshold),
+            self.differential,
+            min_run,
+        );
+
+        // Output
+        self.events = Some(events(&regions, self.sample_rate));
+    }
+}
+
+python_class! {
+    /// Detect impulsive clicks and discontinuities of the waveform, e.g. from bad edits
+    ///
+    /// Samples that linear prediction from the preceding ones misses by far are flagged,
+    /// as in the Declicker, but they're only reported with their severity and not repaired.
+    #[pyclass(module = "muslib", get_all, set_all)]
+    pub struct ClickDetector {
+        /// Input: list[float] -- audio signal
+        pub signal: Vec<f64>,
+
+        /// Output: Optional[list[tuple[float, float]]] -- clicks as [start, end) times in seconds
+        pub events: Option<Events>,
+        /// Output: Optional[list[float]] -- peak prediction error of each click as a multiple of the median
+        pub severities: Option<Vec<f64>>,
+
+        /// Param: float -- sampling rate of the audio signal in Hz (default: 44100)
+        pub sample_rate: f64,
+        /// Param: int -- order of the linear prediction model (default: 16)
+        pub order: usize,
+        /// Param: float -- detection threshold as a multiple of the median prediction error (default: 10)
+        pub threshold: f64,
+    }
+}
+
+#[cfg(feature = "python")]
+pymethods_with_params! {
+    impl ClickDetector {
+        #[new]
+        #[pyo3(signature = (
+            sample_rate=44100.0,
+            order=16,
+            threshold=10.0,
+        ))]
+        fn pynew(sample_rate: f64, order: usize, threshold: f64) -> Self {
+            Self::with_params(ClickDetectorParams {
+                sample_rate,
+                order,
+                threshold,
+            })
+        }
+
+        /// Compute the Algorithm
+        ///
+        /// Inputs:
+        ///   - signal: list[float]
+        ///
+        /// Outputs:
+        ///   - events: list[tuple[float, float]]
+        ///   - severities: list[float]
+        ///
+        /// See data descriptors for more details.
+        #[pyo3(name = "compute", signature = (signal=None))]
+        fn pycompute(&mut self, py: Python<'_>, signal: Option<ArrayView<f64>>) -> (Events, Array<f64>) {
+            if let Some(arg) = signal {
+                arg.assign_to(&mut self.signal)
+            }
+
+            py.allow_threads(|| self.run());
+
+            (
+                self.events.as_ref().unwrap().clone(),
+                self.severities.as_ref().unwrap().clone().into(),
+            )
+        }
+
+        fn __call__(&mut self, py: Python<'_>) {
+            py.allow_threads(|| self.run())
+        }
+    }
+
+    params = ["sample_rate", "order", "threshold"];
+    async = compute_async;
+
+    fn reset(&mut self) {
+        self.events = None;
+        self.severities = None;
+    }
+}
+
+/// Parameters of the ClickDetector, see its data descriptors for details
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClickDetectorParams {
+    /// sampling rate of the audio signal in Hz
+    pub sample_rate: f64,
+    /// order of the linear prediction model
+    pub order: usize,
+    /// detection threshold as a multiple of the median prediction error
+    pub threshold: f64,
+}
+
+impl Default for ClickDetectorParams {
+    fn default() -> Self {
+        ClickDetectorParams {
+            sample_rate: 44100.0,
+            order: 16,
+            threshold: 10.0,
+        }
+    }
+}
+
+impl ToJson for ClickDetectorParams {
+    fn to_json(&self) -> Json {
+        Json::object([
+            ("sample_rate", self.sample_rate.to_json()),
+            ("order", self.order.to_json()),
+            ("threshold", self.threshold.to_json()),
+        ])
+    }
+}
+
+impl FromJson for ClickDetectorParams {
+    fn from_json(json: &Json) -> Result<Self> {
+        let mut params = Self::default();
+        for (key, value) in json.fields()? {
+            match key.as_str() {
+                "sample_rate" => params.sample_rate = FromJson::from_json(value)?,
+                "order" => params.order = FromJson::from_json(value)?,
+                "threshold" => params.threshold = FromJson::from_json(value)?,
+                _ => return Err(json::unknown_key(key)),
+            }
+        }
+        Ok(params)
+    }
+}
+
+/// Builder of a ClickDetector starting from default parameters, see ClickDetector::builder()
+#[derive(Debug, Clone, Default)]
+pub struct ClickDetectorBuilder {
+    params: ClickDetectorParams,
+}
+
+impl ClickDetectorBuilder {
+    /// sampling rate of the audio signal in Hz
+    pub fn sample_rate(mut self, sample_rate: f64) -> Self {
+        self.params.sample_rate = sample_rate;
+        self
+    }
+
+    /// order of the linear prediction model
+    pub fn order(mut self, order: usize) -> Self {
+        self.params.order = order;
+        self
+    }
+
+    /// detection threshold as a multiple of the median prediction error
+    pub fn threshold(mut self, threshold: f64) -> Self {
+        self.params.threshold = threshold;
+        self
+    }
+
+    /// create the ClickDetector with the parameters set so far
+    pub fn build(self) -> ClickDetector {
+        ClickDetector::with_params(self.params)
+    }
+}
+
+impl Algorithm for ClickDetector {
+    /// audio signal
+    type Input = Vec<f64>;
+    /// detected clicks and their severities
+    type Output = (Events, Vec<f64>);
+    type Params = ClickDetectorParams;
+
+    fn with_params(params: ClickDetectorParams) -> Self {
+        ClickDetector {
+            signal: Vec::new(),
+
+            events: None,
+            severities: None,
+
+            sample_rate: params.sample_rate,
+            order: params.order,
+            threshold: params.threshold,
+        }
+    }
+
+    fn compute(&mut self, signal: Vec<f64>) -> Result<Self::Output> {
+        self.signal = signal;
+        self.run();
+        Ok((self.events.take().unwrap(), self.severities.take().unwrap()))
+    }
+}
+
+impl ClickDetector {
+    /// start building a ClickDetector from default parameters
+    pub fn builder() -> ClickDetectorBuilder {
+        ClickDetectorBuilder::default()
+    }
+
+    /// detect clicks in the signal and set the outputs
+    fn run(&mut self) {
+        let clicks = detect_discontinuities(&self.signal, self.order, self.threshold);
+
+        let regions: Regions = clicks.iter().map(|(s, e, _)| (*s, *e)).collect();
+
+        // Output
+        self.events = Some(events(&regions, self.sample_rate));
+        self.severities = Some(clicks.into_iter().map(|(_, _, x)| x).collect());
+    }
+}
+
+python_class! {
+    /// Find the first and the last non-silent frames of a recording, frame by frame
+    ///
+    /// Frames of a stream are given one at a time and the outputs are updated with each of them,
+    /// so after the last frame they bound the audible part of the recording.
+    /// Call .reset() before starting with another recording.
+    #[pyclass(module = "muslib")]
+    pub struct StartStopSilence {
+        /// Input: list[float] -- frame of an audio signal
+        #[pyo3(get, set)]
+        pub frame: Vec<f64>,
+
+        /// Output: Optional[int] -- index of the first non-silent frame, None while all frames are silent
+        #[pyo3(get)]
+        pub start_frame: Option<usize>,
+        /// Output: Optional[int] -- index of the last non-silent frame, None while all frames are silent
+        #[pyo3(get)]
+        pub stop_frame: Option<usize>,
+
+        /// Param: float -- power in dB at or below which a frame is silent (default: -60)
+        #[pyo3(get, set)]
+        pub threshold: f64,
+
+        frames: usize,
+    }
+}
+
+#[cfg(feature = "python")]
+pymethods_with_params! {
+    impl StartStopSilence {
+        #[new]
+        #[pyo3(signature = (threshold=-60.0))]
+        fn pynew(threshold: f64) -> Self {
+            Self::with_params(StartStopSilenceParams { threshold })
+        }
+
+        /// Compute the Algorithm
+        ///
+        /// Inputs:
+        ///   - frame: list[float]
+        ///
+        /// Outputs:
+        ///   - start_frame: Optional[int]
+        ///   - stop_frame: Optional[int]
+        ///
+        /// See data descriptors for more details.
+        #[pyo3(name = "compute", signature = (frame=None))]
+        fn pycompute(
+            &mut self,
+            py: Python<'_>,
+            frame: Option<ArrayView<f64>>,
+        ) -> (Option<usize>, Option<usize>) {
+            if let Some(arg) = frame {
+                arg.assign_to(&mut self.frame)
+            }
+
+            py.allow_threads(|| self.run());
+
+            (self.start_frame, self.stop_frame)
+        }
+
+        fn __call__(&mut self, py: Python<'_>) {
+            py.allow_threads(|| self.run())
+        }
+    }
+
+    params = ["threshold"];
+    async = compute_async;
+
+    fn reset(&mut self) {
+        self.reset();
+    }
+}
+
+/// Parameters of the StartStopSilence, see its data descriptors for details
+#[derive(Debug, Clone, PartialEq)]
+pub struct StartStopSilenceParams {
+    /// power in dB at or below which a frame is silent
+    pub threshold: f64,
+}
+
+impl Default for StartStopSilenceParams {
+    fn default() -> Self {
+        StartStopSilenceParams { threshold: -60.0 }
+    }
+}
+
+impl ToJson for StartStopSilenceParams {
+    fn to_json(&self) -> Json {
+        Json::object([("threshold", self.threshold.to_json())])
+    }
+}
+
+impl FromJson for StartStopSilenceParams {
+    fn from_json(json: &Json) -> Result<Self> {
+        let mut params = Self::default();
+        for (key, value) in json.fields()? {
+            match key.as_str() {
+                "threshold" => params.threshold = FromJson::from_json(value)?,
+                _ => return Err(json::unknown_key(key)),
+            }
+        }
+        Ok(params)
+    }
+}
+
+/// Builder of a StartStopSilence starting from default parameters, see StartStopSilence::builder()
+#[derive(Debug, Clone, Default)]
+pub struct StartStopSilenceBuilder {
+    params: StartStopSilenceParams,
+}
+
+impl StartStopSilenceBuilder {
+    /// power in dB at or below which a frame is silent
+    pub fn threshold(mut self, threshold: f64) -> Self {
+        self.params.threshold = threshold;
+        self
+    }
+
+    /// create the StartStopSilence with the parameters set so far
+    pub fn build(self) -> StartStopSilence {
+        StartStopSilence::with_params(self.params)
+    }
+}
+
+impl Algorithm for StartStopSilence {
+    /// frame of an audio signal
+    type Input = Vec<f64>;
+    /// indices of the first and the last non-silent frames so far
+    type Output = (Option<usize>, Option<usize>);
+    type Params = StartStopSilenceParams;
+
+    fn with_params(params: StartStopSilenceParams) -> Self {
+        StartStopSilence {
+            frame: Vec::new(),
+
+            start_frame: None,
+            stop_frame: None,
+
+            threshold: params.threshold,
+
+            frames: 0,
+        }
+    }
+
+    fn compute(&mut self, frame: Vec<f64>) -> Result<Self::Output> {
+        self.frame = frame;
+        self.run();
+        Ok((self.start_frame, self.stop_frame))
+    }
+}
+
+impl StartStopSilence {
+    /// start building a StartStopSilence from default parameters
+    pub fn builder() -> StartStopSilenceBuilder {
+        StartStopSilenceBuilder::default()
+    }
+
+    /// forget all frames given so far
+    pub fn reset(&mut self) {
+        self.start_frame = None;
+        self.stop_frame = None;
+        self.frames = 0;
+    }
+
+    /// check the next frame for silence and update the outputs
+    fn run(&mut self) {
+        if !is_silent(&self.frame, db_to_power(self.threshold)) {
+            // Output
+            self.start_frame = self.start_frame.or(Some(self.frames));
+            self.stop_frame = Some(self.frames);
+        }
+        self.frames += 1;
+    }
+}
+
+python_class! {
+    /// Measure true peaks of a multi-channel signal after ITU-R BS.1770
+    ///
+    /// Each channel is oversampled four times with the interpolation filter of the recommendation,
+    /// so peaks between samples, which a DAC would reproduce, are not missed.
+    /// Overs are the regions where the true peak exceeds the threshold.
+    #[pyclass(module = "muslib", get_all, set_all)]
+    pub struct TruePeakDetector {
+        /// Input: list[list[float]] -- audio signal with one list of samples per channel
+        pub channels: Vec<Vec<f64>>,
+
+        /// Output: Optional[list[float]] -- maximum true peak of each channel in dBTP
+        pub peaks: Option<Vec<f64>>,
+        /// Output: Optional[list[list[tuple[float, float]]]] -- overs of each channel as [start, end) times in seconds
+        pub overs: Option<Vec<Events>>,
+
+        /// Param: float -- sampling rate of the audio signal in Hz (default: 44100)
+        pub sample_rate: f64,
+        /// Param: float -- level in dBTP above which the true peak is an over (default: -1)
+        pub threshold: f64,
+    }
+}
+
+#[cfg(feature = "python")]
+pymethods_with_params! {
+    impl TruePeakDetector {
+        #[new]
+        #[pyo3(signature = (
+            sample_rate=44100.0,
+            threshold=-1.0,
+        ))]
+        fn pynew(sample_rate: f64, threshold: f64) -> Self {
+            Self::with_params(TruePeakDetectorParams {
+                sample_rate,
+                threshold,
+            })
+        }
+
+        /// Compute the Algorithm
+        ///
+        /// Inputs:
+        ///   - channels: list[list[float]]
+        ///
+        /// Outputs:
+        ///   - peaks: list[float]
+        ///   - overs: list[list[tuple[float, float]]]
+        ///
+        /// See data descriptors for more details.
+        #[pyo3(name = "compute", signature = (channels=None))]
+        fn pycompute(
+            &mut self,
+            py: Python<'_>,
+            channels: Option<Array2<f64>>,
+        ) -> (Array<f64>, Vec<Events>) {
+            if let Some(arg) = channels {
+                self.channels = arg.0
+            }
+
+            py.allow_threads(|| self.run());
+
+            (
+                self.peaks.as_ref().unwrap().clone().into(),
+                self.overs.as_ref().unwrap().clone(),
+            )
+        }
+
+        fn __call__(&mut self, py: Python<'_>) {
+            py.allow_threads(|| self.run())
+        }
+    }
+
+    params = ["sample_rate", "threshold"];
+    async = compute_async;
+
+    fn reset(&mut self) {
+        self.peaks = None;
+        self.overs = None;
+    }
+}
+
+/// Parameters of the TruePeakDetector, see its data descriptors for details
+#[derive(Debug, Clone, PartialEq)]
+pub struct TruePeakDetectorParams {
+    /// sampling rate of the audio signal in Hz
+    pub sample_rate: f64,
+    /// level in dBTP above which the true peak is an over
+    pub threshold: f64,
+}
+
+impl Default for TruePeakDetectorParams {
+    fn default() -> Self {
+        TruePeakDetectorParams {
+            sample_rate: 44100.0,
+            threshold: -1.0,
+        }
+    }
+}
+
+impl ToJson for TruePeakDetectorParams {
+    fn to_json(&self) -> Json {
+        Json::object([
+            ("sample_rate", self.sample_rate.to_json()),
+            ("threshold", self.threshold.to_json()),
+        ])
+    }
+}
+
+impl FromJson for TruePeakDetectorParams {
+    fn from_json(json: &Json) -> Result<Self> {
+        let mut params = Self::default();
+        for (key, value) in json.fields()? {
+            match key.as_str() {
+                "sample_rate" => params.sample_rate = FromJson::from_json(value)?,
+                "threshold" => params.threshold = FromJson::from_json(value)?,
+                _ => return Err(json::unknown_key(key)),
+            }
+        }
+        Ok(params)
+    }
+}
+
+/// Builder of a TruePeakDetector starting from default parameters, see TruePeakDetector::builder()
+#[derive(Debug, Clone, Default)]
+pub struct TruePeakDetectorBuilder {
+    params: TruePeakDetectorParams,
+}
+
+impl TruePeakDetectorBuilder {
+    /// sampling rate of the audio signal in Hz
+    pub fn sample_rate(mut self, sample_rate: f64) -> Self {
+        self.params.sample_rate = sample_rate;
+        self
+    }
+
+    /// level in dBTP above which the true peak is an over
+    pub fn threshold(mut self, threshold: f64) -> Self {
+        self.params.threshold = threshold;
+        self
+    }
+
+    /// create the TruePeakDetector with the parameters set so far
+    pub fn build(self) -> TruePeakDetector {
+        TruePeakDetector::with_params(self.params)
+    }
+}
+
+impl Algorithm for TruePeakDetector {
+    /// audio signal with one list of samples per channel
+    type Input = Vec<Vec<f64>>;
+    /// true peak of each channel and its overs
+    type Output = (Vec<f64>, Vec<Events>);
+    type Params = TruePeakDetectorParams;
+
+    fn with_params(params: TruePeakDetectorParams) -> Self {
+        TruePeakDetector {
+            channels: Vec::new(),
+
+            peaks: None,
+            overs: None,
+
+            sample_rate: params.sample_rate,
+            threshold: params.threshold,
+        }
+    }
+
+    fn compute(&mut self, channels: Vec<Vec<f64>>) -> Result<Self::Output> {
+        self.channels = channels;
+        self.run();
+        Ok((self.peaks.take().unwrap(), self.overs.take().unwrap()))
+    }
+}
+
+impl TruePeakDetector {
+    /// start building a TruePeakDetector from default parameters
+    pub fn builder() -> TruePeakDetectorBuilder {
+        TruePeakDetectorBuilder::default()
+    }
+
+    /// measure true peaks of all channels and set the outputs
+    fn run(&mut self) {
+        let level = db_to_amplitude(self.threshold);
+        let rate = OVERSAMPLING as f64 * self.sample_rate;
+        let time = |i: usize| (i as f64 - OVERSAMPLING_DELAY).max(0.0) / rate;
+
+        let mut peaks = Vec::with_capacity(self.channels.len());
+        let mut overs = Vec::with_capacity(self.channels.len());
+        for channel in &self.channels {
+            let oversampled = oversample(channel);
+            let peak = oversampled
+                .iter()
+                .chain(channel.iter())
+                .fold(0.0, |m: f64, x| m.max(x.abs()));
+            peaks.push(20.0 * peak.max(1e-12).log10());
+
+            let regions = runs(oversampled.iter().map(|x| x.abs() > level), 1);
+            overs.push(regions.iter().map(|(s, e)| (time(*s), time(*e))).collect());
+        }
+
+        // Output
+        self.peaks = Some(peaks);
+        self.overs = Some(overs);
+    }
+}
+
+python_class! {
+    /// Detect mains hum at 50 or 60 Hz and its harmonics
+    ///
+    /// The energy at the mains frequency and its harmonics is compared with the energy
+    /// at nearby frequencies in overlapping frames, hum is found in frames where it stands out
+    /// and it's reported when it lasts long enough.
+    #[pyclass(module = "muslib", get_all, set_all)]
+    pub struct HumDetector {
+        /// Input: list[float] -- audio signal
+        pub signal: Vec<f64>,
+
+        /// Output: Optional[list[tuple[float, float]]] -- hum as [start, end) times in seconds
+        pub events: Option<Events>,
+        /// Output: Optional[list[float]] -- mains frequency of each event in Hz
+        pub frequencies: Option<Vec<f64>>,
+
+        /// Param: float -- sampling rate of the audio signal in Hz (default: 44100)
+        pub sample_rate: f64,
+        /// Param: float -- duration of the analysis frames in seconds, which overlap by half (default: 1)
+        pub frame_duration: f64,
+        /// Param: int -- number of harmonics including the mains frequency (default: 4)
+        pub harmonics: usize,
+        /// Param: float -- how far the hum must stand out from nearby frequencies in dB (default: 20)
+        pub threshold: f64,
+        /// Param: float -- minimum duration of hum in seconds (default: 2)
+        pub min_duration: f64,
+    }
+}
+
+#[cfg(feature = "python")]
+pymethods_with_params! {
+    impl HumDetector {
+        #[new]
+        #[pyo3(signature = (
+            sample_rate=44100.0,
+            frame_duration=1.0,
+            harmonics=4,
+            threshold=20.0,
+            min_duration=2.0,
+        ))]
+        fn pynew(
+            sample_rate: f64,
+            frame_duration: f64,
+            harmonics: usize,
+            threshold: f64,
+            min_duration: f64,
+        ) -> Self {
+            Self::with_params(HumDetectorParams {
+                sample_rate,
+                frame_duration,
+                harmonics,
+                threshold,
+                min_duration,
+            })
+        }
+
+        /// Compute the Algorithm
+        ///
+        /// Inputs:
+        ///   - signal: list[float]
+        ///
+        /// Outputs:
+        ///   - events: list[tuple[float, float]]
+        ///   - frequencies: list[float]
+        ///
+        /// See data descriptors for more details.
+        #[pyo3(name = "compute", signature = (signal=None))]
+        fn pycompute(&mut self, py: Python<'_>, signal: Option<ArrayView<f64>>) -> (Events, Array<f64>) {
+            if let Some(arg) = signal {
+                arg.assign_to(&mut self.signal)
+            }
+
+            py.allow_threads(|| self.run());
+
+            (
+                self.events.as_ref().unwrap().clone(),
+                self.frequencies.as_ref().unwrap().clone().into(),
+            )
+        }
+
+        fn __call__(&mut self, py: Python<'_>) {
+            py.allow_threads(|| self.run())
+        }
+    }
+
+    params = ["sample_rate", "frame_duration", "harmonics", "threshold", "min_duration"];
+    async = compute_async;
+
+    fn reset(&mut self) {
+        self.events = None;
+        self.frequencies = None;
+    }
+}
+
+/// Parameters of the HumDetector, see its data descriptors for details
+#[derive(Debug, Clone, PartialEq)]
+pub struct HumDetectorParams {
+    /// sampling rate of the audio signal in Hz
+    pub sample_rate: f64,
+    /// duration of the analysis frames in seconds, which overlap by half
+    pub frame_duration: f64,
+    /// number of harmonics including the mains frequency
+    pub harmonics: usize,
+    /// how far the hum must stand out from nearby frequencies in dB
+    pub threshold: f64,
+    /// minimum duration of hum in seconds
+    pub min_duration: f64,
+}
+
+impl Default for HumDetectorParams {
+    fn default() -> Self {
+        HumDetectorParams {
+            sample_rate: 44100.0,
+            frame_duration: 1.0,
+            harmonics: 4,
+            threshold: 20.0,
+            min_duration: 2.0,
+        }
+    }
+}
+
+impl ToJson for HumDetectorParams {
+    fn to_json(&self) -> Json {
+        Json::object([
+            ("sample_rate", self.sample_rate.to_json()),
+            ("frame_duration", self.frame_duration.to_json()),
+            ("harmonics", self.harmonics.to_json()),
+            ("threshold", self.threshold.to_json()),
+            ("min_duration", self.min_duration.to_json()),
+        ])
+    }
+}
+
+impl FromJson for HumDetectorParams {
+    fn from_json(json: &Json) -> Result<Self> {
+        let mut params = Self::default();
+        for (key, value) in json.fields()? {
+            match key.as_str() {
+                "sample_rate" => params.sample_rate = FromJson::from_json(value)?,
+                "frame_duration" => params.frame_duration = FromJson::from_json(value)?,
+                "harmonics" => params.harmonics = FromJson::from_json(value)?,
+                "threshold" => params.threshold = FromJson::from_json(value)?,
+                "min_duration" => params.min_duration = FromJson::from_json(value)?,
+                _ => return Err(json::unknown_key(key)),
+            }
+        }
+        Ok(params)
+    }
+}
+
+/// Builder of a HumDetector starting from default parameters, see HumDetector::builder()
+#[derive(Debug, Clone, Default)]
+pub struct HumDetectorBuilder {
+    params: HumDetectorParams,
+}
+
+impl HumDetectorBuilder {
+    /// sampling rate of the audio signal in Hz
+    pub fn sample_rate(mut self, sample_rate: f64) -> Self {
+        self.params.sample_rate = sample_rate;
+        self
+    }
+
+    /// duration of the analysis frames in seconds, which overlap by half
+    pub fn frame_duration(mut self, frame_duration: f64) -> Self {
+        self.params.frame_duration = frame_duration;
+        self
+    }
+
+    /// number of harmonics including the mains frequency
+    pub fn harmonics(mut self, harmonics: usize) -> Self {
+        self.params.harmonics = harmonics;
+        self
+    }
+
+    /// how far the hum must stand out from nearby frequencies in dB
+    pub fn threshold(mut self, threshold: f64) -> Self {
+        self.params.threshold = threshold;
+        self
+    }
+
+    /// minimum duration of hum in seconds
+    pub fn min_duration(mut self, min_duration: f64) -> Self {
+        self.params.min_duration = min_duration;
+        self
+    }
+
+    /// create the HumDetector with the parameters set so far
+    pub fn build(self) -> HumDetector {
+        HumDetector::with_params(self.params)
+    }
+}
+
+impl Algorithm for HumDetector {
+    /// audio signal
+    type Input = Vec<f64>;
+    /// detected hum and its mains frequency
+    type Output = (Events, Vec<f64>);
+    type Params = HumDetectorParams;
+
+    fn with_params(params: HumDetectorParams) -> Self {
+        HumDetector {
+            signal: Vec::new(),
+
+            events: None,
+            frequencies: None,
+
+            sample_rate: params.sample_rate,
+            frame_duration: params.frame_duration,
+            harmonics: params.harmonics,
+            threshold: params.threshold,
+            min_duration: params.min_duration,
+        }
+    }
+
+    fn compute(&mut self, signal: Vec<f64>) -> Result<Self::Output> {
+        self.signal = signal;
+        self.run();
+        Ok((
+            self.events.take().unwrap(),
+            self.frequencies.take().unwrap(),
+        ))
+    }
+}
+
+impl HumDetector {
+    /// start building a HumDetector from default parameters
+    pub fn builder() -> HumDetectorBuilder {
+        HumDetectorBuilder::default()
+    }
+
+    /// detect hum in the signal and set the outputs
+    fn run(&mut self) {
+        let frame_size = std::cmp::max(samples(self.frame_duration, self.sample_rate), 2);
+        let hum = detect_hum(
+            &self.signal,
+            self.sample_rate,
+            frame_size,
+            self.harmonics,
+            self.threshold,
+        );
+
+        let min_run = samples(self.min_duration, self.sample_rate);
+        let mut regions = Regions::new();
+        let mut frequencies = Vec::new();
+        for (start, end, frequency) in hum {
+            if end - start >= min_run {
+                regions.push((start, end));
+                frequencies.push(frequency);
+            }
+        }
+
+        // Output
+        self.events = Some(events(&regions, self.sample_rate));
+        self.frequencies = Some(frequencies);
+    }
+}
+
+/// find runs of samples at or below a level, not touching the start or the end of the signal
+pub fn detect_gaps(signal: &[f64], level: f64, min_run: usize) -> Regions {
+    runs(signal.iter().map(|x| x.abs() <= level), min_run)
+        .into_iter()
+        .filter(|(start, end)| *start > 0 && *end < signal.len())
+        .collect()
+}
+
+/// find runs of samples at or above a level that change by at most the differential
+pub fn detect_saturation(signal: &[f64], level: f64, differential: f64, min_run: usize) -> Regions {
+    let flat = |i: usize, j: usize| (signal[i] - signal[j]).abs() <= differential;
+    let saturated = (0..signal.len()).map(|i| {
+        signal[i].abs() >= level
+            && ((i > 0 && flat(i, i - 1)) || (i + 1 < signal.len() && flat(i, i + 1)))
+    });
+    runs(saturated, min_run)
+}
+
+/// find clicks and discontinuities as outliers of the linear prediction error
+///
+/// Outliers closer than the order of the model are grouped, since a single click disturbs
+/// the prediction of the samples after it. Returns [start, end) sample ranges
+/// with the largest error in each of them as a multiple of the median error.
+pub fn detect_discontinuities(
+    signal: &[f64],
+    order: usize,
+    threshold: f64,
+) -> Vec<(usize, usize, f64)> {
+    let mut clicks: Vec<(usize, usize, f64)> = Vec::new();
+    for (i, severity) in restore::prediction_outliers(signal, order, threshold) {
+        match clicks.last_mut() {
+            Some(last) if i <= last.1 + order => {
+                last.1 = i + 1;
+                last.2 = last.2.max(severity);
+            }
+            _ => clicks.push((i, i + 1, severity)),
+        }
+    }
+    clicks
+}
+
+/// find the audible part of a signal as the range from the first to the last non-silent frame
+///
+/// Frames are cut every hop_size samples, the last one may be shorter.
+/// Returns the [start, end) sample range, or None when the whole signal is silent.
+pub fn detect_start_stop(
+    signal: &[f64],
+    frame_size: usize,
+    hop_size: usize,
+    power: f64,
+) -> Option<(usize, usize)> {
+    let frame = |start: usize| &signal[start..std::cmp::min(start + frame_size, signal.len())];
+    let mut starts = (0..signal.len()).step_by(std::cmp::max(hop_size, 1));
+    let first = starts.clone().find(|s| !is_silent(frame(*s), power))?;
+    let last = starts.rfind(|s| !is_silent(frame(*s), power))?;
+    Some((first, frame(last).len() + last))
+}
+
+/// check whether the mean power of a frame is at or below the given power
+fn is_silent(frame: &[f64], power: f64) -> bool {
+    frame.iter().map(|x| x * x).sum::<f64>() <= power * frame.len() as f64
+}
+
+/// find runs of frames where hum stands out by more than the threshold in dB
+///
+/// Returns [start, end) sample ranges of consecutive frames with the same mains frequency.
+pub fn detect_hum(
+    signal: &[f64],
+    sample_rate: f64,
+    frame_size: usize,
+    harmonics: usize,
+    threshold: f64,
+) -> Vec<(usize, usize, f64)> {
+    // offsets of the nearby frequencies in Hz, outside the main lobe of the window
+    const NEARBY: [f64; 4] = [-7.0, -5.0, 5.0, 7.0];
+
+    let window: Vec<f64> = (0..frame_size)
+        .map(|i| 0.5 - 0.5 * (2.0 * std::f64::consts::PI * i as f64 / frame_size as f64).cos())
+        .collect();
+    let hop = frame_size / 2;
+
+    let mut hum: Vec<(usize, usize, f64)> = Vec::new();
+    let mut start = 0;
+    while start + frame_size <= signal.len() {
+        let frame: Vec<f64> = signal[start..start + frame_size]
+            .iter()
+            .zip(window.iter())
+            .map(|(x, w)| x * w)
+            .collect();
+
+        let mut best: Option<(f64, f64)> = None;
+        for mains in MAINS {
+            let (mut tone, mut noise) = (0.0, 0.0);
+            for k in 1..=harmonics {
+                let frequency = mains * k as f64;
+                if frequency + NEARBY[3] >= sample_rate / 2.0 {
+                    break;
+                }
+                tone += goertzel(&frame, frequency / sample_rate);
+                noise += NEARBY
+                    .iter()
+                    .map(|x| goertzel(&frame, (frequency + x) / sample_rate))
+                    .sum::<f64>()
+                    / NEARBY.len() as f64;
+            }
+            if tone <= f64::MIN_POSITIVE {
+                continue;
+            }
+            let salience = 10.0 * (tone / noise.max(f64::MIN_POSITIVE)).log10();
+            if salience > threshold && best.is_none_or(|(_, s)| salience > s) {
+                best = Some((mains, salience));
+            }
+        }
+
+        if let Some((mains, _)) = best {
+            let end = start + frame_size;
+            match hum.last_mut() {
+                Some(last) if last.2 == mains && start <= last.1 => last.1 = end,
+                _ => hum.push((start, end, mains)),
+            }
+        }
+        start += hop;
+    }
+    hum
+}
+
+/// power of a signal at a frequency given in cycles per sample, with the Goertzel algorithm
+fn goertzel(signal: &[f64], frequency: f64) -> f64 {
+    let c = 2.0 * (2.0 * std::f64::consts::PI * frequency).cos();
+    let (mut s1, mut s2) = (0.0, 0.0);
+    for x in signal {
+        let s = x + c * s1 - s2;
+        s2 = s1;
+        s1 = s;
+    }
+    s1 * s1 + s2 * s2 - c * s1 * s2
+}
+
+/// oversample a signal four times with the interpolation filter of ITU-R BS.1770
+///
+/// The output is delayed by OVERSAMPLING_DELAY samples of the output rate,
+/// and it has extra samples at the end, so the last input samples are fully interpolated.
+pub fn oversample(signal: &[f64]) -> Vec<f64> {
+    let taps = TRUE_PEAK_FILTER[0].len();
+    let mut output = Vec::with_capacity(OVERSAMPLING * (signal.len() + taps));
+    for n in 0..signal.len() + taps - 1 {
+        for phase in TRUE_PEAK_FILTER.iter() {
+            let y = phase
+                .iter()
+                .enumerate()
+                .filter(|(k, _)| *k <= n && n - k < signal.len())
+                .map(|(k, h)| h * signal[n - k])
+                .sum();
+            output.push(y);
+        }
+    }
+    output
+}
+
+/// [start, end) ranges of consecutive flags that are set, at least min_run long
+fn runs(flags: impl Iterator<Item = bool>, min_run: usize) -> Regions {
+    let mut regions = Vec::new();
+    let mut run_start = None;
+    let mut len = 0;
+    for (i, flag) in flags.enumerate() {
+        match (flag, run_start) {
+            (true, None) => run_start = Some(i),
+            (false, Some(s)) => {
+                if i - s >= std::cmp::max(min_run, 1) {
+                    regions.push((s, i));
+                }
+                run_start = None;
+            }
+            _ => {}
+        }
+        len = i + 1;
+    }
+    if let Some(s) = run_start {
+        if len - s >= std::cmp::max(min_run, 1) {
+            regions.push((s, len));
+        }
+    }
+    regions
+}
+
+/// number of samples in a duration given in seconds, rounded up
+fn samples(duration: f64, sample_rate: f64) -> usize {
+    (duration * sample_rate).max(0.0).ceil() as usize
+}
+
+fn db_to_amplitude(db: f64) -> f64 {
+    10f64.powf(db / 20.0)
+}
+
+fn db_to_power(db: f64) -> f64 {
+    10f64.powf(db / 10.0)
+}
+
+/// sample ranges converted to times in seconds
+fn events(regions: &[(usize, usize)], sample_rate: f64) -> Events {
+    regions
+        .iter()
+        .map(|(start, end)| (*start as f64 / sample_rate, *end as f64 / sample_rate))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        detect_start_stop, Algorithm, ClickDetector, GapDetector, HumDetector, SaturationDetector,
+        StartStopSilence, TruePeakDetector,
+    };
+    use crate::rng::Rng;
+
+    fn sine(frequency: f64, amplitude: f64, sample_rate: f64, n: usize) -> Vec<f64> {
+        (0..n)
+            .map(|i| {
+                let t = i as f64 / sample_rate;
+                amplitude * (2.0 * std::f64::consts::PI * frequency * t).sin()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn gaps_and_saturation() {
+        // silence at the start isn't a gap, a dropout in the middle is
+        let mut signal = sine(440.0, 0.5, 44100.0, 44100);
+        signal[..1000].fill(0.0);
+        signal[20000..21000].fill(0.0);
+        let gaps = GapDetector::new().compute(signal.clone()).unwrap();
+        assert_eq!(gaps.len(), 1);
+        assert!((gaps[0].0 - 20000.0 / 44100.0).abs() < 1e-4, "{:?}", gaps);
+        assert!((gaps[0].1 - 21000.0 / 44100.0).abs() < 1e-4, "{:?}", gaps);
+
+        // a loud sine is not saturated until it's clipped
+        let loud = sine(200.0, 0.99, 44100.0, 4410);
+        let mut detector = SaturationDetector::new();
+        assert!(detector.compute(loud).unwrap().is_empty());
+        let clipped: Vec<f64> = sine(200.0, 1.5, 44100.0, 4410)
+            .iter()
+            .map(|x| x.clamp(-1.0, 1.0))
+            .collect();
+        assert_eq!(detector.compute(clipped).unwrap().len(), 40);
+    }
+
+    #[test]
+    fn clicks() {
+        // a click and a splice that skips part of the waveform
+        let clean = sine(440.0, 0.5, 44100.0, 8192);
+        let mut signal = clean.clone();
+        signal[3000] += 0.8;
+        signal.splice(6000.., clean[6050..].iter().copied());
+
+        let mut detector = ClickDetector::new();
+        let (events, severities) = detector.compute(signal).unwrap();
+        let at = |i: usize| i as f64 / 44100.0;
+        assert!(
+            events.iter().any(|e| e.0 <= at(3000) && at(3000) < e.1),
+            "{:?}",
+            events
+        );
+        assert!(
+            events.iter().any(|e| e.0 <= at(6000) && at(6000) < e.1),
+            "{:?}",
+            events
+        );
+        assert!(severities.iter().all(|x| *x > 10.0));
+
+        let (events, _) = detector.compute(clean).unwrap();
+        assert!(events.is_empty(), "{:?}", events);
+    }
+
+    #[test]
+    fn start_stop() {
+        // a tone between 0.1 and 0.3 seconds with quiet noise around it
+        let mut rng = Rng::new(5);
+        let mut signal: Vec<f64> = (0..16000).map(|_| 1e-4 * rng.bipolar()).collect();
+        for (x, y) in signal[1600..4800]
+            .iter_mut()
+            .zip(sine(440.0, 0.5, 16000.0, 3200))
+        {
+            *x += y;
+        }
+
+        let mut detector = StartStopSilence::new();
+        let mut bounds = (None, None);
+        for frame in signal.chunks(512) {
+            bounds = detector.compute(frame.to_vec()).unwrap();
+        }
+        assert_eq!(bounds, (Some(3), Some(9)));
+        assert_eq!(
+            detect_start_stop(&signal, 512, 512, 1e-6),
+            Some((1536, 5120))
+        );
+
+        detector.reset();
+        let silence = detector.compute(signal[..1024].to_vec()).unwrap();
+        assert_eq!(silence, (None, None));
+        assert_eq!(detect_start_stop(&signal[..1024], 512, 256, 1e-6), None);
+    }
+
+    #[test]
+    fn true_peak() {
+        // a sine at a quarter of the sample rate, sampled 45 degrees away from its peaks
+        let sample_rate = 48000.0;
+        let inter: Vec<f64> = (0..4800)
+            .map(|i| (std::f64::consts::PI * (i as f64 / 2.0 + 0.25)).sin())
+            .collect();
+        let sample_peak = inter.iter().fold(0.0, |m: f64, x| m.max(x.abs()));
+        assert!((20.0 * sample_peak.log10() + 3.01).abs() < 0.01);
+
+        let quiet = sine(1000.0, 0.1, sample_rate, 4800);
+        let mut detector = TruePeakDetector::builder().sample_rate(sample_rate).build();
+        let (peaks, overs) = detector.compute(vec![inter, quiet]).unwrap();
+        assert!(peaks[0].abs() < 0.5, "{:?}", peaks);
+        assert!((peaks[1] + 20.0).abs() < 0.1, "{:?}", peaks);
+        assert!(!overs[0].is_empty() && overs[0].iter().all(|(s, e)| s < e && *e <= 0.11));
+        assert!(overs[1].is_empty());
+    }
+
+    #[test]
+    fn hum() {
+        // 60 Hz hum with a harmonic between 2 and 6 seconds of noise
+        let sample_rate = 8000.0;
+        let mut rng = Rng::new(3);
+        let mut signal: Vec<f64> = (0..8 * 8000).map(|_| 0.1 * rng.bipolar()).collect();
+        let hum = sine(60.0, 0.05, sample_rate, 4 * 8000);
+        let harmonic = sine(120.0, 0.02, sample_rate, 4 * 8000);
+        for i in 0..hum.len() {
+            signal[2 * 8000 + i] += hum[i] + harmonic[i];
+        }
+
+        let mut detector = HumDetector::builder().sample_rate(sample_rate).build();
+        let (events, frequencies) = detector.compute(signal.clone()).unwrap();
+        assert_eq!(frequencies, vec![60.0]);
+        assert!(events[0].0 >= 1.0 && events[0].0 <= 2.5, "{:?}", events);
+        assert!(events[0].1 >= 5.5 && events[0].1 <= 7.0, "{:?}", events);
+
+        // the noise alone has no hum
+        let (events, _) = detector.compute(signal[..2 * 8000].to_vec()).unwrap();
+        assert!(events.is_empty());
+    }
+}
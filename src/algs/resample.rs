@@ -0,0 +1,512 @@
+use std::f64::consts::PI;
+
+#[cfg(feature = "python")]
+use pyo3::{PyResult, Python};
+
+use super::{check_choice, Algorithm};
+use crate::json::{self, FromJson, Json, ToJson};
+#[cfg(feature = "python")]
+use crate::pymod::array::{Array, ArrayView};
+#[cfg(feature = "python")]
+use crate::pymod::state::pymethods_with_params;
+use crate::{Error, Result};
+
+/// zero crossings on each side of the windowed sinc evaluated for every output sample
+const SINC_ZEROS: usize = 32;
+
+/// zero crossings on each side of the windowed sinc tabulated for polyphase filters
+const POLYPHASE_ZEROS: usize = 16;
+
+/// largest number of phases of a polyphase filter
+const MAX_PHASES: usize = 4096;
+
+/// cutoff relative to the lower of the Nyquist frequencies, leaving room for the transition band
+const ROLLOFF: f64 = 0.95;
+
+python_class! {
+    /// Convert a signal between sample rates
+    ///
+    /// Linear interpolation is the fastest, but aliases and dulls high frequencies.
+    /// Polyphase filtering tabulates a windowed sinc for each phase of a rational ratio of
+    /// integer rates like 44100/48000, and falls back to the windowed sinc for other rates.
+    /// The windowed sinc is evaluated for every output sample, with the longest filter.
+    #[pyclass(module = "muslib", get_all)]
+    pub struct Resample {
+        /// Input: list[float] -- audio signal
+        #[pyo3(set)]
+        pub signal: Vec<f64>,
+
+        /// Output: Optional[list[float]] -- signal at the output sample rate
+        #[pyo3(set)]
+        pub resampled: Option<Vec<f64>>,
+
+        /// Param: float -- sampling rate of the input signal in Hz (default: 48000)
+        #[pyo3(set)]
+        pub input_sample_rate: f64,
+        /// Param: float -- sampling rate of the output signal in Hz (default: 44100)
+        #[pyo3(set)]
+        pub output_sample_rate: f64,
+        /// Param: str -- interpolation, one of {linear, polyphase, sinc} (default: polyphase)
+        pub quality: String,
+    }
+}
+
+#[cfg(feature = "python")]
+pymethods_with_params! {
+    impl Resample {
+        #[new]
+        #[pyo3(signature = (
+            input_sample_rate=48000.0,
+            output_sample_rate=44100.0,
+            quality="polyphase",
+        ))]
+        fn pynew(input_sample_rate: f64, output_sample_rate: f64, quality: &str) -> PyResult<Self> {
+            check_quality(quality)?;
+            Ok(Self::with_params(ResampleParams {
+                input_sample_rate,
+                output_sample_rate,
+                quality: quality.into(),
+            }))
+        }
+
+        #[setter(quality)]
+        fn set_quality(&mut self, quality: &str) -> PyResult<()> {
+            check_quality(quality)?;
+            self.quality = quality.into();
+            Ok(())
+        }
+
+        /// Compute the Algorithm
+        ///
+        /// Inputs:
+        ///   - signal: list[float]
+        ///
+        /// Outputs:
+        ///   - resampled: list[float]
+        ///
+        /// See data descriptors for more details.
+        #[pyo3(name = "compute", signature = (signal=None))]
+        fn pycompute(&mut self, py: Python<'_>, signal: Option<ArrayView<f64>>) -> PyResult<Array<f64>> {
+            if let Some(arg) = signal {
+                arg.assign_to(&mut self.signal)
+            }
+
+            py.allow_threads(|| self.run())?;
+
+            Ok(self.resampled.as_ref().unwrap().clone().into())
+        }
+
+        fn __call__(&mut self, py: Python<'_>) -> PyResult<()> {
+            Ok(py.allow_threads(|| self.run())?)
+        }
+    }
+
+    params = ["input_sample_rate", "output_sample_rate", "quality"];
+    async = compute_async;
+
+    fn reset(&mut self) {
+        self.resampled = None;
+    }
+}
+
+/// Parameters of the Resample, see its data descriptors for details
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResampleParams {
+    /// sampling rate of the input signal in Hz
+    pub input_sample_rate: f64,
+    /// sampling rate of the output signal in Hz
+    pub output_sample_rate: f64,
+    /// interpolation, one of {linear, polyphase, sinc}
+    pub quality: String,
+}
+
+impl Default for ResampleParams {
+    fn default() -> Self {
+        ResampleParams {
+            input_sample_rate: 48000.0,
+            output_sample_rate: 44100.0,
+            quality: "polyphase".into(),
+        }
+    }
+}
+
+impl ToJson for ResampleParams {
+    fn to_json(&self) -> Json {
+        Json::object([
+            ("input_sample_rate", self.input_sample_rate.to_json()),
+            ("output_sample_rate", self.output_sample_rate.to_json()),
+            ("quality", self.quality.to_json()),
+        ])
+    }
+}
+
+impl FromJson for ResampleParams {
+    fn from_json(json: &Json) -> Result<Self> {
+        let mut params = Self::default();
+        for (key, value) in json.fields()? {
+            match key.as_str() {
+                "input_sample_rate" => params.input_sample_rate = FromJson::from_json(value)?,
+                "output_sample_rate" => params.output_sample_rate = FromJson::from_json(value)?,
+                "quality" => params.quality = json::checked(value, |q: &String| check_quality(q))?,
+                _ => return Err(json::unknown_key(key)),
+            }
+        }
+        Ok(params)
+    }
+}
+
+/// Builder of a Resample starting from default parameters, see Resample::builder()
+#[derive(Debug, Clone, Default)]
+pub struct ResampleBuilder {
+    params: ResampleParams,
+}
+
+impl ResampleBuilder {
+    /// sampling rate of the input signal in Hz
+    pub fn input_sample_rate(mut self, input_sample_rate: f64) -> Self {
+        self.params.input_sample_rate = input_sample_rate;
+        self
+    }
+
+    /// sampling rate of the output signal in Hz
+    pub fn output_sample_rate(mut self, output_sample_rate: f64) -> Self {
+        self.params.output_sample_rate = output_sample_rate;
+        self
+    }
+
+    /// interpolation, one of {linear, polyphase, sinc}
+    pub fn quality(mut self, quality: &str) -> Self {
+        self.params.quality = quality.into();
+        self
+    }
+
+    /// create the Resample with the parameters set so far
+    pub fn build(self) -> Resample {
+        Resample::with_params(self.params)
+    }
+}
+
+impl Algorithm for Resample {
+    /// audio signal
+    type Input = Vec<f64>;
+    /// signal at the output sample rate
+    type Output = Vec<f64>;
+    type Params = ResampleParams;
+
+    fn with_params(params: ResampleParams) -> Self {
+        Resample {
+            signal: Vec::new(),
+
+            resampled: None,
+
+            input_sample_rate: params.input_sample_rate,
+            output_sample_rate: params.output_sample_rate,
+            quality: params.quality,
+        }
+    }
+
+    fn compute(&mut self, signal: Vec<f64>) -> Result<Vec<f64>> {
+        self.signal = signal;
+        self.run()?;
+        Ok(self.resampled.take().unwrap())
+    }
+}
+
+impl Resample {
+    /// start building a Resample from default parameters
+    pub fn builder() -> ResampleBuilder {
+        ResampleBuilder::default()
+    }
+
+    /// resample the signal and set the output
+    fn run(&mut self) -> Result<()> {
+        let quality = Quality::from_str(&self.quality);
+        let resampled = resample(
+            &self.signal,
+            self.input_sample_rate,
+            self.output_sample_rate,
+            &quality,
+        )?;
+
+        // Output
+        self.resampled = Some(resampled);
+        Ok(())
+    }
+}
+
+/// check that the interpolation is known
+fn check_quality(quality: &str) -> Result<()> {
+    check_choice("quality", quality, &["linear", "polyphase", "sinc"])
+}
+
+/// interpolation used to convert between sample rates
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Quality {
+    /// straight lines between neighbouring samples
+    Linear,
+    /// windowed sinc tabulated for each phase of a rational ratio of rates
+    Polyphase,
+    /// windowed sinc evaluated for every output sample
+    Sinc,
+}
+
+impl Quality {
+    fn from_str(quality: &str) -> Self {
+        match quality {
+            "linear" => Quality::Linear,
+            "sinc" => Quality::Sinc,
+            _ => Quality::Polyphase,
+        }
+    }
+}
+
+/// convert a signal from the input to the output sample rate
+///
+/// The output has a sample for every period of the output rate within the input.
+/// Fails with Error::InvalidParam unless both rates are positive.
+pub fn resample(
+    signal: &[f64],
+    input_sample_rate: f64,
+    output_sample_rate: f64,
+    quality: &Quality,
+) -> Result<Vec<f64>> {
+    let mut resampler = Resampler::new(input_sample_rate, output_sample_rate, *quality)?;
+    let mut resampled = resampler.push(signal);
+    resampled.append(&mut resampler.flush());
+    Ok(resampled)
+}
+
+/// Resampler converts a signal pushed block by block between sample rates.
+///
+/// An output sample is produced as soon as all the input samples its filter reaches have
+/// been pushed, the rest of them once the signal is flushed at its end.
+/// The output is the same as resample() of the whole signal.
+pub struct Resampler {
+    ratio: f64,
+    quality: Quality,
+    cutoff: f64,
+    zeros: usize,
+    width: isize,
+    taps: Option<Phases>,
+    buffer: Vec<f64>,
+    start: usize,
+    received: usize,
+    produced: usize,
+}
+
+/// polyphase filter for a ratio of rates up/down, with a table of taps for each phase
+struct Phases {
+    up: usize,
+    down: usize,
+    taps: Vec<Vec<f64>>,
+}
+
+impl Resampler {
+    /// create a Resampler from the input to the output sample rate
+    ///
+    /// Fails with Error::InvalidParam unless both rates are positive.
+    pub fn new(input_sample_rate: f64, output_sample_rate: f64, quality: Quality) -> Result<Self> {
+        let valid = |rate: f64| rate.is_finite() && rate > 0.0;
+        if !valid(input_sample_rate) || !valid(output_sample_rate) {
+            return Err(Error::InvalidParam(format!(
+                "sample rates must be positive, got {} and {}",
+                input_sample_rate, output_sample_rate
+            )));
+        }
+        let ratio = output_sample_rate / input_sample_rate;
+        let cutoff = ROLLOFF * ratio.min(1.0);
+        let zeros = match quality {
+            Quality::Sinc => SINC_ZEROS,
+            _ => POLYPHASE_ZEROS,
+        };
+        let width = match quality {
+            Quality::Linear => 1,
+            _ => (zeros as f64 / cutoff).ceil() as isize,
+        };
+        let taps = match quality {
+            Quality::Polyphase => {
+                phases(input_sample_rate, output_sample_rate).map(|(up, down)| {
+                    let taps = (0..up)
+                        .map(|phase| {
+                            let fraction = phase as f64 / up as f64;
+                            (0..2 * width)
+                                .map(|j| kernel(fraction + (width - 1 - j) as f64, cutoff, zeros))
+                                .collect()
+                        })
+                        .collect();
+                    Phases { up, down, taps }
+                })
+            }
+            _ => None,
+        };
+        Ok(Resampler {
+            ratio,
+            quality,
+            cutoff,
+            zeros,
+            width,
+            taps,
+            buffer: Vec::new(),
+            start: 0,
+            received: 0,
+            produced: 0,
+        })
+    }
+
+    /// push the next block of the input and get the output samples it completes
+    pub fn push(&mut self, block: &[f64]) -> Vec<f64> {
+        if self.ratio == 1.0 {
+            return block.to_vec();
+        }
+        self.buffer.extend_from_slice(block);
+        self.received += block.len();
+
+        let mut output = Vec::new();
+        while self.base(self.produced) + self.width < self.received as isize {
+            output.push(self.sample(self.produced));
+            self.produced += 1;
+        }
+
+        // input samples before the filter of the next output sample are no longer needed
+        let keep = (self.base(self.produced) - self.width + 1).max(0) as usize;
+        if keep > self.start {
+            let drop = (keep - self.start).min(self.buffer.len());
+            self.buffer.drain(..drop);
+            self.start += drop;
+        }
+        output
+    }
+
+    /// get the remaining output samples at the end of the input
+    pub fn flush(&mut self) -> Vec<f64> {
+        if self.ratio == 1.0 {
+            return Vec::new();
+        }
+        let length = (self.received as f64 * self.ratio).ceil() as usize;
+        let output = (self.produced..length).map(|k| self.sample(k)).collect();
+        self.produced = self.produced.max(length);
+        output
+    }
+
+    /// index of the input sample at or just before an output sample
+    fn base(&self, k: usize) -> isize {
+        match &self.taps {
+            Some(phases) => (k * phases.down / phases.up) as isize,
+            None => (k as f64 / self.ratio).floor() as isize,
+        }
+    }
+
+    /// output sample at an index, with the input outside of the signal left out
+    fn sample(&self, k: usize) -> f64 {
+        let get = |i: isize| {
+            (i >= 0 && (i as usize) < self.received).then(|| self.buffer[i as usize - self.start])
+        };
+        let base = self.base(k);
+        if let Some(phases) = &self.taps {
+            let taps = &phases.taps[k * phases.down % phases.up];
+            let first = base - self.width + 1;
+            return taps
+                .iter()
+                .enumerate()
+                .filter_map(|(j, tap)| get(first + j as isize).map(|x| x * tap))
+                .sum();
+        }
+        let position = k as f64 / self.ratio;
+        match self.quality {
+            Quality::Linear => {
+                let a = get(base).unwrap_or_default();
+                let b = get(base + 1).unwrap_or(a);
+                a + (position - base as f64) * (b - a)
+            }
+            _ => ((base - self.width + 1).max(0)..=base + self.width)
+                .filter_map(|i| {
+                    get(i).map(|x| x * kernel(position - i as f64, self.cutoff, self.zeros))
+                })
+                .sum(),
+        }
+    }
+}
+
+/// reduced ratio of integer sample rates as up/down, if it needs few enough phases
+fn phases(input_sample_rate: f64, output_sample_rate: f64) -> Option<(usize, usize)> {
+    if input_sample_rate.fract() != 0.0 || output_sample_rate.fract() != 0.0 {
+        return None;
+    }
+    let (a, b) = (input_sample_rate as usize, output_sample_rate as usize);
+    let (mut x, mut y) = (a, b);
+    while y != 0 {
+        (x, y) = (y, x % y);
+    }
+    let (up, down) = (b / x, a / x);
+    (up <= MAX_PHASES).then_some((up, down))
+}
+
+/// Blackman windowed sinc at a distance t in input samples, low-pass at a cutoff relative to Nyquist
+fn kernel(t: f64, cutoff: f64, zeros: usize) -> f64 {
+    let x = t * cutoff;
+    let z = zeros as f64;
+    if x.abs() >= z {
+        return 0.0;
+    }
+    let sinc = if x == 0.0 {
+        1.0
+    } else {
+        (PI * x).sin() / (PI * x)
+    };
+    let window = 0.42 + 0.5 * (PI * x / z).cos() + 0.08 * (2.0 * PI * x / z).cos();
+    cutoff * sinc * window
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Algorithm, Quality, Resample, Resampler};
+
+    fn sine(freq: f64, sample_rate: f64, n: usize) -> Vec<f64> {
+        (0..n)
+            .map(|i| (2.0 * std::f64::consts::PI * freq * i as f64 / sample_rate).sin())
+            .collect()
+    }
+
+    #[test]
+    fn resample() {
+        let input = sine(1000.0, 48000.0, 4800);
+        let expected = sine(1000.0, 44100.0, 4410);
+
+        for (quality, tolerance) in [("linear", 5e-3), ("polyphase", 1e-3), ("sinc", 1e-3)] {
+            let mut resample = Resample::builder().quality(quality).build();
+            let output = resample.compute(input.clone()).unwrap();
+            assert_eq!(output.len(), expected.len(), "test {}", quality);
+            // away from the edges, where the filters reach outside of the signal
+            for i in 500..3900 {
+                let error = (output[i] - expected[i]).abs();
+                assert!(error < tolerance, "test {} {} {}", quality, i, error);
+            }
+        }
+
+        // a tone above the Nyquist frequency of the output is filtered out instead of aliased
+        let input = sine(6000.0, 48000.0, 4800);
+        let rms = |x: &[f64]| (x.iter().map(|x| x * x).sum::<f64>() / x.len() as f64).sqrt();
+        for (quality, min, max) in [("linear", 0.5, 1.0), ("polyphase", 0.0, 0.01)] {
+            let mut resample = Resample::builder()
+                .output_sample_rate(8000.0)
+                .quality(quality)
+                .build();
+            let output = resample.compute(input.clone()).unwrap();
+            let level = rms(&output[100..700]);
+            assert!(level >= min && level < max, "test {} {}", quality, level);
+        }
+
+        // streaming in blocks of any size gives the same output as the whole signal
+        let input = sine(1000.0, 44100.0, 2000);
+        for quality in [Quality::Linear, Quality::Polyphase, Quality::Sinc] {
+            let expected = super::resample(&input, 44100.0, 16000.0, &quality).unwrap();
+            let mut resampler = Resampler::new(44100.0, 16000.0, quality).unwrap();
+            let mut output: Vec<f64> = input.chunks(77).flat_map(|x| resampler.push(x)).collect();
+            output.append(&mut resampler.flush());
+            assert_eq!(output, expected, "test {:?}", quality);
+        }
+
+        let mut resample = Resample::builder().input_sample_rate(0.0).build();
+        assert!(resample.compute(vec![0.0; 16]).is_err());
+    }
+}
@@ -0,0 +1,849 @@
+#[cfg(feature = "python")]
+use pyo3::Python;
+
+use super::{check_choice, check_positive, check_range, Algorithm, MAX_LENGTH};
+use crate::json::{self, FromJson, Json, ToJson};
+#[cfg(feature = "python")]
+use crate::pymod::array::{Array, ArrayView};
+#[cfg(feature = "python")]
+use crate::pymod::state::pymethods_with_params;
+use crate::Result;
+#[cfg(feature = "python")]
+use pyo3::PyResult;
+
+/// highest order of the linear prediction models
+const MAX_ORDER: usize = 1024;
+
+/// regions of a signal given as pairs of start and end samples
+pub type Regions = Vec<(usize, usize)>;
+
+python_class! {
+    /// Detect and repair impulsive clicks with linear prediction
+    #[pyclass(module = "muslib", get_all, set_all)]
+    pub struct Declicker {
+        /// Input: list[float] -- audio signal
+        pub signal: Vec<f64>,
+
+        /// Output: Optional[list[float]] -- restored audio signal
+        pub restored: Option<Vec<f64>>,
+        /// Output: Optional[list[tuple[int, int]]] -- repaired regions as [start, end) sample ranges
+        pub regions: Option<Vec<(usize, usize)>>,
+
+        /// Param: int -- order of the linear prediction model, from 1 to 1024 (default: 16)
+        pub order: usize,
+        /// Param: float -- detection threshold as a multiple of the median prediction error, above 0 (default: 10)
+        pub threshold: f64,
+        /// Param: int -- number of samples repaired around each detection (default: 2)
+        pub margin: usize,
+        /// Param: str -- interpolation method, one of {cubic, ar} (default: ar)
+        pub method: String,
+    }
+}
+
+#[cfg(feature = "python")]
+pymethods_with_params! {
+    impl Declicker {
+        #[new]
+        #[pyo3(signature = (
+            order=16,
+            threshold=10.0,
+            margin=2,
+            method="ar",
+        ))]
+        fn pynew(order: usize, threshold: f64, margin: usize, method: &str) -> PyResult<Self> {
+            let declicker = Self::with_params(DeclickerParams {
+                order,
+                threshold,
+                margin,
+                method: method.into(),
+            });
+            declicker.check()?;
+            Ok(declicker)
+        }
+
+        /// Compute the Algorithm
+        ///
+        /// Inputs:
+        ///   - signal: list[float]
+        ///
+        /// Outputs:
+        ///   - restored: list[float]
+        ///   - regions: list[tuple[int, int]]
+        ///
+        /// See data descriptors for more details.
+        #[pyo3(name = "compute", signature = (signal=None))]
+        fn pycompute(&mut self, py: Python<'_>, signal: Option<ArrayView<f64>>) -> PyResult<(Array<f64>, Regions)> {
+            if let Some(arg) = signal {
+                arg.assign_to(&mut self.signal)
+            }
+
+            py.allow_threads(|| self.run())?;
+
+            Ok((
+                self.restored.as_ref().unwrap().clone().into(),
+                self.regions.as_ref().unwrap().clone(),
+            ))
+        }
+
+        fn __call__(&mut self, py: Python<'_>) -> PyResult<()> {
+            Ok(py.allow_threads(|| self.run())?)
+        }
+    }
+
+    params = ["order", "threshold", "margin", "method"];
+    async = compute_async;
+
+    fn reset(&mut self) {
+        self.restored = None;
+        self.regions = None;
+    }
+}
+
+/// Parameters of the Declicker, see its data descriptors for details
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeclickerParams {
+    /// order of the linear prediction model
+    pub order: usize,
+    /// detection threshold as a multiple of the median prediction error
+    pub threshold: f64,
+    /// number of samples repaired around each detection
+    pub margin: usize,
+    /// interpolation method, one of {cubic, ar}
+    pub method: String,
+}
+
+impl Default for DeclickerParams {
+    fn default() -> Self {
+        DeclickerParams {
+            order: 16,
+            threshold: 10.0,
+            margin: 2,
+            method: "ar".into(),
+        }
+    }
+}
+
+impl ToJson for DeclickerParams {
+    fn to_json(&self) -> Json {
+        Json::object([
+            ("order", self.order.to_json()),
+            ("threshold", self.threshold.to_json()),
+            ("margin", self.margin.to_json()),
+            ("method", self.method.to_json()),
+        ])
+    }
+}
+
+impl FromJson for DeclickerParams {
+    fn from_json(json: &Json) -> Result<Self> {
+        let mut params = Self::default();
+        for (key, value) in json.fields()? {
+            match key.as_str() {
+                "order" => params.order = FromJson::from_json(value)?,
+                "threshold" => params.threshold = FromJson::from_json(value)?,
+                "margin" => params.margin = FromJson::from_json(value)?,
+                "method" => params.method = json::checked(value, |m: &String| check_method(m))?,
+                _ => return Err(json::unknown_key(key)),
+            }
+        }
+        Ok(params)
+    }
+}
+
+/// Builder of a Declicker starting from default parameters, see Declicker::builder()
+#[derive(Debug, Clone, Default)]
+pub struct DeclickerBuilder {
+    params: DeclickerParams,
+}
+
+impl DeclickerBuilder {
+    /// order of the linear prediction model
+    pub fn order(mut self, order: usize) -> Self {
+        self.params.order = order;
+        self
+    }
+
+    /// detection threshold as a multiple of the median prediction error
+    pub fn threshold(mut self, threshold: f64) -> Self {
+        self.params.threshold = threshold;
+        self
+    }
+
+    /// number of samples repaired around each detection
+    pub fn margin(mut self, margin: usize) -> Self {
+        self.params.margin = margin;
+        self
+    }
+
+    /// interpolation method, one of {cubic, ar}
+    pub fn method(mut self, method: &str) -> Self {
+        self.params.method = method.into();
+        self
+    }
+
+    /// create the Declicker with the parameters set so far
+    pub fn build(self) -> Declicker {
+        Declicker::with_params(self.params)
+    }
+}
+
+impl Algorithm for Declicker {
+    /// audio signal
+    type Input = Vec<f64>;
+    /// restored audio signal and the repaired regions
+    type Output = (Vec<f64>, Regions);
+    type Params = DeclickerParams;
+
+    fn with_params(params: DeclickerParams) -> Self {
+        Declicker {
+            signal: Vec::new(),
+
+            restored: None,
+            regions: None,
+
+            order: params.order,
+            threshold: params.threshold,
+            margin: params.margin,
+            method: params.method,
+        }
+    }
+
+    fn compute(&mut self, signal: Vec<f64>) -> Result<Self::Output> {
+        self.signal = signal;
+        self.run()?;
+        Ok((self.restored.take().unwrap(), self.regions.take().unwrap()))
+    }
+}
+
+impl Declicker {
+    /// start building a Declicker from default parameters
+    pub fn builder() -> DeclickerBuilder {
+        DeclickerBuilder::default()
+    }
+
+    /// check the parameters, with Error::InvalidParam for the first one out of its range
+    fn check(&self) -> Result<()> {
+        check_order(self.order)?;
+        check_positive("threshold", self.threshold)?;
+        check_range("margin", self.margin as f64, 0.0, MAX_LENGTH as f64)?;
+        check_method(&self.method)
+    }
+
+    /// detect and repair clicks in the signal and set the outputs
+    ///
+    /// Fails with Error::InvalidParam for parameters out of their ranges.
+    fn run(&mut self) -> Result<()> {
+        self.check()?;
+        let regions = detect_clicks(&self.signal, self.order, self.threshold, self.margin);
+
+        let mut restored = self.signal.clone();
+        let method = Interpolation::from_str(&self.method, self.order)?;
+        interpolate(&mut restored, &regions, &method);
+
+        // Output
+        self.restored = Some(restored);
+        self.regions = Some(regions);
+        Ok(())
+    }
+}
+
+python_class! {
+    /// Detect and repair clipped regions by interpolating over them
+    #[pyclass(module = "muslib", get_all, set_all)]
+    pub struct Declipper {
+        /// Input: list[float] -- audio signal
+        pub signal: Vec<f64>,
+
+        /// Output: Optional[list[float]] -- restored audio signal
+        pub restored: Option<Vec<f64>>,
+        /// Output: Optional[list[tuple[int, int]]] -- repaired regions as [start, end) sample ranges
+        pub regions: Option<Vec<(usize, usize)>>,
+
+        /// Param: float -- absolute level considered clipped, 0 to use the signal peak (default: 0)
+        pub clip_level: f64,
+        /// Param: int -- minimum number of consecutive samples at the clip level, at least 1 (default: 3)
+        pub min_run: usize,
+        /// Param: int -- order of the linear prediction model for ar interpolation, from 1 to 1024 (default: 16)
+        pub order: usize,
+        /// Param: str -- interpolation method, one of {cubic, ar} (default: cubic)
+        pub method: String,
+    }
+}
+
+#[cfg(feature = "python")]
+pymethods_with_params! {
+    impl Declipper {
+        #[new]
+        #[pyo3(signature = (
+            clip_level=0.0,
+            min_run=3,
+            order=16,
+            method="cubic",
+        ))]
+        fn pynew(clip_level: f64, min_run: usize, order: usize, method: &str) -> PyResult<Self> {
+            let declipper = Self::with_params(DeclipperParams {
+                clip_level,
+                min_run,
+                order,
+                method: method.into(),
+            });
+            declipper.check()?;
+            Ok(declipper)
+        }
+
+        /// Compute the Algorithm
+        ///
+        /// Inputs:
+        ///   - signal: list[float]
+        ///
+        /// Outputs:
+        ///   - restored: list[float]
+        ///   - regions: list[tuple[int, int]]
+        ///
+        /// See data descriptors for more details.
+        #[pyo3(name = "compute", signature = (signal=None))]
+        fn pycompute(&mut self, py: Python<'_>, signal: Option<ArrayView<f64>>) -> PyResult<(Array<f64>, Regions)> {
+            if let Some(arg) = signal {
+                arg.assign_to(&mut self.signal)
+            }
+
+            py.allow_threads(|| self.run())?;
+
+            Ok((
+                self.restored.as_ref().unwrap().clone().into(),
+                self.regions.as_ref().unwrap().clone(),
+            ))
+        }
+
+        fn __call__(&mut self, py: Python<'_>) -> PyResult<()> {
+            Ok(py.allow_threads(|| self.run())?)
+        }
+    }
+
+    params = ["clip_level", "min_run", "order", "method"];
+    async = compute_async;
+
+    fn reset(&mut self) {
+        self.restored = None;
+        self.regions = None;
+    }
+}
+
+/// Parameters of the Declipper, see its data descriptors for details
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeclipperParams {
+    /// absolute level considered clipped, 0 to use the signal peak
+    pub clip_level: f64,
+    /// minimum number of consecutive samples at the clip level
+    pub min_run: usize,
+    /// order of the linear prediction model for ar interpolation
+    pub order: usize,
+    /// interpolation method, one of {cubic, ar}
+    pub method: String,
+}
+
+impl Default for DeclipperParams {
+    fn default() -> Self {
+        DeclipperParams {
+            clip_level: 0.0,
+            min_run: 3,
+            order: 16,
+            method: "cubic".into(),
+        }
+    }
+}
+
+impl ToJson for DeclipperParams {
+    fn to_json(&self) -> Json {
+        Json::object([
+            ("clip_level", self.clip_level.to_json()),
+            ("min_run", self.min_run.to_json()),
+            ("order", self.order.to_json()),
+            ("method", self.method.to_json()),
+        ])
+    }
+}
+
+impl FromJson for DeclipperParams {
+    fn from_json(json: &Json) -> Result<Self> {
+        let mut params = Self::default();
+        for (key, value) in json.fields()? {
+            match key.as_str() {
+                "clip_level" => params.clip_level = FromJson::from_json(value)?,
+                "min_run" => params.min_run = FromJson::from_json(value)?,
+                "order" => params.order = FromJson::from_json(value)?,
+                "method" => params.method = json::checked(value, |m: &String| check_method(m))?,
+                _ => return Err(json::unknown_key(key)),
+            }
+        }
+        Ok(params)
+    }
+}
+
+/// Builder of a Declipper starting from default parameters, see Declipper::builder()
+#[derive(Debug, Clone, Default)]
+pub struct DeclipperBuilder {
+    params: DeclipperParams,
+}
+
+impl DeclipperBuilder {
+    /// absolute level considered clipped, 0 to use the signal peak
+    pub fn clip_level(mut self, clip_level: f64) -> Self {
+        self.params.clip_level = clip_level;
+        self
+    }
+
+    /// minimum number of consecutive samples at the clip level
+    pub fn min_run(mut self, min_run: usize) -> Self {
+        self.params.min_run = min_run;
+        self
+    }
+
+    /// order of the linear prediction model for ar interpolation
+    pub fn order(mut self, order: usize) -> Self {
+        self.params.order = order;
+        self
+    }
+
+    /// interpolation method, one of {cubic, ar}
+    pub fn method(mut self, method: &str) -> Self {
+        self.params.method = method.into();
+        self
+    }
+
+    /// create the Declipper with the parameters set so far
+    pub fn build(self) -> Declipper {
+        Declipper::with_params(self.params)
+    }
+}
+
+impl Algorithm for Declipper {
+    /// audio signal
+    type Input = Vec<f64>;
+    /// restored audio signal and the repaired regions
+    type Output = (Vec<f64>, Regions);
+    type Params = DeclipperParams;
+
+    fn with_params(params: DeclipperParams) -> Self {
+        Declipper {
+            signal: Vec::new(),
+
+            restored: None,
+            regions: None,
+
+            clip_level: params.clip_level,
+            min_run: params.min_run,
+            order: params.order,
+            method: params.method,
+        }
+    }
+
+    fn compute(&mut self, signal: Vec<f64>) -> Result<Self::Output> {
+        self.signal = signal;
+        self.run()?;
+        Ok((self.restored.take().unwrap(), self.regions.take().unwrap()))
+    }
+}
+
+impl Declipper {
+    /// start building a Declipper from default parameters
+    pub fn builder() -> DeclipperBuilder {
+        DeclipperBuilder::default()
+    }
+
+    /// check the parameters, with Error::InvalidParam for the first one out of its range
+    fn check(&self) -> Result<()> {
+        check_range("clip_level", self.clip_level, 0.0, f64::INFINITY)?;
+        check_range("min_run", self.min_run as f64, 1.0, MAX_LENGTH as f64)?;
+        check_order(self.order)?;
+        check_method(&self.method)
+    }
+
+    /// detect and repair clipped regions in the signal and set the outputs
+    ///
+    /// Fails with Error::InvalidParam for parameters out of their ranges.
+    fn run(&mut self) -> Result<()> {
+        self.check()?;
+        let regions = detect_clipping(&self.signal, self.clip_level, self.min_run);
+
+        let mut restored = self.signal.clone();
+        let method = Interpolation::from_str(&self.method, self.order)?;
+        interpolate(&mut restored, &regions, &method);
+
+        // Output
+        self.restored = Some(restored);
+        self.regions = Some(regions);
+        Ok(())
+    }
+}
+
+/// interpolation methods used to fill in damaged samples
+pub enum Interpolation {
+    /// cubic polynomial through two samples on each side of the gap
+    Cubic,
+    /// forward and backward linear prediction of the given order, cross-faded over the gap
+    AR(usize),
+}
+
+impl Interpolation {
+    /// method given by name, with Error::InvalidParam for names other than cubic and ar
+    fn from_str(method: &str, order: usize) -> Result<Self> {
+        check_choice("method", method, &["cubic", "ar"])?;
+        Ok(match method {
+            "ar" => Interpolation::AR(order),
+            _ => Interpolation::Cubic,
+        })
+    }
+}
+
+/// check that the interpolation method is known
+fn check_method(method: &str) -> Result<()> {
+    Interpolation::from_str(method, 0).map(|_| ())
+}
+
+/// check that the order of a linear prediction model is from 1 to MAX_ORDER
+fn check_order(order: usize) -> Result<()> {
+    check_range("order", order as f64, 1.0, MAX_ORDER as f64)
+}
+
+/// linear prediction coefficients with the autocorrelation method
+///
+/// The prediction is `x[n] = sum(a[k] * x[n - 1 - k])`.
+pub fn lpc(signal: &[f64], order: usize) -> Vec<f64> {
+    let r: Vec<f64> = (0..=order)
+        .map(|lag| {
+            signal
+                .iter()
+                .zip(signal.iter().skip(lag))
+                .map(|(x, y)| x * y)
+                .sum()
+        })
+        .collect();
+
+    // Levinson-Durbin recursion
+    let mut a = vec![0.0; order];
+    let mut error = r[0];
+    for i in 0..order {
+        if error <= 0.0 {
+            break;
+        }
+
+        let mut k = r[i + 1];
+        for j in 0..i {
+            k -= a[j] * r[i - j];
+        }
+        k /= error;
+
+        let previous = a.clone();
+        a[i] = k;
+        for j in 0..i {
+            a[j] = previous[j] - k * previous[i - 1 - j];
+        }
+        error *= 1.0 - k * k;
+    }
+    a
+}
+
+/// find clicks as outliers of the linear prediction error
+///
+/// Returns merged [start, end) regions extended by the margin on each side.
+pub fn detect_clicks(
+    signal: &[f64],
+    order: usize,
+    threshold: f64,
+    margin: usize,
+) -> Vec<(usize, usize)> {
+    let flagged: Vec<usize> = prediction_outliers(signal, order, threshold)
+        .into_iter()
+        .map(|(i, _)| i)
+        .collect();
+    regions(&flagged, margin, signal.len())
+}
+
+/// samples whose linear prediction error exceeds the threshold times the median error
+///
+/// Returns the position of each outlier with its error as a multiple of the median,
+/// the model is fitted to blocks of the signal, so it follows changes in the sound.
+pub fn prediction_outliers(signal: &[f64], order: usize, threshold: f64) -> Vec<(usize, f64)> {
+    let block = std::cmp::max(4096, 8 * order);
+    let mut outliers = Vec::new();
+
+    let mut start = 0;
+    while start < signal.len() {
+        let end = std::cmp::min(start + block, signal.len());
+        let x = &signal[start..end];
+        let a = lpc(x, order);
+
+        let error: Vec<f64> = (order..x.len())
+            .map(|n| {
+                let prediction: f64 = a.iter().enumerate().map(|(k, a)| a * x[n - 1 - k]).sum();
+                (x[n] - prediction).abs()
+            })
+            .collect();
+
+        let mut sorted = error.clone();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+        let median = sorted.get(sorted.len() / 2).copied().unwrap_or(0.0);
+
+        if median > 0.0 {
+            for (i, e) in error.iter().enumerate() {
+                if *e > threshold * median {
+                    outliers.push((start + order + i, e / median));
+                }
+            }
+        }
+        start = end;
+    }
+    outliers
+}
+
+/// find runs of samples at or above the clip level
+///
+/// A clip level of 0 or less uses the peak absolute value of the signal.
+pub fn detect_clipping(signal: &[f64], clip_level: f64, min_run: usize) -> Vec<(usize, usize)> {
+    let level = if clip_level > 0.0 {
+        clip_level
+    } else {
+        signal.iter().fold(0.0, |m: f64, x| m.max(x.abs()))
+    };
+    if level == 0.0 {
+        return Vec::new();
+    }
+
+    let mut regions = Vec::new();
+    let mut run_start = None;
+    for i in 0..=signal.len() {
+        let clipped = i < signal.len() && signal[i].abs() >= level * (1.0 - 1e-9);
+        match (clipped, run_start) {
+            (true, None) => run_start = Some(i),
+            (false, Some(s)) => {
+                if i - s >= std::cmp::max(min_run, 1) {
+                    regions.push((s, i));
+                }
+                run_start = None;
+            }
+            _ => {}
+        }
+    }
+    regions
+}
+
+fn regions(flagged: &[usize], margin: usize, len: usize) -> Vec<(usize, usize)> {
+    let mut regions: Vec<(usize, usize)> = Vec::new();
+    for i in flagged {
+        let start = i.saturating_sub(margin);
+        let end = std::cmp::min(i + margin + 1, len);
+        match regions.last_mut() {
+            Some(last) if start <= last.1 => last.1 = std::cmp::max(last.1, end),
+            _ => regions.push((start, end)),
+        }
+    }
+    regions
+}
+
+/// replace samples in the given [start, end) regions with interpolated values
+pub fn interpolate(signal: &mut [f64], regions: &[(usize, usize)], method: &Interpolation) {
+    for (start, end) in regions {
+        let (start, end) = (*start, std::cmp::min(*end, signal.len()));
+        if start >= end {
+            continue;
+        }
+        match method {
+            Interpolation::AR(order) => {
+                if !ar_fill(signal, start, end, *order) {
+                    cubic_fill(signal, start, end)
+                }
+            }
+            Interpolation::Cubic => cubic_fill(signal, start, end),
+        }
+    }
+}
+
+fn cubic_fill(signal: &mut [f64], start: usize, end: usize) {
+    // known points around the gap
+    let mut points: Vec<(f64, f64)> = Vec::with_capacity(4);
+    for i in [
+        start as isize - 2,
+        start as isize - 1,
+        end as isize,
+        end as isize + 1,
+    ] {
+        if i >= 0 && (i as usize) < signal.len() {
+            points.push((i as f64, signal[i as usize]));
+        }
+    }
+    if points.is_empty() {
+        return;
+    }
+
+    // Lagrange polynomial through all known points
+    for (i, y) in signal.iter_mut().enumerate().take(end).skip(start) {
+        let x = i as f64;
+        *y = 0.0;
+        for (j, (xj, yj)) in points.iter().enumerate() {
+            let mut l = 1.0;
+            for (k, (xk, _)) in points.iter().enumerate() {
+                if j != k {
+                    l *= (x - xk) / (xj - xk);
+                }
+            }
+            *y += yj * l;
+        }
+    }
+}
+
+/// linear prediction coefficients with the covariance method over separate segments
+fn least_squares(segments: &[&[f64]], order: usize) -> Vec<f64> {
+    // normal equations with a small diagonal load for stability
+    let mut m = vec![vec![0.0; order + 1]; order];
+    for x in segments {
+        for n in order..x.len() {
+            for i in 0..order {
+                for j in 0..order {
+                    m[i][j] += x[n - 1 - i] * x[n - 1 - j];
+                }
+                m[i][order] += x[n - 1 - i] * x[n];
+            }
+        }
+    }
+    let load = 1e-9 * (0..order).map(|i| m[i][i]).sum::<f64>().max(1e-12);
+    for (i, row) in m.iter_mut().enumerate() {
+        row[i] += load;
+    }
+
+    // gaussian elimination with partial pivoting
+    for i in 0..order {
+        let pivot = (i..order)
+            .max_by(|a, b| m[*a][i].abs().total_cmp(&m[*b][i].abs()))
+            .unwrap();
+        m.swap(i, pivot);
+        let (upper, lower) = m.split_at_mut(i + 1);
+        let pivot = &upper[i];
+        for row in lower.iter_mut() {
+            let f = row[i] / pivot[i];
+            for (x, p) in row.iter_mut().zip(pivot.iter()).skip(i) {
+                *x -= f * p;
+            }
+        }
+    }
+    let mut a = vec![0.0; order];
+    for i in (0..order).rev() {
+        let mut x = m[i][order];
+        for j in (i + 1)..order {
+            x -= m[i][j] * a[j];
+        }
+        a[i] = x / m[i][i];
+    }
+    a
+}
+
+fn ar_fill(signal: &mut [f64], start: usize, end: usize, order: usize) -> bool {
+    let len = end - start;
+    let context = std::cmp::max(4 * order, 2 * len);
+    if order == 0 || start < context || end + context > signal.len() {
+        return false;
+    }
+
+    let a = least_squares(
+        &[&signal[start - context..start], &signal[end..end + context]],
+        order,
+    );
+
+    let predict = |history: &[f64]| -> f64 {
+        a.iter()
+            .enumerate()
+            .map(|(k, a)| a * history[history.len() - 1 - k])
+            .sum()
+    };
+
+    let mut forward = signal[start - order..start].to_vec();
+    for _ in 0..len {
+        let x = predict(&forward);
+        forward.push(x);
+    }
+
+    let mut backward: Vec<f64> = signal[end..end + order].iter().rev().copied().collect();
+    for _ in 0..len {
+        let x = predict(&backward);
+        backward.push(x);
+    }
+
+    for i in 0..len {
+        let w = (i + 1) as f64 / (len + 1) as f64;
+        let f = forward[order + i];
+        let b = backward[order + len - 1 - i];
+        signal[start + i] = (1.0 - w) * f + w * b;
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{detect_clicks, detect_clipping, Algorithm, Declicker, DeclickerParams, Declipper};
+    use crate::Error;
+
+    fn sine(amplitude: f64, n: usize) -> Vec<f64> {
+        (0..n)
+            .map(|i| amplitude * (2.0 * std::f64::consts::PI * 440.0 * i as f64 / 44100.0).sin())
+            .collect()
+    }
+
+    fn error(x: &[f64], y: &[f64]) -> f64 {
+        x.iter().zip(y.iter()).map(|(x, y)| (x - y).abs()).sum()
+    }
+
+    #[test]
+    fn declick() {
+        let clean = sine(0.5, 8192);
+        let mut signal = clean.clone();
+        signal[3000] += 0.8;
+        signal[6000] -= 0.6;
+
+        let regions = detect_clicks(&signal, 16, 10.0, 2);
+        assert!(regions.iter().any(|r| r.0 <= 3000 && 3000 < r.1));
+        assert!(regions.iter().any(|r| r.0 <= 6000 && 6000 < r.1));
+
+        for method in ["ar", "cubic"] {
+            let mut declicker = Declicker::with_params(DeclickerParams {
+                method: method.into(),
+                ..Default::default()
+            });
+            let (restored, _) = declicker.compute(signal.clone()).unwrap();
+            assert!(error(&restored, &clean) < 0.05, "test {}", method);
+        }
+    }
+
+    #[test]
+    fn declip() {
+        let clean = sine(1.0, 4410);
+        let signal: Vec<f64> = clean.iter().map(|x| x.clamp(-0.9, 0.9)).collect();
+
+        let regions = detect_clipping(&signal, 0.0, 3);
+        assert_eq!(regions.len(), 88);
+
+        let mut declipper = Declipper::new();
+        let (restored, _) = declipper.compute(signal.clone()).unwrap();
+        assert!(error(&restored, &clean) < error(&signal, &clean) / 4.0);
+    }
+
+    #[test]
+    fn invalid_params() {
+        let signal = sine(0.5, 1000);
+        let invalid = |result| matches!(result, Err(Error::InvalidParam(_)));
+
+        // an unknown method isn't replaced with cubic interpolation
+        let mut declicker = Declicker::builder().method("spline").build();
+        assert!(invalid(declicker.compute(signal.clone())));
+        let mut declipper = Declipper::builder().method("AR").build();
+        assert!(invalid(declipper.compute(signal.clone())));
+
+        for order in [0, 100_000] {
+            let mut declicker = Declicker::builder().order(order).build();
+            assert!(invalid(declicker.compute(signal.clone())), "test {}", order);
+        }
+        let mut declicker = Declicker::builder().threshold(f64::NAN).build();
+        assert!(invalid(declicker.compute(signal.clone())));
+        let mut declipper = Declipper::builder().min_run(0).build();
+        assert!(invalid(declipper.compute(signal.clone())));
+    }
+}
@@ -0,0 +1,363 @@
+#[cfg(feature = "python")]
+use pyo3::Python;
+
+use super::{check_range, Algorithm};
+use crate::json::{self, FromJson, Json, ToJson};
+#[cfg(feature = "python")]
+use crate::pymod::array::{Array, ArrayView};
+#[cfg(feature = "python")]
+use crate::pymod::state::pymethods_with_params;
+use crate::Result;
+#[cfg(feature = "python")]
+use pyo3::PyResult;
+
+python_class! {
+    /// Split a stereo signal into mid and side signals
+    #[pyclass(module = "muslib", get_all, set_all)]
+    pub struct MidSide {
+        /// Input: list[float] -- left channel of the stereo signal
+        pub left: Vec<f64>,
+        /// Input: list[float] -- right channel of the stereo signal
+        pub right: Vec<f64>,
+
+        /// Output: Optional[list[float]] -- mid signal, the average of both channels
+        pub mid: Option<Vec<f64>>,
+        /// Output: Optional[list[float]] -- side signal, half the difference of both channels
+        pub side: Option<Vec<f64>>,
+    }
+}
+
+#[cfg(feature = "python")]
+pymethods_with_params! {
+    impl MidSide {
+        #[new]
+        fn pynew() -> Self {
+            Self::with_params(())
+        }
+
+        /// Compute the Algorithm
+        ///
+        /// Inputs:
+        ///   - left: list[float]
+        ///   - right: list[float]
+        ///
+        /// Outputs:
+        ///   - mid: list[float]
+        ///   - side: list[float]
+        ///
+        /// See data descriptors for more details.
+        #[pyo3(name = "compute", signature = (left=None, right=None))]
+        fn pycompute(
+            &mut self,
+            py: Python<'_>,
+            left: Option<ArrayView<f64>>,
+            right: Option<ArrayView<f64>>,
+        ) -> (Array<f64>, Array<f64>) {
+            if let Some(arg) = left {
+                arg.assign_to(&mut self.left)
+            }
+            if let Some(arg) = right {
+                arg.assign_to(&mut self.right)
+            }
+
+            py.allow_threads(|| self.run());
+
+            (
+                self.mid.as_ref().unwrap().clone().into(),
+                self.side.as_ref().unwrap().clone().into(),
+            )
+        }
+
+        fn __call__(&mut self, py: Python<'_>) {
+            py.allow_threads(|| self.run())
+        }
+    }
+
+    params = [];
+    async = compute_async;
+
+    fn reset(&mut self) {
+        self.mid = None;
+        self.side = None;
+    }
+}
+
+impl Algorithm for MidSide {
+    /// left and right channels of the stereo signal
+    type Input = (Vec<f64>, Vec<f64>);
+    /// mid and side signals
+    type Output = (Vec<f64>, Vec<f64>);
+    type Params = ();
+
+    fn with_params(_: ()) -> Self {
+        MidSide {
+            left: Vec::new(),
+            right: Vec::new(),
+
+            mid: None,
+            side: None,
+        }
+    }
+
+    fn compute(&mut self, (left, right): Self::Input) -> Result<Self::Output> {
+        self.left = left;
+        self.right = right;
+        self.run();
+        Ok((self.mid.take().unwrap(), self.side.take().unwrap()))
+    }
+}
+
+impl MidSide {
+    /// split the channels into mid and side signals and set the outputs
+    fn run(&mut self) {
+        let (mid, side) = encode(&self.left, &self.right);
+
+        // Output
+        self.mid = Some(mid);
+        self.side = Some(side);
+    }
+}
+
+python_class! {
+    /// Adjust the width of a stereo image with mid-side processing
+    #[pyclass(module = "muslib", get_all, set_all)]
+    pub struct StereoWidener {
+        /// Input: list[float] -- left channel of the stereo signal
+        pub left: Vec<f64>,
+        /// Input: list[float] -- right channel of the stereo signal
+        pub right: Vec<f64>,
+
+        /// Output: Optional[tuple[list[float], list[float]]] -- left and right channels after processing
+        pub stereo_data: Option<(Vec<f64>, Vec<f64>)>,
+
+        /// Param: float -- gain of the side signal, 0 for mono, 1 for unchanged, above 1 for wider (default: 1.0)
+        pub width: f64,
+    }
+}
+
+#[cfg(feature = "python")]
+pymethods_with_params! {
+    impl StereoWidener {
+        #[new]
+        #[pyo3(signature = (
+            width=1.0,
+        ))]
+        fn pynew(width: f64) -> PyResult<Self> {
+            let widener = Self::with_params(StereoWidenerParams { width });
+            widener.check()?;
+            Ok(widener)
+        }
+
+        /// Compute the Algorithm
+        ///
+        /// Inputs:
+        ///   - left: list[float]
+        ///   - right: list[float]
+        ///
+        /// Outputs:
+        ///   - stereo_data: tuple[list[float], list[float]]
+        ///
+        /// See data descriptors for more details.
+        #[pyo3(name = "compute", signature = (left=None, right=None))]
+        fn pycompute(
+            &mut self,
+            py: Python<'_>,
+            left: Option<ArrayView<f64>>,
+            right: Option<ArrayView<f64>>,
+        ) -> PyResult<(Array<f64>, Array<f64>)> {
+            if let Some(arg) = left {
+                arg.assign_to(&mut self.left)
+            }
+            if let Some(arg) = right {
+                arg.assign_to(&mut self.right)
+            }
+
+            py.allow_threads(|| self.run())?;
+
+            let (left, right) = self.stereo_data.as_ref().unwrap().clone();
+            Ok((left.into(), right.into()))
+        }
+
+        fn __call__(&mut self, py: Python<'_>) -> PyResult<()> {
+            Ok(py.allow_threads(|| self.run())?)
+        }
+    }
+
+    params = ["width"];
+    async = compute_async;
+
+    fn reset(&mut self) {
+        self.stereo_data = None;
+    }
+}
+
+/// Parameters of the StereoWidener, see its data descriptors for details
+#[derive(Debug, Clone, PartialEq)]
+pub struct StereoWidenerParams {
+    /// gain of the side signal, 0 for mono, 1 for unchanged, above 1 for wider
+    pub width: f64,
+}
+
+impl Default for StereoWidenerParams {
+    fn default() -> Self {
+        StereoWidenerParams { width: 1.0 }
+    }
+}
+
+impl ToJson for StereoWidenerParams {
+    fn to_json(&self) -> Json {
+        Json::object([("width", self.width.to_json())])
+    }
+}
+
+impl FromJson for StereoWidenerParams {
+    fn from_json(json: &Json) -> Result<Self> {
+        let mut params = Self::default();
+        for (key, value) in json.fields()? {
+            match key.as_str() {
+                "width" => params.width = FromJson::from_json(value)?,
+                _ => return Err(json::unknown_key(key)),
+            }
+        }
+        Ok(params)
+    }
+}
+
+/// Builder of a StereoWidener starting from default parameters, see StereoWidener::builder()
+#[derive(Debug, Clone, Default)]
+pub struct StereoWidenerBuilder {
+    params: StereoWidenerParams,
+}
+
+impl StereoWidenerBuilder {
+    /// gain of the side signal, 0 for mono, 1 for unchanged, above 1 for wider
+    pub fn width(mut self, width: f64) -> Self {
+        self.params.width = width;
+        self
+    }
+
+    /// create the StereoWidener with the parameters set so far
+    pub fn build(self) -> StereoWidener {
+        StereoWidener::with_params(self.params)
+    }
+}
+
+impl Algorithm for StereoWidener {
+    /// left and right channels of the stereo signal
+    type Input = (Vec<f64>, Vec<f64>);
+    /// left and right channels after processing
+    type Output = (Vec<f64>, Vec<f64>);
+    type Params = StereoWidenerParams;
+
+    fn with_params(params: StereoWidenerParams) -> Self {
+        StereoWidener {
+            left: Vec::new(),
+            right: Vec::new(),
+
+            stereo_data: None,
+
+            width: params.width,
+        }
+    }
+
+    fn compute(&mut self, (left, right): Self::Input) -> Result<Self::Output> {
+        self.left = left;
+        self.right = right;
+        self.run()?;
+        Ok(self.stereo_data.take().unwrap())
+    }
+}
+
+impl StereoWidener {
+    /// start building a StereoWidener from default parameters
+    pub fn builder() -> StereoWidenerBuilder {
+        StereoWidenerBuilder::default()
+    }
+
+    /// check that the width is a finite number of at least 0
+    fn check(&self) -> Result<()> {
+        check_range("width", self.width, 0.0, f64::INFINITY)
+    }
+
+    /// scale the side signal by the width and set the output
+    ///
+    /// Fails with Error::InvalidParam for a width out of its range.
+    fn run(&mut self) -> Result<()> {
+        self.check()?;
+        let (mid, mut side) = encode(&self.left, &self.right);
+        for x in side.iter_mut() {
+            *x *= self.width;
+        }
+
+        // Output
+        self.stereo_data = Some(decode(&mid, &side));
+        Ok(())
+    }
+}
+
+/// encode left and right channels into mid and side signals
+///
+/// Channels of different lengths are truncated to the shorter one.
+pub fn encode(left: &[f64], right: &[f64]) -> (Vec<f64>, Vec<f64>) {
+    left.iter()
+        .zip(right.iter())
+        .map(|(l, r)| ((l + r) / 2.0, (l - r) / 2.0))
+        .unzip()
+}
+
+/// decode mid and side signals back into left and right channels
+///
+/// Signals of different lengths are truncated to the shorter one.
+pub fn decode(mid: &[f64], side: &[f64]) -> (Vec<f64>, Vec<f64>) {
+    mid.iter()
+        .zip(side.iter())
+        .map(|(m, s)| (m + s, m - s))
+        .unzip()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode, encode, Algorithm, StereoWidener, StereoWidenerParams};
+    use crate::Error;
+
+    #[test]
+    fn mid_side() {
+        let left = vec![1.0, 0.5, 0.0, -0.25];
+        let right = vec![1.0, -0.5, 0.5, 0.25];
+
+        let (mid, side) = encode(&left, &right);
+        assert_eq!(mid, vec![1.0, 0.0, 0.25, 0.0]);
+        assert_eq!(side, vec![0.0, 0.5, -0.25, -0.25]);
+
+        let (l, r) = decode(&mid, &side);
+        assert_eq!(l, left);
+        assert_eq!(r, right);
+    }
+
+    #[test]
+    fn widener() {
+        let input = (vec![1.0, 0.5, 0.0], vec![0.0, 0.5, 1.0]);
+        let width = [0.0, 1.0, 2.0];
+        let result = [
+            (vec![0.5, 0.5, 0.5], vec![0.5, 0.5, 0.5]),
+            (vec![1.0, 0.5, 0.0], vec![0.0, 0.5, 1.0]),
+            (vec![1.5, 0.5, -0.5], vec![-0.5, 0.5, 1.5]),
+        ];
+
+        for i in 0..width.len() {
+            let mut widener = StereoWidener::with_params(StereoWidenerParams { width: width[i] });
+            let output = widener.compute(input.clone()).unwrap();
+            assert_eq!(output, result[i], "test {}", i);
+        }
+
+        for width in [-1.0, f64::NAN, f64::INFINITY] {
+            let mut widener = StereoWidener::with_params(StereoWidenerParams { width });
+            let result = widener.compute(input.clone());
+            assert!(
+                matches!(result, Err(Error::InvalidParam(_))),
+                "test {}",
+                width
+            );
+        }
+    }
+}
@@ -1,150 +1,699 @@
-use pyo3::{pyclass, pymethods};
+#[cfg(feature = "python")]
+use pyo3::{PyResult, Python};
 use symphonia::core::dsp::complex::Complex;
 use symphonia::core::dsp::fft::Fft;
 
-use super::Algorithm;
+use super::frames::FrameCutter;
+use super::{check_choice, Algorithm};
+use crate::json::{self, FromJson, Json, ToJson};
+#[cfg(feature = "python")]
+use crate::pymod::array::{Array, Array2, ArrayView, ComplexArray, ComplexArray2};
+#[cfg(feature = "python")]
+use crate::pymod::matrix::Matrix;
+#[cfg(feature = "python")]
+use crate::pymod::state::pymethods_with_params;
+use crate::sample::Sample;
+use crate::simd;
+use crate::{Error, Result};
 
-#[pyclass(get_all)]
-pub struct FFT {
-    /// Input: list[float] -- audio input frame, max len 65535
-    #[pyo3(set)]
-    pub frame: Vec<f64>,
-    /// Output: list[tuple[float, float]] -- fft data
-    pub fft_data: Vec<(f32, f32)>,
+python_class! {
+    /// Fast Fourier transform of a single audio frame or a spectrogram of a whole signal
+    ///
+    /// Framing, windowing and zero-padding parameters only apply to the spectrogram,
+    /// single frames are transformed as they are.
+    #[pyclass(module = "muslib", get_all)]
+    pub struct FFT {
+        /// Input: list[float] -- audio input frame, max len 65535
+        #[pyo3(set)]
+        pub frame: Vec<f64>,
+        /// Input: list[float] -- audio signal to cut into frames for the spectrogram
+        #[pyo3(set)]
+        pub signal: Vec<f64>,
+
+        /// Output: list[tuple[float, float]] -- fft data
+        pub fft_data: Vec<(f32, f32)>,
+        /// Output: Optional[list[list[float]]] -- magnitude spectrum of each frame, (frame_size + zero_padding) / 2 + 1 bins
+        pub spectrogram: Option<Vec<Vec<f64>>>,
+
+        /// Param: int -- number of samples in each frame of the spectrogram (default: 2048)
+        #[pyo3(set)]
+        pub frame_size: usize,
+        /// Param: int -- number of samples between the starts of consecutive frames (default: 512)
+        #[pyo3(set)]
+        pub hop_size: usize,
+        /// Param: str -- window applied to each frame, one of {hann, hamming, blackman, rectangular} (default: hann)
+        pub window: String,
+        /// Param: int -- number of zeros appended to each windowed frame (default: 0)
+        #[pyo3(set)]
+        pub zero_padding: usize,
+    }
 }
 
-#[pymethods]
-impl FFT {
-    #[new]
-    fn pynew() -> Self {
+#[cfg(feature = "python")]
+pymethods_with_params! {
+    impl FFT {
+        #[new]
+        #[pyo3(signature = (
+            frame_size=2048,
+            hop_size=512,
+            window="hann",
+            zero_padding=0,
+        ))]
+        fn pynew(
+            frame_size: usize,
+            hop_size: usize,
+            window: &str,
+            zero_padding: usize,
+        ) -> PyResult<Self> {
+            check_window(window)?;
+            Ok(Self::with_params(FFTParams {
+                frame_size,
+                hop_size,
+                window: window.into(),
+                zero_padding,
+            }))
+        }
+
+        #[setter(window)]
+        fn set_window(&mut self, window: &str) -> PyResult<()> {
+            check_window(window)?;
+            self.window = window.into();
+            Ok(())
+        }
+
+        /// Compute the Algorithm
+        ///
+        /// Inputs:
+        ///   - frame: list[float]
+        ///
+        /// Outputs:
+        ///   - fft_data: list[tuple[float, float]]
+        ///
+        /// See data descriptors for more details.
+        #[pyo3(name = "compute", signature = (frame = None))]
+        fn pycompute(
+            &mut self,
+            py: Python<'_>,
+            frame: Option<ArrayView<f64>>,
+        ) -> PyResult<ComplexArray<f32>> {
+            if let Some(arg) = frame {
+                arg.assign_to(&mut self.frame)
+            }
+
+            check_size(self.frame.len())?;
+            py.allow_threads(|| self.run());
+
+            Ok(self.fft_data.clone().into())
+        }
+
+        /// Compute the Algorithm for many frames in a single call
+        ///
+        /// Inputs:
+        ///   - frames: list[list[float]]
+        ///
+        /// Outputs:
+        ///   - list[list[tuple[float, float]]] -- fft data of each frame
+        ///
+        /// See data descriptors for more details.
+        fn compute_batch(
+            &mut self,
+            py: Python<'_>,
+            frames: Array2<f64>,
+        ) -> PyResult<ComplexArray2<f32>> {
+            for frame in frames.0.iter() {
+                check_size(frame.len())?;
+            }
+
+            let output = py.allow_threads(|| {
+                let mut output = Vec::with_capacity(frames.0.len());
+                for frame in frames.0 {
+                    self.frame = frame;
+                    self.run();
+                    output.push(self.fft_data.clone());
+                }
+                output
+            });
+
+            Ok(output.into())
+        }
+
+        /// Compute the spectrogram of a whole signal
+        ///
+        /// Inputs:
+        ///   - signal: list[float]
+        ///
+        /// Outputs:
+        ///   - spectrogram: list[list[float]]
+        ///
+        /// See data descriptors for more details.
+        #[pyo3(name = "compute_spectrogram", signature = (signal = None))]
+        fn pycompute_spectrogram(
+            &mut self,
+            py: Python<'_>,
+            signal: Option<ArrayView<f64>>,
+        ) -> PyResult<Array2<f64>> {
+            if let Some(arg) = signal {
+                arg.assign_to(&mut self.signal)
+            }
+
+            py.allow_threads(|| self.compute_spectrogram())?;
+
+            Ok(self.spectrogram.as_ref().unwrap().clone().into())
+        }
+
+        /// Compute the spectrogram of a whole signal as a Matrix for plotting
+        ///
+        /// Inputs:
+        ///   - signal: list[float]
+        ///   - sample_rate: float -- sample rate of the signal, used for the axes
+        ///
+        /// Outputs:
+        ///   - Matrix -- magnitudes with a row for each frequency in Hz and a column for each frame
+        ///
+        /// See data descriptors for more details.
+        #[pyo3(signature = (signal = None, sample_rate = 44100.0))]
+        fn compute_matrix(
+            &mut self,
+            py: Python<'_>,
+            signal: Option<ArrayView<f64>>,
+            sample_rate: f64,
+        ) -> PyResult<Matrix> {
+            if let Some(arg) = signal {
+                arg.assign_to(&mut self.signal)
+            }
+
+            py.allow_threads(|| self.compute_spectrogram())?;
+
+            let n = self.frame_size + self.zero_padding;
+            let bins = (0..n / 2 + 1)
+                .map(|k| k as f64 * sample_rate / n as f64)
+                .collect();
+            let hop = self.hop_size as f64 / sample_rate;
+            let frames = self.spectrogram.as_ref().unwrap().clone();
+            Ok(Matrix::new(frames, hop, bins))
+        }
+    }
+
+    params = ["frame_size", "hop_size", "window", "zero_padding"];
+    async = compute_async;
+
+    fn reset(&mut self) {
+        self.fft_data.clear();
+        self.spectrogram = None;
+    }
+}
+
+/// Parameters of the FFT, see its data descriptors for details
+#[derive(Debug, Clone, PartialEq)]
+pub struct FFTParams {
+    /// number of samples in each frame of the spectrogram
+    pub frame_size: usize,
+    /// number of samples between the starts of consecutive frames
+    pub hop_size: usize,
+    /// window applied to each frame, one of {hann, hamming, blackman, rectangular}
+    pub window: String,
+    /// number of zeros appended to each windowed frame
+    pub zero_padding: usize,
+}
+
+impl Default for FFTParams {
+    fn default() -> Self {
+        FFTParams {
+            frame_size: 2048,
+            hop_size: 512,
+            window: "hann".into(),
+            zero_padding: 0,
+        }
+    }
+}
+
+impl ToJson for FFTParams {
+    fn to_json(&self) -> Json {
+        Json::object([
+            ("frame_size", self.frame_size.to_json()),
+            ("hop_size", self.hop_size.to_json()),
+            ("window", self.window.to_json()),
+            ("zero_padding", self.zero_padding.to_json()),
+        ])
+    }
+}
+
+impl FromJson for FFTParams {
+    fn from_json(json: &Json) -> Result<Self> {
+        let mut params = Self::default();
+        for (key, value) in json.fields()? {
+            match key.as_str() {
+                "frame_size" => params.frame_size = FromJson::from_json(value)?,
+                "hop_size" => params.hop_size = FromJson::from_json(value)?,
+                "window" => params.window = json::checked(value, |w: &String| check_window(w))?,
+                "zero_padding" => params.zero_padding = FromJson::from_json(value)?,
+                _ => return Err(json::unknown_key(key)),
+            }
+        }
+        Ok(params)
+    }
+}
+
+/// Builder of an FFT starting from default parameters, see FFT::builder()
+#[derive(Debug, Clone, Default)]
+pub struct FFTBuilder {
+    params: FFTParams,
+}
+
+impl FFTBuilder {
+    /// number of samples in each frame of the spectrogram
+    pub fn frame_size(mut self, frame_size: usize) -> Self {
+        self.params.frame_size = frame_size;
+        self
+    }
+
+    /// number of samples between the starts of consecutive frames
+    pub fn hop_size(mut self, hop_size: usize) -> Self {
+        self.params.hop_size = hop_size;
+        self
+    }
+
+    /// window applied to each frame, one of {hann, hamming, blackman, rectangular}
+    pub fn window(mut self, window: &str) -> Self {
+        self.params.window = window.into();
+        self
+    }
+
+    /// number of zeros appended to each windowed frame
+    pub fn zero_padding(mut self, zero_padding: usize) -> Self {
+        self.params.zero_padding = zero_padding;
+        self
+    }
+
+    /// create the FFT with the parameters set so far
+    pub fn build(self) -> FFT {
+        FFT::with_params(self.params)
+    }
+}
+
+impl Algorithm for FFT {
+    /// audio frame, its length must be a power of two
+    type Input = Vec<f64>;
+    /// fft data
+    type Output = Vec<(f32, f32)>;
+    type Params = FFTParams;
+
+    fn with_params(params: FFTParams) -> Self {
         FFT {
             frame: Vec::new(),
+            signal: Vec::new(),
             fft_data: Vec::new(),
+            spectrogram: None,
+            frame_size: params.frame_size,
+            hop_size: params.hop_size,
+            window: params.window,
+            zero_padding: params.zero_padding,
         }
     }
 
-    /// Compute the Algorithm
-    ///
-    /// Inputs:
-    ///   - frame: list[float]
-    ///
-    /// Outputs:
-    ///   - fft_data: list[tuple[float, float]]
+    fn compute(&mut self, frame: Vec<f64>) -> Result<Vec<(f32, f32)>> {
+        check_size(frame.len())?;
+        self.frame = frame;
+        self.run();
+        Ok(std::mem::take(&mut self.fft_data))
+    }
+
+    fn compute_into(&mut self, frame: &Vec<f64>, output: &mut Vec<(f32, f32)>) -> Result<()> {
+        check_size(frame.len())?;
+        fft_into(frame, output);
+        Ok(())
+    }
+}
+
+impl FFT {
+    /// start building an FFT from default parameters
+    pub fn builder() -> FFTBuilder {
+        FFTBuilder::default()
+    }
+
+    /// transform the input frame and set the output
+    fn run(&mut self) {
+        fft_into(&self.frame, &mut self.fft_data);
+    }
+
+    /// check that the signal can be cut into frames and each of them transformed
+    fn check_framing(&self) -> Result<()> {
+        if self.frame_size == 0 || self.hop_size == 0 {
+            return Err(Error::InvalidParam(
+                "frame_size and hop_size must be positive".into(),
+            ));
+        }
+        check_size(self.frame_size + self.zero_padding)
+    }
+
+    /// compute the magnitude spectrogram of the input signal
     ///
-    /// See data descriptors for more details.
-    #[pyo3(name = "compute", signature = (frame = None))]
-    fn pycompute(&mut self, frame: Option<Vec<f64>>) -> Vec<(f32, f32)> {
-        if let Some(arg) = frame {
-            self.frame = arg
+    /// Fails with Error::InvalidParam for an unknown window or frames that can't be transformed.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "FFT::compute_spectrogram", level = "debug", skip_all)
+    )]
+    pub fn compute_spectrogram(&mut self) -> Result<()> {
+        self.check_framing()?;
+        let window = self.window.parse::<Window>()?.coefficients(self.frame_size);
+        let n = self.frame_size + self.zero_padding;
+        let bins = n / 2 + 1;
+
+        let mut cutter = FrameCutter::new(self.frame_size, self.hop_size);
+        cutter.push(&self.signal);
+        let mut frames = Vec::new();
+        while let Some(frame) = cutter.flush() {
+            frames.push(frame);
         }
 
-        self.compute();
+        let spectrum = |mut frame: Vec<f64>| -> Vec<f64> {
+            simd::mul(&mut frame, &window);
+            frame.resize(n, 0.0);
+            fft(&frame)
+                .iter()
+                .take(bins)
+                .map(|(re, im)| (re * re + im * im).sqrt() as f64)
+                .collect()
+        };
+        #[cfg(feature = "parallel")]
+        let spectrogram = crate::parallel::map(frames, spectrum);
+        #[cfg(not(feature = "parallel"))]
+        let spectrogram = frames.into_iter().map(spectrum).collect();
+        self.spectrogram = Some(spectrogram);
+        Ok(())
+    }
+}
 
-        self.fft_data.clone()
+/// transform a frame of samples of any type, e.g. straight from the Loader
+///
+/// Fails with Error::InvalidParam unless the length of the frame is a power of two of at least 2.
+pub fn forward<S: Sample>(frame: &[S]) -> Result<Vec<(f32, f32)>> {
+    check_size(frame.len())?;
+    let frame: Vec<f64> = frame.iter().map(|x| x.to_f64()).collect();
+    Ok(fft(&frame))
+}
+
+/// transform fft data back to a frame of samples of any type, e.g. for the Writer
+///
+/// It takes the output of forward() or FFT as it is.
+/// Fails with Error::InvalidParam unless the length of the data is a power of two of at least 2.
+pub fn inverse<S: Sample>(fft_data: &[(f32, f32)]) -> Result<Vec<S>> {
+    check_size(fft_data.len())?;
+    let fft_data: Vec<(f64, f64)> = fft_data
+        .iter()
+        .map(|(re, im)| (*re as f64, *im as f64))
+        .collect();
+    let mut frame = Vec::new();
+    ifft_into(&fft_data, &mut frame);
+    Ok(frame.into_iter().map(|x| S::from_f64(x as f64)).collect())
+}
+
+/// transform a frame, only the first Fft::MAX_SIZE samples are used
+fn fft(frame: &[f64]) -> Vec<(f32, f32)> {
+    let mut output = Vec::new();
+    fft_into(frame, &mut output);
+    output
+}
+
+/// transform a frame and replace the contents of the output with the result
+fn fft_into(frame: &[f64], output: &mut Vec<(f32, f32)>) {
+    // contruct a buffer of complex numbers
+    let mut buf: [Complex; Fft::MAX_SIZE] = [Complex { re: 0.0, im: 0.0 }; Fft::MAX_SIZE];
+    let n = std::cmp::min(frame.len(), Fft::MAX_SIZE);
+    let buf = &mut buf[0..n];
+    for (b, x) in buf.iter_mut().zip(frame.iter()) {
+        b.re = *x as f32;
     }
+
+    let fft = Fft::new(n);
+    fft.fft_inplace(buf);
+
+    // convert the buffer to output
+    output.clear();
+    output.extend(buf.iter().map(|x| (x.re, x.im)));
 }
 
-impl Algorithm for FFT {
-    fn new() -> Self {
-        Self::pynew()
+/// check that the window function is known
+fn check_window(window: &str) -> Result<()> {
+    window.parse::<Window>().map(|_| ())
+}
+
+/// window functions applied to frames before the transform
+pub enum Window {
+    /// raised cosine reaching zero at the edges
+    Hann,
+    /// raised cosine with a lower first sidelobe
+    Hamming,
+    /// sum of cosines with strongly attenuated sidelobes
+    Blackman,
+    /// no windowing
+    Rectangular,
+}
+
+impl core::str::FromStr for Window {
+    type Err = Error;
+
+    /// window of a given name, fails with Error::InvalidParam for an unknown one
+    fn from_str(window: &str) -> Result<Self> {
+        check_choice(
+            "window",
+            window,
+            &["hann", "hamming", "blackman", "rectangular"],
+        )?;
+        Ok(match window {
+            "hamming" => Window::Hamming,
+            "blackman" => Window::Blackman,
+            "rectangular" => Window::Rectangular,
+            _ => Window::Hann,
+        })
     }
+}
+
+impl Window {
+    /// periodic window coefficients for a frame of a given size
+    pub fn coefficients(&self, size: usize) -> Vec<f64> {
+        let phase = |i: usize| 2.0 * std::f64::consts::PI * i as f64 / size as f64;
+        (0..size)
+            .map(|i| match self {
+                Window::Hann => 0.5 - 0.5 * phase(i).cos(),
+                Window::Hamming => 0.54 - 0.46 * phase(i).cos(),
+                Window::Blackman => 0.42 - 0.5 * phase(i).cos() + 0.08 * (2.0 * phase(i)).cos(),
+                Window::Rectangular => 1.0,
+            })
+            .collect()
+    }
+}
 
-    fn compute(&mut self) {
-        // contruct a buffer of complex numbers
-        let mut buf: [Complex; Fft::MAX_SIZE] = [Complex { re: 0.0, im: 0.0 }; Fft::MAX_SIZE];
-        let n = std::cmp::min(self.frame.len(), Fft::MAX_SIZE);
-        let buf = &mut buf[0..n];
-        for i in 0..n {
-            buf[i].re = self.frame[i] as f32;
+python_class! {
+    /// Inverse fast Fourier transform of a single spectrum frame
+    #[pyclass(module = "muslib", get_all)]
+    pub struct IFFT {
+        /// Input: list[tuple[float, float]] -- fft data, max len 65535
+        #[pyo3(set)]
+        pub fft_data: Vec<(f64, f64)>,
+        /// Output: list[float] -- the IFFT of the input frame
+        pub frame: Vec<f32>,
+    }
+}
+
+#[cfg(feature = "python")]
+pymethods_with_params! {
+    impl IFFT {
+        #[new]
+        fn pynew() -> Self {
+            Self::with_params(())
         }
 
-        let fft = Fft::new(n);
-        fft.fft_inplace(buf);
+        /// Compute the Algorithm
+        ///
+        /// Inputs:
+        ///   - fft_data: list[tuple[float, float]]
+        ///
+        /// Outputs:
+        ///   - frame: list[float]
+        ///
+        /// See data descriptors for more details.
+        #[pyo3(name = "compute", signature = (fft_data = None))]
+        fn pycompute(
+            &mut self,
+            py: Python<'_>,
+            fft_data: Option<ComplexArray<f64>>,
+        ) -> PyResult<Array<f32>> {
+            if let Some(arg) = fft_data {
+                self.fft_data = arg.0
+            }
+
+            check_size(self.fft_data.len())?;
+            py.allow_threads(|| self.run());
 
-        // convert and store the buffer as output
-        self.fft_data = Vec::with_capacity(n);
-        for x in buf.iter().take(n) {
-            self.fft_data.push((x.re, x.im))
+            Ok(self.frame.clone().into())
         }
     }
-}
 
-#[pyclass(get_all)]
-pub struct IFFT {
-    /// Input: list[tuple[float, float]] -- fft data, max len 65535
-    #[pyo3(set)]
-    pub fft_data: Vec<(f64, f64)>,
-    /// Output: list[float] -- the IFFT of the input frame
-    pub frame: Vec<f32>,
+    params = [];
+    async = compute_async;
+
+    fn reset(&mut self) {
+        self.frame.clear();
+    }
 }
 
-#[pymethods]
-impl IFFT {
-    #[new]
-    fn pynew() -> Self {
+impl Algorithm for IFFT {
+    /// fft data, its length must be a power of two
+    type Input = Vec<(f64, f64)>;
+    /// the IFFT of the input frame
+    type Output = Vec<f32>;
+    type Params = ();
+
+    fn with_params(_: ()) -> Self {
         IFFT {
             fft_data: Vec::new(),
             frame: Vec::new(),
         }
     }
 
-    /// Compute the Algorithm
-    ///
-    /// Inputs:
-    ///   - fft_data: list[tuple[float, float]]
-    ///
-    /// Outputs:
-    ///   - frame: list[float]
-    ///
-    /// See data descriptors for more details.
-    #[pyo3(name = "compute", signature = (fft_data = None))]
-    fn pycompute(&mut self, fft_data: Option<Vec<(f64, f64)>>) -> Vec<f32> {
-        if let Some(arg) = fft_data {
-            self.fft_data = arg
-        }
+    fn compute(&mut self, fft_data: Vec<(f64, f64)>) -> Result<Vec<f32>> {
+        check_size(fft_data.len())?;
+        self.fft_data = fft_data;
+        self.run();
+        Ok(std::mem::take(&mut self.frame))
+    }
 
-        self.compute();
+    fn compute_into(&mut self, fft_data: &Vec<(f64, f64)>, output: &mut Vec<f32>) -> Result<()> {
+        check_size(fft_data.len())?;
+        ifft_into(fft_data, output);
+        Ok(())
+    }
+}
 
-        self.frame.clone()
+impl IFFT {
+    /// transform the input fft data back and set the output
+    fn run(&mut self) {
+        ifft_into(&self.fft_data, &mut self.frame);
     }
 }
 
-impl Algorithm for IFFT {
-    fn new() -> Self {
-        Self::pynew()
+/// transform fft data back and replace the contents of the output with the result
+fn ifft_into(fft_data: &[(f64, f64)], output: &mut Vec<f32>) {
+    // contruct a buffer of complex numbers
+    let mut buf: [Complex; Fft::MAX_SIZE] = [Complex { re: 0.0, im: 0.0 }; Fft::MAX_SIZE];
+    let n = std::cmp::min(fft_data.len(), Fft::MAX_SIZE);
+    let buf = &mut buf[0..n];
+    for (b, x) in buf.iter_mut().zip(fft_data.iter()) {
+        b.re = x.0 as f32;
+        b.im = x.1 as f32;
     }
 
-    fn compute(&mut self) {
-        // contruct a buffer of complex numbers
-        let mut buf: [Complex; Fft::MAX_SIZE] = [Complex { re: 0.0, im: 0.0 }; Fft::MAX_SIZE];
-        let n = std::cmp::min(self.fft_data.len(), Fft::MAX_SIZE);
-        let buf = &mut buf[0..n];
-        for i in 0..n {
-            buf[i].re = self.fft_data[i].0 as f32;
-            buf[i].im = self.fft_data[i].1 as f32;
-        }
+    let fft = Fft::new(n);
+    ifft_inplace(&fft, buf);
 
-        let fft = Fft::new(n);
-        fft.ifft_inplace(buf);
+    // convert the buffer to output
+    output.clear();
+    output.extend(buf.iter().map(|x| x.re));
+}
 
-        // convert and store the buffer as output
-        self.frame = Vec::with_capacity(n);
-        for x in buf.iter().take(n) {
-            self.frame.push(x.re)
-        }
+/// inverse transform in place, scaled by 1/N
+///
+/// The inverse transform of symphonia recurses without end on fewer than 32 bins,
+/// so the conjugated data goes through the forward transform instead, which handles every size.
+pub(crate) fn ifft_inplace(fft: &Fft, buf: &mut [Complex]) {
+    for x in buf.iter_mut() {
+        *x = x.conj();
+    }
+    fft.fft_inplace(buf);
+    let scale = 1.0 / buf.len() as f32;
+    for x in buf.iter_mut() {
+        *x = x.conj().scale(scale);
+    }
+}
+
+/// check that a frame of a given length can be transformed
+fn check_size(len: usize) -> Result<()> {
+    let n = std::cmp::min(len, Fft::MAX_SIZE);
+    if n >= 2 && n.is_power_of_two() {
+        Ok(())
+    } else {
+        Err(Error::InvalidParam(format!(
+            "frame size must be a power of two of at least 2, got {}",
+            len
+        )))
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use super::{forward, inverse, Window, FFT};
+    use crate::algs::Algorithm;
 
     #[test]
     fn fft() {
-        // TODO
+        let frame: Vec<i16> = (0..64).map(|i| (i * 997 % 20000) as i16 - 10000).collect();
+        let fft_data = forward(&frame).unwrap();
+        // the first bin is the sum of all samples
+        let sum: f64 = frame.iter().map(|x| *x as f64 / 32768.0).sum();
+        assert!((fft_data[0].0 as f64 - sum).abs() < 1e-3);
+        assert_eq!(inverse::<i16>(&fft_data).unwrap(), frame);
+        assert!(forward(&[0.0f32; 3]).is_err() && inverse::<f32>(&[(0.0, 0.0); 6]).is_err());
+        assert!(forward(&[0.0f32; 1]).is_err() && inverse::<f32>(&[(0.0, 0.0)]).is_err());
+        // sizes below 32 are transformed back too
+        for n in [2, 4, 8, 16] {
+            let frame: Vec<f32> = (0..n).map(|i| (i as f32 * 0.7).sin()).collect();
+            let frame_back = inverse::<f32>(&forward(&frame).unwrap()).unwrap();
+            for (x, y) in frame.iter().zip(frame_back.iter()) {
+                assert!((x - y).abs() < 1e-6, "{:?} != {:?}", frame, frame_back);
+            }
+        }
+    }
+
+    #[test]
+    fn window() {
+        let hann = Window::Hann.coefficients(4);
+        let result = [0.0, 0.5, 1.0, 0.5];
+        for (x, y) in hann.iter().zip(result.iter()) {
+            assert!((x - y).abs() < 1e-12, "{:?} != {:?}", hann, result);
+        }
+        assert_eq!(Window::Rectangular.coefficients(3), vec![1.0; 3]);
+    }
+
+    #[test]
+    fn spectrogram() {
+        let mut fft = FFT::new();
+        fft.frame_size = 8;
+        fft.hop_size = 4;
+        fft.window = "rectangular".into();
+        fft.zero_padding = 8;
+        fft.signal = vec![1.0; 16];
+        fft.compute_spectrogram().unwrap();
+
+        let spectrogram = fft.spectrogram.unwrap();
+        // frames start at 0, 4, 8 and 12, the last one is padded
+        assert_eq!(spectrogram.len(), 4);
+        for frame in spectrogram.iter() {
+            assert_eq!(frame.len(), 9);
+        }
+        // the DC bin sums the samples in each frame
+        assert!((spectrogram[0][0] - 8.0).abs() < 1e-4);
+        assert!((spectrogram[3][0] - 4.0).abs() < 1e-4);
     }
 
     #[test]
     fn ifft() {
         // TODO
     }
+
+    #[test]
+    fn compute_into() {
+        let mut fft = FFT::new();
+        let mut output = Vec::new();
+        fft.compute_into(&vec![1.0, 0.0, -1.0, 0.0], &mut output)
+            .unwrap();
+        assert_eq!(output, fft.compute(vec![1.0, 0.0, -1.0, 0.0]).unwrap());
+
+        // the buffer of the previous frame is reused
+        let buffer = output.as_ptr();
+        fft.compute_into(&vec![0.0, 1.0, 0.0, -1.0], &mut output)
+            .unwrap();
+        assert_eq!(output.as_ptr(), buffer);
+        assert_eq!(output, fft.compute(vec![0.0, 1.0, 0.0, -1.0]).unwrap());
+    }
 }
@@ -0,0 +1,406 @@
+#[cfg(feature = "python")]
+use pyo3::{PyResult, Python};
+
+use super::{check_length, check_positive, check_range, Algorithm, MAX_LENGTH};
+use crate::json::{self, FromJson, Json, ToJson};
+#[cfg(feature = "python")]
+use crate::pymod::array::{Array, ArrayView};
+#[cfg(feature = "python")]
+use crate::pymod::state::pymethods_with_params;
+use crate::Result;
+
+python_class! {
+    /// Time-stretching with waveform similarity overlap-add (WSOLA)
+    #[pyclass(module = "muslib", get_all)]
+    pub struct WSOLA {
+        /// Input: list[float] -- audio signal
+        #[pyo3(set)]
+        pub signal: Vec<f64>,
+
+        /// Output: Optional[list[float]] -- time-stretched audio signal
+        pub stretched: Option<Vec<f64>>,
+
+        /// Param: float -- ratio of the output duration to the input duration, above 0 (default: 1.0)
+        pub factor: f64,
+        /// Param: int -- size of the overlap-add window in samples, at least 2 (default: 1024)
+        pub frame_size: usize,
+        /// Param: int -- maximum shift in samples when looking for the most similar segment (default: 256)
+        pub tolerance: usize,
+    }
+}
+
+#[cfg(feature = "python")]
+pymethods_with_params! {
+    impl WSOLA {
+        #[new]
+        #[pyo3(signature = (
+            factor=1.0,
+            frame_size=1024,
+            tolerance=256,
+        ))]
+        fn pynew(factor: f64, frame_size: usize, tolerance: usize) -> PyResult<Self> {
+            let wsola = Self::with_params(WSOLAParams {
+                factor,
+                frame_size,
+                tolerance,
+            });
+            wsola.check()?;
+            Ok(wsola)
+        }
+
+        #[setter(factor)]
+        fn set_factor(&mut self, factor: f64) -> PyResult<()> {
+            check_factor(factor)?;
+            self.factor = factor;
+            Ok(())
+        }
+
+        #[setter(frame_size)]
+        fn set_frame_size(&mut self, frame_size: usize) -> PyResult<()> {
+            check_frame_size(frame_size)?;
+            self.frame_size = frame_size;
+            Ok(())
+        }
+
+        #[setter(tolerance)]
+        fn set_tolerance(&mut self, tolerance: usize) -> PyResult<()> {
+            check_tolerance(tolerance)?;
+            self.tolerance = tolerance;
+            Ok(())
+        }
+
+        /// Compute the Algorithm
+        ///
+        /// Inputs:
+        ///   - signal: list[float]
+        ///
+        /// Outputs:
+        ///   - stretched: list[float]
+        ///
+        /// See data descriptors for more details.
+        #[pyo3(name = "compute", signature = (signal=None))]
+        fn pycompute(&mut self, py: Python<'_>, signal: Option<ArrayView<f64>>) -> PyResult<Array<f64>> {
+            if let Some(arg) = signal {
+                arg.assign_to(&mut self.signal)
+            }
+
+            py.allow_threads(|| self.run())?;
+
+            Ok(self.stretched.as_ref().unwrap().clone().into())
+        }
+
+        fn __call__(&mut self, py: Python<'_>) -> PyResult<()> {
+            Ok(py.allow_threads(|| self.run())?)
+        }
+    }
+
+    params = ["factor", "frame_size", "tolerance"];
+    async = compute_async;
+
+    fn reset(&mut self) {
+        self.stretched = None;
+    }
+}
+
+/// Parameters of the WSOLA, see its data descriptors for details
+#[derive(Debug, Clone, PartialEq)]
+pub struct WSOLAParams {
+    /// ratio of the output duration to the input duration, above 0
+    pub factor: f64,
+    /// size of the overlap-add window in samples, at least 2
+    pub frame_size: usize,
+    /// maximum shift in samples when looking for the most similar segment
+    pub tolerance: usize,
+}
+
+impl Default for WSOLAParams {
+    fn default() -> Self {
+        WSOLAParams {
+            factor: 1.0,
+            frame_size: 1024,
+            tolerance: 256,
+        }
+    }
+}
+
+impl ToJson for WSOLAParams {
+    fn to_json(&self) -> Json {
+        Json::object([
+            ("factor", self.factor.to_json()),
+            ("frame_size", self.frame_size.to_json()),
+            ("tolerance", self.tolerance.to_json()),
+        ])
+    }
+}
+
+impl FromJson for WSOLAParams {
+    fn from_json(json: &Json) -> Result<Self> {
+        let mut params = Self::default();
+        for (key, value) in json.fields()? {
+            match key.as_str() {
+                "factor" => params.factor = FromJson::from_json(value)?,
+                "frame_size" => params.frame_size = FromJson::from_json(value)?,
+                "tolerance" => params.tolerance = FromJson::from_json(value)?,
+                _ => return Err(json::unknown_key(key)),
+            }
+        }
+        Ok(params)
+    }
+}
+
+/// Builder of a WSOLA starting from default parameters, see WSOLA::builder()
+#[derive(Debug, Clone, Default)]
+pub struct WSOLABuilder {
+    params: WSOLAParams,
+}
+
+impl WSOLABuilder {
+    /// ratio of the output duration to the input duration, above 0
+    pub fn factor(mut self, factor: f64) -> Self {
+        self.params.factor = factor;
+        self
+    }
+
+    /// size of the overlap-add window in samples, at least 2
+    pub fn frame_size(mut self, frame_size: usize) -> Self {
+        self.params.frame_size = frame_size;
+        self
+    }
+
+    /// maximum shift in samples when looking for the most similar segment
+    pub fn tolerance(mut self, tolerance: usize) -> Self {
+        self.params.tolerance = tolerance;
+        self
+    }
+
+    /// create the WSOLA with the parameters set so far
+    pub fn build(self) -> WSOLA {
+        WSOLA::with_params(self.params)
+    }
+}
+
+impl Algorithm for WSOLA {
+    /// audio signal
+    type Input = Vec<f64>;
+    /// time-stretched audio signal
+    type Output = Vec<f64>;
+    type Params = WSOLAParams;
+
+    fn with_params(params: WSOLAParams) -> Self {
+        WSOLA {
+            signal: Vec::new(),
+
+            stretched: None,
+
+            factor: params.factor,
+            frame_size: params.frame_size,
+            tolerance: params.tolerance,
+        }
+    }
+
+    fn compute(&mut self, signal: Vec<f64>) -> Result<Vec<f64>> {
+        self.signal = signal;
+        self.run()?;
+        Ok(self.stretched.take().unwrap())
+    }
+}
+
+impl WSOLA {
+    /// start building a WSOLA from default parameters
+    pub fn builder() -> WSOLABuilder {
+        WSOLABuilder::default()
+    }
+
+    /// check the parameters, with Error::InvalidParam for the first one out of its range
+    fn check(&self) -> Result<()> {
+        check_factor(self.factor)?;
+        check_frame_size(self.frame_size)?;
+        check_tolerance(self.tolerance)
+    }
+
+    /// stretch the signal and set the output
+    ///
+    /// Fails with Error::InvalidParam for parameters out of their ranges,
+    /// or a factor that would stretch the signal above MAX_LENGTH samples.
+    fn run(&mut self) -> Result<()> {
+        self.check()?;
+        let n = self.frame_size;
+        let hop = n / 2;
+        let factor = self.factor;
+        let length = (self.signal.len() as f64 * factor).round();
+        check_length("stretched signal", length)?;
+        let length = length as usize;
+
+        // periodic hann window sums up to a constant with 50% overlap
+        let window: Vec<f64> = (0..n)
+            .map(|i| 0.5 - 0.5 * (2.0 * std::f64::consts::PI * i as f64 / n as f64).cos())
+            .collect();
+
+        let mut output = vec![0.0; length + n];
+        let mut norm = vec![0.0; length + n];
+
+        // analysis position of the previously copied segment
+        let mut previous = 0_isize;
+        let mut k = 0;
+        while k * hop < length {
+            let nominal = ((k * hop) as f64 / factor).round() as isize;
+            let position = if k == 0 {
+                0
+            } else {
+                self.best_match(previous + hop as isize, nominal, hop)
+            };
+
+            let offset = k * hop;
+            for i in 0..n {
+                output[offset + i] += window[i] * self.sample(position + i as isize);
+                norm[offset + i] += window[i];
+            }
+
+            previous = position;
+            k += 1;
+        }
+
+        output.truncate(length);
+        for (x, w) in output.iter_mut().zip(norm.iter()) {
+            if *w > 1e-9 {
+                *x /= *w;
+            }
+        }
+
+        // Output
+        self.stretched = Some(output);
+        Ok(())
+    }
+
+    fn sample(&self, i: isize) -> f64 {
+        if i < 0 {
+            return 0.0;
+        }
+        *self.signal.get(i as usize).unwrap_or(&0.0)
+    }
+
+    /// find the position around nominal most similar to the natural continuation
+    fn best_match(&self, continuation: isize, nominal: isize, overlap: usize) -> isize {
+        let tolerance = self.tolerance as isize;
+
+        let mut best = nominal;
+        let mut best_score = f64::MIN;
+        // check the smallest shifts first, so they win the ties
+        for d in 0..=(2 * tolerance) {
+            let delta = if d % 2 == 0 { d / 2 } else { -(d + 1) / 2 };
+            let candidate = nominal + delta;
+            if candidate < 0 {
+                continue;
+            }
+
+            let mut correlation = 0.0;
+            let mut energy = 0.0;
+            for i in 0..overlap as isize {
+                let x = self.sample(candidate + i);
+                correlation += x * self.sample(continuation + i);
+                energy += x * x;
+            }
+
+            let score = if energy > 0.0 {
+                correlation / energy.sqrt()
+            } else {
+                0.0
+            };
+            if score > best_score + 1e-12 {
+                best_score = score;
+                best = candidate;
+            }
+        }
+        best
+    }
+}
+
+/// check that the factor is a finite number above 0
+fn check_factor(factor: f64) -> Result<()> {
+    check_positive("factor", factor)
+}
+
+/// check that the frame size is at least 2 and at most MAX_LENGTH
+fn check_frame_size(frame_size: usize) -> Result<()> {
+    check_range("frame_size", frame_size as f64, 2.0, MAX_LENGTH as f64)
+}
+
+/// check that the tolerance is at most MAX_LENGTH
+fn check_tolerance(tolerance: usize) -> Result<()> {
+    check_range("tolerance", tolerance as f64, 0.0, MAX_LENGTH as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Algorithm, WSOLA};
+    use crate::Error;
+
+    fn sine(freq: f64, n: usize) -> Vec<f64> {
+        (0..n)
+            .map(|i| (2.0 * std::f64::consts::PI * freq * i as f64 / 44100.0).sin())
+            .collect()
+    }
+
+    fn zero_crossings(x: &[f64]) -> usize {
+        x.windows(2).filter(|w| w[0] < 0.0 && w[1] >= 0.0).count()
+    }
+
+    #[test]
+    fn identity() {
+        let input = sine(440.0, 8192);
+
+        let mut wsola = WSOLA::new();
+        let output = wsola.compute(input.clone()).unwrap();
+        assert_eq!(output.len(), input.len());
+        for i in 0..input.len() {
+            assert!((output[i] - input[i]).abs() < 1e-9, "test {}", i);
+        }
+    }
+
+    #[test]
+    fn stretch() {
+        let input = sine(440.0, 44100);
+
+        for factor in [0.5, 1.5, 2.0] {
+            let mut wsola = WSOLA::builder().factor(factor).build();
+            let output = wsola.compute(input.clone()).unwrap();
+            let length = (input.len() as f64 * factor).round() as usize;
+            assert_eq!(output.len(), length, "test {}", factor);
+
+            // the pitch should be preserved
+            let expected = 440.0 * factor;
+            let crossings = zero_crossings(&output) as f64;
+            assert!(
+                (crossings - expected).abs() / expected < 0.02,
+                "test {}",
+                factor
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_params() {
+        let input = sine(440.0, 1024);
+
+        for factor in [0.0, -1.0, f64::NAN, f64::INFINITY, 1e12] {
+            let mut wsola = WSOLA::builder().factor(factor).build();
+            let result = wsola.compute(input.clone());
+            assert!(
+                matches!(result, Err(Error::InvalidParam(_))),
+                "test {}",
+                factor
+            );
+        }
+        for frame_size in [0, 1] {
+            let mut wsola = WSOLA::builder().frame_size(frame_size).build();
+            let result = wsola.compute(input.clone());
+            assert!(
+                matches!(result, Err(Error::InvalidParam(_))),
+                "test {}",
+                frame_size
+            );
+        }
+        let mut wsola = WSOLA::builder().tolerance(usize::MAX).build();
+        assert!(matches!(wsola.compute(input), Err(Error::InvalidParam(_))));
+    }
+}
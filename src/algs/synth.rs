@@ -1,88 +1,829 @@
-use pyo3::{pyclass, pymethods};
-
-use super::Algorithm;
-
-#[pyclass(get_all)]
-pub struct Synthesizer {
-    /// Input: list[float] -- frequencies of consecutive tones expressed in Hz
-    #[pyo3(set)]
-    pub freq: Vec<f64>,
-    /// Input: list[float] -- durations of consecutive tones expressed in seconds
-    #[pyo3(set)]
-    pub durations: Vec<f64>,
-    /// Output: Optional[list[int]] -- raw 16-bit pcm values of synthesized data
-    pub pcm_data: Option<Vec<u16>>,
-    /// Param: int -- sample rate (default: 44100)
-    #[pyo3(set)]
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+#[cfg(feature = "python")]
+use pyo3::exceptions::PyValueError;
+#[cfg(feature = "python")]
+use pyo3::{PyRefMut, PyResult, Python};
+
+use super::{check_choice, check_length, check_positive, check_range, Algorithm};
+use crate::json::{self, FromJson, Json, ToJson};
+#[cfg(not(feature = "std"))]
+use crate::math::Float;
+use crate::notes::Tuning;
+#[cfg(feature = "python")]
+use crate::pymod::array::{Array, ArrayView};
+#[cfg(feature = "python")]
+use crate::pymod::state::pymethods_with_params;
+use crate::rng::Rng;
+use crate::sample::Sample;
+use crate::{Error, Result};
+
+python_class! {
+    /// Synthesizer for sequences of simple tones
+    #[pyclass(module = "muslib", get_all)]
+    pub struct Synthesizer {
+        /// Input: list[float] -- frequencies of consecutive tones expressed in Hz, 0 for rests
+        #[pyo3(set)]
+        pub freq: Vec<f64>,
+        /// Input: list[float] -- durations of consecutive tones expressed in seconds
+        #[pyo3(set)]
+        pub durations: Vec<f64>,
+        /// Input: list[float] -- optional gains of consecutive tones from range <0; 1>,
+        /// full scale for tones without one
+        #[pyo3(set)]
+        pub amplitudes: Vec<f64>,
+        /// Input: list[float] -- optional pans of consecutive tones from range <-1; 1>
+        /// for stereo synthesis, the global pan for tones without one
+        #[pyo3(set)]
+        pub pans: Vec<f64>,
+        /// Output: Optional[list[int]] -- signed 16-bit pcm values of synthesized data, silent at 0
+        pub pcm_data: Option<Vec<i16>>,
+        /// Param: int -- sample rate, above 0 (default: 44100)
+        pub sample_rate: usize,
+        /// Param: list[float] -- optional parameters for the tone envelope [a, h, d, s, r]
+        pub envelope: Vec<f64>,
+        /// Param: str -- waveform type as a str, one of {sin, sqr, saw, tri, pulse, white, pink, brown, additive, table},
+        /// with a duty cycle like pulse:0.1, partials like additive:1,0.5@3 or a cycle like table:0,1,0,-1
+        pub waveform: String,
+        /// Param: float -- time in seconds to slide in pitch from a tone to the next one,
+        /// 0 to jump (default: 0)
+        pub glide: f64,
+        /// Param: list[float] -- optional vibrato [rate, depth] in Hz and cents
+        pub vibrato: Vec<f64>,
+        /// Param: list[float] -- optional tremolo [rate, depth] in Hz and from range <0; 1>
+        pub tremolo: Vec<f64>,
+        /// Param: list[float] -- optional unison [voices, detune, spread] with 1 to 16 voices, detune in cents
+        /// and spread from range <0; 1> of the stereo field
+        pub unison: Vec<f64>,
+        /// Param: float -- time in seconds to fade every tone in and out, so tones
+        /// without an envelope don't click at their boundaries, 0 to turn off (default: 0.005)
+        pub fade: f64,
+        /// Param: float -- position of tones in the stereo field from range <-1; 1>
+        /// for stereo synthesis, from the left to the right (default: 0)
+        pub pan: f64,
+    }
+}
+
+#[cfg(feature = "python")]
+pymethods_with_params! {
+    impl Synthesizer {
+        #[new]
+        #[pyo3(signature = (
+            sample_rate=44100,
+            envelope=None,
+            waveform="sin",
+            glide=0.0,
+            vibrato=None,
+            tremolo=None,
+            unison=None,
+            fade=0.005,
+            pan=0.0
+        ))]
+        #[allow(clippy::too_many_arguments)]
+        fn pynew(
+            sample_rate: usize,
+            envelope: Option<Vec<f64>>,
+            waveform: &str,
+            glide: f64,
+            vibrato: Option<Vec<f64>>,
+            tremolo: Option<Vec<f64>>,
+            unison: Option<Vec<f64>>,
+            fade: f64,
+            pan: f64,
+        ) -> PyResult<Self> {
+            let envelope = envelope.unwrap_or_default();
+            let vibrato = vibrato.unwrap_or_default();
+            let tremolo = tremolo.unwrap_or_default();
+            let unison = unison.unwrap_or_default();
+            check_envelope(&envelope)?;
+            check_waveform(waveform)?;
+            check_modulation("vibrato", &vibrato)?;
+            check_modulation("tremolo", &tremolo)?;
+            let synth = Self::with_params(SynthesizerParams {
+                sample_rate,
+                envelope,
+                waveform: waveform.into(),
+                glide,
+                vibrato,
+                tremolo,
+                unison,
+                fade,
+                pan,
+            });
+            synth.check()?;
+            Ok(synth)
+        }
+
+        #[setter(sample_rate)]
+        fn set_sample_rate(&mut self, sample_rate: usize) -> PyResult<()> {
+            check_positive("sample_rate", sample_rate as f64)?;
+            self.sample_rate = sample_rate;
+            Ok(())
+        }
+
+        #[setter(glide)]
+        fn set_glide(&mut self, glide: f64) -> PyResult<()> {
+            check_range("glide", glide, 0.0, f64::INFINITY)?;
+            self.glide = glide;
+            Ok(())
+        }
+
+        #[setter(fade)]
+        fn set_fade(&mut self, fade: f64) -> PyResult<()> {
+            check_range("fade", fade, 0.0, f64::INFINITY)?;
+            self.fade = fade;
+            Ok(())
+        }
+
+        #[setter(pan)]
+        fn set_pan(&mut self, pan: f64) -> PyResult<()> {
+            check_range("pan", pan, -1.0, 1.0)?;
+            self.pan = pan;
+            Ok(())
+        }
+
+        #[setter(envelope)]
+        fn set_envelope(&mut self, envelope: Vec<f64>) -> PyResult<()> {
+            check_envelope(&envelope)?;
+            self.envelope = envelope;
+            Ok(())
+        }
+
+        #[setter(waveform)]
+        fn set_waveform(&mut self, waveform: &str) -> PyResult<()> {
+            check_waveform(waveform)?;
+            self.waveform = waveform.into();
+            Ok(())
+        }
+
+        #[setter(vibrato)]
+        fn set_vibrato(&mut self, vibrato: Vec<f64>) -> PyResult<()> {
+            check_modulation("vibrato", &vibrato)?;
+            self.vibrato = vibrato;
+            Ok(())
+        }
+
+        #[setter(tremolo)]
+        fn set_tremolo(&mut self, tremolo: Vec<f64>) -> PyResult<()> {
+            check_modulation("tremolo", &tremolo)?;
+            self.tremolo = tremolo;
+            Ok(())
+        }
+
+        #[setter(unison)]
+        fn set_unison(&mut self, unison: Vec<f64>) -> PyResult<()> {
+            check_unison(&unison)?;
+            self.unison = unison;
+            Ok(())
+        }
+
+        /// Compute the Algorithm
+        ///
+        /// Inputs:
+        ///   - freq: list[float]
+        ///   - durations: list[float]
+        ///   - amplitudes: list[float]
+        ///
+        /// Outputs:
+        ///   - pcm_data: list[int]
+        ///
+        /// See data descriptors for more details.
+        #[pyo3(name = "compute", signature = (freq=None, durations=None, amplitudes=None))]
+        fn pycompute(
+            &mut self,
+            py: Python<'_>,
+            freq: Option<ArrayView<f64>>,
+            durations: Option<ArrayView<f64>>,
+            amplitudes: Option<ArrayView<f64>>,
+        ) -> PyResult<Array<i16>> {
+            if let Some(arg) = freq {
+                arg.assign_to(&mut self.freq)
+            }
+            if let Some(arg) = durations {
+                arg.assign_to(&mut self.durations)
+            }
+            if let Some(arg) = amplitudes {
+                arg.assign_to(&mut self.amplitudes)
+            }
+
+            py.allow_threads(|| self.run())?;
+
+            Ok(self.pcm_data.as_ref().unwrap().clone().into())
+        }
+
+        /// Compute the Algorithm with outputs in the unsigned format used before,
+        /// centered at 32767, for code that still expects it
+        ///
+        /// Takes the same arguments as .compute(), .pcm_data is left unset.
+        ///
+        /// Outputs:
+        ///   - pcm_data: list[int]
+        #[pyo3(name = "compute_u16", signature = (freq=None, durations=None, amplitudes=None))]
+        fn pycompute_u16(
+            &mut self,
+            py: Python<'_>,
+            freq: Option<ArrayView<f64>>,
+            durations: Option<ArrayView<f64>>,
+            amplitudes: Option<ArrayView<f64>>,
+        ) -> PyResult<Array<u16>> {
+            if let Some(arg) = freq {
+                arg.assign_to(&mut self.freq)
+            }
+            if let Some(arg) = durations {
+                arg.assign_to(&mut self.durations)
+            }
+            if let Some(arg) = amplitudes {
+                arg.assign_to(&mut self.amplitudes)
+            }
+            Ok(py.allow_threads(|| self.compute_u16((self.freq.clone(), self.durations.clone())))?
+                .into())
+        }
+
+        fn __call__(&mut self, py: Python<'_>) -> PyResult<()> {
+            Ok(py.allow_threads(|| self.run())?)
+        }
+
+        /// Synthesize notes that may overlap, like chords, instead of the sequence of tones
+        ///
+        /// Inputs:
+        ///   - notes: list[tuple[float, float, float, float]] -- freq, start and duration
+        ///     in seconds and velocity from range <0; 1> of every note
+        ///
+        /// Outputs:
+        ///   - pcm_data: list[int]
+        #[pyo3(name = "compute_notes")]
+        fn pycompute_notes(
+            &mut self,
+            py: Python<'_>,
+            notes: Vec<(f64, f64, f64, f64)>,
+        ) -> PyResult<Array<i16>> {
+            let notes: Vec<NoteEvent> = notes
+                .into_iter()
+                .map(|(freq, start, duration, velocity)| {
+                    NoteEvent::new(freq, start, duration).velocity(velocity)
+                })
+                .collect();
+            let pcm_data = py.allow_threads(|| self.render_notes::<i16>(&notes))?;
+            self.pcm_data = Some(pcm_data.clone());
+            Ok(pcm_data.into())
+        }
+
+        /// Synthesize the sequence of tones in stereo, panned by their pans or the global pan
+        ///
+        /// Takes the same inputs as .compute() and the pans of the tones.
+        ///
+        /// Outputs:
+        ///   - left, right: tuple[list[float], list[float]] -- samples from range <-1; 1>
+        #[pyo3(signature = (freq=None, durations=None, amplitudes=None, pans=None))]
+        fn stereo(
+            &mut self,
+            py: Python<'_>,
+            freq: Option<ArrayView<f64>>,
+            durations: Option<ArrayView<f64>>,
+            amplitudes: Option<ArrayView<f64>>,
+            pans: Option<ArrayView<f64>>,
+        ) -> PyResult<(Array<f64>, Array<f64>)> {
+            if let Some(arg) = freq {
+                arg.assign_to(&mut self.freq)
+            }
+            if let Some(arg) = durations {
+                arg.assign_to(&mut self.durations)
+            }
+            if let Some(arg) = amplitudes {
+                arg.assign_to(&mut self.amplitudes)
+            }
+            if let Some(arg) = pans {
+                arg.assign_to(&mut self.pans)
+            }
+            let (left, right) = py.allow_threads(|| self.render_stereo::<f64>())?;
+            Ok((left.into(), right.into()))
+        }
+
+        /// Synthesize notes that may overlap in stereo, each panned on its own
+        ///
+        /// Inputs:
+        ///   - notes: list[tuple[float, float, float, float, float]] -- freq, start and duration
+        ///     in seconds, velocity from range <0; 1> and pan from range <-1; 1> of every note
+        ///
+        /// Outputs:
+        ///   - left, right: tuple[list[float], list[float]] -- samples from range <-1; 1>
+        fn stereo_notes(
+            &self,
+            py: Python<'_>,
+            notes: Vec<(f64, f64, f64, f64, f64)>,
+        ) -> PyResult<(Array<f64>, Array<f64>)> {
+            let notes: Vec<NoteEvent> = notes
+                .into_iter()
+                .map(|(freq, start, duration, velocity, pan)| {
+                    NoteEvent::new(freq, start, duration)
+                        .velocity(velocity)
+                        .pan(pan)
+                })
+                .collect();
+            let (left, right) = py.allow_threads(|| self.render_notes_stereo::<f64>(&notes))?;
+            Ok((left.into(), right.into()))
+        }
+    }
+
+    params = [
+        "sample_rate",
+        "envelope",
+        "waveform",
+        "glide",
+        "vibrato",
+        "tremolo",
+        "unison",
+        "fade",
+        "pan",
+    ];
+    async = compute_async;
+
+    fn reset(&mut self) {
+        self.pcm_data = None;
+    }
+}
+
+/// Parameters of the Synthesizer, see its data descriptors for details
+#[derive(Debug, Clone, PartialEq)]
+pub struct SynthesizerParams {
+    /// sample rate, above 0
     pub sample_rate: usize,
-    /// Param: list[float] -- optional parameters for the tone envelope [a, h, d, s, r]
-    #[pyo3(set)]
+    /// optional parameters for the tone envelope [a, h, d, s, r]
     pub envelope: Vec<f64>,
-    /// Param: str -- waveform type as a str, one of {sin, sqr, saw}
-    #[pyo3(set)]
+    /// waveform type, one of {sin, sqr, saw, tri, pulse, white, pink, brown, additive, table},
+    /// with a duty cycle like pulse:0.1, partials like additive:1,0.5@3 or a cycle like table:0,1,0,-1
     pub waveform: String,
+    /// time in seconds to slide in pitch from a tone to the next one, 0 to jump
+    pub glide: f64,
+    /// optional vibrato [rate, depth] in Hz and cents
+    pub vibrato: Vec<f64>,
+    /// optional tremolo [rate, depth] in Hz and from range <0; 1>
+    pub tremolo: Vec<f64>,
+    /// optional unison [voices, detune, spread] with 1 to 16 voices, detune in cents and spread from range <0; 1>
+    pub unison: Vec<f64>,
+    /// time in seconds to fade every tone in and out, 0 to turn off
+    pub fade: f64,
+    /// position of tones in the stereo field from range <-1; 1> for stereo synthesis
+    pub pan: f64,
 }
 
-#[pymethods]
-impl Synthesizer {
-    #[new]
-    #[pyo3(signature = (
-        sample_rate=44100,
-        envelope=None,
-        waveform="sin"
-    ))]
-    fn pynew(sample_rate: usize, envelope: Option<Vec<f64>>, waveform: &str) -> Self {
+impl Default for SynthesizerParams {
+    fn default() -> Self {
+        SynthesizerParams {
+            sample_rate: 44100,
+            envelope: Vec::new(),
+            waveform: "sin".into(),
+            glide: 0.0,
+            vibrato: Vec::new(),
+            tremolo: Vec::new(),
+            unison: Vec::new(),
+            fade: 0.005,
+            pan: 0.0,
+        }
+    }
+}
+
+impl ToJson for SynthesizerParams {
+    fn to_json(&self) -> Json {
+        Json::object([
+            ("sample_rate", self.sample_rate.to_json()),
+            ("envelope", self.envelope.to_json()),
+            ("waveform", self.waveform.to_json()),
+            ("glide", self.glide.to_json()),
+            ("vibrato", self.vibrato.to_json()),
+            ("tremolo", self.tremolo.to_json()),
+            ("unison", self.unison.to_json()),
+            ("fade", self.fade.to_json()),
+            ("pan", self.pan.to_json()),
+        ])
+    }
+}
+
+impl FromJson for SynthesizerParams {
+    fn from_json(json: &Json) -> Result<Self> {
+        let mut params = Self::default();
+        for (key, value) in json.fields()? {
+            match key.as_str() {
+                "sample_rate" => params.sample_rate = FromJson::from_json(value)?,
+                "envelope" => params.envelope = FromJson::from_json(value)?,
+                "waveform" => {
+                    params.waveform = json::checked(value, |w: &String| check_waveform(w))?
+                }
+                "glide" => params.glide = FromJson::from_json(value)?,
+                "vibrato" => params.vibrato = FromJson::from_json(value)?,
+                "tremolo" => params.tremolo = FromJson::from_json(value)?,
+                "unison" => params.unison = FromJson::from_json(value)?,
+                "fade" => params.fade = FromJson::from_json(value)?,
+                "pan" => params.pan = FromJson::from_json(value)?,
+                _ => return Err(json::unknown_key(key)),
+            }
+        }
+        Ok(params)
+    }
+}
+
+/// Builder of a Synthesizer starting from default parameters, see Synthesizer::builder()
+#[derive(Debug, Clone, Default)]
+pub struct SynthesizerBuilder {
+    params: SynthesizerParams,
+}
+
+impl SynthesizerBuilder {
+    /// sample rate, above 0
+    pub fn sample_rate(mut self, sample_rate: usize) -> Self {
+        self.params.sample_rate = sample_rate;
+        self
+    }
+
+    /// optional parameters for the tone envelope [a, h, d, s, r]
+    pub fn envelope(mut self, envelope: Vec<f64>) -> Self {
+        self.params.envelope = envelope;
+        self
+    }
+
+    /// waveform type, one of {sin, sqr, saw, tri, pulse, white, pink, brown, additive, table},
+    /// with a duty cycle like pulse:0.1, partials like additive:1,0.5@3 or a cycle like table:0,1,0,-1
+    pub fn waveform(mut self, waveform: &str) -> Self {
+        self.params.waveform = waveform.into();
+        self
+    }
+
+    /// time in seconds to slide in pitch from a tone to the next one, 0 to jump
+    pub fn glide(mut self, glide: f64) -> Self {
+        self.params.glide = glide;
+        self
+    }
+
+    /// optional vibrato [rate, depth] in Hz and cents
+    pub fn vibrato(mut self, vibrato: Vec<f64>) -> Self {
+        self.params.vibrato = vibrato;
+        self
+    }
+
+    /// optional tremolo [rate, depth] in Hz and from range <0; 1>
+    pub fn tremolo(mut self, tremolo: Vec<f64>) -> Self {
+        self.params.tremolo = tremolo;
+        self
+    }
+
+    /// optional unison [voices, detune, spread] with 1 to 16 voices, detune in cents and spread from range <0; 1>
+    pub fn unison(mut self, unison: Vec<f64>) -> Self {
+        self.params.unison = unison;
+        self
+    }
+
+    /// time in seconds to fade every tone in and out, 0 to turn off
+    pub fn fade(mut self, fade: f64) -> Self {
+        self.params.fade = fade;
+        self
+    }
+
+    /// position of tones in the stereo field from range <-1; 1> for stereo synthesis
+    pub fn pan(mut self, pan: f64) -> Self {
+        self.params.pan = pan;
+        self
+    }
+
+    /// create the Synthesizer with the parameters set so far
+    pub fn build(self) -> Synthesizer {
+        Synthesizer::with_params(self.params)
+    }
+}
+
+impl Algorithm for Synthesizer {
+    /// frequencies in Hz, 0 for rests, and durations in seconds of consecutive tones
+    type Input = (Vec<f64>, Vec<f64>);
+    /// signed 16-bit pcm values of synthesized data, silent at 0
+    type Output = Vec<i16>;
+    type Params = SynthesizerParams;
+
+    fn with_params(params: SynthesizerParams) -> Self {
         Synthesizer {
             freq: Vec::new(),
             durations: Vec::new(),
+            amplitudes: Vec::new(),
+            pans: Vec::new(),
             pcm_data: None,
-            sample_rate,
-            envelope: envelope.unwrap_or_default(),
-            waveform: waveform.into(),
+            sample_rate: params.sample_rate,
+            envelope: params.envelope,
+            waveform: params.waveform,
+            glide: params.glide,
+            vibrato: params.vibrato,
+            tremolo: params.tremolo,
+            unison: params.unison,
+            fade: params.fade,
+            pan: params.pan,
         }
     }
 
-    /// Compute the Algorithm
+    fn compute(&mut self, (freq, durations): Self::Input) -> Result<Vec<i16>> {
+        self.freq = freq;
+        self.durations = durations;
+        self.run()?;
+        Ok(self.pcm_data.take().unwrap())
+    }
+}
+
+impl Synthesizer {
+    /// start building a Synthesizer from default parameters
+    pub fn builder() -> SynthesizerBuilder {
+        SynthesizerBuilder::default()
+    }
+
+    /// synthesize the sequence of tones and set the output
+    fn run(&mut self) -> Result<()> {
+        self.pcm_data = Some(self.render()?);
+        Ok(())
+    }
+
+    /// compute the sequence of tones into the unsigned 16-bit pcm used before, centered at 32767
+    ///
+    /// It's kept for code written for that format, while .compute() returns signed pcm
+    /// without the offset and .render::<u16>() returns offset binary pcm centered at 0x8000.
+    pub fn compute_u16(&mut self, (freq, durations): (Vec<f64>, Vec<f64>)) -> Result<Vec<u16>> {
+        self.freq = freq;
+        self.durations = durations;
+        Ok(self.render::<f64>()?.into_iter().map(legacy_u16).collect())
+    }
+
+    /// synthesize the sequence of tones set as inputs into samples of any type
     ///
-    /// Inputs:
-    ///   - freq: list[float]
-    ///   - durations: list[float]
+    /// Unlike .compute(), which returns signed 16-bit pcm, it can render e.g. f32 samples
+    /// for further processing without converting them by hand.
+    /// Every tone is scaled by its amplitude, if there is one.
+    /// Fails with Error::InvalidParam for parameters out of their ranges, negative or non-finite
+    /// durations, or tones adding up to more than MAX_LENGTH samples.
+    pub fn render<S: Sample>(&self) -> Result<Vec<S>> {
+        self.check()?;
+        if self.glide > 0.0 {
+            let (left, _) = self.render_glide(false)?;
+            return Ok(left.into_iter().map(S::from_f64).collect());
+        }
+        let mut t = self.wavetable()?;
+
+        let n = core::cmp::min(self.freq.len(), self.durations.len());
+
+        let mut r = t.time(0.0).render::<S>()?;
+        for i in 0..n {
+            if self.freq[i] > 0.0 {
+                t.generator.freq(self.freq[i]);
+                let gain = self.amplitude(i);
+                let m = t.time(self.durations[i]).render::<f64>()?;
+                r.extend(m.into_iter().map(|x| S::from_f64(gain * x)));
+            } else {
+                let n = t.generator.time(self.durations[i]);
+                r.resize(r.len() + n, S::from_f64(0.0));
+            }
+        }
+        Ok(r)
+    }
+
+    /// synthesize notes that may overlap, like chords or legato melodies, into samples of any type
     ///
-    /// Outputs:
-    ///   - pcm_data: list[int]
+    /// Every note is a voice with its own envelope, scaled by its velocity and added to the others,
+    /// so integer samples are clipped if they add up above full scale.
+    /// Fails with Error::InvalidParam for negative or non-finite starts and durations,
+    /// notes ending above MAX_LENGTH samples, or a sample rate of 0.
+    pub fn render_notes<S: Sample>(&self, notes: &[NoteEvent]) -> Result<Vec<S>> {
+        self.check_notes(notes)?;
+
+        let mut t = self.wavetable()?;
+        let len = notes
+            .iter()
+            .map(|n| t.generator.time(n.start) + t.generator.time(n.duration))
+            .max()
+            .unwrap_or(0);
+        let mut mix = vec![0.0; len];
+        for note in notes.iter().filter(|n| n.freq > 0.0) {
+            t.generator.freq(note.freq);
+            let start = t.generator.time(note.start);
+            let voice = t.time(note.duration).render::<f64>()?;
+            for (y, x) in mix[start..].iter_mut().zip(voice) {
+                *y += note.velocity * x;
+            }
+        }
+        Ok(mix.into_iter().map(S::from_f64).collect())
+    }
+
+    /// synthesize the sequence of tones set as inputs into left and right channels
     ///
-    /// See data descriptors for more details.
-    #[pyo3(name = "compute", signature = (freq=None, durations=None))]
-    fn pycompute(&mut self, freq: Option<Vec<f64>>, durations: Option<Vec<f64>>) -> Vec<u16> {
-        if let Some(arg) = freq {
-            self.freq = arg
+    /// Every tone is panned with constant power by its pan, or the global pan if it has none,
+    /// and the voices of unison are spread around it.
+    /// Fails with Error::InvalidParam like .render(), or for pans that aren't finite.
+    pub fn render_stereo<S: Sample>(&self) -> Result<(Vec<S>, Vec<S>)> {
+        self.check()?;
+        if let Some(pan) = self.pans.iter().find(|pan| !pan.is_finite()) {
+            return Err(Error::InvalidParam(format!(
+                "pans must be finite, got {}",
+                pan
+            )));
         }
-        if let Some(arg) = durations {
-            self.durations = arg
+        let convert = |x: Vec<f64>| x.into_iter().map(S::from_f64).collect();
+        if self.glide > 0.0 {
+            let (left, right) = self.render_glide(true)?;
+            return Ok((convert(left), convert(right)));
         }
+        let mut t = self.wavetable()?;
 
-        self.compute();
+        let n = core::cmp::min(self.freq.len(), self.durations.len());
 
-        self.pcm_data.as_ref().unwrap().clone()
+        let (mut left, mut right) = (Vec::new(), Vec::new());
+        for i in 0..n {
+            if self.freq[i] > 0.0 {
+                t.generator.freq(self.freq[i]);
+                t.pan = Some(self.pan(i));
+                let gain = self.amplitude(i);
+                let (l, r) = t.time(self.durations[i]).render_stereo::<f64>()?;
+                left.extend(l.into_iter().map(|x| gain * x));
+                right.extend(r.into_iter().map(|x| gain * x));
+            } else {
+                let n = left.len() + t.generator.time(self.durations[i]);
+                left.resize(n, 0.0);
+                right.resize(n, 0.0);
+            }
+        }
+        Ok((convert(left), convert(right)))
     }
 
-    fn __call__(&mut self) {
-        self.compute()
+    /// synthesize notes that may overlap into left and right channels
+    ///
+    /// Every note is panned with constant power by its pan, or the global pan if it has none.
+    /// Fails with Error::InvalidParam like .render_notes(), or for pans that aren't finite.
+    pub fn render_notes_stereo<S: Sample>(&self, notes: &[NoteEvent]) -> Result<(Vec<S>, Vec<S>)> {
+        self.check_notes(notes)?;
+        if let Some(note) = notes
+            .iter()
+            .find(|n| !n.pan.unwrap_or(self.pan).is_finite())
+        {
+            return Err(Error::InvalidParam(format!(
+                "pans must be finite, got {:?}",
+                note
+            )));
+        }
+
+        let mut t = self.wavetable()?;
+        let len = notes
+            .iter()
+            .map(|n| t.generator.time(n.start) + t.generator.time(n.duration))
+            .max()
+            .unwrap_or(0);
+        let (mut left, mut right) = (vec![0.0; len], vec![0.0; len]);
+        for note in notes.iter().filter(|n| n.freq > 0.0) {
+            t.generator.freq(note.freq);
+            t.pan = Some(note.pan.unwrap_or(self.pan));
+            let start = t.generator.time(note.start);
+            let (l, r) = t.time(note.duration).render_stereo::<f64>()?;
+            for (y, x) in left[start..].iter_mut().zip(l) {
+                *y += note.velocity * x;
+            }
+            for (y, x) in right[start..].iter_mut().zip(r) {
+                *y += note.velocity * x;
+            }
+        }
+        let convert = |x: Vec<f64>| x.into_iter().map(S::from_f64).collect();
+        Ok((convert(left), convert(right)))
     }
-}
 
-impl Algorithm for Synthesizer {
-    fn new() -> Self {
-        Self::pynew(44100, None, "sin")
+    /// check the parameters and the durations of the sequence of tones in mono and stereo
+    ///
+    /// Durations must be positive times, adding up to at most MAX_LENGTH samples.
+    fn check(&self) -> Result<()> {
+        check_positive("sample_rate", self.sample_rate as f64)?;
+        check_range("glide", self.glide, 0.0, f64::INFINITY)?;
+        check_range("fade", self.fade, 0.0, f64::INFINITY)?;
+        check_range("pan", self.pan, -1.0, 1.0)?;
+        check_waveform(&self.waveform)?;
+        check_unison(&self.unison)?;
+        if let Some(d) = self
+            .durations
+            .iter()
+            .find(|d| !(d.is_finite() && **d >= 0.0))
+        {
+            return Err(Error::InvalidParam(format!(
+                "durations must be positive times, got {}",
+                d
+            )));
+        }
+        let rate = self.sample_rate as f64;
+        let length = self.durations.iter().map(|d| (d * rate).ceil()).sum();
+        check_length("synthesized data", length)
     }
 
-    fn compute(&mut self) {
-        let w = match self.waveform.as_str() {
-            "sin" => Waveform::Sin,
-            "sqr" => Waveform::Square,
-            "saw" => Waveform::Sawtooth,
-            _ => Waveform::Sin,
-        };
+    /// check the notes to synthesize, the sample rate, pan and unison
+    fn check_notes(&self, notes: &[NoteEvent]) -> Result<()> {
+        check_positive("sample_rate", self.sample_rate as f64)?;
+        check_range("pan", self.pan, -1.0, 1.0)?;
+        check_waveform(&self.waveform)?;
+        check_unison(&self.unison)?;
+        if let Some(note) = notes.iter().find(|n| {
+            !(n.start.is_finite() && n.start >= 0.0 && n.duration.is_finite() && n.duration >= 0.0)
+        }) {
+            return Err(Error::InvalidParam(format!(
+                "notes must start and last for a positive time, got {:?}",
+                note
+            )));
+        }
+        let rate = self.sample_rate as f64;
+        let length = notes
+            .iter()
+            .map(|n| (n.start * rate).ceil() + (n.duration * rate).ceil())
+            .fold(0.0, f64::max);
+        check_length("synthesized data", length)
+    }
+
+    /// synthesize the sequence of tones sliding in pitch from one to the next
+    ///
+    /// The frequency moves exponentially, so evenly in pitch, from the previous tone
+    /// over the glide time. The phase is accumulated across tones so the wave is continuous,
+    /// while the envelope starts over with every tone. Tones after a rest start at their pitch.
+    /// Tones only fade in after a rest and out before one, as they are joined otherwise.
+    /// In stereo, tones are panned into both channels, otherwise the right one stays empty.
+    fn render_glide(&self, stereo: bool) -> Result<(Vec<f64>, Vec<f64>)> {
+        let t = self.wavetable()?;
+        let mut voices = t.voices();
+        let Wavetable {
+            generator,
+            envelope,
+            vibrato,
+            tremolo,
+            unison,
+            fade,
+            ..
+        } = t;
+        let unison = unison.unwrap_or_default();
+        let rate = generator.sample_rate;
+        let glide = generator.time(self.glide) as f64;
+        let fade = fade.map_or(0, |fade| generator.time(fade));
+
+        let (mut left, mut right) = (Vec::new(), Vec::new());
+        let mut last = None;
+        let tones = core::cmp::min(self.freq.len(), self.durations.len());
+        for (k, (&freq, &duration)) in self.freq.iter().zip(self.durations.iter()).enumerate() {
+            let n = generator.time(duration);
+            if freq <= 0.0 {
+                left.resize(left.len() + n, 0.0);
+                if stereo {
+                    right.resize(right.len() + n, 0.0);
+                }
+                last = None;
+                continue;
+            }
+            // a mono tone takes all of every voice in the left channel
+            let pans: Vec<(f64, f64)> = (0..voices.len())
+                .map(|j| match stereo {
+                    true => pan_gains(self.pan(k) + unison.voice(j).1),
+                    false => (1.0, 0.0),
+                })
+                .collect();
+            let fade_in = last.is_none();
+            let fade_out = k + 1 == tones || self.freq[k + 1] <= 0.0;
+            let from = last.unwrap_or(freq);
+            for i in 0..n {
+                let mut f = from * (freq / from).powf((i as f64 / glide).min(1.0));
+                if let Some(v) = &vibrato {
+                    f *= v.ratio(i, rate);
+                }
+                let mut gain = match &envelope {
+                    Some(e) => e.multiplier(&generator, i, n),
+                    None => 1.0,
+                };
+                if let Some(t) = &tremolo {
+                    gain *= t.gain(i, rate);
+                }
+                gain *= self.amplitude(k) / voices.len() as f64;
+                gain *= ramps(i, n, fade, fade_in, fade_out);
+                let (mut l, mut r) = (0.0, 0.0);
+                for (j, ((g, _), (gl, gr))) in voices.iter_mut().zip(&pans).enumerate() {
+                    g.set_freq(f * unison.voice(j).0);
+                    let y = g.next_sample();
+                    l += gl * y;
+                    r += gr * y;
+                }
+                left.push(gain * l);
+                if stereo {
+                    right.push(gain * r);
+                }
+            }
+            last = Some(freq);
+        }
+        Ok((left, right))
+    }
+
+    /// gain of the i-th tone of the sequence, full scale if it has no amplitude
+    fn amplitude(&self, i: usize) -> f64 {
+        self.amplitudes.get(i).copied().unwrap_or(1.0)
+    }
+
+    /// pan of the i-th tone of the sequence, the global pan if it has none
+    fn pan(&self, i: usize) -> f64 {
+        self.pans.get(i).copied().unwrap_or(self.pan)
+    }
+
+    /// wavetable of the waveform and envelope of this Synthesizer
+    fn wavetable(&self) -> Result<Wavetable> {
+        let w = self.waveform.parse()?;
 
         let e = if self.envelope.len() == 5 {
             Some(Envelope {
@@ -91,206 +832,1903 @@ impl Algorithm for Synthesizer {
                 d: self.envelope[2],
                 s: self.envelope[3],
                 r: self.envelope[4],
+                ..Envelope::default()
             })
         } else {
             None
         };
 
-        let mut t = Wavetable {
+        let lfo = |x: &[f64]| match x {
+            [rate, depth] => Some(LFO::new(*rate, *depth)),
+            _ => None,
+        };
+
+        Ok(Wavetable {
             generator: Generator::new(0.0, Some(self.sample_rate as f64), Some(w)),
             envelope: e,
             samples: None,
-        };
+            vibrato: lfo(&self.vibrato),
+            tremolo: lfo(&self.tremolo),
+            unison: match self.unison[..] {
+                [voices, detune, spread] => {
+                    Some(Unison::new(voices as usize, detune).spread(spread))
+                }
+                _ => None,
+            },
+            fade: (self.fade > 0.0).then_some(self.fade),
+            pan: None,
+        })
+    }
+}
+
+/// NoteEvent is a note played by the Synthesizer at any time, overlapping others or not.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NoteEvent {
+    /// frequency of the tone in Hz, 0 for a rest
+    pub freq: f64,
+    /// start of the note in seconds
+    pub start: f64,
+    /// duration of the note in seconds, including the release of the envelope
+    pub duration: f64,
+    /// gain of the note from range <0; 1>
+    pub velocity: f64,
+    /// position in the stereo field from range <-1; 1>, the pan of the Synthesizer if None
+    pub pan: Option<f64>,
+}
+
+impl NoteEvent {
+    /// create a note at full velocity
+    pub fn new(freq: f64, start: f64, duration: f64) -> Self {
+        NoteEvent {
+            freq,
+            start,
+            duration,
+            velocity: 1.0,
+            pan: None,
+        }
+    }
+
+    /// set the gain of the note from range <0; 1>
+    pub fn velocity(mut self, velocity: f64) -> Self {
+        self.velocity = velocity;
+        self
+    }
+
+    /// set the position in the stereo field from range <-1; 1>, from the left to the right
+    pub fn pan(mut self, pan: f64) -> Self {
+        self.pan = Some(pan);
+        self
+    }
+}
+
+/// Sequencer of notes and rests written in note values at a tempo, played by the Synthesizer
+///
+/// Note values are fractions of a whole note, like 0.25 for a quarter note or 0.375
+/// for a dotted one, and the tempo counts beats of a quarter note unless set otherwise.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sequencer {
+    tempo: f64,
+    beat: f64,
+    tuning: Tuning,
+    /// MIDI note numbers, None for rests, with their note values
+    steps: Vec<(Option<u8>, f64)>,
+}
+
+impl Sequencer {
+    /// create an empty Sequencer at a tempo in beats per minute
+    pub fn new(tempo: f64) -> Self {
+        Sequencer {
+            tempo,
+            beat: 0.25,
+            tuning: Tuning::default(),
+            steps: Vec::new(),
+        }
+    }
+
+    /// set the note value of a beat, e.g. 0.375 for a dotted quarter note in 6/8
+    pub fn beat(mut self, beat: f64) -> Self {
+        self.beat = beat;
+        self
+    }
+
+    /// set the tuning mapping note numbers to frequencies
+    pub fn tuning(mut self, tuning: Tuning) -> Self {
+        self.tuning = tuning;
+        self
+    }
+
+    /// add a note of a MIDI note number lasting for a note value
+    pub fn note(mut self, pitch: u8, value: f64) -> Self {
+        self.steps.push((Some(pitch), value));
+        self
+    }
+
+    /// add a rest lasting for a note value
+    pub fn rest(mut self, value: f64) -> Self {
+        self.steps.push((None, value));
+        self
+    }
+
+    /// frequencies of consecutive notes in Hz, 0 for rests, as inputs of the Synthesizer
+    pub fn frequencies(&self) -> Vec<f64> {
+        self.steps
+            .iter()
+            .map(|(pitch, _)| pitch.map_or(0.0, |p| self.tuning.frequency(p as f64)))
+            .collect()
+    }
+
+    /// durations of consecutive notes and rests in seconds, as inputs of the Synthesizer
+    ///
+    /// Fails with Error::InvalidParam for a tempo or beat that isn't positive,
+    /// or a negative note value.
+    pub fn durations(&self) -> Result<Vec<f64>> {
+        if !(self.tempo.is_finite() && self.tempo > 0.0 && self.beat.is_finite() && self.beat > 0.0)
+        {
+            return Err(Error::InvalidParam(format!(
+                "tempo and beat of a Sequencer must be positive, got {} and {}",
+                self.tempo, self.beat
+            )));
+        }
+        let whole = 60.0 / self.tempo / self.beat;
+        self.steps
+            .iter()
+            .map(|(_, value)| {
+                if value.is_finite() && *value >= 0.0 {
+                    Ok(value * whole)
+                } else {
+                    Err(Error::InvalidParam(format!(
+                        "note values must be positive, got {}",
+                        value
+                    )))
+                }
+            })
+            .collect()
+    }
+
+    /// synthesize the sequence with a Synthesizer, returning its signed 16-bit pcm values
+    pub fn render(&self, synthesizer: &mut Synthesizer) -> Result<Vec<i16>> {
+        synthesizer.compute((self.frequencies(), self.durations()?))
+    }
+}
+
+/// check that the envelope is either empty or has all of its 5 values
+#[cfg(feature = "python")]
+fn check_envelope(envelope: &[f64]) -> PyResult<()> {
+    if envelope.is_empty() || envelope.len() == 5 {
+        Ok(())
+    } else {
+        Err(PyValueError::new_err(format!(
+            "envelope must have 5 values [a, h, d, s, r], got {:?}",
+            envelope
+        )))
+    }
+}
+
+/// check that a modulation is either empty or has its rate and depth
+#[cfg(feature = "python")]
+fn check_modulation(name: &str, lfo: &[f64]) -> PyResult<()> {
+    if lfo.is_empty() || lfo.len() == 2 {
+        Ok(())
+    } else {
+        Err(PyValueError::new_err(format!(
+            "{} must have 2 values [rate, depth], got {:?}",
+            name, lfo
+        )))
+    }
+}
+
+/// check that the unison is either empty or has all of its 3 values,
+/// with 1 to Unison::MAX_VOICES voices, a finite detune and a spread from range <0; 1>
+fn check_unison(unison: &[f64]) -> Result<()> {
+    match unison {
+        [] => Ok(()),
+        [voices, detune, spread] => {
+            check_range("unison voices", *voices, 1.0, Unison::MAX_VOICES as f64)?;
+            check_range("unison detune", *detune, f64::NEG_INFINITY, f64::INFINITY)?;
+            check_range("unison spread", *spread, 0.0, 1.0)
+        }
+        _ => Err(Error::InvalidParam(format!(
+            "unison must have 3 values [voices, detune, spread], got {:?}",
+            unison
+        ))),
+    }
+}
+
+/// check that the waveform is known, see Waveform::parse()
+fn check_waveform(waveform: &str) -> Result<()> {
+    waveform.parse::<Waveform>().map(|_| ())
+}
+
+/// check that the color of noise is known, and get its waveform
+#[cfg(feature = "python")]
+fn check_noise(color: &str) -> Result<Waveform> {
+    check_choice("color", color, &["white", "pink", "brown"])?;
+    color.parse()
+}
+
+/// waveforms supported by the tone generator
+#[derive(Debug, Clone, PartialEq)]
+pub enum Waveform {
+    /// sinusoidal wave
+    Sin,
+    /// square wave
+    Square,
+    /// sawtooth wave
+    Sawtooth,
+    /// triangle wave
+    Triangle,
+    /// pulse wave, high for a duty cycle from range (0; 1) of the period
+    Pulse(f64),
+    /// white noise, the same for every tone as it doesn't depend on the frequency
+    WhiteNoise,
+    /// pink noise falling by 3 dB per octave, the same for every tone
+    PinkNoise,
+    /// brown noise falling by 6 dB per octave, the same for every tone
+    BrownNoise,
+    /// sum of sine partials given as (amplitude, detune in cents) of harmonics from the fundamental up,
+    /// scaled to fit the range
+    Additive(Vec<(f64, f64)>),
+    /// single cycle drawn or sampled by the user, read with linear or cubic interpolation
+    Table {
+        /// evenly spaced samples of the cycle from range <-1; 1>, without repeating the first one
+        samples: Vec<f64>,
+        /// interpolate with a Catmull-Rom spline through 4 samples instead of a line through 2
+        cubic: bool,
+    },
+}
+
+impl core::str::FromStr for Waveform {
+    type Err = Error;
+
+    /// waveform of a name, see Waveform::parse()
+    ///
+    /// Fails with Error::InvalidParam for unknown names, saying what additive waves and tables need.
+    fn from_str(name: &str) -> Result<Self> {
+        if let Some(waveform) = Waveform::parse(name) {
+            return Ok(waveform);
+        }
+        if name.starts_with("additive") {
+            return Err(Error::InvalidParam(format!(
+                "additive waveform needs amplitudes of its partials like additive:1,0.5@3, got {}",
+                name
+            )));
+        }
+        if name.starts_with("table") {
+            return Err(Error::InvalidParam(format!(
+                "table waveform needs the samples of a cycle like table:0,1,0,-1 or table:cubic:0,1,0,-1, got {}",
+                name
+            )));
+        }
+        let names: Vec<&str> = Waveform::ALL.iter().map(|w| w.name()).collect();
+        check_choice("waveform", name, &names)?;
+        unreachable!("every name of Waveform::ALL is parsed")
+    }
+}
+
+impl core::fmt::Display for Waveform {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Waveform::Pulse(duty) if *duty != Waveform::DUTY => write!(f, "pulse:{}", duty),
+            Waveform::Additive(partials) => {
+                let partials: Vec<String> = partials
+                    .iter()
+                    .map(|(a, cents)| match cents {
+                        0.0 => a.to_string(),
+                        _ => format!("{}@{}", a, cents),
+                    })
+                    .collect();
+                write!(f, "additive:{}", partials.join(","))
+            }
+            Waveform::Table { samples, cubic } => {
+                let samples: Vec<String> = samples.iter().map(|x| x.to_string()).collect();
+                let cubic = if *cubic { "cubic:" } else { "" };
+                write!(f, "table:{}{}", cubic, samples.join(","))
+            }
+            _ => f.write_str(self.name()),
+        }
+    }
+}
+
+impl Waveform {
+    /// all of the waveforms, in the order of their names in the docs
+    pub const ALL: [Waveform; 10] = [
+        Waveform::Sin,
+        Waveform::Square,
+        Waveform::Sawtooth,
+        Waveform::Triangle,
+        Waveform::Pulse(Waveform::DUTY),
+        Waveform::WhiteNoise,
+        Waveform::PinkNoise,
+        Waveform::BrownNoise,
+        Waveform::Additive(Vec::new()),
+        Waveform::Table {
+            samples: Vec::new(),
+            cubic: false,
+        },
+    ];
+
+    /// duty cycle of a pulse wave named without one
+    pub const DUTY: f64 = 0.25;
+
+    /// waveform of a name, with an optional duty cycle of a pulse wave like pulse:0.1
+    ///
+    /// Additive waves list the amplitudes of their partials, each detuned by a number of cents
+    /// after an @, like additive:1,0.5@3,0.25 for a third harmonic, a slightly sharp second one.
+    /// Tables list the samples of their cycle, like table:0,1,0,-1 or table:cubic:0,1,0,-1
+    /// to read them with cubic interpolation.
+    /// None for unknown names, duty cycles out of range (0; 1) and additive waves or tables
+    /// without values.
+    pub fn parse(name: &str) -> Option<Waveform> {
+        if let Some(duty) = name.strip_prefix("pulse:") {
+            return duty
+                .trim()
+                .parse()
+                .ok()
+                .filter(|duty| *duty > 0.0 && *duty < 1.0)
+                .map(Waveform::Pulse);
+        }
+        let number = |x: &str| x.trim().parse().ok().filter(|x: &f64| x.is_finite());
+        if let Some(samples) = name.strip_prefix("table:") {
+            let (samples, cubic) = match samples.strip_prefix("cubic:") {
+                Some(samples) => (samples, true),
+                None => (samples, false),
+            };
+            return samples
+                .split(',')
+                .map(number)
+                .collect::<Option<Vec<_>>>()
+                .map(|samples| Waveform::Table { samples, cubic });
+        }
+        if let Some(partials) = name.strip_prefix("additive:") {
+            return partials
+                .split(',')
+                .map(|partial| match partial.split_once('@') {
+                    Some((a, cents)) => Some((number(a)?, number(cents)?)),
+                    None => Some((number(partial)?, 0.0)),
+                })
+                .collect::<Option<Vec<_>>>()
+                .map(Waveform::Additive);
+        }
+        Waveform::ALL
+            .iter()
+            .filter(|w| !matches!(w, Waveform::Additive(_) | Waveform::Table { .. }))
+            .find(|w| w.name() == name)
+            .cloned()
+    }
+
+    /// additive wave of harmonics with amplitudes from the fundamental up, in tune
+    pub fn additive(amplitudes: &[f64]) -> Waveform {
+        Waveform::Additive(amplitudes.iter().map(|a| (*a, 0.0)).collect())
+    }
+
+    /// table of a single cycle read with linear interpolation
+    pub fn table(samples: Vec<f64>) -> Waveform {
+        Waveform::Table {
+            samples,
+            cubic: false,
+        }
+    }
+
+    /// short name of this waveform, one of {sin, sqr, saw, tri, pulse, white, pink, brown, additive, table}
+    pub fn name(&self) -> &'static str {
+        match self {
+            Waveform::Sin => "sin",
+            Waveform::Square => "sqr",
+            Waveform::Sawtooth => "saw",
+            Waveform::Triangle => "tri",
+            Waveform::Pulse(_) => "pulse",
+            Waveform::WhiteNoise => "white",
+            Waveform::PinkNoise => "pink",
+            Waveform::BrownNoise => "brown",
+            Waveform::Additive(_) => "additive",
+            Waveform::Table { .. } => "table",
+        }
+    }
+
+    /// amplitude value from range <-1; 1> at a phase given in periods since the start of the tone
+    ///
+    /// Only the fraction of the phase matters for periodic waves, while detuned partials
+    /// of additive waves drift apart over the periods.
+    /// Noise has no period, so it's drawn at random for every phase.
+    pub fn at_phase(&self, phase: f64) -> f64 {
+        let cycle = phase.rem_euclid(1.0);
+        match self {
+            Waveform::Sin => (2.0 * core::f64::consts::PI * cycle).sin(),
+            Waveform::Square => Waveform::Pulse(0.5).at_phase(cycle),
+            Waveform::Sawtooth => 2.0 * cycle - 1.0,
+            Waveform::Triangle => 1.0 - 4.0 * ((cycle + 0.25).fract() - 0.5).abs(),
+            Waveform::Pulse(duty) => {
+                if cycle < *duty {
+                    1.0
+                } else {
+                    -1.0
+                }
+            }
+            Waveform::WhiteNoise | Waveform::PinkNoise | Waveform::BrownNoise => {
+                self.noise(phase.to_bits(), 0)
+            }
+            Waveform::Additive(partials) => additive(partials, phase, f64::INFINITY),
+            Waveform::Table { samples, cubic } => table(samples, cycle, *cubic),
+        }
+    }
+
+    /// amplitude value from range <-1; 1> of noise at a sample x, the same for the same x and seed
+    ///
+    /// Different seeds give independent noise, while seed 0 is the noise of .at_phase().
+    /// Other waveforms are silent.
+    pub fn noise(&self, x: u64, seed: u64) -> f64 {
+        // pink noise as white noise summed over rows held for 2^k samples (Voss-McCartney)
+        const ROWS: u32 = 16;
+        let seed = seed.wrapping_mul(0x9e37_79b9_7f4a_7c15);
+        let white = |row: u32, x: u64| Rng::new(x ^ ((row as u64) << 58) ^ seed).bipolar();
+        match self {
+            Waveform::WhiteNoise => white(0, x),
+            Waveform::PinkNoise => {
+                let sum: f64 = (0..ROWS).map(|k| white(k, x >> k)).sum();
+                // most of the time well within range, keeping the level close to white noise
+                (sum / (ROWS / 2) as f64).clamp(-1.0, 1.0)
+            }
+            Waveform::BrownNoise => {
+                // rows ramping between their values, weighted by 2^(k/2) so they fall by 6 dB
+                // per octave above 2^-k of the sample rate
+                let sum: f64 = (0..ROWS)
+                    .map(|k| {
+                        let t = (x & ((1 << k) - 1)) as f64 / (1u64 << k) as f64;
+                        let (a, b) = (white(k, x >> k), white(k, (x >> k) + 1));
+                        2f64.powf(k as f64 / 2.0) * (a + (b - a) * t)
+                    })
+                    .sum();
+                (sum / (1.5 * (1u64 << (ROWS / 2)) as f64)).clamp(-1.0, 1.0)
+            }
+            _ => 0.0,
+        }
+    }
+}
+
+python_class! {
+    /// tone generator with a given frequency and sample rate
+    ///
+    /// Samples are either computed at any index with .amplitude(), or one after another
+    /// with .next_sample(), accumulating the phase so the frequency can change without clicks.
+    #[pyclass(module = "muslib")]
+    #[derive(Clone)]
+    pub struct Generator {
+        /// tone frequency expressed in Hz
+        #[pyo3(get, set)]
+        freq: f64,
+        /// sample rate
+        #[pyo3(get, set)]
+        sample_rate: f64,
+        waveform: Waveform,
+        /// smooth the edges of square, sawtooth and pulse waves with PolyBLEP to avoid aliasing,
+        /// and leave out partials of additive waves above the Nyquist frequency
+        #[pyo3(get, set)]
+        band_limited: bool,
+        /// phase in periods of the next sample of .next_sample()
+        phase: f64,
+        /// number of samples returned by .next_sample()
+        position: usize,
+        /// seed of the noise, generators with the same seed make the same noise
+        #[pyo3(get, set)]
+        seed: u64,
+    }
+}
+
+#[cfg(feature = "python")]
+pymethods_with_params! {
+    impl Generator {
+        #[new]
+        #[pyo3(signature = (
+            freq=440.0,
+            sample_rate=44100.0,
+            waveform="sin",
+            band_limited=false,
+            seed=0
+        ))]
+        fn pynew(
+            freq: f64,
+            sample_rate: f64,
+            waveform: &str,
+            band_limited: bool,
+            seed: u64,
+        ) -> PyResult<Self> {
+            let mut g = Self::new(freq, Some(sample_rate), Some(waveform.parse()?));
+            g.band_limited(band_limited);
+            g.seed(seed);
+            Ok(g)
+        }
+
+        /// waveform type as a str, one of {sin, sqr, saw, tri, pulse, white, pink, brown, additive, table},
+        /// with a duty cycle like pulse:0.1, partials like additive:1,0.5@3 or a cycle like table:0,1,0,-1
+        #[getter(waveform)]
+        fn get_waveform(&self) -> String {
+            self.waveform.to_string()
+        }
+
+        #[setter(waveform)]
+        fn set_waveform(&mut self, waveform: &str) -> PyResult<()> {
+            self.waveform = waveform.parse()?;
+            Ok(())
+        }
+
+        /// amplitude value from range <-1; 1> of the tone for a sample x
+        #[pyo3(name = "amplitude")]
+        fn pyamplitude(&self, x: usize) -> f64 {
+            self.amplitude(x)
+        }
 
-        let n = std::cmp::min(self.freq.len(), self.durations.len());
+        /// sample number for time given in seconds
+        #[pyo3(name = "time")]
+        fn pytime(&self, t: f64) -> usize {
+            self.time(t)
+        }
+
+        /// amplitude value from range <-1; 1> of the next sample of a tone running continuously
+        ///
+        /// The phase is kept when .freq is set in between, so the wave has no jumps.
+        #[pyo3(name = "next_sample")]
+        fn pynext_sample(&mut self) -> f64 {
+            self.next_sample()
+        }
+    }
+
+    params = ["freq", "sample_rate", "waveform", "band_limited", "seed"];
+
+    fn reset(&mut self) {
+        self.reset();
+    }
+}
+
+impl Generator {
+    /// create a new tone generator
+    pub fn new(freq: f64, sample_rate: Option<f64>, w: Option<Waveform>) -> Self {
+        Generator {
+            freq,
+            sample_rate: sample_rate.unwrap_or(44100.0),
+            waveform: w.unwrap_or(Waveform::Sin),
+            band_limited: false,
+            phase: 0.0,
+            position: 0,
+            seed: 0,
+        }
+    }
+
+    /// change the tone frequency for this tone generator
+    pub fn freq(&mut self, f: f64) -> &Self {
+        self.freq = f;
+        self
+    }
+
+    /// change the sample rate for this tone generator
+    pub fn sample_rate(&mut self, sample_rate: f64) -> &Self {
+        self.sample_rate = sample_rate;
+        self
+    }
+
+    /// change the tone waveform type for this tone generator
+    pub fn w(&mut self, w: Waveform) -> &Self {
+        self.waveform = w;
+        self
+    }
+
+    /// switch between naive waves and band-limited ones, clean enough for spectral analysis
+    ///
+    /// The jumps of square, sawtooth and pulse waves are smoothed with PolyBLEP,
+    /// partials of additive waves above the Nyquist frequency are left out,
+    /// others are the same either way.
+    pub fn band_limited(&mut self, band_limited: bool) -> &Self {
+        self.band_limited = band_limited;
+        self
+    }
+
+    /// change the seed of the noise, so generators make independent noise
+    pub fn seed(&mut self, seed: u64) -> &Self {
+        self.seed = seed;
+        self
+    }
+
+    /// amplitude value of the sinusoidal wave tone for a sample x
+    fn sin(&self, x: f64) -> f64 {
+        let x: f64 = (core::f64::consts::PI) * 2.0 * x * self.freq / self.sample_rate;
+        x.sin()
+    }
+
+    /// amplitude value of the square wave tone for a sample x
+    fn sqr(&self, x: f64) -> f64 {
+        if (2.0 * x * self.freq / self.sample_rate) % 2.0 < 1.0 {
+            1.0
+        } else {
+            -1.0
+        }
+    }
+
+    /// amplitude of the sawtooth wave tone for a sample x
+    fn saw(&self, x: f64) -> f64 {
+        let x: f64 = x * self.freq / self.sample_rate;
+        2.0 * (x - x.floor()) - 1.0
+    }
+
+    /// amplitude value from range <-1; 1> of the tone for a sample x
+    pub fn amplitude(&self, x: usize) -> f64 {
+        let w = &self.waveform;
+        let dt = self.freq / self.sample_rate;
+        let limited = matches!(
+            w,
+            Waveform::Square | Waveform::Pulse(_) | Waveform::Sawtooth | Waveform::Additive(_)
+        );
+        if self.band_limited && dt > 0.0 && limited {
+            return self.at_phase(x as f64 * dt, x);
+        }
+        match w {
+            Waveform::Sin => self.sin(x as f64),
+            Waveform::Square => self.sqr(x as f64),
+            Waveform::Sawtooth => self.saw(x as f64),
+            Waveform::WhiteNoise | Waveform::PinkNoise | Waveform::BrownNoise => {
+                w.noise(x as u64, self.seed)
+            }
+            _ => w.at_phase(x as f64 * dt),
+        }
+    }
+
+    /// amplitude value from range <-1; 1> of the tone at a phase in periods for a sample x
+    ///
+    /// Unlike .amplitude(), the phase can be accumulated with .advance() while the frequency
+    /// changes, so the wave stays continuous. Noise only depends on the sample.
+    pub fn at_phase(&self, phase: f64, x: usize) -> f64 {
+        let w = &self.waveform;
+        let dt = self.freq / self.sample_rate;
+        if self.band_limited && dt > 0.0 {
+            let cycle = phase.rem_euclid(1.0);
+            // jumps up by 2 at the start of the period, and down at the end of the duty cycle
+            let jump = |duty: f64| {
+                w.at_phase(cycle) + polyblep(cycle, dt)
+                    - polyblep((cycle - duty).rem_euclid(1.0), dt)
+            };
+            match w {
+                Waveform::Square => return jump(0.5),
+                Waveform::Pulse(duty) => return jump(*duty),
+                Waveform::Sawtooth => return w.at_phase(cycle) - polyblep(cycle, dt),
+                // partials above the Nyquist frequency would alias
+                Waveform::Additive(partials) => return additive(partials, phase, 0.5 / dt),
+                _ => {}
+            }
+        }
+        match w {
+            Waveform::WhiteNoise | Waveform::PinkNoise | Waveform::BrownNoise => {
+                w.noise(x as u64, self.seed)
+            }
+            _ => w.at_phase(phase),
+        }
+    }
+
+    /// phase in periods after one more sample at the current frequency
+    pub fn advance(&self, phase: f64) -> f64 {
+        phase + self.freq / self.sample_rate
+    }
+
+    /// amplitude value from range <-1; 1> of the next sample of a tone running continuously
+    ///
+    /// The phase is accumulated sample by sample, so the frequency can change between calls
+    /// without a jump in the wave that would be heard as a click.
+    pub fn next_sample(&mut self) -> f64 {
+        let y = self.at_phase(self.phase, self.position);
+        self.phase = self.advance(self.phase);
+        self.position += 1;
+        y
+    }
+
+    /// change the tone frequency from the next sample on, keeping the phase of the running tone
+    pub fn set_freq(&mut self, f: f64) {
+        self.freq = f;
+    }
+
+    /// start the running tone of .next_sample() over from phase 0
+    pub fn reset(&mut self) {
+        self.phase = 0.0;
+        self.position = 0;
+    }
+
+    /// sample number for time given in seconds
+    pub fn time(&self, t: f64) -> usize {
+        (t * self.sample_rate).ceil() as usize
+    }
+}
+
+python_class! {
+    /// source of white, pink or brown noise rendered to buffers, e.g. to test filters or spectra
+    ///
+    /// The noise is the same as the noise waveforms of a Generator with the same seed,
+    /// so any part of it can be rendered again the same, and different seeds are independent.
+    #[pyclass(module = "muslib")]
+    #[derive(Clone)]
+    pub struct Noise {
+        color: Waveform,
+        /// seed of the noise
+        #[pyo3(get, set)]
+        pub seed: u64,
+    }
+}
+
+#[cfg(feature = "python")]
+pymethods_with_params! {
+    impl Noise {
+        #[new]
+        #[pyo3(signature = (color="white", seed=0))]
+        fn pynew(color: &str, seed: u64) -> PyResult<Self> {
+            Ok(Noise::new(check_noise(color)?, seed))
+        }
+
+        /// color of the noise as a str, one of {white, pink, brown}
+        #[getter(color)]
+        fn get_color(&self) -> String {
+            self.color.to_string()
+        }
+
+        #[setter(color)]
+        fn set_color(&mut self, color: &str) -> PyResult<()> {
+            self.color = check_noise(color)?;
+            Ok(())
+        }
+
+        /// amplitude value from range <-1; 1> of the noise at a sample x
+        #[pyo3(name = "at")]
+        fn pyat(&self, x: usize) -> f64 {
+            self.at(x)
+        }
+
+        /// array of n samples of the noise from the start
+        #[pyo3(name = "render")]
+        fn pyrender(&self, py: Python<'_>, n: usize) -> Array<f64> {
+            py.allow_threads(|| self.render(n)).into()
+        }
+    }
+
+    params = ["color", "seed"];
+}
+
+impl Noise {
+    /// create a new source of noise of a color, one of the noise waveforms
+    ///
+    /// Any other waveform is silent.
+    pub fn new(color: Waveform, seed: u64) -> Self {
+        Noise { color, seed }
+    }
+
+    /// white noise with equal power at all frequencies
+    pub fn white(seed: u64) -> Self {
+        Self::new(Waveform::WhiteNoise, seed)
+    }
+
+    /// pink noise falling by 3 dB per octave
+    pub fn pink(seed: u64) -> Self {
+        Self::new(Waveform::PinkNoise, seed)
+    }
+
+    /// brown noise falling by 6 dB per octave
+    pub fn brown(seed: u64) -> Self {
+        Self::new(Waveform::BrownNoise, seed)
+    }
+
+    /// amplitude value from range <-1; 1> of the noise at a sample x
+    pub fn at(&self, x: usize) -> f64 {
+        self.color.noise(x as u64, self.seed)
+    }
+
+    /// n samples of the noise from the start
+    pub fn render(&self, n: usize) -> Vec<f64> {
+        (0..n).map(|x| self.at(x)).collect()
+    }
+}
+
+python_class! {
+    /// sine sweeping from one frequency to another over a duration, e.g. to measure filters
+    ///
+    /// A linear sweep changes the frequency by the same number of Hz every second,
+    /// a logarithmic one by the same number of octaves, spending as much time on every octave.
+    /// The phase is integrated exactly, so the sweep is continuous and starts at phase 0.
+    #[pyclass(module = "muslib")]
+    #[derive(Clone)]
+    pub struct Sweep {
+        /// starting frequency in Hz
+        #[pyo3(get, set)]
+        pub f0: f64,
+        /// final frequency in Hz
+        #[pyo3(get, set)]
+        pub f1: f64,
+        /// duration in seconds
+        #[pyo3(get, set)]
+        pub duration: f64,
+        /// sample rate
+        #[pyo3(get, set)]
+        pub sample_rate: f64,
+        /// sweep logarithmically instead of linearly, both frequencies must be positive
+        #[pyo3(get, set)]
+        pub log: bool,
+    }
+}
+
+#[cfg(feature = "python")]
+pymethods_with_params! {
+    impl Sweep {
+        #[new]
+        #[pyo3(signature = (f0=20.0, f1=20000.0, duration=1.0, sample_rate=44100.0, log=false))]
+        fn pynew(f0: f64, f1: f64, duration: f64, sample_rate: f64, log: bool) -> Self {
+            Sweep::new(f0, f1, duration, sample_rate).log(log)
+        }
+
+        /// instantaneous frequency in Hz at time t in seconds
+        #[pyo3(name = "freq")]
+        fn pyfreq(&self, t: f64) -> f64 {
+            self.freq(t)
+        }
+
+        /// amplitude value from range <-1; 1> of the sweep at a sample x
+        #[pyo3(name = "amplitude")]
+        fn pyamplitude(&self, x: usize) -> f64 {
+            self.amplitude(x)
+        }
+
+        /// generate the whole sweep as f64 type samples from range <-1; 1>
+        #[pyo3(name = "render")]
+        fn pyrender(&self, py: Python<'_>) -> PyResult<Array<f64>> {
+            Ok(py.allow_threads(|| self.render::<f64>())?.into())
+        }
+    }
+
+    params = ["f0", "f1", "duration", "sample_rate", "log"];
+}
+
+impl Sweep {
+    /// create a new linear sweep from f0 to f1 Hz over a duration in seconds
+    pub fn new(f0: f64, f1: f64, duration: f64, sample_rate: f64) -> Self {
+        Sweep {
+            f0,
+            f1,
+            duration,
+            sample_rate,
+            log: false,
+        }
+    }
+
+    /// switch between a linear and a logarithmic sweep
+    pub fn log(mut self, log: bool) -> Self {
+        self.log = log;
+        self
+    }
+
+    /// instantaneous frequency in Hz at time t in seconds, kept at f1 after the duration
+    pub fn freq(&self, t: f64) -> f64 {
+        let progress = (t / self.duration).clamp(0.0, 1.0);
+        if self.log {
+            self.f0 * (self.f1 / self.f0).powf(progress)
+        } else {
+            self.f0 + (self.f1 - self.f0) * progress
+        }
+    }
+
+    /// phase in periods at time t in seconds, the integral of the frequency
+    pub fn phase(&self, t: f64) -> f64 {
+        let d = self.duration;
+        let swept = t.clamp(0.0, d);
+        let phase = if self.log && self.f0 != self.f1 {
+            let k = (self.f1 / self.f0).ln();
+            self.f0 * d / k * ((swept / d * k).exp() - 1.0)
+        } else if self.log || d <= 0.0 {
+            self.f0 * swept
+        } else {
+            self.f0 * swept + (self.f1 - self.f0) * swept * swept / (2.0 * d)
+        };
+        phase + self.f1 * (t - swept).max(0.0)
+    }
+
+    /// amplitude value from range <-1; 1> of the sweep at a sample x
+    pub fn amplitude(&self, x: usize) -> f64 {
+        let phase = self.phase(x as f64 / self.sample_rate).rem_euclid(1.0);
+        (2.0 * core::f64::consts::PI * phase).sin()
+    }
+
+    /// number of samples of the whole sweep
+    pub fn len(&self) -> usize {
+        (self.duration * self.sample_rate).ceil() as usize
+    }
+
+    /// true if the sweep is shorter than a sample
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// generate the whole sweep as samples of any type
+    ///
+    /// Fails with Error::InvalidParam for a sample rate that isn't positive, a negative duration
+    /// or frequencies, or a logarithmic sweep from or to 0 Hz.
+    pub fn render<S: Sample>(&self) -> Result<Vec<S>> {
+        let positive = |x: f64| x.is_finite() && x > 0.0;
+        if !positive(self.sample_rate) {
+            return Err(Error::InvalidParam(format!(
+                "sample rate of a sweep must be positive, got {}",
+                self.sample_rate
+            )));
+        }
+        if !(self.duration.is_finite() && self.duration >= 0.0) {
+            return Err(Error::InvalidParam(format!(
+                "duration of a sweep can't be negative, got {}",
+                self.duration
+            )));
+        }
+        let valid = |f: f64| match self.log {
+            true => positive(f),
+            false => f.is_finite() && f >= 0.0,
+        };
+        if !valid(self.f0) || !valid(self.f1) {
+            return Err(Error::InvalidParam(format!(
+                "frequencies of a {} sweep must be {}, got {} and {}",
+                if self.log { "logarithmic" } else { "linear" },
+                if self.log { "positive" } else { "at least 0" },
+                self.f0,
+                self.f1
+            )));
+        }
+        Ok((0..self.len())
+            .map(|x| S::from_f64(self.amplitude(x)))
+            .collect())
+    }
+}
+
+/// unsigned pcm of the old .u16() and .compute_u16() outputs, centered at 32767
+///
+/// Unlike u16 as a Sample, which is offset binary pcm, it's scaled by 32767 on both sides.
+fn legacy_u16(x: f64) -> u16 {
+    let m = (u16::MAX / 2) as f64;
+    (m + x.clamp(-1.0, 1.0) * m).round() as u16
+}
+
+/// sum of sine partials at a phase in periods of the fundamental, scaled by their total amplitude
+///
+/// Partials at a frequency ratio of max_ratio or above are left out.
+fn additive(partials: &[(f64, f64)], phase: f64, max_ratio: f64) -> f64 {
+    let total: f64 = partials.iter().map(|(a, _)| a.abs()).sum();
+    if total == 0.0 {
+        return 0.0;
+    }
+    let mut sum = 0.0;
+    for (k, (a, cents)) in partials.iter().enumerate() {
+        let ratio = (k + 1) as f64 * 2f64.powf(cents / 1200.0);
+        if ratio < max_ratio {
+            sum += a * (2.0 * core::f64::consts::PI * (ratio * phase).rem_euclid(1.0)).sin();
+        }
+    }
+    sum / total
+}
+
+/// value of a single cycle of samples at a phase from range <0; 1), wrapping around at the end
+fn table(samples: &[f64], phase: f64, cubic: bool) -> f64 {
+    let n = samples.len();
+    if n == 0 {
+        return 0.0;
+    }
+    let x = phase * n as f64;
+    let i = x.floor() as usize % n;
+    let t = x.fract();
+    let at = |k: usize| samples[(i + k) % n];
+    if !cubic {
+        return at(0) + t * (at(1) - at(0));
+    }
+    // Catmull-Rom spline through the samples around, may overshoot between them
+    let (y0, y1, y2, y3) = (at(n - 1), at(0), at(1), at(2));
+    let y = y1
+        + 0.5
+            * t
+            * (y2 - y0
+                + t * (2.0 * y0 - 5.0 * y1 + 4.0 * y2 - y3 + t * (3.0 * (y1 - y2) + y3 - y0)));
+    y.clamp(-1.0, 1.0)
+}
+
+/// correction of a jump by 2 of a wave, at a phase from range <0; 1) after the jump
+///
+/// It's the difference between a unit step and its polynomial approximation,
+/// spread over the samples around the jump at a phase increment dt.
+fn polyblep(phase: f64, dt: f64) -> f64 {
+    if phase < dt {
+        let t = phase / dt;
+        2.0 * t - t * t - 1.0
+    } else if phase > 1.0 - dt {
+        let t = (phase - 1.0) / dt;
+        t * t + 2.0 * t + 1.0
+    } else {
+        0.0
+    }
+}
+
+python_class! {
+    /// envelope used for wavetable generation, linear unless its segments are curved
+    ///
+    /// It's either applied to tones of a known duration with .multiplier(),
+    /// or played like a key with .note_on() and .note_off() when the length isn't known in advance.
+    #[pyclass(module = "muslib")]
+    #[derive(Clone)]
+    pub struct Envelope {
+        /// attack - time duration in seconds
+        #[pyo3(get, set)]
+        pub a: f64,
+        /// hold - time duration in seconds
+        #[pyo3(get, set)]
+        pub h: f64,
+        /// decay - time duration in seconds
+        #[pyo3(get, set)]
+        pub d: f64,
+        /// sustain - amplitude level maintained until the key is released
+        #[pyo3(get, set)]
+        pub s: f64,
+        /// release - time duration in seconds
+        #[pyo3(get, set)]
+        pub r: f64,
+        /// shape of the attack
+        pub attack_curve: Curve,
+        /// shape of the decay
+        pub decay_curve: Curve,
+        /// shape of the release
+        pub release_curve: Curve,
+        /// state when played with note on and off
+        gate: Gate,
+    }
+}
+
+impl Default for Envelope {
+    fn default() -> Self {
+        Envelope {
+            a: 0.0,
+            h: 0.0,
+            d: 0.0,
+            s: 1.0,
+            r: 0.0,
+            attack_curve: Curve::Linear,
+            decay_curve: Curve::Linear,
+            release_curve: Curve::Linear,
+            gate: Gate::IDLE,
+        }
+    }
+}
+
+#[cfg(feature = "python")]
+pymethods_with_params! {
+    impl Envelope {
+        #[new]
+        #[pyo3(signature = (
+            a=0.0,
+            h=0.0,
+            d=0.0,
+            s=1.0,
+            r=0.0,
+            attack_curve="lin",
+            decay_curve="lin",
+            release_curve="lin"
+        ))]
+        #[allow(clippy::too_many_arguments)]
+        fn pynew(
+            a: f64,
+            h: f64,
+            d: f64,
+            s: f64,
+            r: f64,
+            attack_curve: &str,
+            decay_curve: &str,
+            release_curve: &str,
+        ) -> PyResult<Self> {
+            Ok(Envelope {
+                a,
+                h,
+                d,
+                s,
+                r,
+                attack_curve: check_curve(attack_curve)?,
+                decay_curve: check_curve(decay_curve)?,
+                release_curve: check_curve(release_curve)?,
+                gate: Gate::IDLE,
+            })
+        }
+
+        /// shape of the attack as a str, one of {lin, exp, log}, or with a curvature like curve:2.5
+        #[getter(attack_curve)]
+        fn get_attack_curve(&self) -> String {
+            self.attack_curve.to_string()
+        }
+
+        #[setter(attack_curve)]
+        fn set_attack_curve(&mut self, curve: &str) -> PyResult<()> {
+            self.attack_curve = check_curve(curve)?;
+            Ok(())
+        }
+
+        /// shape of the decay as a str, one of {lin, exp, log}, or with a curvature like curve:2.5
+        #[getter(decay_curve)]
+        fn get_decay_curve(&self) -> String {
+            self.decay_curve.to_string()
+        }
+
+        #[setter(decay_curve)]
+        fn set_decay_curve(&mut self, curve: &str) -> PyResult<()> {
+            self.decay_curve = check_curve(curve)?;
+            Ok(())
+        }
+
+        /// shape of the release as a str, one of {lin, exp, log}, or with a curvature like curve:2.5
+        #[getter(release_curve)]
+        fn get_release_curve(&self) -> String {
+            self.release_curve.to_string()
+        }
+
+        #[setter(release_curve)]
+        fn set_release_curve(&mut self, curve: &str) -> PyResult<()> {
+            self.release_curve = check_curve(curve)?;
+            Ok(())
+        }
+
+        /// create a new ADSR envelope
+        #[staticmethod]
+        #[pyo3(name = "adsr")]
+        fn pyadsr(a: f64, d: f64, s: f64, r: f64) -> Self {
+            Self::adsr(a, d, s, r)
+        }
+
+        /// find a multiplier that should be applied to the tone at point x
+        ///
+        /// - for a known duration pass the amount of samples
+        /// - for unknown duration pass 0 and the release will not be applied
+        #[pyo3(name = "multiplier", signature = (g, x, duration=0))]
+        fn pymultiplier(&self, g: &Generator, x: usize, duration: usize) -> f64 {
+            self.multiplier(g, x, duration)
+        }
+
+        /// start the attack from the current level, when a key is pressed
+        #[pyo3(name = "note_on")]
+        fn pynote_on(&mut self) {
+            self.note_on()
+        }
+
+        /// start the release from the current level, when a key is released
+        #[pyo3(name = "note_off")]
+        fn pynote_off(&mut self) {
+            self.note_off()
+        }
+
+        /// multiplier for the next sample when played with .note_on() and .note_off()
+        #[pyo3(name = "next_level")]
+        fn pynext_level(&mut self, sample_rate: f64) -> f64 {
+            self.next_level(sample_rate)
+        }
+
+        /// whether the envelope is sounding after .note_on(), until the release is over
+        #[pyo3(name = "active")]
+        fn pyactive(&self) -> bool {
+            self.active()
+        }
+    }
+
+    params = ["a", "h", "d", "s", "r", "attack_curve", "decay_curve", "release_curve"];
+
+    fn reset(&mut self) {
+        self.gate = Gate::IDLE;
+    }
+}
+
+impl Envelope {
+    /// create a new ADSR envelope
+    pub fn adsr(a: f64, d: f64, s: f64, r: f64) -> Self {
+        Envelope {
+            a,
+            d,
+            s,
+            r,
+            ..Envelope::default()
+        }
+    }
+
+    /// set the shapes of the attack, decay and release
+    pub fn curves(mut self, attack: Curve, decay: Curve, release: Curve) -> Self {
+        self.attack_curve = attack;
+        self.decay_curve = decay;
+        self.release_curve = release;
+        self
+    }
+
+    /// find a multiplier that should be applied to the tone at point x
+    ///
+    /// - for a known duration pass the amount of samples
+    /// - for unknown duration pass 0 and the release will not be applied
+    pub fn multiplier(&self, g: &Generator, x: usize, duration: usize) -> f64 {
+        // convert time in seconds to samples and find which function slope to apply
+        let a = g.time(self.a);
+        if x < a {
+            return self.attack_curve.at(x as f64 / a as f64, 0.0, 1.0);
+        }
+
+        let h = a + g.time(self.h);
+        if x < h {
+            return 1.0;
+        }
+
+        let d = g.time(self.d);
+        if x < h + d {
+            let x = x - h;
+            return self.decay_curve.at(x as f64 / d as f64, 1.0, self.s);
+        }
+
+        // a release longer than the tone takes all of it
+        let r = g.time(self.r).min(duration);
+        if duration > 0 && x > duration - r {
+            let x = x - (duration - r);
+            return self.release_curve.at(x as f64 / r as f64, self.s, 0.0);
+        }
+
+        self.s
+    }
+
+    /// start the attack from the current level, when a key is pressed
+    ///
+    /// Unlike .multiplier(), which needs the duration of the tone up front,
+    /// the envelope is played like a key, sustained until .note_off() starts the release.
+    pub fn note_on(&mut self) {
+        self.gate.enter(Stage::Attack);
+    }
+
+    /// start the release from the current level, when a key is released
+    pub fn note_off(&mut self) {
+        if self.gate.stage != Stage::Idle {
+            self.gate.release();
+        }
+    }
+
+    /// multiplier for the next sample when played with .note_on() and .note_off()
+    pub fn next_level(&mut self, sample_rate: f64) -> f64 {
+        let mut gate = self.gate;
+        let level = gate.advance(self, sample_rate);
+        self.gate = gate;
+        level
+    }
+
+    /// whether the envelope is sounding after .note_on(), until the release is over
+    pub fn active(&self) -> bool {
+        self.gate.stage != Stage::Idle
+    }
+}
+
+/// check that the curve is known, see Curve::parse()
+#[cfg(feature = "python")]
+fn check_curve(curve: &str) -> Result<Curve> {
+    curve.parse()
+}
+
+/// shape of a segment of an Envelope between two levels
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Curve {
+    /// straight line
+    Linear,
+    /// bent like an exponential, rising slowly at first and falling fast at first,
+    /// which sounds natural for decays and releases
+    Exponential,
+    /// bent the other way, rising fast at first and falling slowly at first
+    Logarithmic,
+    /// bent like Exponential for positive values and like Logarithmic for negative ones,
+    /// more as the value grows, 0 is a straight line
+    Curvature(f64),
+}
+
+impl core::fmt::Display for Curve {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Curve::Linear => f.write_str("lin"),
+            Curve::Exponential => f.write_str("exp"),
+            Curve::Logarithmic => f.write_str("log"),
+            Curve::Curvature(c) => write!(f, "curve:{}", c),
+        }
+    }
+}
+
+impl core::str::FromStr for Curve {
+    type Err = Error;
+
+    /// curve of a name, see Curve::parse(), fails with Error::InvalidParam for unknown names
+    fn from_str(name: &str) -> Result<Self> {
+        Curve::parse(name).ok_or_else(|| {
+            Error::InvalidParam(format!(
+                "curve must be one of [\"lin\", \"exp\", \"log\"] or like \"curve:2.5\", got {:?}",
+                name
+            ))
+        })
+    }
+}
+
+impl Curve {
+    /// curvature of Exponential, and of Logarithmic with the opposite sign
+    pub const CURVATURE: f64 = 5.0;
+
+    /// curve of a name, one of {lin, exp, log}, or with a curvature like curve:2.5
+    ///
+    /// None for unknown names and curvatures that are not finite.
+    pub fn parse(name: &str) -> Option<Curve> {
+        match name {
+            "lin" => Some(Curve::Linear),
+            "exp" => Some(Curve::Exponential),
+            "log" => Some(Curve::Logarithmic),
+            _ => name
+                .strip_prefix("curve:")?
+                .trim()
+                .parse()
+                .ok()
+                .filter(|c: &f64| c.is_finite())
+                .map(Curve::Curvature),
+        }
+    }
+
+    /// curvature of this curve, 0 for a straight line
+    pub fn curvature(&self) -> f64 {
+        match self {
+            Curve::Linear => 0.0,
+            Curve::Exponential => Curve::CURVATURE,
+            Curve::Logarithmic => -Curve::CURVATURE,
+            Curve::Curvature(c) => *c,
+        }
+    }
+
+    /// level at a fraction t from range <0; 1> of a segment going from one level to another
+    pub fn at(&self, t: f64, from: f64, to: f64) -> f64 {
+        let t = t.clamp(0.0, 1.0);
+        let c = self.curvature();
+        if c.abs() < 1e-9 {
+            return from + (to - from) * t;
+        }
+        // the same convex shape for both directions, mirrored in time when falling
+        let convex = |t: f64| (c * t).exp_m1() / c.exp_m1();
+        let progress = if to >= from {
+            convex(t)
+        } else {
+            1.0 - convex(1.0 - t)
+        };
+        from + (to - from) * progress
+    }
+}
+
+python_class! {
+    /// low-frequency oscillator modulating the frequency or the amplitude of a tone
+    ///
+    /// As vibrato its depth is in cents above and below the frequency,
+    /// as tremolo it's the fraction of the amplitude taken away at the lowest point.
+    #[pyclass(module = "muslib")]
+    #[derive(Clone)]
+    pub struct LFO {
+        /// rate - frequency of the oscillation in Hz
+        #[pyo3(get, set)]
+        pub rate: f64,
+        /// depth - in cents for vibrato, from range <0; 1> for tremolo
+        #[pyo3(get, set)]
+        pub depth: f64,
+        /// shape of the oscillation
+        pub waveform: Waveform,
+    }
+}
+
+#[cfg(feature = "python")]
+pymethods_with_params! {
+    impl LFO {
+        #[new]
+        #[pyo3(signature = (rate=5.0, depth=0.0, waveform="sin"))]
+        fn pynew(rate: f64, depth: f64, waveform: &str) -> PyResult<Self> {
+            Ok(LFO::new(rate, depth).waveform(waveform.parse()?))
+        }
+
+        /// shape of the oscillation as a str, one of {sin, sqr, saw, tri, pulse, white, pink, brown, additive, table}
+        #[getter(waveform)]
+        fn get_waveform(&self) -> String {
+            self.waveform.to_string()
+        }
+
+        #[setter(waveform)]
+        fn set_waveform(&mut self, waveform: &str) -> PyResult<()> {
+            self.waveform = waveform.parse()?;
+            Ok(())
+        }
+
+        /// value from range <-1; 1> of the oscillation at a sample x
+        #[pyo3(name = "at")]
+        fn pyat(&self, x: usize, sample_rate: f64) -> f64 {
+            self.at(x, sample_rate)
+        }
+    }
+
+    params = ["rate", "depth", "waveform"];
+}
+
+impl LFO {
+    /// create a new sinusoidal LFO
+    pub fn new(rate: f64, depth: f64) -> Self {
+        LFO {
+            rate,
+            depth,
+            waveform: Waveform::Sin,
+        }
+    }
+
+    /// set the shape of the oscillation
+    pub fn waveform(mut self, waveform: Waveform) -> Self {
+        self.waveform = waveform;
+        self
+    }
+
+    /// value from range <-1; 1> of the oscillation at a sample x
+    pub fn at(&self, x: usize, sample_rate: f64) -> f64 {
+        self.waveform.at_phase(self.rate * x as f64 / sample_rate)
+    }
+
+    /// ratio of the frequency modulated as vibrato at a sample x
+    pub fn ratio(&self, x: usize, sample_rate: f64) -> f64 {
+        2f64.powf(self.depth * self.at(x, sample_rate) / 1200.0)
+    }
+
+    /// gain from range <1 - depth; 1> of the amplitude modulated as tremolo at a sample x
+    pub fn gain(&self, x: usize, sample_rate: f64) -> f64 {
+        1.0 - self.depth * (1.0 - self.at(x, sample_rate)) / 2.0
+    }
+}
+
+python_class! {
+    /// unison of detuned copies of a tone, spread across the stereo field
+    ///
+    /// Voices are detuned evenly from half of the detune below the tone to half of it above,
+    /// and panned evenly from the left to the right by the spread. They start in phase
+    /// and are mixed at equal gains, scaled down so they fit the range.
+    #[pyclass(module = "muslib")]
+    #[derive(Clone)]
+    pub struct Unison {
+        /// number of voices, from 1 to 16
+        #[pyo3(get)]
+        pub voices: usize,
+        /// difference in cents between the lowest and the highest voice
+        #[pyo3(get, set)]
+        pub detune: f64,
+        /// width from range <0; 1> of the stereo field taken by the voices, 0 in the center
+        #[pyo3(get, set)]
+        pub spread: f64,
+    }
+}
+
+impl Default for Unison {
+    fn default() -> Self {
+        Unison::new(1, 0.0)
+    }
+}
+
+#[cfg(feature = "python")]
+pymethods_with_params! {
+    impl Unison {
+        #[new]
+        #[pyo3(signature = (voices=3, detune=20.0, spread=0.0))]
+        fn pynew(voices: usize, detune: f64, spread: f64) -> PyResult<Self> {
+            check_unison(&[voices as f64, detune, spread])?;
+            Ok(Unison::new(voices, detune).spread(spread))
+        }
+
+        #[setter(voices)]
+        fn set_voices(&mut self, voices: usize) -> PyResult<()> {
+            check_range("unison voices", voices as f64, 1.0, Unison::MAX_VOICES as f64)?;
+            self.voices = voices;
+            Ok(())
+        }
+
+        /// frequency ratio and pan from range <-1; 1> of the k-th voice
+        #[pyo3(name = "voice")]
+        fn pyvoice(&self, k: usize) -> (f64, f64) {
+            self.voice(k)
+        }
+    }
+
+    params = ["voices", "detune", "spread"];
+}
+
+impl Unison {
+    /// largest number of voices of a unison, each of them is a generator of its own
+    pub const MAX_VOICES: usize = 16;
+
+    /// create a new unison of voices detuned by a number of cents, in the center
+    pub fn new(voices: usize, detune: f64) -> Self {
+        Unison {
+            voices,
+            detune,
+            spread: 0.0,
+        }
+    }
+
+    /// set the width from range <0; 1> of the stereo field taken by the voices
+    pub fn spread(mut self, spread: f64) -> Self {
+        self.spread = spread;
+        self
+    }
 
-        let mut r = t.time(0.0).u16();
-        for i in 0..n {
-            t.generator.freq(self.freq[i]);
-            let mut m = t.time(self.durations[i]).u16();
-            r.append(&mut m);
+    /// frequency ratio and pan from range <-1; 1> of the k-th voice
+    pub fn voice(&self, k: usize) -> (f64, f64) {
+        if self.voices < 2 {
+            return (1.0, 0.0);
         }
-
-        self.pcm_data = Some(r);
+        // position from -1 for the lowest voice to 1 for the highest one
+        let position = 2.0 * k as f64 / (self.voices - 1) as f64 - 1.0;
+        let ratio = 2f64.powf(position * self.detune / 2.0 / 1200.0);
+        (ratio, position * self.spread.clamp(0.0, 1.0))
     }
 }
 
-/// waveforms supported by the tone generator
-pub enum Waveform {
-    /// sinusoidal wave
-    Sin,
-    /// square wave
-    Square,
-    /// sawtooth wave
-    Sawtooth,
+python_class! {
+    /// wavetable generator
+    #[pyclass(module = "muslib", get_all, set_all)]
+    pub struct Wavetable {
+        /// base tone generator
+        pub generator: Generator,
+        /// envelope applied to the base tone
+        pub envelope: Option<Envelope>,
+        /// number of samples to be generated
+        pub samples: Option<usize>,
+        /// oscillation of the frequency, with depth in cents
+        pub vibrato: Option<LFO>,
+        /// oscillation of the amplitude, with depth from range <0; 1>
+        pub tremolo: Option<LFO>,
+        /// detuned copies of the tone played together
+        pub unison: Option<Unison>,
+        /// time in seconds to fade the tone in and out, so it starts and ends without a click
+        pub fade: Option<f64>,
+        /// position in the stereo field from range <-1; 1>, with voices of unison spread around it
+        pub pan: Option<f64>,
+    }
 }
 
-/// tone generator with a given frequency and sample rate
-pub struct Generator {
-    freq: f64,
-    sample_rate: f64,
-    waveform: Waveform,
-}
+#[cfg(feature = "python")]
+pymethods_with_params! {
+    impl Wavetable {
+        #[new]
+        #[pyo3(signature = (
+            generator=None,
+            envelope=None,
+            samples=None,
+            vibrato=None,
+            tremolo=None,
+            unison=None,
+            fade=None,
+            pan=None
+        ))]
+        #[allow(clippy::too_many_arguments)]
+        fn pynew(
+            generator: Option<Generator>,
+            envelope: Option<Envelope>,
+            samples: Option<usize>,
+            vibrato: Option<LFO>,
+            tremolo: Option<LFO>,
+            unison: Option<Unison>,
+            fade: Option<f64>,
+            pan: Option<f64>,
+        ) -> Self {
+            Wavetable {
+                generator: generator.unwrap_or_else(|| Generator::new(440.0, None, None)),
+                envelope,
+                samples,
+                vibrato,
+                tremolo,
+                unison,
+                fade,
+                pan,
+            }
+        }
 
-impl Generator {
-    /// create a new tone generator
-    pub fn new(freq: f64, sample_rate: Option<f64>, w: Option<Waveform>) -> Self {
-        Generator {
-            freq,
-            sample_rate: sample_rate.unwrap_or(44100.0),
-            waveform: w.unwrap_or(Waveform::Sin),
+        /// set the number of samples to be generated based on time duration in seconds
+        #[pyo3(name = "time")]
+        fn pytime(mut slf: PyRefMut<'_, Self>, t: f64) -> PyRefMut<'_, Self> {
+            slf.time(t);
+            slf
         }
-    }
 
-    /// change the tone frequency for this tone generator
-    pub fn freq(&mut self, f: f64) -> &Self {
-        self.freq = f;
-        self
-    }
+        /// generate a wavetable of u16 type samples in the format used before, centered at 32767
+        #[pyo3(name = "u16")]
+        fn pyu16(&self, py: Python<'_>) -> PyResult<Array<u16>> {
+            Ok(py.allow_threads(|| self.u16())?.into())
+        }
 
-    /// change the sample rate for this tone generator
-    pub fn sample_rate(&mut self, sample_rate: f64) -> &Self {
-        self.sample_rate = sample_rate;
-        self
+        /// generate a wavetable of signed i16 type samples, silent at 0
+        #[pyo3(name = "i16")]
+        fn pyi16(&self, py: Python<'_>) -> PyResult<Array<i16>> {
+            Ok(py.allow_threads(|| self.i16())?.into())
+        }
+
+        /// generate a wavetable of f32 type samples from range <-1; 1>
+        #[pyo3(name = "f32")]
+        fn pyf32(&self, py: Python<'_>) -> PyResult<Array<f32>> {
+            Ok(py.allow_threads(|| self.f32())?.into())
+        }
+
+        /// generate a wavetable of f64 type samples from range <-1; 1>
+        #[pyo3(name = "f64")]
+        fn pyf64(&self, py: Python<'_>) -> PyResult<Array<f64>> {
+            Ok(py.allow_threads(|| self.f64())?.into())
+        }
+
+        /// generate left and right channels of f64 type samples, with voices of unison spread
+        #[pyo3(name = "stereo")]
+        fn pystereo(&self, py: Python<'_>) -> PyResult<(Array<f64>, Array<f64>)> {
+            let (left, right) = py.allow_threads(|| self.render_stereo::<f64>())?;
+            Ok((left.into(), right.into()))
+        }
     }
 
-    /// change the tone waveform type for this tone generator
-    pub fn w(&mut self, w: Waveform) -> &Self {
-        self.waveform = w;
+    params = ["generator", "envelope", "samples", "vibrato", "tremolo", "unison", "fade", "pan"];
+}
+
+impl Wavetable {
+    /// set the number of samples to be generated based on time duration in seconds
+    pub fn time(&mut self, t: f64) -> &Self {
+        self.samples = Some(self.generator.time(t));
         self
     }
 
-    /// amplitude value of the sinusoidal wave tone for a sample x
-    fn sin(&self, x: f64) -> f64 {
-        let x: f64 = (std::f64::consts::PI) * 2.0 * x * self.freq / self.sample_rate;
-        x.sin()
+    /// generate a wavetable of u16 type samples in the format used before, centered at 32767
+    ///
+    /// Fails with Error::InvalidParam if the length was not set with .time() first.
+    pub fn u16(&self) -> Result<Vec<u16>> {
+        Ok(self.render::<f64>()?.into_iter().map(legacy_u16).collect())
     }
 
-    /// amplitude value of the square wave tone for a sample x
-    fn sqr(&self, x: f64) -> f64 {
-        if (2.0 * x * self.freq / self.sample_rate) % 2.0 < 1.0 {
-            1.0
-        } else {
-            -1.0
-        }
+    /// generate a wavetable of signed i16 type samples, silent at 0
+    ///
+    /// Fails with Error::InvalidParam if the length was not set with .time() first.
+    pub fn i16(&self) -> Result<Vec<i16>> {
+        self.render()
     }
 
-    /// amplitude of the sawtooth wave tone for a sample x
-    fn saw(&self, x: f64) -> f64 {
-        let x: f64 = x * self.freq / self.sample_rate;
-        2.0 * (x - x.floor()) - 1.0
+    /// generate a wavetable of f32 type samples from range <-1; 1>
+    ///
+    /// Fails with Error::InvalidParam if the length was not set with .time() first.
+    pub fn f32(&self) -> Result<Vec<f32>> {
+        self.render()
     }
 
-    /// amplitude value from range <-1; 1> of the tone for a sample x
-    pub fn amplitude(&self, x: usize) -> f64 {
-        let x = x as f64;
-        match self.waveform {
-            Waveform::Sin => self.sin(x),
-            Waveform::Square => self.sqr(x),
-            Waveform::Sawtooth => self.saw(x),
-        }
+    /// generate a wavetable of f64 type samples from range <-1; 1>
+    ///
+    /// Fails with Error::InvalidParam if the length was not set with .time() first.
+    pub fn f64(&self) -> Result<Vec<f64>> {
+        self.render()
     }
 
-    /// sample number for time given in seconds
-    pub fn time(&self, t: f64) -> usize {
-        (t * self.sample_rate).ceil() as usize
-    }
-}
+    /// generate a wavetable of samples of any type, e.g. f32 for further processing
+    ///
+    /// Fails with Error::InvalidParam if the length was not set with .time() first,
+    /// it is above MAX_LENGTH samples or the unison has more than Unison::MAX_VOICES voices.
+    pub fn render<S: Sample>(&self) -> Result<Vec<S>> {
+        let n = self.length()?;
 
-/// linear envelope used for wavetable generation
-pub struct Envelope {
-    /// attack - time duration in seconds
-    pub a: f64,
-    /// hold - time duration in seconds
-    pub h: f64,
-    /// decay - time duration in seconds
-    pub d: f64,
-    /// sustain - amplitude level maintained until the key is released
-    pub s: f64,
-    /// release - time duration in seconds
-    pub r: f64,
-}
+        let mut output: Vec<S> = Vec::with_capacity(n);
 
-impl Envelope {
-    /// create a new ADSR envelope
-    pub fn adsr(a: f64, d: f64, s: f64, r: f64) -> Self {
-        Envelope { a, h: 0.0, d, s, r }
+        let mut voices = self.voices();
+        let scale = 1.0 / voices.len() as f64;
+        for i in 0..n {
+            let f = self.gain(i, n) * scale;
+            let y: f64 = voices.iter_mut().map(|(g, _)| self.amplitude(g, i)).sum();
+            output.push(S::from_f64(f * y));
+        }
+        Ok(output)
     }
 
-    /// find a multiplier that should be applied to the tone at point x
+    /// generate left and right channels of samples of any type
     ///
-    /// - for a known duration pass the amount of samples
-    /// - for unknown duration pass 0 and the release will not be applied
-    pub fn multiplier(&self, g: &Generator, x: usize, duration: usize) -> f64 {
-        // convert time in seconds to samples and find which function slope to apply
-        let a = g.time(self.a);
-        if x < a {
-            return (x as f64) / (a as f64);
+    /// Voices of unison are panned around the pan of the tone by its spread with constant power,
+    /// a single voice is at the pan, or in the center without one.
+    /// Fails with Error::InvalidParam like .render().
+    pub fn render_stereo<S: Sample>(&self) -> Result<(Vec<S>, Vec<S>)> {
+        let n = self.length()?;
+
+        let mut left: Vec<S> = Vec::with_capacity(n);
+        let mut right: Vec<S> = Vec::with_capacity(n);
+
+        let mut voices = self.voices();
+        let scale = 1.0 / voices.len() as f64;
+        let center = self.pan.unwrap_or(0.0);
+        let pans: Vec<(f64, f64)> = voices
+            .iter()
+            .map(|(_, pan)| pan_gains(center + pan))
+            .collect();
+        for i in 0..n {
+            let f = self.gain(i, n) * scale;
+            let (mut l, mut r) = (0.0, 0.0);
+            for ((g, _), (gl, gr)) in voices.iter_mut().zip(&pans) {
+                let y = self.amplitude(g, i);
+                l += gl * y;
+                r += gr * y;
+            }
+            left.push(S::from_f64(f * l));
+            right.push(S::from_f64(f * r));
         }
+        Ok((left, right))
+    }
 
-        let h = a + g.time(self.h);
-        if x < h {
-            return 1.0;
+    /// number of samples to render, checked along with the number of voices of unison
+    fn length(&self) -> Result<usize> {
+        let n = self.samples.ok_or_else(|| {
+            Error::InvalidParam("length of the output is not set, call .time() first".into())
+        })?;
+        check_length("wavetable", n as f64)?;
+        if let Some(unison) = &self.unison {
+            let voices = unison.voices as f64;
+            check_range("unison voices", voices, 1.0, Unison::MAX_VOICES as f64)?;
         }
+        Ok(n)
+    }
 
-        let d = g.time(self.d);
-        if x < h + d {
-            let x = x - h;
-            return 1.0 - (x as f64) * (1.0 - self.s) / (d as f64);
+    /// generators of the voices of unison, or of the only one, with their pans
+    fn voices(&self) -> Vec<(Generator, f64)> {
+        let unison = self.unison.clone().unwrap_or_default();
+        (0..unison.voices.max(1))
+            .map(|k| {
+                let (ratio, pan) = unison.voice(k);
+                let mut g = self.generator.clone();
+                g.freq(g.freq * ratio);
+                g.reset();
+                (g, pan)
+            })
+            .collect()
+    }
+
+    /// multiplier of the envelope, tremolo and fades at a sample x of n
+    fn gain(&self, x: usize, n: usize) -> f64 {
+        let g = &self.generator;
+        let mut f = match &self.envelope {
+            Some(e) => e.multiplier(g, x, n),
+            None => 1.0,
+        };
+        if let Some(t) = &self.tremolo {
+            f *= t.gain(x, g.sample_rate);
         }
+        if let Some(fade) = self.fade {
+            f *= ramps(x, n, g.time(fade), true, true);
+        }
+        f
+    }
 
-        let r = g.time(self.r);
-        if duration > 0 && x > duration - r {
-            let x = x - (duration - r);
-            return self.s - (x as f64) * (self.s) / (r as f64);
+    /// amplitude of the tone of a voice at a sample x
+    fn amplitude(&self, voice: &mut Generator, x: usize) -> f64 {
+        match &self.vibrato {
+            // with vibrato the phase is accumulated as the frequency changes
+            Some(v) => {
+                let freq = voice.freq;
+                voice.set_freq(freq * v.ratio(x, voice.sample_rate));
+                let y = voice.next_sample();
+                voice.set_freq(freq);
+                y
+            }
+            None => voice.amplitude(x),
         }
+    }
+}
 
-        self.s
+/// gain of linear ramps of m samples fading a tone of n samples in and out at a sample x
+///
+/// The tone starts and ends at 0, and ramps of short tones are shortened to half of them.
+fn ramps(x: usize, n: usize, m: usize, fade_in: bool, fade_out: bool) -> f64 {
+    let m = core::cmp::min(m, n / 2);
+    if m == 0 {
+        return 1.0;
+    }
+    let mut gain: f64 = 1.0;
+    if fade_in {
+        gain = gain.min(x as f64 / m as f64);
     }
+    if fade_out {
+        gain = gain.min((n - 1 - x) as f64 / m as f64);
+    }
+    gain
 }
 
-/// wavetable generator
-pub struct Wavetable {
-    /// base tone generator
-    pub generator: Generator,
-    /// envelope applied to the base tone
-    pub envelope: Option<Envelope>,
-    /// number of samples to be generated
-    pub samples: Option<usize>,
+/// gains of the left and right channel of a pan from range <-1; 1>, keeping constant power
+fn pan_gains(pan: f64) -> (f64, f64) {
+    let angle = (pan.clamp(-1.0, 1.0) + 1.0) * core::f64::consts::FRAC_PI_4;
+    (angle.cos(), angle.sin())
 }
 
-impl Wavetable {
-    /// set the number of samples to be generated based on time duration in seconds
-    pub fn time(&mut self, t: f64) -> &Self {
-        self.samples = Some(self.generator.time(t));
-        self
+/// stage of an envelope played with note on and off
+#[derive(Clone, Copy, PartialEq)]
+pub(super) enum Stage {
+    Idle,
+    Attack,
+    /// with the number of samples left
+    Hold(usize),
+    Decay,
+    Sustain,
+    Release,
+}
+
+/// state of an envelope played with note on and off, advanced sample by sample
+#[derive(Clone, Copy)]
+pub(super) struct Gate {
+    level: f64,
+    pub(super) stage: Stage,
+    /// level at the start of the current stage
+    start: f64,
+    /// number of samples since the start of the current stage
+    elapsed: usize,
+}
+
+impl Gate {
+    pub(super) const IDLE: Gate = Gate {
+        level: 0.0,
+        stage: Stage::Idle,
+        start: 0.0,
+        elapsed: 0,
+    };
+
+    /// move on to a stage starting from the current level
+    pub(super) fn enter(&mut self, stage: Stage) {
+        self.stage = stage;
+        self.start = self.level;
+        self.elapsed = 0;
     }
 
-    /// generate a wavetable of u16 type samples
-    pub fn u16(&self) -> Vec<u16> {
-        let n = self
-            .samples
-            .expect("Lenght for the Wavetable synth output is not set. Call .time() first.");
+    /// start the release from the current level
+    pub(super) fn release(&mut self) {
+        self.enter(if self.level <= 0.0 {
+            Stage::Idle
+        } else {
+            Stage::Release
+        });
+    }
 
-        let mut output: Vec<u16> = Vec::with_capacity(n);
+    /// fraction of a stage lasting a number of samples done after the next sample
+    fn step(&mut self, samples: f64) -> f64 {
+        self.elapsed += 1;
+        if samples >= 1.0 {
+            (self.elapsed as f64 / samples).min(1.0)
+        } else {
+            1.0
+        }
+    }
 
-        let m = (u16::MAX / 2) as f64;
-        let g = &self.generator;
-        for i in 0..n {
-            let f = match &self.envelope {
-                Some(e) => e.multiplier(g, i, n),
-                None => 1.0,
-            };
-            let v = m + f * g.amplitude(i) * m;
-            output.push(v.round() as u16);
+    /// envelope level for the next sample
+    pub(super) fn advance(&mut self, envelope: &Envelope, sample_rate: f64) -> f64 {
+        match self.stage {
+            Stage::Idle => self.level = 0.0,
+            Stage::Attack => {
+                // a retriggered note rises from its current level, as fast as from silence
+                let t = self.step(envelope.a * sample_rate * (1.0 - self.start));
+                self.level = envelope.attack_curve.at(t, self.start, 1.0);
+                if t >= 1.0 {
+                    self.level = 1.0;
+                    self.enter(Stage::Hold((envelope.h * sample_rate) as usize));
+                }
+            }
+            Stage::Hold(0) => self.enter(Stage::Decay),
+            Stage::Hold(n) => self.stage = Stage::Hold(n - 1),
+            Stage::Decay => {
+                let t = self.step(envelope.d * sample_rate);
+                self.level = envelope.decay_curve.at(t, self.start, envelope.s);
+                if t >= 1.0 {
+                    self.level = envelope.s;
+                    self.enter(Stage::Sustain);
+                }
+            }
+            Stage::Sustain => self.level = envelope.s,
+            Stage::Release => {
+                let t = self.step(envelope.r * sample_rate);
+                self.level = envelope.release_curve.at(t, self.start, 0.0);
+                if t >= 1.0 {
+                    self.level = 0.0;
+                    self.enter(Stage::Idle);
+                }
+            }
         }
-        output
+        self.level
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{Envelope, Generator, Waveform, Wavetable};
+    use super::{
+        Curve, Envelope, Generator, Noise, NoteEvent, Sequencer, Sweep, Synthesizer, Unison,
+        Waveform, Wavetable, LFO,
+    };
+    use crate::algs::stft::forward;
+    use crate::algs::Algorithm;
+    use crate::{Error, Result};
 
     #[test]
     fn generator() {
@@ -323,6 +2761,642 @@ mod tests {
             g.w(Waveform::Sawtooth);
             assert_eq!(g.amplitude(input[i]), result[2][i], "test {}", i);
         }
+
+        // a period of 8 samples
+        let mut g = Generator::new(1000.0, Some(8000.0), None);
+        let period = |g: &Generator| (0..8).map(|x| g.amplitude(x)).collect::<Vec<f64>>();
+        g.w(Waveform::Triangle);
+        assert_eq!(period(&g), [0.0, 0.5, 1.0, 0.5, 0.0, -0.5, -1.0, -0.5]);
+        g.w(Waveform::Pulse(0.25));
+        assert_eq!(period(&g), [1.0, 1.0, -1.0, -1.0, -1.0, -1.0, -1.0, -1.0]);
+
+        // noise is reproducible, and pink noise is correlated between neighbouring samples
+        let mut correlation = |w: Waveform| {
+            g.w(w);
+            let x: Vec<f64> = (0..20000).map(|x| g.amplitude(x)).collect();
+            assert!(x.iter().all(|x| (-1.0..=1.0).contains(x)));
+            assert_eq!(g.amplitude(123), x[123]);
+            let power: f64 = x.iter().map(|x| x * x).sum();
+            x.windows(2).map(|w| w[0] * w[1]).sum::<f64>() / power
+        };
+        assert!(correlation(Waveform::WhiteNoise).abs() < 0.05);
+        assert!(correlation(Waveform::PinkNoise) > 0.5);
+        assert!(correlation(Waveform::BrownNoise) > 0.99);
+
+        for name in ["tri", "pulse", "pulse:0.1", "white", "pink", "brown"] {
+            assert_eq!(Waveform::parse(name).unwrap().to_string(), name);
+        }
+        assert_eq!(Waveform::parse("pulse:1.5"), None);
+        assert_eq!(
+            "pulse:0.5".parse::<Waveform>().unwrap(),
+            Waveform::Pulse(0.5)
+        );
+        assert!(matches!(
+            "sine".parse::<Waveform>(),
+            Err(Error::InvalidParam(_))
+        ));
+    }
+
+    #[test]
+    fn noise() {
+        // power per bin falls by 3 dB per octave for pink noise and 6 dB for brown noise,
+        // compared 3 octaves apart in spectra averaged over frames
+        let slope = |noise: &Noise| {
+            let x = noise.render(16 * 4096);
+            let mut power = vec![0.0; 4096];
+            for frame in x.chunks(4096) {
+                for (p, (re, im)) in power.iter_mut().zip(forward(frame).unwrap()) {
+                    *p += (re * re + im * im) as f64;
+                }
+            }
+            power[32..64].iter().sum::<f64>() / power[256..512].iter().sum::<f64>() * 8.0
+        };
+        assert!((0.5..2.0).contains(&slope(&Noise::white(1))));
+        assert!((4.0..16.0).contains(&slope(&Noise::pink(1))));
+        assert!((32.0..128.0).contains(&slope(&Noise::brown(1))));
+
+        // seeds are reproducible and independent, and the same in a Generator
+        let brown = Noise::brown(7);
+        assert_eq!(brown.render(100), Noise::brown(7).render(100));
+        assert_ne!(brown.render(100), Noise::brown(8).render(100));
+        assert_eq!(Noise::white(0).at(5), Waveform::WhiteNoise.noise(5, 0));
+        let mut g = Generator::new(440.0, None, Some(Waveform::BrownNoise));
+        g.seed(7);
+        assert_eq!(g.amplitude(50), brown.at(50));
+        assert_eq!(Noise::new(Waveform::Sin, 7).at(50), 0.0);
+    }
+
+    #[test]
+    fn sweep() {
+        // the frequency is the derivative of the phase, and holds at f1 after the end
+        for log in [false, true] {
+            let s = Sweep::new(100.0, 1000.0, 1.0, 8192.0).log(log);
+            for t in [0.1, 0.5, 0.9, 1.5] {
+                let dt = 1e-6;
+                let f = (s.phase(t + dt) - s.phase(t - dt)) / (2.0 * dt);
+                assert!((f - s.freq(t)).abs() < 1e-3, "{} {} {}", log, t, f);
+            }
+        }
+        let linear = Sweep::new(100.0, 1000.0, 1.0, 8192.0);
+        assert_eq!(linear.freq(0.5), 550.0);
+        let log = linear.clone().log(true);
+        assert!((log.freq(0.5) - 1e5f64.sqrt()).abs() < 1e-9);
+
+        // the peak of the spectrum of short frames follows the frequency, with 32 Hz bins
+        for s in [linear, log] {
+            let x = s.render::<f64>().unwrap();
+            assert_eq!(x.len(), 8192);
+            for start in [1024, 4096, 6144] {
+                let spectrum = forward(&x[start..start + 256]).unwrap();
+                let peak = (0..128)
+                    .max_by(|a, b| {
+                        let power = |(re, im): (f32, f32)| re * re + im * im;
+                        power(spectrum[*a]).total_cmp(&power(spectrum[*b]))
+                    })
+                    .unwrap();
+                let f = s.freq((start + 128) as f64 / 8192.0);
+                assert!((peak as f64 * 32.0 - f).abs() <= 32.0, "{} {}", peak, f);
+            }
+        }
+        assert!(Sweep::new(0.0, 1000.0, 1.0, 8192.0)
+            .log(true)
+            .render::<f32>()
+            .is_err());
+    }
+
+    #[test]
+    fn additive() {
+        // with 1 Hz bins, harmonics of 100 Hz in the spectrum of a second
+        let mut g = Generator::new(
+            100.0,
+            Some(8192.0),
+            Some(Waveform::additive(&[1.0, 0.0, 0.5])),
+        );
+        let spectrum = |g: &Generator| {
+            let x: Vec<f64> = (0..8192).map(|x| g.amplitude(x)).collect();
+            assert!(x.iter().all(|x| (-1.0..=1.0).contains(x)));
+            forward(&x).unwrap()[..4096]
+                .iter()
+                .map(|(re, im)| (re * re + im * im).sqrt() as f64)
+                .collect::<Vec<f64>>()
+        };
+        let m = spectrum(&g);
+        assert!((m[300] / m[100] - 0.5).abs() < 1e-3);
+        assert!(m[200] < 1e-3 * m[100]);
+
+        // the second partial a semitone sharp, and the third one above Nyquist when band-limited
+        g.w(Waveform::parse("additive:1,1@100,1").unwrap());
+        let m = spectrum(&g);
+        let sharp = (200.0 * 2f64.powf(1.0 / 12.0)).round() as usize;
+        assert!(m[sharp] > 0.5 * m[100] && m[200] < 0.5 * m[100]);
+        g.w(Waveform::additive(&[1.0, 1.0, 1.0]));
+        g.freq(1500.0);
+        let alias = 8192 - 4500;
+        assert!(spectrum(&g)[alias] > 0.5 * spectrum(&g)[1500]);
+        g.band_limited(true);
+        let m = spectrum(&g);
+        assert!(m[alias] < 1e-3 * m[1500]);
+
+        for name in ["additive:1,0.5", "additive:1,0.5@3,0.25"] {
+            assert_eq!(Waveform::parse(name).unwrap().to_string(), name);
+        }
+        assert_eq!(Waveform::parse("additive"), None);
+        assert_eq!(Waveform::parse("additive:1,x"), None);
+    }
+
+    #[test]
+    fn table() {
+        // a period of 8 samples, reading a cycle of 4 drawn as a triangle
+        let mut g = Generator::new(
+            1000.0,
+            Some(8000.0),
+            Some(Waveform::table(vec![0.0, 1.0, 0.0, -1.0])),
+        );
+        let period: Vec<f64> = (0..8).map(|x| g.amplitude(x)).collect();
+        assert_eq!(period, [0.0, 0.5, 1.0, 0.5, 0.0, -0.5, -1.0, -0.5]);
+
+        // a cycle of a sine sampled 16 times is closer to it with cubic interpolation
+        let sine: Vec<f64> = (0..16)
+            .map(|k| (2.0 * std::f64::consts::PI * k as f64 / 16.0).sin())
+            .collect();
+        let mut error = |cubic: bool| {
+            g.w(Waveform::Table {
+                samples: sine.clone(),
+                cubic,
+            });
+            g.freq(100.0);
+            (0..80)
+                .map(|x| {
+                    (g.amplitude(x) - (2.0 * std::f64::consts::PI * x as f64 / 80.0).sin()).abs()
+                })
+                .fold(0f64, f64::max)
+        };
+        let (linear, cubic) = (error(false), error(true));
+        assert!(cubic < linear / 4.0, "{} {}", linear, cubic);
+
+        for name in ["table:0,1,0,-1", "table:cubic:0,0.5,-0.5"] {
+            assert_eq!(Waveform::parse(name).unwrap().to_string(), name);
+        }
+        assert_eq!(Waveform::parse("table"), None);
+        assert_eq!(Waveform::parse("table:cubic:"), None);
+    }
+
+    #[test]
+    fn phase() {
+        let mut g = Generator::new(440.0, Some(8000.0), None);
+        let steady: Vec<f64> = (0..100).map(|_| g.next_sample()).collect();
+        assert!((0..100).all(|x| (steady[x] - g.amplitude(x)).abs() < 1e-9));
+
+        // no jump where the frequency changes, unlike with a sample index
+        g.set_freq(660.0);
+        let next = g.next_sample();
+        let slope = 2.0 * std::f64::consts::PI * 660.0 / 8000.0;
+        assert!((next - steady[99]).abs() <= slope);
+        assert!((g.amplitude(100) - steady[99]).abs() > slope);
+
+        g.reset();
+        assert_eq!(g.next_sample(), 0.0);
+    }
+
+    #[test]
+    fn lfo() {
+        let mut t = Wavetable {
+            generator: Generator::new(400.0, Some(8000.0), None),
+            envelope: None,
+            samples: Some(8000),
+            vibrato: None,
+            tremolo: Some(LFO::new(2.0, 1.0)),
+            unison: None,
+            fade: None,
+            pan: None,
+        };
+        // tremolo at 2 Hz, silent at 3/4 of its period and at full scale at 1/4
+        let x = t.render::<f64>().unwrap();
+        let peak = |x: &[f64]| x.iter().fold(0f64, |a, b| a.max(b.abs()));
+        assert!(peak(&x[950..1050]) > 0.99);
+        assert!(peak(&x[2990..3010]) < 0.01);
+
+        // vibrato of a semitone at 5 Hz, sharp and flat by turns
+        t.tremolo = None;
+        t.vibrato = Some(LFO::new(5.0, 100.0));
+        let x = t.render::<f64>().unwrap();
+        let crossings = |x: &[f64]| x.windows(2).filter(|x| x[0] < 0.0 && x[1] >= 0.0).count();
+        let (sharp, flat) = (crossings(&x[..800]), crossings(&x[800..1600]));
+        assert!(sharp >= 41 && flat <= 39, "{} {}", sharp, flat);
+
+        let synth = Synthesizer::builder()
+            .sample_rate(8000)
+            .vibrato(vec![5.0, 100.0])
+            .fade(0.0)
+            .build();
+        let y = synth
+            .render_notes::<f64>(&[NoteEvent::new(400.0, 0.0, 1.0)])
+            .unwrap();
+        assert_eq!(x, y);
+    }
+
+    #[test]
+    fn fade() {
+        // tones of a period of 8 samples, faded in and out over 40 samples without an envelope
+        let mut synth = Synthesizer::builder().sample_rate(8000).build();
+        synth.freq = vec![1000.0, 2000.0];
+        synth.durations = vec![0.1; 2];
+        let x = synth.render::<f64>().unwrap();
+        assert_eq!((x[0], x[799], x[800], x[1599]), (0.0, 0.0, 0.0, 0.0));
+        assert!((x[18] - 18.0 / 40.0).abs() < 1e-9);
+        assert!((x[400] - 0.0).abs() < 1e-9 && (x[402] - 1.0).abs() < 1e-9);
+
+        // without fades the sine starts at 0 but its last sample is cut off at a peak
+        synth.fade = 0.0;
+        let x = synth.render::<f64>().unwrap();
+        assert!((x[798] + 1.0).abs() < 1e-9);
+
+        // legato tones sliding in pitch are only faded at the ends
+        synth.fade = 0.005;
+        synth.glide = 0.01;
+        let x = synth.render::<f64>().unwrap();
+        assert_eq!((x[0], x[1599]), (0.0, 0.0));
+        assert!(x[795..805].iter().any(|x| x.abs() > 0.5));
+
+        let t = Wavetable {
+            generator: Generator::new(1000.0, Some(8000.0), None),
+            envelope: None,
+            samples: Some(800),
+            vibrato: None,
+            tremolo: None,
+            unison: None,
+            fade: Some(0.005),
+            pan: None,
+        };
+        let y = t.f64().unwrap();
+        assert_eq!(y[0], 0.0);
+        assert_eq!(y[799], 0.0);
+        assert!((y[18] - 18.0 / 40.0).abs() < 1e-9);
+        synth.fade = -1.0;
+        assert!(synth.render::<f64>().is_err());
+    }
+
+    #[test]
+    fn stereo() {
+        // constant power panning of a tone hard left, a tone in the center and a rest
+        let mut synth = Synthesizer::builder()
+            .sample_rate(8000)
+            .fade(0.0)
+            .pan(1.0)
+            .build();
+        synth.freq = vec![1000.0, 1000.0, 0.0, 1000.0];
+        synth.durations = vec![0.1; 4];
+        synth.pans = vec![-1.0, 0.0];
+        let mono = synth.render::<f64>().unwrap();
+        let (left, right) = synth.render_stereo::<f64>().unwrap();
+        assert_eq!((left.len(), right.len()), (mono.len(), mono.len()));
+        let center = 0.5f64.sqrt();
+        for i in 0..800 {
+            assert!((left[i] - mono[i]).abs() < 1e-12 && right[i].abs() < 1e-12);
+            let (l, r) = (left[800 + i], right[800 + i]);
+            assert!((l - center * mono[800 + i]).abs() < 1e-12 && (l - r).abs() < 1e-12);
+            assert_eq!((left[1600 + i], right[1600 + i]), (0.0, 0.0));
+            // the last tone has no pan of its own, so it takes the global one
+            assert!(
+                left[2400 + i].abs() < 1e-12 && (right[2400 + i] - mono[2400 + i]).abs() < 1e-12
+            );
+        }
+
+        // sliding tones are panned the same way
+        synth.glide = 0.01;
+        let mono = synth.render::<f64>().unwrap();
+        let (left, right) = synth.render_stereo::<f64>().unwrap();
+        assert!((0..800).all(|i| (left[i] - mono[i]).abs() < 1e-12 && right[i].abs() < 1e-12));
+
+        // overlapping notes panned on their own
+        let notes = [
+            NoteEvent::new(1000.0, 0.0, 0.1).pan(-1.0),
+            NoteEvent::new(1500.0, 0.05, 0.1).velocity(0.5),
+        ];
+        let (left, right) = synth.render_notes_stereo::<f64>(&notes).unwrap();
+        let solo = synth
+            .render_notes::<f64>(&[NoteEvent::new(1500.0, 0.05, 0.1).velocity(0.5)])
+            .unwrap();
+        assert_eq!(left.len(), 1200);
+        assert!((0..1200).all(|i| (right[i] - solo[i]).abs() < 1e-12));
+        synth.pan = f64::NAN;
+        assert!(synth.render_stereo::<f64>().is_err());
+    }
+
+    #[test]
+    fn signed() {
+        // signed pcm is silent at 0 without an offset, the old format is centered at 32767
+        let mut synth = Synthesizer::builder().sample_rate(8000).build();
+        let input = (vec![1000.0, 0.0], vec![0.01, 0.01]);
+        let pcm = synth.compute(input.clone()).unwrap();
+        assert_eq!(pcm.len(), 160);
+        assert!(pcm[80..].iter().all(|x| *x == 0));
+        let mean = pcm.iter().map(|x| *x as f64).sum::<f64>() / pcm.len() as f64;
+        assert!(mean.abs() < 1.0);
+
+        let old = synth.compute_u16(input).unwrap();
+        let offset: Vec<u16> = pcm.iter().map(|x| *x as u16 ^ 0x8000).collect();
+        assert_eq!(offset, synth.render::<u16>().unwrap());
+        assert!(old[80..].iter().all(|x| *x == 32767));
+        for (x, y) in pcm.iter().zip(&old) {
+            assert!((*x as i32 - (*y as i32 - 32767)).abs() <= 1);
+        }
+    }
+
+    #[test]
+    fn amplitudes() {
+        let mut synth = Synthesizer::builder().sample_rate(8000).build();
+        synth.freq = vec![440.0, 0.0, 440.0, 440.0];
+        synth.durations = vec![0.25; 4];
+        synth.amplitudes = vec![0.5, 1.0, 0.25];
+        let pcm = synth.render::<f64>().unwrap();
+        let notes = [
+            NoteEvent::new(440.0, 0.0, 0.25).velocity(0.5),
+            NoteEvent::new(440.0, 0.5, 0.25).velocity(0.25),
+            NoteEvent::new(440.0, 0.75, 0.25),
+        ];
+        let expected = synth.render_notes::<f64>(&notes).unwrap();
+        assert!(pcm
+            .iter()
+            .zip(&expected)
+            .all(|(x, y)| (x - y).abs() < 1e-12));
+
+        synth.glide = 0.1;
+        let peak = |x: &[f64]| x.iter().fold(0f64, |a, b| a.max(b.abs()));
+        let pcm = synth.render::<f64>().unwrap();
+        assert!((peak(&pcm[..2000]) - 0.5).abs() < 0.01);
+        assert!((peak(&pcm[4000..6000]) - 0.25).abs() < 0.01);
+        assert!((peak(&pcm[6000..]) - 1.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn unison() {
+        let unison = Unison::new(3, 20.0).spread(0.5);
+        let cents = |x: f64| 1200.0 * x.log2();
+        assert!((cents(unison.voice(0).0) + 10.0).abs() < 1e-9);
+        assert_eq!(unison.voice(1), (1.0, 0.0));
+        assert!((cents(unison.voice(2).0) - 10.0).abs() < 1e-9);
+        assert_eq!(unison.voice(2).1, 0.5);
+
+        // voices in tune add up to the tone, in the center of the stereo field
+        let mut t = Wavetable {
+            generator: Generator::new(400.0, Some(8000.0), None),
+            envelope: None,
+            samples: Some(8000),
+            vibrato: None,
+            tremolo: None,
+            unison: None,
+            fade: None,
+            pan: None,
+        };
+        let tone = t.f64().unwrap();
+        t.unison = Some(Unison::new(4, 0.0));
+        assert!(t
+            .f64()
+            .unwrap()
+            .iter()
+            .zip(&tone)
+            .all(|(x, y)| (x - y).abs() < 1e-12));
+        let (left, right) = t.render_stereo::<f64>().unwrap();
+        assert!(left.iter().zip(&right).all(|(l, r)| (l - r).abs() < 1e-12));
+        assert!((left[5] / tone[5] - 0.5f64.sqrt()).abs() < 1e-9);
+
+        // an octave apart, panned hard to the left and to the right
+        t.unison = Some(Unison::new(2, 1200.0).spread(1.0));
+        let (left, right) = t.render_stereo::<f64>().unwrap();
+        let crossings = |x: &[f64]| x.windows(2).filter(|x| x[0] < 0.0 && x[1] >= 0.0).count();
+        assert_eq!(crossings(&left), (400.0 * 0.5f64.sqrt()) as usize);
+        assert_eq!(crossings(&right), (400.0 * 2f64.sqrt()) as usize);
+
+        let synth = Synthesizer::builder()
+            .sample_rate(8000)
+            .unison(vec![2.0, 1200.0, 1.0])
+            .fade(0.0)
+            .build();
+        let mono = synth
+            .render_notes::<f64>(&[NoteEvent::new(400.0, 0.0, 1.0)])
+            .unwrap();
+        assert!(mono
+            .iter()
+            .zip(t.f64().unwrap())
+            .all(|(x, y)| (x - y).abs() < 1e-12));
+    }
+
+    #[test]
+    fn sequencer() {
+        // a quarter, a dotted eighth and a sixteenth note, then a half rest at 120 BPM
+        let sequencer = Sequencer::new(120.0)
+            .note(69, 0.25)
+            .note(72, 0.1875)
+            .note(76, 0.0625)
+            .rest(0.5);
+        assert_eq!(sequencer.durations().unwrap(), [0.5, 0.375, 0.125, 1.0]);
+        let freq = sequencer.frequencies();
+        assert_eq!((freq[0], freq[3]), (440.0, 0.0));
+        assert!((freq[1] - 523.25).abs() < 0.01);
+
+        // the same in 6/8 with dotted quarter beats
+        let durations = sequencer.clone().beat(0.375).durations().unwrap();
+        assert!((durations[3] - 2.0 / 3.0).abs() < 1e-12);
+
+        let mut synth = Synthesizer::builder().sample_rate(8000).build();
+        assert_eq!(sequencer.render(&mut synth).unwrap().len(), 16000);
+        assert!(Sequencer::new(0.0).note(60, 0.25).durations().is_err());
+        assert!(Sequencer::new(90.0).rest(-1.0).durations().is_err());
+    }
+
+    #[test]
+    fn glide() {
+        let mut synth = Synthesizer::builder().sample_rate(8000).glide(0.25).build();
+        synth.freq = vec![220.0, 440.0, 0.0, 440.0];
+        synth.durations = vec![0.5, 0.5, 0.25, 0.25];
+        let pcm = synth.render::<f64>().unwrap();
+        assert_eq!(pcm.len(), 12000);
+
+        // the wave is continuous while the pitch slides, steps are below the fastest sine slope
+        let slope = 2.0 * std::f64::consts::PI * 440.0 / 8000.0;
+        assert!(pcm[..8000].windows(2).all(|x| (x[1] - x[0]).abs() <= slope));
+        let crossings = |x: &[f64]| x.windows(2).filter(|x| x[0] < 0.0 && x[1] >= 0.0).count();
+        assert!(crossings(&pcm[4000..5000]) > 30 && crossings(&pcm[4000..5000]) < 50);
+        assert_eq!(crossings(&pcm[6000..8000]), 110);
+        // after a rest the tone starts at its own pitch
+        assert!(pcm[8000..10000].iter().all(|x| *x == 0.0));
+        assert_eq!(crossings(&pcm[10000..]), 110);
+
+        synth.glide = -1.0;
+        assert!(synth.render::<f64>().is_err());
+    }
+
+    #[test]
+    fn notes() {
+        let synth = Synthesizer::builder()
+            .sample_rate(8000)
+            .envelope(vec![0.01, 0.0, 0.0, 1.0, 0.01])
+            .build();
+        let voice = |freq, duration| {
+            synth
+                .render_notes::<f64>(&[NoteEvent::new(freq, 0.0, duration)])
+                .unwrap()
+        };
+        // a single note is the same as a sequence of one tone
+        let mut single = Synthesizer::builder()
+            .sample_rate(8000)
+            .envelope(vec![0.01, 0.0, 0.0, 1.0, 0.01])
+            .build();
+        single.freq = vec![440.0];
+        single.durations = vec![0.5];
+        assert_eq!(single.render::<f64>().unwrap(), voice(440.0, 0.5));
+
+        // a chord is the sum of its voices, each with its own velocity
+        let chord = [
+            NoteEvent::new(261.63, 0.0, 0.5).velocity(0.5),
+            NoteEvent::new(329.63, 0.0, 0.5).velocity(0.25),
+            NoteEvent::new(392.0, 0.0, 0.5).velocity(0.25),
+        ];
+        let voices = [voice(261.63, 0.5), voice(329.63, 0.5), voice(392.0, 0.5)];
+        let sum: Vec<f64> = (0..4000)
+            .map(|i| 0.5 * voices[0][i] + 0.25 * voices[1][i] + 0.25 * voices[2][i])
+            .collect();
+        let mix = synth.render_notes::<f64>(&chord).unwrap();
+        assert!(mix.iter().zip(sum).all(|(x, y)| (x - y).abs() < 1e-12));
+
+        // overlapping notes of a legato melody, the second one ending last
+        let legato = [
+            NoteEvent::new(440.0, 0.0, 0.3),
+            NoteEvent::new(0.0, 0.0, 0.1),
+            NoteEvent::new(494.0, 0.25, 0.3),
+        ];
+        let mix = synth.render_notes::<i16>(&legato).unwrap();
+        assert_eq!(mix.len(), 2000 + 2400);
+        assert!(mix[2000..2400].iter().any(|x| x.abs() > 16384));
+
+        assert!(synth
+            .render_notes::<f64>(&[NoteEvent::new(440.0, -1.0, 0.5)])
+            .is_err());
+    }
+
+    #[test]
+    fn invalid_params() {
+        let invalid = |result: Result<Vec<i16>>| matches!(result, Err(Error::InvalidParam(_)));
+
+        let mut synth = Synthesizer::builder().sample_rate(0).build();
+        assert!(invalid(synth.compute((vec![440.0], vec![0.5]))));
+        assert!(synth
+            .render_notes::<f64>(&[NoteEvent::new(440.0, 0.0, 0.5)])
+            .is_err());
+
+        let mut synth = Synthesizer::new();
+        for duration in [-0.5, f64::NAN, f64::INFINITY] {
+            let result = synth.compute((vec![440.0, 0.0], vec![0.5, duration]));
+            assert!(invalid(result), "test {}", duration);
+        }
+        // too long to allocate, even though every duration is finite
+        assert!(invalid(synth.compute((vec![440.0], vec![1e300]))));
+        assert!(invalid(synth.compute((vec![0.0; 2], vec![1e5; 2]))));
+        assert!(synth
+            .render_notes::<f64>(&[NoteEvent::new(440.0, 1e300, 0.5)])
+            .is_err());
+        let t = Wavetable {
+            generator: Generator::new(440.0, None, None),
+            envelope: None,
+            samples: Some(usize::MAX),
+            vibrato: None,
+            tremolo: None,
+            unison: None,
+            fade: None,
+            pan: None,
+        };
+        assert!(t.render::<f64>().is_err());
+
+        for glide in [-0.1, f64::NAN] {
+            let mut synth = Synthesizer::builder().glide(glide).build();
+            assert!(
+                invalid(synth.compute((vec![440.0], vec![0.5]))),
+                "test {}",
+                glide
+            );
+        }
+
+        for pan in [-1.5, 2.0, f64::NAN] {
+            let synth = Synthesizer::builder().pan(pan).build();
+            assert!(synth.render_stereo::<f64>().is_err(), "test {}", pan);
+        }
+
+        // a voice for every generator, so their number is capped
+        for unison in [
+            vec![1e9, 10.0, 0.0],
+            vec![0.0, 10.0, 0.0],
+            vec![3.0, 10.0, 2.0],
+        ] {
+            let mut synth = Synthesizer::builder().unison(unison.clone()).build();
+            let result = synth.compute((vec![440.0], vec![0.5]));
+            assert!(invalid(result), "test {:?}", unison);
+        }
+        let t = Wavetable {
+            samples: Some(100),
+            unison: Some(Unison::new(1 << 20, 10.0)),
+            ..t
+        };
+        assert!(t.render::<f64>().is_err());
+    }
+
+    #[test]
+    fn band_limited() {
+        // with 1 Hz bins, harmonics of 600 Hz fall on multiples of 600 and aliases in between
+        let mut g = Generator::new(600.0, Some(8192.0), None);
+        let aliasing = |g: &Generator| {
+            let x: Vec<f64> = (0..8192).map(|x| g.amplitude(x)).collect();
+            let spectrum = forward(&x).unwrap();
+            let power = |(re, im): &(f32, f32)| (re * re + im * im) as f64;
+            let aliased: f64 = (1..4096)
+                .filter(|k| k % 600 != 0)
+                .map(|k| power(&spectrum[k]))
+                .sum();
+            aliased
+                / spectrum[600..4096]
+                    .iter()
+                    .step_by(600)
+                    .map(power)
+                    .sum::<f64>()
+        };
+        for w in [Waveform::Sawtooth, Waveform::Square, Waveform::Pulse(0.25)] {
+            g.w(w.clone());
+            g.band_limited(false);
+            let naive = aliasing(&g);
+            g.band_limited(true);
+            let smooth = aliasing(&g);
+            assert!(smooth < naive / 10.0, "{:?} {} {}", w, naive, smooth);
+        }
+        g.w(Waveform::Sin);
+        assert_eq!(
+            g.amplitude(5),
+            Generator::new(600.0, Some(8192.0), None).amplitude(5)
+        );
+    }
+
+    #[test]
+    fn gate() {
+        // attack, decay and the sustain held until the note off, then the release
+        let mut e = Envelope::adsr(0.01, 0.01, 0.5, 0.02);
+        assert!(!e.active());
+        assert_eq!(e.next_level(1000.0), 0.0);
+        e.note_on();
+        let levels: Vec<f64> = (0..100).map(|_| e.next_level(1000.0)).collect();
+        assert!((levels[4] - 0.5).abs() < 1e-9 && levels[9] == 1.0);
+        assert!((levels[15] - 0.75).abs() < 1e-9);
+        assert!(levels[25..].iter().all(|x| *x == 0.5));
+        e.note_off();
+        let levels: Vec<f64> = (0..30).map(|_| e.next_level(1000.0)).collect();
+        assert!((levels[9] - 0.25).abs() < 1e-9 && levels[19] == 0.0);
+        assert!(!e.active());
+
+        // a note off during the attack releases from the level reached
+        e.note_on();
+        e.next_level(1000.0);
+        e.next_level(1000.0);
+        e.note_off();
+        assert!(e.active());
+        assert!((e.next_level(1000.0) - 0.19).abs() < 1e-9);
     }
 
     #[test]
@@ -367,11 +3441,46 @@ mod tests {
                 d: input[i].0[2],
                 s: input[i].0[3],
                 r: input[i].0[4],
+                ..Envelope::default()
             };
             let x = input[i].1[0];
             let duration = input[i].1[1];
             assert_eq!(e.multiplier(&g, x, duration), result[i], "test {}", i);
         }
+
+        // curved segments start and end at the same levels, bent below or above a line
+        let e = Envelope::adsr(1.0, 1.0, 0.5, 1.0).curves(
+            Curve::Exponential,
+            Curve::Exponential,
+            Curve::Logarithmic,
+        );
+        let levels: Vec<f64> = [0, 500, 1000, 1500, 2500, 3500, 4000]
+            .iter()
+            .map(|x| e.multiplier(&g, *x, 4000))
+            .collect();
+        assert_eq!((levels[0], levels[2], levels[4]), (0.0, 1.0, 0.5));
+        assert!(levels[1] < 0.5 && levels[3] < 0.75 && levels[5] > 0.25);
+        assert!(levels[6].abs() < 1e-12);
+        assert_eq!(
+            Curve::Curvature(0.0).at(0.3, 1.0, 0.0),
+            Curve::Linear.at(0.3, 1.0, 0.0)
+        );
+        for name in ["lin", "exp", "log", "curve:-2.5"] {
+            assert_eq!(Curve::parse(name).unwrap().to_string(), name);
+        }
+        assert_eq!(Curve::parse("curve:inf"), None);
+
+        // the release is cut to the length of a shorter tone, fading it out completely
+        let e = Envelope::adsr(0.0, 0.0, 1.0, 1.0);
+        assert_eq!(e.multiplier(&g, 0, 400), 1.0);
+        assert_eq!(e.multiplier(&g, 200, 400), 0.5);
+        assert_eq!(e.multiplier(&g, 400, 400), 0.0);
+        let mut synth = Synthesizer::builder()
+            .sample_rate(8000)
+            .envelope(vec![0.0, 0.0, 0.0, 1.0, 1.0])
+            .build();
+        let pcm = synth.compute((vec![440.0], vec![0.1])).unwrap();
+        assert_eq!(pcm.len(), 800);
     }
 
     #[test]
@@ -450,8 +3559,26 @@ mod tests {
             generator: Generator::new(440.0, Some(8000.0), None),
             envelope: Some(Envelope::adsr(0.02, 0.02, 0.5, 0.02)),
             samples: Some(800),
+            vibrato: None,
+            tremolo: None,
+            unison: None,
+            fade: None,
+            pan: None,
         };
 
-        assert_eq!(t.u16(), result);
+        assert_eq!(t.u16().unwrap(), result);
+
+        // signed and floating point samples are centered at 0
+        let x = t.f64().unwrap();
+        assert!(x
+            .iter()
+            .zip(t.f32().unwrap())
+            .all(|(x, y)| (x - y as f64).abs() < 1e-6));
+        let i = t.i16().unwrap();
+        assert!(x
+            .iter()
+            .zip(&i)
+            .all(|(x, i)| (x * 32768.0 - *i as f64).abs() <= 0.5));
+        assert_eq!(i[0], 0);
     }
 }
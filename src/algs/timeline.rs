@@ -0,0 +1,160 @@
+use std::collections::VecDeque;
+
+use super::instrument::Instrument;
+use super::synth::{Envelope, Waveform};
+use crate::midi::Message;
+
+/// event played by a Timeline at a sample
+#[derive(Clone)]
+pub enum Event {
+    /// a MIDI message, like a note on or off or a change of the volume
+    Midi(Message),
+    /// change the waveform of all voices
+    Waveform(Waveform),
+    /// change the envelope of all notes, including the ones sounding
+    Envelope(Envelope),
+}
+
+/// Timeline of events scheduled at absolute sample positions, played by an Instrument
+///
+/// Events are pushed in any order and played at their exact samples while blocks are rendered,
+/// whatever the size of the blocks, so the output is the same as of a single block.
+/// Events at the same sample are played in the order they were pushed,
+/// and events pushed for a sample that was already rendered are played at the next one.
+pub struct Timeline {
+    instrument: Instrument,
+    events: VecDeque<(u64, Event)>,
+    position: u64,
+}
+
+impl Timeline {
+    /// create an empty Timeline playing an Instrument from sample 0
+    pub fn new(instrument: Instrument) -> Self {
+        Timeline {
+            instrument,
+            events: VecDeque::new(),
+            position: 0,
+        }
+    }
+
+    /// schedule an event at a sample
+    pub fn push(&mut self, position: u64, event: Event) -> &mut Self {
+        let i = self.events.partition_point(|(p, _)| *p <= position);
+        self.events.insert(i, (position, event));
+        self
+    }
+
+    /// schedule a note on of a MIDI note number with a velocity from 1 to 127 at a sample
+    pub fn note_on(&mut self, position: u64, pitch: u8, velocity: u8) -> &mut Self {
+        let message = Message::NoteOn {
+            channel: 0,
+            pitch,
+            velocity,
+        };
+        self.push(position, Event::Midi(message))
+    }
+
+    /// schedule a note off of a MIDI note number at a sample
+    pub fn note_off(&mut self, position: u64, pitch: u8) -> &mut Self {
+        self.push(
+            position,
+            Event::Midi(Message::NoteOff { channel: 0, pitch }),
+        )
+    }
+
+    /// sample where the next block starts
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// number of events not played yet
+    pub fn pending(&self) -> usize {
+        self.events.len()
+    }
+
+    /// the Instrument playing the events
+    pub fn instrument(&self) -> &Instrument {
+        &self.instrument
+    }
+
+    /// render the next block of samples, playing the events scheduled within it
+    pub fn render(&mut self, block: &mut [f64]) {
+        let end = self.position + block.len() as u64;
+        let mut start = 0;
+        while let Some((position, _)) = self.events.front() {
+            if *position >= end {
+                break;
+            }
+            let at = position.saturating_sub(self.position) as usize;
+            self.instrument.render(&mut block[start..at]);
+            start = at;
+            let (_, event) = self.events.pop_front().unwrap();
+            self.play(event);
+        }
+        self.instrument.render(&mut block[start..]);
+        self.position = end;
+    }
+
+    /// render n samples from the current position on
+    pub fn render_samples(&mut self, n: usize) -> Vec<f64> {
+        let mut block = vec![0.0; n];
+        self.render(&mut block);
+        block
+    }
+
+    fn play(&mut self, event: Event) {
+        match event {
+            Event::Midi(message) => self.instrument.handle(message),
+            Event::Waveform(waveform) => self.instrument.waveform = waveform,
+            Event::Envelope(envelope) => self.instrument.envelope = envelope,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Event, Timeline};
+    use crate::algs::instrument::Instrument;
+    use crate::algs::synth::{Envelope, Waveform};
+
+    #[test]
+    fn timeline() {
+        let schedule = |t: &mut Timeline| {
+            // pushed out of order, and a chord of two notes at the same sample
+            t.note_off(300, 69)
+                .note_on(100, 69, 127)
+                .push(200, Event::Waveform(Waveform::Square))
+                .note_on(250, 76, 64)
+                .note_off(250, 76);
+        };
+        let instrument = || Instrument::new(8000.0).envelope(Envelope::adsr(0.0, 0.0, 1.0, 0.0));
+        let mut whole = Timeline::new(instrument());
+        schedule(&mut whole);
+        assert_eq!(whole.pending(), 5);
+        let x = whole.render_samples(400);
+        assert_eq!((whole.position(), whole.pending()), (400, 0));
+
+        // silent until the note on, and the waveform changes at its sample
+        assert!(x[..100].iter().all(|x| *x == 0.0));
+        assert!(
+            x[100] == 0.0
+                && (x[101] - (std::f64::consts::TAU * 440.0 / 8000.0).sin()).abs() < 1e-12
+        );
+        assert!(x[200..250].iter().all(|x| x.abs() == 1.0));
+        assert!(x[300..].iter().all(|x| *x == 0.0));
+
+        // the same in blocks of any size
+        let mut blocks = Timeline::new(instrument());
+        schedule(&mut blocks);
+        let mut y = Vec::new();
+        for n in [64, 1, 35, 100, 200] {
+            y.extend(blocks.render_samples(n));
+        }
+        assert_eq!(x, y);
+
+        // late events are played at the start of the next block, with the square wave set before
+        blocks.note_on(10, 69, 127);
+        assert_eq!(blocks.render_samples(1), [1.0]);
+        assert_eq!(blocks.instrument().active(), 1);
+    }
+}
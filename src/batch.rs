@@ -0,0 +1,274 @@
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender};
+use std::sync::Arc;
+use std::thread;
+
+use crate::json::{FromJson, Json};
+use crate::mixer::Loader;
+use crate::pool::{Pool, Value};
+use crate::registry::{DynAlgorithm, Registry};
+use crate::streaming::{Frames, Node};
+use crate::{Error, Result};
+
+/// result of extracting features from a single file
+pub type Extracted = (PathBuf, Result<Pool>);
+
+/// Extraction of features from a list of files on worker threads, for corpus-scale analysis
+///
+/// Each worker takes the next file from a shared queue as soon as it finishes the previous one,
+/// so a few long files don't hold up the others.
+/// Results are passed on in the order they are finished, and at most .queue() of them
+/// wait to be consumed, so memory use doesn't grow with the number of files.
+/// A file that fails doesn't stop the batch, its error is passed on in place of a Pool.
+pub struct Batch<F> {
+    extract: F,
+    threads: usize,
+    queue: usize,
+}
+
+impl<F: Fn(&Path) -> Result<Pool> + Sync> Batch<F> {
+    /// create a new Batch running a function for each file, with a worker for each core
+    pub fn new(extract: F) -> Self {
+        let threads = thread::available_parallelism().map_or(1, NonZeroUsize::get);
+        Batch {
+            extract,
+            threads,
+            queue: 2 * threads,
+        }
+    }
+
+    /// set the number of worker threads
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = std::cmp::max(1, threads);
+        self
+    }
+
+    /// set how many results may wait to be consumed before workers pause
+    pub fn queue(mut self, queue: usize) -> Self {
+        self.queue = queue;
+        self
+    }
+
+    /// extract features from all files and pass each result to a callback as soon as it's ready
+    pub fn run<C: FnMut(&Path, Result<Pool>)>(&self, files: &[PathBuf], mut callback: C) {
+        let next = AtomicUsize::new(0);
+        let (sender, receiver) = sync_channel(self.queue);
+        thread::scope(|s| {
+            for _ in 0..std::cmp::min(self.threads, files.len()) {
+                let sender = sender.clone();
+                let next = &next;
+                s.spawn(move || work(files, next, &sender, |i, path| (i, (self.extract)(path))));
+            }
+            drop(sender);
+            for (i, result) in receiver {
+                callback(&files[i], result);
+            }
+        });
+    }
+}
+
+impl<F: Fn(&Path) -> Result<Pool> + Send + Sync + 'static> Batch<F> {
+    /// extract features from all files in the background and receive the results from a channel
+    ///
+    /// Workers stop early once the receiver is dropped.
+    pub fn spawn(self, files: Vec<PathBuf>) -> Receiver<Extracted> {
+        let files = Arc::new(files);
+        let next = Arc::new(AtomicUsize::new(0));
+        let extract = Arc::new(self.extract);
+        let (sender, receiver) = sync_channel(self.queue);
+        for _ in 0..std::cmp::min(self.threads, files.len()) {
+            let (files, next, extract) = (files.clone(), next.clone(), extract.clone());
+            let sender = sender.clone();
+            thread::spawn(move || {
+                work(&files, &next, &sender, |_, path| {
+                    (path.to_path_buf(), extract(path))
+                })
+            });
+        }
+        receiver
+    }
+}
+
+/// take files from the queue until there are none left or the receiver is gone
+fn work<T, F: Fn(usize, &Path) -> T>(
+    files: &[PathBuf],
+    next: &AtomicUsize,
+    sender: &SyncSender<T>,
+    f: F,
+) {
+    loop {
+        let i = next.fetch_add(1, Ordering::Relaxed);
+        if i >= files.len() || sender.send(f(i, &files[i])).is_err() {
+            return;
+        }
+    }
+}
+
+/// Extraction config running pipelines of the Registry on frames of each file
+///
+/// The config is a JSON object like:
+///
+/// ```json
+/// {
+///     "frame_size": 2048,
+///     "hop_size": 1024,
+///     "features": {
+///         "lowlevel.spectrum": [{"algorithm": "FFT"}],
+///         "lowlevel.smooth": [{"algorithm": "MovingAverage", "params": {"window_size": 5}}]
+///     }
+/// }
+/// ```
+///
+/// The output of each pipeline for every frame is added to the Pool under its key,
+/// and the sample rate of the file is set under "metadata.sample_rate".
+/// Files are decoded packet by packet, so only a few frames are in memory at once.
+pub struct FrameExtractor {
+    registry: Registry,
+    frame_size: usize,
+    hop_size: usize,
+    features: Vec<(String, Json)>,
+}
+
+impl FrameExtractor {
+    /// read an extraction config for algorithms of the default Registry
+    pub fn from_json(config: &Json) -> Result<Self> {
+        Self::with_registry(Registry::default(), config)
+    }
+
+    /// read an extraction config for algorithms of a given Registry
+    ///
+    /// Fails with Error::InvalidParam if any of the pipelines can't be created.
+    pub fn with_registry(registry: Registry, config: &Json) -> Result<Self> {
+        let mut extractor = FrameExtractor {
+            registry,
+            frame_size: 2048,
+            hop_size: 1024,
+            features: Vec::new(),
+        };
+        for (key, value) in config.fields()? {
+            match key.as_str() {
+                "frame_size" => extractor.frame_size = FromJson::from_json(value)?,
+                "hop_size" => extractor.hop_size = FromJson::from_json(value)?,
+                "features" => {
+                    for (key, steps) in value.fields()? {
+                        // fail early on configs with unknown algorithms or parameters
+                        extractor.registry.pipeline(steps)?;
+                        extractor.features.push((key.clone(), steps.clone()));
+                    }
+                }
+                _ => {
+                    return Err(Error::InvalidParam(format!(
+                        "unknown extraction setting {}",
+                        key
+                    )))
+                }
+            }
+        }
+        if extractor.frame_size == 0 || extractor.hop_size == 0 {
+            return Err(Error::InvalidParam(
+                "frame_size and hop_size must be positive".into(),
+            ));
+        }
+        Ok(extractor)
+    }
+
+    /// extract features from a file with new instances of all pipelines
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "FrameExtractor::extract", level = "debug", skip_all)
+    )]
+    pub fn extract(&self, path: &Path) -> Result<Pool> {
+        let mut pipelines = self
+            .features
+            .iter()
+            .map(|(key, steps)| Ok((key, self.registry.pipeline(steps)?)))
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut pool = Pool::new();
+        let mut packets = Loader::<f64>::new()
+            .file(path.to_path_buf())
+            .mono()
+            .packets()?;
+        let mut frames = Frames::new(self.frame_size, self.hop_size);
+        frames.run(packets.by_ref(), |frame| {
+            let frame = Json::Array(frame.into_iter().map(Json::Number).collect());
+            for (key, pipeline) in pipelines.iter_mut() {
+                let output = pipeline.compute_json(&frame)?;
+                pool.add(key, Value::from_json(&output)?)?;
+            }
+            Ok(())
+        })?;
+        if let Some(sample_rate) = packets.sample_rate() {
+            pool.set("metadata.sample_rate", sample_rate as f64)?;
+        }
+        Ok(pool)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Batch, FrameExtractor};
+    use crate::json::Json;
+    use crate::mixer::Writer;
+    use crate::pool::Value;
+    use crate::Error;
+
+    #[test]
+    fn batch() {
+        let dir = std::env::temp_dir();
+        let mut files = Vec::new();
+        for n in [40, 100, 70] {
+            let path = dir.join(format!("muslib-batch-{}.wav", n));
+            let signal: Vec<f64> = (0..n).map(|x| (x as f64 * 0.2).sin() * 0.5).collect();
+            Writer::new().file(path.clone()).write(&signal).unwrap();
+            files.push(path);
+        }
+        files.push(dir.join("muslib-batch-missing.wav"));
+
+        let config = r#"{
+            "frame_size": 16,
+            "hop_size": 16,
+            "features": {"smooth": [{"algorithm": "MovingAverage", "params": {"window_size": 3}}]}
+        }"#;
+        let extractor = FrameExtractor::from_json(&Json::parse(config).unwrap()).unwrap();
+        let batch = Batch::new(move |path| extractor.extract(path))
+            .threads(2)
+            .queue(1);
+
+        let mut frames = Vec::new();
+        batch.run(&files, |path, result| match result {
+            Ok(pool) => {
+                assert_eq!(
+                    pool.value("metadata.sample_rate"),
+                    Some(&Value::Real(44100.0))
+                );
+                frames.push((path.to_path_buf(), pool.get("smooth").unwrap().len()));
+            }
+            Err(e) => assert!(matches!(e, Error::Io(_)) && path == files[3]),
+        });
+        frames.sort();
+        // frames are cut every 16 samples, the last one padded
+        let mut expected = vec![
+            (files[0].clone(), 3),
+            (files[1].clone(), 7),
+            (files[2].clone(), 5),
+        ];
+        expected.sort();
+        assert_eq!(frames, expected);
+
+        let receiver = batch.spawn(files.clone());
+        assert_eq!(receiver.iter().count(), files.len());
+
+        for path in &files[..3] {
+            std::fs::remove_file(path).unwrap();
+        }
+
+        let config = Json::parse(r#"{"features": {"x": [{"algorithm": "Essentia"}]}}"#).unwrap();
+        assert!(matches!(
+            FrameExtractor::from_json(&config),
+            Err(Error::InvalidParam(_))
+        ));
+    }
+}
@@ -0,0 +1,250 @@
+use std::path::Path;
+
+use crate::extractor::MusicExtractor;
+use crate::pool::Value;
+use crate::{Error, Result};
+
+/// Cover song similarity of two recordings, after J. Serra et al.
+///
+/// HPCP frames are averaged in blocks and both recordings are brought to a common key
+/// with the optimal transposition index (OTI) of their global HPCPs.
+/// Two frames match when no other transposition fits them better, and a local alignment
+/// of the matching frames with the Smith-Waterman algorithm finds the longest similar segment.
+/// Gaps in the alignment absorb differences in tempo and structure.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CoverSimilarity {
+    /// number of consecutive HPCP frames averaged into one
+    pub downsample: usize,
+    /// score added for a pair of matching frames
+    pub match_score: f64,
+    /// penalty for a pair of frames that don't match
+    pub mismatch_penalty: f64,
+    /// penalty for skipping a frame in either recording
+    pub gap_penalty: f64,
+    /// extractor of HPCP frames used by .compare_files()
+    pub extractor: MusicExtractor,
+}
+
+impl Default for CoverSimilarity {
+    fn default() -> Self {
+        CoverSimilarity {
+            downsample: 4,
+            match_score: 1.0,
+            mismatch_penalty: 0.9,
+            gap_penalty: 0.7,
+            extractor: MusicExtractor::default(),
+        }
+    }
+}
+
+/// best local alignment of two recordings found by CoverSimilarity
+#[derive(Debug, Clone, PartialEq)]
+pub struct Alignment {
+    /// similarity from 0 for unrelated recordings to 1 when the shorter one matches throughout
+    pub score: f64,
+    /// semitones by which the second recording is transposed up from the first one
+    pub transposition: usize,
+    /// start and end of the matching segment of the first recording
+    pub first: (f64, f64),
+    /// start and end of the matching segment of the second recording
+    pub second: (f64, f64),
+}
+
+impl CoverSimilarity {
+    /// create a new CoverSimilarity with default settings
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// compare two audio files, with segment boundaries in seconds
+    pub fn compare_files(&self, first: &Path, second: &Path) -> Result<Alignment> {
+        let frames = |path| -> Result<(Vec<Vec<f64>>, f64)> {
+            let pool = self.extractor.frames(path)?;
+            let hpcp = pool.vectors("tonal.hpcp").unwrap_or_default();
+            let sample_rate = match pool.value("metadata.audio_properties.sample_rate") {
+                Some(Value::Real(x)) => *x,
+                _ => 44100.0,
+            };
+            let hpcp = hpcp.into_iter().map(|x| x.to_vec()).collect();
+            Ok((hpcp, self.extractor.hop_size as f64 / sample_rate))
+        };
+        let (a, duration_a) = frames(first)?;
+        let (b, duration_b) = frames(second)?;
+        let mut alignment = self.compare(&a, &b)?;
+        alignment.first = (
+            alignment.first.0 * duration_a,
+            alignment.first.1 * duration_a,
+        );
+        alignment.second = (
+            alignment.second.0 * duration_b,
+            alignment.second.1 * duration_b,
+        );
+        Ok(alignment)
+    }
+
+    /// compare two sequences of 12 bin HPCP frames, with segment boundaries in frames
+    ///
+    /// Fails with Error::InvalidParam if either sequence is empty or has frames of another size.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "CoverSimilarity::compare", level = "debug", skip_all)
+    )]
+    pub fn compare(&self, first: &[Vec<f64>], second: &[Vec<f64>]) -> Result<Alignment> {
+        let a = self.blocks(first)?;
+        let b = self.blocks(second)?;
+
+        let transposition = oti(&mean(&a), &mean(&b));
+        let b: Vec<[f64; 12]> = b.iter().map(|x| rotate(x, transposition)).collect();
+
+        // Smith-Waterman alignment, remembering where the path of each cell started,
+        // so empty cells start a new path at their own position
+        let (n, m) = (a.len(), b.len());
+        let mut h: Vec<Vec<(f64, (usize, usize))>> = (0..=n)
+            .map(|i| (0..=m).map(|j| (0.0, (i, j))).collect())
+            .collect();
+        let mut best = (0.0, (0, 0), (0, 0));
+        for i in 1..=n {
+            for j in 1..=m {
+                let similarity = if oti(&a[i - 1], &b[j - 1]) == 0 {
+                    self.match_score
+                } else {
+                    -self.mismatch_penalty
+                };
+                let diagonal = (h[i - 1][j - 1].0 + similarity, h[i - 1][j - 1].1);
+                let up = (h[i - 1][j].0 - self.gap_penalty, h[i - 1][j].1);
+                let left = (h[i][j - 1].0 - self.gap_penalty, h[i][j - 1].1);
+                let mut cell = (0.0, (i, j));
+                for x in [diagonal, up, left] {
+                    if x.0 > cell.0 {
+                        cell = x;
+                    }
+                }
+                h[i][j] = cell;
+                if cell.0 > best.0 {
+                    best = (cell.0, cell.1, (i, j));
+                }
+            }
+        }
+
+        let (score, start, end) = best;
+        let d = self.downsample as f64;
+        Ok(Alignment {
+            score: score / (self.match_score * n.min(m) as f64),
+            transposition,
+            first: (start.0 as f64 * d, end.0 as f64 * d),
+            second: (start.1 as f64 * d, end.1 as f64 * d),
+        })
+    }
+
+    /// average frames in blocks and normalize each block to a maximum of 1
+    fn blocks(&self, frames: &[Vec<f64>]) -> Result<Vec<[f64; 12]>> {
+        if frames.is_empty() || frames.iter().any(|x| x.len() != 12) {
+            return Err(Error::InvalidParam(
+                "cover similarity needs a non-empty sequence of 12 bin HPCP frames".into(),
+            ));
+        }
+        let blocks = frames.chunks(self.downsample.max(1)).map(|chunk| {
+            let mut block = [0.0; 12];
+            for frame in chunk {
+                for (b, x) in block.iter_mut().zip(frame.iter()) {
+                    *b += x;
+                }
+            }
+            let max = block.iter().cloned().fold(0.0, f64::max);
+            if max > 0.0 {
+                block.iter_mut().for_each(|x| *x /= max);
+            }
+            block
+        });
+        Ok(blocks.collect())
+    }
+}
+
+/// element-wise mean of HPCP frames
+fn mean(frames: &[[f64; 12]]) -> [f64; 12] {
+    let mut mean = [0.0; 12];
+    for frame in frames {
+        for (m, x) in mean.iter_mut().zip(frame.iter()) {
+            *m += x / frames.len() as f64;
+        }
+    }
+    mean
+}
+
+/// HPCP with bins shifted down by a number of semitones
+fn rotate(x: &[f64; 12], shift: usize) -> [f64; 12] {
+    std::array::from_fn(|i| x[(i + shift) % 12])
+}
+
+/// optimal transposition index, the shift of the second HPCP best correlated with the first one
+fn oti(a: &[f64; 12], b: &[f64; 12]) -> usize {
+    let mut best = (0, f64::NEG_INFINITY);
+    for shift in 0..12 {
+        let dot: f64 = (0..12).map(|i| a[i] * b[(i + shift) % 12]).sum();
+        if dot > best.1 {
+            best = (shift, dot);
+        }
+    }
+    best.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CoverSimilarity;
+    use crate::rng::Rng;
+
+    /// HPCP frame of a triad on a root, counted in semitones from A
+    fn triad(root: usize, minor: bool) -> Vec<f64> {
+        let mut frame = vec![0.05; 12];
+        let third = if minor { 3 } else { 4 };
+        for i in [0, third, 7] {
+            frame[(root + i) % 12] = 1.0;
+        }
+        frame
+    }
+
+    #[test]
+    fn cover() {
+        // a progression of chords, each held for 8 frames
+        let chords = [
+            (0, false),
+            (5, false),
+            (7, false),
+            (9, true),
+            (2, true),
+            (7, false),
+        ];
+        let song: Vec<Vec<f64>> = chords
+            .iter()
+            .flat_map(|(root, minor)| vec![triad(*root, *minor); 8])
+            .collect();
+
+        // the cover is 3 semitones higher, 50% slower and starts after an unrelated intro
+        let mut rng = Rng::new(1);
+        let mut cover: Vec<Vec<f64>> = (0..24)
+            .map(|_| (0..12).map(|_| rng.next_f64()).collect())
+            .collect();
+        for (root, minor) in chords {
+            cover.extend(vec![triad(root + 3, minor); 12]);
+        }
+
+        let similarity = CoverSimilarity::new();
+        let alignment = similarity.compare(&song, &cover).unwrap();
+        assert_eq!(alignment.transposition, 3);
+        assert!(alignment.score > 0.5, "{:?}", alignment);
+        assert!(
+            alignment.first.0 <= 4.0 && alignment.first.1 >= 44.0,
+            "{:?}",
+            alignment
+        );
+        assert!(alignment.second.0 >= 20.0, "{:?}", alignment);
+
+        let noise: Vec<Vec<f64>> = (0..96)
+            .map(|_| (0..12).map(|_| rng.next_f64()).collect())
+            .collect();
+        let unrelated = similarity.compare(&song, &noise).unwrap();
+        assert!(unrelated.score < alignment.score / 2.0, "{:?}", unrelated);
+
+        assert!(similarity.compare(&song, &[]).is_err());
+    }
+}
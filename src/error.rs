@@ -0,0 +1,96 @@
+use alloc::string::String;
+#[cfg(feature = "std")]
+use alloc::string::ToString;
+use core::fmt;
+
+/// Error returned by muslib when audio can not be loaded, processed or written
+///
+/// In Python it is raised as MuslibError, or as OSError and ValueError for the matching variants.
+#[derive(Debug)]
+pub enum Error {
+    /// reading or writing a file failed
+    #[cfg(feature = "std")]
+    Io(std::io::Error),
+    /// the format of a file or its codec is not supported
+    UnsupportedFormat(String),
+    /// audio data is malformed and can not be decoded or encoded
+    Decode(String),
+    /// a parameter or an input has a value that can not be used
+    InvalidParam(String),
+    /// processing was stopped with a CancelToken
+    Cancelled,
+}
+
+/// Result with the muslib Error
+pub type Result<T> = core::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(feature = "std")]
+            Error::Io(err) => write!(f, "io error: {}", err),
+            Error::UnsupportedFormat(msg) => write!(f, "unsupported format: {}", msg),
+            Error::Decode(msg) => write!(f, "malformed audio data: {}", msg),
+            Error::InvalidParam(msg) => write!(f, "invalid parameter: {}", msg),
+            Error::Cancelled => write!(f, "cancelled"),
+        }
+    }
+}
+
+impl core::error::Error for Error {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            #[cfg(feature = "std")]
+            Error::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<symphonia::core::errors::Error> for Error {
+    fn from(err: symphonia::core::errors::Error) -> Self {
+        use symphonia::core::errors::Error as E;
+        match err {
+            E::IoError(err) => Error::Io(err),
+            E::Unsupported(msg) => Error::UnsupportedFormat(msg.into()),
+            err => Error::Decode(err.to_string()),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<hound::Error> for Error {
+    fn from(err: hound::Error) -> Self {
+        match err {
+            hound::Error::IoError(err) => Error::Io(err),
+            hound::Error::Unsupported => Error::UnsupportedFormat(err.to_string()),
+            hound::Error::InvalidSampleFormat => Error::InvalidParam(err.to_string()),
+            err => Error::Decode(err.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Error;
+
+    #[test]
+    fn conversions() {
+        let err = symphonia::core::errors::Error::Unsupported("codec");
+        assert!(matches!(Error::from(err), Error::UnsupportedFormat(msg) if msg == "codec"));
+
+        let err = hound::Error::FormatError("no RIFF tag found");
+        assert!(matches!(Error::from(err), Error::Decode(_)));
+
+        let err = std::io::Error::from(std::io::ErrorKind::NotFound);
+        assert!(matches!(Error::from(err), Error::Io(_)));
+    }
+}
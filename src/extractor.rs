@@ -0,0 +1,401 @@
+use std::path::Path;
+
+use crate::algs::filters::{median, Edge};
+use crate::algs::hpcp::{HPCPParams, HPCP};
+use crate::algs::stft::{Window, FFT};
+use crate::algs::Algorithm;
+use crate::mixer::Loader;
+use crate::pool::{Aggregator, Pool, Value};
+use crate::streaming::{Frames, Node};
+use crate::{notes, simd, Error, Result};
+
+/// Krumhansl-Kessler profile of a major key, starting from the tonic
+const MAJOR: [f64; 12] = [
+    6.35, 2.23, 3.48, 2.33, 4.38, 4.09, 2.52, 5.19, 2.39, 3.66, 2.29, 2.88,
+];
+
+/// Krumhansl-Kessler profile of a minor key, starting from the tonic
+const MINOR: [f64; 12] = [
+    6.33, 2.68, 3.52, 5.38, 2.60, 3.53, 2.54, 4.75, 3.98, 2.69, 3.34, 3.17,
+];
+
+/// One-shot extractor of standard descriptors of a file, inspired by the Essentia MusicExtractor
+///
+/// Descriptors computed for each frame, aggregated to statistics like ".mean" by .extract():
+///  - lowlevel.rms, lowlevel.zerocrossingrate
+///  - lowlevel.spectral_energy, lowlevel.spectral_centroid, lowlevel.spectral_rolloff
+///  - rhythm.onset_strength -- half-wave rectified spectral flux
+///  - tonal.hpcp -- of spectral peaks below 5 kHz
+///
+/// Descriptors of the whole file:
+///  - metadata.audio_properties.length, metadata.audio_properties.sample_rate
+///  - rhythm.bpm -- strongest periodicity of onset strength
+///  - rhythm.onset_times, rhythm.onset_rate
+///  - tonal.key_key, tonal.key_scale, tonal.key_strength -- correlation of the mean HPCP
+///    with Krumhansl-Kessler key profiles
+#[derive(Debug, Clone, PartialEq)]
+pub struct MusicExtractor {
+    /// size of analysis frames in samples, a power of two
+    pub frame_size: usize,
+    /// distance between the starts of consecutive frames in samples
+    pub hop_size: usize,
+    /// slowest tempo in BPM considered by the tempo estimate
+    pub min_tempo: f64,
+    /// fastest tempo in BPM considered by the tempo estimate
+    pub max_tempo: f64,
+    /// statistics of descriptors of frames computed by .extract()
+    pub aggregator: Aggregator,
+}
+
+impl Default for MusicExtractor {
+    fn default() -> Self {
+        MusicExtractor {
+            frame_size: 2048,
+            hop_size: 1024,
+            min_tempo: 40.0,
+            max_tempo: 208.0,
+            aggregator: Aggregator::default(),
+        }
+    }
+}
+
+impl MusicExtractor {
+    /// create a new MusicExtractor with default settings
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// descriptors of each frame of a file, with the metadata
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "MusicExtractor::frames", level = "debug", skip_all)
+    )]
+    pub fn frames(&self, path: &Path) -> Result<Pool> {
+        if !self.frame_size.is_power_of_two() || self.hop_size == 0 {
+            return Err(Error::InvalidParam(
+                "frame_size must be a power of two and hop_size must be positive".into(),
+            ));
+        }
+
+        let mut packets = Loader::<f64>::new()
+            .file(path.to_path_buf())
+            .mono()
+            .packets()?;
+        // the sample rate is known once the first packet is decoded
+        let first = packets.next();
+        let sample_rate = packets.sample_rate().unwrap_or(44100) as f64;
+
+        let mut analysis = Analysis::new(self.frame_size, sample_rate);
+        let mut length = 0;
+        let blocks = first.into_iter().chain(packets.by_ref()).inspect(|x| {
+            if let Ok(x) = x {
+                length += x.len();
+            }
+        });
+        let mut frames = Frames::new(self.frame_size, self.hop_size);
+        frames.run(blocks, |frame| analysis.frame(frame))?;
+
+        let mut pool = analysis.pool;
+        pool.set(
+            "metadata.audio_properties.length",
+            length as f64 / sample_rate,
+        )?;
+        pool.set("metadata.audio_properties.sample_rate", sample_rate)?;
+        Ok(pool)
+    }
+
+    /// descriptors of a whole file, with descriptors of frames aggregated by the Aggregator
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "MusicExtractor::extract", level = "debug", skip_all)
+    )]
+    pub fn extract(&self, path: &Path) -> Result<Pool> {
+        let frames = self.frames(path)?;
+        let mut pool = self.aggregator.aggregate(&frames)?;
+
+        let sample_rate = match frames.value("metadata.audio_properties.sample_rate") {
+            Some(Value::Real(x)) => *x,
+            _ => 44100.0,
+        };
+        let frame_rate = sample_rate / self.hop_size as f64;
+        let strength = frames.reals("rhythm.onset_strength").unwrap_or_default();
+        let onsets = onset_times(&strength, frame_rate);
+        let length = strength.len() as f64 / frame_rate;
+        if length > 0.0 {
+            pool.set("rhythm.onset_rate", onsets.len() as f64 / length)?;
+        }
+        pool.set("rhythm.onset_times", onsets)?;
+        let bpm = tempo(&strength, frame_rate, self.min_tempo, self.max_tempo);
+        pool.set("rhythm.bpm", bpm)?;
+
+        if let Some(Value::Vector(hpcp)) = frames.mean("tonal.hpcp") {
+            let (tonic, scale, strength) = key(&hpcp);
+            pool.set("tonal.key_key", tonic)?;
+            pool.set("tonal.key_scale", scale)?;
+            pool.set("tonal.key_strength", strength)?;
+        }
+        Ok(pool)
+    }
+}
+
+/// state of the analysis carried between frames
+struct Analysis {
+    fft: FFT,
+    hpcp: HPCP,
+    window: Vec<f64>,
+    windowed: Vec<f64>,
+    spectrum: Vec<(f32, f32)>,
+    magnitudes: Vec<f64>,
+    previous: Vec<f64>,
+    sample_rate: f64,
+    pool: Pool,
+}
+
+impl Analysis {
+    fn new(frame_size: usize, sample_rate: f64) -> Self {
+        let hpcp = HPCP::with_params(HPCPParams {
+            sample_rate,
+            ..Default::default()
+        });
+        Analysis {
+            fft: FFT::new(),
+            hpcp,
+            window: Window::Hann.coefficients(frame_size),
+            windowed: Vec::with_capacity(frame_size),
+            spectrum: Vec::with_capacity(frame_size),
+            magnitudes: Vec::with_capacity(frame_size / 2 + 1),
+            previous: Vec::new(),
+            sample_rate,
+            pool: Pool::new(),
+        }
+    }
+
+    /// compute the descriptors of a single frame and add them to the pool
+    fn frame(&mut self, frame: Vec<f64>) -> Result<()> {
+        let n = frame.len() as f64;
+        let rms = (frame.iter().map(|x| x * x).sum::<f64>() / n).sqrt();
+        let crossings = frame
+            .windows(2)
+            .filter(|x| (x[0] < 0.0) != (x[1] < 0.0))
+            .count();
+
+        self.windowed.clone_from(&frame);
+        simd::mul(&mut self.windowed, &self.window);
+        self.fft.compute_into(&self.windowed, &mut self.spectrum)?;
+        let bins = self.spectrum.len() / 2 + 1;
+        self.magnitudes.clear();
+        self.magnitudes.extend(
+            self.spectrum[..bins]
+                .iter()
+                .map(|(re, im)| (re * re + im * im).sqrt() as f64),
+        );
+        let bin_hz = self.sample_rate / self.spectrum.len() as f64;
+
+        let energy: f64 = self.magnitudes.iter().map(|x| x * x).sum();
+        let total: f64 = self.magnitudes.iter().sum();
+        let centroid = if total > 0.0 {
+            let weighted = self.magnitudes.iter().enumerate();
+            weighted.map(|(i, x)| i as f64 * bin_hz * x).sum::<f64>() / total
+        } else {
+            0.0
+        };
+        let mut cumulative = 0.0;
+        let rolloff = self
+            .magnitudes
+            .iter()
+            .position(|x| {
+                cumulative += x * x;
+                cumulative >= 0.85 * energy
+            })
+            .map_or(0.0, |i| i as f64 * bin_hz);
+        let flux = if self.previous.len() == bins {
+            let pairs = self.magnitudes.iter().zip(self.previous.iter());
+            pairs.map(|(x, y)| (x - y).max(0.0)).sum()
+        } else {
+            0.0
+        };
+        self.previous.clone_from(&self.magnitudes);
+
+        let peaks = spectral_peaks(&self.magnitudes, bin_hz, 5000.0);
+        let hpcp = self.hpcp.compute(peaks)?;
+
+        self.pool.add("lowlevel.rms", rms)?;
+        self.pool
+            .add("lowlevel.zerocrossingrate", crossings as f64 / n)?;
+        self.pool.add("lowlevel.spectral_energy", energy)?;
+        self.pool.add("lowlevel.spectral_centroid", centroid)?;
+        self.pool.add("lowlevel.spectral_rolloff", rolloff)?;
+        self.pool.add("rhythm.onset_strength", flux)?;
+        self.pool.add("tonal.hpcp", hpcp)?;
+        Ok(())
+    }
+}
+
+/// frequencies and magnitudes of local maxima of a magnitude spectrum, refined with a parabola
+fn spectral_peaks(magnitudes: &[f64], bin_hz: f64, max_frequency: f64) -> (Vec<f64>, Vec<f64>) {
+    let mut peaks = (Vec::new(), Vec::new());
+    for (i, x) in magnitudes.windows(3).enumerate() {
+        let (l, c, r) = (x[0], x[1], x[2]);
+        if c > l && c >= r && c > 0.0 {
+            let curvature = l - 2.0 * c + r;
+            let delta = if curvature != 0.0 {
+                0.5 * (l - r) / curvature
+            } else {
+                0.0
+            };
+            let frequency = (i as f64 + 1.0 + delta) * bin_hz;
+            if frequency > max_frequency {
+                break;
+            }
+            peaks.0.push(frequency);
+            peaks.1.push(c - 0.25 * (l - r) * delta);
+        }
+    }
+    peaks
+}
+
+/// times in seconds of peaks of onset strength rising above its running median
+fn onset_times(strength: &[f64], frame_rate: f64) -> Vec<f64> {
+    let max = strength.iter().cloned().fold(0.0, f64::max);
+    let threshold = median(strength, 7, &Edge::Nearest);
+    let mut onsets = Vec::new();
+    for i in 1..strength.len() {
+        let next = strength.get(i + 1).cloned().unwrap_or(0.0);
+        let x = strength[i];
+        if x > strength[i - 1] && x >= next && x > threshold[i] + 0.1 * max {
+            onsets.push(i as f64 / frame_rate);
+        }
+    }
+    onsets
+}
+
+/// tempo in BPM of the strongest autocorrelation peak of onset strength, 0 if there is none
+///
+/// Peaks are weighted with a log-normal prior centered at 120 BPM with a width of one octave,
+/// like in the beat tracker of D. Ellis, so multiples of the beat period are less likely.
+fn tempo(strength: &[f64], frame_rate: f64, min_tempo: f64, max_tempo: f64) -> f64 {
+    let n = strength.len();
+    if n == 0 {
+        return 0.0;
+    }
+    let mean = strength.iter().sum::<f64>() / n as f64;
+    let x: Vec<f64> = strength.iter().map(|x| x - mean).collect();
+    let autocorrelation = |lag: usize| -> f64 {
+        let products = x.iter().zip(x[lag..].iter()).map(|(a, b)| a * b);
+        products.sum::<f64>() / (n - lag) as f64
+    };
+
+    let shortest = (60.0 * frame_rate / max_tempo).ceil() as usize;
+    let longest = ((60.0 * frame_rate / min_tempo).floor() as usize).min(n.saturating_sub(2));
+    let prior = |lag: usize| {
+        let octaves = (60.0 * frame_rate / lag as f64 / 120.0).log2();
+        (-0.5 * octaves * octaves).exp()
+    };
+    let best = (shortest.max(1)..=longest)
+        .map(|lag| (lag, autocorrelation(lag)))
+        .filter(|(lag, r)| *r > autocorrelation(lag - 1) && *r >= autocorrelation(lag + 1))
+        .map(|(lag, r)| (lag, r * prior(lag)))
+        .fold(None, |best: Option<(usize, f64)>, x| match best {
+            Some(b) if b.1 >= x.1 => Some(b),
+            _ => Some(x),
+        });
+    match best {
+        Some((lag, _)) => {
+            // refine the lag between bins with a parabola through the neighbours
+            let (l, c, r) = (
+                autocorrelation(lag - 1),
+                autocorrelation(lag),
+                autocorrelation(lag + 1),
+            );
+            let curvature = l - 2.0 * c + r;
+            let delta = if curvature != 0.0 {
+                0.5 * (l - r) / curvature
+            } else {
+                0.0
+            };
+            60.0 * frame_rate / (lag as f64 + delta)
+        }
+        None => 0.0,
+    }
+}
+
+/// tonic, scale and strength of the key profile best correlated with a 12 bin HPCP
+fn key(hpcp: &[f64]) -> (&'static str, &'static str, f64) {
+    // HPCP bins start from A
+    let mut best = (notes::PITCH_CLASSES[9], "major", f64::NEG_INFINITY);
+    if hpcp.len() != 12 {
+        return (best.0, best.1, 0.0);
+    }
+    for tonic in 0..12 {
+        let name = notes::PITCH_CLASSES[(tonic + 9) % 12];
+        for (scale, profile) in [("major", &MAJOR), ("minor", &MINOR)] {
+            let rotated: Vec<f64> = (0..12).map(|i| hpcp[(tonic + i) % 12]).collect();
+            let r = correlation(&rotated, profile);
+            if r > best.2 {
+                best = (name, scale, r);
+            }
+        }
+    }
+    best
+}
+
+/// Pearson correlation of two sequences of the same length
+fn correlation(x: &[f64], y: &[f64]) -> f64 {
+    let n = x.len() as f64;
+    let (mx, my) = (x.iter().sum::<f64>() / n, y.iter().sum::<f64>() / n);
+    let pairs = x.iter().zip(y.iter());
+    let cov: f64 = pairs.map(|(a, b)| (a - mx) * (b - my)).sum();
+    let vx: f64 = x.iter().map(|a| (a - mx).powi(2)).sum();
+    let vy: f64 = y.iter().map(|b| (b - my).powi(2)).sum();
+    if vx > 0.0 && vy > 0.0 {
+        cov / (vx * vy).sqrt()
+    } else {
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MusicExtractor;
+    use crate::mixer::Writer;
+    use crate::pool::Value;
+
+    #[test]
+    fn extract() {
+        // an A major chord struck every half a second, so at 120 BPM
+        let sr = 44100.0;
+        let signal: Vec<f64> = (0..4 * 44100)
+            .map(|i| {
+                let t = i as f64 / sr;
+                let decay = (-8.0 * (t % 0.5)).exp();
+                let chord = [220.0, 277.18, 329.63].iter();
+                let tone: f64 = chord
+                    .map(|f| (2.0 * std::f64::consts::PI * f * t).sin())
+                    .sum();
+                0.25 * decay * tone
+            })
+            .collect();
+        let path = std::env::temp_dir().join("muslib-extractor.wav");
+        Writer::new().file(path.clone()).write(&signal).unwrap();
+
+        let pool = MusicExtractor::new().extract(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(pool.value("tonal.key_key"), Some(&Value::from("A")));
+        assert_eq!(pool.value("tonal.key_scale"), Some(&Value::from("major")));
+        match pool.value("rhythm.bpm") {
+            Some(Value::Real(bpm)) => assert!((bpm - 120.0).abs() < 3.0, "{}", bpm),
+            x => panic!("{:?}", x),
+        }
+        match pool.value("rhythm.onset_rate") {
+            Some(Value::Real(rate)) => assert!((rate - 2.0).abs() < 0.5, "{}", rate),
+            x => panic!("{:?}", x),
+        }
+        match pool.value("metadata.audio_properties.length") {
+            Some(Value::Real(length)) => assert!((length - 4.0).abs() < 1e-9),
+            x => panic!("{:?}", x),
+        }
+        assert!(pool.contains("lowlevel.spectral_centroid.mean"));
+        assert!(matches!(pool.value("tonal.hpcp.mean"), Some(Value::Vector(x)) if x.len() == 12));
+    }
+}
@@ -0,0 +1,808 @@
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+use crate::algs::{
+    denoise, dynamics, filters, hpcp, io, problems, resample, restore, stereo, stft, stretch,
+    synth, Algorithm,
+};
+use crate::json::{FromJson, Json, ToJson};
+use crate::{Error, Result};
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Algorithm created by name and computed with flat arrays or JSON values, whatever its own types are
+trait Dynamic {
+    /// replace the parameters, the ones missing from the object are set to their defaults
+    fn configure(&mut self, params: &Json) -> Result<()>;
+    /// compute the Algorithm for its Input read from JSON
+    fn compute(&mut self, input: &Json) -> Result<Json>;
+    /// compute the Algorithm for its Input read from flat arrays, returning its Output as flat arrays
+    fn compute_arrays(&mut self, inputs: &[&[f64]]) -> Result<Vec<Vec<f64>>>;
+    /// number of arrays returned by .compute_arrays()
+    fn outputs(&self) -> usize;
+}
+
+impl<A> Dynamic for A
+where
+    A: Algorithm,
+    A::Params: FromJson,
+    A::Input: FromJson + FromArrays,
+    A::Output: ToJson + IntoArrays,
+{
+    fn configure(&mut self, params: &Json) -> Result<()> {
+        *self = A::with_params(FromJson::from_json(params)?);
+        Ok(())
+    }
+
+    fn compute(&mut self, input: &Json) -> Result<Json> {
+        let input = A::Input::from_json(input)?;
+        Ok(Algorithm::compute(self, input)?.to_json())
+    }
+
+    fn compute_arrays(&mut self, inputs: &[&[f64]]) -> Result<Vec<Vec<f64>>> {
+        let mut arrays = Arrays(inputs);
+        let input = A::Input::from_arrays(&mut arrays)?;
+        if !arrays.0.is_empty() {
+            return Err(Error::InvalidParam(format!(
+                "expected {} inputs, got {}",
+                inputs.len() - arrays.0.len(),
+                inputs.len()
+            )));
+        }
+        let mut outputs = Vec::with_capacity(A::Output::COUNT);
+        Algorithm::compute(self, input)?.into_arrays(&mut outputs);
+        Ok(outputs)
+    }
+
+    fn outputs(&self) -> usize {
+        A::Output::COUNT
+    }
+}
+
+/// Algorithm taking paths of files, which is computed with JSON only
+struct JsonOnly<A>(A);
+
+impl<A> Dynamic for JsonOnly<A>
+where
+    A: Algorithm,
+    A::Params: FromJson,
+    A::Input: FromJson,
+    A::Output: ToJson,
+{
+    fn configure(&mut self, params: &Json) -> Result<()> {
+        self.0 = A::with_params(FromJson::from_json(params)?);
+        Ok(())
+    }
+
+    fn compute(&mut self, input: &Json) -> Result<Json> {
+        let input = A::Input::from_json(input)?;
+        Ok(self.0.compute(input)?.to_json())
+    }
+
+    fn compute_arrays(&mut self, _: &[&[f64]]) -> Result<Vec<Vec<f64>>> {
+        Err(Error::InvalidParam(
+            "this algorithm takes a path, use muslib_compute_json()".into(),
+        ))
+    }
+
+    fn outputs(&self) -> usize {
+        0
+    }
+}
+
+/// flat arrays passed to muslib_compute() not read as an Input yet
+struct Arrays<'a>(&'a [&'a [f64]]);
+
+impl<'a> Arrays<'a> {
+    /// take the next array, if there is one
+    fn next(&mut self) -> Option<&'a [f64]> {
+        let (first, rest) = self.0.split_first()?;
+        self.0 = rest;
+        Some(first)
+    }
+
+    /// take the next array of a required input
+    fn required(&mut self) -> Result<&'a [f64]> {
+        self.next()
+            .ok_or_else(|| Error::InvalidParam("too few inputs".into()))
+    }
+}
+
+/// Input of an Algorithm read straight from flat arrays, without going through JSON
+trait FromArrays: Sized {
+    /// take the arrays of this Input from the front of the arrays left
+    fn from_arrays(arrays: &mut Arrays) -> Result<Self>;
+}
+
+/// a signal
+impl FromArrays for Vec<f64> {
+    fn from_arrays(arrays: &mut Arrays) -> Result<Self> {
+        Ok(arrays.required()?.to_vec())
+    }
+}
+
+/// complex numbers with interleaved real and imaginary parts
+impl FromArrays for Vec<(f64, f64)> {
+    fn from_arrays(arrays: &mut Arrays) -> Result<Self> {
+        let array = arrays.required()?;
+        if !array.len().is_multiple_of(2) {
+            return Err(Error::InvalidParam(
+                "expected interleaved real and imaginary parts, got an odd number of values".into(),
+            ));
+        }
+        Ok(array.chunks_exact(2).map(|x| (x[0], x[1])).collect())
+    }
+}
+
+/// every array left, like the channels of a track
+impl FromArrays for Vec<Vec<f64>> {
+    fn from_arrays(arrays: &mut Arrays) -> Result<Self> {
+        Ok(std::iter::from_fn(|| arrays.next())
+            .map(|x| x.to_vec())
+            .collect())
+    }
+}
+
+/// an optional trailing input, like a sidechain
+impl FromArrays for Option<Vec<f64>> {
+    fn from_arrays(arrays: &mut Arrays) -> Result<Self> {
+        Ok(arrays.next().map(|x| x.to_vec()))
+    }
+}
+
+impl<A: FromArrays, B: FromArrays> FromArrays for (A, B) {
+    fn from_arrays(arrays: &mut Arrays) -> Result<Self> {
+        Ok((A::from_arrays(arrays)?, B::from_arrays(arrays)?))
+    }
+}
+
+/// Output of an Algorithm written straight to flat arrays, without going through JSON
+trait IntoArrays {
+    /// number of arrays written
+    const COUNT: usize;
+    /// append the arrays of this Output
+    fn into_arrays(self, out: &mut Vec<Vec<f64>>);
+}
+
+/// signals of any sample type converted to f64
+macro_rules! signal_into_arrays {
+    ($($t:ty),*) => {
+        $(
+            impl IntoArrays for Vec<$t> {
+                const COUNT: usize = 1;
+                fn into_arrays(self, out: &mut Vec<Vec<f64>>) {
+                    out.push(self.into_iter().map(|x| x as f64).collect());
+                }
+            }
+        )*
+    };
+}
+
+signal_into_arrays!(f64, f32, i16);
+
+/// pairs, like complex numbers or regions, interleaved
+macro_rules! pairs_into_arrays {
+    ($($t:ty),*) => {
+        $(
+            impl IntoArrays for Vec<($t, $t)> {
+                const COUNT: usize = 1;
+                fn into_arrays(self, out: &mut Vec<Vec<f64>>) {
+                    out.push(self.into_iter().flat_map(|(a, b)| [a as f64, b as f64]).collect());
+                }
+            }
+        )*
+    };
+}
+
+pairs_into_arrays!(f64, f32, usize);
+
+/// events of every channel as (channel, start, end) triples
+impl IntoArrays for Vec<Vec<(f64, f64)>> {
+    const COUNT: usize = 1;
+    fn into_arrays(self, out: &mut Vec<Vec<f64>>) {
+        let triples = self.into_iter().enumerate().flat_map(|(i, events)| {
+            events
+                .into_iter()
+                .flat_map(move |(start, end)| [i as f64, start, end])
+        });
+        out.push(triples.collect());
+    }
+}
+
+/// a single position, NaN if there is none
+impl IntoArrays for Option<usize> {
+    const COUNT: usize = 1;
+    fn into_arrays(self, out: &mut Vec<Vec<f64>>) {
+        out.push(vec![self.map_or(f64::NAN, |x| x as f64)]);
+    }
+}
+
+impl<A: IntoArrays, B: IntoArrays> IntoArrays for (A, B) {
+    const COUNT: usize = A::COUNT + B::COUNT;
+    fn into_arrays(self, out: &mut Vec<Vec<f64>>) {
+        self.0.into_arrays(out);
+        self.1.into_arrays(out);
+    }
+}
+
+/// Algorithm that can be created through the C API
+struct Entry {
+    name: &'static str,
+    create: fn() -> Box<dyn Dynamic>,
+}
+
+fn create<A>() -> Box<dyn Dynamic>
+where
+    A: Algorithm + 'static,
+    A::Params: FromJson,
+    A::Input: FromJson + FromArrays,
+    A::Output: ToJson + IntoArrays,
+{
+    Box::new(A::new())
+}
+
+fn create_json<A>() -> Box<dyn Dynamic>
+where
+    A: Algorithm + 'static,
+    A::Params: FromJson,
+    A::Input: FromJson,
+    A::Output: ToJson,
+{
+    Box::new(JsonOnly(A::new()))
+}
+
+/// every Algorithm of the default Registry, by name
+const ALGORITHMS: &[Entry] = &[
+    Entry {
+        name: "ClickDetector",
+        create: create::<problems::ClickDetector>,
+    },
+    Entry {
+        name: "Compressor",
+        create: create::<dynamics::Compressor>,
+    },
+    Entry {
+        name: "Declicker",
+        create: create::<restore::Declicker>,
+    },
+    Entry {
+        name: "Declipper",
+        create: create::<restore::Declipper>,
+    },
+    Entry {
+        name: "Denoiser",
+        create: create::<denoise::Denoiser>,
+    },
+    Entry {
+        name: "FFT",
+        create: create::<stft::FFT>,
+    },
+    Entry {
+        name: "GapDetector",
+        create: create::<problems::GapDetector>,
+    },
+    Entry {
+        name: "Gate",
+        create: create::<dynamics::Gate>,
+    },
+    Entry {
+        name: "HPCP",
+        create: create::<hpcp::HPCP>,
+    },
+    Entry {
+        name: "HumDetector",
+        create: create::<problems::HumDetector>,
+    },
+    Entry {
+        name: "IFFT",
+        create: create::<stft::IFFT>,
+    },
+    Entry {
+        name: "MedianFilter",
+        create: create::<filters::MedianFilter>,
+    },
+    Entry {
+        name: "MidSide",
+        create: create::<stereo::MidSide>,
+    },
+    Entry {
+        name: "MonoFloatLoader",
+        create: create_json::<io::MonoFloatLoader>,
+    },
+    Entry {
+        name: "MonoLoader",
+        create: create_json::<io::MonoLoader>,
+    },
+    Entry {
+        name: "MonoWriter",
+        create: create_json::<io::MonoWriter>,
+    },
+    Entry {
+        name: "MovingAverage",
+        create: create::<filters::MovingAverage>,
+    },
+    Entry {
+        name: "Resample",
+        create: create::<resample::Resample>,
+    },
+    Entry {
+        name: "SaturationDetector",
+        create: create::<problems::SaturationDetector>,
+    },
+    Entry {
+        name: "StartStopSilence",
+        create: create::<problems::StartStopSilence>,
+    },
+    Entry {
+        name: "StereoWidener",
+        create: create::<stereo::StereoWidener>,
+    },
+    Entry {
+        name: "Synthesizer",
+        create: create::<synth::Synthesizer>,
+    },
+    Entry {
+        name: "TruePeakDetector",
+        create: create::<problems::TruePeakDetector>,
+    },
+    Entry {
+        name: "WSOLA",
+        create: create::<stretch::WSOLA>,
+    },
+];
+
+/// Opaque handle of an Algorithm, created with muslib_create() and released with muslib_free()
+pub struct MuslibAlgorithm {
+    algorithm: Box<dyn Dynamic>,
+    name: &'static str,
+}
+
+/// Flat array of samples passed to and returned from muslib_compute()
+///
+/// Arrays returned by muslib are owned by the caller and released with muslib_free_array().
+#[repr(C)]
+pub struct MuslibArray {
+    /// pointer to the first value, may be null when the array is empty
+    pub data: *mut f64,
+    /// number of values
+    pub len: usize,
+}
+
+impl MuslibArray {
+    fn from_vec(data: Vec<f64>) -> Self {
+        let len = data.len();
+        let data = Box::into_raw(data.into_boxed_slice()) as *mut f64;
+        MuslibArray { data, len }
+    }
+
+    /// values of an array passed in by the caller
+    unsafe fn as_slice(&self) -> &[f64] {
+        if self.data.is_null() || self.len == 0 {
+            &[]
+        } else {
+            std::slice::from_raw_parts(self.data, self.len)
+        }
+    }
+}
+
+/// run a call of the C API, keeping its error or panic for muslib_last_error()
+fn guard<T>(fallback: T, f: impl FnOnce() -> Result<T>) -> T {
+    let result = panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|panic| {
+        let msg = panic
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| panic.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic".into());
+        Err(Error::InvalidParam(format!("algorithm panicked: {}", msg)))
+    });
+    match result {
+        Ok(value) => value,
+        Err(err) => {
+            // error messages have no NUL bytes, except ones quoted from the input
+            let msg = CString::new(err.to_string().replace('\0', "\\0")).unwrap_or_default();
+            LAST_ERROR.with(|last| *last.borrow_mut() = Some(msg));
+            fallback
+        }
+    }
+}
+
+/// read a NUL-terminated UTF-8 string passed in by the caller
+unsafe fn read_str<'a>(s: *const c_char, what: &str) -> Result<&'a str> {
+    if s.is_null() {
+        return Err(Error::InvalidParam(format!("{} is null", what)));
+    }
+    CStr::from_ptr(s)
+        .to_str()
+        .map_err(|_| Error::InvalidParam(format!("{} is not valid UTF-8", what)))
+}
+
+/// read parameters as a JSON object, null stands for an empty one
+unsafe fn read_params(params: *const c_char) -> Result<Json> {
+    if params.is_null() {
+        return Ok(Json::object([]));
+    }
+    Json::parse(read_str(params, "params")?)
+}
+
+unsafe fn handle<'a>(algorithm: *mut MuslibAlgorithm) -> Result<&'a mut MuslibAlgorithm> {
+    algorithm
+        .as_mut()
+        .ok_or_else(|| Error::InvalidParam("algorithm is null".into()))
+}
+
+/// Create an Algorithm by its name, e.g. "HPCP", configured with parameters given as a JSON object.
+///
+/// Every Algorithm of this crate is available under the name of its type.
+/// Parameters missing from the object, or all of them when `params` is null, keep their defaults.
+/// Returns null on error, see muslib_last_error().
+///
+/// # Safety
+///
+/// `name` and `params` must be null or point to NUL-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn muslib_create(
+    name: *const c_char,
+    params: *const c_char,
+) -> *mut MuslibAlgorithm {
+    guard(ptr::null_mut(), || {
+        let name = read_str(name, "name")?;
+        let entry = ALGORITHMS
+            .iter()
+            .find(|entry| entry.name == name)
+            .ok_or_else(|| Error::InvalidParam(format!("unknown algorithm {}", name)))?;
+        let mut algorithm = (entry.create)();
+        algorithm.configure(&read_params(params)?)?;
+        Ok(Box::into_raw(Box::new(MuslibAlgorithm {
+            algorithm,
+            name: entry.name,
+        })))
+    })
+}
+
+/// Set new parameters of an Algorithm given as a JSON object, clearing its state.
+///
+/// Parameters missing from the object are set to their defaults.
+/// Returns 0 on success and -1 on error, see muslib_last_error().
+///
+/// # Safety
+///
+/// `algorithm` must be null or a handle returned by muslib_create(),
+/// and `params` must be null or point to a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn muslib_configure(
+    algorithm: *mut MuslibAlgorithm,
+    params: *const c_char,
+) -> i32 {
+    guard(-1, || {
+        let params = read_params(params)?;
+        handle(algorithm)?.algorithm.configure(&params)?;
+        Ok(0)
+    })
+}
+
+/// Compute an Algorithm for flat arrays of samples and write its outputs.
+///
+/// Inputs are passed as separate arrays, e.g. the left and right channel to MidSide,
+/// and trailing optional ones like a sidechain can be left out.
+/// TruePeakDetector takes one array for each channel.
+/// FFT outputs and IFFT inputs interleave real and imaginary parts, regions found by
+/// Declicker and Declipper and events found by the problem detectors interleave their start and end,
+/// and overs found by TruePeakDetector are (channel, start, end) triples.
+/// A position missing from the output of StartStopSilence is NaN.
+/// MonoLoader, MonoFloatLoader and MonoWriter take paths and are computed with muslib_compute_json().
+/// Writes the outputs to `outputs` and returns how many were written, or -1 on error,
+/// see muslib_last_error(). Each written array has to be released with muslib_free_array().
+///
+/// # Safety
+///
+/// `algorithm` must be null or a handle returned by muslib_create(),
+/// `inputs` must point to `count` arrays, each pointing to `len` values,
+/// and `outputs` must point to room for `capacity` arrays.
+#[no_mangle]
+pub unsafe extern "C" fn muslib_compute(
+    algorithm: *mut MuslibAlgorithm,
+    inputs: *const MuslibArray,
+    count: usize,
+    outputs: *mut MuslibArray,
+    capacity: usize,
+) -> isize {
+    guard(-1, || {
+        let algorithm = handle(algorithm)?;
+        let count_outputs = algorithm.algorithm.outputs();
+        if count_outputs > 0 && (outputs.is_null() || capacity < count_outputs) {
+            return Err(Error::InvalidParam(format!(
+                "{} returns {} outputs, got room for {}",
+                algorithm.name, count_outputs, capacity
+            )));
+        }
+        let inputs: Vec<&[f64]> = if inputs.is_null() {
+            Vec::new()
+        } else {
+            std::slice::from_raw_parts(inputs, count)
+                .iter()
+                .map(|x| x.as_slice())
+                .collect()
+        };
+        let arrays = algorithm.algorithm.compute_arrays(&inputs)?;
+        let n = arrays.len();
+        for (i, array) in arrays.into_iter().enumerate() {
+            outputs.add(i).write(MuslibArray::from_vec(array));
+        }
+        Ok(n as isize)
+    })
+}
+
+/// Compute an Algorithm for its input given as JSON and return its output as JSON.
+///
+/// The input has the same shape as in Python, e.g. `[[440.0], [1.0]]` for a Synthesizer.
+/// Returns null on error, see muslib_last_error().
+/// The returned string has to be released with muslib_free_string().
+///
+/// # Safety
+///
+/// `algorithm` must be null or a handle returned by muslib_create(),
+/// and `input` must be null or point to a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn muslib_compute_json(
+    algorithm: *mut MuslibAlgorithm,
+    input: *const c_char,
+) -> *mut c_char {
+    guard(ptr::null_mut(), || {
+        let input = Json::parse(read_str(input, "input")?)?;
+        let output = handle(algorithm)?.algorithm.compute(&input)?;
+        // JSON strings escape control characters, so there are no NUL bytes in the output
+        Ok(CString::new(output.to_string())
+            .unwrap_or_default()
+            .into_raw())
+    })
+}
+
+/// Message of the last error on this thread, or null if there was none.
+///
+/// The message stays valid until the next failing call on the same thread.
+#[no_mangle]
+pub extern "C" fn muslib_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .as_ref()
+            .map_or(ptr::null(), |msg| msg.as_ptr())
+    })
+}
+
+/// Release an Algorithm created with muslib_create().
+///
+/// # Safety
+///
+/// `algorithm` must be null or a handle returned by muslib_create() that was not released yet.
+#[no_mangle]
+pub unsafe extern "C" fn muslib_free(algorithm: *mut MuslibAlgorithm) {
+    if !algorithm.is_null() {
+        drop(Box::from_raw(algorithm));
+    }
+}
+
+/// Release an array written by muslib_compute().
+///
+/// # Safety
+///
+/// `array` must be written by muslib_compute() and not released yet.
+#[no_mangle]
+pub unsafe extern "C" fn muslib_free_array(array: MuslibArray) {
+    if !array.data.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(
+            array.data, array.len,
+        )));
+    }
+}
+
+/// Release a string returned by muslib_compute_json().
+///
+/// # Safety
+///
+/// `s` must be null or a string returned by muslib_compute_json() that was not released yet.
+#[no_mangle]
+pub unsafe extern "C" fn muslib_free_string(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::CStr;
+    use std::ptr;
+
+    use super::{
+        muslib_compute, muslib_compute_json, muslib_configure, muslib_create, muslib_free,
+        muslib_free_array, muslib_free_string, muslib_last_error, MuslibArray, ALGORITHMS,
+    };
+    use crate::registry::Registry;
+
+    fn last_error() -> String {
+        unsafe { CStr::from_ptr(muslib_last_error()) }
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    #[test]
+    fn c_api() {
+        unsafe {
+            assert!(muslib_create(c"Unknown".as_ptr(), ptr::null()).is_null());
+            assert!(last_error().contains("unknown algorithm Unknown"));
+
+            // fft data of a frame comes back as interleaved real and imaginary parts
+            let fft = muslib_create(c"FFT".as_ptr(), ptr::null());
+            assert!(!fft.is_null());
+            let mut frame = vec![1.0; 8];
+            let input = MuslibArray {
+                data: frame.as_mut_ptr(),
+                len: frame.len(),
+            };
+            let mut spectrum = MuslibArray {
+                data: ptr::null_mut(),
+                len: 0,
+            };
+            assert_eq!(muslib_compute(fft, &input, 1, &mut spectrum, 1), 1);
+            assert_eq!(spectrum.as_slice().len(), 16);
+            assert_eq!(spectrum.as_slice()[..3], [8.0, 0.0, 0.0]);
+            muslib_free_array(spectrum);
+            frame.pop();
+            let input = MuslibArray {
+                data: frame.as_mut_ptr(),
+                len: frame.len(),
+            };
+            let mut spectrum = MuslibArray {
+                data: ptr::null_mut(),
+                len: 0,
+            };
+            assert_eq!(muslib_compute(fft, &input, 1, &mut spectrum, 1), -1);
+            assert!(last_error().contains("power of two"));
+            muslib_free(fft);
+
+            // two inputs and two outputs, and room for too few outputs
+            let ms = muslib_create(c"MidSide".as_ptr(), c"{}".as_ptr());
+            let (mut left, mut right) = (vec![1.0, 0.5], vec![0.0, 0.5]);
+            let inputs = [
+                MuslibArray {
+                    data: left.as_mut_ptr(),
+                    len: 2,
+                },
+                MuslibArray {
+                    data: right.as_mut_ptr(),
+                    len: 2,
+                },
+            ];
+            let mut outputs = [
+                MuslibArray {
+                    data: ptr::null_mut(),
+                    len: 0,
+                },
+                MuslibArray {
+                    data: ptr::null_mut(),
+                    len: 0,
+                },
+            ];
+            assert_eq!(
+                muslib_compute(ms, inputs.as_ptr(), 2, outputs.as_mut_ptr(), 1),
+                -1
+            );
+            assert!(last_error().contains("room for 1"));
+            assert_eq!(
+                muslib_compute(ms, inputs.as_ptr(), 2, outputs.as_mut_ptr(), 2),
+                2
+            );
+            assert_eq!(outputs[0].as_slice(), &[0.5, 0.5]);
+            assert_eq!(outputs[1].as_slice(), &[0.5, 0.0]);
+            for array in outputs {
+                muslib_free_array(array);
+            }
+            muslib_free(ms);
+
+            // parameters are configured and results read as JSON
+            let ma = muslib_create(c"MovingAverage".as_ptr(), c"{\"window_size\": 3}".as_ptr());
+            let json = muslib_compute_json(ma, c"[3.0, 3.0, 3.0, 3.0]".as_ptr());
+            assert_eq!(CStr::from_ptr(json).to_str().unwrap(), "[3,3,3,3]");
+            muslib_free_string(json);
+            assert_eq!(muslib_configure(ma, c"{\"size\": 3}".as_ptr()), -1);
+            assert!(last_error().contains("size"));
+            assert_eq!(muslib_configure(ma, c"{\"window_size\": 1}".as_ptr()), 0);
+            assert!(muslib_compute_json(ma, c"[\"a\"]".as_ptr()).is_null());
+            muslib_free(ma);
+        }
+    }
+
+    fn array(data: &mut [f64]) -> MuslibArray {
+        MuslibArray {
+            data: data.as_mut_ptr(),
+            len: data.len(),
+        }
+    }
+
+    fn empty() -> MuslibArray {
+        MuslibArray {
+            data: ptr::null_mut(),
+            len: 0,
+        }
+    }
+
+    #[test]
+    fn algorithms() {
+        let names: Vec<&str> = ALGORITHMS.iter().map(|entry| entry.name).collect();
+        assert_eq!(names, Registry::default().names());
+
+        unsafe {
+            // arrays are read as the typed input of every algorithm, without JSON
+            let mut signal = vec![0.0; 64];
+            signal[32] = 0.5;
+            let resample = muslib_create(
+                c"Resample".as_ptr(),
+                c"{\"input_sample_rate\": 44100, \"output_sample_rate\": 22050}".as_ptr(),
+            );
+            let mut output = empty();
+            assert_eq!(
+                muslib_compute(resample, &array(&mut signal), 1, &mut output, 1),
+                1
+            );
+            assert_eq!(output.as_slice().len(), 32);
+            muslib_free_array(output);
+            let mut output = empty();
+            let inputs = [array(&mut signal), array(&mut signal.clone())];
+            assert_eq!(
+                muslib_compute(resample, inputs.as_ptr(), 2, &mut output, 1),
+                -1
+            );
+            assert!(last_error().contains("expected 1 inputs, got 2"));
+            assert_eq!(muslib_compute(resample, ptr::null(), 0, &mut output, 1), -1);
+            assert!(last_error().contains("too few inputs"));
+            muslib_free(resample);
+
+            // a silent signal has neither a start nor a stop
+            let silence = muslib_create(c"StartStopSilence".as_ptr(), ptr::null());
+            let mut outputs = [empty(), empty()];
+            let mut quiet = vec![0.0; 64];
+            assert_eq!(
+                muslib_compute(silence, &array(&mut quiet), 1, outputs.as_mut_ptr(), 2),
+                2
+            );
+            assert!(outputs.iter().all(|x| x.as_slice()[0].is_nan()));
+            for array in outputs {
+                muslib_free_array(array);
+            }
+            muslib_free(silence);
+
+            // every array is a channel, and overs come back as (channel, start, end) triples
+            let peaks = muslib_create(c"TruePeakDetector".as_ptr(), ptr::null());
+            let mut loud = vec![0.0; 64];
+            loud[10] = 1.5;
+            let inputs = [array(&mut quiet), array(&mut loud)];
+            let mut outputs = [empty(), empty()];
+            assert_eq!(
+                muslib_compute(peaks, inputs.as_ptr(), 2, outputs.as_mut_ptr(), 2),
+                2
+            );
+            assert_eq!(outputs[0].as_slice().len(), 2);
+            let overs = outputs[1].as_slice();
+            assert!(!overs.is_empty() && overs.len().is_multiple_of(3));
+            assert!(overs.chunks(3).all(|x| x[0] == 1.0 && x[1] <= x[2]));
+            for array in outputs {
+                muslib_free_array(array);
+            }
+            muslib_free(peaks);
+
+            // loaders and writers take paths, so they are computed with JSON only
+            let loader = muslib_create(c"MonoLoader".as_ptr(), ptr::null());
+            assert!(!loader.is_null());
+            assert_eq!(
+                muslib_compute(loader, ptr::null(), 0, ptr::null_mut(), 0),
+                -1
+            );
+            assert!(last_error().contains("muslib_compute_json"));
+            assert!(muslib_compute_json(loader, c"\"missing.wav\"".as_ptr()).is_null());
+            muslib_free(loader);
+        }
+    }
+}
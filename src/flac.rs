@@ -0,0 +1,418 @@
+use std::fs::File;
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use crate::{Error, Result};
+
+/// number of samples of each channel in a frame
+const BLOCK_SIZE: usize = 4096;
+
+/// largest order of the fixed polynomial predictors
+const MAX_ORDER: usize = 4;
+
+/// largest Rice parameter, the next one is reserved for escape codes
+const MAX_RICE: u32 = 14;
+
+/// FlacWriter encodes interleaved integer samples to a FLAC file, frame by frame.
+///
+/// Each channel of a frame is compressed with the best fixed polynomial predictor of FLAC
+/// and Rice coding of its residual, or stored verbatim if that's shorter.
+/// The STREAMINFO block is updated with the number of samples when the stream is finished,
+/// while its MD5 signature is left unset.
+pub struct FlacWriter {
+    file: BufWriter<File>,
+    channels: usize,
+    bits: u32,
+    sample_rate: u32,
+    block: Vec<i32>,
+    frames: u64,
+    samples: u64,
+    frame_sizes: Option<(usize, usize)>,
+    finished: bool,
+}
+
+impl FlacWriter {
+    /// create a FLAC file for 1 to 8 channels of 8, 16 or 24-bit samples
+    ///
+    /// Fails with Error::InvalidParam for other channels, sample sizes or sample rates.
+    pub fn create(path: &Path, channels: u16, sample_rate: u32, bits: u16) -> Result<Self> {
+        if !(1..=8).contains(&channels)
+            || ![8, 16, 24].contains(&bits)
+            || !(1..=655350).contains(&sample_rate)
+        {
+            return Err(Error::InvalidParam(format!(
+                "FLAC can't store {} channels of {}-bit samples at {} Hz",
+                channels, bits, sample_rate
+            )));
+        }
+        let mut writer = FlacWriter {
+            file: BufWriter::new(File::create(path)?),
+            channels: channels as usize,
+            bits: bits as u32,
+            sample_rate,
+            block: Vec::with_capacity(BLOCK_SIZE * channels as usize),
+            frames: 0,
+            samples: 0,
+            frame_sizes: None,
+            finished: false,
+        };
+        writer.file.write_all(b"fLaC")?;
+        let streaminfo = writer.streaminfo();
+        writer.file.write_all(&streaminfo)?;
+        Ok(writer)
+    }
+
+    /// append interleaved samples of all channels, as integers of the sample size
+    pub fn write(&mut self, samples: &[i32]) -> Result<()> {
+        for x in samples {
+            self.block.push(*x);
+            if self.block.len() == BLOCK_SIZE * self.channels {
+                self.frame()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// number of bits of each sample
+    pub fn bits_per_sample(&self) -> u16 {
+        self.bits as u16
+    }
+
+    /// number of samples of all channels written so far
+    pub fn len(&self) -> u64 {
+        self.samples * self.channels as u64 + self.block.len() as u64
+    }
+
+    /// check if no samples were written yet
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// update the STREAMINFO block and flush the file, so the frames encoded so far can be read
+    ///
+    /// Samples of a frame that isn't complete yet are kept until the next frame or the end.
+    pub fn flush(&mut self) -> Result<()> {
+        let streaminfo = self.streaminfo();
+        self.file.seek(SeekFrom::Start(4))?;
+        self.file.write_all(&streaminfo)?;
+        self.file.seek(SeekFrom::End(0))?;
+        self.file.flush()?;
+        Ok(())
+    }
+
+    /// encode the last frame, update the STREAMINFO block and flush the file
+    ///
+    /// Fails with Error::InvalidParam if the samples don't split evenly into the channels.
+    pub fn finalize(mut self) -> Result<()> {
+        self.finish()
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        if self.finished {
+            return Ok(());
+        }
+        self.finished = true;
+        if !self.block.len().is_multiple_of(self.channels) {
+            return Err(Error::InvalidParam(format!(
+                "the last frame has samples missing in some of the {} channels",
+                self.channels
+            )));
+        }
+        if !self.block.is_empty() {
+            self.frame()?;
+        }
+        let streaminfo = self.streaminfo();
+        self.file.seek(SeekFrom::Start(4))?;
+        self.file.write_all(&streaminfo)?;
+        self.file.flush()?;
+        Ok(())
+    }
+
+    /// STREAMINFO metadata block with its header
+    fn streaminfo(&self) -> Vec<u8> {
+        let (min_frame, max_frame) = self.frame_sizes.unwrap_or((0, 0));
+        let mut w = BitWriter::default();
+        // last metadata block, of type 0 and 34 bytes long
+        w.write(1, 1);
+        w.write(0, 7);
+        w.write(34, 24);
+        w.write(BLOCK_SIZE as u64, 16);
+        w.write(BLOCK_SIZE as u64, 16);
+        w.write(min_frame as u64, 24);
+        w.write(max_frame as u64, 24);
+        w.write(self.sample_rate as u64, 20);
+        w.write(self.channels as u64 - 1, 3);
+        w.write(self.bits as u64 - 1, 5);
+        w.write(self.samples, 36);
+        // unset MD5 signature
+        w.write(0, 64);
+        w.write(0, 64);
+        w.bytes
+    }
+
+    /// encode the samples of the current block as a frame
+    fn frame(&mut self) -> Result<()> {
+        let n = self.block.len() / self.channels;
+        let mut w = BitWriter::default();
+        // sync code, fixed blocking strategy, block size in 16 bits at the end of the header,
+        // sample rate from STREAMINFO and independent channels
+        w.write(0b11111111111110, 14);
+        w.write(0, 2);
+        w.write(0b0111, 4);
+        w.write(0, 4);
+        w.write(self.channels as u64 - 1, 4);
+        w.write(
+            match self.bits {
+                8 => 0b001,
+                16 => 0b100,
+                _ => 0b110,
+            },
+            3,
+        );
+        w.write(0, 1);
+        for byte in utf8(self.frames) {
+            w.write(byte as u64, 8);
+        }
+        w.write(n as u64 - 1, 16);
+        let crc = crc8(&w.bytes);
+        w.write(crc as u64, 8);
+
+        for c in 0..self.channels {
+            let x: Vec<i64> = self.block[c..]
+                .iter()
+                .step_by(self.channels)
+                .map(|x| *x as i64)
+                .collect();
+            subframe(&mut w, &x, self.bits);
+        }
+        w.align();
+        let crc = crc16(&w.bytes);
+        w.write(crc as u64, 16);
+
+        self.file.write_all(&w.bytes)?;
+        let size = w.bytes.len();
+        self.frame_sizes = Some(match self.frame_sizes {
+            Some((min, max)) => (min.min(size), max.max(size)),
+            None => (size, size),
+        });
+        self.frames += 1;
+        self.samples += n as u64;
+        self.block.clear();
+        Ok(())
+    }
+}
+
+impl Drop for FlacWriter {
+    fn drop(&mut self) {
+        let _ = self.finish();
+    }
+}
+
+/// writer of values of any number of bits, most significant bits first
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    current: u8,
+    used: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, value: u64, bits: u32) {
+        for i in (0..bits).rev() {
+            self.current = (self.current << 1) | ((value >> i) & 1) as u8;
+            self.used += 1;
+            if self.used == 8 {
+                self.bytes.push(self.current);
+                self.current = 0;
+                self.used = 0;
+            }
+        }
+    }
+
+    /// write a run of zeros of any length, as in unary codes
+    fn zeros(&mut self, count: u64) {
+        for _ in 0..count {
+            self.write(0, 1);
+        }
+    }
+
+    /// write a signed value in two's complement
+    fn write_signed(&mut self, value: i64, bits: u32) {
+        self.write(value as u64 & ((1 << bits) - 1), bits);
+    }
+
+    /// pad the last byte with zeros
+    fn align(&mut self) {
+        if self.used > 0 {
+            self.write(0, 8 - self.used);
+        }
+    }
+}
+
+/// encode a subframe of a channel with the shortest of its constant, fixed or verbatim forms
+fn subframe(w: &mut BitWriter, x: &[i64], bits: u32) {
+    if x.iter().all(|v| *v == x[0]) {
+        w.write(0b0000000, 7);
+        w.write(0, 1);
+        w.write_signed(x[0], bits);
+        return;
+    }
+
+    // residuals of fixed predictors are differences of consecutive samples, taken order times
+    let mut best: Option<(usize, u32, u64)> = None;
+    let mut residual = x.to_vec();
+    let mut residuals = Vec::with_capacity(MAX_ORDER + 1);
+    for order in 0..=MAX_ORDER.min(x.len() - 1) {
+        if order > 0 {
+            residual = residual.windows(2).map(|d| d[1] - d[0]).collect();
+        }
+        let (k, cost) = rice(&residual);
+        let cost = cost + (order as u64) * bits as u64;
+        if best.is_none_or(|b| cost < b.2) {
+            best = Some((order, k, cost));
+        }
+        residuals.push(residual.clone());
+    }
+
+    match best {
+        Some((order, k, cost)) if cost < x.len() as u64 * bits as u64 => {
+            w.write(0, 1);
+            w.write(0b001000 | order as u64, 6);
+            w.write(0, 1);
+            for v in &x[..order] {
+                w.write_signed(*v, bits);
+            }
+            // Rice coding with a 4-bit parameter and a single partition
+            w.write(0, 2);
+            w.write(0, 4);
+            w.write(k as u64, 4);
+            for r in &residuals[order] {
+                let u = zigzag(*r);
+                w.zeros(u >> k);
+                w.write(1, 1);
+                w.write(u & ((1 << k) - 1), k);
+            }
+        }
+        _ => {
+            w.write(0, 1);
+            w.write(0b000001, 6);
+            w.write(0, 1);
+            for v in x {
+                w.write_signed(*v, bits);
+            }
+        }
+    }
+}
+
+/// best Rice parameter for a residual and the number of bits it takes with it
+fn rice(residual: &[i64]) -> (u32, u64) {
+    let cost = |k: u32| -> u64 {
+        residual
+            .iter()
+            .map(|r| (zigzag(*r) >> k) + 1 + k as u64)
+            .sum()
+    };
+    let header = 2 + 4 + 4;
+    (0..=MAX_RICE)
+        .map(|k| (k, cost(k) + header))
+        .min_by_key(|(_, cost)| *cost)
+        .unwrap_or((0, header))
+}
+
+/// signed value folded into an unsigned one, 0, -1, 1, -2, 2... to 0, 1, 2, 3, 4...
+fn zigzag(x: i64) -> u64 {
+    ((x << 1) ^ (x >> 63)) as u64
+}
+
+/// frame number coded like a UTF-8 character
+fn utf8(value: u64) -> Vec<u8> {
+    if value < 0x80 {
+        return vec![value as u8];
+    }
+    // bits that fit in n bytes: 7 - n in the first one and 6 in each of the others
+    let bits = 64 - value.leading_zeros() as usize;
+    let n = (2..=7).find(|n| 7 - n + 6 * (n - 1) >= bits).unwrap_or(7);
+    let mut bytes = vec![(0xff00u16 >> n) as u8 | (value >> (6 * (n - 1))) as u8];
+    for i in (0..n - 1).rev() {
+        bytes.push(0x80 | ((value >> (6 * i)) & 0x3f) as u8);
+    }
+    bytes
+}
+
+/// CRC-8 of frame headers, with polynomial x^8 + x^2 + x + 1
+fn crc8(bytes: &[u8]) -> u8 {
+    let mut crc = 0u8;
+    for byte in bytes {
+        crc ^= byte;
+        for _ in 0..8 {
+            crc = if crc & 0x80 != 0 {
+                (crc << 1) ^ 0x07
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+/// CRC-16 of frames, with polynomial x^16 + x^15 + x^2 + 1
+fn crc16(bytes: &[u8]) -> u16 {
+    let mut crc = 0u16;
+    for byte in bytes {
+        crc ^= (*byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x8005
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{utf8, FlacWriter};
+    use crate::mixer::Loader;
+
+    #[test]
+    fn flac() {
+        assert_eq!(utf8(0x7f), [0x7f]);
+        assert_eq!(utf8(0x80), [0xc2, 0x80]);
+        assert_eq!(utf8(0x800), [0xe0, 0xa0, 0x80]);
+
+        let path = std::env::temp_dir().join("muslib-flac.flac");
+        // a tone on the left, silence on the right and noise at the end of both
+        let mut data = Vec::new();
+        for i in 0..10000 {
+            let tone = ((i as f64 * 0.05).sin() * 20000.0) as i32;
+            data.push(tone);
+            data.push(0);
+        }
+        for i in 0..1000i32 {
+            data.push(i.wrapping_mul(7919) % 32768);
+            data.push(-(i.wrapping_mul(104729) % 32768));
+        }
+
+        let mut writer = FlacWriter::create(&path, 2, 44100, 16).unwrap();
+        writer.write(&data[..10000]).unwrap();
+        // only the complete frame can be read after flushing
+        writer.flush().unwrap();
+        let mut loader = Loader::<i16>::new();
+        loader.file(path.clone()).interleaved().load().unwrap();
+        assert_eq!(loader.data().len(), 8192);
+        writer.write(&data[10000..]).unwrap();
+        assert_eq!(writer.len(), data.len() as u64);
+        writer.finalize().unwrap();
+        let size = std::fs::metadata(&path).unwrap().len();
+        assert!(size < data.len() as u64 * 2, "{}", size);
+
+        let mut loader = Loader::<i16>::new();
+        loader.file(path.clone()).interleaved().load().unwrap();
+        assert_eq!(loader.sample_rate(), Some(44100));
+        let decoded: Vec<i32> = loader.data().iter().map(|x| *x as i32).collect();
+        assert_eq!(decoded, data);
+        std::fs::remove_file(path).unwrap();
+    }
+}
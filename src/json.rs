@@ -0,0 +1,779 @@
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+#[cfg(feature = "std")]
+use std::{fs, path::Path};
+
+#[cfg(not(feature = "std"))]
+use crate::math::Float;
+use crate::{Error, Result};
+
+/// serde Serialize and Deserialize through Json, and TOML documents
+#[cfg(feature = "serde")]
+mod serialize;
+
+/// JSON value used to read parameters from config files and to write results
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    /// null, also written in place of NaN and infinite numbers
+    Null,
+    /// true or false
+    Bool(bool),
+    /// any number
+    Number(f64),
+    /// a string
+    String(String),
+    /// a list of values
+    Array(Vec<Json>),
+    /// named values with keys sorted alphabetically
+    Object(BTreeMap<String, Json>),
+}
+
+/// conversion of parameters and results to JSON
+pub trait ToJson {
+    /// represent this value as JSON
+    fn to_json(&self) -> Json;
+}
+
+/// conversion of parameters and results from JSON
+pub trait FromJson: Sized {
+    /// read this value from JSON
+    ///
+    /// Parameters of algorithms are read from an object, and the ones missing from it keep their defaults.
+    /// Fails with Error::InvalidParam if the JSON has another type or shape.
+    fn from_json(json: &Json) -> Result<Self>;
+}
+
+impl Json {
+    /// create an object from pairs of keys and values
+    pub fn object<'a, I: IntoIterator<Item = (&'a str, Json)>>(fields: I) -> Self {
+        Json::Object(fields.into_iter().map(|(k, v)| (k.into(), v)).collect())
+    }
+
+    /// named values of an object
+    ///
+    /// Fails with Error::InvalidParam if this is not an object.
+    pub fn fields(&self) -> Result<&BTreeMap<String, Json>> {
+        match self {
+            Json::Object(fields) => Ok(fields),
+            _ => Err(mismatch("an object", self)),
+        }
+    }
+
+    /// parse a JSON document, e.g. the contents of a config file
+    ///
+    /// Fails with Error::InvalidParam pointing at the first malformed byte,
+    /// or at the array or object nested deeper than MAX_DEPTH.
+    pub fn parse(s: &str) -> Result<Self> {
+        let mut parser = Parser {
+            s: s.as_bytes(),
+            i: 0,
+            depth: 0,
+        };
+        let json = parser.value()?;
+        parser.whitespace();
+        if parser.i < s.len() {
+            return Err(parser.error("unexpected data after the end"));
+        }
+        Ok(json)
+    }
+
+    /// read and parse a JSON file, e.g. a config with parameters of algorithms
+    #[cfg(feature = "std")]
+    pub fn read(path: &Path) -> Result<Self> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    /// JSON indented with four spaces, the way Essentia extractors write it
+    pub fn pretty(&self) -> String {
+        let mut out = String::new();
+        self.write_pretty(&mut out, 0);
+        out.push('\n');
+        out
+    }
+
+    /// YAML with nested objects as indented blocks and arrays in the flow style of Essentia extractors
+    pub fn yaml(&self) -> String {
+        match self {
+            Json::Object(fields) if !fields.is_empty() => {
+                let mut out = String::new();
+                Self::yaml_block(fields, &mut out, 0);
+                out
+            }
+            _ => format!("{}\n", self.flow()),
+        }
+    }
+
+    /// write the value as pretty JSON to a file
+    #[cfg(feature = "std")]
+    pub fn write(&self, path: &Path) -> Result<()> {
+        Ok(fs::write(path, self.pretty())?)
+    }
+
+    /// write the value as YAML to a file
+    #[cfg(feature = "std")]
+    pub fn write_yaml(&self, path: &Path) -> Result<()> {
+        Ok(fs::write(path, self.yaml())?)
+    }
+
+    fn write_pretty(&self, out: &mut String, indent: usize) {
+        let pad = " ".repeat(4 * (indent + 1));
+        match self {
+            Json::Array(x) if !x.is_empty() => {
+                out.push_str("[\n");
+                for (i, v) in x.iter().enumerate() {
+                    out.push_str(&pad);
+                    v.write_pretty(out, indent + 1);
+                    out.push_str(if i + 1 < x.len() { ",\n" } else { "\n" });
+                }
+                out.push_str(&pad[4..]);
+                out.push(']');
+            }
+            Json::Object(x) if !x.is_empty() => {
+                out.push_str("{\n");
+                for (i, (k, v)) in x.iter().enumerate() {
+                    out.push_str(&pad);
+                    out.push_str(&format!("{}: ", Json::String(k.clone())));
+                    v.write_pretty(out, indent + 1);
+                    out.push_str(if i + 1 < x.len() { ",\n" } else { "\n" });
+                }
+                out.push_str(&pad[4..]);
+                out.push('}');
+            }
+            _ => out.push_str(&self.to_string()),
+        }
+    }
+
+    fn yaml_block(fields: &BTreeMap<String, Json>, out: &mut String, indent: usize) {
+        for (k, v) in fields {
+            out.push_str(&" ".repeat(4 * indent));
+            let plain = !k.is_empty() && k.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+            match plain {
+                true => out.push_str(k),
+                false => out.push_str(&Json::String(k.clone()).to_string()),
+            }
+            match v {
+                Json::Object(x) if !x.is_empty() => {
+                    out.push_str(":\n");
+                    Self::yaml_block(x, out, indent + 1);
+                }
+                _ => out.push_str(&format!(": {}\n", v.flow())),
+            }
+        }
+    }
+
+    /// YAML flow style, which is JSON with spaces after separators
+    fn flow(&self) -> String {
+        match self {
+            Json::Array(x) => {
+                let items: Vec<String> = x.iter().map(|v| v.flow()).collect();
+                format!("[{}]", items.join(", "))
+            }
+            Json::Object(x) => {
+                let items: Vec<String> = x
+                    .iter()
+                    .map(|(k, v)| format!("{}: {}", Json::String(k.clone()), v.flow()))
+                    .collect();
+                format!("{{{}}}", items.join(", "))
+            }
+            _ => self.to_string(),
+        }
+    }
+
+    /// name of the type of this value used in error messages
+    fn kind(&self) -> &'static str {
+        match self {
+            Json::Null => "null",
+            Json::Bool(_) => "a bool",
+            Json::Number(_) => "a number",
+            Json::String(_) => "a string",
+            Json::Array(_) => "an array",
+            Json::Object(_) => "an object",
+        }
+    }
+}
+
+/// error for a value of another type than expected
+fn mismatch(expected: &str, json: &Json) -> Error {
+    Error::InvalidParam(format!("expected {}, got {}", expected, json.kind()))
+}
+
+/// read a parameter and check its value, e.g. that it's one of a few choices
+///
+/// Fails with Error::InvalidParam for JSON of another type and for values rejected by the check.
+pub(crate) fn checked<T: FromJson>(json: &Json, check: impl FnOnce(&T) -> Result<()>) -> Result<T> {
+    let value = T::from_json(json)?;
+    check(&value)?;
+    Ok(value)
+}
+
+/// error for a key that a parameter struct does not have
+pub(crate) fn unknown_key(key: &str) -> Error {
+    Error::InvalidParam(format!("unknown parameter {}", key))
+}
+
+impl fmt::Display for Json {
+    /// compact JSON without whitespace
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(x) => write!(f, "{}", x),
+            Json::Number(x) if x.is_finite() => write!(f, "{}", x),
+            Json::Number(_) => write!(f, "null"),
+            Json::String(x) => write_string(f, x),
+            Json::Array(x) => {
+                write!(f, "[")?;
+                for (i, v) in x.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", v)?;
+                }
+                write!(f, "]")
+            }
+            Json::Object(x) => {
+                write!(f, "{{")?;
+                for (i, (k, v)) in x.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write_string(f, k)?;
+                    write!(f, ":{}", v)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+/// write a quoted string with escapes
+fn write_string(f: &mut fmt::Formatter<'_>, s: &str) -> fmt::Result {
+    write!(f, "\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    write!(f, "\"")
+}
+
+/// how deep arrays and objects can be nested in a parsed document
+///
+/// The parser recurses on every level, so a limit keeps malicious input from overflowing the stack.
+pub const MAX_DEPTH: usize = 128;
+
+/// recursive descent parser over the bytes of a document
+struct Parser<'a> {
+    s: &'a [u8],
+    i: usize,
+    /// arrays and objects currently open
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, msg: &str) -> Error {
+        Error::InvalidParam(format!("malformed JSON at byte {}: {}", self.i, msg))
+    }
+
+    fn whitespace(&mut self) {
+        while self.i < self.s.len() && self.s[self.i].is_ascii_whitespace() {
+            self.i += 1;
+        }
+    }
+
+    /// skip a literal if it comes next
+    fn eat(&mut self, literal: &str) -> bool {
+        if self.s[self.i..].starts_with(literal.as_bytes()) {
+            self.i += literal.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn value(&mut self) -> Result<Json> {
+        self.whitespace();
+        match self.s.get(self.i).copied() {
+            None => Err(self.error("unexpected end")),
+            Some(b'{' | b'[') if self.depth == MAX_DEPTH => Err(self.error("nested too deep")),
+            Some(b'{') => {
+                self.depth += 1;
+                let object = self.object();
+                self.depth -= 1;
+                object
+            }
+            Some(b'[') => {
+                self.depth += 1;
+                let array = self.array();
+                self.depth -= 1;
+                array
+            }
+            Some(b'"') => Ok(Json::String(self.string()?)),
+            Some(b'-' | b'0'..=b'9') => self.number(),
+            _ if self.eat("null") => Ok(Json::Null),
+            _ if self.eat("true") => Ok(Json::Bool(true)),
+            _ if self.eat("false") => Ok(Json::Bool(false)),
+            _ => Err(self.error("unexpected character")),
+        }
+    }
+
+    fn object(&mut self) -> Result<Json> {
+        self.i += 1;
+        let mut fields = BTreeMap::new();
+        self.whitespace();
+        if self.eat("}") {
+            return Ok(Json::Object(fields));
+        }
+        loop {
+            self.whitespace();
+            if self.s.get(self.i) != Some(&b'"') {
+                return Err(self.error("expected a key"));
+            }
+            let key = self.string()?;
+            self.whitespace();
+            if !self.eat(":") {
+                return Err(self.error("expected a colon"));
+            }
+            fields.insert(key, self.value()?);
+            self.whitespace();
+            if self.eat("}") {
+                return Ok(Json::Object(fields));
+            }
+            if !self.eat(",") {
+                return Err(self.error("expected a comma or the end of an object"));
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Json> {
+        self.i += 1;
+        let mut values = Vec::new();
+        self.whitespace();
+        if self.eat("]") {
+            return Ok(Json::Array(values));
+        }
+        loop {
+            values.push(self.value()?);
+            self.whitespace();
+            if self.eat("]") {
+                return Ok(Json::Array(values));
+            }
+            if !self.eat(",") {
+                return Err(self.error("expected a comma or the end of an array"));
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String> {
+        self.i += 1;
+        let mut out = String::new();
+        loop {
+            let start = self.i;
+            while self.i < self.s.len() && self.s[self.i] != b'"' && self.s[self.i] != b'\\' {
+                self.i += 1;
+            }
+            // the input is a str and quotes or backslashes never split a character
+            out.push_str(core::str::from_utf8(&self.s[start..self.i]).unwrap());
+            match self.s.get(self.i) {
+                None => return Err(self.error("unterminated string")),
+                Some(b'"') => {
+                    self.i += 1;
+                    return Ok(out);
+                }
+                _ => {
+                    let c = self.s.get(self.i + 1).copied();
+                    self.i += 2;
+                    match c {
+                        Some(b'"') => out.push('"'),
+                        Some(b'\\') => out.push('\\'),
+                        Some(b'/') => out.push('/'),
+                        Some(b'b') => out.push('\u{8}'),
+                        Some(b'f') => out.push('\u{c}'),
+                        Some(b'n') => out.push('\n'),
+                        Some(b'r') => out.push('\r'),
+                        Some(b't') => out.push('\t'),
+                        Some(b'u') => out.push(self.unicode()?),
+                        _ => return Err(self.error("invalid escape")),
+                    }
+                }
+            }
+        }
+    }
+
+    /// character of a \u escape, including surrogate pairs
+    fn unicode(&mut self) -> Result<char> {
+        let mut code = self.hex()?;
+        if (0xd800..0xdc00).contains(&code) && self.eat("\\u") {
+            let low = self.hex()?;
+            if !(0xdc00..0xe000).contains(&low) {
+                return Err(self.error("invalid unicode escape"));
+            }
+            code = 0x10000 + ((code - 0xd800) << 10) + (low - 0xdc00);
+        }
+        char::from_u32(code).ok_or_else(|| self.error("invalid unicode escape"))
+    }
+
+    fn hex(&mut self) -> Result<u32> {
+        let digits = self.s.get(self.i..self.i + 4);
+        let digits = digits.and_then(|x| core::str::from_utf8(x).ok());
+        let code = digits.and_then(|x| u32::from_str_radix(x, 16).ok());
+        let code = code.ok_or_else(|| self.error("invalid unicode escape"))?;
+        self.i += 4;
+        Ok(code)
+    }
+
+    /// skip digits and return how many there were
+    fn digits(&mut self) -> usize {
+        let start = self.i;
+        while self.i < self.s.len() && self.s[self.i].is_ascii_digit() {
+            self.i += 1;
+        }
+        self.i - start
+    }
+
+    /// number in the JSON grammar, without leading zeros, and within the range of f64
+    fn number(&mut self) -> Result<Json> {
+        let start = self.i;
+        self.eat("-");
+        let valid = if self.eat("0") {
+            !self.s.get(self.i).is_some_and(u8::is_ascii_digit)
+        } else {
+            self.digits() > 0
+        };
+        let valid = valid && (!self.eat(".") || self.digits() > 0);
+        let valid = valid
+            && (!(self.eat("e") || self.eat("E")) || {
+                let _ = self.eat("+") || self.eat("-");
+                self.digits() > 0
+            });
+
+        let s = core::str::from_utf8(&self.s[start..self.i]).unwrap();
+        match s.parse::<f64>() {
+            Ok(x) if valid && x.is_finite() => Ok(Json::Number(x)),
+            _ => {
+                self.i = start;
+                Err(self.error("invalid number"))
+            }
+        }
+    }
+}
+
+impl ToJson for Json {
+    fn to_json(&self) -> Json {
+        self.clone()
+    }
+}
+
+impl FromJson for Json {
+    fn from_json(json: &Json) -> Result<Self> {
+        Ok(json.clone())
+    }
+}
+
+impl ToJson for bool {
+    fn to_json(&self) -> Json {
+        Json::Bool(*self)
+    }
+}
+
+impl FromJson for bool {
+    fn from_json(json: &Json) -> Result<Self> {
+        match json {
+            Json::Bool(x) => Ok(*x),
+            _ => Err(mismatch("a bool", json)),
+        }
+    }
+}
+
+impl ToJson for f64 {
+    fn to_json(&self) -> Json {
+        Json::Number(*self)
+    }
+}
+
+impl FromJson for f64 {
+    fn from_json(json: &Json) -> Result<Self> {
+        match json {
+            Json::Number(x) => Ok(*x),
+            _ => Err(mismatch("a number", json)),
+        }
+    }
+}
+
+impl ToJson for f32 {
+    fn to_json(&self) -> Json {
+        Json::Number(*self as f64)
+    }
+}
+
+impl FromJson for f32 {
+    fn from_json(json: &Json) -> Result<Self> {
+        f64::from_json(json).map(|x| x as f32)
+    }
+}
+
+impl ToJson for usize {
+    fn to_json(&self) -> Json {
+        Json::Number(*self as f64)
+    }
+}
+
+impl FromJson for usize {
+    fn from_json(json: &Json) -> Result<Self> {
+        match json {
+            Json::Number(x) if *x >= 0.0 && x.fract() == 0.0 => Ok(*x as usize),
+            _ => Err(mismatch("a non-negative integer", json)),
+        }
+    }
+}
+
+impl ToJson for u16 {
+    fn to_json(&self) -> Json {
+        Json::Number(*self as f64)
+    }
+}
+
+impl FromJson for u16 {
+    fn from_json(json: &Json) -> Result<Self> {
+        match usize::from_json(json)? {
+            x if x <= u16::MAX as usize => Ok(x as u16),
+            _ => Err(mismatch("a 16-bit integer", json)),
+        }
+    }
+}
+
+impl ToJson for i16 {
+    fn to_json(&self) -> Json {
+        Json::Number(*self as f64)
+    }
+}
+
+impl FromJson for i16 {
+    fn from_json(json: &Json) -> Result<Self> {
+        match json {
+            Json::Number(x)
+                if x.fract() == 0.0 && (i16::MIN as f64..=i16::MAX as f64).contains(x) =>
+            {
+                Ok(*x as i16)
+            }
+            _ => Err(mismatch("a signed 16-bit integer", json)),
+        }
+    }
+}
+
+impl ToJson for String {
+    fn to_json(&self) -> Json {
+        Json::String(self.clone())
+    }
+}
+
+impl FromJson for String {
+    fn from_json(json: &Json) -> Result<Self> {
+        match json {
+            Json::String(x) => Ok(x.clone()),
+            _ => Err(mismatch("a string", json)),
+        }
+    }
+}
+
+impl<T: ToJson> ToJson for Vec<T> {
+    fn to_json(&self) -> Json {
+        Json::Array(self.iter().map(|x| x.to_json()).collect())
+    }
+}
+
+impl<T: FromJson> FromJson for Vec<T> {
+    fn from_json(json: &Json) -> Result<Self> {
+        match json {
+            Json::Array(x) => x.iter().map(T::from_json).collect(),
+            _ => Err(mismatch("an array", json)),
+        }
+    }
+}
+
+impl<T: ToJson> ToJson for BTreeMap<String, T> {
+    fn to_json(&self) -> Json {
+        Json::Object(self.iter().map(|(k, v)| (k.clone(), v.to_json())).collect())
+    }
+}
+
+impl<T: FromJson> FromJson for BTreeMap<String, T> {
+    fn from_json(json: &Json) -> Result<Self> {
+        let fields = json.fields()?;
+        fields
+            .iter()
+            .map(|(k, v)| Ok((k.clone(), T::from_json(v)?)))
+            .collect()
+    }
+}
+
+impl<T: ToJson> ToJson for Option<T> {
+    fn to_json(&self) -> Json {
+        match self {
+            Some(x) => x.to_json(),
+            None => Json::Null,
+        }
+    }
+}
+
+impl<T: FromJson> FromJson for Option<T> {
+    fn from_json(json: &Json) -> Result<Self> {
+        match json {
+            Json::Null => Ok(None),
+            _ => T::from_json(json).map(Some),
+        }
+    }
+}
+
+/// pairs are written as arrays of two values
+impl<A: ToJson, B: ToJson> ToJson for (A, B) {
+    fn to_json(&self) -> Json {
+        Json::Array(vec![self.0.to_json(), self.1.to_json()])
+    }
+}
+
+impl<A: FromJson, B: FromJson> FromJson for (A, B) {
+    fn from_json(json: &Json) -> Result<Self> {
+        match json {
+            Json::Array(x) if x.len() == 2 => Ok((A::from_json(&x[0])?, B::from_json(&x[1])?)),
+            _ => Err(mismatch("an array of two values", json)),
+        }
+    }
+}
+
+/// algorithms without parameters are configured with an empty object
+impl ToJson for () {
+    fn to_json(&self) -> Json {
+        Json::Object(BTreeMap::new())
+    }
+}
+
+impl FromJson for () {
+    fn from_json(json: &Json) -> Result<Self> {
+        match json.fields()?.keys().next() {
+            Some(key) => Err(unknown_key(key)),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FromJson, Json, ToJson, MAX_DEPTH};
+    use crate::algs::hpcp::HPCPParams;
+    use crate::algs::stft::FFTParams;
+    use crate::algs::synth::SynthesizerParams;
+    use crate::Error;
+
+    #[test]
+    fn parse_and_write() {
+        let doc = r#" {"b": [1, -2.5e1, true, null], "a": "x\"é🎵\n", "c": {}} "#;
+        let json = Json::parse(doc).unwrap();
+        let expected = r#"{"a":"x\"é🎵\n","b":[1,-25,true,null],"c":{}}"#;
+        assert_eq!(json.to_string(), expected);
+        assert_eq!(Json::parse(expected).unwrap(), json);
+
+        let pretty = "{\n    \"a\": \"x\\\"é🎵\\n\",\n    \"b\": [\n        1,\n        -25,\n        true,\n        null\n    ],\n    \"c\": {}\n}\n";
+        assert_eq!(json.pretty(), pretty);
+        assert_eq!(Json::parse(pretty).unwrap(), json);
+        let yaml = "a: \"x\\\"é🎵\\n\"\nb: [1, -25, true, null]\nc: {}\n";
+        assert_eq!(json.yaml(), yaml);
+
+        for doc in ["", "[1,]", "{\"a\" 1}", "\"abc", "01x", "[1] 2"] {
+            assert!(
+                matches!(Json::parse(doc), Err(Error::InvalidParam(_))),
+                "{}",
+                doc
+            );
+        }
+
+        let nested = |n| "[".repeat(n) + &"]".repeat(n);
+        assert!(Json::parse(&nested(MAX_DEPTH)).is_ok());
+        assert!(matches!(
+            Json::parse(&nested(MAX_DEPTH + 1)),
+            Err(Error::InvalidParam(_))
+        ));
+        assert!(Json::parse(&"[".repeat(2_000_000)).is_err());
+    }
+
+    #[test]
+    fn numbers_and_escapes() {
+        for (doc, x) in [
+            ("0", 0.0),
+            ("-0.5", -0.5),
+            ("1e3", 1e3),
+            ("2E-2", 2e-2),
+            ("1.5e+1", 15.0),
+        ] {
+            assert_eq!(Json::parse(doc).unwrap(), Json::Number(x), "{}", doc);
+        }
+        for doc in [
+            "01", "-01", "-", "1.", "1.e3", "1e", "1e+", "+1", "--1", "1e999", "-1e999",
+        ] {
+            assert!(
+                matches!(Json::parse(doc), Err(Error::InvalidParam(_))),
+                "{}",
+                doc
+            );
+        }
+
+        let doc = r#""\ud83c\udfb5\u00e9""#;
+        assert_eq!(Json::parse(doc).unwrap(), Json::String("🎵é".into()));
+        for doc in [
+            r#""\ud800\u0041""#,
+            r#""\ud800""#,
+            r#""\udc00""#,
+            r#""\u12""#,
+        ] {
+            assert!(
+                matches!(Json::parse(doc), Err(Error::InvalidParam(_))),
+                "{}",
+                doc
+            );
+        }
+    }
+
+    #[test]
+    fn params() {
+        let params = HPCPParams::from_json(&Json::parse(r#"{"size": 24}"#).unwrap()).unwrap();
+        assert_eq!(params.size, 24);
+        assert_eq!(params.sample_rate, HPCPParams::default().sample_rate);
+        assert_eq!(HPCPParams::from_json(&params.to_json()).unwrap(), params);
+
+        for doc in [
+            r#"{"size": 2.5}"#,
+            r#"{"sise": 24}"#,
+            r#"{"size": 13}"#,
+            "[]",
+        ] {
+            let result = HPCPParams::from_json(&Json::parse(doc).unwrap());
+            assert!(matches!(result, Err(Error::InvalidParam(_))), "{}", doc);
+        }
+    }
+
+    #[test]
+    fn choices() {
+        let doc = Json::parse(r#"{"waveform": "pulse:0.1"}"#).unwrap();
+        assert_eq!(
+            SynthesizerParams::from_json(&doc).unwrap().waveform,
+            "pulse:0.1"
+        );
+
+        // unknown choices are rejected instead of falling back to the default
+        let doc = Json::parse(r#"{"waveform": "sine"}"#).unwrap();
+        let result = SynthesizerParams::from_json(&doc);
+        assert!(matches!(result, Err(Error::InvalidParam(m)) if m.contains("sine")));
+        let doc = Json::parse(r#"{"window": "hanning"}"#).unwrap();
+        let result = FFTParams::from_json(&doc);
+        assert!(matches!(result, Err(Error::InvalidParam(m)) if m.contains("hanning")));
+    }
+}
@@ -0,0 +1,241 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::{fs, path::Path};
+
+use serde::de::{self, Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::ser::{Serialize, SerializeMap, SerializeSeq, Serializer};
+
+use super::{FromJson, Json, ToJson};
+use crate::algs::denoise::DenoiserParams;
+use crate::algs::dynamics::{CompressorParams, GateParams};
+use crate::algs::filters::{MedianFilterParams, MovingAverageParams};
+use crate::algs::hpcp::HPCPParams;
+use crate::algs::io::{MonoFloatLoaderParams, MonoWriterParams};
+use crate::algs::problems::{
+    ClickDetectorParams, GapDetectorParams, HumDetectorParams, SaturationDetectorParams,
+    StartStopSilenceParams, TruePeakDetectorParams,
+};
+use crate::algs::resample::ResampleParams;
+use crate::algs::restore::{DeclickerParams, DeclipperParams};
+use crate::algs::stereo::StereoWidenerParams;
+use crate::algs::stft::FFTParams;
+use crate::algs::stretch::WSOLAParams;
+use crate::algs::synth::SynthesizerParams;
+use crate::pool::{Pool, Stat, Value};
+use crate::{Error, Result};
+
+impl Json {
+    /// parse a TOML document, e.g. a config file with parameters of algorithms
+    ///
+    /// Tables become objects, and dates and times are not supported.
+    /// Fails with Error::InvalidParam for malformed documents.
+    pub fn parse_toml(s: &str) -> Result<Self> {
+        toml::from_str(s).map_err(|err| Error::InvalidParam(err.to_string()))
+    }
+
+    /// read and parse a TOML file, e.g. a config with parameters of algorithms
+    pub fn read_toml(path: &Path) -> Result<Self> {
+        Self::parse_toml(&fs::read_to_string(path)?)
+    }
+
+    /// TOML document of an object, with nested objects as tables
+    ///
+    /// Fails with Error::InvalidParam for values TOML can't hold,
+    /// like null, which is also written in place of NaN, or a top level that isn't an object.
+    pub fn toml(&self) -> Result<String> {
+        toml::to_string_pretty(self).map_err(|err| Error::InvalidParam(err.to_string()))
+    }
+
+    /// write the value as TOML to a file
+    pub fn write_toml(&self, path: &Path) -> Result<()> {
+        Ok(fs::write(path, self.toml()?)?)
+    }
+}
+
+impl Serialize for Json {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        match self {
+            Json::Null => serializer.serialize_unit(),
+            Json::Bool(x) => serializer.serialize_bool(*x),
+            // whole numbers stay integers, so sizes and sample rates read back as such
+            Json::Number(x) if x.fract() == 0.0 && x.abs() < 9e15 => {
+                serializer.serialize_i64(*x as i64)
+            }
+            Json::Number(x) => serializer.serialize_f64(*x),
+            Json::String(x) => serializer.serialize_str(x),
+            Json::Array(x) => {
+                let mut seq = serializer.serialize_seq(Some(x.len()))?;
+                for v in x {
+                    seq.serialize_element(v)?;
+                }
+                seq.end()
+            }
+            Json::Object(x) => {
+                let mut map = serializer.serialize_map(Some(x.len()))?;
+                for (k, v) in x {
+                    map.serialize_entry(k, v)?;
+                }
+                map.end()
+            }
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Json {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        deserializer.deserialize_any(JsonVisitor)
+    }
+}
+
+/// visitor building a Json value from any self-describing format
+struct JsonVisitor;
+
+impl<'de> Visitor<'de> for JsonVisitor {
+    type Value = Json;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a JSON value")
+    }
+
+    fn visit_unit<E>(self) -> std::result::Result<Json, E> {
+        Ok(Json::Null)
+    }
+
+    fn visit_none<E>(self) -> std::result::Result<Json, E> {
+        Ok(Json::Null)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, d: D) -> std::result::Result<Json, D::Error> {
+        Json::deserialize(d)
+    }
+
+    fn visit_bool<E>(self, x: bool) -> std::result::Result<Json, E> {
+        Ok(Json::Bool(x))
+    }
+
+    fn visit_i64<E>(self, x: i64) -> std::result::Result<Json, E> {
+        Ok(Json::Number(x as f64))
+    }
+
+    fn visit_u64<E>(self, x: u64) -> std::result::Result<Json, E> {
+        Ok(Json::Number(x as f64))
+    }
+
+    fn visit_f64<E>(self, x: f64) -> std::result::Result<Json, E> {
+        Ok(Json::Number(x))
+    }
+
+    fn visit_str<E>(self, x: &str) -> std::result::Result<Json, E> {
+        Ok(Json::String(x.into()))
+    }
+
+    fn visit_string<E>(self, x: String) -> std::result::Result<Json, E> {
+        Ok(Json::String(x))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> std::result::Result<Json, A::Error> {
+        let mut x = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(v) = seq.next_element()? {
+            x.push(v);
+        }
+        Ok(Json::Array(x))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> std::result::Result<Json, A::Error> {
+        let mut x = BTreeMap::new();
+        while let Some((k, v)) = map.next_entry()? {
+            x.insert(k, v);
+        }
+        Ok(Json::Object(x))
+    }
+}
+
+/// implement Serialize through ToJson, and Deserialize through FromJson with its checks
+macro_rules! serde_with_json {
+    (serialize: $($ty:ty),*) => {
+        $(
+            impl Serialize for $ty {
+                fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+                    self.to_json().serialize(serializer)
+                }
+            }
+        )*
+    };
+    ($($ty:ty),*) => {
+        serde_with_json!(serialize: $($ty),*);
+        $(
+            impl<'de> Deserialize<'de> for $ty {
+                fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+                    let json = Json::deserialize(deserializer)?;
+                    <$ty>::from_json(&json).map_err(de::Error::custom)
+                }
+            }
+        )*
+    };
+}
+
+serde_with_json!(
+    ClickDetectorParams,
+    CompressorParams,
+    DeclickerParams,
+    DeclipperParams,
+    DenoiserParams,
+    FFTParams,
+    GapDetectorParams,
+    GateParams,
+    HPCPParams,
+    HumDetectorParams,
+    MedianFilterParams,
+    MonoFloatLoaderParams,
+    MonoWriterParams,
+    MovingAverageParams,
+    ResampleParams,
+    SaturationDetectorParams,
+    StartStopSilenceParams,
+    StereoWidenerParams,
+    SynthesizerParams,
+    TruePeakDetectorParams,
+    WSOLAParams,
+    Stat,
+    Value
+);
+serde_with_json!(serialize: Pool);
+
+#[cfg(test)]
+mod tests {
+    use super::Json;
+    use crate::algs::hpcp::HPCPParams;
+    use crate::algs::synth::SynthesizerParams;
+    use crate::Error;
+
+    #[test]
+    fn toml() {
+        let doc = "size = 36\nsample_rate = 48000.0\n\n[extra]\nnames = [\"a\", \"b\"]\n";
+        let json = Json::parse_toml(doc).unwrap();
+        assert_eq!(json.fields().unwrap()["size"], Json::Number(36.0));
+        assert_eq!(Json::parse_toml(&json.toml().unwrap()).unwrap(), json);
+
+        // whole numbers are written as integers
+        let json = Json::parse(r#"{"size": 36, "pan": 0.5}"#).unwrap();
+        assert_eq!(json.toml().unwrap(), "pan = 0.5\nsize = 36\n");
+
+        assert!(matches!(
+            Json::parse_toml("size = "),
+            Err(Error::InvalidParam(_))
+        ));
+        assert!(matches!(Json::Null.toml(), Err(Error::InvalidParam(_))));
+    }
+
+    #[test]
+    fn params() {
+        let params: HPCPParams = toml::from_str("size = 24").unwrap();
+        assert_eq!(params.size, 24);
+        let text = toml::to_string(&params).unwrap();
+        assert_eq!(toml::from_str::<HPCPParams>(&text).unwrap(), params);
+
+        // the checks of FromJson apply, so unknown choices and keys are errors
+        assert!(toml::from_str::<HPCPParams>("size = 13").is_err());
+        assert!(toml::from_str::<HPCPParams>("sise = 24").is_err());
+        assert!(toml::from_str::<SynthesizerParams>("waveform = \"sine\"").is_err());
+    }
+}
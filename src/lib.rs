@@ -5,17 +5,115 @@
 //!   - tonal analysis with harmonic pitch class profile -- **HPCP**
 //!   - inverse fast Fourier transform -- **IFFT**
 //!   - short-time Fourier transform -- **STFT**
-//!   - simple **mixer** to create mono tracks
-//!   - **synth**esizer for simple waveforms
+//!   - simple **mixer** to load, combine and write tracks
+//!   - **MIDI** file export of sequenced or transcribed notes, and rendering with the synthesizer
+//!   - **notes** with names, frequencies, intervals and chord spelling
+//!   - **score**s of melodies in ABC notation, rendered with the synthesizer
+//!   - **streaming** networks pushing data through algorithms frame by frame
+//!   - **registry** creating algorithms and pipelines by name from config files
+//!   - one-shot **extractor** of low-level, rhythm and tonal descriptors of a file
+//!   - **cover** song similarity with transposition and tempo invariant alignment
+//!   - **batch** extraction of features from many files on worker threads
+//!   - **pool** of features collected during analysis, with simple statistics
+//!   - dynamics processing with sidechain input -- **compressor**, **gate**
+//!   - noise reduction with spectral subtraction -- **denoise**
+//!   - restoration of clicks and clipped regions -- **declick**, **declip**
+//!   - detection of audio problems for quality control -- **hum**, **gaps**, **saturation**, **clicks**, **true peak**, **silence**
+//!   - **resampl**ing between sample rates with linear, polyphase or windowed sinc interpolation
+//!   - **mid-side** processing and stereo widening
+//!   - **synth**esizer for simple, additive and drawn waveforms, seeded white, pink and brown noise and sine sweeps
+//!   - polyphonic **instrument** played live with MIDI messages
+//!   - time-stretching with waveform similarity overlap-add -- **WSOLA**
+//!   - C API to create and compute algorithms from other languages -- **ffi**
+//!   - WebAssembly bindings for in-browser synthesis and analysis -- **wasm**
 //!
 //! This should be sufficient to allow for flexible synthesis, processing and analysis of audio.
+//!
+//! Without the default "std" feature the crate is `no_std` and only needs `alloc`,
+//! so the synthesis engine can run on embedded targets.
+//! This core keeps the synthesizer with its generators and envelopes, the filters,
+//! the FFT plans of [algs::fft], notes and JSON parameters, with math functions provided by libm.
+//! File I/O, analysis algorithms, the C API and the Python module need "std".
 
+#![cfg_attr(not(feature = "std"), no_std)]
 #![warn(missing_docs)]
+// pyo3 0.20 macros expand to impl blocks nested in constants
+#![cfg_attr(feature = "python", allow(non_local_definitions))]
+
+extern crate alloc;
 
 /// algorithms implementation
 pub mod algs;
-/// simple mixer to load and create mono tracks
+/// feature extraction from many files on worker threads
+#[cfg(feature = "std")]
+pub mod batch;
+/// cover song similarity of two recordings
+#[cfg(feature = "std")]
+pub mod cover;
+/// error type returned across the crate
+pub mod error;
+/// one-shot extraction of standard descriptors of a file
+#[cfg(feature = "std")]
+pub mod extractor;
+/// C API creating and computing algorithms by name, see include/muslib.h
+#[cfg(feature = "std")]
+pub mod ffi;
+/// FLAC encoding of integer samples written by the mixer
+#[cfg(feature = "std")]
+pub mod flac;
+/// reading and writing JSON for parameters and results, YAML output, and TOML with the serde feature
+pub mod json;
+/// standard MIDI files of synthesized or transcribed notes, rendered by the synthesizer
+#[cfg(feature = "std")]
+pub mod midi;
+/// simple mixer to load, combine and write tracks
+#[cfg(feature = "std")]
 pub mod mixer;
+/// note names, MIDI numbers, frequencies, intervals and chords
+pub mod notes;
+/// frame-level analysis on all cores with rayon
+#[cfg(feature = "parallel")]
+pub mod parallel;
+/// container of features collected during analysis
+#[cfg(feature = "std")]
+pub mod pool;
+/// headerless pcm read by the mixer, e.g. dumped by embedded capture devices
+#[cfg(feature = "std")]
+pub mod raw;
+/// creating algorithms and pipelines by name from config files
+#[cfg(feature = "std")]
+pub mod registry;
+/// seeded random numbers reproducible across runs and platforms
+pub mod rng;
+/// audio sample types and conversions between them
+pub mod sample;
+/// melodies written as text in ABC notation
+#[cfg(feature = "std")]
+pub mod score;
+/// vectorized loops over blocks of samples with runtime CPU feature detection
+#[cfg(feature = "std")]
+pub mod simd;
+/// streaming networks of algorithms connected frame by frame
+#[cfg(feature = "std")]
+pub mod streaming;
+/// spans of processing stages, traced with the "tracing" feature
+#[cfg(feature = "std")]
+pub mod trace;
+/// Ogg Vorbis encoding of samples written by the mixer, built with the "vorbis" feature
+#[cfg(feature = "vorbis")]
+pub mod vorbis;
+
+/// WebAssembly bindings of the synthesizer, FFT and HPCP for the browser, built with the "wasm" feature
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+/// floating point functions of std provided by libm without it
+#[cfg_attr(feature = "std", allow(dead_code))]
+mod math;
 
 // muslib python module
+#[cfg(feature = "python")]
 mod pymod;
+
+pub use error::{Error, Result};
+pub use sample::Sample;
@@ -0,0 +1,124 @@
+/// methods of f64 from std used by the core DSP, implemented with libm
+///
+/// With std the inherent methods take precedence, so this trait is only imported without it
+/// and the code calls `x.sin()` the same way in both cases.
+pub(crate) trait Float {
+    fn acos(self) -> Self;
+    fn atan2(self, other: Self) -> Self;
+    fn ceil(self) -> Self;
+    fn cos(self) -> Self;
+    fn exp(self) -> Self;
+    fn exp_m1(self) -> Self;
+    fn floor(self) -> Self;
+    fn fract(self) -> Self;
+    fn ln(self) -> Self;
+    fn log2(self) -> Self;
+    fn powf(self, n: Self) -> Self;
+    fn rem_euclid(self, rhs: Self) -> Self;
+    fn round(self) -> Self;
+    fn sin(self) -> Self;
+    fn sqrt(self) -> Self;
+    fn tan(self) -> Self;
+}
+
+impl Float for f64 {
+    fn acos(self) -> f64 {
+        libm::acos(self)
+    }
+
+    fn atan2(self, other: f64) -> f64 {
+        libm::atan2(self, other)
+    }
+
+    fn ceil(self) -> f64 {
+        libm::ceil(self)
+    }
+
+    fn cos(self) -> f64 {
+        libm::cos(self)
+    }
+
+    fn exp(self) -> f64 {
+        libm::exp(self)
+    }
+
+    fn exp_m1(self) -> f64 {
+        libm::expm1(self)
+    }
+
+    fn floor(self) -> f64 {
+        libm::floor(self)
+    }
+
+    fn fract(self) -> f64 {
+        self - libm::trunc(self)
+    }
+
+    fn ln(self) -> f64 {
+        libm::log(self)
+    }
+
+    fn log2(self) -> f64 {
+        libm::log2(self)
+    }
+
+    fn powf(self, n: f64) -> f64 {
+        libm::pow(self, n)
+    }
+
+    fn rem_euclid(self, rhs: f64) -> f64 {
+        let r = self % rhs;
+        if r < 0.0 {
+            r + rhs.abs()
+        } else {
+            r
+        }
+    }
+
+    fn round(self) -> f64 {
+        libm::round(self)
+    }
+
+    fn sin(self) -> f64 {
+        libm::sin(self)
+    }
+
+    fn sqrt(self) -> f64 {
+        libm::sqrt(self)
+    }
+
+    fn tan(self) -> f64 {
+        libm::tan(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Float;
+
+    #[test]
+    fn matches_std() {
+        let close = |a: f64, b: f64| (a - b).abs() <= 1e-12 * b.abs().max(1.0);
+        for x in [-7.25, -1.0, -0.3, 0.0, 0.5, 0.7, 1.0, 2.5, 3.25, 10.0] {
+            assert_eq!(Float::rem_euclid(x, 1.0), x.rem_euclid(1.0));
+            assert_eq!(Float::rem_euclid(x, -3.0), x.rem_euclid(-3.0));
+            assert_eq!(Float::fract(x), x.fract());
+            assert_eq!(Float::round(x), x.round());
+            assert_eq!(Float::floor(x), x.floor());
+            assert_eq!(Float::ceil(x), x.ceil());
+            assert!(close(Float::sin(x), x.sin()));
+            assert!(close(Float::cos(x), x.cos()));
+            assert!(close(Float::exp(x), x.exp()));
+            assert!(close(Float::exp_m1(x), x.exp_m1()));
+            assert!(close(Float::powf(2.0, x), 2f64.powf(x)));
+            assert!(close(Float::atan2(x, 2.0), x.atan2(2.0)));
+        }
+        for x in [0.1, 1.0, 440.0, 1e6] {
+            assert!(close(Float::ln(x), x.ln()));
+            assert!(close(Float::log2(x), x.log2()));
+            assert!(close(Float::sqrt(x), x.sqrt()));
+            assert!(close(Float::tan(1.0 / x), (1.0 / x).tan()));
+            assert!(close(Float::acos(x / 1e6), (x / 1e6).acos()));
+        }
+    }
+}
@@ -0,0 +1,692 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::algs::synth::{NoteEvent, Synthesizer};
+use crate::notes::{self, Tuning};
+use crate::{Error, Result, Sample};
+
+/// Note played at a time for a while, e.g. sequenced for a synthesizer or transcribed from audio
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Note {
+    /// MIDI note number, 69 for A4
+    pub pitch: u8,
+    /// MIDI velocity from 1 to 127
+    pub velocity: u8,
+    /// start time in seconds
+    pub start: f64,
+    /// duration in seconds
+    pub duration: f64,
+}
+
+impl Note {
+    /// create a Note with the default velocity of 100
+    pub fn new(pitch: u8, start: f64, duration: f64) -> Self {
+        Note {
+            pitch,
+            velocity: 100,
+            start,
+            duration,
+        }
+    }
+
+    /// consecutive notes of tones given as frequencies and durations, like Synthesizer inputs
+    ///
+    /// Frequencies of 0 or less are rests.
+    pub fn sequence(frequencies: &[f64], durations: &[f64]) -> Vec<Note> {
+        let mut notes = Vec::new();
+        let mut start = 0.0;
+        for (frequency, duration) in frequencies.iter().zip(durations.iter()) {
+            if *frequency > 0.0 {
+                notes.push(Note::new(notes::midi(*frequency), start, *duration));
+            }
+            start += duration;
+        }
+        notes
+    }
+}
+
+/// segment a pitch contour into notes
+///
+/// The contour has a frequency in Hz for every hop_size seconds, 0 or less where it's unvoiced.
+/// Consecutive frames rounding to the same MIDI note become a single note,
+/// and notes shorter than min_duration seconds are dropped.
+pub fn segment_contour(contour: &[f64], hop_size: f64, min_duration: f64) -> Vec<Note> {
+    let mut notes = Vec::new();
+    let mut current: Option<(u8, usize)> = None;
+    for i in 0..=contour.len() {
+        let frame = contour
+            .get(i)
+            .filter(|x| **x > 0.0)
+            .map(|x| notes::midi(*x));
+        match current {
+            Some((p, _)) if frame == Some(p) => continue,
+            Some((p, start)) => {
+                let duration = (i - start) as f64 * hop_size;
+                if duration >= min_duration {
+                    notes.push(Note::new(p, start as f64 * hop_size, duration));
+                }
+            }
+            None => {}
+        }
+        current = frame.map(|p| (p, i));
+    }
+    notes
+}
+
+/// channel message of the MIDI protocol, as received from an input port
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Message {
+    /// a key was pressed
+    NoteOn {
+        /// channel from 0 to 15
+        channel: u8,
+        /// MIDI note number
+        pitch: u8,
+        /// MIDI velocity from 1 to 127
+        velocity: u8,
+    },
+    /// a key was released
+    NoteOff {
+        /// channel from 0 to 15
+        channel: u8,
+        /// MIDI note number
+        pitch: u8,
+    },
+    /// a controller changed its value, e.g. 7 for volume or 64 for the sustain pedal
+    ControlChange {
+        /// channel from 0 to 15
+        channel: u8,
+        /// controller number
+        controller: u8,
+        /// new value from 0 to 127
+        value: u8,
+    },
+}
+
+impl Message {
+    /// read a message from its raw bytes, None for other kinds of messages
+    ///
+    /// A note on with a velocity of 0 is a note off, as sent by many keyboards.
+    pub fn parse(bytes: &[u8]) -> Option<Message> {
+        let (status, data) = bytes.split_first()?;
+        let channel = status & 0x0f;
+        let data = |i: usize| data.get(i).filter(|x| **x < 0x80).copied();
+        match status & 0xf0 {
+            0x80 => Some(Message::NoteOff {
+                channel,
+                pitch: data(0)?,
+            }),
+            0x90 => match (data(0)?, data(1)?) {
+                (pitch, 0) => Some(Message::NoteOff { channel, pitch }),
+                (pitch, velocity) => Some(Message::NoteOn {
+                    channel,
+                    pitch,
+                    velocity,
+                }),
+            },
+            0xb0 => Some(Message::ControlChange {
+                channel,
+                controller: data(0)?,
+                value: data(1)?,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Writer of notes to standard MIDI files
+///
+/// Notes are written to a single track of a format 0 file, at a constant tempo.
+/// Times are rounded to the nearest tick, so with the default of 480 ticks per beat
+/// at 120 BPM the resolution is about 1 ms.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MidiWriter {
+    tempo: f64,
+    ticks_per_beat: u16,
+    channel: u8,
+    program: Option<u8>,
+}
+
+impl Default for MidiWriter {
+    fn default() -> Self {
+        MidiWriter {
+            tempo: 120.0,
+            ticks_per_beat: 480,
+            channel: 0,
+            program: None,
+        }
+    }
+}
+
+impl MidiWriter {
+    /// create a new MidiWriter at 120 BPM with 480 ticks per beat
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// set the tempo in beats per minute
+    pub fn tempo(mut self, tempo: f64) -> Self {
+        self.tempo = tempo;
+        self
+    }
+
+    /// set the number of ticks in a beat
+    pub fn ticks_per_beat(mut self, ticks_per_beat: u16) -> Self {
+        self.ticks_per_beat = ticks_per_beat;
+        self
+    }
+
+    /// set the MIDI channel of all notes, from 0 to 15
+    pub fn channel(mut self, channel: u8) -> Self {
+        self.channel = channel;
+        self
+    }
+
+    /// set the General MIDI program played by the channel, from 0 to 127
+    pub fn program(mut self, program: u8) -> Self {
+        self.program = Some(program);
+        self
+    }
+
+    /// write notes to a MIDI file
+    pub fn write(&self, path: &Path, notes: &[Note]) -> Result<()> {
+        let bytes = self.bytes(notes)?;
+        std::fs::write(path, bytes)?;
+        Ok(())
+    }
+
+    /// encode notes as the contents of a MIDI file
+    ///
+    /// Fails with Error::InvalidParam if a setting or a note is out of the MIDI range.
+    pub fn bytes(&self, notes: &[Note]) -> Result<Vec<u8>> {
+        if self.tempo.is_nan()
+            || self.tempo <= 0.0
+            || self.ticks_per_beat == 0
+            || self.ticks_per_beat > 0x7fff
+        {
+            return Err(Error::InvalidParam(
+                "tempo and ticks_per_beat of a MIDI file must be positive".into(),
+            ));
+        }
+        if self.channel > 15 || self.program.is_some_and(|x| x > 127) {
+            return Err(Error::InvalidParam(
+                "MIDI channel must be below 16 and program below 128".into(),
+            ));
+        }
+
+        let ticks_per_second = self.ticks_per_beat as f64 * self.tempo / 60.0;
+        let tick = |seconds: f64| (seconds.max(0.0) * ticks_per_second).round() as u64;
+
+        // events as (tick, order, message), note offs go before note ons at the same tick
+        let mut events: Vec<(u64, u8, [u8; 3])> = Vec::with_capacity(2 * notes.len());
+        for note in notes {
+            if note.pitch > 127 || note.velocity == 0 || note.velocity > 127 {
+                return Err(Error::InvalidParam(format!(
+                    "note pitch must be below 128 and velocity from 1 to 127, got {:?}",
+                    note
+                )));
+            }
+            let (start, end) = (tick(note.start), tick(note.start + note.duration));
+            events.push((start, 1, [0x90 | self.channel, note.pitch, note.velocity]));
+            events.push((end.max(start), 0, [0x80 | self.channel, note.pitch, 0]));
+        }
+        events.sort_by_key(|(tick, order, _)| (*tick, *order));
+
+        let mut track = Vec::new();
+        let tempo = (60_000_000.0 / self.tempo).round().min(0xff_ffff as f64) as u32;
+        track.extend([0x00, 0xff, 0x51, 0x03]);
+        track.extend(&tempo.to_be_bytes()[1..]);
+        if let Some(program) = self.program {
+            track.extend([0x00, 0xc0 | self.channel, program]);
+        }
+        let mut last = 0;
+        for (tick, _, message) in events {
+            write_varlen(&mut track, tick - last);
+            track.extend(message);
+            last = tick;
+        }
+        track.extend([0x00, 0xff, 0x2f, 0x00]);
+
+        let mut bytes = Vec::with_capacity(22 + track.len());
+        bytes.extend(b"MThd");
+        bytes.extend(6u32.to_be_bytes());
+        bytes.extend(0u16.to_be_bytes());
+        bytes.extend(1u16.to_be_bytes());
+        bytes.extend(self.ticks_per_beat.to_be_bytes());
+        bytes.extend(b"MTrk");
+        bytes.extend((track.len() as u32).to_be_bytes());
+        bytes.extend(track);
+        Ok(bytes)
+    }
+}
+
+/// Renderer of standard MIDI files with the Synthesizer
+///
+/// Notes of all tracks of a format 0 or 1 file are read with their velocities,
+/// and tempo changes from any track apply to all of them.
+/// Every note becomes a voice of the Synthesizer, so chords and overlapping notes are mixed.
+/// Program changes, controllers and pitch bends are ignored.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct MidiRenderer {
+    tuning: Tuning,
+    channel: Option<u8>,
+}
+
+impl MidiRenderer {
+    /// create a new MidiRenderer of all channels tuned to A4 at 440 Hz
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// set the tuning mapping note numbers to frequencies
+    pub fn tuning(mut self, tuning: Tuning) -> Self {
+        self.tuning = tuning;
+        self
+    }
+
+    /// only read notes of a MIDI channel from 0 to 15, e.g. to leave out drums on channel 9,
+    /// or notes of all channels with None
+    pub fn channel(mut self, channel: Option<u8>) -> Self {
+        self.channel = channel;
+        self
+    }
+
+    /// read notes from a MIDI file, sorted by their start
+    pub fn read(&self, path: &Path) -> Result<Vec<Note>> {
+        self.parse(&std::fs::read(path)?)
+    }
+
+    /// read notes from the contents of a MIDI file, sorted by their start
+    ///
+    /// Fails with Error::Decode if the file is malformed,
+    /// or Error::UnsupportedFormat for format 2 files of independent sequences.
+    pub fn parse(&self, bytes: &[u8]) -> Result<Vec<Note>> {
+        let mut reader = Reader { bytes, pos: 0 };
+        let (id, mut header) = reader.chunk()?;
+        if id != b"MThd" || header.bytes.len() < 6 {
+            return Err(Error::Decode("a MIDI file must start with a header".into()));
+        }
+        let format = header.u16()?;
+        let n_tracks = header.u16()?;
+        let division = header.u16()?;
+        if format > 1 {
+            return Err(Error::UnsupportedFormat(format!(
+                "MIDI files of format {} are not supported",
+                format
+            )));
+        }
+        // tempo changes in microseconds per beat, or a constant rate of SMPTE frames
+        let mut tempo = vec![(0, 500_000)];
+        let ticks_per_second = if division & 0x8000 != 0 {
+            let fps = match -((division >> 8) as u8 as i8) {
+                29 => 29.97,
+                fps => fps as f64,
+            };
+            Some(fps * (division & 0xff) as f64)
+        } else {
+            None
+        };
+        if division == 0 || ticks_per_second == Some(0.0) {
+            return Err(Error::Decode("MIDI division must be positive".into()));
+        }
+
+        // notes in ticks as (start, end, pitch, velocity)
+        let mut ticks = Vec::new();
+        let mut tracks = 0;
+        while tracks < n_tracks && reader.pos < reader.bytes.len() {
+            let (id, track) = reader.chunk()?;
+            if id == b"MTrk" {
+                self.track(track, &mut ticks, &mut tempo)?;
+                tracks += 1;
+            }
+        }
+
+        tempo.sort_by_key(|(tick, _)| *tick);
+        // start of every tempo change as (tick, seconds, seconds per tick)
+        let mut segments: Vec<(u64, f64, f64)> = Vec::with_capacity(tempo.len());
+        for (tick, usec) in tempo {
+            let rate = match ticks_per_second {
+                Some(x) => 1.0 / x,
+                None => usec as f64 / 1e6 / division as f64,
+            };
+            let seconds = match segments.last() {
+                Some((t, s, r)) => s + (tick - t) as f64 * r,
+                None => 0.0,
+            };
+            segments.push((tick, seconds, rate));
+        }
+        let time = |tick: u64| {
+            let i = segments.partition_point(|(t, _, _)| *t <= tick) - 1;
+            let (t, s, r) = segments[i];
+            s + (tick - t) as f64 * r
+        };
+
+        let mut notes: Vec<Note> = ticks
+            .into_iter()
+            .map(|(start, end, pitch, velocity)| Note {
+                pitch,
+                velocity,
+                start: time(start),
+                duration: time(end) - time(start),
+            })
+            .collect();
+        notes.sort_by(|a, b| a.start.total_cmp(&b.start));
+        Ok(notes)
+    }
+
+    /// read notes and tempo changes of a track chunk
+    ///
+    /// Notes still held at the end of the track are released there.
+    fn track(
+        &self,
+        mut track: Reader,
+        notes: &mut Vec<(u64, u64, u8, u8)>,
+        tempo: &mut Vec<(u64, u32)>,
+    ) -> Result<()> {
+        // start ticks and velocities of held notes by channel and pitch, first in first out
+        let mut held: HashMap<(u8, u8), Vec<(u64, u8)>> = HashMap::new();
+        let mut tick = 0;
+        let mut running = None;
+        while track.pos < track.bytes.len() {
+            tick += track.varlen()?;
+            let mut status = track.u8()?;
+            match status {
+                0xff => {
+                    let kind = track.u8()?;
+                    let len = track.varlen()? as usize;
+                    let data = track.take(len)?;
+                    match (kind, data) {
+                        (0x2f, _) => break,
+                        (0x51, [a, b, c]) => {
+                            tempo.push((tick, u32::from_be_bytes([0, *a, *b, *c]).max(1)))
+                        }
+                        _ => {}
+                    }
+                    continue;
+                }
+                0xf0 | 0xf7 => {
+                    let len = track.varlen()? as usize;
+                    track.take(len)?;
+                    running = None;
+                    continue;
+                }
+                0x80..=0xef => running = Some(status),
+                _ => match running {
+                    // running status, the byte was the first data byte
+                    Some(x) => {
+                        status = x;
+                        track.pos -= 1;
+                    }
+                    None => {
+                        return Err(Error::Decode(format!(
+                            "unexpected MIDI status byte {:#04x}",
+                            status
+                        )))
+                    }
+                },
+            }
+            let len = if matches!(status & 0xf0, 0xc0 | 0xd0) {
+                1
+            } else {
+                2
+            };
+            let mut message = [status, 0, 0];
+            message[1..=len].copy_from_slice(track.take(len)?);
+            match Message::parse(&message[..=len]) {
+                Some(Message::NoteOn {
+                    channel,
+                    pitch,
+                    velocity,
+                }) if self.channel.is_none_or(|x| x == channel) => {
+                    held.entry((channel, pitch))
+                        .or_default()
+                        .push((tick, velocity));
+                }
+                Some(Message::NoteOff { channel, pitch }) => {
+                    if let Some(on) = held.get_mut(&(channel, pitch)).filter(|x| !x.is_empty()) {
+                        let (start, velocity) = on.remove(0);
+                        notes.push((start, tick, pitch, velocity));
+                    }
+                }
+                _ => {}
+            }
+        }
+        for ((_, pitch), on) in held {
+            notes.extend(on.into_iter().map(|(start, v)| (start, tick, pitch, v)));
+        }
+        Ok(())
+    }
+
+    /// synthesize the notes of a MIDI file with a Synthesizer into samples of any type
+    pub fn render<S: Sample>(&self, path: &Path, synthesizer: &Synthesizer) -> Result<Vec<S>> {
+        let notes = self.read(path)?;
+        self.render_notes(&notes, synthesizer)
+    }
+
+    /// synthesize notes with a Synthesizer into samples of any type
+    ///
+    /// The velocity of 127 plays a note at full scale.
+    pub fn render_notes<S: Sample>(
+        &self,
+        notes: &[Note],
+        synthesizer: &Synthesizer,
+    ) -> Result<Vec<S>> {
+        let events: Vec<NoteEvent> = notes
+            .iter()
+            .map(|note| {
+                let freq = self.tuning.frequency(note.pitch as f64);
+                NoteEvent::new(freq, note.start, note.duration)
+                    .velocity(note.velocity as f64 / 127.0)
+            })
+            .collect();
+        synthesizer.render_notes(&events)
+    }
+}
+
+/// cursor over the bytes of a MIDI file or one of its chunks
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    /// next n bytes
+    fn take(&mut self, n: usize) -> Result<&'a [u8]> {
+        let bytes = self
+            .bytes
+            .get(self.pos..self.pos.saturating_add(n))
+            .ok_or_else(|| Error::Decode("MIDI data ends unexpectedly".into()))?;
+        self.pos += n;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    /// a MIDI variable-length quantity of up to 4 bytes
+    fn varlen(&mut self) -> Result<u64> {
+        let mut value = 0;
+        for _ in 0..4 {
+            let byte = self.u8()?;
+            value = value << 7 | (byte & 0x7f) as u64;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(Error::Decode(
+            "MIDI variable-length quantity is too long".into(),
+        ))
+    }
+
+    /// the id and a reader of the contents of the next chunk
+    fn chunk(&mut self) -> Result<(&'a [u8], Reader<'a>)> {
+        let id = self.take(4)?;
+        let len = self.take(4)?;
+        let len = u32::from_be_bytes([len[0], len[1], len[2], len[3]]) as usize;
+        let bytes = self.take(len)?;
+        Ok((id, Reader { bytes, pos: 0 }))
+    }
+}
+
+/// append a number as a MIDI variable-length quantity, 7 bits per byte
+fn write_varlen(bytes: &mut Vec<u8>, value: u64) {
+    let value = value.min(0x0fff_ffff);
+    let mut shift = 21;
+    while shift > 0 && value >> shift == 0 {
+        shift -= 7;
+    }
+    while shift > 0 {
+        bytes.push(0x80 | (value >> shift) as u8 & 0x7f);
+        shift -= 7;
+    }
+    bytes.push(value as u8 & 0x7f);
+}
+
+/// an Instrument played live from a MIDI input port, built with the "midi-input" feature
+#[cfg(feature = "midi-input")]
+pub mod input;
+
+#[cfg(test)]
+mod tests {
+    use super::{segment_contour, write_varlen, Message, MidiRenderer, MidiWriter, Note};
+    use crate::algs::synth::Synthesizer;
+
+    #[test]
+    fn midi() {
+        let mut bytes = Vec::new();
+        for x in [0, 0x7f, 0x80, 0x3fff, 0x4000, 0x0fff_ffff] {
+            write_varlen(&mut bytes, x);
+        }
+        let expected = [
+            0x00, 0x7f, 0x81, 0x00, 0xff, 0x7f, 0x81, 0x80, 0x00, 0xff, 0xff, 0xff, 0x7f,
+        ];
+        assert_eq!(bytes, expected);
+
+        // A4 for a beat, a rest, then C5 for half a beat
+        let notes = Note::sequence(&[440.0, 0.0, 523.25], &[0.5, 0.5, 0.25]);
+        assert_eq!(notes, [Note::new(69, 0.0, 0.5), Note::new(72, 1.0, 0.25)]);
+
+        let bytes = MidiWriter::new().program(0).bytes(&notes).unwrap();
+        let mut expected = b"MThd\0\0\0\x06\0\0\0\x01\x01\xe0MTrk\0\0\0\x21".to_vec();
+        expected.extend([0x00, 0xff, 0x51, 0x03, 0x07, 0xa1, 0x20, 0x00, 0xc0, 0x00]);
+        expected.extend([0x00, 0x90, 69, 100, 0x83, 0x60, 0x80, 69, 0]);
+        expected.extend([0x83, 0x60, 0x90, 72, 100, 0x81, 0x70, 0x80, 72, 0]);
+        expected.extend([0x00, 0xff, 0x2f, 0x00]);
+        assert_eq!(bytes, expected);
+
+        let path = std::env::temp_dir().join("muslib-midi.mid");
+        MidiWriter::new().write(&path, &notes).unwrap();
+        assert_eq!(std::fs::read(&path).unwrap()[..4], *b"MThd");
+        std::fs::remove_file(path).unwrap();
+
+        assert!(MidiWriter::new().channel(16).bytes(&notes).is_err());
+    }
+
+    #[test]
+    fn renderer() {
+        // notes written at a constant tempo are read back, with overlaps and velocities
+        let notes = [
+            Note::new(60, 0.0, 1.0),
+            Note {
+                velocity: 64,
+                ..Note::new(64, 0.5, 1.0)
+            },
+            Note::new(67, 1.5, 0.25),
+        ];
+        let bytes = MidiWriter::new().tempo(90.0).bytes(&notes).unwrap();
+        let read = MidiRenderer::new().parse(&bytes).unwrap();
+        assert_eq!(read.len(), 3);
+        for (a, b) in read.iter().zip(notes) {
+            assert_eq!((a.pitch, a.velocity), (b.pitch, b.velocity));
+            assert!((a.start - b.start).abs() < 1e-3 && (a.duration - b.duration).abs() < 1e-3);
+        }
+
+        // format 1 with the tempo doubling after a beat in the first track,
+        // running status and a note on of velocity 0 in the second one
+        let mut bytes = b"MThd\0\0\0\x06\0\x01\0\x02\0\x60".to_vec();
+        let tempo = [0x00, 0xff, 0x51, 0x03, 0x07, 0xa1, 0x20];
+        let double = [
+            0x60, 0xff, 0x51, 0x03, 0x03, 0xd0, 0x90, 0x00, 0xff, 0x2f, 0x00,
+        ];
+        bytes.extend(b"MTrk\0\0\0\x12");
+        bytes.extend(tempo);
+        bytes.extend(double);
+        let track = [
+            0x00, 0x91, 69, 100, 0x60, 69, 0, 0x00, 72, 127, 0x60, 0x81, 72, 0, 0x00, 0xff, 0x2f,
+            0x00,
+        ];
+        bytes.extend(b"MTrk\0\0\0\x12");
+        bytes.extend(track);
+        let read = MidiRenderer::new().parse(&bytes).unwrap();
+        assert_eq!(
+            read,
+            [
+                Note::new(69, 0.0, 0.5),
+                Note {
+                    velocity: 127,
+                    ..Note::new(72, 0.5, 0.25)
+                }
+            ]
+        );
+        assert!(MidiRenderer::new()
+            .channel(Some(0))
+            .parse(&bytes)
+            .unwrap()
+            .is_empty());
+
+        let synthesizer = Synthesizer::builder().sample_rate(8000).build();
+        let pcm: Vec<f32> = MidiRenderer::new()
+            .render_notes(&read, &synthesizer)
+            .unwrap();
+        assert_eq!(pcm.len(), 6000);
+        let peak = |x: &[f32]| x.iter().fold(0f32, |a, b| a.max(b.abs()));
+        assert!((peak(&pcm[..4000]) - 100.0 / 127.0).abs() < 0.01);
+        assert!((peak(&pcm[4000..]) - 1.0).abs() < 0.01);
+
+        bytes[9] = 2;
+        assert!(MidiRenderer::new().parse(&bytes).is_err());
+        assert!(MidiRenderer::new().parse(&bytes[..30]).is_err());
+    }
+
+    #[test]
+    fn messages() {
+        let on = Message::NoteOn {
+            channel: 1,
+            pitch: 60,
+            velocity: 64,
+        };
+        assert_eq!(Message::parse(&[0x91, 60, 64]), Some(on));
+        let off = Message::NoteOff {
+            channel: 0,
+            pitch: 60,
+        };
+        assert_eq!(Message::parse(&[0x80, 60, 10]), Some(off));
+        assert_eq!(Message::parse(&[0x90, 60, 0]), Some(off));
+        assert!(Message::parse(&[0xb0, 64, 127]).is_some());
+        assert_eq!(Message::parse(&[0xe0, 0, 64]), None);
+        assert_eq!(Message::parse(&[0x90, 60]), None);
+        assert_eq!(Message::parse(&[]), None);
+    }
+
+    #[test]
+    fn contour() {
+        // a slightly out of tune A4, a blip of B4 and C5 after a pause
+        let mut contour = vec![442.0; 20];
+        contour.extend([494.0; 2]);
+        contour.extend([0.0; 5]);
+        contour.extend([523.0; 10]);
+
+        let notes = segment_contour(&contour, 0.01, 0.05);
+        let pitches: Vec<u8> = notes.iter().map(|x| x.pitch).collect();
+        assert_eq!(pitches, [69, 72]);
+        assert!((notes[0].duration - 0.2).abs() < 1e-9);
+        assert!((notes[1].start - 0.27).abs() < 1e-9);
+        assert!((notes[1].duration - 0.1).abs() < 1e-9);
+    }
+}
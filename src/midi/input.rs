@@ -0,0 +1,172 @@
+use std::io;
+use std::sync::{Arc, Mutex, PoisonError};
+
+use midir::{Ignore, MidiInput, MidiInputConnection};
+
+use super::Message;
+use crate::algs::instrument::Instrument;
+use crate::{Error, Result, Sample};
+
+/// name of the client and its ports shown by the MIDI system
+const CLIENT_NAME: &str = "muslib";
+
+/// Instrument played live from a MIDI input port
+///
+/// Messages received from the port are parsed with [Message::parse] and handled by the shared
+/// Instrument, while an [AudioCallback] renders it block by block for the audio output.
+/// The Instrument is only locked for a single message or block, so playing stays responsive.
+pub struct LiveInstrument {
+    instrument: Arc<Mutex<Instrument>>,
+    connection: Option<MidiInputConnection<()>>,
+}
+
+impl LiveInstrument {
+    /// share an Instrument, it's not connected to any port until .connect() is called
+    pub fn new(instrument: Instrument) -> Self {
+        LiveInstrument {
+            instrument: Arc::new(Mutex::new(instrument)),
+            connection: None,
+        }
+    }
+
+    /// names of the available MIDI input ports
+    pub fn ports() -> Result<Vec<String>> {
+        let input = MidiInput::new(CLIENT_NAME).map_err(device_error)?;
+        input
+            .ports()
+            .iter()
+            .map(|port| input.port_name(port).map_err(device_error))
+            .collect()
+    }
+
+    /// listen on the first input port with a name containing `port`, or the first port with None
+    ///
+    /// A previous connection is closed.
+    pub fn connect(&mut self, port: Option<&str>) -> Result<()> {
+        self.connection = None;
+        let mut input = MidiInput::new(CLIENT_NAME).map_err(device_error)?;
+        // timing clock, active sensing and system exclusive messages aren't played
+        input.ignore(Ignore::All);
+        let ports = input.ports();
+        let found = ports.iter().find(|p| match port {
+            Some(name) => input.port_name(p).is_ok_and(|n| n.contains(name)),
+            None => true,
+        });
+        let Some(found) = found else {
+            return Err(Error::InvalidParam(match port {
+                Some(name) => format!("no MIDI input port named like {:?}", name),
+                None => "no MIDI input port is available".into(),
+            }));
+        };
+        let instrument = self.instrument.clone();
+        let connection = input
+            .connect(
+                found,
+                CLIENT_NAME,
+                move |_, bytes, _| receive(&instrument, bytes),
+                (),
+            )
+            .map_err(device_error)?;
+        self.connection = Some(connection);
+        Ok(())
+    }
+
+    /// stop listening on the connected port
+    pub fn disconnect(&mut self) {
+        if let Some(connection) = self.connection.take() {
+            connection.close();
+        }
+    }
+
+    /// play raw bytes of a MIDI message as if it came from the port, unknown messages are ignored
+    pub fn receive(&self, bytes: &[u8]) {
+        receive(&self.instrument, bytes);
+    }
+
+    /// callback for an audio output rendering the shared Instrument, e.g. in a cpal stream
+    pub fn callback(&self) -> AudioCallback {
+        AudioCallback {
+            instrument: self.instrument.clone(),
+            block: Vec::new(),
+        }
+    }
+}
+
+/// handle a message from the port, called on the thread of the MIDI system
+fn receive(instrument: &Mutex<Instrument>, bytes: &[u8]) {
+    if let Some(message) = Message::parse(bytes) {
+        let mut instrument = instrument.lock().unwrap_or_else(PoisonError::into_inner);
+        instrument.handle(message);
+    }
+}
+
+/// errors of the MIDI system, some of them hold the input and can't be sent between threads
+fn device_error(err: impl std::fmt::Display) -> Error {
+    Error::Io(io::Error::other(err.to_string()))
+}
+
+/// Audio callback rendering a [LiveInstrument] block by block
+///
+/// It's moved into the audio thread, and its block buffer is allocated once for the largest
+/// output seen, so nothing is allocated in later calls.
+pub struct AudioCallback {
+    instrument: Arc<Mutex<Instrument>>,
+    block: Vec<f64>,
+}
+
+impl AudioCallback {
+    /// fill an interleaved output of `channels` with the next block, the same in every channel
+    pub fn render<S: Sample>(&mut self, output: &mut [S], channels: usize) {
+        let channels = channels.max(1);
+        let frames = output.len() / channels;
+        if self.block.len() < frames {
+            self.block.resize(frames, 0.0);
+        }
+        let block = &mut self.block[..frames];
+        {
+            let mut instrument = self
+                .instrument
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            instrument.render(block);
+        }
+        for (frame, x) in output.chunks_mut(channels).zip(block.iter()) {
+            frame.fill(S::from_f64(*x));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LiveInstrument;
+    use crate::algs::instrument::Instrument;
+
+    #[test]
+    fn live_instrument() {
+        let live = LiveInstrument::new(Instrument::new(8000.0));
+        let mut callback = live.callback();
+        let mut output = vec![0.0f32; 1600];
+
+        callback.render(&mut output, 2);
+        assert!(output.iter().all(|x| *x == 0.0));
+
+        // note on of A4, and a timing clock which isn't played
+        live.receive(&[0x90, 69, 127]);
+        live.receive(&[0xf8]);
+        callback.render(&mut output, 2);
+        assert!(output.chunks(2).all(|x| x[0] == x[1]));
+        let crossings = output
+            .chunks(2)
+            .collect::<Vec<_>>()
+            .windows(2)
+            .filter(|x| x[0][0] < 0.0 && x[1][0] >= 0.0)
+            .count();
+        assert_eq!(crossings, 43);
+
+        // a note on with velocity 0 releases the note, which fades out in the next blocks
+        live.receive(&[0x90, 69, 0]);
+        callback.render(&mut output, 2);
+        callback.render(&mut output, 2);
+        assert_eq!(output[1599], 0.0);
+    }
+}
@@ -1,15 +1,33 @@
+use std::f64::consts::PI;
 use std::fs::File;
+use std::io::{BufWriter, Cursor, Read, Seek, SeekFrom};
+use std::marker::PhantomData;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 
 use hound::{WavSpec, WavWriter};
 use symphonia::core::audio::{AudioBuffer, Signal};
-use symphonia::core::codecs::{DecoderOptions, CODEC_TYPE_NULL};
+use symphonia::core::codecs::{CodecParameters, Decoder, DecoderOptions, CODEC_TYPE_NULL};
 use symphonia::core::conv::ConvertibleSample;
-use symphonia::core::errors::Error;
-use symphonia::core::formats::FormatOptions;
-use symphonia::core::io::{MediaSourceStream, MediaSourceStreamOptions};
-use symphonia::core::meta::MetadataOptions;
+use symphonia::core::errors::Error as SymphoniaError;
+use symphonia::core::formats::{FormatOptions, FormatReader, SeekMode, SeekTo, Track};
+use symphonia::core::io::{MediaSource, MediaSourceStream, MediaSourceStreamOptions};
+use symphonia::core::meta::{MetadataLog, MetadataOptions, StandardTagKey, Tag};
 use symphonia::core::probe::Hint;
+use symphonia::core::units::{Time, TimeBase};
+
+use crate::algs::frames::FrameCutter;
+use crate::algs::problems::detect_start_stop;
+use crate::algs::resample::{Quality, Resampler};
+use crate::algs::{check_length, check_range};
+use crate::flac::FlacWriter;
+use crate::raw::{RawFormat, RawReader};
+use crate::sample::Sample;
+use crate::trace;
+#[cfg(feature = "vorbis")]
+use crate::vorbis::VorbisWriter;
+use crate::{Error, Result};
 
 /// Loader provides a facility for audio input.
 ///
@@ -23,127 +41,933 @@ use symphonia::core::probe::Hint;
 /// In muslib this has been unified with a single Loader.
 /// Essentia is using ffmpeg for the AudioLoader, we however are using Symphonia.
 pub struct Loader<T> {
-    file_path: PathBuf,
+    source: Source,
     gain: Option<f64>,
-    channel: Option<usize>,
+    gains: Vec<f64>,
+    layout: Layout,
     track: Option<usize>,
+    offset: f64,
+    duration: Option<f64>,
+    target_sample_rate: Option<u32>,
+    quality: Quality,
+    progress: Option<Box<ProgressCallback>>,
+    cancel: Option<CancelToken>,
+    normalize: Option<Target>,
+    trim: Option<(f64, f64)>,
+    raw: Option<(u32, usize, RawFormat)>,
     sample_rate: Option<u32>,
+    channels: Option<usize>,
     data: Vec<T>,
 }
 
-impl<T: ConvertibleSample> Default for Loader<T> {
+/// level the data of a Loader is normalized to
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Target {
+    /// linear peak amplitude, e.g. 1.0 for full scale
+    Peak(f64),
+    /// root mean square level in dBFS, of all channels together
+    Rms(f64),
+    /// integrated loudness in LUFS after ITU-R BS.1770, e.g. -23.0 for EBU R 128
+    Lufs(f64),
+}
+
+/// integrated loudness of channels in LUFS after ITU-R BS.1770, with all channels weighted equally
+///
+/// None for signals shorter than a block of 400 ms or quieter than the absolute gate.
+fn loudness(channels: &[Vec<f64>], sample_rate: f64) -> Option<f64> {
+    // K-weighting as a high shelf followed by a high-pass, for any sample rate
+    let biquad = |x: &[f64], b: [f64; 3], a: [f64; 3]| {
+        let (mut x1, mut x2, mut y1, mut y2) = (0.0, 0.0, 0.0, 0.0);
+        x.iter()
+            .map(|x| {
+                let y = b[0] * x + b[1] * x1 + b[2] * x2 - a[1] * y1 - a[2] * y2;
+                (x2, x1, y2, y1) = (x1, *x, y1, y);
+                y
+            })
+            .collect::<Vec<f64>>()
+    };
+    let (f0, gain, q) = (1681.974450955533, 3.999843853973347, 0.7071752369554196);
+    let k = (PI * f0 / sample_rate).tan();
+    let vh = 10f64.powf(gain / 20.0);
+    let vb = vh.powf(0.4996667741545416);
+    let a0 = 1.0 + k / q + k * k;
+    let shelf_b = [
+        (vh + vb * k / q + k * k) / a0,
+        2.0 * (k * k - vh) / a0,
+        (vh - vb * k / q + k * k) / a0,
+    ];
+    let shelf_a = [1.0, 2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0];
+    let (f0, q) = (38.13547087602444, 0.5003270373238773);
+    let k = (PI * f0 / sample_rate).tan();
+    let a0 = 1.0 + k / q + k * k;
+    let highpass_b = [1.0, -2.0, 1.0];
+    let highpass_a = [1.0, 2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0];
+    let weighted: Vec<Vec<f64>> = channels
+        .iter()
+        .map(|x| biquad(&biquad(x, shelf_b, shelf_a), highpass_b, highpass_a))
+        .collect();
+
+    // mean square of blocks of 400 ms overlapping by 75 %, summed over channels
+    let size = (0.4 * sample_rate).round() as usize;
+    let hop = (0.1 * sample_rate).round() as usize;
+    let len = weighted.iter().map(|x| x.len()).min().unwrap_or(0);
+    if size == 0 || len < size {
+        return None;
+    }
+    let powers: Vec<f64> = (0..=(len - size) / hop)
+        .map(|j| {
+            weighted
+                .iter()
+                .map(|x| {
+                    x[j * hop..j * hop + size]
+                        .iter()
+                        .map(|x| x * x)
+                        .sum::<f64>()
+                        / size as f64
+                })
+                .sum()
+        })
+        .collect();
+
+    let lufs = |power: f64| -0.691 + 10.0 * power.log10();
+    let gated = |threshold: f64| -> Option<f64> {
+        let above: Vec<f64> = powers
+            .iter()
+            .copied()
+            .filter(|p| lufs(*p) > threshold)
+            .collect();
+        (!above.is_empty()).then(|| above.iter().sum::<f64>() / above.len() as f64)
+    };
+    let relative = lufs(gated(-70.0)?) - 10.0;
+    gated(relative.max(-70.0)).map(lufs)
+}
+
+/// length of a track in seconds, if the container tells it
+fn duration(params: &CodecParameters) -> Option<f64> {
+    params
+        .n_frames
+        .and_then(|n| match (params.time_base, params.sample_rate) {
+            (Some(time_base), _) => {
+                let time = time_base.calc_time(n);
+                Some(time.seconds as f64 + time.frac)
+            }
+            (None, Some(rate)) => Some(n as f64 / rate as f64),
+            _ => None,
+        })
+}
+
+/// number of channels of a track, from its channels or their layout
+fn channel_count(params: &CodecParameters) -> Option<usize> {
+    params
+        .channels
+        .or_else(|| params.channel_layout.map(|layout| layout.into_channels()))
+        .map(|channels| channels.count())
+}
+
+/// reader of the container, metadata found while probing it and the track to read
+type Probed = (Box<dyn FormatReader>, Option<MetadataLog>, Track);
+
+/// callback of Loader::on_progress() with the seconds decoded so far and the length of the track
+type ProgressCallback = dyn FnMut(f64, Option<f64>) + Send;
+
+/// CancelToken stops decoding from another thread, e.g. when a user aborts loading a file.
+///
+/// Clones share the same state, so one of them can be given to a Loader and another kept
+/// to cancel it.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    /// create a token that is not cancelled yet
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// cancel everything using this token or its clones
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// check if the token was cancelled
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+impl<T: ConvertibleSample + Sample> Default for Loader<T> {
     fn default() -> Self {
         Self::new()
     }
 }
 
-impl<T: ConvertibleSample> Loader<T> {
+impl<T: ConvertibleSample + Sample> Loader<T> {
     /// creates a new Loader instance with empty or default values
     pub fn new() -> Self {
         Loader {
-            file_path: PathBuf::from(""),
-            gain: None,        // will default to neutral
-            channel: None,     // None will mix down all the channels to mono
-            track: None,       // defaults to the first track
+            source: Source::File(PathBuf::from("")),
+            gain: None, // will default to 1/channels when mixing down, neutral otherwise
+            gains: Vec::new(), // every channel defaults to neutral
+            layout: Layout::Mono, // will mix down all the channels to mono
+            track: None, // defaults to the first supported track
+            offset: 0.0, // from the start of the track
+            duration: None, // until the end of the track
+            target_sample_rate: None, // keeps the sample rate of the track
+            quality: Quality::Polyphase,
+            progress: None,
+            cancel: None,
+            normalize: None,   // keeps the level of the track
+            trim: None,        // keeps silence at the start and the end
+            raw: None,         // the container is probed
             sample_rate: None, // will be discovered on .load()
+            channels: None,    // will be discovered on .load()
             data: Vec::new(),
         }
     }
 
+    /// creates a Loader decoding a file held in memory, e.g. fetched over the network
+    pub fn from_bytes(bytes: Vec<u8>) -> Self {
+        let mut loader = Self::new();
+        loader.source = Source::Bytes(bytes.into());
+        loader
+    }
+
+    /// creates a Loader decoding a file from any seekable reader, e.g. a database blob
+    ///
+    /// The reader is consumed by the first .load(), .packets() or .frames(),
+    /// later ones fail with Error::InvalidParam.
+    pub fn from_reader<R: Read + Seek + Send + Sync + 'static>(reader: R) -> Self {
+        let mut loader = Self::new();
+        loader.source = Source::Reader(Mutex::new(Some(Box::new(SeekableSource(reader)))));
+        loader
+    }
+
+    /// pick a track by its index in the container, listed by .tracks()
+    ///
+    /// The first supported track is read by default.
+    /// Reading fails with Error::InvalidParam for an index out of range.
+    pub fn track(&mut self, index: usize) -> &mut Self {
+        self.track = Some(index);
+        self
+    }
+
+    /// read the source as headerless pcm instead of probing its container
+    ///
+    /// Raw buffers, e.g. dumped by embedded capture devices, don't tell their sample rate,
+    /// channels and encoding, so they have to be given. Channels are interleaved frame by frame.
+    /// Reading fails with Error::InvalidParam for a sample rate of 0 or 0 channels.
+    pub fn raw(&mut self, sample_rate: u32, channels: usize, format: RawFormat) -> &mut Self {
+        self.raw = Some((sample_rate, channels, format));
+        self
+    }
+
     /// set path to a file that this Loader will read
     pub fn file(&mut self, file_path: PathBuf) -> &mut Self {
-        self.file_path = file_path;
+        self.source = Source::File(file_path);
         self
     }
 
-    /// set gain value that will be used while mixing all the channels
+    /// set a linear gain applied to all the channels
+    ///
+    /// Without it, channels mixed down to mono are scaled by 1/channels, so the mix can't clip,
+    /// and a single channel or interleaved channels are left as they are.
     pub fn gain(&mut self, gain: f64) -> &mut Self {
         self.gain = Some(gain);
         self
     }
 
+    /// set a gain in dB applied to all the channels, see .gain()
+    pub fn gain_db(&mut self, gain: f64) -> &mut Self {
+        self.gain(10f64.powf(gain / 20.0))
+    }
+
+    /// set a gain for each channel, applied before mixing, channels without one are left as is
+    pub fn channel_gains(&mut self, gains: Vec<f64>) -> &mut Self {
+        self.gains = gains;
+        self
+    }
+
     /// pick a single channel to read from
     pub fn channel(&mut self, channel: usize) -> &mut Self {
-        self.channel = Some(channel);
+        self.layout = Layout::Channel(channel);
         self
     }
 
     /// read all channels and mix them down to mono
     pub fn mono(&mut self) -> &mut Self {
-        self.channel = None;
+        self.layout = Layout::Mono;
+        self
+    }
+
+    /// read all channels, interleaved frame by frame
+    pub fn interleaved(&mut self) -> &mut Self {
+        self.layout = Layout::Interleaved;
+        self
+    }
+
+    /// start reading at an offset in seconds from the start of the track
+    ///
+    /// The container is seeked to the offset if it supports it, otherwise the samples
+    /// before it are decoded and dropped.
+    pub fn offset(&mut self, seconds: f64) -> &mut Self {
+        self.offset = seconds.max(0.0);
+        self
+    }
+
+    /// stop reading after a duration in seconds from the offset
+    pub fn duration(&mut self, seconds: f64) -> &mut Self {
+        self.duration = Some(seconds.max(0.0));
+        self
+    }
+
+    /// resample the decoded audio to a sample rate, as it's decoded
+    ///
+    /// Tracks at other rates are converted with a Resampler, after mixing the channels.
+    pub fn target_sample_rate(&mut self, sample_rate: u32) -> &mut Self {
+        self.target_sample_rate = Some(sample_rate);
+        self
+    }
+
+    /// set the interpolation used to resample, polyphase filters by default
+    pub fn resample_quality(&mut self, quality: Quality) -> &mut Self {
+        self.quality = quality;
+        self
+    }
+
+    /// call a function after each decoded packet of .load()
+    ///
+    /// It gets the seconds of the track decoded so far and the length of the track,
+    /// if the container tells it.
+    pub fn on_progress<F: FnMut(f64, Option<f64>) + Send + 'static>(
+        &mut self,
+        callback: F,
+    ) -> &mut Self {
+        self.progress = Some(Box::new(callback));
+        self
+    }
+
+    /// stop decoding once a token is cancelled, with Error::Cancelled
+    ///
+    /// It applies to .load() as well as to .packets() and .frames().
+    pub fn cancel_token(&mut self, token: CancelToken) -> &mut Self {
+        self.cancel = Some(token);
+        self
+    }
+
+    /// scale the loaded data to a consistent level, after it's all decoded
+    ///
+    /// It applies to .load() only, as the level depends on the whole track.
+    /// Integer samples above full scale are clipped, silence is left as it is.
+    pub fn normalize(&mut self, target: Target) -> &mut Self {
+        self.normalize = Some(target);
+        self
+    }
+
+    /// remove silence from the start and the end of the loaded data, after it's all decoded
+    ///
+    /// Audio is silent where the power of 10 ms frames is at or below threshold_db in dBFS,
+    /// across all the channels read. Silence shorter than min_duration in seconds is kept.
+    /// It applies to .load() only, before normalizing. A track that is all silent is kept.
+    pub fn trim_silence(&mut self, threshold_db: f64, min_duration: f64) -> &mut Self {
+        self.trim = Some((threshold_db, min_duration.max(0.0)));
         self
     }
 
     /// execute the Loader to load and mix the data
-    pub fn load(&mut self) -> Result<&Self, Error> {
-        let file = File::open(&self.file_path).expect("Could not open file path for this Loader.");
+    ///
+    /// When cancelled, the data decoded so far is kept in this Loader with its sample rate
+    /// and number of channels, but it is neither trimmed nor normalized.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "Loader::load", level = "debug", skip_all)
+    )]
+    pub fn load(&mut self) -> Result<&Self> {
+        let mut packets = self.packets()?;
+        while let Some(block) = packets.next() {
+            // stream properties are known from the first packet, and kept on errors
+            self.sample_rate = packets.sample_rate().or(self.sample_rate);
+            self.channels = packets.channels().or(self.channels);
+            self.data.append(&mut block?);
+            if let (Some(progress), Some(time)) = (self.progress.as_mut(), packets.time()) {
+                progress(time, packets.duration());
+            }
+        }
+        if let Some((threshold_db, min_duration)) = self.trim {
+            self.apply_trim(threshold_db, min_duration);
+        }
+        if let Some(target) = self.normalize {
+            self.apply_normalization(target);
+        }
+        Ok(self)
+    }
 
-        let mut hint = Hint::new();
-        if let Some(ext) = self.file_path.extension() {
-            if let Some(ext_s) = ext.to_str() {
-                hint.with_extension(ext_s);
+    /// drop the silent samples at the start and the end of the loaded data
+    fn apply_trim(&mut self, threshold_db: f64, min_duration: f64) {
+        let channels = match self.layout {
+            Layout::Interleaved => self.channels.unwrap_or(1).max(1),
+            _ => 1,
+        };
+        // the root mean square across channels for each sample
+        let rms: Vec<f64> = self
+            .data
+            .chunks(channels)
+            .map(|c| (c.iter().map(|x| x.to_f64().powi(2)).sum::<f64>() / c.len() as f64).sqrt())
+            .collect();
+        let rate = self.sample_rate.unwrap_or(44100) as f64;
+        let frame_size = ((rate * 0.01).round() as usize).max(1);
+        let power = 10f64.powf(threshold_db / 10.0);
+        let min_length = (min_duration * rate).round() as usize;
+        if let Some((start, end)) = detect_start_stop(&rms, frame_size, frame_size, power) {
+            let start = if start >= min_length { start } else { 0 };
+            let end = if rms.len() - end >= min_length {
+                end
+            } else {
+                rms.len()
+            };
+            self.data.truncate(end * channels);
+            self.data.drain(..start * channels);
+        }
+    }
+
+    /// scale the loaded data by the gain that brings its level to the target
+    fn apply_normalization(&mut self, target: Target) {
+        let channels = match self.layout {
+            Layout::Interleaved => self.channels.unwrap_or(1).max(1),
+            _ => 1,
+        };
+        let x: Vec<f64> = self.data.iter().map(|x| x.to_f64()).collect();
+        let db = |power: f64| 10.0 * power.log10();
+        let gain = match target {
+            Target::Peak(peak) => peak / x.iter().fold(0.0, |max: f64, x| max.max(x.abs())),
+            Target::Rms(rms) => {
+                let power = x.iter().map(|x| x * x).sum::<f64>() / x.len() as f64;
+                10f64.powf((rms - db(power)) / 20.0)
             }
+            Target::Lufs(lufs) => {
+                let planar: Vec<Vec<f64>> = (0..channels)
+                    .map(|c| x.iter().skip(c).step_by(channels).copied().collect())
+                    .collect();
+                let rate = self.sample_rate.unwrap_or(44100) as f64;
+                match loudness(&planar, rate) {
+                    Some(level) => 10f64.powf((lufs - level) / 20.0),
+                    None => return,
+                }
+            }
+        };
+        if gain.is_finite() && gain > 0.0 {
+            self.data = x.iter().map(|x| T::from_f64(x * gain)).collect();
         }
+    }
 
-        let mss_opts = MediaSourceStreamOptions::default();
-        let mss = MediaSourceStream::new(Box::new(file), mss_opts);
-
-        let format_opts = FormatOptions::default();
-        let metadata_opts = MetadataOptions::default();
-        let probe = symphonia::default::get_probe()
-            .format(&hint, mss, &format_opts, &metadata_opts)
-            .expect("Could not recognize and load this file format.");
-
-        let mut format = probe.format;
-        let track = self
-            .track
-            .and_then(|t| format.tracks().get(t))
-            .or_else(|| {
-                format
-                    .tracks()
-                    .iter()
-                    .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
+    /// read the tags and stream properties of the track without decoding it
+    ///
+    /// A reader given to .from_reader() is consumed by it, like by .load().
+    pub fn metadata(&self) -> Result<Metadata> {
+        let (mut format, mut probed, track) = self.probe()?;
+        let params = &track.codec_params;
+        let duration = duration(params);
+        let bytes = match &self.source {
+            Source::File(file_path) => Some(std::fs::metadata(file_path)?.len()),
+            Source::Bytes(bytes) => Some(bytes.len() as u64),
+            Source::Reader(_) => None,
+        };
+
+        let mut metadata = Metadata {
+            duration,
+            codec: symphonia::default::get_codecs()
+                .get_codec(params.codec)
+                .map(|codec| codec.short_name.to_string()),
+            bitrate: bytes
+                .zip(duration.filter(|d| *d > 0.0))
+                .map(|(bytes, duration)| (bytes as f64 * 8.0 / duration).round() as u32),
+            sample_rate: params.sample_rate,
+            channels: channel_count(params),
+            ..Metadata::default()
+        };
+        // tags found while probing, e.g. ID3, come before the tags of the container
+        if let Some(log) = probed.as_mut() {
+            if let Some(revision) = log.metadata().current() {
+                metadata.add_tags(revision.tags());
+            }
+        }
+        if let Some(revision) = format.metadata().current() {
+            metadata.add_tags(revision.tags());
+        }
+        Ok(metadata)
+    }
+
+    /// list the tracks of the container, with their index to pick one with .track()
+    ///
+    /// A reader given to .from_reader() is consumed by it, like by .load().
+    pub fn tracks(&self) -> Result<Vec<TrackInfo>> {
+        let (format, _) = self.open()?;
+        let codecs = symphonia::default::get_codecs();
+        Ok(format
+            .tracks()
+            .iter()
+            .enumerate()
+            .map(|(index, track)| {
+                let params = &track.codec_params;
+                TrackInfo {
+                    index,
+                    codec: codecs
+                        .get_codec(params.codec)
+                        .map(|codec| codec.short_name.to_string()),
+                    supported: params.codec != CODEC_TYPE_NULL
+                        && codecs.get_codec(params.codec).is_some(),
+                    language: track.language.clone(),
+                    sample_rate: params.sample_rate,
+                    channels: channel_count(params),
+                    duration: duration(params),
+                }
             })
-            .expect("Could not find any supported audio tracks.");
+            .collect())
+    }
+
+    /// open the source, probe its container and pick the track to read
+    ///
+    /// Metadata found while probing, outside of the container, is returned with the reader.
+    fn probe(&self) -> Result<Probed> {
+        let (format, probed) = self.open()?;
+        let track = match self.track {
+            Some(t) => format.tracks().get(t).ok_or_else(|| {
+                Error::InvalidParam(format!(
+                    "track {} is out of range for a file with {} tracks",
+                    t,
+                    format.tracks().len()
+                ))
+            })?,
+            None => format
+                .tracks()
+                .iter()
+                .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
+                .ok_or_else(|| {
+                    Error::UnsupportedFormat("could not find any supported audio tracks".into())
+                })?,
+        }
+        .clone();
+        Ok((format, probed, track))
+    }
+
+    /// open the source and probe its container, unless it's raw pcm
+    fn open(&self) -> Result<(Box<dyn FormatReader>, Option<MetadataLog>)> {
+        let mut hint = Hint::new();
+        let source: Box<dyn MediaSource> = match &self.source {
+            Source::File(file_path) => {
+                if let Some(ext) = file_path.extension() {
+                    if let Some(ext_s) = ext.to_str() {
+                        hint.with_extension(ext_s);
+                    }
+                }
+                Box::new(File::open(file_path)?)
+            }
+            Source::Bytes(bytes) => Box::new(Cursor::new(bytes.clone())),
+            Source::Reader(reader) => reader
+                .lock()
+                .map_err(|_| Error::InvalidParam("the reader is poisoned".into()))?
+                .take()
+                .ok_or_else(|| Error::InvalidParam("the reader was already read".into()))?,
+        };
+
+        let mss_opts = MediaSourceStreamOptions::default();
+        let mss = MediaSourceStream::new(source, mss_opts);
+
+        let (format, probed): (Box<dyn FormatReader>, _) = match self.raw {
+            Some((sample_rate, channels, raw)) => (
+                Box::new(RawReader::new(mss, sample_rate, channels, raw)?),
+                None,
+            ),
+            None => {
+                let format_opts = FormatOptions::default();
+                let metadata_opts = MetadataOptions::default();
+                let probe = symphonia::default::get_probe().format(
+                    &hint,
+                    mss,
+                    &format_opts,
+                    &metadata_opts,
+                )?;
+                (probe.format, probe.metadata.into_inner())
+            }
+        };
+        Ok((format, probed))
+    }
+
+    /// open the file and decode it lazily, one packet at a time
+    ///
+    /// Decoded blocks are mixed down the same way as with .load(),
+    /// but they are not stored in this Loader.
+    ///
+    /// Fails with Error::InvalidParam for a target sample rate of 0.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "Loader::packets", level = "debug", skip_all)
+    )]
+    pub fn packets(&self) -> Result<Packets<T>> {
+        if self.target_sample_rate == Some(0) {
+            return Err(Error::InvalidParam(
+                "the target sample rate must be positive".to_string(),
+            ));
+        }
+        let (mut format, _, track) = self.probe()?;
         let track_id = track.id;
+        let n_frames = track.codec_params.n_frames;
+        let time_base = track.codec_params.time_base;
 
         let decode_opts = DecoderOptions::default();
-        let mut decoder = symphonia::default::get_codecs()
-            .make(&track.codec_params, &decode_opts)
-            .expect("The codec is unsupported.");
+        let mut decoder =
+            symphonia::default::get_codecs().make(&track.codec_params, &decode_opts)?;
+
+        // frames before the first packet, which is past the start of the track after seeking
+        let mut decoded = 0;
+        if self.offset > 0.0 {
+            // seeking is only a shortcut, samples before the offset are dropped while decoding
+            let seek = format.seek(
+                SeekMode::Accurate,
+                SeekTo::Time {
+                    time: Time::from(self.offset),
+                    track_id: Some(track_id),
+                },
+            );
+            if let Ok(seeked) = seek {
+                decoder.reset();
+                if let Some(rate) = track.codec_params.sample_rate {
+                    decoded = to_frames(seeked.actual_ts, time_base, rate);
+                }
+            }
+        }
 
-        // decode
+        Ok(Packets {
+            format,
+            decoder,
+            track_id,
+            n_frames,
+            time_base,
+            decoded,
+            layout: self.layout,
+            offset: self.offset,
+            duration: self.duration,
+            target_sample_rate: self.target_sample_rate,
+            quality: self.quality,
+            resamplers: Vec::new(),
+            cancel: self.cancel.clone(),
+            gain: self.gain,
+            gains: self.gains.clone(),
+            sample_rate: None,
+            channels: None,
+            finished: false,
+            sample: PhantomData,
+        })
+    }
+
+    /// read the loaded pcm data as a vector, interleaved if all channels were read
+    pub fn data(&self) -> Vec<T> {
+        self.data.clone()
+    }
+
+    /// read the loaded pcm data split into a vector for each channel
+    ///
+    /// Unless all channels were read with .interleaved(), there is a single one.
+    pub fn planar(&self) -> Vec<Vec<T>> {
+        let channels = match self.layout {
+            Layout::Interleaved => self.channels.unwrap_or(1).max(1),
+            _ => 1,
+        };
+        (0..channels)
+            .map(|c| {
+                self.data
+                    .iter()
+                    .skip(c)
+                    .step_by(channels)
+                    .copied()
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// read the loaded sample rate
+    pub fn sample_rate(&self) -> Option<u32> {
+        self.sample_rate
+    }
+
+    /// read the number of channels in the loaded track
+    pub fn channels(&self) -> Option<usize> {
+        self.channels
+    }
+
+    /// open the file and cut it lazily into overlapping frames
+    ///
+    /// Only the packets needed for the next frame are decoded, so long files are never held
+    /// in memory as a whole. The last frame is padded with silence.
+    /// Fails with Error::InvalidParam if the frame or hop size is 0.
+    pub fn frames(&self, frame_size: usize, hop_size: usize) -> Result<Frames<T>> {
+        if frame_size == 0 || hop_size == 0 {
+            return Err(Error::InvalidParam(
+                "frame_size and hop_size must be positive".into(),
+            ));
+        }
+        Ok(Frames {
+            packets: self.packets()?,
+            cutter: FrameCutter::new(frame_size, hop_size),
+            finished: false,
+        })
+    }
+}
+
+/// TrackInfo describes a track of a container, e.g. one of the audio streams of an MKA file.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TrackInfo {
+    /// index of the track in the container, to pick it with Loader::track()
+    pub index: usize,
+    /// short name of the codec, e.g. "flac" or "vorbis", None if it's unknown
+    pub codec: Option<String>,
+    /// whether the track can be decoded
+    pub supported: bool,
+    /// language of the track, e.g. "eng"
+    pub language: Option<String>,
+    /// sample rate of the track
+    pub sample_rate: Option<u32>,
+    /// number of channels of the track
+    pub channels: Option<usize>,
+    /// length of the track in seconds
+    pub duration: Option<f64>,
+}
+
+/// Metadata of a track read by a Loader, from its tags and stream properties.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Metadata {
+    /// title of the track
+    pub title: Option<String>,
+    /// artist performing the track
+    pub artist: Option<String>,
+    /// album the track is on
+    pub album: Option<String>,
+    /// number of the track on the album
+    pub track: Option<u32>,
+    /// length of the track in seconds
+    pub duration: Option<f64>,
+    /// short name of the codec, e.g. "flac" or "pcm_s16le"
+    pub codec: Option<String>,
+    /// average bitrate in bits per second, including the container
+    pub bitrate: Option<u32>,
+    /// sample rate of the track
+    pub sample_rate: Option<u32>,
+    /// number of channels of the track
+    pub channels: Option<usize>,
+    /// all tags as keys and values, with the keys used by the format of the file
+    pub tags: Vec<(String, String)>,
+}
+
+impl Metadata {
+    /// collect tags, filling the standard fields from the first tag of their kind
+    fn add_tags(&mut self, tags: &[Tag]) {
+        for tag in tags {
+            // strings of some formats are padded with nulls
+            let value = tag.value.to_string().trim_end_matches('\0').to_string();
+            let field = match tag.std_key {
+                Some(StandardTagKey::TrackTitle) => Some(&mut self.title),
+                Some(StandardTagKey::Artist) => Some(&mut self.artist),
+                Some(StandardTagKey::Album) => Some(&mut self.album),
+                _ => None,
+            };
+            if let Some(field) = field {
+                field.get_or_insert_with(|| value.clone());
+            }
+            if tag.std_key == Some(StandardTagKey::TrackNumber) && self.track.is_none() {
+                // numbers like 3/12 also give the total number of tracks
+                self.track = value.split('/').next().and_then(|x| x.trim().parse().ok());
+            }
+            self.tags.push((tag.key.clone(), value));
+        }
+    }
+}
+
+/// where the encoded data read by a Loader comes from
+enum Source {
+    File(PathBuf),
+    Bytes(Arc<[u8]>),
+    Reader(Mutex<Option<Box<dyn MediaSource>>>),
+}
+
+/// reader that can seek, which Symphonia only provides for files and cursors
+struct SeekableSource<R>(R);
+
+impl<R: Read> Read for SeekableSource<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+
+impl<R: Seek> Seek for SeekableSource<R> {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        self.0.seek(pos)
+    }
+}
+
+impl<R: Read + Seek + Send + Sync> MediaSource for SeekableSource<R> {
+    fn is_seekable(&self) -> bool {
+        true
+    }
+
+    fn byte_len(&self) -> Option<u64> {
+        None
+    }
+}
+
+/// which channels of a track are read and how
+#[derive(Debug, Clone, Copy)]
+enum Layout {
+    /// all channels mixed down to one
+    Mono,
+    /// a single channel
+    Channel(usize),
+    /// all channels, interleaved frame by frame
+    Interleaved,
+}
+
+/// Packets lazily decodes a file opened by a Loader.
+///
+/// It is an iterator over blocks of samples, one block for each decoded packet,
+/// with all channels interleaved or mixed down the same way as in the Loader.
+pub struct Packets<T> {
+    format: Box<dyn FormatReader>,
+    decoder: Box<dyn Decoder>,
+    track_id: u32,
+    n_frames: Option<u64>,
+    time_base: Option<TimeBase>,
+    decoded: u64,
+    layout: Layout,
+    offset: f64,
+    duration: Option<f64>,
+    target_sample_rate: Option<u32>,
+    quality: Quality,
+    resamplers: Vec<Resampler>,
+    cancel: Option<CancelToken>,
+    gain: Option<f64>,
+    gains: Vec<f64>,
+    sample_rate: Option<u32>,
+    channels: Option<usize>,
+    finished: bool,
+    sample: PhantomData<T>,
+}
+
+impl<T: ConvertibleSample + Sample> Packets<T> {
+    /// read the sample rate of packets decoded so far, or the target rate they are resampled to
+    pub fn sample_rate(&self) -> Option<u32> {
+        self.sample_rate
+            .map(|rate| self.target_sample_rate.unwrap_or(rate))
+    }
+
+    /// read the number of channels of packets decoded so far
+    pub fn channels(&self) -> Option<usize> {
+        self.channels
+    }
+
+    /// fraction of the track decoded so far, if the length of the track is known
+    pub fn progress(&self) -> Option<f64> {
+        self.n_frames
+            .filter(|n| *n > 0)
+            .map(|n| (self.decoded as f64 / n as f64).min(1.0))
+    }
+
+    /// seconds of the track decoded so far
+    pub fn time(&self) -> Option<f64> {
+        self.sample_rate
+            .map(|rate| self.decoded as f64 / rate as f64)
+    }
+
+    /// length of the track in seconds, if it's known
+    pub fn duration(&self) -> Option<f64> {
+        self.n_frames
+            .zip(self.sample_rate)
+            .map(|(n, rate)| n as f64 / rate as f64)
+    }
+
+    /// gain of a channel of a track, combining the gain of all channels with its own
+    fn gain(&self, channel: usize, channels: usize) -> f64 {
+        let gain = self.gain.unwrap_or(match self.layout {
+            Layout::Mono => 1.0 / channels as f64,
+            _ => 1.0,
+        });
+        gain * self.gains.get(channel).copied().unwrap_or(1.0)
+    }
+
+    /// resample a block to the target sample rate, channel by channel
+    fn resample(&mut self, block: Vec<T>, rate: u32) -> Result<Vec<T>> {
+        let target = match self.target_sample_rate {
+            Some(target) if target != rate => target,
+            _ => return Ok(block),
+        };
+        let channels = match self.layout {
+            Layout::Interleaved => self.channels.unwrap_or(1),
+            _ => 1,
+        };
+        if self.resamplers.is_empty() {
+            self.resamplers = (0..channels)
+                .map(|_| Resampler::new(rate as f64, target as f64, self.quality))
+                .collect::<Result<_>>()?;
+        }
+        let planar = self
+            .resamplers
+            .iter_mut()
+            .enumerate()
+            .map(|(c, resampler)| {
+                let x: Vec<f64> = block
+                    .iter()
+                    .skip(c)
+                    .step_by(channels)
+                    .map(|x| x.to_f64())
+                    .collect();
+                resampler.push(&x)
+            })
+            .collect();
+        Ok(interleave(planar))
+    }
+
+    /// remaining resampled samples at the end of the track
+    fn flush(&mut self) -> Option<Vec<T>> {
+        let planar: Vec<Vec<f64>> = std::mem::take(&mut self.resamplers)
+            .iter_mut()
+            .map(|resampler| resampler.flush())
+            .collect();
+        let block: Vec<T> = interleave(planar);
+        (!block.is_empty()).then_some(block)
+    }
+
+    fn next_block(&mut self) -> Result<Option<Vec<T>>> {
         loop {
-            let packet = match format.next_packet() {
+            let packet = match self.format.next_packet() {
                 Ok(packet) => packet,
                 // finished reading the file
-                Err(Error::ResetRequired) => {
-                    return Ok(self);
+                Err(SymphoniaError::ResetRequired) => {
+                    return Ok(None);
                 }
-                Err(Error::DecodeError(_)) | Err(Error::IoError(_)) => {
-                    return Ok(self);
+                Err(SymphoniaError::DecodeError(_)) | Err(SymphoniaError::IoError(_)) => {
+                    return Ok(None);
                 }
                 // real errors
                 Err(err) => {
-                    return Err(err);
+                    return Err(err.into());
                 }
             };
 
-            if packet.track_id() != track_id {
+            if packet.track_id() != self.track_id {
                 continue;
             }
 
-            while !format.metadata().is_latest() {
-                format.metadata().pop();
+            while !self.format.metadata().is_latest() {
+                self.format.metadata().pop();
             }
 
-            match decoder.decode(&packet) {
+            let _span = trace::frame_span("Packets::decode");
+            match self.decoder.decode(&packet) {
                 Ok(decoded) => {
                     let spec = *decoded.spec();
                     if let Some(rate) = self.sample_rate {
                         if rate != spec.rate {
                             // sample rate mismatch error between reads, stream changed
-                            return Err(Error::ResetRequired);
+                            return Err(Error::Decode(format!(
+                                "sample rate changed from {} Hz to {} Hz",
+                                rate, spec.rate
+                            )));
                         }
                     } else {
                         self.sample_rate = Some(spec.rate);
@@ -154,62 +978,183 @@ impl<T: ConvertibleSample> Loader<T> {
 
                     let len = decoded.frames(); // n of samples in each channel
                     let channels = data.spec().channels.count();
+                    self.channels = Some(channels);
+                    self.decoded += len as u64;
 
-                    let mut buf = Vec::<T>::with_capacity(len);
-                    let channel = data.chan(self.channel.unwrap_or(0));
-
-                    for ch in channel.iter().take(len) {
-                        buf.push(self.apply_gain(*ch));
+                    // part of the packet within the requested span, in frames from the start of the track
+                    let rate = spec.rate as f64;
+                    let start = (self.offset * rate).round() as u64;
+                    let end = self.duration.map(|d| start + (d * rate).round() as u64);
+                    let ts = to_frames(packet.ts(), self.time_base, spec.rate);
+                    if end.is_some_and(|end| ts >= end) {
+                        return Ok(None);
                     }
+                    let first = (start.saturating_sub(ts) as usize).min(len);
+                    let last = end.map_or(len, |end| ((end - ts) as usize).min(len));
+                    if first >= last {
+                        continue;
+                    }
+                    let frames = first..last;
 
-                    if self.channel.is_none() {
-                        // mixing down to mono
-                        for ch in 1..channels {
-                            let channel = data.chan(ch);
-                            for i in 0..len {
-                                buf[i] = buf[i] + self.apply_gain(channel[i]);
-                            }
+                    let gains: Vec<f64> = (0..channels).map(|c| self.gain(c, channels)).collect();
+                    let sample = |c: usize, i: usize| {
+                        let x = data.chan(c)[i];
+                        // samples are left untouched by a neutral gain, not even converted
+                        if gains[c] == 1.0 {
+                            x
+                        } else {
+                            T::from_f64(x.to_f64() * gains[c])
                         }
-                    }
+                    };
 
-                    self.data.append(&mut buf);
+                    let buf: Vec<T> = match self.layout {
+                        Layout::Channel(c) if c >= channels => {
+                            return Err(Error::InvalidParam(format!(
+                                "channel {} is out of range for a track with {} channels",
+                                c, channels
+                            )));
+                        }
+                        Layout::Channel(c) => frames.map(|i| sample(c, i)).collect(),
+                        Layout::Mono if channels == 1 => frames.map(|i| sample(0, i)).collect(),
+                        // mixing down to mono
+                        Layout::Mono => frames
+                            .map(|i| {
+                                let mix =
+                                    (0..channels).map(|c| data.chan(c)[i].to_f64() * gains[c]);
+                                T::from_f64(mix.sum())
+                            })
+                            .collect(),
+                        Layout::Interleaved => frames
+                            .flat_map(|i| (0..channels).map(move |c| (c, i)))
+                            .map(|(c, i)| sample(c, i))
+                            .collect(),
+                    };
+
+                    return self.resample(buf, spec.rate).map(Some);
                 }
-                Err(Error::ResetRequired) => {
+                Err(SymphoniaError::ResetRequired) => {
                     // stream changed, so we finished reading the file
-                    return Ok(self);
+                    return Ok(None);
                 }
-                Err(Error::DecodeError(_)) | Err(Error::IoError(_)) => {
+                Err(SymphoniaError::DecodeError(_)) | Err(SymphoniaError::IoError(_)) => {
                     // the packet can be discarded
                     continue;
                 }
-                Err(err) => return Err(err),
+                Err(err) => return Err(err.into()),
             }
         }
     }
+}
 
-    fn apply_gain(&self, x: T) -> T {
-        // TODO
-        x
+impl<T: ConvertibleSample + Sample> Iterator for Packets<T> {
+    type Item = Result<Vec<T>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        if self
+            .cancel
+            .as_ref()
+            .is_some_and(|token| token.is_cancelled())
+        {
+            self.finished = true;
+            return Some(Err(Error::Cancelled));
+        }
+        let block = self.next_block();
+        if !matches!(block, Ok(Some(_))) {
+            self.finished = true;
+        }
+        if matches!(block, Ok(None)) {
+            return self.flush().map(Ok);
+        }
+        block.transpose()
     }
+}
 
-    /// read the loaded pcm data as a vector
-    pub fn data(&self) -> Vec<T> {
-        self.data.clone()
+/// convert a timestamp of a track to frames at its sample rate
+///
+/// Timestamps count frames, unless the track has a time base, e.g. milliseconds in MKV files.
+fn to_frames(ts: u64, time_base: Option<TimeBase>, rate: u32) -> u64 {
+    match time_base {
+        Some(time_base) => {
+            let time = time_base.calc_time(ts);
+            ((time.seconds as f64 + time.frac) * rate as f64).round() as u64
+        }
+        None => ts,
     }
+}
 
-    /// read the loaded sample rate
+/// samples of channels interleaved frame by frame, as long as the shortest channel
+fn interleave<T: Sample>(planar: Vec<Vec<f64>>) -> Vec<T> {
+    let len = planar.iter().map(|x| x.len()).min().unwrap_or(0);
+    let mut interleaved = Vec::with_capacity(len * planar.len());
+    for i in 0..len {
+        interleaved.extend(planar.iter().map(|x| T::from_f64(x[i])));
+    }
+    interleaved
+}
+
+/// Frames lazily cuts a file opened by a Loader into overlapping frames.
+///
+/// It is an iterator over frames of mono samples, decoding packets only as they are needed.
+pub struct Frames<T> {
+    packets: Packets<T>,
+    cutter: FrameCutter<T>,
+    finished: bool,
+}
+
+impl<T: ConvertibleSample + Sample> Frames<T> {
+    /// read the sample rate of packets decoded so far
     pub fn sample_rate(&self) -> Option<u32> {
-        self.sample_rate
+        self.packets.sample_rate()
+    }
+
+    /// read the number of channels of packets decoded so far
+    pub fn channels(&self) -> Option<usize> {
+        self.packets.channels()
+    }
+
+    /// fraction of the track decoded so far, if the length of the track is known
+    pub fn progress(&self) -> Option<f64> {
+        self.packets.progress()
+    }
+}
+
+impl<T: ConvertibleSample + Sample> Iterator for Frames<T> {
+    type Item = Result<Vec<T>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(frame) = self.cutter.pop() {
+                return Some(Ok(frame));
+            }
+            if self.finished {
+                return self.cutter.flush().map(Ok);
+            }
+            match self.packets.next() {
+                Some(Ok(block)) => self.cutter.push(&block),
+                Some(Err(err)) => {
+                    self.finished = true;
+                    return Some(Err(err));
+                }
+                None => self.finished = true,
+            }
+        }
     }
 }
 
 /// Writer provides a facility for audio output.
 ///
 /// Essentia is using ffmpeg for the AudioWriter,
-/// we however are using hound and for now only support output to simple 16-bit WAV files.
+/// we however are using hound for WAV files,
+/// with any number of channels of 8, 16, 24 or 32-bit integer or 32-bit float samples,
+/// and our own encoder for FLAC files of up to 8 channels of 8, 16 or 24-bit integer samples,
+/// and for Ogg Vorbis files with the vorbis feature.
 pub struct Writer {
     file_path: PathBuf,
     spec: WavSpec,
+    format: Option<Format>,
 }
 
 impl Default for Writer {
@@ -229,6 +1174,7 @@ impl Writer {
                 bits_per_sample: 16,
                 sample_format: hound::SampleFormat::Int,
             },
+            format: None,
         }
     }
 
@@ -244,18 +1190,1203 @@ impl Writer {
         self
     }
 
-    /// execute the Writer to store data in a file
-    pub fn write(&self, data: &[u16]) -> Result<(), ()> {
-        let mut writer = WavWriter::create(&self.file_path, self.spec)
-            .expect("Failed to create a file for the Writer.");
+    /// set the number of bits of each sample, 16 by default
+    pub fn bits_per_sample(&mut self, bits_per_sample: u16) -> &mut Self {
+        self.spec.bits_per_sample = bits_per_sample;
+        self
+    }
+
+    /// set the encoding of samples, integer by default
+    pub fn sample_format(&mut self, sample_format: SampleFormat) -> &mut Self {
+        self.spec.sample_format = match sample_format {
+            SampleFormat::Int => hound::SampleFormat::Int,
+            SampleFormat::Float => hound::SampleFormat::Float,
+        };
+        self
+    }
+
+    /// set the format of the file, guessed from the file extension by default
+    pub fn format(&mut self, format: Format) -> &mut Self {
+        self.format = Some(format);
+        self
+    }
+
+    /// set the number of channels that will be written, 1 by default
+    pub fn channels(&mut self, channels: u16) -> &mut Self {
+        self.spec.channels = channels;
+        self
+    }
+
+    /// execute the Writer to store data of any sample type in a file
+    ///
+    /// With more than one channel the data is interleaved, see .write_interleaved().
+    pub fn write<S: Sample>(&self, data: &[S]) -> Result<()> {
+        self.write_interleaved(data)
+    }
+
+    /// store samples of all channels interleaved frame by frame
+    ///
+    /// Fails with Error::InvalidParam if the data doesn't split evenly into the channels.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "Writer::write", level = "debug", skip_all)
+    )]
+    pub fn write_interleaved<S: Sample>(&self, data: &[S]) -> Result<()> {
+        let channels = self.spec.channels as usize;
+        if channels == 0 || !data.len().is_multiple_of(channels) {
+            return Err(Error::InvalidParam(format!(
+                "{} samples can't be split evenly into {} channels",
+                data.len(),
+                channels
+            )));
+        }
+        let mut stream = self.open()?;
+        stream.write(data)?;
+        stream.finalize()
+    }
+
+    /// store a vector of samples for each channel, e.g. stems of a mix
+    ///
+    /// Fails with Error::InvalidParam unless there is a vector for every channel,
+    /// all of the same length.
+    pub fn write_planar<S: Sample>(&self, data: &[Vec<S>]) -> Result<()> {
+        let len = data.first().map_or(0, |x| x.len());
+        if data.len() != self.spec.channels as usize || data.iter().any(|x| x.len() != len) {
+            return Err(Error::InvalidParam(format!(
+                "expected {} channels of the same length",
+                self.spec.channels
+            )));
+        }
+        let interleaved: Vec<S> = (0..len)
+            .flat_map(|i| data.iter().map(move |x| x[i]))
+            .collect();
+        self.write_interleaved(&interleaved)
+    }
+
+    /// create the file and write data to it in consecutive blocks
+    ///
+    /// The file is complete once the returned Stream is finalized or dropped.
+    /// Fails with Error::InvalidParam for integers of other than 8, 16, 24 or 32 bits
+    /// and floats of other than 32 bits, or for samples FLAC can't store.
+    pub fn open(&self) -> Result<Stream> {
+        let format = self.format.unwrap_or_else(|| {
+            match self.file_path.extension().and_then(|x| x.to_str()) {
+                Some(x) if x.eq_ignore_ascii_case("flac") => Format::Flac,
+                #[cfg(feature = "vorbis")]
+                Some(x) if x.eq_ignore_ascii_case("ogg") || x.eq_ignore_ascii_case("oga") => {
+                    Format::OggVorbis
+                }
+                _ => Format::Wav,
+            }
+        });
+        #[cfg(feature = "vorbis")]
+        if format == Format::OggVorbis {
+            let writer =
+                VorbisWriter::create(&self.file_path, self.spec.channels, self.spec.sample_rate)?;
+            return Ok(Stream {
+                encoder: Encoder::Vorbis(Box::new(writer)),
+            });
+        }
+        if format == Format::Flac {
+            if self.spec.sample_format != hound::SampleFormat::Int {
+                return Err(Error::InvalidParam(
+                    "FLAC only stores integer samples".to_string(),
+                ));
+            }
+            let writer = FlacWriter::create(
+                &self.file_path,
+                self.spec.channels,
+                self.spec.sample_rate,
+                self.spec.bits_per_sample,
+            )?;
+            return Ok(Stream {
+                encoder: Encoder::Flac(writer),
+            });
+        }
+        let supported = match self.spec.sample_format {
+            hound::SampleFormat::Int => [8, 16, 24, 32].contains(&self.spec.bits_per_sample),
+            hound::SampleFormat::Float => self.spec.bits_per_sample == 32,
+        };
+        if !supported {
+            return Err(Error::InvalidParam(format!(
+                "can't write {}-bit {:?} samples",
+                self.spec.bits_per_sample, self.spec.sample_format
+            )));
+        }
+        Ok(Stream {
+            encoder: Encoder::Wav(WavWriter::create(&self.file_path, self.spec)?),
+        })
+    }
+}
+
+/// container and codec of files written by the Writer
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    /// uncompressed WAV
+    Wav,
+    /// lossless FLAC
+    Flac,
+    /// lossy Ogg Vorbis of a fixed quality, any sample format and size is encoded the same
+    #[cfg(feature = "vorbis")]
+    OggVorbis,
+}
+
+/// encoding of samples written by the Writer
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SampleFormat {
+    /// signed integers, or unsigned ones for 8 bits
+    Int,
+    /// floating point values in range [-1, 1]
+    Float,
+}
+
+/// integer sample of a number of bits from a floating point value in range [-1, 1]
+fn integer(x: f64, bits: u16) -> i32 {
+    let scale = (1i64 << (bits - 1)) as f64;
+    (x * scale).round().clamp(-scale, scale - 1.0) as i32
+}
+
+/// Stream of blocks written to a file opened with Writer::open()
+pub struct Stream {
+    encoder: Encoder,
+}
+
+/// backend encoding the samples of a Stream
+enum Encoder {
+    Wav(WavWriter<BufWriter<File>>),
+    Flac(FlacWriter),
+    #[cfg(feature = "vorbis")]
+    Vorbis(Box<VorbisWriter>),
+}
+
+impl Stream {
+    /// append a block of samples of any sample type at the end of the file
+    pub fn write<S: Sample>(&mut self, data: &[S]) -> Result<()> {
+        match &mut self.encoder {
+            Encoder::Wav(writer) => {
+                let spec = writer.spec();
+                for t in data.iter() {
+                    match (spec.sample_format, spec.bits_per_sample) {
+                        (hound::SampleFormat::Float, _) => writer.write_sample(t.to_f64() as f32)?,
+                        (_, 16) => writer.write_sample(i16::from_sample(*t))?,
+                        (_, bits) => writer.write_sample(integer(t.to_f64(), bits))?,
+                    }
+                }
+                Ok(())
+            }
+            Encoder::Flac(writer) => {
+                let bits = writer.bits_per_sample();
+                let block: Vec<i32> = data.iter().map(|t| integer(t.to_f64(), bits)).collect();
+                writer.write(&block)
+            }
+            #[cfg(feature = "vorbis")]
+            Encoder::Vorbis(writer) => {
+                let block: Vec<f64> = data.iter().map(|t| t.to_f64()).collect();
+                writer.write(&block)
+            }
+        }
+    }
+
+    /// number of samples written so far
+    pub fn len(&self) -> u32 {
+        match &self.encoder {
+            Encoder::Wav(writer) => writer.len(),
+            Encoder::Flac(writer) => writer.len() as u32,
+            #[cfg(feature = "vorbis")]
+            Encoder::Vorbis(writer) => writer.len() as u32,
+        }
+    }
+
+    /// check if no samples were written yet
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// update the header and flush the file, so the samples written so far can be read
+    ///
+    /// The stream stays open, e.g. to keep a recording readable while it's being made.
+    /// FLAC files only include complete frames of 4096 samples of each channel,
+    /// and Ogg Vorbis files steps of 1024 samples of each channel, except the last one.
+    pub fn flush(&mut self) -> Result<()> {
+        match &mut self.encoder {
+            Encoder::Wav(writer) => Ok(writer.flush()?),
+            Encoder::Flac(writer) => writer.flush(),
+            #[cfg(feature = "vorbis")]
+            Encoder::Vorbis(writer) => writer.flush(),
+        }
+    }
+
+    /// update the header and flush the file
+    pub fn finalize(self) -> Result<()> {
+        match self.encoder {
+            Encoder::Wav(writer) => Ok(writer.finalize()?),
+            Encoder::Flac(writer) => writer.finalize(),
+            #[cfg(feature = "vorbis")]
+            Encoder::Vorbis(writer) => writer.finalize(),
+        }
+    }
+}
+
+/// Mixer combines several mono tracks into a mono or stereo mix.
+///
+/// Each track has its own gain, pan and offset from the start of the mix.
+/// Tracks are panned with a constant power law, so a centered track is 3 dB quieter
+/// in each channel than a track panned hard to one side.
+pub struct Mixer {
+    sample_rate: u32,
+    stereo: bool,
+    clipping: Clipping,
+    tracks: Vec<MixerTrack>,
+}
+
+/// Track of a Mixer, configured after it's added with Mixer::add()
+pub struct MixerTrack {
+    samples: Vec<f64>,
+    gain: f64,
+    pan: f64,
+    offset: Offset,
+}
+
+/// where a track starts in the mix
+#[derive(Debug, Clone, Copy)]
+enum Offset {
+    Samples(usize),
+    Seconds(f64),
+}
+
+/// how a Mixer treats samples beyond full scale
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Clipping {
+    /// leave samples as they are, integer samples are still clipped when converted
+    None,
+    /// clip samples to [-1, 1]
+    Hard,
+    /// saturate samples smoothly with tanh, which barely changes quiet ones
+    Soft,
+    /// scale the whole mix down if its peak is above full scale
+    Normalize,
+    /// lower the gain smoothly around peaks above full scale, starting ahead of them
+    ///
+    /// The gain falls over LIMITER_LOOKAHEAD before a peak and recovers with a time constant
+    /// of LIMITER_RELEASE after it, so no sample is beyond full scale and quiet parts are untouched.
+    Limit,
+}
+
+/// lookahead of Clipping::Limit in seconds
+pub const LIMITER_LOOKAHEAD: f64 = 0.005;
+
+/// time constant in seconds of the gain of Clipping::Limit recovering after a peak
+pub const LIMITER_RELEASE: f64 = 0.05;
+
+impl Default for Mixer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Mixer {
+    /// creates a new Mixer with a mono output at 44100 Hz and hard clipping
+    pub fn new() -> Self {
+        Mixer {
+            sample_rate: 44100,
+            stereo: false,
+            clipping: Clipping::Hard,
+            tracks: Vec::new(),
+        }
+    }
+
+    /// set the sample rate of all tracks, used for offsets in seconds
+    pub fn sample_rate(&mut self, sample_rate: u32) -> &mut Self {
+        self.sample_rate = sample_rate;
+        self
+    }
+
+    /// mix down to a single channel, ignoring pan
+    pub fn mono(&mut self) -> &mut Self {
+        self.stereo = false;
+        self
+    }
+
+    /// mix to two channels, interleaved frame by frame
+    pub fn stereo(&mut self) -> &mut Self {
+        self.stereo = true;
+        self
+    }
+
+    /// set how samples beyond full scale are treated
+    pub fn clipping(&mut self, clipping: Clipping) -> &mut Self {
+        self.clipping = clipping;
+        self
+    }
+
+    /// add a mono track of any sample type, centered at the start of the mix
+    pub fn add<S: Sample>(&mut self, samples: &[S]) -> &mut MixerTrack {
+        self.tracks.push(MixerTrack {
+            samples: samples.iter().map(|x| x.to_f64()).collect(),
+            gain: 1.0,
+            pan: 0.0,
+            offset: Offset::Samples(0),
+        });
+        self.tracks.last_mut().unwrap()
+    }
+
+    /// number of tracks added so far
+    pub fn len(&self) -> usize {
+        self.tracks.len()
+    }
+
+    /// check if no tracks were added yet
+    pub fn is_empty(&self) -> bool {
+        self.tracks.is_empty()
+    }
 
-        for t in data.iter() {
-            let t = (*t ^ 0x8000) as i16; // hack for the sign conversion
+    /// sum all tracks into samples of any sample type, long enough for the last one to end
+    ///
+    /// Fails with Error::InvalidParam for offsets in seconds that are negative or not finite,
+    /// and for offsets or tracks ending beyond MAX_LENGTH samples.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "Mixer::mix", level = "debug", skip_all)
+    )]
+    pub fn mix<S: Sample>(&self) -> Result<Vec<S>> {
+        let channels = if self.stereo { 2 } else { 1 };
+        let mut starts = Vec::with_capacity(self.tracks.len());
+        let mut len = 0;
+        for track in &self.tracks {
+            let start = match track.offset {
+                Offset::Samples(n) => n,
+                Offset::Seconds(t) => {
+                    check_range("offset", t, 0.0, f64::INFINITY)?;
+                    let n = (t * self.sample_rate as f64).round();
+                    check_length("offset", n)?;
+                    n as usize
+                }
+            };
+            let end = start.checked_add(track.samples.len());
+            check_length("track end", end.map_or(f64::INFINITY, |x| x as f64))?;
+            starts.push(start);
+            len = len.max(end.unwrap_or_default());
+        }
+
+        let mut mix = vec![0.0; len * channels];
+        for (track, start) in self.tracks.iter().zip(starts) {
+            let gains = if self.stereo {
+                let angle = (track.pan.clamp(-1.0, 1.0) + 1.0) * PI / 4.0;
+                vec![track.gain * angle.cos(), track.gain * angle.sin()]
+            } else {
+                vec![track.gain]
+            };
+            for (i, x) in track.samples.iter().enumerate() {
+                for (c, gain) in gains.iter().enumerate() {
+                    mix[(start + i) * channels + c] += x * gain;
+                }
+            }
+        }
+
+        match self.clipping {
+            Clipping::None => {}
+            Clipping::Hard => mix.iter_mut().for_each(|x| *x = x.clamp(-1.0, 1.0)),
+            Clipping::Soft => mix.iter_mut().for_each(|x| *x = x.tanh()),
+            Clipping::Normalize => {
+                let peak = mix.iter().fold(0.0, |max: f64, x| max.max(x.abs()));
+                if peak > 1.0 {
+                    mix.iter_mut().for_each(|x| *x /= peak);
+                }
+            }
+            Clipping::Limit => limit(&mut mix, channels, self.sample_rate),
+        }
+        Ok(mix.into_iter().map(S::from_f64).collect())
+    }
+}
+
+/// scale frames of interleaved samples down smoothly, so none is beyond full scale
+fn limit(mix: &mut [f64], channels: usize, sample_rate: u32) {
+    let lookahead = (LIMITER_LOOKAHEAD * sample_rate as f64).round().max(1.0);
+    let release = 1.0 - (-1.0 / (LIMITER_RELEASE * sample_rate as f64)).exp();
+    // the gain each frame needs on its own
+    let mut gains: Vec<f64> = mix
+        .chunks(channels)
+        .map(|frame| {
+            let peak = frame.iter().fold(0.0, |max: f64, x| max.max(x.abs()));
+            if peak > 1.0 {
+                1.0 / peak
+            } else {
+                1.0
+            }
+        })
+        .collect();
+    // falling linearly ahead of peaks, from any gain within the lookahead
+    for i in (1..gains.len()).rev() {
+        gains[i - 1] = gains[i - 1].min(gains[i] + 1.0 / lookahead);
+    }
+    // and recovering exponentially after them
+    for i in 1..gains.len() {
+        gains[i] = gains[i].min(gains[i - 1] + (1.0 - gains[i - 1]) * release);
+    }
+    for (frame, gain) in mix.chunks_mut(channels).zip(gains) {
+        frame.iter_mut().for_each(|x| *x *= gain);
+    }
+}
+
+impl MixerTrack {
+    /// set a linear gain of the track
+    pub fn gain(&mut self, gain: f64) -> &mut Self {
+        self.gain = gain;
+        self
+    }
+
+    /// set a gain of the track in dB
+    pub fn gain_db(&mut self, gain: f64) -> &mut Self {
+        self.gain(10f64.powf(gain / 20.0))
+    }
+
+    /// set the position of the track in a stereo mix, from -1 for left to 1 for right
+    pub fn pan(&mut self, pan: f64) -> &mut Self {
+        self.pan = pan;
+        self
+    }
+
+    /// start the track a number of samples after the start of the mix
+    pub fn offset(&mut self, samples: usize) -> &mut Self {
+        self.offset = Offset::Samples(samples);
+        self
+    }
+
+    /// start the track a number of seconds after the start of the mix
+    pub fn offset_seconds(&mut self, seconds: f64) -> &mut Self {
+        self.offset = Offset::Seconds(seconds);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        to_frames, CancelToken, Clipping, Format, Loader, Mixer, SampleFormat, Target, TimeBase,
+        Writer,
+    };
+    use crate::algs::frames::FrameCutter;
+    use crate::algs::resample::{resample, Quality};
+    use crate::raw::RawFormat;
+    use crate::Error;
+    use std::f64::consts::PI;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn errors() {
+        let mut loader = Loader::<u16>::new();
+        let result = loader.file("does/not/exist.wav".into()).load();
+        assert!(matches!(result, Err(Error::Io(_))));
+
+        let mut writer = Writer::new();
+        let result = writer.file("does/not/exist.wav".into()).write(&[0u16]);
+        assert!(matches!(result, Err(Error::Io(_))));
+    }
+
+    #[test]
+    fn stream() {
+        let path = std::env::temp_dir().join("muslib-mixer-stream.wav");
+        let mut stream = Writer::new().file(path.clone()).open().unwrap();
+        let data: Vec<u16> = (0..1000).map(|x| x * 64).collect();
+        for block in data.chunks(300) {
+            stream.write(block).unwrap();
+        }
+        assert_eq!(stream.len(), 1000);
+
+        // the file is readable before it's finalized
+        stream.flush().unwrap();
+        let mut loader = Loader::<u16>::new();
+        loader.file(path.clone()).load().unwrap();
+        assert_eq!(loader.data(), &data[..]);
+        stream.write(&data).unwrap();
+        stream.finalize().unwrap();
+        let data = [data.clone(), data].concat();
+
+        let mut loader = Loader::<u16>::new();
+        loader.file(path.clone()).load().unwrap();
+        assert_eq!(loader.data(), &data[..]);
+
+        let result = Loader::<u16>::new()
+            .file(path.clone())
+            .channel(1)
+            .load()
+            .err();
+        assert!(matches!(result, Some(Error::InvalidParam(_))));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn segment() {
+        let data: Vec<i16> = (0..20000).map(|x| (x % 30000) as i16).collect();
+        for extension in ["wav", "flac"] {
+            let path = std::env::temp_dir().join(format!("muslib-mixer-segment.{}", extension));
+            Writer::new()
+                .file(path.clone())
+                .sample_rate(1000)
+                .write(&data)
+                .unwrap();
+
+            let mut loader = Loader::<i16>::new();
+            loader
+                .file(path.clone())
+                .offset(12.5)
+                .duration(5.0)
+                .load()
+                .unwrap();
+            assert_eq!(loader.data(), &data[12500..17500], "{}", extension);
+
+            let mut loader = Loader::<i16>::new();
+            loader.file(path.clone()).offset(19.0).load().unwrap();
+            assert_eq!(loader.data(), &data[19000..], "{}", extension);
+
+            let mut loader = Loader::<i16>::new();
+            loader.file(path.clone()).offset(30.0).load().unwrap();
+            assert!(loader.data().is_empty());
+            std::fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn seek() {
+        let path = std::env::temp_dir().join("muslib-mixer-seek.wav");
+        let data: Vec<i16> = (0..20000).map(|x| x as i16).collect();
+        Writer::new()
+            .file(path.clone())
+            .sample_rate(1000)
+            .write(&data)
+            .unwrap();
+
+        // blocks start at the offset, and progress counts from the start of the track
+        let mut loader = Loader::<i16>::new();
+        loader.file(path.clone()).offset(12.5);
+        let mut packets = loader.packets().unwrap();
+        let block = packets.next().unwrap().unwrap();
+        assert_eq!(block[0], 12500);
+        let time = packets.time().unwrap();
+        assert_eq!(time, 12.5 + block.len() as f64 / 1000.0);
+        assert_eq!(packets.progress(), Some(time / 20.0));
+        let rest: Vec<i16> = packets.by_ref().flat_map(|x| x.unwrap()).collect();
+        assert_eq!([block, rest].concat(), &data[12500..]);
+        assert_eq!(packets.progress(), Some(1.0));
+        std::fs::remove_file(path).unwrap();
+
+        // timestamps in a time base other than the sample rate are converted to frames
+        assert_eq!(to_frames(1500, Some(TimeBase::new(1, 1000)), 44100), 66150);
+        assert_eq!(to_frames(1500, None, 44100), 1500);
+    }
+
+    #[test]
+    fn resampling() {
+        let path = std::env::temp_dir().join("muslib-mixer-resampling.wav");
+        let left: Vec<f64> = (0..48000).map(|x| (x as f64 * 0.05).sin() * 0.5).collect();
+        let right: Vec<f64> = left.iter().map(|x| -x).collect();
+        Writer::new()
+            .file(path.clone())
+            .sample_rate(48000)
+            .channels(2)
+            .sample_format(SampleFormat::Float)
+            .bits_per_sample(32)
+            .write_planar(&[left.clone(), right])
+            .unwrap();
+
+        let mut loader = Loader::<f64>::new();
+        loader
+            .file(path.clone())
+            .interleaved()
+            .target_sample_rate(16000)
+            .load()
+            .unwrap();
+        assert_eq!(loader.sample_rate(), Some(16000));
+        let channels = loader.planar();
+        let left = left.iter().map(|x| *x as f32 as f64).collect::<Vec<f64>>();
+        let expected = resample(&left, 48000.0, 16000.0, &Quality::Polyphase).unwrap();
+        assert_eq!(channels[0].len(), 16000);
+        for (x, y) in channels[0].iter().zip(expected.iter()) {
+            assert!((x - y).abs() < 1e-12);
+        }
+        for (x, y) in channels[0].iter().zip(channels[1].iter()) {
+            assert_eq!(*x, -y);
+        }
+
+        let mut loader = Loader::<f64>::new();
+        let result = loader.file(path.clone()).target_sample_rate(0).load();
+        assert!(matches!(result, Err(Error::InvalidParam(_))));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn memory() {
+        let path = std::env::temp_dir().join("muslib-mixer-memory.flac");
+        let data: Vec<i16> = (0..5000).map(|x| (x * 13 % 4000) as i16).collect();
+        Writer::new().file(path.clone()).write(&data).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        let mut loader = Loader::<i16>::from_bytes(bytes.clone());
+        assert_eq!(loader.load().unwrap().data(), data);
+        // the bytes can be decoded again
+        let packets: Vec<i16> = loader.packets().unwrap().flat_map(|x| x.unwrap()).collect();
+        assert_eq!(packets, data);
+
+        let mut loader = Loader::<i16>::from_reader(std::io::Cursor::new(bytes));
+        loader.offset(0.05).load().unwrap();
+        assert_eq!(loader.data(), &data[2205..]);
+        assert!(matches!(loader.load(), Err(Error::InvalidParam(_))));
+    }
+
+    #[test]
+    fn metadata() {
+        // a WAV file with a RIFF INFO list of tags before the data
+        let mut info = b"INFO".to_vec();
+        for (key, value) in [("INAM", "Song"), ("IART", "Band"), ("IPRD", "Album")] {
+            info.extend(key.as_bytes());
+            info.extend(6u32.to_le_bytes());
+            info.extend(format!("{:\0<6}", value).as_bytes());
+        }
+        info.extend(b"IPRT");
+        info.extend(6u32.to_le_bytes());
+        info.extend(b"3/12\0\0");
+        let mut chunks = b"fmt ".to_vec();
+        chunks.extend(16u32.to_le_bytes());
+        for x in [1u16, 1] {
+            chunks.extend(x.to_le_bytes());
+        }
+        chunks.extend(8000u32.to_le_bytes());
+        chunks.extend(16000u32.to_le_bytes());
+        for x in [2u16, 16] {
+            chunks.extend(x.to_le_bytes());
+        }
+        chunks.extend(b"LIST");
+        chunks.extend((info.len() as u32).to_le_bytes());
+        chunks.extend(info);
+        chunks.extend(b"data");
+        chunks.extend(32000u32.to_le_bytes());
+        chunks.extend(vec![0; 32000]);
+        let mut bytes = b"RIFF".to_vec();
+        bytes.extend((chunks.len() as u32 + 4).to_le_bytes());
+        bytes.extend(b"WAVE");
+        bytes.extend(chunks);
+
+        let metadata = Loader::<f64>::from_bytes(bytes).metadata().unwrap();
+        assert_eq!(metadata.title.as_deref(), Some("Song"));
+        assert_eq!(metadata.artist.as_deref(), Some("Band"));
+        assert_eq!(metadata.album.as_deref(), Some("Album"));
+        assert_eq!(metadata.track, Some(3));
+        assert_eq!(metadata.duration, Some(2.0));
+        assert_eq!(metadata.codec.as_deref(), Some("pcm_s16le"));
+        assert_eq!(metadata.sample_rate, Some(8000));
+        assert_eq!(metadata.channels, Some(1));
+        assert_eq!(metadata.tags.len(), 4);
+        assert!(metadata.bitrate.unwrap().abs_diff(128000) < 500);
+    }
+
+    #[test]
+    fn progress() {
+        let path = std::env::temp_dir().join("muslib-mixer-progress.wav");
+        let data: Vec<i16> = vec![100; 44100];
+        Writer::new().file(path.clone()).write(&data).unwrap();
+
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let reported = calls.clone();
+        let mut loader = Loader::<i16>::new();
+        loader
+            .file(path.clone())
+            .on_progress(move |time, duration| reported.lock().unwrap().push((time, duration)))
+            .load()
+            .unwrap();
+        let calls = calls.lock().unwrap();
+        assert!(calls.len() > 1);
+        assert!(calls.windows(2).all(|x| x[0].0 < x[1].0));
+        assert_eq!(calls.last(), Some(&(1.0, Some(1.0))));
+
+        // cancelled from the callback after the first packet
+        let token = CancelToken::new();
+        let cancel = token.clone();
+        let mut loader = Loader::<i16>::new();
+        let result = loader
+            .file(path.clone())
+            .cancel_token(token)
+            .on_progress(move |_, _| cancel.cancel())
+            .load()
+            .err();
+        assert!(matches!(result, Some(Error::Cancelled)));
+        assert!(!loader.data().is_empty() && loader.data().len() < data.len());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn cancel() {
+        let path = std::env::temp_dir().join("muslib-mixer-cancel.wav");
+        let left: Vec<f64> = (0..48000).map(|x| (x as f64 * 0.05).sin() * 0.5).collect();
+        let right: Vec<f64> = left.iter().map(|x| -x).collect();
+        Writer::new()
+            .file(path.clone())
+            .sample_rate(48000)
+            .channels(2)
+            .write_planar(&[left.clone(), right])
+            .unwrap();
+
+        // cancelled before the first packet, nothing is known about the stream yet
+        let token = CancelToken::new();
+        token.cancel();
+        let mut loader = Loader::<f64>::new();
+        let result = loader.file(path.clone()).cancel_token(token).load().err();
+        assert!(matches!(result, Some(Error::Cancelled)));
+        assert!(loader.data().is_empty());
+        assert_eq!((loader.sample_rate(), loader.channels()), (None, None));
+
+        // cancelled after the first packet, the partial data comes with its stream properties
+        let token = CancelToken::new();
+        let cancel = token.clone();
+        let mut loader = Loader::<f64>::new();
+        let result = loader
+            .file(path.clone())
+            .interleaved()
+            .cancel_token(token)
+            .on_progress(move |_, _| cancel.cancel())
+            .load()
+            .err();
+        assert!(matches!(result, Some(Error::Cancelled)));
+        assert_eq!(loader.sample_rate(), Some(48000));
+        assert_eq!(loader.channels(), Some(2));
+        let planar = loader.planar();
+        assert_eq!(planar.len(), 2);
+        assert!(!planar[0].is_empty() && planar[0].len() < left.len());
+        for (x, y) in planar[0].iter().zip(left.iter()) {
+            assert!((x - y).abs() < 1e-4);
+        }
+        assert_eq!(planar[0].len(), planar[1].len());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn normalize() {
+        let path = std::env::temp_dir().join("muslib-mixer-normalize.wav");
+        let sine: Vec<f64> = (0..144000)
+            .map(|i| (2.0 * PI * 1000.0 * i as f64 / 48000.0).sin() * 0.5)
+            .collect();
+        let mut writer = Writer::new();
+        writer
+            .file(path.clone())
+            .sample_rate(48000)
+            .sample_format(SampleFormat::Float)
+            .bits_per_sample(32);
+        writer.write(&sine).unwrap();
+
+        let peak = |x: &[f64]| x.iter().fold(0.0, |max: f64, x| max.max(x.abs()));
+        // a sine at 1 kHz and full scale has a loudness of -3.01 LUFS
+        for (target, expected) in [
+            (Target::Peak(0.9), 0.9),
+            (Target::Rms(-20.0), 0.1 * 2f64.sqrt()),
+            (Target::Lufs(-23.0), 10f64.powf(-19.99 / 20.0)),
+        ] {
+            let mut loader = Loader::<f64>::new();
+            loader.file(path.clone()).normalize(target).load().unwrap();
+            let level = peak(&loader.data());
+            assert!(
+                (level / expected - 1.0).abs() < 0.005,
+                "{:?} {}",
+                target,
+                level
+            );
+        }
+
+        // both channels add up to the loudness of a stereo track
+        writer.channels(2);
+        writer.write_planar(&[sine.clone(), sine]).unwrap();
+        let mut loader = Loader::<f64>::new();
+        loader
+            .file(path.clone())
+            .interleaved()
+            .normalize(Target::Lufs(-23.0))
+            .load()
+            .unwrap();
+        let level = peak(&loader.data());
+        let expected = 10f64.powf(-23.0 / 20.0);
+        assert!((level / expected - 1.0).abs() < 0.005, "{}", level);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn raw() {
+        let path = std::env::temp_dir().join("muslib-mixer-raw.pcm");
+        let left: Vec<i16> = (0..10000).map(|i| (i % 2000) as i16 * 16 - 16000).collect();
+        let right: Vec<i16> = left.iter().map(|x| -x).collect();
+        let bytes: Vec<u8> = left
+            .iter()
+            .zip(right.iter())
+            .flat_map(|(l, r)| [l.to_le_bytes(), r.to_le_bytes()].concat())
+            .collect();
+        std::fs::write(&path, &bytes).unwrap();
+
+        let mut loader = Loader::<i16>::new();
+        loader
+            .file(path.clone())
+            .raw(8000, 2, RawFormat::S16Le)
+            .interleaved()
+            .load()
+            .unwrap();
+        assert_eq!(loader.planar(), vec![left.clone(), right]);
+        assert_eq!(
+            (loader.sample_rate(), loader.channels()),
+            (Some(8000), Some(2))
+        );
+
+        // big-endian floats from memory, with an offset
+        let floats: Vec<f32> = left.iter().map(|x| *x as f32 / 32768.0).collect();
+        let bytes: Vec<u8> = floats.iter().flat_map(|x| x.to_be_bytes()).collect();
+        let mut loader = Loader::<f32>::from_bytes(bytes);
+        loader
+            .raw(8000, 1, RawFormat::F32Be)
+            .offset(0.5)
+            .load()
+            .unwrap();
+        assert_eq!(loader.data(), floats[4000..]);
+        assert_eq!(
+            loader.metadata().unwrap().duration,
+            Some(1.25),
+            "the length of raw pcm in memory is known"
+        );
+
+        assert!(matches!(
+            loader.raw(8000, 0, RawFormat::U8).load().err(),
+            Some(Error::InvalidParam(_))
+        ));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn tracks() {
+        // a Matroska audio file with the headers of two tracks, with languages
+        let element = |id: &[u8], data: &[u8]| {
+            let size = (data.len() as u64 | 1 << 56).to_be_bytes();
+            [id, &size, data].concat()
+        };
+        let track = |number: u8, codec: &str, language: &str, channels: u8| {
+            let audio = [
+                element(&[0xB5], &48000f64.to_be_bytes()),
+                element(&[0x9F], &[channels]),
+                element(&[0x62, 0x64], &[16]),
+            ]
+            .concat();
+            let entry = [
+                element(&[0xD7], &[number]),
+                element(&[0x73, 0xC5], &[number]),
+                element(&[0x83], &[2]),
+                element(&[0x86], codec.as_bytes()),
+                element(&[0x22, 0xB5, 0x9C], language.as_bytes()),
+                element(&[0xE1], &audio),
+            ]
+            .concat();
+            element(&[0xAE], &entry)
+        };
+        let tracks = [
+            track(1, "A_PCM/INT/LIT", "eng", 2),
+            track(2, "A_UNKNOWN", "pol", 1),
+        ]
+        .concat();
+        let segment = [
+            element(
+                &[0x15, 0x49, 0xA9, 0x66],
+                &element(&[0x2A, 0xD7, 0xB1], &1_000_000u32.to_be_bytes()),
+            ),
+            element(&[0x16, 0x54, 0xAE, 0x6B], &tracks),
+        ]
+        .concat();
+        let mka = [
+            element(
+                &[0x1A, 0x45, 0xDF, 0xA3],
+                &element(&[0x42, 0x82], b"matroska"),
+            ),
+            element(&[0x18, 0x53, 0x80, 0x67], &segment),
+        ]
+        .concat();
+
+        let tracks = Loader::<f32>::from_bytes(mka).tracks().unwrap();
+        assert_eq!(tracks.len(), 2);
+        assert_eq!(
+            (
+                tracks[0].index,
+                tracks[0].codec.as_deref(),
+                tracks[0].supported
+            ),
+            (0, Some("pcm_s16le"), true)
+        );
+        assert_eq!(
+            (tracks[0].language.as_deref(), tracks[0].channels),
+            (Some("eng"), Some(2))
+        );
+        assert_eq!((tracks[1].index, tracks[1].supported), (1, false));
+        assert_eq!(
+            (tracks[1].language.as_deref(), tracks[1].sample_rate),
+            (Some("pol"), Some(48000))
+        );
+
+        let path = std::env::temp_dir().join("muslib-mixer-tracks.wav");
+        let mut writer = Writer::new();
+        writer.file(path.clone()).sample_rate(8000);
+        writer.write(&[0.5f32; 800]).unwrap();
+        let mut loader = Loader::<f32>::new();
+        let tracks = loader.file(path.clone()).tracks().unwrap();
+        assert_eq!(tracks.len(), 1);
+        assert_eq!(tracks[0].duration, Some(0.1));
+        assert_eq!(loader.track(0).load().unwrap().data().len(), 800);
+        assert!(matches!(
+            loader.track(1).load().err(),
+            Some(Error::InvalidParam(_))
+        ));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn trim() {
+        let path = std::env::temp_dir().join("muslib-mixer-trim.wav");
+        // half a second of silence, a second of noise at -40 dBFS, then a sine and silence
+        let mut signal = vec![0i16; 4000];
+        signal.extend((0..8000).map(|i| if i % 2 == 0 { 328 } else { -328 }));
+        signal.extend((0..8000).map(|i| ((i as f64 * 0.1).sin() * 16000.0) as i16));
+        signal.extend(vec![0i16; 2000]);
+        let mut writer = Writer::new();
+        writer.file(path.clone()).sample_rate(8000).channels(2);
+        writer
+            .write_planar(&[signal.clone(), signal.clone()])
+            .unwrap();
+
+        let load = |threshold_db, min_duration| {
+            let mut loader = Loader::<i16>::new();
+            loader
+                .file(path.clone())
+                .interleaved()
+                .trim_silence(threshold_db, min_duration)
+                .load()
+                .unwrap();
+            loader.planar()
+        };
+        // the noise is kept above its level and stripped with the silence below it
+        assert_eq!(load(-60.0, 0.0), vec![signal[4000..20000].to_vec(); 2]);
+        assert_eq!(load(-30.0, 0.0), vec![signal[12000..20000].to_vec(); 2]);
+        // only the silence at the start is long enough
+        assert_eq!(load(-60.0, 0.4), vec![signal[4000..].to_vec(); 2]);
+        // all silent
+        assert_eq!(load(0.0, 0.0), vec![signal; 2]);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn mixer() {
+        let mut mixer = Mixer::new();
+        mixer.sample_rate(10);
+        mixer.add(&[0.5, 0.5, 0.5]);
+        mixer
+            .add(&[0.25f32; 2])
+            .gain_db(20.0 * 2f64.log10())
+            .offset(2);
+        mixer.add(&[0.1]).gain(-1.0).offset_seconds(0.4);
+        assert_eq!(mixer.len(), 3);
+        assert_eq!(mixer.mix::<f64>().unwrap(), [0.5, 0.5, 1.0, 0.5, -0.1]);
+
+        mixer.clipping(Clipping::None);
+        mixer.add(&[0.75]);
+        assert_eq!(mixer.mix::<f64>().unwrap()[0], 1.25);
+        mixer.clipping(Clipping::Hard);
+        assert_eq!(mixer.mix::<f64>().unwrap()[0], 1.0);
+        mixer.clipping(Clipping::Normalize);
+        assert_eq!(mixer.mix::<f64>().unwrap()[0], 1.0);
+        assert_eq!(mixer.mix::<f64>().unwrap()[1], 0.4);
+        mixer.clipping(Clipping::Soft);
+        assert_eq!(mixer.mix::<f64>().unwrap()[1], 0.5f64.tanh());
+
+        // the limiter lowers the gain ahead of a peak and recovers after it
+        let mut mixer = Mixer::new();
+        mixer.sample_rate(1000).clipping(Clipping::Limit);
+        mixer.add(&[0.5; 200]);
+        mixer.add(&[1.5]).offset(100);
+        let mix = mixer.mix::<f64>().unwrap();
+        assert!(mix.iter().all(|x| *x <= 1.0 + 1e-12));
+        assert!((mix[100] - 1.0).abs() < 1e-12);
+        assert_eq!(mix[..98], [0.5; 98]);
+        assert!(mix[97..100].windows(2).all(|x| x[0] > x[1]));
+        assert!(mix[101..].windows(2).all(|x| x[0] < x[1]));
+        assert!(mix[199] > 0.45);
+
+        // offsets must fit in the mix
+        mixer.add(&[0.5]).offset_seconds(-1.0);
+        assert!(matches!(mixer.mix::<f64>(), Err(Error::InvalidParam(_))));
+        mixer.tracks.pop();
+        mixer.add(&[0.5]).offset_seconds(f64::INFINITY);
+        assert!(matches!(mixer.mix::<f64>(), Err(Error::InvalidParam(_))));
+        mixer.tracks.pop();
+        mixer.add(&[0.5, 0.5]).offset(usize::MAX);
+        assert!(matches!(mixer.mix::<f64>(), Err(Error::InvalidParam(_))));
+
+        let mut mixer = Mixer::new();
+        mixer.stereo();
+        mixer.add(&[1.0]).pan(-1.0);
+        mixer.add(&[0.0, 1.0]).pan(1.0);
+        mixer.add(&[0.0, 0.0, 1.0]);
+        let mix = mixer.mix::<f64>().unwrap();
+        let expected = [1.0, 0.0, 0.0, 1.0, 0.5f64.sqrt(), 0.5f64.sqrt()];
+        for (x, y) in mix.iter().zip(expected) {
+            assert!((x - y).abs() < 1e-12, "{:?}", mix);
+        }
+        assert_eq!(mixer.mix::<i16>().unwrap()[3], i16::MAX);
+    }
+
+    #[test]
+    fn frames() {
+        let path = std::env::temp_dir().join("muslib-mixer-frames.wav");
+        let data: Vec<u16> = (0..1000).map(|x| x * 64).collect();
+        Writer::new().file(path.clone()).write(&data).unwrap();
+
+        let mut cutter = FrameCutter::new(300, 200);
+        cutter.push(&data);
+        let expected: Vec<Vec<u16>> = std::iter::from_fn(|| cutter.flush()).collect();
+
+        let mut loader = Loader::<u16>::new();
+        loader.file(path.clone());
+        let frames = loader.frames(300, 200).unwrap();
+        let frames: Vec<Vec<u16>> = frames.map(|x| x.unwrap()).collect();
+        assert_eq!(frames.len(), 5);
+        assert_eq!(frames, expected);
+        assert!(loader.frames(0, 200).is_err());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn channels() {
+        let path = std::env::temp_dir().join("muslib-mixer-channels.wav");
+        let spec = hound::WavSpec {
+            channels: 2,
+            sample_rate: 8000,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+        let left: Vec<i16> = (0..100).map(|x| x * 100).collect();
+        let right: Vec<i16> = (0..100).map(|x| -x * 50).collect();
+        for (l, r) in left.iter().zip(right.iter()) {
+            writer.write_sample(*l).unwrap();
+            writer.write_sample(*r).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        let mut loader = Loader::<i16>::new();
+        loader.file(path.clone()).interleaved().load().unwrap();
+        assert_eq!(loader.channels(), Some(2));
+        assert_eq!(loader.data()[..4], [left[0], right[0], left[1], right[1]]);
+        assert_eq!(loader.planar(), [left.clone(), right.clone()]);
+
+        let mut loader = Loader::<i16>::new();
+        loader
+            .file(path.clone())
+            .channel_gains(vec![1.0, 4.0])
+            .mono()
+            .load()
+            .unwrap();
+        // mixed down with the default gain of 1/2
+        let mono: Vec<i16> = left
+            .iter()
+            .zip(right.iter())
+            .map(|(l, r)| l / 2 + r * 2)
+            .collect();
+        assert_eq!(loader.data(), mono);
+        assert_eq!(loader.planar(), [mono]);
+
+        let mut loader = Loader::<i16>::new();
+        loader
+            .file(path.clone())
+            .gain_db(-6.0206)
+            .channel(0)
+            .load()
+            .unwrap();
+        let half: Vec<i16> = left.iter().map(|l| l / 2).collect();
+        assert_eq!(loader.data(), half);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn stereo() {
+        let path = std::env::temp_dir().join("muslib-mixer-stereo.wav");
+        let left: Vec<i16> = (0..100).map(|x| x * 100).collect();
+        let right: Vec<i16> = (0..100).map(|x| -x * 50).collect();
+        let stems = vec![left, right];
+
+        let mut writer = Writer::new();
+        writer.file(path.clone()).channels(2);
+        writer.write_planar(&stems).unwrap();
+        assert!(writer.write_interleaved(&[0i16; 3]).is_err());
+        assert!(writer.write_planar(&stems[..1]).is_err());
+
+        let mut loader = Loader::<i16>::new();
+        loader.file(path.clone()).interleaved().load().unwrap();
+        assert_eq!(loader.channels(), Some(2));
+        assert_eq!(loader.planar(), stems);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn formats() {
+        let path = std::env::temp_dir().join("muslib-mixer-formats.wav");
+        let data: Vec<f64> = (0..1000).map(|x| (x as f64 * 0.01).sin() * 0.9).collect();
+
+        for (format, bits, tolerance) in [
+            (SampleFormat::Float, 32, 1e-7),
+            (SampleFormat::Int, 24, 1e-6),
+            (SampleFormat::Int, 32, 1e-9),
+            (SampleFormat::Int, 8, 1e-2),
+        ] {
+            let mut writer = Writer::new();
             writer
-                .write_sample(t)
-                .expect("Failed to write an output sample.");
+                .file(path.clone())
+                .bits_per_sample(bits)
+                .sample_format(format);
+            writer.write(&data).unwrap();
+
+            let mut loader = Loader::<f64>::new();
+            loader.file(path.clone()).load().unwrap();
+            let output = loader.data();
+            assert_eq!(output.len(), data.len());
+            for (x, y) in data.iter().zip(output.iter()) {
+                assert!((x - y).abs() < tolerance, "test {} {} {}", bits, x, y);
+            }
         }
 
-        Ok(())
+        let mut writer = Writer::new();
+        writer.file(path.clone()).bits_per_sample(64);
+        assert!(matches!(writer.write(&data), Err(Error::InvalidParam(_))));
+        std::fs::remove_file(path).unwrap();
+
+        // FLAC chosen by the file extension or explicitly
+        let path = std::env::temp_dir().join("muslib-mixer-formats.flac");
+        let stereo: Vec<i16> = (0..20000).map(|x| (x * 37 % 5000) as i16).collect();
+        let mut writer = Writer::new();
+        writer.file(path.clone()).channels(2);
+        writer.write(&stereo).unwrap();
+        let mut loader = Loader::<i16>::new();
+        loader.file(path.clone()).interleaved().load().unwrap();
+        assert_eq!(loader.channels(), Some(2));
+        assert_eq!(loader.data(), stereo);
+
+        writer
+            .format(Format::Flac)
+            .sample_format(SampleFormat::Float);
+        assert!(matches!(writer.write(&stereo), Err(Error::InvalidParam(_))));
+        writer.sample_format(SampleFormat::Int).bits_per_sample(32);
+        assert!(matches!(writer.write(&stereo), Err(Error::InvalidParam(_))));
+        std::fs::remove_file(path).unwrap();
+
+        // Ogg Vorbis chosen by the file extension, whatever the sample format
+        #[cfg(feature = "vorbis")]
+        {
+            let path = std::env::temp_dir().join("muslib-mixer-formats.ogg");
+            let mut writer = Writer::new();
+            writer.file(path.clone()).bits_per_sample(32);
+            writer.write(&data).unwrap();
+            let mut loader = Loader::<f64>::new();
+            loader.file(path.clone()).load().unwrap();
+            assert_eq!(loader.channels(), Some(1));
+            assert!(loader.data().len() >= data.len());
+            std::fs::remove_file(path).unwrap();
+        }
     }
 }
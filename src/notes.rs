@@ -0,0 +1,279 @@
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+#[cfg(not(feature = "std"))]
+use crate::math::Float;
+use crate::{Error, Result};
+
+/// names of pitch classes starting from C, spelled as in key names
+pub const PITCH_CLASSES: [&str; 12] = [
+    "C", "C#", "D", "Eb", "E", "F", "F#", "G", "Ab", "A", "Bb", "B",
+];
+
+/// letters of note names with the pitch classes of their natural notes
+pub(crate) const LETTERS: [(char, i32); 7] = [
+    ('C', 0),
+    ('D', 2),
+    ('E', 4),
+    ('F', 5),
+    ('G', 7),
+    ('A', 9),
+    ('B', 11),
+];
+
+/// qualities of simple intervals within an octave, with the interval number
+const INTERVALS: [(&str, i32); 12] = [
+    ("P", 1),
+    ("m", 2),
+    ("M", 2),
+    ("m", 3),
+    ("M", 3),
+    ("P", 4),
+    ("A", 4),
+    ("P", 5),
+    ("m", 6),
+    ("M", 6),
+    ("m", 7),
+    ("M", 7),
+];
+
+/// intervals of chord notes from the root, as pairs of steps between letters and semitones
+type Intervals = &'static [(usize, i32)];
+
+/// suffixes of chord symbols with the intervals of their notes from the root
+///
+/// Steps between letters make the notes spelled properly, e.g. the minor third of F is Ab and not G#.
+const CHORDS: [(&str, Intervals); 11] = [
+    ("", &[(0, 0), (2, 4), (4, 7)]),
+    ("m", &[(0, 0), (2, 3), (4, 7)]),
+    ("dim", &[(0, 0), (2, 3), (4, 6)]),
+    ("aug", &[(0, 0), (2, 4), (4, 8)]),
+    ("sus2", &[(0, 0), (1, 2), (4, 7)]),
+    ("sus4", &[(0, 0), (3, 5), (4, 7)]),
+    ("7", &[(0, 0), (2, 4), (4, 7), (6, 10)]),
+    ("maj7", &[(0, 0), (2, 4), (4, 7), (6, 11)]),
+    ("m7", &[(0, 0), (2, 3), (4, 7), (6, 10)]),
+    ("m7b5", &[(0, 0), (2, 3), (4, 6), (6, 10)]),
+    ("dim7", &[(0, 0), (2, 3), (4, 6), (6, 9)]),
+];
+
+/// Tuning of the equal-tempered scale given by the frequency of A4
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tuning {
+    /// frequency of A4 in Hz
+    pub a4: f64,
+}
+
+impl Default for Tuning {
+    fn default() -> Self {
+        Tuning { a4: 440.0 }
+    }
+}
+
+impl Tuning {
+    /// create a Tuning with A4 at a given frequency in Hz
+    pub fn new(a4: f64) -> Self {
+        Tuning { a4 }
+    }
+
+    /// frequency in Hz of a MIDI note number, fractional numbers fall between notes
+    pub fn frequency(&self, midi: f64) -> f64 {
+        self.a4 * 2f64.powf((midi - 69.0) / 12.0)
+    }
+
+    /// fractional MIDI note number of a frequency in Hz
+    pub fn midi(&self, frequency: f64) -> f64 {
+        69.0 + 12.0 * (frequency / self.a4).log2()
+    }
+
+    /// nearest MIDI note number of a frequency in Hz and the deviation from it in cents
+    pub fn nearest(&self, frequency: f64) -> (u8, f64) {
+        let midi = self.midi(frequency);
+        let note = midi.round().clamp(0.0, 127.0);
+        (note as u8, 100.0 * (midi - note))
+    }
+}
+
+/// frequency in Hz of a MIDI note number with A4 at 440 Hz
+pub fn frequency(midi: u8) -> f64 {
+    Tuning::default().frequency(midi as f64)
+}
+
+/// nearest MIDI note number of a frequency in Hz with A4 at 440 Hz
+pub fn midi(frequency: f64) -> u8 {
+    Tuning::default().nearest(frequency).0
+}
+
+/// distance from the first to the second frequency in cents
+pub fn cents(from: f64, to: f64) -> f64 {
+    1200.0 * (to / from).log2()
+}
+
+/// name of a MIDI note number with its octave, e.g. "C4" for 60 or "Bb-1" for 10
+pub fn name(midi: u8) -> String {
+    format!(
+        "{}{}",
+        PITCH_CLASSES[midi as usize % 12],
+        midi as i32 / 12 - 1
+    )
+}
+
+/// MIDI note number of a note name like "A4", "C#5", "Db3" or "B-1"
+///
+/// Fails with Error::InvalidParam if the name can't be read or the note is out of the MIDI range.
+pub fn parse(name: &str) -> Result<u8> {
+    let invalid = || Error::InvalidParam(format!("invalid note name {}", name));
+    let (letter, offset, octave) = split_name(name).ok_or_else(invalid)?;
+    let octave: i32 = octave.parse().map_err(|_| invalid())?;
+    note_number(letter, offset, octave, 0).ok_or_else(invalid)
+}
+
+/// name of an interval given in semitones, e.g. "m3", "P5", "A4" or "M9" for compound ones
+///
+/// Descending intervals are named like ascending ones.
+pub fn interval(semitones: i32) -> String {
+    let semitones = semitones.abs();
+    let (quality, number) = INTERVALS[(semitones % 12) as usize];
+    format!("{}{}", quality, number + 7 * (semitones / 12))
+}
+
+/// spell the notes of a chord symbol like "C", "F#m7" or "Bbdim" in root position
+///
+/// Fails with Error::InvalidParam for unknown roots or chord qualities.
+pub fn spell_chord(symbol: &str) -> Result<Vec<String>> {
+    let (letter, offset, intervals) = split_chord(symbol)?;
+    let notes = intervals.iter().map(|(steps, semitones)| {
+        let (l, natural) = LETTERS[(letter + steps) % 7];
+        let offset = (LETTERS[letter].1 + offset + semitones - natural + 6).rem_euclid(12) - 6;
+        let accidentals = if offset < 0 { "b" } else { "#" };
+        format!(
+            "{}{}",
+            l,
+            accidentals.repeat(offset.unsigned_abs() as usize)
+        )
+    });
+    Ok(notes.collect())
+}
+
+/// MIDI note numbers of a chord symbol in root position, with the root in a given octave
+///
+/// Fails with Error::InvalidParam for unknown chords or notes out of the MIDI range.
+pub fn chord(symbol: &str, octave: i32) -> Result<Vec<u8>> {
+    let (letter, offset, intervals) = split_chord(symbol)?;
+    intervals
+        .iter()
+        .map(|(_, semitones)| {
+            note_number(letter, offset, octave, *semitones)
+                .ok_or_else(|| Error::InvalidParam(format!("chord {} out of range", symbol)))
+        })
+        .collect()
+}
+
+/// symbol of the chord formed by some MIDI notes in any inversion, e.g. "Am" or "G7"
+///
+/// The lowest note is preferred as the root, None if the notes don't form a known chord.
+pub fn identify_chord(notes: &[u8]) -> Option<String> {
+    let mut classes: Vec<i32> = notes.iter().map(|x| *x as i32 % 12).collect();
+    let bass = *notes.iter().min()? as i32 % 12;
+    classes.sort();
+    classes.dedup();
+
+    let roots = core::iter::once(bass).chain(classes.iter().copied().filter(|x| *x != bass));
+    for root in roots {
+        for (suffix, intervals) in CHORDS {
+            let mut chord: Vec<i32> = intervals.iter().map(|(_, s)| (root + s) % 12).collect();
+            chord.sort();
+            if chord == classes {
+                return Some(format!("{}{}", PITCH_CLASSES[root as usize], suffix));
+            }
+        }
+    }
+    None
+}
+
+/// MIDI note number of a letter with accidentals in an octave, shifted by some semitones
+///
+/// None if it's out of the MIDI range, also when the octave is too far off to compute it.
+fn note_number(letter: usize, offset: i32, octave: i32, semitones: i32) -> Option<u8> {
+    let midi = octave
+        .checked_add(1)?
+        .checked_mul(12)?
+        .checked_add(LETTERS[letter].1 + offset)?
+        .checked_add(semitones)?;
+    u8::try_from(midi).ok().filter(|x| *x < 128)
+}
+
+/// letter index, offset of the accidentals in semitones and the rest of a note name
+fn split_name(name: &str) -> Option<(usize, i32, &str)> {
+    let mut chars = name.char_indices();
+    let (_, first) = chars.next()?;
+    let letter = LETTERS
+        .iter()
+        .position(|(l, _)| *l == first.to_ascii_uppercase())?;
+    let mut offset = 0;
+    for (i, c) in chars {
+        match c {
+            '#' | '♯' => offset += 1,
+            'b' | '♭' => offset -= 1,
+            _ => return Some((letter, offset, &name[i..])),
+        }
+    }
+    Some((letter, offset, ""))
+}
+
+/// root and intervals of a chord symbol
+fn split_chord(symbol: &str) -> Result<(usize, i32, Intervals)> {
+    let invalid = || Error::InvalidParam(format!("unknown chord {}", symbol));
+    let (letter, offset, suffix) = split_name(symbol).ok_or_else(invalid)?;
+    let (_, intervals) = CHORDS
+        .iter()
+        .find(|(s, _)| *s == suffix)
+        .ok_or_else(invalid)?;
+    Ok((letter, offset, intervals))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        cents, chord, frequency, identify_chord, interval, midi, name, parse, spell_chord, Tuning,
+    };
+
+    #[test]
+    fn notes() {
+        assert_eq!(frequency(69), 440.0);
+        assert!((frequency(60) - 261.6256).abs() < 1e-4);
+        assert_eq!(midi(261.0), 60);
+        assert!((cents(440.0, 880.0) - 1200.0).abs() < 1e-9);
+
+        let baroque = Tuning::new(415.0);
+        assert_eq!(baroque.frequency(69.0), 415.0);
+        let (note, deviation) = baroque.nearest(440.0);
+        assert_eq!(note, 70);
+        assert!((deviation - 1.27).abs() < 0.01, "{}", deviation);
+
+        for (midi, note) in [(60, "C4"), (70, "Bb4"), (0, "C-1"), (127, "G9")] {
+            assert_eq!(name(midi), note);
+            assert_eq!(parse(note).unwrap(), midi);
+        }
+        assert_eq!(parse("C#5").unwrap(), 73);
+        assert_eq!(parse("E♭3").unwrap(), 51);
+        assert_eq!(parse("Cb4").unwrap(), 59);
+        assert!(parse("H2").is_err() && parse("C").is_err() && parse("G#9").is_err());
+        assert!(parse("C999999999").is_err() && parse("C-999999999").is_err());
+
+        let names: Vec<String> = [0, 3, 6, 7, 12, 14, -4].map(interval).to_vec();
+        assert_eq!(names, ["P1", "m3", "A4", "P5", "P8", "M9", "M3"]);
+
+        assert_eq!(spell_chord("Db").unwrap(), ["Db", "F", "Ab"]);
+        assert_eq!(spell_chord("F#m7").unwrap(), ["F#", "A", "C#", "E"]);
+        assert_eq!(spell_chord("Bdim7").unwrap(), ["B", "D", "F", "Ab"]);
+        assert!(spell_chord("Cmaj13").is_err());
+
+        assert_eq!(chord("Am", 3).unwrap(), [57, 60, 64]);
+        assert!(chord("C", i32::MAX).is_err() && chord("C", i32::MIN).is_err());
+        assert_eq!(identify_chord(&[60, 64, 69]).unwrap(), "Am");
+        assert_eq!(identify_chord(&[43, 59, 62, 65]).unwrap(), "G7");
+        assert_eq!(identify_chord(&[60, 61]), None);
+    }
+}
@@ -0,0 +1,99 @@
+use rayon::prelude::*;
+
+use crate::algs::Algorithm;
+use crate::trace;
+use crate::Result;
+
+/// apply a function to every item on all cores, keeping the order of items
+///
+/// Items are spread over the global rayon thread pool, whose threads steal work from each other,
+/// so items taking longer don't hold up the rest. Call it inside `ThreadPool::install()`
+/// to run it on a pool of your own, e.g. with fewer threads.
+pub fn map<T, U, F>(items: Vec<T>, f: F) -> Vec<U>
+where
+    T: Send,
+    U: Send,
+    F: Fn(T) -> U + Sync + Send,
+{
+    items.into_par_iter().map(f).collect()
+}
+
+/// compute an Algorithm for every frame on all cores, keeping the order of frames
+///
+/// Each piece of work rayon splits off creates its own instance of the Algorithm
+/// from the same parameters, so algorithms carrying state between calls see only a part of the frames.
+/// Fails with the error of the first frame that failed.
+pub fn compute_frames<A>(params: &A::Params, frames: Vec<A::Input>) -> Result<Vec<A::Output>>
+where
+    A: Algorithm,
+    A::Params: Clone + Sync,
+    A::Input: Send,
+    A::Output: Send,
+{
+    let _span = trace::span(std::any::type_name::<A>());
+    let outputs: Vec<Result<A::Output>> = frames
+        .into_par_iter()
+        .map_init(|| A::with_params(params.clone()), |alg, x| alg.compute(x))
+        .collect();
+    outputs.into_iter().collect()
+}
+
+/// run two independent computations concurrently and return both results
+///
+/// Combined with compute_frames() it runs several algorithms over the same frames at once:
+///
+/// ```
+/// use muslib::algs::filters::{MedianFilter, MovingAverage};
+/// use muslib::parallel::{compute_frames, join};
+///
+/// let frames = vec![vec![0.0, 1.0, 0.0, 1.0]; 16];
+/// let (smooth, median) = join(
+///     || compute_frames::<MovingAverage>(&Default::default(), frames.clone()),
+///     || compute_frames::<MedianFilter>(&Default::default(), frames.clone()),
+/// );
+/// assert_eq!(smooth?.len(), median?.len());
+/// # Ok::<(), muslib::Error>(())
+/// ```
+pub fn join<A, B, RA, RB>(a: A, b: B) -> (RA, RB)
+where
+    A: FnOnce() -> RA + Send,
+    B: FnOnce() -> RB + Send,
+    RA: Send,
+    RB: Send,
+{
+    rayon::join(a, b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compute_frames, map};
+    use crate::algs::stft::FFT;
+    use crate::algs::Algorithm;
+
+    #[test]
+    fn same_as_sequential() {
+        assert_eq!(
+            map((0..100).collect(), |x: u32| x * 2),
+            (0..200).step_by(2).collect::<Vec<_>>()
+        );
+
+        let frames: Vec<Vec<f64>> = (0..37).map(|i| vec![i as f64, 1.0, -1.0, 0.5]).collect();
+        let mut fft = FFT::new();
+        let expected: Vec<_> = frames
+            .iter()
+            .map(|x| fft.compute(x.clone()).unwrap())
+            .collect();
+        assert_eq!(
+            compute_frames::<FFT>(&Default::default(), frames.clone()).unwrap(),
+            expected
+        );
+
+        // on a pool of a single thread too
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(1)
+            .build()
+            .unwrap();
+        let output = pool.install(|| compute_frames::<FFT>(&Default::default(), frames));
+        assert_eq!(output.unwrap(), expected);
+    }
+}
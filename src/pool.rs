@@ -0,0 +1,578 @@
+use std::collections::BTreeMap;
+
+use crate::json::{FromJson, Json, ToJson};
+use crate::{Error, Result};
+
+/// single value stored in a Pool
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    /// a number, e.g. a spectral centroid of a frame
+    Real(f64),
+    /// a label, e.g. a detected key
+    String(String),
+    /// a list of numbers, e.g. a pitch class profile of a frame
+    Vector(Vec<f64>),
+    /// a list of rows of numbers, e.g. a spectrogram
+    Matrix(Vec<Vec<f64>>),
+}
+
+impl Value {
+    /// name of the type of this value used in error messages
+    fn kind(&self) -> &'static str {
+        match self {
+            Value::Real(_) => "real",
+            Value::String(_) => "string",
+            Value::Vector(_) => "vector",
+            Value::Matrix(_) => "matrix",
+        }
+    }
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Self {
+        Value::Real(value)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::String(value.into())
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Value::String(value)
+    }
+}
+
+impl From<Vec<f64>> for Value {
+    fn from(value: Vec<f64>) -> Self {
+        Value::Vector(value)
+    }
+}
+
+impl From<Vec<f32>> for Value {
+    fn from(value: Vec<f32>) -> Self {
+        Value::Vector(value.into_iter().map(|x| x as f64).collect())
+    }
+}
+
+impl From<Vec<Vec<f64>>> for Value {
+    fn from(value: Vec<Vec<f64>>) -> Self {
+        Value::Matrix(value)
+    }
+}
+
+impl ToJson for Value {
+    fn to_json(&self) -> Json {
+        match self {
+            Value::Real(x) => x.to_json(),
+            Value::String(x) => x.to_json(),
+            Value::Vector(x) => x.to_json(),
+            Value::Matrix(x) => x.to_json(),
+        }
+    }
+}
+
+/// numbers become reals, arrays of numbers vectors, and arrays of arrays of numbers matrices
+///
+/// Arrays of pairs, like complex FFT bins, are read as matrices with two columns.
+impl FromJson for Value {
+    fn from_json(json: &Json) -> Result<Self> {
+        match json {
+            Json::String(x) => Ok(Value::String(x.clone())),
+            Json::Array(x) if x.first().is_some_and(|x| matches!(x, Json::Array(_))) => {
+                Ok(Value::Matrix(FromJson::from_json(json)?))
+            }
+            Json::Array(_) => Ok(Value::Vector(FromJson::from_json(json)?)),
+            _ => Ok(Value::Real(FromJson::from_json(json)?)),
+        }
+    }
+}
+
+/// Container of features collected during analysis, inspired by the Essentia Pool
+///
+/// Keys are names separated with dots, like "tonal.hpcp" or "metadata.sample_rate".
+/// Values under each key are either added one for each frame with .add()
+/// or set once for the whole file with .set(), the same key can't be used both ways.
+/// All values added under a key must have the same type.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Pool {
+    frames: BTreeMap<String, Vec<Value>>,
+    single: BTreeMap<String, Value>,
+}
+
+impl Pool {
+    /// create a new empty Pool
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// append a value for the next frame under a key
+    ///
+    /// Fails with Error::InvalidParam if the key holds a single value or values of another type.
+    pub fn add<V: Into<Value>>(&mut self, key: &str, value: V) -> Result<()> {
+        let value = value.into();
+        if self.single.contains_key(key) {
+            return Err(Error::InvalidParam(format!(
+                "{} is already set to a single value",
+                key
+            )));
+        }
+        let values = self.frames.entry(key.into()).or_default();
+        if let Some(first) = values.first() {
+            if first.kind() != value.kind() {
+                return Err(Error::InvalidParam(format!(
+                    "cannot add a {} to {} holding values of type {}",
+                    value.kind(),
+                    key,
+                    first.kind()
+                )));
+            }
+        }
+        values.push(value);
+        Ok(())
+    }
+
+    /// set a single value for the whole file under a key, replacing the previous one
+    ///
+    /// Fails with Error::InvalidParam if values for each frame were already added under the key.
+    pub fn set<V: Into<Value>>(&mut self, key: &str, value: V) -> Result<()> {
+        if self.frames.contains_key(key) {
+            return Err(Error::InvalidParam(format!(
+                "{} already holds values for each frame",
+                key
+            )));
+        }
+        self.single.insert(key.into(), value.into());
+        Ok(())
+    }
+
+    /// a sink for Node::run() adding each output of a streaming network under a key
+    pub fn sink<'a, V: Into<Value>>(
+        &'a mut self,
+        key: &'a str,
+    ) -> impl FnMut(V) -> Result<()> + 'a {
+        move |value| self.add(key, value)
+    }
+
+    /// values added for each frame under a key
+    pub fn get(&self, key: &str) -> Option<&[Value]> {
+        self.frames.get(key).map(|x| &x[..])
+    }
+
+    /// single value set under a key
+    pub fn value(&self, key: &str) -> Option<&Value> {
+        self.single.get(key)
+    }
+
+    /// numbers added under a key, if they are real values
+    pub fn reals(&self, key: &str) -> Option<Vec<f64>> {
+        self.get(key)?
+            .iter()
+            .map(|x| match x {
+                Value::Real(x) => Some(*x),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// lists of numbers added under a key, if they are vector values
+    pub fn vectors(&self, key: &str) -> Option<Vec<&[f64]>> {
+        self.get(key)?
+            .iter()
+            .map(|x| match x {
+                Value::Vector(x) => Some(&x[..]),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// check if there is anything stored under a key
+    pub fn contains(&self, key: &str) -> bool {
+        self.frames.contains_key(key) || self.single.contains_key(key)
+    }
+
+    /// all keys in alphabetical order
+    pub fn keys(&self) -> Vec<&str> {
+        let mut keys: Vec<&str> = self
+            .frames
+            .keys()
+            .chain(self.single.keys())
+            .map(|x| &x[..])
+            .collect();
+        keys.sort();
+        keys
+    }
+
+    /// remove everything stored under a key
+    pub fn remove(&mut self, key: &str) {
+        self.frames.remove(key);
+        self.single.remove(key);
+    }
+
+    /// move everything from another Pool into this one
+    ///
+    /// Values of each frame are appended after the ones already stored,
+    /// single values replace the ones already stored.
+    pub fn merge(&mut self, other: Pool) -> Result<()> {
+        for (key, values) in other.frames {
+            for value in values {
+                self.add(&key, value)?;
+            }
+        }
+        for (key, value) in other.single {
+            self.set(&key, value)?;
+        }
+        Ok(())
+    }
+
+    /// mean of real or vector values added under a key, vectors are averaged element-wise
+    pub fn mean(&self, key: &str) -> Option<Value> {
+        self.aggregate(key, |x| x.iter().sum::<f64>() / x.len() as f64)
+    }
+
+    /// population variance of real or vector values added under a key
+    pub fn var(&self, key: &str) -> Option<Value> {
+        self.aggregate(key, |x| {
+            let mean = x.iter().sum::<f64>() / x.len() as f64;
+            x.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / x.len() as f64
+        })
+    }
+
+    /// minimum of real or vector values added under a key
+    pub fn min(&self, key: &str) -> Option<Value> {
+        self.aggregate(key, |x| x.iter().cloned().fold(f64::INFINITY, f64::min))
+    }
+
+    /// maximum of real or vector values added under a key
+    pub fn max(&self, key: &str) -> Option<Value> {
+        self.aggregate(key, |x| x.iter().cloned().fold(f64::NEG_INFINITY, f64::max))
+    }
+
+    /// apply a statistic to reals, or to each element of vectors of the same length
+    pub(crate) fn aggregate<F: Fn(&[f64]) -> f64>(&self, key: &str, stat: F) -> Option<Value> {
+        if let Some(reals) = self.reals(key) {
+            return (!reals.is_empty()).then(|| Value::Real(stat(&reals)));
+        }
+        let vectors = self.vectors(key)?;
+        let n = vectors.first()?.len();
+        if vectors.iter().any(|x| x.len() != n) {
+            return None;
+        }
+        let columns = (0..n).map(|i| {
+            let column: Vec<f64> = vectors.iter().map(|x| x[i]).collect();
+            stat(&column)
+        });
+        Some(Value::Vector(columns.collect()))
+    }
+}
+
+/// statistic summarizing a sequence of values added for each frame
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Stat {
+    /// arithmetic mean
+    Mean,
+    /// population variance
+    Var,
+    /// middle value, or the mean of the two middle values
+    Median,
+    /// smallest value
+    Min,
+    /// largest value
+    Max,
+    /// value below which a given percent of values lie, interpolated linearly between ranks
+    Percentile(f64),
+    /// mean of absolute differences between consecutive values
+    DMean,
+    /// variance of absolute differences between consecutive values
+    DVar,
+    /// mean of absolute second differences
+    DMean2,
+    /// variance of absolute second differences
+    DVar2,
+}
+
+impl Stat {
+    /// name used as the last part of keys of aggregated values, e.g. "mean" or "p90"
+    pub fn name(&self) -> String {
+        match self {
+            Stat::Mean => "mean".into(),
+            Stat::Var => "var".into(),
+            Stat::Median => "median".into(),
+            Stat::Min => "min".into(),
+            Stat::Max => "max".into(),
+            Stat::Percentile(p) => format!("p{}", p),
+            Stat::DMean => "dmean".into(),
+            Stat::DVar => "dvar".into(),
+            Stat::DMean2 => "dmean2".into(),
+            Stat::DVar2 => "dvar2".into(),
+        }
+    }
+
+    /// read a statistic from its name
+    ///
+    /// Fails with Error::InvalidParam for unknown names and percentiles outside of [0, 100].
+    pub fn from_name(name: &str) -> Result<Self> {
+        let stat = match name {
+            "mean" => Stat::Mean,
+            "var" => Stat::Var,
+            "median" => Stat::Median,
+            "min" => Stat::Min,
+            "max" => Stat::Max,
+            "dmean" => Stat::DMean,
+            "dvar" => Stat::DVar,
+            "dmean2" => Stat::DMean2,
+            "dvar2" => Stat::DVar2,
+            _ => match name.strip_prefix('p').and_then(|p| p.parse::<f64>().ok()) {
+                Some(p) if (0.0..=100.0).contains(&p) => Stat::Percentile(p),
+                _ => return Err(Error::InvalidParam(format!("unknown statistic {}", name))),
+            },
+        };
+        Ok(stat)
+    }
+
+    /// compute the statistic of a sequence, differences of sequences too short to have any are 0
+    pub fn compute(&self, x: &[f64]) -> f64 {
+        let mean = |x: &[f64]| x.iter().sum::<f64>() / x.len() as f64;
+        let var = |x: &[f64]| {
+            let m = mean(x);
+            x.iter().map(|x| (x - m).powi(2)).sum::<f64>() / x.len() as f64
+        };
+        let diff = |x: &[f64]| -> Vec<f64> { x.windows(2).map(|x| x[1] - x[0]).collect() };
+        let abs = |x: Vec<f64>| -> Vec<f64> { x.into_iter().map(f64::abs).collect() };
+        let or_zero = |x: Vec<f64>, f: &dyn Fn(&[f64]) -> f64| {
+            if x.is_empty() {
+                0.0
+            } else {
+                f(&x)
+            }
+        };
+        match self {
+            Stat::Mean => mean(x),
+            Stat::Var => var(x),
+            Stat::Median => percentile(x, 50.0),
+            Stat::Min => x.iter().cloned().fold(f64::INFINITY, f64::min),
+            Stat::Max => x.iter().cloned().fold(f64::NEG_INFINITY, f64::max),
+            Stat::Percentile(p) => percentile(x, *p),
+            Stat::DMean => or_zero(abs(diff(x)), &mean),
+            Stat::DVar => or_zero(abs(diff(x)), &var),
+            Stat::DMean2 => or_zero(abs(diff(&diff(x))), &mean),
+            Stat::DVar2 => or_zero(abs(diff(&diff(x))), &var),
+        }
+    }
+}
+
+/// value below which p percent of values lie, between the closest ranks like numpy
+fn percentile(x: &[f64], p: f64) -> f64 {
+    if x.is_empty() {
+        return f64::NAN;
+    }
+    let mut sorted = x.to_vec();
+    sorted.sort_unstable_by(|a, b| a.total_cmp(b));
+    let rank = p.clamp(0.0, 100.0) / 100.0 * (sorted.len() - 1) as f64;
+    let (low, high) = (rank.floor() as usize, rank.ceil() as usize);
+    sorted[low] + (sorted[high] - sorted[low]) * (rank - low as f64)
+}
+
+impl ToJson for Stat {
+    fn to_json(&self) -> Json {
+        Json::String(self.name())
+    }
+}
+
+impl FromJson for Stat {
+    fn from_json(json: &Json) -> Result<Self> {
+        Stat::from_name(&String::from_json(json)?)
+    }
+}
+
+/// Aggregation of values added for each frame into global descriptors, like the Essentia PoolAggregator
+///
+/// Every statistic of the values under a key is set under the key followed by its name,
+/// e.g. "lowlevel.rms.mean", vectors are aggregated element-wise.
+/// Single values are copied as they are, strings and matrices added for each frame are left out.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Aggregator {
+    /// statistics computed for every key
+    pub stats: Vec<Stat>,
+}
+
+impl Default for Aggregator {
+    fn default() -> Self {
+        Aggregator {
+            stats: vec![
+                Stat::Mean,
+                Stat::Var,
+                Stat::Median,
+                Stat::Min,
+                Stat::Max,
+                Stat::DMean,
+                Stat::DVar,
+            ],
+        }
+    }
+}
+
+impl Aggregator {
+    /// create a new Aggregator computing given statistics
+    pub fn new(stats: Vec<Stat>) -> Self {
+        Aggregator { stats }
+    }
+
+    /// statistics of a sequence of values with their names, none for an empty sequence
+    pub fn compute(&self, x: &[f64]) -> Vec<(String, f64)> {
+        if x.is_empty() {
+            return Vec::new();
+        }
+        self.stats
+            .iter()
+            .map(|s| (s.name(), s.compute(x)))
+            .collect()
+    }
+
+    /// aggregate every key of a Pool into a new Pool of single values
+    pub fn aggregate(&self, pool: &Pool) -> Result<Pool> {
+        let mut aggregated = Pool::new();
+        for key in pool.keys() {
+            if let Some(value) = pool.value(key) {
+                aggregated.set(key, value.clone())?;
+                continue;
+            }
+            for stat in self.stats.iter() {
+                if let Some(value) = pool.aggregate(key, |x| stat.compute(x)) {
+                    aggregated.set(&format!("{}.{}", key, stat.name()), value)?;
+                }
+            }
+        }
+        Ok(aggregated)
+    }
+}
+
+/// nested objects split at the dots in keys, the layout of Essentia extractor outputs
+///
+/// Values added for each frame become arrays, single values are written as they are.
+impl ToJson for Pool {
+    fn to_json(&self) -> Json {
+        let mut root = BTreeMap::new();
+        for (key, values) in &self.frames {
+            insert(&mut root, key, values.to_json());
+        }
+        for (key, value) in &self.single {
+            insert(&mut root, key, value.to_json());
+        }
+        Json::Object(root)
+    }
+}
+
+/// insert a value into nested objects following the dots in its key
+fn insert(fields: &mut BTreeMap<String, Json>, key: &str, value: Json) {
+    if let Some((head, tail)) = key.split_once('.') {
+        let next = fields
+            .entry(head.into())
+            .or_insert_with(|| Json::object([]));
+        if let Json::Object(next) = next {
+            return insert(next, tail, value);
+        }
+        // a value is already stored under this prefix, keep the rest of the key whole
+    }
+    fields.insert(key.into(), value);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Aggregator, Pool, Stat, Value};
+    use crate::json::ToJson;
+    use crate::streaming::{map, Node};
+    use crate::Error;
+
+    #[test]
+    fn pool() {
+        let mut pool = Pool::new();
+        for x in [1.0, 2.0, 6.0] {
+            pool.add("lowlevel.loudness", x).unwrap();
+            pool.add("tonal.hpcp", vec![x, -x]).unwrap();
+        }
+        pool.set("tonal.key", "A").unwrap();
+
+        assert_eq!(
+            pool.keys(),
+            vec!["lowlevel.loudness", "tonal.hpcp", "tonal.key"]
+        );
+        assert_eq!(pool.reals("lowlevel.loudness"), Some(vec![1.0, 2.0, 6.0]));
+        assert_eq!(pool.mean("lowlevel.loudness"), Some(Value::Real(3.0)));
+        assert_eq!(pool.var("lowlevel.loudness"), Some(Value::Real(14.0 / 3.0)));
+        assert_eq!(pool.max("tonal.hpcp"), Some(Value::Vector(vec![6.0, -1.0])));
+        assert_eq!(pool.mean("tonal.key"), None);
+
+        let result = pool.add("lowlevel.loudness", "loud");
+        assert!(matches!(result, Err(Error::InvalidParam(_))));
+        let result = pool.set("tonal.hpcp", 0.0);
+        assert!(matches!(result, Err(Error::InvalidParam(_))));
+
+        let mut other = Pool::new();
+        other.add("lowlevel.loudness", 3.0).unwrap();
+        pool.merge(other).unwrap();
+        assert_eq!(pool.min("lowlevel.loudness"), Some(Value::Real(1.0)));
+        assert_eq!(pool.get("lowlevel.loudness").unwrap().len(), 4);
+
+        let mut network = map(|x: f64| x * 2.0);
+        network
+            .run(vec![Ok(1.0), Ok(2.0)], pool.sink("doubled"))
+            .unwrap();
+        assert_eq!(pool.reals("doubled"), Some(vec![2.0, 4.0]));
+
+        let json = pool.to_json();
+        assert_eq!(
+            json.to_string(),
+            r#"{"doubled":[2,4],"lowlevel":{"loudness":[1,2,6,3]},"tonal":{"hpcp":[[1,-1],[2,-2],[6,-6]],"key":"A"}}"#
+        );
+        let yaml = "doubled: [2, 4]\nlowlevel:\n    loudness: [1, 2, 6, 3]\ntonal:\n    hpcp: [[1, -1], [2, -2], [6, -6]]\n    key: \"A\"\n";
+        assert_eq!(json.yaml(), yaml);
+    }
+
+    #[test]
+    fn aggregator() {
+        let x = [1.0, 3.0, 2.0, 6.0];
+        assert_eq!(Stat::Median.compute(&x), 2.5);
+        assert_eq!(Stat::Percentile(25.0).compute(&x), 1.75);
+        assert_eq!(Stat::Percentile(100.0).compute(&x), 6.0);
+        // differences are 2, -1, 4 and second differences -3, 5
+        assert_eq!(Stat::DMean.compute(&x), 7.0 / 3.0);
+        assert_eq!(Stat::DMean2.compute(&x), 4.0);
+        assert_eq!(Stat::DVar2.compute(&x), 1.0);
+        assert_eq!(Stat::DVar.compute(&[1.0]), 0.0);
+
+        for name in ["mean", "dvar2", "p12.5"] {
+            assert_eq!(Stat::from_name(name).unwrap().name(), name);
+        }
+        assert!(matches!(
+            Stat::from_name("p101"),
+            Err(Error::InvalidParam(_))
+        ));
+        assert!(matches!(
+            Stat::from_name("mode"),
+            Err(Error::InvalidParam(_))
+        ));
+
+        let mut pool = Pool::new();
+        for x in x {
+            pool.add("rms", x).unwrap();
+            pool.add("hpcp", vec![x, 1.0]).unwrap();
+            pool.add("key", "A").unwrap();
+        }
+        pool.set("bpm", 120.0).unwrap();
+        let aggregator = Aggregator::new(vec![Stat::Max, Stat::Percentile(50.0)]);
+        let aggregated = aggregator.aggregate(&pool).unwrap();
+        assert_eq!(
+            aggregated.keys(),
+            vec!["bpm", "hpcp.max", "hpcp.p50", "rms.max", "rms.p50"]
+        );
+        assert_eq!(
+            aggregated.value("hpcp.p50"),
+            Some(&Value::Vector(vec![2.5, 1.0]))
+        );
+        assert_eq!(aggregated.value("bpm"), Some(&Value::Real(120.0)));
+    }
+}
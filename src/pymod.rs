@@ -1,15 +1,181 @@
 use pyo3::prelude::*;
+use pyo3::wrap_pymodule;
 
 use crate::algs::*;
 
+/// conversions of numeric arrays from and to Python objects
+pub(crate) mod array;
+/// exceptions raised from the Python module
+pub(crate) mod errors;
+/// analysis results over time with the values along both axes
+pub(crate) mod matrix;
+/// tags and stream properties of audio files
+pub(crate) mod metadata;
+/// configuration of Python classes with named parameters
+pub(crate) mod state;
+/// running computations in the background for asyncio
+pub(crate) mod tasks;
+
 #[pymodule]
 /// Rust library for music synthesis and processing, inspired by Essentia.
-fn muslib(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
-    m.add_class::<hpcp::HPCP>()?;
+///
+/// Inputs accept lists as well as numpy arrays or any other buffer, contiguous ones are read in place.
+/// Outputs are returned as numpy arrays owning their memory when numpy is installed, and as lists otherwise.
+/// The GIL is released while computing, so algorithms can run concurrently in threads.
+/// Each .compute() has a .compute_async() variant that can be awaited in asyncio code.
+///
+/// Classes are grouped in submodules, e.g. muslib.io or muslib.synth,
+/// and all of them are also available directly in muslib.
+fn muslib(py: Python<'_>, m: &PyModule) -> PyResult<()> {
+    m.add("MuslibError", py.get_type::<errors::MuslibError>())?;
+    m.add_class::<matrix::Matrix>()?;
+    add_submodule(py, m, wrap_pymodule!(denoise_module)(py))?;
+    add_submodule(py, m, wrap_pymodule!(dynamics_module)(py))?;
+    add_submodule(py, m, wrap_pymodule!(filters_module)(py))?;
+    add_submodule(py, m, wrap_pymodule!(io_module)(py))?;
+    add_submodule(py, m, wrap_pymodule!(problems_module)(py))?;
+    add_submodule(py, m, wrap_pymodule!(resample_module)(py))?;
+    add_submodule(py, m, wrap_pymodule!(restore_module)(py))?;
+    add_submodule(py, m, wrap_pymodule!(spectral_module)(py))?;
+    add_submodule(py, m, wrap_pymodule!(stereo_module)(py))?;
+    add_submodule(py, m, wrap_pymodule!(stretch_module)(py))?;
+    add_submodule(py, m, wrap_pymodule!(synth_module)(py))?;
+    add_submodule(py, m, wrap_pymodule!(tonal_module)(py))?;
+    Ok(())
+}
+
+#[pymodule]
+#[pyo3(name = "denoise")]
+/// Noise reduction with spectral subtraction.
+fn denoise_module(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
+    m.add_class::<denoise::Denoiser>()?;
+    Ok(())
+}
+
+#[pymodule]
+#[pyo3(name = "dynamics")]
+/// Dynamics processors with sidechain input.
+fn dynamics_module(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
+    m.add_class::<dynamics::Compressor>()?;
+    m.add_class::<dynamics::Gate>()?;
+    Ok(())
+}
+
+#[pymodule]
+#[pyo3(name = "filters")]
+/// Smoothing filters.
+fn filters_module(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
+    m.add_class::<filters::MedianFilter>()?;
+    m.add_class::<filters::MovingAverage>()?;
+    Ok(())
+}
+
+#[pymodule]
+#[pyo3(name = "io")]
+/// Input and output of audio files and streams.
+fn io_module(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
+    m.add_class::<metadata::AudioMetadata>()?;
+    m.add_class::<frames::FrameGenerator>()?;
+    m.add_class::<io::MonoFloatLoader>()?;
     m.add_class::<io::MonoLoader>()?;
     m.add_class::<io::MonoWriter>()?;
+    m.add_class::<io::MonoWriterStream>()?;
+    Ok(())
+}
+
+#[pymodule]
+#[pyo3(name = "problems")]
+/// Detection of audio problems for quality control.
+fn problems_module(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
+    m.add_class::<problems::ClickDetector>()?;
+    m.add_class::<problems::GapDetector>()?;
+    m.add_class::<problems::HumDetector>()?;
+    m.add_class::<problems::SaturationDetector>()?;
+    m.add_class::<problems::StartStopSilence>()?;
+    m.add_class::<problems::TruePeakDetector>()?;
+    Ok(())
+}
+
+#[pymodule]
+#[pyo3(name = "resample")]
+/// Sample rate conversion.
+fn resample_module(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
+    m.add_class::<resample::Resample>()?;
+    Ok(())
+}
+
+#[pymodule]
+#[pyo3(name = "restore")]
+/// Restoration of clicks and clipped regions.
+fn restore_module(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
+    m.add_class::<restore::Declicker>()?;
+    m.add_class::<restore::Declipper>()?;
+    Ok(())
+}
+
+#[pymodule]
+#[pyo3(name = "spectral")]
+/// Spectral analysis with the Fourier transform.
+fn spectral_module(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
     m.add_class::<stft::FFT>()?;
     m.add_class::<stft::IFFT>()?;
+    Ok(())
+}
+
+#[pymodule]
+#[pyo3(name = "stereo")]
+/// Mid-side processing of stereo signals.
+fn stereo_module(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
+    m.add_class::<stereo::MidSide>()?;
+    m.add_class::<stereo::StereoWidener>()?;
+    Ok(())
+}
+
+#[pymodule]
+#[pyo3(name = "stretch")]
+/// Time-stretching with WSOLA.
+fn stretch_module(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
+    m.add_class::<stretch::WSOLA>()?;
+    Ok(())
+}
+
+#[pymodule]
+#[pyo3(name = "synth")]
+/// Synthesis of simple waveforms.
+fn synth_module(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
+    m.add_class::<synth::Envelope>()?;
+    m.add_class::<synth::Generator>()?;
+    m.add_class::<synth::LFO>()?;
+    m.add_class::<synth::Noise>()?;
+    m.add_class::<synth::Sweep>()?;
     m.add_class::<synth::Synthesizer>()?;
+    m.add_class::<synth::Unison>()?;
+    m.add_class::<synth::Wavetable>()?;
     Ok(())
 }
+
+#[pymodule]
+#[pyo3(name = "tonal")]
+/// Tonal analysis.
+fn tonal_module(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
+    m.add_class::<hpcp::HPCP>()?;
+    Ok(())
+}
+
+/// add a submodule and all of its classes to the parent module
+///
+/// The submodule is registered in sys.modules, so it can be imported with `from muslib.io import ...`.
+fn add_submodule(py: Python<'_>, parent: &PyModule, module: Py<PyModule>) -> PyResult<()> {
+    let module = module.as_ref(py);
+    for (key, value) in module.dict().iter() {
+        let key: &str = key.extract()?;
+        if !key.starts_with("__") {
+            parent.add(key, value)?;
+        }
+    }
+    parent.add_submodule(module)?;
+    let path = format!("{}.{}", parent.name()?, module.name()?);
+    module.setattr("__name__", &path)?;
+    let modules = py.import("sys")?.getattr("modules")?;
+    modules.set_item(path, module)
+}
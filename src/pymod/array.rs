@@ -0,0 +1,417 @@
+use std::any::Any;
+use std::ops::Deref;
+use std::os::raw::{c_int, c_void};
+
+use pyo3::buffer::{Element, PyBuffer};
+use pyo3::exceptions::{PyBufferError, PyDeprecationWarning, PyValueError};
+use pyo3::ffi;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+use crate::Sample;
+
+/// one-dimensional array of numbers passed between Rust and Python
+///
+/// It is extracted from any object supporting the buffer protocol, like a numpy array,
+/// and falls back to a Python sequence otherwise.
+/// It is converted to a numpy array owning its memory when numpy is available, and to a list otherwise.
+pub struct Array<T>(pub Vec<T>);
+
+impl<T> From<Vec<T>> for Array<T> {
+    fn from(value: Vec<T>) -> Self {
+        Array(value)
+    }
+}
+
+impl<'a, T: Element + FromPyObject<'a>> FromPyObject<'a> for Array<T> {
+    fn extract(ob: &'a PyAny) -> PyResult<Self> {
+        Ok(Array(ArrayView::extract(ob)?.into_vec()))
+    }
+}
+
+impl<T: Dtype + IntoPy<PyObject>> IntoPy<PyObject> for Array<T> {
+    fn into_py(self, py: Python<'_>) -> PyObject {
+        match py.import("numpy") {
+            Ok(numpy) => numpy_array(numpy, self.0, T::DTYPE, None),
+            Err(_) => self.0.into_py(py),
+        }
+    }
+}
+
+/// one-dimensional array of numbers borrowed from Python for the duration of a call
+///
+/// A contiguous buffer of the same type, like a numpy array, is read in place without copying it.
+/// Other buffers and Python sequences are copied, like for an Array.
+/// It must only be read while holding the GIL, so that Python code can't modify it meanwhile.
+pub struct ArrayView<T: Element>(View<T>);
+
+enum View<T: Element> {
+    Borrowed(PyBuffer<T>),
+    Owned(Vec<T>),
+}
+
+impl<T: Element> ArrayView<T> {
+    /// take the numbers, copying them only if they are borrowed
+    pub fn into_vec(self) -> Vec<T> {
+        match self.0 {
+            View::Borrowed(ref buf) => contiguous(buf).to_vec(),
+            View::Owned(data) => data,
+        }
+    }
+
+    /// store the numbers in an input of an algorithm, reusing its memory
+    pub fn assign_to(self, target: &mut Vec<T>) {
+        match self.0 {
+            View::Borrowed(ref buf) => {
+                target.clear();
+                target.extend_from_slice(contiguous(buf));
+            }
+            View::Owned(data) => *target = data,
+        }
+    }
+}
+
+impl<T: Element> Deref for ArrayView<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        match &self.0 {
+            View::Borrowed(buf) => contiguous(buf),
+            View::Owned(data) => data,
+        }
+    }
+}
+
+impl<'a, T: Element + FromPyObject<'a>> FromPyObject<'a> for ArrayView<T> {
+    fn extract(ob: &'a PyAny) -> PyResult<Self> {
+        if let Ok(buf) = PyBuffer::<T>::get(ob) {
+            if buf.dimensions() == 1 {
+                if buf.is_c_contiguous() {
+                    return Ok(ArrayView(View::Borrowed(buf)));
+                }
+                return Ok(ArrayView(View::Owned(buf.to_vec(ob.py())?)));
+            }
+        }
+        Ok(ArrayView(View::Owned(ob.extract()?)))
+    }
+}
+
+/// items of a C-contiguous buffer, read in place
+fn contiguous<T: Element>(buf: &PyBuffer<T>) -> &[T] {
+    debug_assert!(buf.is_c_contiguous());
+    if buf.item_count() == 0 {
+        return &[];
+    }
+    // SAFETY: PyBuffer::get checked the item size and alignment for T,
+    // the buffer is contiguous and stays exported until the PyBuffer is dropped
+    unsafe { std::slice::from_raw_parts(buf.buf_ptr() as *const T, buf.item_count()) }
+}
+
+/// one-dimensional array of complex numbers passed between Rust and Python
+///
+/// It is extracted from a complex numpy array, or a sequence of (re, im) pairs.
+/// It is converted to a complex numpy array when numpy is available,
+/// and to a list of (re, im) tuples otherwise.
+pub struct ComplexArray<T>(pub Vec<(T, T)>);
+
+impl<T> From<Vec<(T, T)>> for ComplexArray<T> {
+    fn from(value: Vec<(T, T)>) -> Self {
+        ComplexArray(value)
+    }
+}
+
+impl<'a> FromPyObject<'a> for ComplexArray<f64> {
+    fn extract(ob: &'a PyAny) -> PyResult<Self> {
+        let py = ob.py();
+        if ob.hasattr("__array__")? {
+            if let Ok(numpy) = py.import("numpy") {
+                let kwargs = PyDict::new(py);
+                kwargs.set_item("dtype", numpy.getattr("complex128")?)?;
+                let complex = numpy.call_method("ascontiguousarray", (ob,), Some(kwargs))?;
+                if complex.getattr("ndim")?.extract::<usize>()? == 1 {
+                    let floats = complex.call_method1("view", (numpy.getattr("float64")?,))?;
+                    let buf = PyBuffer::<f64>::get(floats)?;
+                    let data = contiguous(&buf).chunks_exact(2);
+                    return Ok(ComplexArray(data.map(|x| (x[0], x[1])).collect()));
+                }
+            }
+        }
+        Ok(ComplexArray(ob.extract()?))
+    }
+}
+
+impl<T: Dtype + IntoPy<PyObject>> IntoPy<PyObject> for ComplexArray<T> {
+    fn into_py(self, py: Python<'_>) -> PyObject {
+        match py.import("numpy") {
+            Ok(numpy) => {
+                let data = self.0.iter().flat_map(|&(re, im)| [re, im]).collect();
+                numpy_array(numpy, data, T::COMPLEX_DTYPE, None)
+            }
+            Err(_) => self.0.into_py(py),
+        }
+    }
+}
+
+/// signed 16-bit pcm data, silent at 0, like the output of MonoLoader and the Synthesizer
+///
+/// It is extracted from buffers of signed or unsigned 16-bit ints, keeping the encoding of their dtype,
+/// so unsigned ones are taken as offset binary pcm centered at 0x8000,
+/// from raw bytes of signed 16-bit little-endian samples, like frames read with the wave module,
+/// or from a sequence of signed ints.
+/// A plain list means the same as an int16 array, with or without numpy.
+/// Lists with values above 32767 are taken as the deprecated unsigned pcm centered at 32767
+/// of .compute_u16() and the old MonoLoader, with a DeprecationWarning.
+pub struct Pcm16(pub Vec<i16>);
+
+impl<'a> FromPyObject<'a> for Pcm16 {
+    fn extract(ob: &'a PyAny) -> PyResult<Self> {
+        if let Ok(buf) = PyBuffer::<i16>::get(ob) {
+            if buf.dimensions() == 1 {
+                return Ok(Pcm16(ArrayView::extract(ob)?.into_vec()));
+            }
+        }
+        if let Ok(buf) = PyBuffer::<u16>::get(ob) {
+            if buf.dimensions() == 1 {
+                let data = ArrayView::<u16>::extract(ob)?;
+                return Ok(Pcm16(data.iter().map(|x| (x ^ 0x8000) as i16).collect()));
+            }
+        }
+        if let Ok(buf) = PyBuffer::<u8>::get(ob) {
+            if buf.dimensions() == 1 {
+                let bytes = ArrayView::<u8>::extract(ob)?;
+                if bytes.len() % 2 != 0 {
+                    return Err(PyValueError::new_err(
+                        "raw 16-bit pcm data must have an even number of bytes",
+                    ));
+                }
+                let data = bytes.chunks_exact(2);
+                return Ok(Pcm16(
+                    data.map(|x| i16::from_le_bytes([x[0], x[1]])).collect(),
+                ));
+            }
+        }
+        let err = match ob.extract() {
+            Ok(data) => return Ok(Pcm16(data)),
+            Err(err) => err,
+        };
+        let Ok(data) = ob.extract::<Vec<u16>>() else {
+            return Err(err);
+        };
+        let py = ob.py();
+        PyErr::warn(
+            py,
+            py.get_type::<PyDeprecationWarning>(),
+            "lists of unsigned pcm centered at 32767 are deprecated, pass signed 16-bit pcm instead",
+            1,
+        )?;
+        Ok(Pcm16(data.into_iter().map(from_legacy_u16).collect()))
+    }
+}
+
+/// signed pcm of a value of the old unsigned lists, undoing legacy_u16 of the Synthesizer
+fn from_legacy_u16(x: u16) -> i16 {
+    let m = (u16::MAX / 2) as f64;
+    i16::from_f64((x as f64 - m) / m)
+}
+
+/// two-dimensional array of numbers, one row for each frame
+///
+/// It is extracted from a 2-D buffer, like a numpy array, or a sequence of rows.
+/// Rows of a sequence may differ in length.
+/// It is converted to a 2-D numpy array owning its memory when numpy is available and all rows have the same length,
+/// and to a list of rows otherwise.
+pub struct Array2<T>(pub Vec<Vec<T>>);
+
+impl<T> From<Vec<Vec<T>>> for Array2<T> {
+    fn from(value: Vec<Vec<T>>) -> Self {
+        Array2(value)
+    }
+}
+
+impl<'a, T: Element + FromPyObject<'a>> FromPyObject<'a> for Array2<T> {
+    fn extract(ob: &'a PyAny) -> PyResult<Self> {
+        if let Ok(buf) = PyBuffer::<T>::get(ob) {
+            if buf.dimensions() == 2 {
+                let (rows, cols) = (buf.shape()[0], buf.shape()[1]);
+                if cols == 0 {
+                    return Ok(Array2(vec![Vec::new(); rows]));
+                }
+                if buf.is_c_contiguous() {
+                    let data = contiguous(&buf).chunks(cols);
+                    return Ok(Array2(data.map(|x| x.to_vec()).collect()));
+                }
+                let data = buf.to_vec(ob.py())?;
+                return Ok(Array2(data.chunks(cols).map(|x| x.to_vec()).collect()));
+            }
+        }
+        let mut rows = Vec::new();
+        for row in ob.iter()? {
+            rows.push(row?.extract::<Array<T>>()?.0);
+        }
+        Ok(Array2(rows))
+    }
+}
+
+impl<T: Dtype + IntoPy<PyObject>> IntoPy<PyObject> for Array2<T> {
+    fn into_py(self, py: Python<'_>) -> PyObject {
+        match (py.import("numpy"), shape(&self.0)) {
+            (Ok(numpy), Some(shape)) => {
+                let data = self.0.into_iter().flatten().collect();
+                numpy_array(numpy, data, T::DTYPE, Some(shape))
+            }
+            _ => self
+                .0
+                .into_iter()
+                .map(Array)
+                .collect::<Vec<_>>()
+                .into_py(py),
+        }
+    }
+}
+
+/// two-dimensional array of complex numbers, one row for each frame
+///
+/// It is converted to a 2-D complex numpy array when numpy is available and all rows have the same length,
+/// and to a list of rows otherwise.
+pub struct ComplexArray2<T>(pub Vec<Vec<(T, T)>>);
+
+impl<T> From<Vec<Vec<(T, T)>>> for ComplexArray2<T> {
+    fn from(value: Vec<Vec<(T, T)>>) -> Self {
+        ComplexArray2(value)
+    }
+}
+
+impl<T: Dtype + IntoPy<PyObject>> IntoPy<PyObject> for ComplexArray2<T> {
+    fn into_py(self, py: Python<'_>) -> PyObject {
+        match (py.import("numpy"), shape(&self.0)) {
+            (Ok(numpy), Some(shape)) => {
+                let data = self.0.iter().flatten().flat_map(|&(re, im)| [re, im]);
+                numpy_array(numpy, data.collect(), T::COMPLEX_DTYPE, Some(shape))
+            }
+            _ => {
+                let rows = self.0.into_iter().map(ComplexArray);
+                rows.collect::<Vec<_>>().into_py(py)
+            }
+        }
+    }
+}
+
+/// numeric types with a matching numpy dtype
+pub trait Dtype: Copy + Send + 'static {
+    /// name of the numpy dtype
+    const DTYPE: &'static str;
+    /// name of the numpy dtype for complex numbers made of pairs of this type
+    const COMPLEX_DTYPE: &'static str = "";
+}
+
+impl Dtype for f64 {
+    const DTYPE: &'static str = "float64";
+    const COMPLEX_DTYPE: &'static str = "complex128";
+}
+
+impl Dtype for f32 {
+    const DTYPE: &'static str = "float32";
+    const COMPLEX_DTYPE: &'static str = "complex64";
+}
+
+impl Dtype for u16 {
+    const DTYPE: &'static str = "uint16";
+}
+
+impl Dtype for i16 {
+    const DTYPE: &'static str = "int16";
+}
+
+/// memory of a Vec handed over to numpy, exposed through the buffer protocol
+///
+/// The numpy array keeps it as its base, so the Vec lives as long as the array
+/// and its numbers are never copied.
+#[pyclass(module = "muslib")]
+struct VecBuffer {
+    _data: Box<dyn Any + Send>,
+    ptr: *mut u8,
+    len: usize,
+}
+
+// SAFETY: the pointer only refers to the heap memory of the boxed Vec, which moves with it
+unsafe impl Send for VecBuffer {}
+
+impl VecBuffer {
+    fn new<T: Send + 'static>(mut data: Vec<T>) -> Self {
+        let ptr = data.as_mut_ptr() as *mut u8;
+        let len = std::mem::size_of_val(data.as_slice());
+        VecBuffer {
+            _data: Box::new(data),
+            ptr,
+            len,
+        }
+    }
+}
+
+#[pymethods]
+impl VecBuffer {
+    /// expose the memory as writable bytes, numpy reads them with the dtype of the array
+    unsafe fn __getbuffer__(
+        slf: &PyCell<Self>,
+        view: *mut ffi::Py_buffer,
+        flags: c_int,
+    ) -> PyResult<()> {
+        if view.is_null() {
+            return Err(PyBufferError::new_err("view is null"));
+        }
+        let buffer = slf.borrow();
+        (*view).obj = ffi::_Py_NewRef(slf.as_ptr());
+        (*view).buf = buffer.ptr as *mut c_void;
+        (*view).len = buffer.len as isize;
+        (*view).readonly = 0;
+        (*view).itemsize = 1;
+        (*view).format = if flags & ffi::PyBUF_FORMAT == ffi::PyBUF_FORMAT {
+            c"B".as_ptr() as *mut _
+        } else {
+            std::ptr::null_mut()
+        };
+        (*view).ndim = 1;
+        (*view).shape = if flags & ffi::PyBUF_ND == ffi::PyBUF_ND {
+            &mut (*view).len
+        } else {
+            std::ptr::null_mut()
+        };
+        (*view).strides = if flags & ffi::PyBUF_STRIDES == ffi::PyBUF_STRIDES {
+            &mut (*view).itemsize
+        } else {
+            std::ptr::null_mut()
+        };
+        (*view).suboffsets = std::ptr::null_mut();
+        (*view).internal = std::ptr::null_mut();
+        Ok(())
+    }
+}
+
+/// hand a Vec over to a writable numpy array without copying it, reshaped to rows and columns if given
+fn numpy_array<T: Dtype>(
+    numpy: &PyModule,
+    data: Vec<T>,
+    dtype: &str,
+    shape: Option<(usize, usize)>,
+) -> PyObject {
+    let py = numpy.py();
+    let wrap = || -> PyResult<PyObject> {
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("dtype", dtype)?;
+        let buffer = PyCell::new(py, VecBuffer::new(data))?;
+        let array = numpy.call_method("frombuffer", (buffer,), Some(kwargs))?;
+        match shape {
+            Some(shape) => Ok(array.call_method1("reshape", (shape,))?.into()),
+            None => Ok(array.into()),
+        }
+    };
+    wrap().expect("numpy.frombuffer accepts a VecBuffer")
+}
+
+/// rows and columns of a matrix, if all rows have the same length
+fn shape<R>(rows: &[Vec<R>]) -> Option<(usize, usize)> {
+    let cols = rows.first().map_or(0, |x| x.len());
+    rows.iter()
+        .all(|x| x.len() == cols)
+        .then_some((rows.len(), cols))
+}
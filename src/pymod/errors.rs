@@ -0,0 +1,26 @@
+use pyo3::create_exception;
+use pyo3::exceptions::{PyException, PyValueError};
+use pyo3::PyErr;
+
+use crate::Error;
+
+create_exception!(
+    muslib,
+    MuslibError,
+    PyException,
+    "Error raised by muslib when audio can not be processed."
+);
+
+/// convert a muslib Error into a Python exception
+///
+/// I/O errors become their matching OSError subclass, e.g. FileNotFoundError,
+/// and invalid parameters become a ValueError.
+impl From<Error> for PyErr {
+    fn from(err: Error) -> Self {
+        match err {
+            Error::Io(err) => err.into(),
+            Error::InvalidParam(msg) => PyValueError::new_err(msg),
+            err => MuslibError::new_err(err.to_string()),
+        }
+    }
+}
@@ -0,0 +1,107 @@
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+use super::array::{Array, Array2};
+
+/// Analysis results of consecutive frames with the values along both axes
+///
+/// Rows are bins and columns are frames, so it can be shown directly with
+/// plt.imshow(m, origin="lower", aspect="auto", extent=m.extent).
+/// It converts to a 2-D numpy array with numpy.asarray().
+#[pyclass(module = "muslib")]
+pub struct Matrix {
+    // values of each frame, transposed when read from Python
+    frames: Vec<Vec<f64>>,
+    times: Vec<f64>,
+    bins: Vec<f64>,
+    extent: (f64, f64, f64, f64),
+}
+
+#[pymethods]
+impl Matrix {
+    /// Output: list[list[float]] -- values with a row for each bin and a column for each frame
+    #[getter]
+    fn data(&self) -> Array2<f64> {
+        self.rows().into()
+    }
+
+    /// Output: list[float] -- start of each frame in seconds
+    #[getter]
+    fn times(&self) -> Array<f64> {
+        self.times.clone().into()
+    }
+
+    /// Output: list[float] -- center of each bin, e.g. a frequency in Hz
+    #[getter]
+    fn bins(&self) -> Array<f64> {
+        self.bins.clone().into()
+    }
+
+    /// Output: tuple[int, int] -- number of bins and frames
+    #[getter]
+    fn shape(&self) -> (usize, usize) {
+        (self.bins.len(), self.frames.len())
+    }
+
+    /// Output: tuple[float, float, float, float] -- left, right, bottom and top edges for plt.imshow
+    #[getter]
+    fn extent(&self) -> (f64, f64, f64, f64) {
+        self.extent
+    }
+
+    // numpy 2 also passes copy, but a new array is made on every call anyway
+    #[pyo3(signature = (dtype=None, copy=None))]
+    fn __array__(
+        &self,
+        py: Python<'_>,
+        dtype: Option<&PyAny>,
+        #[allow(unused_variables)] copy: Option<bool>,
+    ) -> PyResult<PyObject> {
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("dtype", dtype)?;
+        let numpy = py.import("numpy")?;
+        let array = numpy.call_method("asarray", (self.data().into_py(py),), Some(kwargs))?;
+        Ok(array.into())
+    }
+
+    fn __len__(&self) -> usize {
+        self.bins.len()
+    }
+
+    fn __repr__(&self) -> String {
+        let (bins, frames) = self.shape();
+        format!("Matrix(bins={}, frames={})", bins, frames)
+    }
+}
+
+impl Matrix {
+    /// collect values of frames that start every hop seconds, with bins spaced evenly
+    pub(crate) fn new(frames: Vec<Vec<f64>>, hop: f64, bins: Vec<f64>) -> Self {
+        let times: Vec<f64> = (0..frames.len()).map(|i| i as f64 * hop).collect();
+        // pixels are centered on bins, each one as wide as the step between them
+        let step = match bins.as_slice() {
+            [first, second, ..] => second - first,
+            _ => 1.0,
+        };
+        let bottom = bins.first().map_or(0.0, |x| x - step / 2.0);
+        let top = bins.last().map_or(0.0, |x| x + step / 2.0);
+        let extent = (0.0, times.len() as f64 * hop, bottom, top);
+        Matrix {
+            frames,
+            times,
+            bins,
+            extent,
+        }
+    }
+
+    fn rows(&self) -> Vec<Vec<f64>> {
+        (0..self.bins.len())
+            .map(|i| {
+                self.frames
+                    .iter()
+                    .map(|x| x.get(i).copied().unwrap_or(0.0))
+                    .collect()
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,72 @@
+use pyo3::prelude::*;
+
+use crate::mixer::{Loader, Metadata};
+
+/// Tags and stream properties of an audio file, read without decoding it
+///
+/// Fields missing from the file are None.
+#[pyclass(module = "muslib", get_all)]
+pub struct AudioMetadata {
+    /// Output: str -- path to the file
+    pub file: String,
+    /// Output: Optional[str] -- title of the track
+    pub title: Option<String>,
+    /// Output: Optional[str] -- artist performing the track
+    pub artist: Option<String>,
+    /// Output: Optional[str] -- album the track is on
+    pub album: Option<String>,
+    /// Output: Optional[int] -- number of the track on the album
+    pub track: Option<u32>,
+    /// Output: Optional[float] -- length of the track in seconds
+    pub duration: Option<f64>,
+    /// Output: Optional[str] -- short name of the codec, e.g. "flac" or "pcm_s16le"
+    pub codec: Option<String>,
+    /// Output: Optional[int] -- average bitrate in bits per second, including the container
+    pub bitrate: Option<u32>,
+    /// Output: Optional[int] -- sample rate of the track
+    pub sample_rate: Option<u32>,
+    /// Output: Optional[int] -- number of channels of the track
+    pub channels: Option<usize>,
+    /// Output: list[tuple[str, str]] -- all tags as keys and values, as named in the file
+    pub tags: Vec<(String, String)>,
+}
+
+#[pymethods]
+impl AudioMetadata {
+    /// Read the metadata of a file
+    #[new]
+    fn pynew(py: Python<'_>, file: String) -> PyResult<Self> {
+        let metadata =
+            py.allow_threads(|| Loader::<f64>::new().file(file.clone().into()).metadata())?;
+        Ok(AudioMetadata::new(file, metadata))
+    }
+
+    fn __repr__(&self) -> String {
+        let text = |x: &Option<String>| x.as_ref().map_or("None".into(), |x| format!("{:?}", x));
+        format!(
+            "AudioMetadata(file={:?}, title={}, artist={}, duration={})",
+            self.file,
+            text(&self.title),
+            text(&self.artist),
+            self.duration.map_or("None".into(), |x| format!("{:?}", x))
+        )
+    }
+}
+
+impl AudioMetadata {
+    fn new(file: String, metadata: Metadata) -> Self {
+        AudioMetadata {
+            file,
+            title: metadata.title,
+            artist: metadata.artist,
+            album: metadata.album,
+            track: metadata.track,
+            duration: metadata.duration,
+            codec: metadata.codec,
+            bitrate: metadata.bitrate,
+            sample_rate: metadata.sample_rate,
+            channels: metadata.channels,
+            tags: metadata.tags,
+        }
+    }
+}
@@ -0,0 +1,179 @@
+use pyo3::exceptions::PyTypeError;
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyType};
+
+/// Python classes configured with a set of named parameters
+pub(crate) trait Params {
+    /// names of the Python attributes that hold parameters
+    const PARAMS: &'static [&'static str];
+}
+
+/// Python methods of a class configured with named parameters, see Params
+///
+/// Takes the impl block of the class, the names of its parameters, whether it has .compute_async(),
+/// and optionally the body of reset() clearing outputs and internal state.
+/// Adds pickling with __getstate__() and __setstate__(), to_dict(), from_dict(), configure(),
+/// reset() and __repr__() to the methods of the block, all in one #[pymethods] impl,
+/// since pyo3 allows only one for each class.
+///
+/// ```text
+/// pymethods_with_params! {
+///     impl HPCP {
+///         #[new]
+///         fn pynew() -> Self { ... }
+///     }
+///
+///     params = ["size", "sample_rate"];
+///     async = compute_async;
+///
+///     fn reset(&mut self) {
+///         self.hpcp_data = None;
+///     }
+/// }
+/// ```
+macro_rules! pymethods_with_params {
+    (
+        impl $name:ident { $($methods:tt)* }
+
+        params = [$($param:literal),* $(,)?];
+        $(async = $compute_async:ident;)?
+    ) => {
+        // nothing to clear, there are no outputs or internal state
+        $crate::pymod::state::pymethods_with_params! {
+            impl $name { $($methods)* }
+
+            params = [$($param),*];
+            $(async = $compute_async;)?
+
+            fn reset(&mut self) {}
+        }
+    };
+    (
+        impl $name:ident { $($methods:tt)* }
+
+        params = [$($param:literal),* $(,)?];
+        $(async = $compute_async:ident;)?
+
+        fn reset(&mut $this:ident) $reset:block
+    ) => {
+        #[::pyo3::pymethods]
+        impl $name {
+            $($methods)*
+
+            $(
+                /// Compute the Algorithm on a background thread
+                ///
+                /// Takes the same arguments as .compute() and returns an awaitable with its outputs,
+                /// so it can be used in asyncio code without blocking the event loop.
+                #[pyo3(signature = (*args, **kwargs))]
+                fn $compute_async<'py>(
+                    slf: &'py ::pyo3::PyCell<Self>,
+                    args: &::pyo3::types::PyTuple,
+                    kwargs: Option<&::pyo3::types::PyDict>,
+                ) -> ::pyo3::PyResult<&'py ::pyo3::PyAny> {
+                    $crate::pymod::tasks::run_in_executor(slf, "compute", args, kwargs)
+                }
+            )?
+
+            fn __getstate__(
+                slf: &::pyo3::PyCell<Self>,
+            ) -> ::pyo3::PyResult<&::pyo3::types::PyDict> {
+                $crate::pymod::state::to_dict(slf, <Self as $crate::pymod::state::Params>::PARAMS)
+            }
+
+            fn __setstate__(
+                slf: &::pyo3::PyCell<Self>,
+                state: &::pyo3::types::PyDict,
+            ) -> ::pyo3::PyResult<()> {
+                $crate::pymod::state::set_from_dict(slf, <Self as $crate::pymod::state::Params>::PARAMS, state)
+            }
+
+            /// parameters of this instance as a dict
+            fn to_dict(slf: &::pyo3::PyCell<Self>) -> ::pyo3::PyResult<&::pyo3::types::PyDict> {
+                $crate::pymod::state::to_dict(slf, <Self as $crate::pymod::state::Params>::PARAMS)
+            }
+
+            /// create a new instance with parameters from a dict
+            #[classmethod]
+            fn from_dict<'py>(
+                cls: &'py ::pyo3::types::PyType,
+                params: &::pyo3::types::PyDict,
+            ) -> ::pyo3::PyResult<&'py ::pyo3::PyAny> {
+                $crate::pymod::state::from_dict(cls, <Self as $crate::pymod::state::Params>::PARAMS, params)
+            }
+
+            /// set parameters given as keyword arguments, others keep their current values
+            #[pyo3(signature = (**params))]
+            fn configure(
+                slf: &::pyo3::PyCell<Self>,
+                params: Option<&::pyo3::types::PyDict>,
+            ) -> ::pyo3::PyResult<()> {
+                match params {
+                    Some(params) => $crate::pymod::state::set_from_dict(slf, <Self as $crate::pymod::state::Params>::PARAMS, params),
+                    None => Ok(()),
+                }
+            }
+
+            /// clear outputs and any internal state carried over between calls
+            #[pyo3(name = "reset")]
+            fn pyreset(&mut $this) $reset
+
+            fn __repr__(slf: &::pyo3::PyCell<Self>) -> ::pyo3::PyResult<String> {
+                $crate::pymod::state::repr(slf, <Self as $crate::pymod::state::Params>::PARAMS)
+            }
+        }
+
+        impl $crate::pymod::state::Params for $name {
+            const PARAMS: &'static [&'static str] = &[$($param),*];
+        }
+    };
+}
+
+pub(crate) use pymethods_with_params;
+
+/// read the parameters of a Python object into a dict
+pub(crate) fn to_dict<'py>(obj: &'py PyAny, params: &[&str]) -> PyResult<&'py PyDict> {
+    let dict = PyDict::new(obj.py());
+    for name in params {
+        dict.set_item(name, obj.getattr(*name)?)?;
+    }
+    Ok(dict)
+}
+
+/// set the parameters of a Python object from a dict
+///
+/// Raises a TypeError for unknown parameters, just like unexpected keyword arguments do.
+pub(crate) fn set_from_dict(obj: &PyAny, params: &[&str], dict: &PyDict) -> PyResult<()> {
+    for (key, value) in dict.iter() {
+        let name: &str = key.extract()?;
+        if !params.contains(&name) {
+            return Err(PyTypeError::new_err(format!(
+                "{} got an unexpected parameter '{}'",
+                obj.get_type().name()?,
+                name
+            )));
+        }
+        obj.setattr(name, value)?;
+    }
+    Ok(())
+}
+
+/// create a new instance of a Python class with default values and set its parameters from a dict
+pub(crate) fn from_dict<'py>(
+    cls: &'py PyType,
+    params: &[&str],
+    dict: &PyDict,
+) -> PyResult<&'py PyAny> {
+    let obj = cls.call0()?;
+    set_from_dict(obj, params, dict)?;
+    Ok(obj)
+}
+
+/// describe a Python object with its class name and parameters
+pub(crate) fn repr(obj: &PyAny, params: &[&str]) -> PyResult<String> {
+    let mut args = Vec::with_capacity(params.len());
+    for name in params {
+        args.push(format!("{}={}", name, obj.getattr(*name)?.repr()?));
+    }
+    Ok(format!("{}({})", obj.get_type().name()?, args.join(", ")))
+}
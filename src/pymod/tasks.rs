@@ -0,0 +1,25 @@
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyTuple};
+
+/// run a method of a Python object in the default executor of the running event loop
+///
+/// Returns an asyncio future with the result of the method.
+/// Methods of muslib classes release the GIL while computing,
+/// so the event loop keeps running while the work is done on a background thread.
+/// Raises a RuntimeError when called outside of a running event loop.
+pub(crate) fn run_in_executor<'py>(
+    obj: &'py PyAny,
+    method: &str,
+    args: &PyTuple,
+    kwargs: Option<&PyDict>,
+) -> PyResult<&'py PyAny> {
+    let py = obj.py();
+    let mut partial_args = vec![obj.getattr(method)?];
+    partial_args.extend(args.iter());
+    let call = py
+        .import("functools")?
+        .getattr("partial")?
+        .call(PyTuple::new(py, partial_args), kwargs)?;
+    let event_loop = py.import("asyncio")?.call_method0("get_running_loop")?;
+    event_loop.call_method1("run_in_executor", (py.None(), call))
+}
@@ -0,0 +1,205 @@
+use std::io::{Read, Seek, SeekFrom};
+
+use symphonia::core::audio::Channels;
+use symphonia::core::codecs::{
+    CodecParameters, CodecType, CODEC_TYPE_PCM_F32BE, CODEC_TYPE_PCM_F32LE, CODEC_TYPE_PCM_F64BE,
+    CODEC_TYPE_PCM_F64LE, CODEC_TYPE_PCM_S16BE, CODEC_TYPE_PCM_S16LE, CODEC_TYPE_PCM_S24BE,
+    CODEC_TYPE_PCM_S24LE, CODEC_TYPE_PCM_S32BE, CODEC_TYPE_PCM_S32LE, CODEC_TYPE_PCM_S8,
+    CODEC_TYPE_PCM_U8,
+};
+use symphonia::core::errors::{
+    end_of_stream_error, seek_error, unsupported_error, Result as SymphoniaResult, SeekErrorKind,
+};
+use symphonia::core::formats::{Cue, FormatOptions, FormatReader, Packet, SeekMode, SeekTo};
+use symphonia::core::formats::{SeekedTo, Track};
+use symphonia::core::io::{MediaSource, MediaSourceStream};
+use symphonia::core::meta::{Metadata, MetadataLog};
+use symphonia::core::units::TimeBase;
+
+use crate::{Error, Result};
+
+/// number of frames read into each packet
+const PACKET_FRAMES: u64 = 4096;
+
+/// encoding of the samples of headerless pcm, interleaved frame by frame
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RawFormat {
+    /// unsigned 8-bit integers
+    U8,
+    /// signed 8-bit integers
+    S8,
+    /// signed 16-bit integers, little-endian
+    S16Le,
+    /// signed 16-bit integers, big-endian
+    S16Be,
+    /// signed 24-bit integers in 3 bytes, little-endian
+    S24Le,
+    /// signed 24-bit integers in 3 bytes, big-endian
+    S24Be,
+    /// signed 32-bit integers, little-endian
+    S32Le,
+    /// signed 32-bit integers, big-endian
+    S32Be,
+    /// 32-bit floating point values in range [-1, 1], little-endian
+    F32Le,
+    /// 32-bit floating point values in range [-1, 1], big-endian
+    F32Be,
+    /// 64-bit floating point values in range [-1, 1], little-endian
+    F64Le,
+    /// 64-bit floating point values in range [-1, 1], big-endian
+    F64Be,
+}
+
+impl RawFormat {
+    /// pcm codec decoding this format
+    fn codec(self) -> CodecType {
+        match self {
+            RawFormat::U8 => CODEC_TYPE_PCM_U8,
+            RawFormat::S8 => CODEC_TYPE_PCM_S8,
+            RawFormat::S16Le => CODEC_TYPE_PCM_S16LE,
+            RawFormat::S16Be => CODEC_TYPE_PCM_S16BE,
+            RawFormat::S24Le => CODEC_TYPE_PCM_S24LE,
+            RawFormat::S24Be => CODEC_TYPE_PCM_S24BE,
+            RawFormat::S32Le => CODEC_TYPE_PCM_S32LE,
+            RawFormat::S32Be => CODEC_TYPE_PCM_S32BE,
+            RawFormat::F32Le => CODEC_TYPE_PCM_F32LE,
+            RawFormat::F32Be => CODEC_TYPE_PCM_F32BE,
+            RawFormat::F64Le => CODEC_TYPE_PCM_F64LE,
+            RawFormat::F64Be => CODEC_TYPE_PCM_F64BE,
+        }
+    }
+
+    /// number of bytes of a sample
+    pub fn bytes(self) -> usize {
+        match self {
+            RawFormat::U8 | RawFormat::S8 => 1,
+            RawFormat::S16Le | RawFormat::S16Be => 2,
+            RawFormat::S24Le | RawFormat::S24Be => 3,
+            RawFormat::S32Le | RawFormat::S32Be | RawFormat::F32Le | RawFormat::F32Be => 4,
+            RawFormat::F64Le | RawFormat::F64Be => 8,
+        }
+    }
+}
+
+/// RawReader splits headerless pcm into packets for the pcm decoder of Symphonia.
+///
+/// Without a header, the sample rate, channels and encoding are given by the user.
+/// The length of the track is known if the length of the source is,
+/// trailing bytes of an incomplete frame are dropped.
+pub(crate) struct RawReader {
+    reader: MediaSourceStream,
+    tracks: Vec<Track>,
+    cues: Vec<Cue>,
+    metadata: MetadataLog,
+    frame_bytes: u64,
+    n_frames: Option<u64>,
+    ts: u64,
+}
+
+impl RawReader {
+    /// read a source of pcm of an encoding, with up to 26 interleaved channels
+    ///
+    /// Fails with Error::InvalidParam for a sample rate or number of channels out of range.
+    pub(crate) fn new(
+        reader: MediaSourceStream,
+        sample_rate: u32,
+        channels: usize,
+        format: RawFormat,
+    ) -> Result<Self> {
+        if sample_rate == 0 {
+            return Err(Error::InvalidParam(
+                "the sample rate of raw pcm must be positive".into(),
+            ));
+        }
+        // channel positions are consecutive bits, taken in order
+        let max = Channels::all().bits().count_ones() as usize;
+        if !(1..=max).contains(&channels) {
+            return Err(Error::InvalidParam(format!(
+                "raw pcm needs 1 to {} channels, not {}",
+                max, channels
+            )));
+        }
+        let layout = Channels::from_bits_truncate((1 << channels) - 1);
+
+        let frame_bytes = (format.bytes() * channels) as u64;
+        let n_frames = reader.byte_len().map(|len| len / frame_bytes);
+        let mut params = CodecParameters::new();
+        params
+            .for_codec(format.codec())
+            .with_sample_rate(sample_rate)
+            .with_time_base(TimeBase::new(1, sample_rate))
+            .with_channels(layout)
+            .with_bits_per_sample(8 * format.bytes() as u32)
+            .with_max_frames_per_packet(PACKET_FRAMES);
+        if let Some(n) = n_frames {
+            params.with_n_frames(n);
+        }
+        Ok(RawReader {
+            reader,
+            tracks: vec![Track::new(0, params)],
+            cues: Vec::new(),
+            metadata: MetadataLog::default(),
+            frame_bytes,
+            n_frames,
+            ts: 0,
+        })
+    }
+}
+
+impl FormatReader for RawReader {
+    fn try_new(_source: MediaSourceStream, _options: &FormatOptions) -> SymphoniaResult<Self> {
+        unsupported_error("raw: the sample rate, channels and encoding are required")
+    }
+
+    fn cues(&self) -> &[Cue] {
+        &self.cues
+    }
+
+    fn metadata(&mut self) -> Metadata<'_> {
+        self.metadata.metadata()
+    }
+
+    fn seek(&mut self, _mode: SeekMode, to: SeekTo) -> SymphoniaResult<SeekedTo> {
+        let rate = self.tracks[0].codec_params.sample_rate.unwrap_or(1);
+        let ts = match to {
+            SeekTo::TimeStamp { ts, .. } => ts,
+            SeekTo::Time { time, .. } => TimeBase::new(1, rate).calc_timestamp(time),
+        };
+        if self.n_frames.is_some_and(|n| ts > n) {
+            return seek_error(SeekErrorKind::OutOfRange);
+        }
+        if !self.reader.is_seekable() {
+            return seek_error(SeekErrorKind::Unseekable);
+        }
+        self.reader.seek(SeekFrom::Start(ts * self.frame_bytes))?;
+        self.ts = ts;
+        Ok(SeekedTo {
+            track_id: 0,
+            required_ts: ts,
+            actual_ts: ts,
+        })
+    }
+
+    fn tracks(&self) -> &[Track] {
+        &self.tracks
+    }
+
+    fn next_packet(&mut self) -> SymphoniaResult<Packet> {
+        let mut buf = Vec::with_capacity((PACKET_FRAMES * self.frame_bytes) as usize);
+        (&mut self.reader)
+            .take(PACKET_FRAMES * self.frame_bytes)
+            .read_to_end(&mut buf)?;
+        let frames = buf.len() as u64 / self.frame_bytes;
+        if frames == 0 {
+            return end_of_stream_error();
+        }
+        buf.truncate((frames * self.frame_bytes) as usize);
+        let packet = Packet::new_from_boxed_slice(0, self.ts, frames, buf.into_boxed_slice());
+        self.ts += frames;
+        Ok(packet)
+    }
+
+    fn into_inner(self: Box<Self>) -> MediaSourceStream {
+        self.reader
+    }
+}
@@ -0,0 +1,199 @@
+use std::collections::BTreeMap;
+
+use crate::algs::{
+    denoise, dynamics, filters, hpcp, io, problems, resample, restore, stereo, stft, stretch,
+    synth, Algorithm,
+};
+use crate::json::{FromJson, Json, ToJson};
+use crate::{Error, Result};
+
+/// Algorithm with its Inputs, Outputs and parameters passed as JSON, created by a Registry
+pub trait DynAlgorithm {
+    /// compute the Algorithm for Inputs read from JSON and return its Outputs as JSON
+    ///
+    /// Fails with Error::InvalidParam if the input doesn't match the Inputs of the Algorithm.
+    fn compute_json(&mut self, input: &Json) -> Result<Json>;
+}
+
+impl<A> DynAlgorithm for A
+where
+    A: Algorithm,
+    A::Input: FromJson,
+    A::Output: ToJson,
+{
+    fn compute_json(&mut self, input: &Json) -> Result<Json> {
+        let input = A::Input::from_json(input)?;
+        Ok(self.compute(input)?.to_json())
+    }
+}
+
+/// function creating an Algorithm from its parameters, stored in a Registry
+type Constructor = fn(&Json) -> Result<Box<dyn DynAlgorithm>>;
+
+fn construct<A>(params: &Json) -> Result<Box<dyn DynAlgorithm>>
+where
+    A: Algorithm + 'static,
+    A::Input: FromJson,
+    A::Output: ToJson,
+    A::Params: FromJson,
+{
+    Ok(Box::new(A::with_params(A::Params::from_json(params)?)))
+}
+
+/// Registry of Algorithms that can be created by name, inspired by the Essentia AlgorithmFactory
+///
+/// The default Registry holds every Algorithm of this crate under the name of its type,
+/// more can be added with .register().
+/// Pipelines are described in config files as a list of steps like
+/// `{"algorithm": "MovingAverage", "params": {"window_size": 5}}`,
+/// where the Outputs of each step are the Inputs of the next one.
+pub struct Registry {
+    constructors: BTreeMap<String, Constructor>,
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        let mut registry = Registry::new();
+        registry.register::<denoise::Denoiser>("Denoiser");
+        registry.register::<dynamics::Compressor>("Compressor");
+        registry.register::<dynamics::Gate>("Gate");
+        registry.register::<filters::MedianFilter>("MedianFilter");
+        registry.register::<filters::MovingAverage>("MovingAverage");
+        registry.register::<hpcp::HPCP>("HPCP");
+        registry.register::<io::MonoFloatLoader>("MonoFloatLoader");
+        registry.register::<io::MonoLoader>("MonoLoader");
+        registry.register::<io::MonoWriter>("MonoWriter");
+        registry.register::<problems::ClickDetector>("ClickDetector");
+        registry.register::<problems::GapDetector>("GapDetector");
+        registry.register::<problems::HumDetector>("HumDetector");
+        registry.register::<problems::SaturationDetector>("SaturationDetector");
+        registry.register::<problems::StartStopSilence>("StartStopSilence");
+        registry.register::<problems::TruePeakDetector>("TruePeakDetector");
+        registry.register::<resample::Resample>("Resample");
+        registry.register::<restore::Declicker>("Declicker");
+        registry.register::<restore::Declipper>("Declipper");
+        registry.register::<stereo::MidSide>("MidSide");
+        registry.register::<stereo::StereoWidener>("StereoWidener");
+        registry.register::<stft::FFT>("FFT");
+        registry.register::<stft::IFFT>("IFFT");
+        registry.register::<stretch::WSOLA>("WSOLA");
+        registry.register::<synth::Synthesizer>("Synthesizer");
+        registry
+    }
+}
+
+impl Registry {
+    /// create an empty Registry, use Registry::default() for one with all Algorithms of this crate
+    pub fn new() -> Self {
+        Registry {
+            constructors: BTreeMap::new(),
+        }
+    }
+
+    /// make an Algorithm available under a name, replacing the one registered before
+    pub fn register<A>(&mut self, name: &str)
+    where
+        A: Algorithm + 'static,
+        A::Input: FromJson,
+        A::Output: ToJson,
+        A::Params: FromJson,
+    {
+        self.constructors.insert(name.into(), construct::<A>);
+    }
+
+    /// names of all registered Algorithms in alphabetical order
+    pub fn names(&self) -> Vec<&str> {
+        self.constructors.keys().map(|x| &x[..]).collect()
+    }
+
+    /// create an Algorithm by name, with parameters missing from the object left at their defaults
+    ///
+    /// Fails with Error::InvalidParam if the name is unknown or the parameters don't match.
+    pub fn create(&self, name: &str, params: &Json) -> Result<Box<dyn DynAlgorithm>> {
+        let constructor = self
+            .constructors
+            .get(name)
+            .ok_or_else(|| Error::InvalidParam(format!("unknown algorithm {}", name)))?;
+        constructor(params)
+    }
+
+    /// create a Pipeline from a list of steps, e.g. read from a config file with Json::read()
+    ///
+    /// In a TOML config, read with Json::read_toml() and the serde feature,
+    /// the steps are an array of tables under a key, like `[[steps]]`.
+    pub fn pipeline(&self, steps: &Json) -> Result<Pipeline> {
+        let steps = match steps {
+            Json::Array(steps) => steps,
+            _ => {
+                return Err(Error::InvalidParam(
+                    "a pipeline must be a list of steps".into(),
+                ))
+            }
+        };
+        let mut algorithms = Vec::with_capacity(steps.len());
+        for step in steps {
+            let fields = step.fields()?;
+            let name = fields
+                .get("algorithm")
+                .ok_or_else(|| Error::InvalidParam("a step is missing its algorithm".into()))?;
+            let params = fields.get("params").cloned().unwrap_or(Json::object([]));
+            algorithms.push(self.create(&String::from_json(name)?, &params)?);
+        }
+        Ok(Pipeline { algorithms })
+    }
+}
+
+/// Algorithms created by a Registry and computed one after another
+pub struct Pipeline {
+    algorithms: Vec<Box<dyn DynAlgorithm>>,
+}
+
+impl DynAlgorithm for Pipeline {
+    fn compute_json(&mut self, input: &Json) -> Result<Json> {
+        let mut data = input.clone();
+        for algorithm in self.algorithms.iter_mut() {
+            data = algorithm.compute_json(&data)?;
+        }
+        Ok(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DynAlgorithm, Registry};
+    use crate::algs::filters::MovingAverage;
+    use crate::algs::Algorithm;
+    use crate::json::{Json, ToJson};
+    use crate::Error;
+
+    #[test]
+    fn registry() {
+        let registry = Registry::default();
+        assert!(registry.names().contains(&"HPCP"));
+
+        let signal = vec![0.0, 3.0, 0.0, 3.0, 0.0, 3.0];
+        let mut expected = MovingAverage::new();
+        let expected = expected.compute(signal.clone()).unwrap();
+        let mut average = registry.create("MovingAverage", &Json::object([])).unwrap();
+        assert_eq!(
+            average.compute_json(&signal.to_json()).unwrap(),
+            expected.to_json()
+        );
+
+        let config = r#"[
+            {"algorithm": "MovingAverage", "params": {"window_size": 3}},
+            {"algorithm": "MedianFilter"}
+        ]"#;
+        let mut pipeline = registry.pipeline(&Json::parse(config).unwrap()).unwrap();
+        let output = pipeline.compute_json(&signal.to_json()).unwrap();
+        assert!(matches!(output, Json::Array(x) if x.len() == signal.len()));
+
+        let result = registry.create("Essentia", &Json::object([]));
+        assert!(matches!(result, Err(Error::InvalidParam(_))));
+        let params = Json::parse(r#"{"window": 3}"#).unwrap();
+        let result = registry.create("MovingAverage", &params);
+        assert!(matches!(result, Err(Error::InvalidParam(_))));
+        let result = average.compute_json(&Json::String("signal".into()));
+        assert!(matches!(result, Err(Error::InvalidParam(_))));
+    }
+}
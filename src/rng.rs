@@ -0,0 +1,122 @@
+/// Seeded pseudo-random number generator shared by all randomized components
+///
+/// It is xoshiro256** seeded with SplitMix64, using only integer arithmetic
+/// and exact conversions to floats, so the same seed gives bit-identical output on every platform.
+/// Components taking an Rng, like noise oscillators or dithering, are reproducible across runs;
+/// use Rng::default() when any fixed seed will do.
+/// It is not suitable for cryptography.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rng {
+    state: [u64; 4],
+}
+
+impl Default for Rng {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_SEED)
+    }
+}
+
+impl Rng {
+    /// seed used by Rng::default()
+    pub const DEFAULT_SEED: u64 = 0x6d75_736c_6962;
+
+    /// create a new generator from a seed, every seed including 0 is valid
+    pub fn new(seed: u64) -> Self {
+        let mut x = seed;
+        let mut state = [0; 4];
+        for s in state.iter_mut() {
+            *s = splitmix64(&mut x);
+        }
+        Rng { state }
+    }
+
+    /// create an independent generator seeded from this one, e.g. one for each voice
+    pub fn fork(&mut self) -> Self {
+        Self::new(self.next_u64())
+    }
+
+    /// next 64 random bits
+    pub fn next_u64(&mut self) -> u64 {
+        let s = &mut self.state;
+        let result = s[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = s[1] << 17;
+        s[2] ^= s[0];
+        s[3] ^= s[1];
+        s[1] ^= s[2];
+        s[0] ^= s[3];
+        s[2] ^= t;
+        s[3] = s[3].rotate_left(45);
+        result
+    }
+
+    /// uniformly distributed number in [0, 1)
+    pub fn next_f64(&mut self) -> f64 {
+        // 53 bits fit the mantissa, so the conversion is exact
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// uniformly distributed number in [low, high)
+    pub fn uniform(&mut self, low: f64, high: f64) -> f64 {
+        low + (high - low) * self.next_f64()
+    }
+
+    /// uniformly distributed sample in [-1, 1), e.g. for white noise
+    pub fn bipolar(&mut self) -> f64 {
+        2.0 * self.next_f64() - 1.0
+    }
+
+    /// sample in (-1, 1) with a triangular distribution, used for TPDF dithering
+    pub fn triangular(&mut self) -> f64 {
+        self.next_f64() - self.next_f64()
+    }
+
+    /// uniformly distributed integer in [0, n), n must be positive
+    pub fn below(&mut self, n: usize) -> usize {
+        // the bias is at most n / 2^64 which is negligible here
+        ((self.next_u64() as u128 * n as u128) >> 64) as usize
+    }
+}
+
+/// advance the seed and return the next SplitMix64 output
+fn splitmix64(x: &mut u64) -> u64 {
+    *x = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *x;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Rng;
+
+    #[test]
+    fn reproducible() {
+        // fixed outputs guard against changes breaking reproducibility of renders
+        let mut rng = Rng::new(0);
+        let bits: Vec<u64> = (0..3).map(|_| rng.next_u64()).collect();
+        assert_eq!(
+            bits,
+            vec![
+                11091344671253066420,
+                13793997310169335082,
+                1900383378846508768
+            ]
+        );
+
+        assert_eq!(Rng::new(7), Rng::new(7));
+        assert_ne!(Rng::new(7).next_u64(), Rng::new(8).next_u64());
+        let mut a = Rng::default();
+        let mut b = a.clone();
+        assert_ne!(a.fork().next_u64(), b.next_u64());
+
+        let mut rng = Rng::default();
+        for _ in 0..1000 {
+            let x = rng.bipolar();
+            assert!((-1.0..1.0).contains(&x));
+            let x = rng.triangular();
+            assert!(x > -1.0 && x < 1.0);
+            assert!(rng.below(3) < 3);
+        }
+    }
+}
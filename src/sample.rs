@@ -0,0 +1,100 @@
+use alloc::vec::Vec;
+
+#[cfg(not(feature = "std"))]
+use crate::math::Float;
+
+/// Audio sample that can be passed between the mixer and algorithms
+///
+/// Every sample type converts from and to a floating point value in range [-1, 1],
+/// so data only has to be converted once at the boundary where the type changes.
+/// Unsigned 16-bit samples are offset binary pcm values with silence at the middle of the range,
+/// signed 16-bit samples are plain pcm values.
+/// Both 16-bit types are scaled by 32768, so converting between them is exact
+/// and silence is rendered as 0x8000 in the unsigned format.
+pub trait Sample: Copy + Default + Send + Sync + 'static {
+    /// convert from a floating point value in range [-1, 1], values outside are clipped
+    fn from_f64(x: f64) -> Self;
+    /// convert to a floating point value in range [-1, 1]
+    fn to_f64(self) -> f64;
+
+    /// convert from a sample of another type
+    fn from_sample<S: Sample>(x: S) -> Self {
+        Self::from_f64(x.to_f64())
+    }
+}
+
+impl Sample for f64 {
+    fn from_f64(x: f64) -> Self {
+        x
+    }
+
+    fn to_f64(self) -> f64 {
+        self
+    }
+}
+
+impl Sample for f32 {
+    fn from_f64(x: f64) -> Self {
+        x as f32
+    }
+
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+}
+
+impl Sample for i16 {
+    fn from_f64(x: f64) -> Self {
+        (x * 32768.0)
+            .round()
+            .clamp(i16::MIN as f64, i16::MAX as f64) as i16
+    }
+
+    fn to_f64(self) -> f64 {
+        self as f64 / 32768.0
+    }
+}
+
+impl Sample for u16 {
+    fn from_f64(x: f64) -> Self {
+        i16::from_f64(x) as u16 ^ 0x8000
+    }
+
+    fn to_f64(self) -> f64 {
+        // flipping the sign bit turns offset binary into two's complement
+        (self ^ 0x8000) as i16 as f64 / 32768.0
+    }
+}
+
+/// convert a block of samples to another sample type
+pub fn convert<S: Sample, T: Sample>(data: &[S]) -> Vec<T> {
+    data.iter().map(|x| T::from_sample(*x)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{convert, Sample};
+
+    #[test]
+    fn conversions() {
+        assert_eq!(u16::from_f64(0.0), 0x8000);
+        assert_eq!(u16::from_f64(1.0), u16::MAX);
+        assert_eq!(u16::from_f64(-2.0), 0);
+        assert_eq!(u16::from_sample(0x8000u16), 0x8000);
+        assert_eq!(i16::from_f64(-1.0), i16::MIN);
+        assert_eq!(i16::from_f64(1.0), i16::MAX);
+
+        // offset binary pcm values are written as two's complement without loss
+        let pcm: Vec<u16> = vec![0, 1, 0x7fff, 0x8000, u16::MAX];
+        let signed: Vec<i16> = convert(&pcm);
+        assert_eq!(signed, vec![i16::MIN, i16::MIN + 1, -1, 0, i16::MAX]);
+
+        let float: Vec<f32> = convert(&signed);
+        assert_eq!(float[3], 0.0);
+        assert_eq!(convert::<f32, i16>(&float), signed);
+
+        // and back to offset binary, every value survives the trip through a float
+        let all: Vec<u16> = (0..=u16::MAX).collect();
+        assert_eq!(convert::<f64, u16>(&convert::<u16, f64>(&all)), all);
+    }
+}
@@ -0,0 +1,463 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::algs::synth::Synthesizer;
+use crate::algs::Algorithm;
+use crate::midi::Note;
+use crate::mixer::Writer;
+use crate::notes::{Tuning, LETTERS};
+use crate::{Error, Result};
+
+/// letters of notes in the order in which sharps are added to key signatures
+const SHARPS: [char; 7] = ['F', 'C', 'G', 'D', 'A', 'E', 'B'];
+
+/// modes of keys with the sharps, or flats when negative, they add to the major key signature
+const MODES: [(&str, i32); 9] = [
+    ("maj", 0),
+    ("ion", 0),
+    ("mix", -1),
+    ("dor", -2),
+    ("min", -3),
+    ("aeo", -3),
+    ("phr", -4),
+    ("loc", -5),
+    ("lyd", 1),
+];
+
+/// Melody written in ABC notation, see <https://abcnotation.com/wiki/abc:standard:v2.1>
+///
+/// A single voice is read with notes and rests of any length, accidentals, key signatures
+/// in any mode, ties, broken rhythms and tuplets, with the unit note length, tempo, meter and key
+/// changed by header fields or inline fields.
+/// Chords can't be played by the Synthesizer and fail to parse, while decorations,
+/// chord symbols, annotations, grace notes, slurs and bar lines are skipped.
+/// Repeats are played only once, and only the first tune of a file is read.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Score {
+    /// title of the tune from the first T: field
+    pub title: Option<String>,
+    /// MIDI note numbers of consecutive notes, None for rests
+    pub pitches: Vec<Option<u8>>,
+    /// durations of consecutive notes in seconds
+    pub durations: Vec<f64>,
+}
+
+impl Score {
+    /// read a melody from a file in ABC notation
+    pub fn read(path: &Path) -> Result<Score> {
+        Score::parse(&std::fs::read_to_string(path)?)
+    }
+
+    /// read a melody from text in ABC notation
+    ///
+    /// Fails with Error::InvalidParam on fields or symbols that can't be read.
+    pub fn parse(text: &str) -> Result<Score> {
+        let mut parser = Parser::default();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            let mut chars = line.chars();
+            match (chars.next(), chars.next()) {
+                (Some('X'), Some(':')) if !parser.score.durations.is_empty() => break,
+                (Some(name), Some(':')) if name.is_ascii_alphabetic() => {
+                    parser.field(name, &line[2..])?
+                }
+                _ => parser.body(line).map_err(|e| match e {
+                    Error::InvalidParam(msg) => {
+                        Error::InvalidParam(format!("line {} of the score: {}", number + 1, msg))
+                    }
+                    e => e,
+                })?,
+            }
+        }
+        Ok(parser.score)
+    }
+
+    /// frequencies of consecutive notes in Hz, 0 for rests, as inputs of the Synthesizer
+    pub fn frequencies(&self, tuning: &Tuning) -> Vec<f64> {
+        self.pitches
+            .iter()
+            .map(|p| p.map_or(0.0, |p| tuning.frequency(p as f64)))
+            .collect()
+    }
+
+    /// notes of the melody with their start times, e.g. for the MidiWriter
+    pub fn notes(&self) -> Vec<Note> {
+        let mut notes = Vec::new();
+        let mut start = 0.0;
+        for (pitch, duration) in self.pitches.iter().zip(self.durations.iter()) {
+            if let Some(pitch) = pitch {
+                notes.push(Note::new(*pitch, start, *duration));
+            }
+            start += duration;
+        }
+        notes
+    }
+
+    /// synthesize the melody with a Synthesizer, returning its signed 16-bit pcm values
+    pub fn render(&self, synthesizer: &mut Synthesizer) -> Result<Vec<i16>> {
+        let frequencies = self.frequencies(&Tuning::default());
+        synthesizer.compute((frequencies, self.durations.clone()))
+    }
+
+    /// synthesize the melody with a Synthesizer and write it to a WAV file
+    pub fn write(&self, path: &Path, synthesizer: &mut Synthesizer) -> Result<()> {
+        let pcm = self.render(synthesizer)?;
+        Writer::new()
+            .file(path.into())
+            .sample_rate(synthesizer.sample_rate as u32)
+            .write(&pcm)
+    }
+}
+
+/// state of reading a tune, carried between lines
+struct Parser {
+    /// unit note length as a fraction of a whole note, and if it was set by an L: field
+    unit: (f64, bool),
+    /// length of a bar as a fraction of a whole note
+    meter: f64,
+    /// note length of a beat as a fraction of a whole note, and beats per minute
+    tempo: (f64, f64),
+    /// accidentals of letters from C to B in the key signature
+    key: [i32; 7],
+    /// accidentals written in the current bar, by letter and octave
+    bar: HashMap<(usize, i32), i32>,
+    /// the next note continues the last one if it has the same pitch
+    tie: bool,
+    /// multiplier of the length of the next note in a broken rhythm
+    broken: f64,
+    /// number of notes left in a tuplet and the multiplier of their lengths
+    tuplet: (usize, f64),
+    score: Score,
+}
+
+impl Default for Parser {
+    fn default() -> Self {
+        Parser {
+            unit: (0.125, false),
+            meter: 1.0,
+            tempo: (0.25, 120.0),
+            key: [0; 7],
+            bar: HashMap::new(),
+            tie: false,
+            broken: 1.0,
+            tuplet: (0, 1.0),
+            score: Score::default(),
+        }
+    }
+}
+
+impl Parser {
+    /// apply a header or inline field, others than T, L, M, Q and K are ignored
+    fn field(&mut self, name: char, value: &str) -> Result<()> {
+        let value = value.trim();
+        let invalid = || Error::InvalidParam(format!("invalid field {}:{}", name, value));
+        match name {
+            'T' if self.score.title.is_none() => self.score.title = Some(value.into()),
+            'L' => self.unit = (fraction(value).ok_or_else(invalid)?, true),
+            'M' => {
+                self.meter = match value {
+                    "C" | "C|" => 1.0,
+                    "none" | "" => self.meter,
+                    _ => fraction(value).ok_or_else(invalid)?,
+                };
+                // the default unit length depends on the meter
+                if !self.unit.1 {
+                    self.unit.0 = if self.meter < 0.75 { 0.0625 } else { 0.125 };
+                }
+            }
+            'Q' => {
+                // skip text in quotes, e.g. Q:"Allegro" 1/4=120
+                let text: String = value.split('"').step_by(2).collect();
+                let text = text.trim();
+                self.tempo = match text.split_once('=') {
+                    Some((beat, bpm)) => {
+                        let beat = beat.split_whitespace().next().unwrap_or_default();
+                        let beat = fraction(beat).ok_or_else(invalid)?;
+                        (beat, bpm.trim().parse().map_err(|_| invalid())?)
+                    }
+                    None if text.is_empty() => self.tempo,
+                    None => (self.unit.0, text.parse().map_err(|_| invalid())?),
+                };
+            }
+            'K' => self.key = signature(value).ok_or_else(invalid)?,
+            _ => (),
+        }
+        Ok(())
+    }
+
+    /// read a line of the tune body
+    fn body(&mut self, line: &str) -> Result<()> {
+        let chars: Vec<char> = line.chars().collect();
+        let unexpected = |c: char| Error::InvalidParam(format!("unexpected {:?}", c));
+        let mut i = 0;
+        while i < chars.len() {
+            let c = chars[i];
+            i += 1;
+            match c {
+                '%' => break,
+                // chord symbols, annotations, decorations and grace notes
+                '"' | '!' | '+' | '{' => {
+                    let end = if c == '{' { '}' } else { c };
+                    while i < chars.len() && chars[i] != end {
+                        i += 1;
+                    }
+                    i += 1;
+                }
+                '[' if chars.get(i + 1) == Some(&':') => {
+                    let field: String = chars[i + 2..].iter().take_while(|c| **c != ']').collect();
+                    self.field(chars[i], &field)?;
+                    i += field.chars().count() + 3;
+                }
+                // bar lines, with numbers of repeat endings like |1 or [2
+                '|' | '[' if chars.get(i).is_some_and(|c| c.is_ascii_digit()) || c == '|' => {
+                    while chars
+                        .get(i)
+                        .is_some_and(|c| c.is_ascii_digit() || *c == ',')
+                    {
+                        i += 1;
+                    }
+                    self.bar.clear();
+                }
+                '[' => return Err(Error::InvalidParam("chords are not supported".into())),
+                '(' if chars.get(i).is_some_and(|c| c.is_ascii_digit()) => {
+                    let p = chars[i].to_digit(10).unwrap_or_default() as usize;
+                    let q = match p {
+                        2 | 4 | 8 => 3.0,
+                        _ => 2.0,
+                    };
+                    self.tuplet = (p, q / p as f64);
+                    i += 1;
+                }
+                '-' => self.tie = true,
+                '>' | '<' => {
+                    let mut n = 1;
+                    while chars.get(i) == Some(&c) {
+                        n += 1;
+                        i += 1;
+                    }
+                    let short = 0.5f64.powi(n);
+                    let (last, next) = match c {
+                        '>' => (2.0 - short, short),
+                        _ => (short, 2.0 - short),
+                    };
+                    if let Some(duration) = self.score.durations.last_mut() {
+                        *duration *= last;
+                    }
+                    self.broken = next;
+                }
+                'z' | 'x' => {
+                    let length = length(&chars, &mut i);
+                    self.push(None, length);
+                }
+                'Z' => {
+                    let bars = number(&chars, &mut i).unwrap_or(1.0);
+                    self.push(None, bars * self.meter / self.unit.0);
+                }
+                '^' | '_' | '=' | 'A'..='G' | 'a'..='g' => {
+                    let pitch = self.note(&chars, &mut i)?;
+                    let length = length(&chars, &mut i);
+                    self.push(Some(pitch), length);
+                }
+                ' ' | '\t' | ':' | ']' | ')' | '(' | '\\' | '`' | 'y' | '.' | '~' => (),
+                'H' | 'L' | 'M' | 'O' | 'P' | 'S' | 'T' | 'u' | 'v' => (),
+                _ => return Err(unexpected(c)),
+            }
+        }
+        Ok(())
+    }
+
+    /// MIDI note number of a note starting at chars[i - 1], with its accidentals and octave
+    fn note(&mut self, chars: &[char], i: &mut usize) -> Result<u8> {
+        let mut accidental = None;
+        let mut c = chars[*i - 1];
+        while matches!(c, '^' | '_' | '=') {
+            let offset = match c {
+                '^' => 1,
+                '_' => -1,
+                _ => 0,
+            };
+            accidental = Some(accidental.unwrap_or(0) + offset);
+            c = *chars
+                .get(*i)
+                .ok_or_else(|| Error::InvalidParam("accidental without a note".into()))?;
+            *i += 1;
+        }
+
+        let letter = LETTERS
+            .iter()
+            .position(|(l, _)| *l == c.to_ascii_uppercase())
+            .ok_or_else(|| Error::InvalidParam(format!("unexpected {:?}", c)))?;
+        let mut octave = if c.is_ascii_lowercase() { 5 } else { 4 };
+        while let Some(mark) = chars.get(*i).filter(|c| matches!(c, '\'' | ',')) {
+            octave += if *mark == '\'' { 1 } else { -1 };
+            *i += 1;
+        }
+
+        let accidental = match accidental {
+            Some(accidental) => {
+                self.bar.insert((letter, octave), accidental);
+                accidental
+            }
+            None => match self.bar.get(&(letter, octave)) {
+                Some(accidental) => *accidental,
+                None => self.key[letter],
+            },
+        };
+        let pitch = 12 * (octave + 1) + LETTERS[letter].1 + accidental;
+        u8::try_from(pitch)
+            .ok()
+            .filter(|x| *x < 128)
+            .ok_or_else(|| Error::InvalidParam("note out of the MIDI range".into()))
+    }
+
+    /// add a note or a rest with a length in unit note lengths
+    fn push(&mut self, pitch: Option<u8>, length: f64) {
+        let mut length = length * self.unit.0 * std::mem::replace(&mut self.broken, 1.0);
+        if self.tuplet.0 > 0 {
+            length *= self.tuplet.1;
+            self.tuplet.0 -= 1;
+        }
+        let (beat, bpm) = self.tempo;
+        let duration = length / beat * 60.0 / bpm;
+
+        let tied = std::mem::replace(&mut self.tie, false);
+        match self.score.durations.last_mut() {
+            Some(last) if tied && pitch.is_some() && self.score.pitches.last() == Some(&pitch) => {
+                *last += duration
+            }
+            _ => {
+                self.score.pitches.push(pitch);
+                self.score.durations.push(duration);
+            }
+        }
+    }
+}
+
+/// accidentals of letters from C to B in the signature of a key like "G", "F#m", "Bb major" or "Ddor"
+fn signature(key: &str) -> Option<[i32; 7]> {
+    let mut signature = [0; 7];
+    let key = key.split_whitespace().collect::<Vec<_>>().join(" ");
+    if key.is_empty() || key == "none" {
+        return Some(signature);
+    }
+
+    let mut chars = key.chars();
+    let letter = chars.next()?;
+    // position of the tonic on the circle of fifths, from C
+    let mut fifths = match letter {
+        'F' => -1,
+        'C' => 0,
+        'G' => 1,
+        'D' => 2,
+        'A' => 3,
+        'E' => 4,
+        'B' => 5,
+        _ => return None,
+    };
+    let rest = chars.as_str();
+    let rest = match rest.chars().next() {
+        Some('#') => {
+            fifths += 7;
+            &rest[1..]
+        }
+        Some('b') => {
+            fifths -= 7;
+            &rest[1..]
+        }
+        _ => rest,
+    };
+
+    // the mode is the first word, other words like clef=bass are ignored
+    let mode = rest.split_whitespace().next().unwrap_or_default();
+    let mode = mode.to_ascii_lowercase();
+    fifths += match mode.as_str() {
+        "" => 0,
+        "m" => -3,
+        _ if mode.contains('=') => 0,
+        _ => MODES.iter().find(|(m, _)| mode.starts_with(m))?.1,
+    };
+
+    if fifths.abs() > 7 {
+        return None;
+    }
+    let letters = SHARPS.iter().take(fifths.max(0) as usize);
+    let flats = SHARPS.iter().rev().take((-fifths).max(0) as usize);
+    for (l, offset) in letters.map(|l| (l, 1)).chain(flats.map(|l| (l, -1))) {
+        let letter = LETTERS.iter().position(|(x, _)| x == l)?;
+        signature[letter] = offset;
+    }
+    Some(signature)
+}
+
+/// a number or a fraction like "3" or "1/8"
+fn fraction(text: &str) -> Option<f64> {
+    match text.trim().split_once('/') {
+        Some((a, b)) => Some(a.trim().parse::<f64>().ok()? / b.trim().parse::<f64>().ok()?),
+        None => text.trim().parse().ok(),
+    }
+}
+
+/// an unsigned integer starting at chars[i], if there is one
+fn number(chars: &[char], i: &mut usize) -> Option<f64> {
+    let start = *i;
+    while chars.get(*i).is_some_and(|c| c.is_ascii_digit()) {
+        *i += 1;
+    }
+    chars[start..*i].iter().collect::<String>().parse().ok()
+}
+
+/// length of a note in unit note lengths written after it, like "3", "/", "/4", "3/2" or "//"
+fn length(chars: &[char], i: &mut usize) -> f64 {
+    let mut length = number(chars, i).unwrap_or(1.0);
+    while chars.get(*i) == Some(&'/') {
+        *i += 1;
+        length /= number(chars, i).unwrap_or(2.0);
+    }
+    length
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Score;
+    use crate::algs::synth::Synthesizer;
+
+    #[test]
+    fn abc() {
+        let text = "X:1\nT:Test\nM:4/4\nL:1/4\nQ:1/4=120\nK:G\n\
+                    GABc|d2 z2|F^F_B=B|[K:C] ^F F|F A>B (3ABc A2-A|]";
+        let score = Score::parse(text).unwrap();
+        assert_eq!(score.title.as_deref(), Some("Test"));
+
+        let pitches: Vec<u8> = score.pitches.iter().map(|p| p.unwrap_or(0)).collect();
+        let expected = [
+            67, 69, 71, 72, 74, 0, 66, 66, 70, 71, 66, 66, 65, 69, 71, 69, 71, 72, 69,
+        ];
+        assert_eq!(pitches, expected);
+
+        let durations = [0.5, 0.5, 0.5, 0.5, 1.0, 1.0, 0.5, 0.5, 0.5, 0.5, 0.5, 0.5];
+        let thirds = [1.0 / 3.0; 3];
+        let expected = [&durations[..], &[0.5, 0.75, 0.25], &thirds, &[1.5]].concat();
+        assert_eq!(score.durations.len(), expected.len());
+        for (d, e) in score.durations.iter().zip(expected.iter()) {
+            assert!((d - e).abs() < 1e-9, "{:?}", score.durations);
+        }
+        assert_eq!(score.notes().len(), expected.len() - 1);
+
+        let mut synthesizer = Synthesizer::builder().sample_rate(8000).build();
+        let pcm = score.render(&mut synthesizer).unwrap();
+        let total: f64 = expected.iter().sum();
+        assert!(
+            (pcm.len() as f64 - total * 8000.0).abs() < 20.0,
+            "{}",
+            pcm.len()
+        );
+
+        // default unit length of 1/8 and a minor key with flats
+        let score = Score::parse("K:Dm\nB e/2f/2").unwrap();
+        assert_eq!(score.pitches, [Some(70), Some(76), Some(77)]);
+        assert_eq!(score.durations, [0.25, 0.125, 0.125]);
+
+        assert!(Score::parse("K:C\n[CEG]").is_err());
+        assert!(Score::parse("K:H\nC").is_err());
+    }
+}
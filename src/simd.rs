@@ -0,0 +1,199 @@
+#[cfg(target_arch = "x86_64")]
+use std::arch::x86_64::*;
+
+/// multiply each sample in place by the corresponding sample of another block, e.g. a window
+///
+/// Only the common part of both blocks is processed.
+pub fn mul(target: &mut [f64], other: &[f64]) {
+    #[cfg(target_arch = "x86_64")]
+    if is_x86_feature_detected!("avx") {
+        // SAFETY: the CPU supports AVX
+        return unsafe { mul_avx(target, other) };
+    }
+    for (x, y) in target.iter_mut().zip(other.iter()) {
+        *x *= y;
+    }
+}
+
+/// add the corresponding sample of another block to each sample in place
+///
+/// Only the common part of both blocks is processed.
+pub fn add(target: &mut [f64], other: &[f64]) {
+    #[cfg(target_arch = "x86_64")]
+    if is_x86_feature_detected!("avx") {
+        // SAFETY: the CPU supports AVX
+        return unsafe { add_avx(target, other) };
+    }
+    for (x, y) in target.iter_mut().zip(other.iter()) {
+        *x += y;
+    }
+}
+
+/// divide each sample in place by a constant
+pub fn div(target: &mut [f64], d: f64) {
+    #[cfg(target_arch = "x86_64")]
+    if is_x86_feature_detected!("avx") {
+        // SAFETY: the CPU supports AVX
+        return unsafe { div_avx(target, d) };
+    }
+    for x in target.iter_mut() {
+        *x /= d;
+    }
+}
+
+/// set each sample to cos²(pi x) for x starting at first and growing by step with each sample
+///
+/// It's the window spreading spectral peaks over neighbouring bins, e.g. in the HPCP,
+/// evaluated with a polynomial accurate to 1e-16 for x from range <-0.5; 0.5> only.
+pub fn cos2(target: &mut [f64], first: f64, step: f64) {
+    #[cfg(target_arch = "x86_64")]
+    if is_x86_feature_detected!("avx") {
+        // SAFETY: the CPU supports AVX
+        return unsafe { cos2_avx(target, first, step) };
+    }
+    for (k, x) in target.iter_mut().enumerate() {
+        *x = cos2_scalar(first + k as f64 * step);
+    }
+}
+
+/// coefficients of the Taylor series of cos in powers of its squared argument, up to the 20th power
+const COS: [f64; 11] = [
+    1.0,
+    -1.0 / 2.0,
+    1.0 / 24.0,
+    -1.0 / 720.0,
+    1.0 / 40320.0,
+    -1.0 / 3628800.0,
+    1.0 / 479001600.0,
+    -1.0 / 87178291200.0,
+    1.0 / 20922789888000.0,
+    -1.0 / 6402373705728000.0,
+    1.0 / 2432902008176640000.0,
+];
+
+/// cos²(pi x) with the same operations as the vectorized loop
+fn cos2_scalar(x: f64) -> f64 {
+    let t = std::f64::consts::PI * x;
+    let z = t * t;
+    let mut c = COS[10];
+    for a in COS[..10].iter().rev() {
+        c = c * z + a;
+    }
+    c * c
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx")]
+unsafe fn cos2_avx(target: &mut [f64], first: f64, step: f64) {
+    let n = target.len();
+    let a = target.as_mut_ptr();
+    let pi = _mm256_set1_pd(std::f64::consts::PI);
+    let mut i = 0;
+    while i + 4 <= n {
+        let k = i as f64;
+        let x = _mm256_set_pd(
+            first + (k + 3.0) * step,
+            first + (k + 2.0) * step,
+            first + (k + 1.0) * step,
+            first + k * step,
+        );
+        let t = _mm256_mul_pd(pi, x);
+        let z = _mm256_mul_pd(t, t);
+        let mut c = _mm256_set1_pd(COS[10]);
+        for coefficient in COS[..10].iter().rev() {
+            c = _mm256_add_pd(_mm256_mul_pd(c, z), _mm256_set1_pd(*coefficient));
+        }
+        _mm256_storeu_pd(a.add(i), _mm256_mul_pd(c, c));
+        i += 4;
+    }
+    for (k, x) in target.iter_mut().enumerate().skip(i) {
+        *x = cos2_scalar(first + k as f64 * step);
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx")]
+unsafe fn mul_avx(target: &mut [f64], other: &[f64]) {
+    let n = std::cmp::min(target.len(), other.len());
+    let (a, b) = (target.as_mut_ptr(), other.as_ptr());
+    let mut i = 0;
+    while i + 4 <= n {
+        let x = _mm256_mul_pd(_mm256_loadu_pd(a.add(i)), _mm256_loadu_pd(b.add(i)));
+        _mm256_storeu_pd(a.add(i), x);
+        i += 4;
+    }
+    for (x, y) in target[i..n].iter_mut().zip(other[i..n].iter()) {
+        *x *= y;
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx")]
+unsafe fn add_avx(target: &mut [f64], other: &[f64]) {
+    let n = std::cmp::min(target.len(), other.len());
+    let (a, b) = (target.as_mut_ptr(), other.as_ptr());
+    let mut i = 0;
+    while i + 4 <= n {
+        let x = _mm256_add_pd(_mm256_loadu_pd(a.add(i)), _mm256_loadu_pd(b.add(i)));
+        _mm256_storeu_pd(a.add(i), x);
+        i += 4;
+    }
+    for (x, y) in target[i..n].iter_mut().zip(other[i..n].iter()) {
+        *x += y;
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx")]
+unsafe fn div_avx(target: &mut [f64], d: f64) {
+    let n = target.len();
+    let a = target.as_mut_ptr();
+    let d4 = _mm256_set1_pd(d);
+    let mut i = 0;
+    while i + 4 <= n {
+        _mm256_storeu_pd(a.add(i), _mm256_div_pd(_mm256_loadu_pd(a.add(i)), d4));
+        i += 4;
+    }
+    for x in target[i..].iter_mut() {
+        *x /= d;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{add, cos2, cos2_scalar, div, mul};
+
+    #[test]
+    fn same_as_scalar() {
+        // odd lengths exercise the scalar tail after full vectors
+        let a: Vec<f64> = (0..11).map(|x| x as f64 * 0.5 - 2.0).collect();
+        let b: Vec<f64> = (0..13).map(|x| (x as f64).sin()).collect();
+
+        let mut x = a.clone();
+        mul(&mut x, &b);
+        let expected: Vec<f64> = a.iter().zip(b.iter()).map(|(x, y)| x * y).collect();
+        assert_eq!(x, expected);
+
+        let mut x = a.clone();
+        add(&mut x, &b);
+        let expected: Vec<f64> = a.iter().zip(b.iter()).map(|(x, y)| x + y).collect();
+        assert_eq!(x, expected);
+
+        let mut x = a.clone();
+        div(&mut x, 3.0);
+        let expected: Vec<f64> = a.iter().map(|x| x / 3.0).collect();
+        assert_eq!(x, expected);
+    }
+
+    #[test]
+    fn window() {
+        // a window of 7 bins, with a scalar tail after a full vector
+        let mut x = vec![0.0; 7];
+        cos2(&mut x, -0.5, 1.0 / 6.0);
+        for (k, x) in x.iter().enumerate() {
+            let t = std::f64::consts::PI * (-0.5 + k as f64 / 6.0);
+            assert!((x - t.cos().powi(2)).abs() < 1e-15, "{} {}", k, x);
+            assert_eq!(*x, cos2_scalar(-0.5 + k as f64 * (1.0 / 6.0)));
+        }
+    }
+}
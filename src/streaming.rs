@@ -0,0 +1,264 @@
+use std::collections::VecDeque;
+use std::marker::PhantomData;
+
+use crate::algs::frames::FrameCutter;
+use crate::algs::Algorithm;
+use crate::sample::Sample;
+use crate::trace;
+use crate::Result;
+
+/// Stage of a streaming network, consuming tokens of one type and producing tokens of another
+///
+/// Nodes are connected with .then() and .fork() into a network that is itself a Node,
+/// the types of connected ports are checked at compile time.
+/// Tokens are pushed through the whole network one at a time,
+/// so memory use depends on the frame size rather than the length of the stream.
+pub trait Node {
+    /// token consumed by this Node
+    type Input;
+    /// token produced by this Node
+    type Output;
+
+    /// consume a token and append tokens produced so far to the output
+    fn push(&mut self, input: Self::Input, output: &mut Vec<Self::Output>) -> Result<()>;
+
+    /// append tokens still buffered after the end of the stream to the output
+    fn finish(&mut self, _output: &mut Vec<Self::Output>) -> Result<()> {
+        Ok(())
+    }
+
+    /// connect the output of this Node to the input of the next one
+    fn then<N: Node<Input = Self::Output>>(self, next: N) -> Chain<Self, N>
+    where
+        Self: Sized,
+    {
+        Chain {
+            first: self,
+            second: next,
+            buffer: Vec::new(),
+        }
+    }
+
+    /// feed the same input to this and another Node and pair up their outputs
+    fn fork<N: Node<Input = Self::Input>>(self, other: N) -> Fork<Self, N>
+    where
+        Self: Sized,
+        Self::Input: Clone,
+    {
+        Fork {
+            left: self,
+            right: other,
+            left_queue: VecDeque::new(),
+            right_queue: VecDeque::new(),
+            buffer: (Vec::new(), Vec::new()),
+        }
+    }
+
+    /// push every token from a source through this Node and pass the output to a sink
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "Node::run", level = "debug", skip_all)
+    )]
+    fn run<I, F>(&mut self, source: I, mut sink: F) -> Result<()>
+    where
+        I: IntoIterator<Item = Result<Self::Input>>,
+        F: FnMut(Self::Output) -> Result<()>,
+    {
+        let mut output = Vec::new();
+        for input in source {
+            self.push(input?, &mut output)?;
+            for x in output.drain(..) {
+                sink(x)?;
+            }
+        }
+        self.finish(&mut output)?;
+        output.into_iter().try_for_each(sink)
+    }
+}
+
+/// Node computing an Algorithm once for every token
+pub struct Compute<A>(pub A);
+
+impl<A: Algorithm> Node for Compute<A> {
+    type Input = A::Input;
+    type Output = A::Output;
+
+    fn push(&mut self, input: A::Input, output: &mut Vec<A::Output>) -> Result<()> {
+        let _span = trace::frame_span(std::any::type_name::<A>());
+        output.push(self.0.compute(input)?);
+        Ok(())
+    }
+}
+
+/// Node cutting blocks of samples of any length into overlapping frames
+///
+/// The last frame is padded with silence at the end of the stream.
+pub struct Frames<S = f64>(pub FrameCutter<S>);
+
+impl<S: Sample> Frames<S> {
+    /// create a new Frames node for a given frame and hop size
+    pub fn new(frame_size: usize, hop_size: usize) -> Self {
+        Frames(FrameCutter::new(frame_size, hop_size))
+    }
+}
+
+impl<S: Sample> Node for Frames<S> {
+    type Input = Vec<S>;
+    type Output = Vec<S>;
+
+    fn push(&mut self, input: Vec<S>, output: &mut Vec<Vec<S>>) -> Result<()> {
+        self.0.push(&input);
+        while let Some(frame) = self.0.pop() {
+            output.push(frame);
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self, output: &mut Vec<Vec<S>>) -> Result<()> {
+        while let Some(frame) = self.0.flush() {
+            output.push(frame);
+        }
+        Ok(())
+    }
+}
+
+/// Node applying a function to every token, e.g. to adapt outputs to inputs of the next Node
+pub struct Map<F, I, O> {
+    f: F,
+    types: PhantomData<fn(I) -> O>,
+}
+
+/// create a Node applying a function to every token
+pub fn map<F: FnMut(I) -> O, I, O>(f: F) -> Map<F, I, O> {
+    Map {
+        f,
+        types: PhantomData,
+    }
+}
+
+impl<F: FnMut(I) -> O, I, O> Node for Map<F, I, O> {
+    type Input = I;
+    type Output = O;
+
+    fn push(&mut self, input: I, output: &mut Vec<O>) -> Result<()> {
+        output.push((self.f)(input));
+        Ok(())
+    }
+}
+
+/// two Nodes connected one after another, see Node::then()
+pub struct Chain<A: Node, B> {
+    first: A,
+    second: B,
+    buffer: Vec<A::Output>,
+}
+
+impl<A: Node, B: Node<Input = A::Output>> Node for Chain<A, B> {
+    type Input = A::Input;
+    type Output = B::Output;
+
+    fn push(&mut self, input: A::Input, output: &mut Vec<B::Output>) -> Result<()> {
+        self.first.push(input, &mut self.buffer)?;
+        for x in self.buffer.drain(..) {
+            self.second.push(x, output)?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self, output: &mut Vec<B::Output>) -> Result<()> {
+        self.first.finish(&mut self.buffer)?;
+        for x in self.buffer.drain(..) {
+            self.second.push(x, output)?;
+        }
+        self.second.finish(output)
+    }
+}
+
+/// two Nodes fed with the same input, see Node::fork()
+///
+/// Outputs are paired in order, so both Nodes should produce the same number of tokens.
+/// Tokens of the Node that runs ahead wait in a queue until the other one catches up.
+pub struct Fork<A: Node, B: Node> {
+    left: A,
+    right: B,
+    left_queue: VecDeque<A::Output>,
+    right_queue: VecDeque<B::Output>,
+    buffer: (Vec<A::Output>, Vec<B::Output>),
+}
+
+impl<A: Node, B: Node> Fork<A, B> {
+    /// move new tokens to the queues and pair them up
+    fn pair(&mut self, output: &mut Vec<(A::Output, B::Output)>) {
+        self.left_queue.extend(self.buffer.0.drain(..));
+        self.right_queue.extend(self.buffer.1.drain(..));
+        while !self.left_queue.is_empty() && !self.right_queue.is_empty() {
+            let l = self.left_queue.pop_front().unwrap();
+            let r = self.right_queue.pop_front().unwrap();
+            output.push((l, r));
+        }
+    }
+}
+
+impl<A, B> Node for Fork<A, B>
+where
+    A: Node,
+    A::Input: Clone,
+    B: Node<Input = A::Input>,
+{
+    type Input = A::Input;
+    type Output = (A::Output, B::Output);
+
+    fn push(&mut self, input: A::Input, output: &mut Vec<Self::Output>) -> Result<()> {
+        self.left.push(input.clone(), &mut self.buffer.0)?;
+        self.right.push(input, &mut self.buffer.1)?;
+        self.pair(output);
+        Ok(())
+    }
+
+    fn finish(&mut self, output: &mut Vec<Self::Output>) -> Result<()> {
+        self.left.finish(&mut self.buffer.0)?;
+        self.right.finish(&mut self.buffer.1)?;
+        self.pair(output);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{map, Compute, Frames, Node};
+    use crate::algs::stft::FFT;
+    use crate::algs::Algorithm;
+
+    #[test]
+    fn network() {
+        let signal: Vec<f64> = (0..100).map(|x| (x as f64 * 0.3).sin()).collect();
+
+        // frames cut from the whole signal at once
+        let mut frames = Frames::new(8, 4);
+        let mut expected = Vec::new();
+        frames.push(signal.clone(), &mut expected).unwrap();
+        frames.finish(&mut expected).unwrap();
+        let mut fft = FFT::new();
+        let expected: Vec<_> = expected
+            .into_iter()
+            .map(|x| (fft.compute(x.clone()).unwrap(), x.len()))
+            .collect();
+
+        // the same frames streamed in blocks of uneven size through a network
+        let mut network =
+            Frames::new(8, 4).then(Compute(FFT::new()).fork(map(|x: Vec<f64>| x.len())));
+        let mut output = Vec::new();
+        let blocks = signal.chunks(7).map(|x| Ok(x.to_vec()));
+        network
+            .run(blocks, |x| {
+                output.push(x);
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(output, expected);
+
+        // errors of any node stop the stream, here frames are too short for the FFT
+        let mut network = Frames::new(6, 6).then(Compute(FFT::new()));
+        assert!(network.run(vec![Ok(signal)], |_| Ok(())).is_err());
+    }
+}
@@ -0,0 +1,35 @@
+/// Span of a stage of processing named at runtime, entered until it's dropped
+///
+/// With the "tracing" feature it's a tracing span of the "muslib" target with the stage as its
+/// `name` field, and a subscriber reports how long it took, e.g. tracing-subscriber
+/// with `FmtSpan::CLOSE`. Stages with fixed names are instrumented functions in the modules
+/// of muslib, so a filter of "muslib" selects them all. Stages of whole files, like loading
+/// or a spectrogram, are at debug level, stages repeated for each packet or frame at trace level.
+/// Without the "tracing" feature Spans are empty and nothing is measured.
+///
+/// ```
+/// let _span = muslib::trace::span("my analysis");
+/// // ... the time until the end of this scope is measured
+/// ```
+pub struct Span {
+    #[cfg(feature = "tracing")]
+    _span: tracing::span::EnteredSpan,
+}
+
+/// start a stage of processing a whole file
+#[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+pub fn span(name: &'static str) -> Span {
+    Span {
+        #[cfg(feature = "tracing")]
+        _span: tracing::debug_span!(target: "muslib", "stage", name).entered(),
+    }
+}
+
+/// start a stage repeated for each packet or frame
+#[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+pub fn frame_span(name: &'static str) -> Span {
+    Span {
+        #[cfg(feature = "tracing")]
+        _span: tracing::trace_span!(target: "muslib", "stage", name).entered(),
+    }
+}
@@ -0,0 +1,721 @@
+use std::f64::consts::PI;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use ogg::writing::{PacketWriteEndInfo, PacketWriter};
+use symphonia::core::dsp::complex::Complex;
+use symphonia::core::dsp::fft::Fft;
+
+use crate::{Error, Result};
+
+/// number of samples of each channel in a block, only long blocks are used
+const BLOCK_SIZE: usize = 2048;
+
+/// number of coefficients of the transform of a block, and of samples between the starts of blocks
+const HALF: usize = BLOCK_SIZE / 2;
+
+/// size of short blocks declared in the identification header, they are never used
+const SHORT_BLOCK_SIZE: usize = 256;
+
+/// range of floor values with a multiplier of 1
+const FLOOR_RANGE: i32 = 256;
+
+/// bits of the positions of floor points, which reach HALF
+const FLOOR_RANGEBITS: u32 = 10;
+
+/// positions of the floor points between the ones at 0 and HALF, denser at low frequencies
+const FLOOR_X: [usize; 32] = [
+    4, 5, 6, 7, 8, 10, 12, 14, 16, 20, 23, 28, 33, 40, 48, 57, 68, 81, 96, 115, 137, 164, 196, 233,
+    278, 332, 397, 473, 565, 674, 804, 960,
+];
+
+/// floor points coded in each partition of the floor
+const FLOOR_DIMENSIONS: usize = 4;
+
+/// ratio of consecutive floor values, the decoder's table spans 140 dB in 256 steps
+const FLOOR_DB_STEP: f64 = 0.06296130868913505;
+
+/// number of coefficients in each partition of the residue
+const PARTITION_SIZE: usize = 32;
+
+/// largest magnitude of a residue value
+const MAX_RESIDUE: i32 = 127;
+
+/// loudest coefficient near a floor point divided by its quantization step, fixing the quality
+const QUALITY: f64 = 32.0;
+
+/// smallest quantization step, coefficients below half of it are dropped
+const MIN_STEP: f64 = 1e-5;
+
+/// codebooks of the setup header
+const FLOOR_BOOK: usize = 0;
+const CLASS_BOOK: usize = 1;
+const RESIDUE_BOOK: usize = 2;
+
+/// serial number of the logical Ogg stream, there is only one
+const SERIAL: u32 = 0x6d75736c;
+
+/// VorbisWriter encodes interleaved floating point samples to an Ogg Vorbis file, block by block.
+///
+/// It's a simple encoder of a fixed quality: every block is long, channels are coded
+/// independently, the spectral envelope is a floor of 34 points and the spectrum
+/// divided by it is quantized to integers coded with a single Huffman codebook,
+/// with no psychoacoustic model. Files are larger than those of libvorbis at a similar quality,
+/// but any Vorbis decoder plays them.
+/// The last block is padded with silence, and the end of the stream marks where the samples end,
+/// which decoders trim only in gapless mode; the Loader keeps up to 1023 padded samples of each channel.
+pub struct VorbisWriter {
+    packets: PacketWriter<BufWriter<File>>,
+    channels: usize,
+    books: [Codebook; 3],
+    window: Vec<f64>,
+    fft: Fft,
+    buffers: Vec<Vec<f64>>,
+    frame: Vec<f64>,
+    blocks: u64,
+    samples: u64,
+    last: Option<(Vec<u8>, u64)>,
+    finished: bool,
+}
+
+impl VorbisWriter {
+    /// create an Ogg Vorbis file for 1 to 255 channels
+    ///
+    /// Fails with Error::InvalidParam for other channels or a sample rate of 0.
+    pub fn create(path: &Path, channels: u16, sample_rate: u32) -> Result<Self> {
+        if !(1..=255).contains(&channels) || sample_rate == 0 {
+            return Err(Error::InvalidParam(format!(
+                "Vorbis can't store {} channels at {} Hz",
+                channels, sample_rate
+            )));
+        }
+        let window = (0..BLOCK_SIZE)
+            .map(|n| {
+                let x = ((n as f64 + 0.5) / BLOCK_SIZE as f64 * PI).sin();
+                (PI / 2.0 * x * x).sin()
+            })
+            .collect();
+        let mut writer = VorbisWriter {
+            packets: PacketWriter::new(BufWriter::new(File::create(path)?)),
+            channels: channels as usize,
+            books: [
+                Codebook::new(1, vec![8; FLOOR_RANGE as usize], None),
+                Codebook::new(1, vec![1, 1], None),
+                Codebook::new(1, residue_lengths(), Some((-MAX_RESIDUE, 1))),
+            ],
+            window,
+            fft: Fft::new(HALF / 2),
+            // the first block starts half a block before the first sample
+            buffers: vec![vec![0.0; HALF]; channels as usize],
+            frame: Vec::with_capacity(channels as usize),
+            blocks: 0,
+            samples: 0,
+            last: None,
+            finished: false,
+        };
+        // the identification header is alone on the first page, the audio starts on a new page
+        let identification = writer.identification(sample_rate);
+        writer.write_packet(identification, 0, PacketWriteEndInfo::EndPage)?;
+        writer.write_packet(comment(), 0, PacketWriteEndInfo::NormalPacket)?;
+        let setup = writer.setup();
+        writer.write_packet(setup, 0, PacketWriteEndInfo::EndPage)?;
+        Ok(writer)
+    }
+
+    /// append interleaved samples of all channels in range [-1, 1]
+    pub fn write(&mut self, samples: &[f64]) -> Result<()> {
+        for x in samples {
+            self.frame.push(*x);
+            if self.frame.len() == self.channels {
+                for (buffer, x) in self.buffers.iter_mut().zip(self.frame.drain(..)) {
+                    buffer.push(x);
+                }
+                self.samples += 1;
+                if self.buffers[0].len() == BLOCK_SIZE {
+                    self.block()?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// number of samples of all channels written so far
+    pub fn len(&self) -> u64 {
+        self.samples * self.channels as u64 + self.frame.len() as u64
+    }
+
+    /// check if no samples were written yet
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// end the current page and flush the file, so the blocks encoded so far can be read
+    ///
+    /// Samples of a block that isn't complete yet are kept until the next block or the end.
+    pub fn flush(&mut self) -> Result<()> {
+        if let Some((packet, granule)) = self.last.take() {
+            self.write_packet(packet, granule, PacketWriteEndInfo::EndPage)?;
+        }
+        self.packets.inner_mut().flush()?;
+        Ok(())
+    }
+
+    /// encode the last blocks, end the stream and flush the file
+    ///
+    /// Fails with Error::InvalidParam if the samples don't split evenly into the channels.
+    pub fn finalize(mut self) -> Result<()> {
+        self.finish()
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        if self.finished {
+            return Ok(());
+        }
+        self.finished = true;
+        if !self.frame.is_empty() {
+            return Err(Error::InvalidParam(format!(
+                "the last frame has samples missing in some of the {} channels",
+                self.channels
+            )));
+        }
+        // the last block is centered at or after the last sample, padded with silence
+        let blocks = self.samples.div_ceil(HALF as u64) + 1;
+        while self.blocks < blocks {
+            for buffer in self.buffers.iter_mut() {
+                buffer.resize(BLOCK_SIZE, 0.0);
+            }
+            self.block()?;
+        }
+        // the granule position of the last page trims the padding
+        if let Some((packet, _)) = self.last.take() {
+            self.write_packet(packet, self.samples, PacketWriteEndInfo::EndStream)?;
+        }
+        self.packets.inner_mut().flush()?;
+        Ok(())
+    }
+
+    fn write_packet(
+        &mut self,
+        packet: Vec<u8>,
+        granule: u64,
+        end: PacketWriteEndInfo,
+    ) -> Result<()> {
+        self.packets
+            .write_packet(packet.into_boxed_slice(), SERIAL, end, granule)?;
+        Ok(())
+    }
+
+    /// identification header with the channels, the sample rate and the block sizes
+    fn identification(&self, sample_rate: u32) -> Vec<u8> {
+        let mut w = header(1);
+        w.write(0, 32);
+        w.write(self.channels as u32, 8);
+        w.write(sample_rate, 32);
+        // no maximum, nominal or minimum bitrate
+        w.write(0, 32);
+        w.write(0, 32);
+        w.write(0, 32);
+        w.write(SHORT_BLOCK_SIZE.ilog2(), 4);
+        w.write(BLOCK_SIZE.ilog2(), 4);
+        w.write(1, 1);
+        w.bytes
+    }
+
+    /// setup header with the codebooks, a floor, a residue, a mapping and a mode
+    fn setup(&self) -> Vec<u8> {
+        let mut w = header(5);
+        w.write(self.books.len() as u32 - 1, 8);
+        for book in self.books.iter() {
+            book.write_setup(&mut w);
+        }
+        // a single placeholder time domain transform
+        w.write(0, 6);
+        w.write(0, 16);
+
+        // floor of type 1 with partitions of a single class coded with the floor book
+        w.write(0, 6);
+        w.write(1, 16);
+        w.write((FLOOR_X.len() / FLOOR_DIMENSIONS) as u32, 5);
+        for _ in 0..FLOOR_X.len() / FLOOR_DIMENSIONS {
+            w.write(0, 4);
+        }
+        w.write(FLOOR_DIMENSIONS as u32 - 1, 3);
+        w.write(0, 2);
+        w.write(FLOOR_BOOK as u32 + 1, 8);
+        w.write(0, 2);
+        w.write(FLOOR_RANGEBITS, 4);
+        for x in FLOOR_X {
+            w.write(x as u32, FLOOR_RANGEBITS);
+        }
+
+        // residue of type 1 with silent partitions and ones coded with the residue book
+        w.write(0, 6);
+        w.write(1, 16);
+        w.write(0, 24);
+        w.write(HALF as u32, 24);
+        w.write(PARTITION_SIZE as u32 - 1, 24);
+        w.write(1, 6);
+        w.write(CLASS_BOOK as u32, 8);
+        w.write(0, 3);
+        w.write(0, 1);
+        w.write(1, 3);
+        w.write(0, 1);
+        w.write(RESIDUE_BOOK as u32, 8);
+
+        // a mapping of every channel to the floor and the residue, without coupling
+        w.write(0, 6);
+        w.write(0, 16);
+        w.write(0, 1);
+        w.write(0, 1);
+        w.write(0, 2);
+        w.write(0, 8);
+        w.write(0, 8);
+        w.write(0, 8);
+
+        // a single mode of long blocks
+        w.write(0, 6);
+        w.write(1, 1);
+        w.write(0, 16);
+        w.write(0, 16);
+        w.write(0, 8);
+        w.write(1, 1);
+        w.bytes
+    }
+
+    /// encode the block at the start of the buffers and move them by half a block
+    fn block(&mut self) -> Result<()> {
+        let mut w = BitPacker::default();
+        // audio packet of the only mode, between long blocks
+        w.write(0, 1);
+        w.write(1, 1);
+        w.write(1, 1);
+
+        let mut residues = Vec::with_capacity(self.channels);
+        for c in 0..self.channels {
+            let spectrum = self.mdct(&self.buffers[c][..BLOCK_SIZE]);
+            residues.push(self.floor(&mut w, &spectrum));
+        }
+        let coded: Vec<&Vec<i32>> = residues.iter().flatten().collect();
+        for start in (0..HALF).step_by(PARTITION_SIZE) {
+            let classes: Vec<bool> = coded
+                .iter()
+                .map(|r| r[start..start + PARTITION_SIZE].iter().any(|x| *x != 0))
+                .collect();
+            for class in classes.iter() {
+                self.books[CLASS_BOOK].write_entry(&mut w, *class as usize);
+            }
+            for (r, _) in coded
+                .iter()
+                .zip(classes.iter())
+                .filter(|(_, class)| **class)
+            {
+                for x in r[start..start + PARTITION_SIZE].iter() {
+                    let entry = (x + MAX_RESIDUE) as usize;
+                    self.books[RESIDUE_BOOK].write_entry(&mut w, entry);
+                }
+            }
+        }
+
+        // a packet is held back until the next one, so the last one can end the stream
+        let granule = self.blocks * HALF as u64;
+        if let Some((packet, granule)) = self.last.replace((w.bytes, granule)) {
+            self.write_packet(packet, granule, PacketWriteEndInfo::NormalPacket)?;
+        }
+        self.blocks += 1;
+        for buffer in self.buffers.iter_mut() {
+            buffer.drain(..HALF);
+        }
+        Ok(())
+    }
+
+    /// windowed MDCT of a block, scaled for the unscaled inverse transform of decoders
+    ///
+    /// The block is folded into a DCT-IV of HALF samples, which is computed with an FFT
+    /// of HALF / 2 complex samples.
+    fn mdct(&self, block: &[f64]) -> Vec<f64> {
+        let x: Vec<f64> = block
+            .iter()
+            .zip(self.window.iter())
+            .map(|(x, w)| x * w)
+            .collect();
+        let q = HALF / 2;
+        let mut v = vec![0.0; HALF];
+        for n in 0..q {
+            v[n] = -x[3 * q - 1 - n] - x[3 * q + n];
+            v[q + n] = x[n] - x[2 * q - 1 - n];
+        }
+        let m = HALF as f64;
+        let mut z: Vec<Complex> = (0..q)
+            .map(|n| {
+                let (sin, cos) = (-PI * (n as f64 + 0.25) / m).sin_cos();
+                let (re, im) = (v[2 * n], v[HALF - 1 - 2 * n]);
+                Complex::new((re * cos - im * sin) as f32, (re * sin + im * cos) as f32)
+            })
+            .collect();
+        self.fft.fft_inplace(&mut z);
+        let scale = 4.0 / BLOCK_SIZE as f64;
+        let mut spectrum = vec![0.0; HALF];
+        for (k, z) in z.iter().enumerate() {
+            let (sin, cos) = (-PI * k as f64 / m).sin_cos();
+            let (re, im) = (z.re as f64, z.im as f64);
+            spectrum[2 * k] = scale * (re * cos - im * sin);
+            spectrum[HALF - 1 - 2 * k] = -scale * (re * sin + im * cos);
+        }
+        spectrum
+    }
+
+    /// code the floor of a channel and get its spectrum quantized as the residue
+    ///
+    /// The floor is a quantization step following the loudest coefficients near each point,
+    /// and it's rendered the way decoders do, so the residue is divided by the exact floor
+    /// they multiply it by. Silent channels have no floor and no residue.
+    fn floor(&self, w: &mut BitPacker, spectrum: &[f64]) -> Option<Vec<i32>> {
+        if spectrum.iter().all(|x| x.abs() < MIN_STEP / 2.0) {
+            w.write(0, 1);
+            return None;
+        }
+        w.write(1, 1);
+
+        // points in the order they're coded, and sorted by their position
+        let xs: Vec<usize> = [0, HALF].into_iter().chain(FLOOR_X).collect();
+        let mut sorted: Vec<usize> = (0..xs.len()).collect();
+        sorted.sort_by_key(|i| xs[*i]);
+        let mut target = vec![0; xs.len()];
+        for (s, i) in sorted.iter().enumerate() {
+            let low = if s > 0 { xs[sorted[s - 1]] } else { 0 };
+            let high = xs[sorted[(s + 1).min(xs.len() - 1)]].min(HALF - 1);
+            let peak = spectrum[low..=high]
+                .iter()
+                .fold(0.0, |a: f64, x| a.max(x.abs()));
+            let step = (peak / QUALITY).max(MIN_STEP);
+            target[*i] = (255.0 + step.ln() / FLOOR_DB_STEP)
+                .round()
+                .clamp(0.0, 255.0) as i32;
+        }
+
+        // the values coded for the first two points are their levels, the others are
+        // offsets from the level predicted by their neighbors, chosen to land on the target
+        let mut levels = target.clone();
+        let mut used = vec![true; xs.len()];
+        w.write(target[0] as u32, 8);
+        w.write(target[1] as u32, 8);
+        for i in 2..xs.len() {
+            let (low, high) = neighbors(&xs, i);
+            let predicted = render_point(xs[low], levels[low], xs[high], levels[high], xs[i]);
+            let val = (0..FLOOR_RANGE)
+                .min_by_key(|val| (floor_level(*val, predicted) - target[i]).abs())
+                .unwrap_or(0);
+            self.books[FLOOR_BOOK].write_entry(w, val as usize);
+            levels[i] = floor_level(val, predicted);
+            used[i] = val != 0;
+            if val != 0 {
+                used[low] = true;
+                used[high] = true;
+            }
+        }
+
+        let mut floor = vec![0.0; HALF];
+        let (mut lx, mut ly) = (0, levels[sorted[0]]);
+        for i in sorted[1..].iter().filter(|i| used[**i]) {
+            render_line(lx, ly, xs[*i], levels[*i], &mut floor);
+            (lx, ly) = (xs[*i], levels[*i]);
+        }
+        Some(
+            spectrum
+                .iter()
+                .zip(floor.iter())
+                .map(|(x, f)| ((x / f).round() as i32).clamp(-MAX_RESIDUE, MAX_RESIDUE))
+                .collect(),
+        )
+    }
+}
+
+impl Drop for VorbisWriter {
+    fn drop(&mut self) {
+        let _ = self.finish();
+    }
+}
+
+/// comment header with the name of the encoder and no comments
+fn comment() -> Vec<u8> {
+    let vendor = concat!("muslib ", env!("CARGO_PKG_VERSION"));
+    let mut w = header(3);
+    w.write(vendor.len() as u32, 32);
+    for byte in vendor.bytes() {
+        w.write(byte as u32, 8);
+    }
+    w.write(0, 32);
+    w.write(1, 1);
+    w.bytes
+}
+
+/// start of a header packet of a type
+fn header(kind: u32) -> BitPacker {
+    let mut w = BitPacker::default();
+    w.write(kind, 8);
+    for byte in b"vorbis" {
+        w.write(*byte as u32, 8);
+    }
+    w
+}
+
+/// level of a floor point decoded from its coded value and the level predicted for it
+fn floor_level(val: i32, predicted: i32) -> i32 {
+    let highroom = FLOOR_RANGE - predicted;
+    let lowroom = predicted;
+    let room = 2 * highroom.min(lowroom);
+    if val == 0 {
+        predicted
+    } else if val >= room {
+        if highroom > lowroom {
+            val - lowroom + predicted
+        } else {
+            predicted - val + highroom - 1
+        }
+    } else if val % 2 == 1 {
+        predicted - (val + 1) / 2
+    } else {
+        predicted + val / 2
+    }
+}
+
+/// closest points below and above point i among the points coded before it
+fn neighbors(xs: &[usize], i: usize) -> (usize, usize) {
+    let below = (0..i).filter(|j| xs[*j] < xs[i]).max_by_key(|j| xs[*j]);
+    let above = (0..i).filter(|j| xs[*j] > xs[i]).min_by_key(|j| xs[*j]);
+    (below.unwrap_or(0), above.unwrap_or(1))
+}
+
+/// level at x on the line between two points, rounded toward the first one
+fn render_point(x0: usize, y0: i32, x1: usize, y1: i32, x: usize) -> i32 {
+    let dy = y1 - y0;
+    let off = dy.abs() * (x - x0) as i32 / (x1 - x0) as i32;
+    if dy < 0 {
+        y0 - off
+    } else {
+        y0 + off
+    }
+}
+
+/// render the floor from x0 up to x1 with Bresenham's algorithm, as decoders do
+fn render_line(x0: usize, y0: i32, x1: usize, y1: i32, floor: &mut [f64]) {
+    let dy = y1 - y0;
+    let adx = (x1 - x0) as i32;
+    let base = dy / adx;
+    let sy = if dy < 0 { base - 1 } else { base + 1 };
+    let ady = dy.abs() - base.abs() * adx;
+    let (mut y, mut err) = (y0, 0);
+    floor[x0] = floor_value(y);
+    for v in floor[x0 + 1..x1.min(HALF)].iter_mut() {
+        err += ady;
+        if err >= adx {
+            err -= adx;
+            y += sy;
+        } else {
+            y += base;
+        }
+        *v = floor_value(y);
+    }
+}
+
+/// floor value of a level, from about -140 dB at 0 up to 1 at 255
+fn floor_value(level: i32) -> f64 {
+    (FLOOR_DB_STEP * (level - 255) as f64).exp()
+}
+
+/// lengths of the Huffman codes of residue values from -127 to 127, falling off with magnitude
+fn residue_lengths() -> Vec<u8> {
+    let weights: Vec<f64> = (-MAX_RESIDUE..=MAX_RESIDUE)
+        .map(|x| (-(x.abs() as f64) / 2.0).exp().max(1e-5))
+        .collect();
+    let mut lengths = vec![0u8; weights.len()];
+    let mut nodes: Vec<(f64, Vec<usize>)> = weights
+        .iter()
+        .enumerate()
+        .map(|(i, w)| (*w, vec![i]))
+        .collect();
+    while nodes.len() > 1 {
+        nodes.sort_by(|a, b| b.0.total_cmp(&a.0));
+        let (w0, mut a) = nodes.pop().unwrap_or_default();
+        let (w1, b) = nodes.pop().unwrap_or_default();
+        a.extend(b);
+        for i in a.iter() {
+            lengths[*i] += 1;
+        }
+        nodes.push((w0 + w1, a));
+    }
+    lengths
+}
+
+/// Huffman codebook with its entries' codewords, and values of a lookup of type 1 if it has one
+struct Codebook {
+    dimensions: u32,
+    lengths: Vec<u8>,
+    codewords: Vec<u32>,
+    lookup: Option<(i32, i32)>,
+}
+
+impl Codebook {
+    /// codebook of entries with given code lengths, with a lookup of values from a minimum by a delta
+    fn new(dimensions: u32, lengths: Vec<u8>, lookup: Option<(i32, i32)>) -> Self {
+        let codewords = codewords(&lengths);
+        Codebook {
+            dimensions,
+            lengths,
+            codewords,
+            lookup,
+        }
+    }
+
+    fn write_setup(&self, w: &mut BitPacker) {
+        w.write(0x564342, 24);
+        w.write(self.dimensions, 16);
+        w.write(self.lengths.len() as u32, 24);
+        // not ordered and not sparse
+        w.write(0, 1);
+        w.write(0, 1);
+        for length in self.lengths.iter() {
+            w.write(*length as u32 - 1, 5);
+        }
+        match self.lookup {
+            None => w.write(0, 4),
+            Some((minimum, delta)) => {
+                w.write(1, 4);
+                w.write(float32(minimum), 32);
+                w.write(float32(delta), 32);
+                // a multiplicand for each entry, which is its index
+                let bits = (self.lengths.len() - 1).ilog2() + 1;
+                w.write(bits - 1, 4);
+                w.write(0, 1);
+                for i in 0..self.lengths.len() {
+                    w.write(i as u32, bits);
+                }
+            }
+        }
+    }
+
+    fn write_entry(&self, w: &mut BitPacker, entry: usize) {
+        let length = self.lengths[entry] as u32;
+        // codewords are read bit by bit from their first bit
+        for i in (0..length).rev() {
+            w.write((self.codewords[entry] >> i) & 1, 1);
+        }
+    }
+}
+
+/// codewords of entries in order, each one the first free codeword of its length
+fn codewords(lengths: &[u8]) -> Vec<u32> {
+    let mut marker = [0u32; 33];
+    let mut codewords = Vec::with_capacity(lengths.len());
+    for length in lengths.iter().map(|l| *l as usize) {
+        let mut entry = marker[length];
+        codewords.push(entry);
+        for j in (1..=length).rev() {
+            if marker[j] & 1 == 1 {
+                marker[j] = if j == 1 {
+                    marker[1] + 1
+                } else {
+                    marker[j - 1] << 1
+                };
+                break;
+            }
+            marker[j] += 1;
+        }
+        for j in length + 1..33 {
+            if marker[j] >> 1 != entry {
+                break;
+            }
+            entry = marker[j];
+            marker[j] = marker[j - 1] << 1;
+        }
+    }
+    codewords
+}
+
+/// integer packed in the float format of Vorbis headers
+fn float32(x: i32) -> u32 {
+    let sign = if x < 0 { 1 << 31 } else { 0 };
+    sign | (788 << 21) | x.unsigned_abs()
+}
+
+/// writer of values of any number of bits, least significant bits first
+#[derive(Default)]
+struct BitPacker {
+    bytes: Vec<u8>,
+    used: u32,
+}
+
+impl BitPacker {
+    fn write(&mut self, value: u32, bits: u32) {
+        for i in 0..bits {
+            if self.used == 0 {
+                self.bytes.push(0);
+            }
+            if let Some(byte) = self.bytes.last_mut() {
+                *byte |= (((value >> i) & 1) as u8) << self.used;
+            }
+            self.used = (self.used + 1) % 8;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{codewords, residue_lengths, VorbisWriter};
+    use crate::mixer::Loader;
+
+    #[test]
+    fn vorbis() {
+        let lengths = residue_lengths();
+        let kraft: f64 = lengths.iter().map(|l| 0.5f64.powi(*l as i32)).sum();
+        assert_eq!(kraft, 1.0);
+        assert!(lengths.iter().all(|l| (1..=32).contains(l)));
+        assert_eq!(codewords(&[2, 1, 3, 3]), [0b00, 0b1, 0b010, 0b011]);
+
+        let path = std::env::temp_dir().join("muslib-vorbis.ogg");
+        // a tone on the left, silence on the right and a quieter, higher tone at the end of both
+        let mut data = Vec::new();
+        for i in 0..10000 {
+            data.push((i as f64 * 0.05).sin() * 0.5);
+            data.push(0.0);
+        }
+        for i in 0..3000 {
+            let x = (i as f64 * 0.3).sin() * 0.1;
+            data.extend([x, -x]);
+        }
+
+        let mut writer = VorbisWriter::create(&path, 2, 44100).unwrap();
+        writer.write(&data[..10000]).unwrap();
+        // only the blocks on complete pages can be read after flushing
+        writer.flush().unwrap();
+        let mut loader = Loader::<f64>::new();
+        loader.file(path.clone()).interleaved().load().unwrap();
+        assert_eq!(loader.data().len(), 2 * 3 * 1024);
+        writer.write(&data[10000..]).unwrap();
+        assert_eq!(writer.len(), data.len() as u64);
+        writer.finalize().unwrap();
+        let size = std::fs::metadata(&path).unwrap().len();
+        assert!(size < data.len() as u64, "{}", size);
+
+        let mut loader = Loader::<f64>::new();
+        loader.file(path.clone()).interleaved().load().unwrap();
+        assert_eq!(loader.sample_rate(), Some(44100));
+        assert_eq!(loader.channels(), Some(2));
+        let decoded = loader.data();
+        // the padding of the last block is kept
+        assert!(decoded.len() >= data.len() && decoded.len() < data.len() + 2 * 1024);
+        assert!(decoded[data.len()..].iter().all(|x| x.abs() < 0.01));
+        // lossy, but the error is over 30 dB below the signal
+        let error: f64 = decoded
+            .iter()
+            .zip(data.iter())
+            .map(|(x, y)| (x - y).powi(2))
+            .sum();
+        let power: f64 = data.iter().map(|x| x * x).sum();
+        assert!(10.0 * (power / error).log10() > 30.0, "{}", error);
+        std::fs::remove_file(path).unwrap();
+
+        assert!(VorbisWriter::create(&std::env::temp_dir().join("x.ogg"), 0, 44100).is_err());
+    }
+}
@@ -0,0 +1,130 @@
+use js_sys::{ArrayBuffer, Uint8Array, JSON};
+use wasm_bindgen::prelude::*;
+
+use crate::algs::{hpcp, stft, synth, Algorithm};
+use crate::json::{FromJson, Json};
+use crate::mixer::Loader;
+use crate::Error;
+
+/// parameters of an Algorithm read from a plain object, the ones missing from it keep their defaults
+fn read_params<P: FromJson + Default>(params: JsValue) -> Result<P, JsError> {
+    if params.is_undefined() || params.is_null() {
+        return Ok(P::default());
+    }
+    let text = JSON::stringify(&params)
+        .map_err(|_| Error::InvalidParam("parameters must be a plain object".into()))?;
+    Ok(P::from_json(&Json::parse(&String::from(text))?)?)
+}
+
+/// mono audio decoded from the bytes of a file
+#[wasm_bindgen]
+pub struct Audio {
+    samples: Vec<f32>,
+    sample_rate: u32,
+}
+
+#[wasm_bindgen]
+impl Audio {
+    /// samples from range <-1; 1>, ready for AudioBuffer.copyToChannel()
+    #[wasm_bindgen(getter)]
+    pub fn samples(&self) -> Vec<f32> {
+        self.samples.clone()
+    }
+
+    /// sample rate in Hz
+    #[wasm_bindgen(getter, js_name = sampleRate)]
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+}
+
+/// decode a file read into an ArrayBuffer, e.g. by fetch() or a file input, mixing it down to mono
+///
+/// Every format of the Loader is supported, and the audio is resampled if a sample rate is given.
+#[wasm_bindgen]
+pub fn decode(data: &ArrayBuffer, sample_rate: Option<u32>) -> Result<Audio, JsError> {
+    let mut loader = Loader::<f32>::from_bytes(Uint8Array::new(data).to_vec());
+    loader.mono();
+    if let Some(rate) = sample_rate {
+        loader.target_sample_rate(rate);
+    }
+    loader.load()?;
+    Ok(Audio {
+        samples: loader.data(),
+        sample_rate: loader.sample_rate().unwrap_or_default(),
+    })
+}
+
+/// Synthesizer for sequences of simple tones, see muslib::algs::synth::Synthesizer
+#[wasm_bindgen]
+pub struct Synthesizer(synth::Synthesizer);
+
+#[wasm_bindgen]
+impl Synthesizer {
+    /// create a Synthesizer with parameters from an object, e.g. {waveform: "saw", sample_rate: 48000}
+    #[wasm_bindgen(constructor)]
+    pub fn new(params: JsValue) -> Result<Synthesizer, JsError> {
+        let params = read_params(params)?;
+        Ok(Synthesizer(synth::Synthesizer::with_params(params)))
+    }
+
+    /// synthesize tones of given frequencies in Hz, 0 for rests, and durations in seconds
+    ///
+    /// Unlike .compute() of the Rust Synthesizer it returns samples from range <-1; 1>
+    /// for an AudioBuffer at the sample rate of the Synthesizer.
+    pub fn render(&mut self, freq: Vec<f64>, durations: Vec<f64>) -> Result<Vec<f32>, JsError> {
+        self.0.freq = freq;
+        self.0.durations = durations;
+        Ok(self.0.render()?)
+    }
+
+    /// sample rate of the rendered samples in Hz
+    #[wasm_bindgen(getter, js_name = sampleRate)]
+    pub fn sample_rate(&self) -> usize {
+        self.0.sample_rate
+    }
+}
+
+/// Fast Fourier transform of audio frames, see muslib::algs::stft::FFT
+#[wasm_bindgen]
+pub struct FFT(stft::FFT);
+
+#[wasm_bindgen]
+impl FFT {
+    /// create an FFT with parameters from an object
+    #[wasm_bindgen(constructor)]
+    pub fn new(params: JsValue) -> Result<FFT, JsError> {
+        Ok(FFT(stft::FFT::with_params(read_params(params)?)))
+    }
+
+    /// transform a frame of samples, its length must be a power of two
+    ///
+    /// The bins are interleaved as real and imaginary parts.
+    pub fn compute(&mut self, frame: &[f32]) -> Result<Vec<f32>, JsError> {
+        let frame = frame.iter().map(|&x| x as f64).collect();
+        let fft_data = self.0.compute(frame)?;
+        Ok(fft_data.into_iter().flat_map(|(re, im)| [re, im]).collect())
+    }
+}
+
+/// Harmonic pitch class profile of spectral peaks, see muslib::algs::hpcp::HPCP
+#[wasm_bindgen]
+pub struct HPCP(hpcp::HPCP);
+
+#[wasm_bindgen]
+impl HPCP {
+    /// create an HPCP with parameters from an object, e.g. {size: 36}
+    #[wasm_bindgen(constructor)]
+    pub fn new(params: JsValue) -> Result<HPCP, JsError> {
+        Ok(HPCP(hpcp::HPCP::with_params(read_params(params)?)))
+    }
+
+    /// compute the profile of peaks with given frequencies in Hz and magnitudes
+    pub fn compute(
+        &mut self,
+        frequencies: Vec<f64>,
+        magnitudes: Vec<f64>,
+    ) -> Result<Vec<f64>, JsError> {
+        Ok(self.0.compute((frequencies, magnitudes))?)
+    }
+}
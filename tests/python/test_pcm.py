@@ -0,0 +1,75 @@
+"""Round trips of pcm data through the Python module, run after `maturin develop` with:
+
+    $ python -m unittest discover tests/python
+"""
+
+import os
+import sys
+import tempfile
+import unittest
+
+# numpy is hidden, so every array is passed as a plain list
+sys.modules["numpy"] = None
+
+import muslib  # noqa: E402
+
+SINE = os.path.join(os.path.dirname(__file__), "..", "..", "examples", "loader", "sine.wav")
+
+
+class PcmRoundTrip(unittest.TestCase):
+    def setUp(self):
+        fd, self.path = tempfile.mkstemp(suffix=".wav")
+        os.close(fd)
+
+    def tearDown(self):
+        os.remove(self.path)
+
+    def test_loader_to_writer(self):
+        loader = muslib.MonoLoader()
+        pcm, sample_rate = loader.compute(SINE)
+        self.assertIsInstance(pcm, list)
+        self.assertTrue(any(x > 0 for x in pcm) and any(x < 0 for x in pcm))
+
+        muslib.MonoWriter(sample_rate).compute(self.path, pcm)
+        self.assertEqual(loader.compute(self.path), (pcm, sample_rate))
+
+    def test_synthesizer_to_writer(self):
+        synth = muslib.Synthesizer(8000)
+        pcm = synth.compute([440.0], [0.1])
+        self.assertIsInstance(pcm, list)
+        self.assertTrue(any(x < 0 for x in pcm))
+
+        muslib.MonoWriter(8000).compute(self.path, pcm)
+        self.assertEqual(muslib.MonoLoader().compute(self.path), (pcm, 8000))
+
+    def test_legacy_unsigned(self):
+        synth = muslib.Synthesizer(8000)
+        pcm = synth.compute([440.0], [0.1])
+        legacy = synth.compute_u16([440.0], [0.1])
+        self.assertTrue(any(x > 32767 for x in legacy))
+
+        with self.assertWarns(DeprecationWarning):
+            muslib.MonoWriter(8000).compute(self.path, legacy)
+        loaded = muslib.MonoLoader().compute(self.path)[0]
+        self.assertLessEqual(max(abs(x - y) for x, y in zip(loaded, pcm)), 2)
+
+        with self.assertRaises(OverflowError):
+            muslib.MonoWriter(8000).compute(self.path, [-1, 40000])
+
+    def test_silence(self):
+        pcm = muslib.Synthesizer(8000).compute([0.0], [0.1])
+        self.assertEqual(set(pcm), {0})
+
+        muslib.MonoWriter(8000).compute(self.path, pcm)
+        self.assertEqual(muslib.MonoLoader().compute(self.path)[0], pcm)
+
+    def test_stream(self):
+        pcm, sample_rate = muslib.MonoLoader().compute(SINE)
+        with muslib.MonoWriter.open(self.path, sample_rate) as writer:
+            writer.write(pcm[:100])
+            writer.write(pcm[100:])
+        self.assertEqual(muslib.MonoLoader().compute(self.path)[0], pcm)
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,106 @@
+//! Comparison of algorithm outputs with reference vectors stored in tests/reference
+//!
+//! Each fixture is a JSON file describing one algorithm of the default Registry:
+//!
+//! ```json
+//! {
+//!     "source": "where the expected outputs come from, e.g. essentia 2.1",
+//!     "algorithm": "HPCP",
+//!     "params": {"size": 12},
+//!     "tolerance": 1e-5,
+//!     "cases": [{"input": [[440], [1]], "output": [1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]}]
+//! }
+//! ```
+//!
+//! Numbers must agree within the absolute tolerance, everything else must be equal.
+//! A fixture of an algorithm missing from the Registry fails, just like a wrong output,
+//! so a renamed or unregistered algorithm can't drop its fixtures unnoticed.
+
+use std::fs;
+use std::path::Path;
+
+use muslib::json::{FromJson, Json};
+use muslib::registry::Registry;
+
+/// describe the first difference between actual and expected values, if any
+fn compare(actual: &Json, expected: &Json, tolerance: f64, path: &str) -> Option<String> {
+    match (actual, expected) {
+        (Json::Number(a), Json::Number(e)) => {
+            ((a - e).abs() > tolerance).then(|| format!("{}: got {} instead of {}", path, a, e))
+        }
+        (Json::Array(a), Json::Array(e)) => {
+            if a.len() != e.len() {
+                return Some(format!(
+                    "{}: got {} values instead of {}",
+                    path,
+                    a.len(),
+                    e.len()
+                ));
+            }
+            a.iter()
+                .zip(e.iter())
+                .enumerate()
+                .find_map(|(i, (a, e))| compare(a, e, tolerance, &format!("{}[{}]", path, i)))
+        }
+        (Json::Object(a), Json::Object(e)) => {
+            if a.keys().ne(e.keys()) {
+                return Some(format!("{}: got keys {:?}", path, a.keys()));
+            }
+            a.iter()
+                .zip(e.values())
+                .find_map(|((k, a), e)| compare(a, e, tolerance, &format!("{}.{}", path, k)))
+        }
+        _ => (actual != expected)
+            .then(|| format!("{}: got {} instead of {}", path, actual, expected)),
+    }
+}
+
+/// run every case of a fixture for a registered algorithm and describe its failures
+fn check(registry: &Registry, name: &str, fixture: &Json) -> Vec<String> {
+    let fields = fixture.fields().unwrap();
+    let tolerance = fields
+        .get("tolerance")
+        .map_or(0.0, |x| f64::from_json(x).unwrap());
+    let params = fields.get("params").cloned().unwrap_or(Json::object([]));
+    let cases = Vec::<Json>::from_json(&fields["cases"]).unwrap();
+
+    let mut failures = Vec::new();
+    for (i, case) in cases.iter().enumerate() {
+        let case = case.fields().unwrap();
+        // a new instance for each case, so state doesn't carry over
+        let mut algorithm = registry.create(name, &params).unwrap();
+        let path = format!("case {}", i);
+        match algorithm.compute_json(&case["input"]) {
+            Ok(output) => failures.extend(compare(&output, &case["output"], tolerance, &path)),
+            Err(e) => failures.push(format!("{}: {}", path, e)),
+        }
+    }
+    failures
+}
+
+#[test]
+fn reference_vectors() {
+    let registry = Registry::default();
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/reference");
+    let mut files: Vec<_> = fs::read_dir(dir)
+        .unwrap()
+        .map(|x| x.unwrap().path())
+        .filter(|x| x.extension().is_some_and(|x| x == "json"))
+        .collect();
+    files.sort();
+    assert!(!files.is_empty(), "no reference fixtures found");
+
+    let mut failures = Vec::new();
+    for file in files {
+        let fixture = Json::read(&file).unwrap();
+        let name = String::from_json(&fixture.fields().unwrap()["algorithm"]).unwrap();
+        if !registry.names().contains(&name.as_str()) {
+            failures.push(format!("{} ({}) is not registered", file.display(), name));
+            continue;
+        }
+        for e in check(&registry, &name, &fixture) {
+            failures.push(format!("{} ({}) {}", file.display(), name, e));
+        }
+    }
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}